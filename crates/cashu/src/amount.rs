@@ -24,6 +24,9 @@ pub enum Error {
     /// Cannot convert units
     #[error("Cannot convert units")]
     CannotConvertUnits,
+    /// Hash amounts measure work and have no rate to other units
+    #[error("Cannot convert between hash and `{0}`")]
+    HashUnitConversion(CurrencyUnit),
     /// Invalid amount
     #[error("Invalid Amount: {0}")]
     InvalidAmount(String),
@@ -76,7 +79,8 @@ impl Amount {
         let mut parts = match target {
             SplitTarget::None => self.split(),
             SplitTarget::Value(amount) => {
-                if self.le(amount) {
+                // A zero target has no parts to repeat
+                if self.le(amount) || *amount == Amount::ZERO {
                     return Ok(self.split());
                 }
 
@@ -210,6 +214,11 @@ impl Amount {
             None
         }
     }
+
+    /// Human readable display of amount in `unit`
+    pub fn display(self, unit: &CurrencyUnit) -> DisplayAmount<'_> {
+        DisplayAmount::new(self, unit)
+    }
 }
 
 impl Default for Amount {
@@ -356,7 +365,10 @@ where
     T: Into<u64>,
 {
     let amount = amount.into();
-    match (current_unit, target_unit) {
+    match (
+        current_unit.clone().canonical(),
+        target_unit.clone().canonical(),
+    ) {
         (CurrencyUnit::Sat, CurrencyUnit::Sat) => Ok(amount.into()),
         (CurrencyUnit::Msat, CurrencyUnit::Msat) => Ok(amount.into()),
         (CurrencyUnit::Sat, CurrencyUnit::Msat) => amount
@@ -366,10 +378,237 @@ where
         (CurrencyUnit::Msat, CurrencyUnit::Sat) => Ok((amount / MSAT_IN_SAT).into()),
         (CurrencyUnit::Usd, CurrencyUnit::Usd) => Ok(amount.into()),
         (CurrencyUnit::Eur, CurrencyUnit::Eur) => Ok(amount.into()),
+        (CurrencyUnit::Hash, CurrencyUnit::Hash) => Ok(amount.into()),
+        (CurrencyUnit::Hash, unit) | (unit, CurrencyUnit::Hash) => {
+            Err(Error::HashUnitConversion(unit))
+        }
         _ => Err(Error::CannotConvertUnits),
     }
 }
 
+/// Amount denominated in a unit
+///
+/// Unlike a bare [`Amount`] it cannot be taken for another unit by mistake, it
+/// only changes unit through [`UnitAmount::convert_to`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct UnitAmount {
+    /// Amount
+    pub amount: Amount,
+    /// Unit of the amount
+    pub unit: CurrencyUnit,
+}
+
+impl UnitAmount {
+    /// Create new [`UnitAmount`]
+    pub fn new<A>(amount: A, unit: CurrencyUnit) -> Self
+    where
+        A: Into<Amount>,
+    {
+        Self {
+            amount: amount.into(),
+            unit,
+        }
+    }
+
+    /// Convert to `unit`, see [`to_unit`]
+    pub fn convert_to(&self, unit: &CurrencyUnit) -> Result<Self, Error> {
+        Ok(Self {
+            amount: to_unit(self.amount, &self.unit, unit)?,
+            unit: unit.clone(),
+        })
+    }
+}
+
+impl fmt::Display for UnitAmount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.amount.display(&self.unit).fmt(f)
+    }
+}
+
+/// Separators accepted between digits by [`parse_amount`]
+///
+/// Formatting always groups digits with an ASCII space. The decimal point is always `.`
+/// regardless of locale.
+pub const AMOUNT_GROUP_SEPARATORS: [char; 2] = [' ', '_'];
+
+/// Decimal places and label used to display amounts of unit
+fn unit_notation(unit: &CurrencyUnit) -> (u32, &str) {
+    match unit {
+        CurrencyUnit::Sat => (0, "sat"),
+        CurrencyUnit::Msat => (0, "msat"),
+        CurrencyUnit::Usd => (2, "USD"),
+        CurrencyUnit::Eur => (2, "EUR"),
+        CurrencyUnit::Auth => (0, "auth"),
+        CurrencyUnit::Hash => (0, "HASH"),
+        CurrencyUnit::Custom(unit) => (0, unit.as_str()),
+    }
+}
+
+/// Decimal places of unit when expressed in BTC
+fn btc_decimals(unit: &CurrencyUnit) -> Option<u32> {
+    match unit {
+        CurrencyUnit::Sat => Some(8),
+        CurrencyUnit::Msat => Some(11),
+        _ => None,
+    }
+}
+
+/// Human readable display of an [`Amount`] in a unit
+///
+/// Digits are grouped in threes and followed by the unit label, e.g. `1 234 sat`,
+/// `512 HASH` or `12.34 USD`. Nothing is allocated, the output is written straight
+/// into the formatter, so it is cheap to pass to `tracing` macros whose level is
+/// disabled.
+#[derive(Debug, Clone, Copy)]
+pub struct DisplayAmount<'a> {
+    amount: Amount,
+    unit: &'a CurrencyUnit,
+    btc: bool,
+}
+
+impl<'a> DisplayAmount<'a> {
+    /// Display `amount` denominated in `unit`
+    pub fn new(amount: Amount, unit: &'a CurrencyUnit) -> Self {
+        Self {
+            amount,
+            unit,
+            btc: false,
+        }
+    }
+
+    /// Display sat and msat amounts in BTC, e.g. `0.00001234 BTC`
+    ///
+    /// Has no effect on other units.
+    pub fn btc(mut self) -> Self {
+        self.btc = true;
+        self
+    }
+}
+
+impl fmt::Display for DisplayAmount<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (decimals, label) = match btc_decimals(self.unit).filter(|_| self.btc) {
+            Some(decimals) => (decimals, "BTC"),
+            None => unit_notation(self.unit),
+        };
+
+        let scale = 10u64.pow(decimals);
+        write_digit_groups(f, self.amount.0 / scale)?;
+        if decimals > 0 {
+            write!(
+                f,
+                ".{:0width$}",
+                self.amount.0 % scale,
+                width = decimals as usize
+            )?;
+        }
+
+        write!(f, " {label}")
+    }
+}
+
+/// Write value with digit groups of three separated by a space
+fn write_digit_groups(f: &mut fmt::Formatter<'_>, value: u64) -> fmt::Result {
+    let mut divisor = 1;
+    while value / divisor >= 1000 {
+        divisor *= 1000;
+    }
+
+    write!(f, "{}", value / divisor)?;
+    while divisor > 1 {
+        divisor /= 1000;
+        write!(f, " {:03}", (value / divisor) % 1000)?;
+    }
+
+    Ok(())
+}
+
+/// Format amount in unit for humans
+///
+/// See [`DisplayAmount`] for the format and for displaying without allocating.
+pub fn format_amount(amount: Amount, unit: &CurrencyUnit) -> String {
+    DisplayAmount::new(amount, unit).to_string()
+}
+
+/// Parse a human readable amount of unit
+///
+/// Accepts the output of [`format_amount`] and [`DisplayAmount::btc`]. Digits may be
+/// separated by any of [`AMOUNT_GROUP_SEPARATORS`] and the unit label is matched case
+/// insensitively. With a label, `.` is the decimal point for units that have one (USD,
+/// EUR, and BTC for sat and msat). Without a label the number is read as an integer in
+/// the smallest denomination of unit, the same as [`Amount::from_str`].
+///
+/// Custom unit labels starting with a digit, `.` or a separator cannot be parsed.
+pub fn parse_amount(s: &str, unit: &CurrencyUnit) -> Result<Amount, Error> {
+    let invalid = || Error::InvalidAmount(s.to_owned());
+
+    let trimmed = s.trim();
+    let number_end = trimmed
+        .find(|c: char| !(c.is_ascii_digit() || c == '.' || AMOUNT_GROUP_SEPARATORS.contains(&c)))
+        .unwrap_or(trimmed.len());
+    let (number, label) = trimmed.split_at(number_end);
+    let (number, label) = (number.trim_end(), label.trim());
+
+    let (unit_decimals, unit_label) = unit_notation(unit);
+    let decimals = if label.is_empty() {
+        0
+    } else if label.eq_ignore_ascii_case(unit_label) {
+        unit_decimals
+    } else if label.eq_ignore_ascii_case("btc") {
+        btc_decimals(unit).ok_or_else(invalid)?
+    } else {
+        return Err(invalid());
+    };
+
+    let (integer, fraction) = match number.split_once('.') {
+        Some((integer, fraction)) => (integer, Some(fraction)),
+        None => (number, None),
+    };
+
+    let integer = parse_digit_groups(integer).ok_or_else(invalid)?;
+    let mut value = integer
+        .checked_mul(10u64.pow(decimals))
+        .ok_or(Error::AmountOverflow)?;
+
+    if let Some(fraction) = fraction {
+        if fraction.is_empty()
+            || fraction.len() > decimals as usize
+            || !fraction.bytes().all(|b| b.is_ascii_digit())
+        {
+            return Err(invalid());
+        }
+
+        let padding = 10u64.pow(decimals - fraction.len() as u32);
+        let fraction: u64 = fraction.parse().map_err(|_| invalid())?;
+        value = value
+            .checked_add(fraction * padding)
+            .ok_or(Error::AmountOverflow)?;
+    }
+
+    Ok(Amount(value))
+}
+
+/// Parse digits separated by [`AMOUNT_GROUP_SEPARATORS`]
+///
+/// Separators are only allowed between two digits.
+fn parse_digit_groups(s: &str) -> Option<u64> {
+    let mut value: u64 = 0;
+    let mut after_digit = false;
+
+    for c in s.chars() {
+        match c.to_digit(10) {
+            Some(digit) => {
+                value = value.checked_mul(10)?.checked_add(digit as u64)?;
+                after_digit = true;
+            }
+            None if after_digit && AMOUNT_GROUP_SEPARATORS.contains(&c) => after_digit = false,
+            None => return None,
+        }
+    }
+
+    after_digit.then_some(value)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -587,6 +826,16 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_split_target_zero_value() {
+        let amount = Amount(13);
+
+        let split = amount
+            .split_targeted(&SplitTarget::Value(Amount::ZERO))
+            .unwrap();
+        assert_eq!(split, amount.split());
+    }
+
     #[test]
     fn test_split_values() {
         let amount = Amount(10);
@@ -686,4 +935,202 @@ mod tests {
 
         assert!(converted.is_err());
     }
+
+    #[test]
+    fn test_hash_amount_to_unit() {
+        let amount = Amount::from(512);
+
+        let converted = to_unit(amount, &CurrencyUnit::Hash, &CurrencyUnit::Hash).unwrap();
+        assert_eq!(converted, 512.into());
+
+        // The legacy custom spelling is the same unit
+        let legacy = CurrencyUnit::Custom("HASH".to_string());
+        let converted = to_unit(amount, &legacy, &CurrencyUnit::Hash).unwrap();
+        assert_eq!(converted, 512.into());
+
+        for unit in [CurrencyUnit::Sat, CurrencyUnit::Msat] {
+            assert!(matches!(
+                to_unit(amount, &CurrencyUnit::Hash, &unit),
+                Err(Error::HashUnitConversion(ref other)) if *other == unit
+            ));
+            assert!(matches!(
+                to_unit(amount, &unit, &legacy),
+                Err(Error::HashUnitConversion(ref other)) if *other == unit
+            ));
+        }
+    }
+
+    #[test]
+    fn test_unit_amount_convert() {
+        let amount = UnitAmount::new(2500u64, CurrencyUnit::Msat);
+        assert_eq!(amount.to_string(), "2 500 msat");
+
+        let converted = amount.convert_to(&CurrencyUnit::Sat).unwrap();
+        assert_eq!(converted, UnitAmount::new(2u64, CurrencyUnit::Sat));
+
+        assert!(amount.convert_to(&CurrencyUnit::Usd).is_err());
+    }
+
+    /// Deterministic xorshift generator so failures are reproducible
+    fn xorshift(state: &mut u64) -> u64 {
+        *state ^= *state << 13;
+        *state ^= *state >> 7;
+        *state ^= *state << 17;
+        *state
+    }
+
+    fn display_units() -> Vec<CurrencyUnit> {
+        vec![
+            CurrencyUnit::Sat,
+            CurrencyUnit::Msat,
+            CurrencyUnit::Usd,
+            CurrencyUnit::Eur,
+            CurrencyUnit::Auth,
+            CurrencyUnit::Hash,
+            CurrencyUnit::Custom("usdt".to_string()),
+            CurrencyUnit::Custom("Gold".to_string()),
+        ]
+    }
+
+    #[test]
+    fn test_format_amount() {
+        assert_eq!(
+            format_amount(Amount::from(1234), &CurrencyUnit::Sat),
+            "1 234 sat"
+        );
+        assert_eq!(
+            format_amount(Amount::from(512), &CurrencyUnit::Hash),
+            "512 HASH"
+        );
+        assert_eq!(
+            format_amount(Amount::from(5), &CurrencyUnit::Usd),
+            "0.05 USD"
+        );
+        assert_eq!(
+            format_amount(Amount::from(1_234_567), &CurrencyUnit::Eur),
+            "12 345.67 EUR"
+        );
+        assert_eq!(
+            format_amount(
+                Amount::from(1_000),
+                &CurrencyUnit::Custom("Gold".to_string())
+            ),
+            "1 000 Gold"
+        );
+        assert_eq!(
+            Amount::from(u64::MAX)
+                .display(&CurrencyUnit::Msat)
+                .to_string(),
+            "18 446 744 073 709 551 615 msat"
+        );
+        assert_eq!(
+            Amount::from(1234)
+                .display(&CurrencyUnit::Sat)
+                .btc()
+                .to_string(),
+            "0.00001234 BTC"
+        );
+        assert_eq!(
+            Amount::from(512)
+                .display(&CurrencyUnit::Hash)
+                .btc()
+                .to_string(),
+            "512 HASH"
+        );
+    }
+
+    #[test]
+    fn test_parse_amount() {
+        let sat = CurrencyUnit::Sat;
+        let usd = CurrencyUnit::Usd;
+
+        assert_eq!(parse_amount("1 234 sat", &sat).unwrap(), Amount(1234));
+        assert_eq!(parse_amount("1_234", &sat).unwrap(), Amount(1234));
+        assert_eq!(parse_amount(" 1234SAT ", &sat).unwrap(), Amount(1234));
+        assert_eq!(parse_amount("0.00001234 BTC", &sat).unwrap(), Amount(1234));
+        assert_eq!(parse_amount("0.1 btc", &sat).unwrap(), Amount(10_000_000));
+        assert_eq!(
+            parse_amount("0.00001234 BTC", &CurrencyUnit::Msat).unwrap(),
+            Amount(1_234_000)
+        );
+        assert_eq!(parse_amount("12.3 usd", &usd).unwrap(), Amount(1230));
+        assert_eq!(parse_amount("12 USD", &usd).unwrap(), Amount(1200));
+        assert_eq!(parse_amount("12", &usd).unwrap(), Amount(12));
+
+        for invalid in [
+            "",
+            "sat",
+            "-1 sat",
+            "12.34",
+            "1.5 sat",
+            "1 234 msat",
+            "1__234",
+            "_1",
+            "1_ sat",
+            "1. sat",
+            ".5 BTC",
+            "1.2.3 BTC",
+            "0.000000001 BTC",
+            "18446744073709551616",
+        ] {
+            assert!(
+                matches!(parse_amount(invalid, &sat), Err(Error::InvalidAmount(_))),
+                "{invalid:?} should not parse"
+            );
+        }
+
+        assert!(parse_amount("0.5 BTC", &CurrencyUnit::Hash).is_err());
+        assert!(parse_amount("1.234 USD", &usd).is_err());
+        assert!(matches!(
+            parse_amount("200 000 000 000 BTC", &sat),
+            Err(Error::AmountOverflow)
+        ));
+    }
+
+    #[test]
+    fn test_format_parse_round_trip() {
+        let mut state = 0x2545_f491_4f6c_dd1d;
+
+        for unit in display_units() {
+            let edge_cases = [0, 1, 999, 1000, 123_456_789, u64::MAX];
+            let random = (0..1000).map(|_| {
+                let shift = xorshift(&mut state) % 64;
+                xorshift(&mut state) >> shift
+            });
+
+            for value in edge_cases.into_iter().chain(random) {
+                let amount = Amount::from(value);
+
+                let formatted = format_amount(amount, &unit);
+                assert_eq!(parse_amount(&formatted, &unit).unwrap(), amount);
+
+                let btc = amount.display(&unit).btc().to_string();
+                assert_eq!(parse_amount(&btc, &unit).unwrap(), amount);
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_amount_fuzz() {
+        const ALPHABET: [char; 22] = [
+            '0', '1', '5', '9', '.', ' ', '_', ',', '-', '+', 's', 'a', 't', 'B', 'T', 'C', 'U',
+            'D', 'H', '\t', '\u{202f}', '\u{e9}',
+        ];
+        let mut state = 0x9e37_79b9_7f4a_7c15;
+
+        for _ in 0..20_000 {
+            let len = xorshift(&mut state) % 32;
+            let input: String = (0..len)
+                .map(|_| ALPHABET[(xorshift(&mut state) % ALPHABET.len() as u64) as usize])
+                .collect();
+
+            for unit in display_units() {
+                // Anything accepted must survive formatting and parsing again
+                if let Ok(amount) = parse_amount(&input, &unit) {
+                    let formatted = format_amount(amount, &unit);
+                    assert_eq!(parse_amount(&formatted, &unit).unwrap(), amount);
+                }
+            }
+        }
+    }
 }
@@ -0,0 +1,234 @@
+//! Canonical JSON
+//!
+//! Deterministic JSON encoding of values that are hashed, so implementations
+//! in other languages and later versions produce the same bytes:
+//!
+//! - Object members are sorted by the UTF-8 bytes of their keys.
+//! - Object members with a `null` value are omitted, so an absent optional
+//!   field and an explicit `null` encode the same. `null` array elements are
+//!   kept.
+//! - There is no whitespace outside strings.
+//! - Numbers must be integers and are written in decimal, without exponent,
+//!   leading zeros or `+` sign. Fractional numbers are refused.
+//! - Strings escape `"`, `\` and the control characters below U+0020 only,
+//!   using `\b`, `\f`, `\n`, `\r`, `\t` or else `\u00xx` with lowercase hex.
+//!   All other characters are written as UTF-8.
+//!
+//! [`canonical_hash`] hashes the encoding behind the version byte
+//! [`CANONICAL_VERSION`], so a later encoding can coexist with this one.
+//!
+//! Test vectors for the request types are published in `vectors.json` next to
+//! this module: the input, its canonical encoding in hex and its hash.
+
+use bitcoin::hashes::{sha256, Hash, HashEngine};
+use serde::Serialize;
+use serde_json::Value;
+use thiserror::Error;
+
+/// Version of the canonical encoding, prefixed to the hashed bytes
+pub const CANONICAL_VERSION: u8 = 1;
+
+/// Canonical JSON Error
+#[derive(Debug, Error)]
+pub enum Error {
+    /// Number is not an integer
+    #[error("Number `{0}` is not an integer")]
+    NonIntegerNumber(String),
+    /// Serde Json error
+    #[error(transparent)]
+    SerdeJsonError(#[from] serde_json::Error),
+}
+
+/// Canonical JSON encoding of `value`
+pub fn to_canonical_json<T>(value: &T) -> Result<Vec<u8>, Error>
+where
+    T: Serialize + ?Sized,
+{
+    let value = serde_json::to_value(value)?;
+    let mut out = Vec::new();
+    write_value(&mut out, &value)?;
+    Ok(out)
+}
+
+/// Hash of a canonical JSON encoding behind [`CANONICAL_VERSION`]
+pub fn hash_canonical_json(json: &[u8]) -> sha256::Hash {
+    let mut engine = sha256::Hash::engine();
+    engine.input(&[CANONICAL_VERSION]);
+    engine.input(json);
+    sha256::Hash::from_engine(engine)
+}
+
+/// Hash of the canonical JSON encoding of `value`
+pub fn canonical_hash<T>(value: &T) -> Result<sha256::Hash, Error>
+where
+    T: Serialize + ?Sized,
+{
+    Ok(hash_canonical_json(&to_canonical_json(value)?))
+}
+
+fn write_value(out: &mut Vec<u8>, value: &Value) -> Result<(), Error> {
+    match value {
+        Value::Null => out.extend_from_slice(b"null"),
+        Value::Bool(true) => out.extend_from_slice(b"true"),
+        Value::Bool(false) => out.extend_from_slice(b"false"),
+        Value::Number(number) => {
+            let number = match (number.as_u64(), number.as_i64()) {
+                (Some(number), _) => number.to_string(),
+                (None, Some(number)) => number.to_string(),
+                (None, None) => return Err(Error::NonIntegerNumber(number.to_string())),
+            };
+            out.extend_from_slice(number.as_bytes());
+        }
+        Value::String(string) => write_string(out, string),
+        Value::Array(items) => {
+            out.push(b'[');
+            for (index, item) in items.iter().enumerate() {
+                if index > 0 {
+                    out.push(b',');
+                }
+                write_value(out, item)?;
+            }
+            out.push(b']');
+        }
+        Value::Object(map) => {
+            let mut members: Vec<_> = map.iter().filter(|(_, value)| !value.is_null()).collect();
+            members.sort_by(|(a, _), (b, _)| a.as_bytes().cmp(b.as_bytes()));
+
+            out.push(b'{');
+            for (index, (key, value)) in members.into_iter().enumerate() {
+                if index > 0 {
+                    out.push(b',');
+                }
+                write_string(out, key);
+                out.push(b':');
+                write_value(out, value)?;
+            }
+            out.push(b'}');
+        }
+    }
+
+    Ok(())
+}
+
+fn write_string(out: &mut Vec<u8>, string: &str) {
+    out.push(b'"');
+    for c in string.chars() {
+        match c {
+            '"' => out.extend_from_slice(b"\\\""),
+            '\\' => out.extend_from_slice(b"\\\\"),
+            '\u{08}' => out.extend_from_slice(b"\\b"),
+            '\u{0c}' => out.extend_from_slice(b"\\f"),
+            '\n' => out.extend_from_slice(b"\\n"),
+            '\r' => out.extend_from_slice(b"\\r"),
+            '\t' => out.extend_from_slice(b"\\t"),
+            c if (c as u32) < 0x20 => {
+                out.extend_from_slice(format!("\\u{:04x}", c as u32).as_bytes())
+            }
+            c => {
+                let mut buf = [0; 4];
+                out.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+            }
+        }
+    }
+    out.push(b'"');
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::de::DeserializeOwned;
+    use serde::Deserialize;
+
+    use super::*;
+    use crate::nuts::{MintQuoteBolt11Request, MintQuoteMiningShareRequest, MintRequest};
+    use crate::util::hex;
+
+    #[derive(Deserialize)]
+    struct Vector {
+        name: String,
+        #[serde(rename = "type")]
+        type_name: String,
+        input: Value,
+        canonical: String,
+        hash: String,
+    }
+
+    /// Round trip the input through `T` and check the published encoding
+    fn check_vector<T>(vector: &Vector)
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        let request: T = serde_json::from_value(vector.input.clone()).unwrap();
+
+        let canonical = to_canonical_json(&request).unwrap();
+        assert_eq!(hex::encode(&canonical), vector.canonical, "{}", vector.name);
+        assert_eq!(
+            canonical_hash(&request).unwrap().to_string(),
+            vector.hash,
+            "{}",
+            vector.name
+        );
+    }
+
+    #[test]
+    fn test_vectors() {
+        let vectors: Vec<Vector> = serde_json::from_str(include_str!("vectors.json")).unwrap();
+        assert!(!vectors.is_empty());
+
+        for vector in &vectors {
+            match vector.type_name.as_str() {
+                "MintQuoteBolt11Request" => check_vector::<MintQuoteBolt11Request>(vector),
+                "MintQuoteMiningShareRequest" => {
+                    check_vector::<MintQuoteMiningShareRequest>(vector)
+                }
+                "MintRequest" => check_vector::<MintRequest<String>>(vector),
+                other => panic!("Unknown vector type {other}"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_field_order_independent() {
+        let request: MintRequest<String> = serde_json::from_str(
+            r#"{"quote":"q","outputs":[{"amount":1,"id":"009a1f293253e41e","B_":"0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798"}]}"#,
+        )
+        .unwrap();
+        let permuted: MintRequest<String> = serde_json::from_str(
+            r#"{"outputs":[{"B_":"0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798","id":"009a1f293253e41e","amount":1}],"signature":null,"quote":"q"}"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            canonical_hash(&request).unwrap(),
+            canonical_hash(&permuted).unwrap()
+        );
+
+        let a: Value = serde_json::from_str(r#"{"b":1,"a":{"d":null,"c":[true,null]}}"#).unwrap();
+        let b: Value = serde_json::from_str(r#"{"a":{"c":[true,null]},"b":1}"#).unwrap();
+        assert_eq!(
+            to_canonical_json(&a).unwrap(),
+            to_canonical_json(&b).unwrap()
+        );
+        assert_eq!(
+            to_canonical_json(&a).unwrap(),
+            br#"{"a":{"c":[true,null]},"b":1}"#
+        );
+    }
+
+    #[test]
+    fn test_numbers_and_strings() {
+        assert_eq!(to_canonical_json(&-7i64).unwrap(), b"-7");
+        assert_eq!(
+            to_canonical_json(&u64::MAX).unwrap(),
+            b"18446744073709551615"
+        );
+        assert!(matches!(
+            to_canonical_json(&1.5f64),
+            Err(Error::NonIntegerNumber(_))
+        ));
+
+        assert_eq!(
+            to_canonical_json("a\"\\/\u{7f}\u{1f}é\r").unwrap(),
+            "\"a\\\"\\\\/\u{7f}\\u001fé\\r\"".as_bytes()
+        );
+    }
+}
@@ -3,6 +3,7 @@
 #![warn(rustdoc::bare_urls)]
 
 pub mod amount;
+pub mod canonical;
 pub mod dhke;
 pub mod mint_url;
 pub mod nuts;
@@ -44,7 +44,7 @@ pub use nut00::{PreMint, PreMintSecrets};
 pub use nut01::{Keys, KeysResponse, PublicKey, SecretKey};
 #[cfg(feature = "mint")]
 pub use nut02::MintKeySet;
-pub use nut02::{Id, KeySet, KeySetInfo, KeysetResponse};
+pub use nut02::{find_shared_keys, Id, KeySet, KeySetInfo, KeysetResponse, SharedKeys};
 #[cfg(feature = "wallet")]
 pub use nut03::PreSwap;
 pub use nut03::{SwapRequest, SwapResponse};
@@ -71,6 +71,10 @@ pub use nut23::{
 };
 pub use nut25::{MeltQuoteBolt12Request, MintQuoteBolt12Request, MintQuoteBolt12Response};
 pub use nutXX::{
-    MeltQuoteMiningShareRequest, MeltQuoteMiningShareResponse, MintQuoteMiningShareRequest,
-    MintQuoteMiningShareResponse, QuoteState as MiningShareQuoteState,
+    BatchMintQuoteMiningShareItem, BatchMintQuoteMiningShareRequest,
+    BatchMintQuoteMiningShareResponse, BatchQuoteStatusItem, BatchQuoteStatusRequest,
+    BatchQuoteStatusResponse, MeltQuoteMiningShareRequest, MeltQuoteMiningShareResponse,
+    MiningShareStats, MintQuoteCancelRequest, MintQuoteCancelResponse, MintQuoteConversionRequest,
+    MintQuoteConversionResponse, MintQuoteMiningShareRequest, MintQuoteMiningShareResponse,
+    QuoteConversionParams, QuoteState as MiningShareQuoteState,
 };
@@ -207,7 +207,11 @@ pub struct BlindedMessage {
     /// Blinded secret message (B_)
     ///
     /// The blinded secret message generated by the sender.
-    #[serde(rename = "B_")]
+    #[serde(
+        rename = "B_",
+        serialize_with = "serialize_compact_pubkey",
+        deserialize_with = "deserialize_compact_pubkey"
+    )]
     #[cfg_attr(feature = "swagger", schema(value_type = String))]
     pub blinded_secret: PublicKey,
     /// Witness
@@ -253,7 +257,11 @@ pub struct BlindSignature {
     /// Blinded signature (C_)
     ///
     /// The blinded signature on the secret message `B_` of [BlindedMessage].
-    #[serde(rename = "C_")]
+    #[serde(
+        rename = "C_",
+        serialize_with = "serialize_compact_pubkey",
+        deserialize_with = "deserialize_compact_pubkey"
+    )]
     #[cfg_attr(feature = "swagger", schema(value_type = String))]
     pub c: PublicKey,
     /// DLEQ Proof
@@ -554,6 +562,28 @@ where
     PublicKey::from_slice(&bytes).map_err(serde::de::Error::custom)
 }
 
+/// Hex in human-readable formats, compressed bytes in binary formats like the
+/// CBOR encoding of mint requests and responses
+fn serialize_compact_pubkey<S>(key: &PublicKey, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    match serializer.is_human_readable() {
+        true => key.serialize(serializer),
+        false => serialize_v4_pubkey(key, serializer),
+    }
+}
+
+fn deserialize_compact_pubkey<'de, D>(deserializer: D) -> Result<PublicKey, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    match deserializer.is_human_readable() {
+        true => PublicKey::deserialize(deserializer),
+        false => deserialize_v4_pubkey(deserializer),
+    }
+}
+
 /// Currency Unit
 #[non_exhaustive]
 #[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
@@ -571,11 +601,25 @@ pub enum CurrencyUnit {
     /// Auth
     Auth,
     /// Ehash
+    ///
+    /// Amounts measure difficulty-weighted work and do not convert to
+    /// bitcoin units.
     Hash,
     /// Custom currency unit
     Custom(String),
 }
 
+impl CurrencyUnit {
+    /// The unit with a custom spelling of a known unit, e.g. the legacy
+    /// `Custom("HASH")`, replaced by the known unit
+    pub fn canonical(self) -> Self {
+        match self {
+            Self::Custom(unit) => Self::from_str(&unit).unwrap_or(Self::Custom(unit)),
+            unit => unit,
+        }
+    }
+}
+
 #[cfg(feature = "mint")]
 impl CurrencyUnit {
     /// Derivation index mint will use for unit
@@ -587,7 +631,10 @@ impl CurrencyUnit {
             Self::Eur => Some(3),
             Self::Auth => Some(4),
             Self::Hash => Some(5),
-            _ => None,
+            Self::Custom(_) => match self.clone().canonical() {
+                Self::Custom(_) => None,
+                unit => unit.derivation_index(),
+            },
         }
     }
 }
@@ -706,7 +753,7 @@ impl<'de> Deserialize<'de> for PaymentMethod {
 
 /// PreMint
 #[cfg(feature = "wallet")]
-#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct PreMint {
     /// Blinded message
     pub blinded_message: BlindedMessage,
@@ -734,7 +781,7 @@ impl PartialOrd for PreMint {
 
 /// Premint Secrets
 #[cfg(feature = "wallet")]
-#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct PreMintSecrets {
     /// Secrets
     pub secrets: Vec<PreMint>,
@@ -989,6 +1036,43 @@ mod tests {
         assert_eq!(b.len(), 1);
     }
 
+    #[test]
+    fn test_blinded_message_cbor_round_trip() {
+        let public_key = PublicKey::from_hex(
+            "02194603ffa36356f4a56b7df9371fc3192472351453ec7398b8da8117e7c3e104",
+        )
+        .unwrap();
+        let blinded_message = BlindedMessage::new(
+            Amount::from(8),
+            Id::from_str("009a1f293253e41e").unwrap(),
+            public_key,
+        );
+
+        let mut cbor = Vec::new();
+        ciborium::into_writer(&blinded_message, &mut cbor).unwrap();
+        let decoded: BlindedMessage = ciborium::from_reader(&cbor[..]).unwrap();
+        assert_eq!(decoded, blinded_message);
+
+        // Only the mint request and response fields use the compressed bytes
+        let mut blinded_secret = Vec::new();
+        ciborium::into_writer(
+            &ciborium::Value::Bytes(public_key.to_bytes().to_vec()),
+            &mut blinded_secret,
+        )
+        .unwrap();
+        assert!(cbor
+            .windows(blinded_secret.len())
+            .any(|window| window == blinded_secret.as_slice()));
+
+        let mut key_cbor = Vec::new();
+        ciborium::into_writer(&public_key, &mut key_cbor).unwrap();
+        let key_hex: String = ciborium::from_reader(&key_cbor[..]).unwrap();
+        assert_eq!(key_hex, public_key.to_hex());
+
+        let json = serde_json::to_value(&blinded_message).unwrap();
+        assert_eq!(json["B_"], public_key.to_hex());
+    }
+
     #[test]
     fn custom_unit_ser_der() {
         let unit = CurrencyUnit::Custom(String::from("test"));
@@ -1049,4 +1133,44 @@ mod tests {
             assert_eq!(method, deserialized);
         }
     }
+
+    #[test]
+    fn test_hash_unit_parsing() {
+        for value in ["HASH", "hash", "Hash"] {
+            assert_eq!(CurrencyUnit::from_str(value).unwrap(), CurrencyUnit::Hash);
+        }
+
+        let unit: CurrencyUnit = serde_json::from_str("\"HASH\"").unwrap();
+        assert_eq!(unit, CurrencyUnit::Hash);
+        let serialized = serde_json::to_string(&CurrencyUnit::Hash).unwrap();
+        assert_eq!(
+            serde_json::from_str::<CurrencyUnit>(&serialized).unwrap(),
+            CurrencyUnit::Hash
+        );
+
+        // The legacy custom form is read back as the known unit
+        let legacy = CurrencyUnit::Custom("HASH".to_string());
+        assert_eq!(legacy.clone().canonical(), CurrencyUnit::Hash);
+        assert_eq!(
+            serde_json::from_str::<CurrencyUnit>(&serde_json::to_string(&legacy).unwrap()).unwrap(),
+            CurrencyUnit::Hash
+        );
+
+        let custom = CurrencyUnit::Custom("points".to_string());
+        assert_eq!(custom.clone().canonical(), custom);
+    }
+
+    #[cfg(feature = "mint")]
+    #[test]
+    fn test_hash_unit_derivation_index() {
+        assert_eq!(CurrencyUnit::Hash.derivation_index(), Some(5));
+        assert_eq!(
+            CurrencyUnit::Custom("HASH".to_string()).derivation_index(),
+            Some(5)
+        );
+        assert_eq!(
+            CurrencyUnit::Custom("points".to_string()).derivation_index(),
+            None
+        );
+    }
 }
@@ -5,8 +5,7 @@
 use core::fmt;
 use core::str::FromStr;
 use std::array::TryFromSliceError;
-#[cfg(feature = "mint")]
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap};
 
 #[cfg(feature = "mint")]
 use bitcoin::bip32::{ChildNumber, DerivationPath, Xpriv};
@@ -518,6 +517,46 @@ impl KeySetInfosMethods for KeySetInfos {
     }
 }
 
+/// Public keys two keysets have in common
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SharedKeys {
+    /// Keyset the keys were first seen in
+    pub keyset_id: Id,
+    /// Keyset reusing the keys
+    pub other_keyset_id: Id,
+    /// Amounts of the reused keys in `other_keyset_id`
+    pub amounts: Vec<Amount>,
+}
+
+/// Find public keys reused across keysets
+///
+/// Every amount key should be unique to its keyset. A key that shows up in
+/// more than one keyset means the keys were derived from the same path, which
+/// links proofs across those keysets.
+pub fn find_shared_keys<'a>(keysets: impl IntoIterator<Item = (Id, &'a Keys)>) -> Vec<SharedKeys> {
+    let mut owners: HashMap<[u8; 33], Id> = HashMap::new();
+    let mut shared: BTreeMap<(Id, Id), Vec<Amount>> = BTreeMap::new();
+
+    for (id, keys) in keysets {
+        for (amount, key) in keys.iter() {
+            let owner = *owners.entry(key.to_bytes()).or_insert(id);
+
+            if owner != id {
+                shared.entry((owner, id)).or_default().push(*amount);
+            }
+        }
+    }
+
+    shared
+        .into_iter()
+        .map(|((keyset_id, other_keyset_id), amounts)| SharedKeys {
+            keyset_id,
+            other_keyset_id,
+            amounts,
+        })
+        .collect()
+}
+
 fn deserialize_input_fee_ppk<'de, D>(deserializer: D) -> Result<u64, D::Error>
 where
     D: Deserializer<'de>,
@@ -665,7 +704,7 @@ mod test {
     use crate::nuts::nut02::{Error, Id};
     use crate::nuts::KeysResponse;
     use crate::util::hex;
-    use crate::CurrencyUnit;
+    use crate::{Amount, CurrencyUnit};
 
     const SHORT_KEYSET_ID: &str = "00456a94ab4e1c46";
     const SHORT_KEYSET: &str = r#"
@@ -921,4 +960,48 @@ mod test {
         assert!(short_id_1.to_string() == "009a1f293253e41e");
         assert!(short_id_2.to_string() == "01adc013fa9d8517");
     }
+
+    #[test]
+    fn test_find_shared_keys() {
+        let short_keys: Keys = serde_json::from_str(SHORT_KEYSET).unwrap();
+        let keys: Keys = serde_json::from_str(KEYSET).unwrap();
+        let short_id = Id::from_str(SHORT_KEYSET_ID).unwrap();
+        let id = Id::from_str(KEYSET_ID).unwrap();
+
+        assert!(super::find_shared_keys([(short_id, &short_keys), (id, &keys)]).is_empty());
+
+        // Copy the second keyset, reusing the amount 4 key of the first one as
+        // its amount 8 key. Pairs are ordered by keyset id.
+        let mut reused = keys.keys().clone();
+        reused.insert(
+            Amount::from(8),
+            short_keys.amount_key(Amount::from(4)).unwrap(),
+        );
+        let reused = Keys::new(reused);
+        let reused_id = Id::v1_from_keys(&reused);
+
+        let shared =
+            super::find_shared_keys([(short_id, &short_keys), (id, &keys), (reused_id, &reused)]);
+
+        assert_eq!(
+            shared,
+            vec![
+                super::SharedKeys {
+                    keyset_id: id,
+                    other_keyset_id: reused_id,
+                    amounts: keys
+                        .keys()
+                        .keys()
+                        .filter(|amount| **amount != Amount::from(8))
+                        .copied()
+                        .collect(),
+                },
+                super::SharedKeys {
+                    keyset_id: short_id,
+                    other_keyset_id: reused_id,
+                    amounts: vec![Amount::from(8)],
+                },
+            ]
+        );
+    }
 }
@@ -11,7 +11,7 @@ use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use super::nut01::PublicKey;
 use super::nut17::SupportedMethods;
 use super::nut19::CachedEndpoint;
-use super::{nut04, nut05, nut15, nut19, MppMethodSettings};
+use super::{nut04, nut05, nut15, nut19, nutXX, MppMethodSettings};
 #[cfg(feature = "auth")]
 use super::{AuthRequired, BlindAuthSettings, ClearAuthSettings, ProtectedEndpoint};
 use crate::CurrencyUnit;
@@ -336,6 +336,10 @@ pub struct Nuts {
     #[serde(skip_serializing_if = "Option::is_none")]
     #[cfg(feature = "auth")]
     pub nut22: Option<BlindAuthSettings>,
+    /// NUT-XX Mining share settings
+    #[serde(rename = "XX")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nutxx: Option<nutXX::Settings>,
 }
 
 impl Nuts {
@@ -453,6 +457,14 @@ impl Nuts {
         }
     }
 
+    /// NUT-XX Mining share settings
+    pub fn nutxx(self, settings: nutXX::Settings) -> Self {
+        Self {
+            nutxx: Some(settings),
+            ..self
+        }
+    }
+
     /// Units where minting is supported
     pub fn supported_mint_units(&self) -> Vec<&CurrencyUnit> {
         self.nut04
@@ -1,8 +1,12 @@
 //! Specific Subscription for the cdk crate
+use std::fmt;
+#[cfg(feature = "mint")]
+use std::str::FromStr;
+
+use bitcoin::hashes::sha256;
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 
-#[cfg(feature = "mint")]
 use super::PublicKey;
 use crate::nuts::nutXX::MintQuoteMiningShareResponse;
 use crate::nuts::{
@@ -27,6 +31,60 @@ pub struct Params<I> {
     pub id: I,
 }
 
+#[cfg(feature = "mint")]
+impl<I> Params<I> {
+    /// Parse the filters according to the subscription [`Kind`]
+    ///
+    /// Filters travel as strings on the wire. Every entry that is not valid for
+    /// the kind is reported in [`Error::InvalidFilters`].
+    pub fn typed_filters(&self) -> Result<Vec<Filter<QuoteId>>, Error> {
+        let mut typed = Vec::with_capacity(self.filters.len());
+        let mut invalid = Vec::new();
+
+        for filter in &self.filters {
+            match self.kind.parse_filter(filter) {
+                Ok(filter) => typed.push(filter),
+                Err(_) => invalid.push(filter.clone()),
+            }
+        }
+
+        if !invalid.is_empty() {
+            return Err(Error::InvalidFilters {
+                kind: self.kind,
+                filters: invalid,
+            });
+        }
+
+        Ok(typed)
+    }
+}
+
+/// Typed subscription filter
+///
+/// The quote id is a [`QuoteId`] on the mint and a `String` on the wallet.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Filter<Q> {
+    /// Quote id, used by the quote kinds
+    QuoteId(Q),
+    /// Proof `Y`, used by [`Kind::ProofState`]
+    ProofY(PublicKey),
+    /// Block header hash of a share, used by [`Kind::MiningShareMintQuote`]
+    HeaderHash(sha256::Hash),
+}
+
+impl<Q> fmt::Display for Filter<Q>
+where
+    Q: fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Filter::QuoteId(quote_id) => write!(f, "{quote_id}"),
+            Filter::ProofY(y) => write!(f, "{y}"),
+            Filter::HeaderHash(header_hash) => write!(f, "{header_hash}"),
+        }
+    }
+}
+
 /// Check state Settings
 #[derive(Debug, Default, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[cfg_attr(feature = "swagger", derive(utoipa::ToSchema))]
@@ -72,6 +130,17 @@ impl SupportedMethods {
         }
     }
 
+    /// Create [`SupportedMethods`] for mining shares with all supported commands
+    pub fn default_mining_share(unit: CurrencyUnit) -> Self {
+        let commands = vec![WsCommand::MiningShareMintQuote, WsCommand::ProofState];
+
+        Self {
+            method: PaymentMethod::MiningShare,
+            unit,
+            commands,
+        }
+    }
+
     /// Create [`SupportedMethods`] for Bolt12 with all supported commands
     pub fn default_bolt12(unit: CurrencyUnit) -> Self {
         let commands = vec![
@@ -108,6 +177,9 @@ pub enum WsCommand {
     /// Command to check the state of a proof
     #[serde(rename = "proof_state")]
     ProofState,
+    /// Websocket support for Mining Share Mint Quote
+    #[serde(rename = "mining_share_mint_quote")]
+    MiningShareMintQuote,
 }
 
 impl<T> From<MintQuoteBolt12Response<T>> for NotificationPayload<T> {
@@ -129,14 +201,16 @@ impl<T> From<MintQuoteMiningShareResponse<T>> for NotificationPayload<T> {
 pub enum NotificationPayload<T> {
     /// Proof State
     ProofState(ProofState),
+    /// Mint Quote Mining Share Response
+    ///
+    /// Listed before the bolt11 responses, which would also match its fields
+    MintQuoteMiningShareResponse(MintQuoteMiningShareResponse<T>),
     /// Melt Quote Bolt11 Response
     MeltQuoteBolt11Response(MeltQuoteBolt11Response<T>),
     /// Mint Quote Bolt11 Response
     MintQuoteBolt11Response(MintQuoteBolt11Response<T>),
     /// Mint Quote Bolt12 Response
     MintQuoteBolt12Response(MintQuoteBolt12Response<T>),
-    /// Mint Quote Mining Share Response
-    MintQuoteMiningShareResponse(MintQuoteMiningShareResponse<T>),
 }
 
 impl<T> From<ProofState> for NotificationPayload<T> {
@@ -173,6 +247,41 @@ pub enum Notification {
     MeltQuoteBolt12(QuoteId),
     /// MintQuote id is a QuoteId
     MintQuoteMiningShare(QuoteId),
+    /// Mining share MintQuote addressed by the header hash of its share
+    MintQuoteMiningShareHash(sha256::Hash),
+}
+
+#[cfg(feature = "mint")]
+impl Notification {
+    /// Parse a wire filter of a subscription of `kind` into a [`Notification`]
+    ///
+    /// Proof state filters are public keys and quote filters are quote ids.
+    /// Mining share filters may also be the 64 hex character header hash of
+    /// the share, which takes precedence over reading it as a base64 quote id.
+    pub fn from_filter(kind: Kind, filter: &str) -> Result<Self, Error> {
+        match (kind, kind.parse_filter(filter)?) {
+            (Kind::ProofState, Filter::ProofY(y)) => Ok(Notification::ProofState(y)),
+            (Kind::Bolt11MeltQuote, Filter::QuoteId(quote_id)) => {
+                Ok(Notification::MeltQuoteBolt11(quote_id))
+            }
+            (Kind::Bolt11MintQuote, Filter::QuoteId(quote_id)) => {
+                Ok(Notification::MintQuoteBolt11(quote_id))
+            }
+            (Kind::Bolt12MintQuote, Filter::QuoteId(quote_id)) => {
+                Ok(Notification::MintQuoteBolt12(quote_id))
+            }
+            (Kind::MiningShareMintQuote, Filter::QuoteId(quote_id)) => {
+                Ok(Notification::MintQuoteMiningShare(quote_id))
+            }
+            (Kind::MiningShareMintQuote, Filter::HeaderHash(header_hash)) => {
+                Ok(Notification::MintQuoteMiningShareHash(header_hash))
+            }
+            (kind, filter) => Err(Error::InvalidFilters {
+                kind,
+                filters: vec![filter.to_string()],
+            }),
+        }
+    }
 }
 
 /// Kind
@@ -187,6 +296,60 @@ pub enum Kind {
     ProofState,
     /// Bolt 12 Mint Quote
     Bolt12MintQuote,
+    /// Mining Share Mint Quote
+    MiningShareMintQuote,
+}
+
+impl Kind {
+    /// Whether subscriptions of this kind accept `filter`
+    pub fn accepts<Q>(&self, filter: &Filter<Q>) -> bool {
+        matches!(
+            (self, filter),
+            (Kind::ProofState, Filter::ProofY(_))
+                | (
+                    Kind::Bolt11MeltQuote
+                        | Kind::Bolt11MintQuote
+                        | Kind::Bolt12MintQuote
+                        | Kind::MiningShareMintQuote,
+                    Filter::QuoteId(_)
+                )
+                | (Kind::MiningShareMintQuote, Filter::HeaderHash(_))
+        )
+    }
+
+    /// Parse a wire filter into the [`Filter`] type expected by this kind
+    #[cfg(feature = "mint")]
+    pub fn parse_filter(&self, filter: &str) -> Result<Filter<QuoteId>, Error> {
+        match self {
+            Kind::ProofState => Ok(Filter::ProofY(PublicKey::from_str(filter)?)),
+            Kind::MiningShareMintQuote if is_header_hash(filter) => Ok(Filter::HeaderHash(
+                sha256::Hash::from_str(filter).map_err(|_| Error::InvalidFilters {
+                    kind: *self,
+                    filters: vec![filter.to_owned()],
+                })?,
+            )),
+            Kind::Bolt11MeltQuote
+            | Kind::Bolt11MintQuote
+            | Kind::Bolt12MintQuote
+            | Kind::MiningShareMintQuote => {
+                // Some public keys are also valid base64 quote ids
+                if PublicKey::from_str(filter).is_ok() {
+                    return Err(Error::InvalidFilters {
+                        kind: *self,
+                        filters: vec![filter.to_owned()],
+                    });
+                }
+
+                Ok(Filter::QuoteId(QuoteId::from_str(filter)?))
+            }
+        }
+    }
+}
+
+/// Whether a filter is written like a share header hash: 64 hex characters
+#[cfg(feature = "mint")]
+fn is_header_hash(filter: &str) -> bool {
+    filter.len() == 64 && filter.bytes().all(|byte| byte.is_ascii_hexdigit())
 }
 
 impl<I> AsRef<I> for Params<I> {
@@ -206,4 +369,169 @@ pub enum Error {
     #[error("PublicKey Error: {0}")]
     /// PublicKey Error
     PublicKey(#[from] crate::nuts::nut01::Error),
+
+    #[error("Invalid filters for {kind:?}: {}", filters.join(", "))]
+    /// Filters that are not valid for the subscription kind
+    InvalidFilters {
+        /// Subscription kind
+        kind: Kind,
+        /// Rejected filter entries
+        filters: Vec<String>,
+    },
+}
+
+#[cfg(all(test, feature = "mint"))]
+mod tests {
+    use bitcoin::hashes::Hash;
+
+    use super::*;
+
+    const PUBKEY: &str = "02194603ffa36356f4a56b7df9371fc3192472351453ec7398b8da8117e7c3e104";
+    const QUOTE_ID: &str = "5a5c0f0b-3e2f-4a51-9f0e-6b1c1f2d8e41";
+
+    const QUOTE_KINDS: [Kind; 4] = [
+        Kind::Bolt11MeltQuote,
+        Kind::Bolt11MintQuote,
+        Kind::Bolt12MintQuote,
+        Kind::MiningShareMintQuote,
+    ];
+
+    #[test]
+    fn test_quote_kinds_accept_quote_ids() {
+        for kind in QUOTE_KINDS {
+            let filter = kind.parse_filter(QUOTE_ID).unwrap();
+            assert_eq!(
+                filter,
+                Filter::QuoteId(QuoteId::from_str(QUOTE_ID).unwrap())
+            );
+            assert_eq!(filter.to_string(), QUOTE_ID);
+        }
+    }
+
+    #[test]
+    fn test_quote_kinds_reject_public_keys() {
+        for kind in QUOTE_KINDS {
+            assert!(matches!(
+                kind.parse_filter(PUBKEY),
+                Err(Error::InvalidFilters { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn test_proof_state_accepts_proof_y() {
+        let filter = Kind::ProofState.parse_filter(PUBKEY).unwrap();
+        assert_eq!(filter, Filter::ProofY(PublicKey::from_str(PUBKEY).unwrap()));
+        assert_eq!(filter.to_string(), PUBKEY);
+    }
+
+    #[test]
+    fn test_proof_state_rejects_quote_ids() {
+        assert!(Kind::ProofState.parse_filter(QUOTE_ID).is_err());
+    }
+
+    #[test]
+    fn test_accepts_matches_parse_filter() {
+        let header_hash = sha256::Hash::hash(b"share").to_string();
+
+        for kind in QUOTE_KINDS.into_iter().chain([Kind::ProofState]) {
+            for filter in [
+                Filter::QuoteId(QUOTE_ID.to_string()),
+                Filter::ProofY(PUBKEY.to_string()),
+                Filter::HeaderHash(header_hash.clone()),
+            ] {
+                assert_eq!(
+                    kind.accepts(&filter),
+                    kind.parse_filter(&filter.to_string())
+                        .is_ok_and(|parsed| std::mem::discriminant(&parsed)
+                            == std::mem::discriminant(&filter)),
+                    "{kind:?} {filter}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_notification_from_filter() {
+        let header_hash = sha256::Hash::hash(b"share");
+        let quote_id = QuoteId::from_str(QUOTE_ID).unwrap();
+
+        assert_eq!(
+            Notification::from_filter(Kind::ProofState, PUBKEY).unwrap(),
+            Notification::ProofState(PublicKey::from_str(PUBKEY).unwrap())
+        );
+        assert_eq!(
+            Notification::from_filter(Kind::Bolt11MintQuote, QUOTE_ID).unwrap(),
+            Notification::MintQuoteBolt11(quote_id.clone())
+        );
+        assert_eq!(
+            Notification::from_filter(Kind::Bolt11MeltQuote, QUOTE_ID).unwrap(),
+            Notification::MeltQuoteBolt11(quote_id.clone())
+        );
+        assert_eq!(
+            Notification::from_filter(Kind::MiningShareMintQuote, QUOTE_ID).unwrap(),
+            Notification::MintQuoteMiningShare(quote_id)
+        );
+        assert_eq!(
+            Notification::from_filter(Kind::MiningShareMintQuote, &header_hash.to_string())
+                .unwrap(),
+            Notification::MintQuoteMiningShareHash(header_hash)
+        );
+    }
+
+    #[test]
+    fn test_notification_from_invalid_filter() {
+        let header_hash = sha256::Hash::hash(b"share").to_string();
+
+        assert!(Notification::from_filter(Kind::ProofState, QUOTE_ID).is_err());
+        assert!(Notification::from_filter(Kind::ProofState, &header_hash).is_err());
+        assert!(Notification::from_filter(Kind::MiningShareMintQuote, PUBKEY).is_err());
+        assert!(Notification::from_filter(Kind::MiningShareMintQuote, "not a quote").is_err());
+        assert!(Notification::from_filter(Kind::MiningShareMintQuote, &header_hash[1..]).is_err());
+        assert!(Notification::from_filter(Kind::Bolt11MintQuote, PUBKEY).is_err());
+    }
+
+    #[test]
+    fn test_mining_share_payload_round_trip() {
+        let response = MintQuoteMiningShareResponse {
+            quote: QUOTE_ID.to_owned(),
+            request: "header_hash".to_owned(),
+            amount: Some(crate::Amount::from(64)),
+            unit: Some(CurrencyUnit::Sat),
+            state: crate::nuts::nutXX::QuoteState::Issued,
+            expiry: Some(1234567890),
+            pubkey: PublicKey::from_str(PUBKEY).unwrap(),
+            keyset_id: crate::nuts::Id::from_str("00456a94ab4e1c46").unwrap(),
+            amount_issued: crate::Amount::from(64),
+            difficulty: None,
+        };
+
+        let json = serde_json::to_string(&NotificationPayload::from(response.clone())).unwrap();
+        let payload: NotificationPayload<String> = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            payload,
+            NotificationPayload::MintQuoteMiningShareResponse(response)
+        );
+    }
+
+    #[test]
+    fn test_typed_filters_report_all_invalid_entries() {
+        let params = Params {
+            kind: Kind::Bolt11MintQuote,
+            filters: vec![
+                QUOTE_ID.to_owned(),
+                PUBKEY.to_owned(),
+                "not a quote".to_owned(),
+            ],
+            id: "uno".to_owned(),
+        };
+
+        match params.typed_filters() {
+            Err(Error::InvalidFilters { kind, filters }) => {
+                assert_eq!(kind, Kind::Bolt11MintQuote);
+                assert_eq!(filters, vec![PUBKEY.to_owned(), "not a quote".to_owned()]);
+            }
+            other => panic!("unexpected result {other:?}"),
+        }
+    }
 }
@@ -8,6 +8,9 @@ use super::{NotificationPayload, Params};
 /// JSON RPC version
 pub const JSON_RPC_VERSION: &str = "2.0";
 
+/// Method of the notification sent when the server starts draining connections
+pub const DRAINING_METHOD: &str = "server_draining";
+
 /// The response to a subscription request
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(bound = "I: Serialize + DeserializeOwned")]
@@ -122,6 +125,17 @@ pub struct WsNotification<T> {
     pub params: T,
 }
 
+/// Parameters of the draining notification
+///
+/// Sent to every connected client before the server closes the connection on
+/// shutdown. Clients should wait the suggested delay before reconnecting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WsDraining {
+    /// Suggested delay before reconnecting, in milliseconds
+    #[serde(rename = "reconnectAfterMs")]
+    pub reconnect_after_ms: u64,
+}
+
 /// Websocket error
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct WsErrorBody {
@@ -129,6 +143,9 @@ pub struct WsErrorBody {
     pub code: i32,
     /// Error message
     pub message: String,
+    /// Additional structured information about the error
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<serde_json::Value>,
 }
 
 /// Websocket response
@@ -165,6 +182,8 @@ pub enum WsMessageOrResponse<I> {
     ErrorResponse(WsErrorResponse),
     /// A notification
     Notification(WsNotification<NotificationInner<String, I>>),
+    /// The server is draining connections before shutting down
+    Draining(WsNotification<WsDraining>),
 }
 
 impl<I> From<(usize, Result<WsResponseResult<I>, WsErrorBody>)> for WsMessageOrResponse<I> {
@@ -183,3 +202,23 @@ impl<I> From<(usize, Result<WsResponseResult<I>, WsErrorBody>)> for WsMessageOrR
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_draining_message() {
+        let json =
+            r#"{"jsonrpc":"2.0","method":"server_draining","params":{"reconnectAfterMs":2500}}"#;
+
+        let message: WsMessageOrResponse<String> = serde_json::from_str(json).unwrap();
+        match message {
+            WsMessageOrResponse::Draining(notification) => {
+                assert_eq!(notification.method, DRAINING_METHOD);
+                assert_eq!(notification.params.reconnect_after_ms, 2500);
+            }
+            _ => panic!("Expected draining notification"),
+        }
+    }
+}
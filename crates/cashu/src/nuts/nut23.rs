@@ -56,6 +56,24 @@ pub enum QuoteState {
     Paid,
     /// ecash issued for quote
     Issued,
+    /// Quote was converted to a quote of another payment method
+    Converted,
+    /// Quote was cancelled before ecash was issued for it
+    Cancelled,
+}
+
+impl QuoteState {
+    /// State reported to wallets in NUT-04 responses
+    ///
+    /// [`QuoteState::Converted`] and [`QuoteState::Cancelled`] are only tracked
+    /// by the mint. Both close the quote for good, so they are reported as
+    /// [`QuoteState::Issued`], the final state every wallet understands.
+    pub fn to_spec(self) -> Self {
+        match self {
+            Self::Converted | Self::Cancelled => Self::Issued,
+            state => state,
+        }
+    }
 }
 
 impl fmt::Display for QuoteState {
@@ -64,6 +82,8 @@ impl fmt::Display for QuoteState {
             Self::Unpaid => write!(f, "UNPAID"),
             Self::Paid => write!(f, "PAID"),
             Self::Issued => write!(f, "ISSUED"),
+            Self::Converted => write!(f, "CONVERTED"),
+            Self::Cancelled => write!(f, "CANCELLED"),
         }
     }
 }
@@ -76,6 +96,8 @@ impl FromStr for QuoteState {
             "PAID" => Ok(Self::Paid),
             "UNPAID" => Ok(Self::Unpaid),
             "ISSUED" => Ok(Self::Issued),
+            "CONVERTED" => Ok(Self::Converted),
+            "CANCELLED" => Ok(Self::Cancelled),
             _ => Err(Error::UnknownState),
         }
     }
@@ -1,14 +1,17 @@
 //! NUT-XX: Mining share functionality
 
+use std::fmt::Display;
+use std::str::FromStr;
+
 use bitcoin::hashes::{sha256, Hash};
+use bitcoin::secp256k1::schnorr::Signature;
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
-use std::fmt::Display;
+use thiserror::Error;
 
 use super::nut02::Id;
-use super::{CurrencyUnit, PublicKey};
+use super::{nut20, CurrencyUnit, PaymentMethod, PublicKey, SecretKey};
 use crate::Amount;
-use thiserror::Error;
 
 /// NUT-XX Error
 #[derive(Debug, Error)]
@@ -21,6 +24,87 @@ pub enum Error {
     InvalidHash,
 }
 
+/// Media type of the compact CBOR encoding for mining share requests and responses
+pub const CBOR_CONTENT_TYPE: &str = "application/cbor";
+
+/// Outputs a mining share mint request may use beyond the minimal split of
+/// the quote amount when the mint does not advertise a slack
+pub const DEFAULT_OUTPUT_SLACK: u32 = 2;
+
+/// Largest mining share quote amount a mint accepts when it does not advertise
+/// a maximum
+pub const DEFAULT_MAX_MINING_SHARE_AMOUNT: u64 = 256;
+
+/// Mining share settings advertised in the mint info
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "swagger", derive(utoipa::ToSchema))]
+pub struct Settings {
+    /// Mint accepts and returns [`CBOR_CONTENT_TYPE`] bodies on the mining share mint endpoint
+    #[serde(default)]
+    pub cbor: bool,
+    /// Outputs a mint request may use beyond the minimal split of the quote amount
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_slack: Option<u32>,
+    /// Most unissued, unexpired quotes a NUT-20 pubkey may hold at once
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_pending_quotes_per_pubkey: Option<u32>,
+    /// Largest amount a mining share quote may request
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_amount: Option<Amount>,
+    /// Largest total amount of the quotes of one mining share quote batch
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_batch_amount: Option<Amount>,
+    /// Mint accepts cancelling mint quotes that have no ecash issued
+    #[serde(default)]
+    pub quote_cancel: bool,
+}
+
+impl Settings {
+    /// Outputs allowed beyond the minimal split, [`DEFAULT_OUTPUT_SLACK`] if not set
+    pub fn output_slack(&self) -> u32 {
+        self.output_slack.unwrap_or(DEFAULT_OUTPUT_SLACK)
+    }
+
+    /// Largest quote amount, [`DEFAULT_MAX_MINING_SHARE_AMOUNT`] if not set
+    pub fn max_amount(&self) -> Amount {
+        self.max_amount
+            .unwrap_or(Amount::from(DEFAULT_MAX_MINING_SHARE_AMOUNT))
+    }
+
+    /// Most outputs a mint request for a quote of `amount` may use in a keyset
+    /// with the given denominations
+    pub fn max_outputs(&self, amount: Amount, denominations: &[u64]) -> usize {
+        minimal_output_count(amount, denominations).saturating_add(self.output_slack() as usize)
+    }
+}
+
+/// Number of outputs in the minimal representation of `amount` using the
+/// denominations of a keyset
+///
+/// Falls back to powers of two when no denominations are given.
+pub fn minimal_output_count(amount: Amount, denominations: &[u64]) -> usize {
+    if denominations.is_empty() {
+        return amount.split().len();
+    }
+
+    let mut denominations: Vec<u64> = denominations.iter().copied().filter(|d| *d > 0).collect();
+    denominations.sort_unstable_by(|a, b| b.cmp(a));
+
+    let mut remaining = u64::from(amount);
+    let mut count: u64 = 0;
+    for denomination in denominations {
+        count = count.saturating_add(remaining / denomination);
+        remaining %= denomination;
+    }
+
+    // An amount the denominations cannot represent still needs an output
+    if remaining > 0 {
+        count = count.saturating_add(1);
+    }
+
+    usize::try_from(count).unwrap_or(usize::MAX)
+}
+
 /// Quote state for mining shares
 #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Default, Serialize, Deserialize)]
 #[serde(rename_all = "UPPERCASE")]
@@ -33,6 +117,10 @@ pub enum QuoteState {
     Paid,
     /// Quote is paid and cashu tokens have been issued for it
     Issued,
+    /// Quote was converted to a quote of another payment method
+    Converted,
+    /// Quote was cancelled before ecash was issued for it
+    Cancelled,
 }
 
 impl Display for QuoteState {
@@ -41,6 +129,8 @@ impl Display for QuoteState {
             QuoteState::Unpaid => write!(f, "UNPAID"),
             QuoteState::Paid => write!(f, "PAID"),
             QuoteState::Issued => write!(f, "ISSUED"),
+            QuoteState::Converted => write!(f, "CONVERTED"),
+            QuoteState::Cancelled => write!(f, "CANCELLED"),
         }
     }
 }
@@ -53,6 +143,8 @@ impl std::str::FromStr for QuoteState {
             "UNPAID" => Ok(QuoteState::Unpaid),
             "PAID" => Ok(QuoteState::Paid),
             "ISSUED" => Ok(QuoteState::Issued),
+            "CONVERTED" => Ok(QuoteState::Converted),
+            "CANCELLED" => Ok(QuoteState::Cancelled),
             _ => Err(Error::InvalidAmount),
         }
     }
@@ -104,14 +196,22 @@ pub struct MintQuoteMiningShareRequest {
     pub description: Option<String>,
     /// Pubkey for NUT-20 signature validation
     pub pubkey: PublicKey,
+    /// Difficulty of the share, for auditing the amount against it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub difficulty: Option<u64>,
 }
 
 impl MintQuoteMiningShareRequest {
-    /// Validate the mining share request
+    /// Validate the mining share request against [`DEFAULT_MAX_MINING_SHARE_AMOUNT`]
     pub fn validate(&self) -> Result<(), Error> {
-        // Valid amounts are between 1 and u64::MAX inclusive
+        self.validate_with_max(Amount::from(DEFAULT_MAX_MINING_SHARE_AMOUNT))
+    }
+
+    /// Validate the mining share request against the mint's maximum amount
+    pub fn validate_with_max(&self, max_amount: Amount) -> Result<(), Error> {
+        // Valid amounts are between 1 and the maximum inclusive
         // Amounts use exponential units (2^difficulty)
-        if self.amount == Amount::ZERO || self.amount > Amount::from(u64::MAX) {
+        if self.amount == Amount::ZERO || self.amount > max_amount {
             return Err(Error::InvalidAmount);
         }
 
@@ -138,6 +238,9 @@ pub struct MintQuoteMiningShareResponse<Q> {
     /// Currency unit
     pub unit: Option<CurrencyUnit>,
     /// Quote State
+    ///
+    /// Required, a response without a state must not be taken for a mintable
+    /// quote.
     pub state: QuoteState,
     /// Unix timestamp until which the quote is valid
     pub expiry: Option<u64>,
@@ -146,7 +249,11 @@ pub struct MintQuoteMiningShareResponse<Q> {
     /// Keyset ID for this quote
     pub keyset_id: Id,
     /// Amount that has been issued for this quote
+    #[serde(default)]
     pub amount_issued: Amount,
+    /// Difficulty of the share the quote was created for, if given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub difficulty: Option<u64>,
 }
 
 impl<Q: ToString> MintQuoteMiningShareResponse<Q> {
@@ -162,17 +269,14 @@ impl<Q: ToString> MintQuoteMiningShareResponse<Q> {
             pubkey: self.pubkey,
             keyset_id: self.keyset_id,
             amount_issued: self.amount_issued,
+            difficulty: self.difficulty,
         }
     }
 
     /// Check if quote has expired
-    pub fn is_expired(&self) -> bool {
+    pub fn is_expired(&self, current_time: u64) -> bool {
         if let Some(expiry) = self.expiry {
-            let now = std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .map(|d| d.as_secs())
-                .unwrap_or(0);
-            now > expiry
+            current_time > expiry
         } else {
             false
         }
@@ -203,6 +307,7 @@ impl From<MintQuoteMiningShareResponse<uuid::Uuid>> for MintQuoteMiningShareResp
             pubkey: value.pubkey,
             keyset_id: value.keyset_id,
             amount_issued: value.amount_issued,
+            difficulty: value.difficulty,
         }
     }
 }
@@ -222,16 +327,340 @@ impl From<MintQuoteMiningShareResponse<crate::quote_id::QuoteId>>
             pubkey: value.pubkey,
             keyset_id: value.keyset_id,
             amount_issued: value.amount_issued,
+            difficulty: value.difficulty,
         }
     }
 }
 
+/// Most quotes a [`BatchQuoteStatusRequest`] may ask for
+pub const MAX_BATCH_QUOTE_STATUS: usize = 100;
+
+/// Request for the state of several mining share mint quotes
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "swagger", derive(utoipa::ToSchema))]
+pub struct BatchQuoteStatusRequest {
+    /// Quote IDs, at most [`MAX_BATCH_QUOTE_STATUS`]
+    pub quotes: Vec<String>,
+}
+
+/// State of one quote in a [`BatchQuoteStatusResponse`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "swagger", derive(utoipa::ToSchema))]
+#[serde(untagged, bound = "Q: Serialize + DeserializeOwned")]
+pub enum BatchQuoteStatusItem<Q> {
+    /// Mining share mint quote
+    Quote(MintQuoteMiningShareResponse<Q>),
+    /// The quote could not be checked
+    Error {
+        /// Quote ID as requested
+        quote: String,
+        /// Why the quote could not be checked
+        error: String,
+    },
+}
+
+/// State of several mining share mint quotes, in the order they were requested
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "swagger", derive(utoipa::ToSchema))]
+#[serde(bound = "Q: Serialize + DeserializeOwned")]
+pub struct BatchQuoteStatusResponse<Q> {
+    /// One entry per requested quote
+    pub quotes: Vec<BatchQuoteStatusItem<Q>>,
+}
+
+#[cfg(feature = "mint")]
+impl From<BatchQuoteStatusResponse<crate::quote_id::QuoteId>> for BatchQuoteStatusResponse<String> {
+    fn from(value: BatchQuoteStatusResponse<crate::quote_id::QuoteId>) -> Self {
+        Self {
+            quotes: value
+                .quotes
+                .into_iter()
+                .map(|item| match item {
+                    BatchQuoteStatusItem::Quote(quote) => BatchQuoteStatusItem::Quote(quote.into()),
+                    BatchQuoteStatusItem::Error { quote, error } => {
+                        BatchQuoteStatusItem::Error { quote, error }
+                    }
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Most quotes a [`BatchMintQuoteMiningShareRequest`] may create
+pub const MAX_BATCH_MINT_QUOTE_MINING_SHARE: usize = 100;
+
+/// Request to create several mining share mint quotes
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "swagger", derive(utoipa::ToSchema))]
+pub struct BatchMintQuoteMiningShareRequest {
+    /// Quote requests, at most [`MAX_BATCH_MINT_QUOTE_MINING_SHARE`]
+    pub quotes: Vec<MintQuoteMiningShareRequest>,
+}
+
+/// Outcome of one request in a [`BatchMintQuoteMiningShareResponse`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "swagger", derive(utoipa::ToSchema))]
+#[serde(untagged, bound = "Q: Serialize + DeserializeOwned")]
+pub enum BatchMintQuoteMiningShareItem<Q> {
+    /// Created mining share mint quote
+    Quote(MintQuoteMiningShareResponse<Q>),
+    /// The quote could not be created
+    Error {
+        /// Mining share hash of the request
+        header_hash: sha256::Hash,
+        /// Why the quote could not be created
+        error: String,
+    },
+}
+
+/// Mining share mint quotes created from a batch, in the order they were requested
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "swagger", derive(utoipa::ToSchema))]
+#[serde(bound = "Q: Serialize + DeserializeOwned")]
+pub struct BatchMintQuoteMiningShareResponse<Q> {
+    /// One entry per requested quote
+    pub quotes: Vec<BatchMintQuoteMiningShareItem<Q>>,
+}
+
+#[cfg(feature = "mint")]
+impl From<BatchMintQuoteMiningShareResponse<crate::quote_id::QuoteId>>
+    for BatchMintQuoteMiningShareResponse<String>
+{
+    fn from(value: BatchMintQuoteMiningShareResponse<crate::quote_id::QuoteId>) -> Self {
+        Self {
+            quotes: value
+                .quotes
+                .into_iter()
+                .map(|item| match item {
+                    BatchMintQuoteMiningShareItem::Quote(quote) => {
+                        BatchMintQuoteMiningShareItem::Quote(quote.into())
+                    }
+                    BatchMintQuoteMiningShareItem::Error { header_hash, error } => {
+                        BatchMintQuoteMiningShareItem::Error { header_hash, error }
+                    }
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Method specific parameters of a quote conversion
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "swagger", derive(utoipa::ToSchema))]
+pub struct QuoteConversionParams {
+    /// Mining share hash, required when converting to a mining share quote
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub header_hash: Option<sha256::Hash>,
+    /// Description of the new quote
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+/// Request to convert an unpaid mint quote to another payment method
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "swagger", derive(utoipa::ToSchema))]
+#[serde(bound = "Q: Serialize + DeserializeOwned")]
+pub struct MintQuoteConversionRequest<Q> {
+    /// Quote to convert
+    pub quote: Q,
+    /// Payment method of the new quote
+    pub method: PaymentMethod,
+    /// Method specific parameters
+    #[serde(flatten)]
+    pub params: QuoteConversionParams,
+}
+
+#[cfg(feature = "mint")]
+impl TryFrom<MintQuoteConversionRequest<String>>
+    for MintQuoteConversionRequest<crate::quote_id::QuoteId>
+{
+    type Error = crate::quote_id::QuoteIdError;
+
+    fn try_from(value: MintQuoteConversionRequest<String>) -> Result<Self, Self::Error> {
+        Ok(Self {
+            quote: value.quote.parse()?,
+            method: value.method,
+            params: value.params,
+        })
+    }
+}
+
+/// Quote created by converting a mint quote to another payment method
+///
+/// The converted quote keeps the amount, unit and NUT-20 pubkey of the original
+/// quote, which is left in the [`MintQuoteState::Converted`](super::MintQuoteState::Converted) state.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "swagger", derive(utoipa::ToSchema))]
+#[serde(bound = "Q: Serialize + DeserializeOwned")]
+pub struct MintQuoteConversionResponse<Q> {
+    /// Quote that was converted
+    pub original_quote: Q,
+    /// Id of the new quote
+    pub quote: Q,
+    /// Payment method of the new quote
+    pub method: PaymentMethod,
+    /// Payment request of the new quote
+    pub request: String,
+    /// Amount
+    pub amount: Option<Amount>,
+    /// Currency unit
+    pub unit: CurrencyUnit,
+    /// State of the new quote
+    pub state: super::MintQuoteState,
+    /// Unix timestamp until which the new quote is valid
+    pub expiry: Option<u64>,
+    /// Pubkey for NUT-20
+    pub pubkey: Option<PublicKey>,
+    /// Keyset ID for mining share quotes
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub keyset_id: Option<Id>,
+    /// Amount paid
+    pub amount_paid: Amount,
+    /// Amount issued
+    pub amount_issued: Amount,
+}
+
+#[cfg(feature = "mint")]
+impl From<MintQuoteConversionResponse<crate::quote_id::QuoteId>>
+    for MintQuoteConversionResponse<String>
+{
+    fn from(value: MintQuoteConversionResponse<crate::quote_id::QuoteId>) -> Self {
+        Self {
+            original_quote: value.original_quote.to_string(),
+            quote: value.quote.to_string(),
+            method: value.method,
+            request: value.request,
+            amount: value.amount,
+            unit: value.unit,
+            state: value.state,
+            expiry: value.expiry,
+            pubkey: value.pubkey,
+            keyset_id: value.keyset_id,
+            amount_paid: value.amount_paid,
+            amount_issued: value.amount_issued,
+        }
+    }
+}
+
+/// Domain separator of the message signed to cancel a mint quote
+const CANCEL_MSG_PREFIX: &str = "cancel:";
+
+/// Request to cancel a mint quote
+///
+/// A quote locked to a NUT-20 pubkey is only cancelled with a signature of
+/// its quote id by that key, see [`MintQuoteCancelRequest::sign`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "swagger", derive(utoipa::ToSchema))]
+#[serde(bound = "Q: Serialize + DeserializeOwned")]
+pub struct MintQuoteCancelRequest<Q> {
+    /// Quote to cancel
+    pub quote: Q,
+    /// Signature of the quote id by the NUT-20 pubkey of the quote
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+}
+
+impl<Q> MintQuoteCancelRequest<Q>
+where
+    Q: ToString,
+{
+    /// Message signed to cancel the quote: `cancel:` followed by the quote id,
+    /// as UTF-8
+    ///
+    /// The prefix keeps the signature from being valid for a mint request.
+    pub fn msg_to_sign(&self) -> Vec<u8> {
+        format!("{CANCEL_MSG_PREFIX}{}", self.quote.to_string()).into_bytes()
+    }
+
+    /// Sign [`MintQuoteCancelRequest`]
+    pub fn sign(&mut self, secret_key: SecretKey) -> Result<(), nut20::Error> {
+        let signature: Signature = secret_key.sign(&self.msg_to_sign())?;
+
+        self.signature = Some(signature.to_string());
+
+        Ok(())
+    }
+
+    /// Verify signature on [`MintQuoteCancelRequest`]
+    pub fn verify_signature(&self, pubkey: PublicKey) -> Result<(), nut20::Error> {
+        let signature = self
+            .signature
+            .as_ref()
+            .ok_or(nut20::Error::SignatureMissing)?;
+
+        let signature =
+            Signature::from_str(signature).map_err(|_| nut20::Error::InvalidSignature)?;
+
+        pubkey.verify(&self.msg_to_sign(), &signature)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "mint")]
+impl TryFrom<MintQuoteCancelRequest<String>> for MintQuoteCancelRequest<crate::quote_id::QuoteId> {
+    type Error = crate::quote_id::QuoteIdError;
+
+    fn try_from(value: MintQuoteCancelRequest<String>) -> Result<Self, Self::Error> {
+        Ok(Self {
+            quote: value.quote.parse()?,
+            signature: value.signature,
+        })
+    }
+}
+
+/// Mint quote left in the [`MintQuoteState::Cancelled`](super::MintQuoteState::Cancelled)
+/// state by a cancellation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "swagger", derive(utoipa::ToSchema))]
+#[serde(bound = "Q: Serialize + DeserializeOwned")]
+pub struct MintQuoteCancelResponse<Q> {
+    /// Quote that was cancelled
+    pub quote: Q,
+    /// Payment method of the quote
+    pub method: PaymentMethod,
+    /// State of the quote
+    pub state: super::MintQuoteState,
+}
+
+#[cfg(feature = "mint")]
+impl From<MintQuoteCancelResponse<crate::quote_id::QuoteId>> for MintQuoteCancelResponse<String> {
+    fn from(value: MintQuoteCancelResponse<crate::quote_id::QuoteId>) -> Self {
+        Self {
+            quote: value.quote.to_string(),
+            method: value.method,
+            state: value.state,
+        }
+    }
+}
+
+/// Longest time range [`MiningShareStats`] are aggregated over, in seconds
+pub const MAX_MINING_SHARE_STATS_WINDOW: u64 = 90 * 24 * 60 * 60;
+
+/// Aggregates of the mining share quotes created in a time range
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "swagger", derive(utoipa::ToSchema))]
+pub struct MiningShareStats {
+    /// Quotes created
+    pub quotes_created: u64,
+    /// Quotes created that ecash was issued for
+    pub quotes_issued: u64,
+    /// Total amount of the quotes created
+    pub amount_quoted: Amount,
+    /// Total amount of ecash issued for the quotes created
+    pub amount_issued: Amount,
+    /// Distinct pubkeys the quotes created are locked to
+    pub distinct_pubkeys: u64,
+}
+
 impl From<super::nut23::QuoteState> for QuoteState {
     fn from(state: super::nut23::QuoteState) -> Self {
         match state {
             super::nut23::QuoteState::Unpaid => QuoteState::Unpaid,
             super::nut23::QuoteState::Paid => QuoteState::Paid,
             super::nut23::QuoteState::Issued => QuoteState::Issued,
+            super::nut23::QuoteState::Converted => QuoteState::Converted,
+            super::nut23::QuoteState::Cancelled => QuoteState::Cancelled,
         }
     }
 }
@@ -242,6 +671,8 @@ impl From<QuoteState> for super::nut23::QuoteState {
             QuoteState::Unpaid => super::nut23::QuoteState::Unpaid,
             QuoteState::Paid => super::nut23::QuoteState::Paid,
             QuoteState::Issued => super::nut23::QuoteState::Issued,
+            QuoteState::Converted => super::nut23::QuoteState::Converted,
+            QuoteState::Cancelled => super::nut23::QuoteState::Cancelled,
         }
     }
 }
@@ -285,9 +716,10 @@ impl std::str::FromStr for MiningShareQuoteState {
 
 #[cfg(test)]
 mod tests {
-    use super::*;
     use uuid::Uuid;
 
+    use super::*;
+
     #[test]
     fn test_mining_share_quote_response_serialization() {
         let mut id_bytes = vec![0x01]; // v2 version (KeySetVersion::Version01)
@@ -308,6 +740,7 @@ mod tests {
             pubkey,
             keyset_id,
             amount_issued: Amount::from(50),
+            difficulty: None,
         };
 
         // Test serialization/deserialization
@@ -318,6 +751,141 @@ mod tests {
         assert_eq!(response.amount_issued, Amount::from(50));
     }
 
+    #[test]
+    fn test_mining_share_quote_response_json() {
+        // A response without state is refused instead of taken for a paid quote
+        let stateless = r#"{
+            "quote": "quote-id",
+            "request": "test_header_hash",
+            "amount": 100,
+            "unit": "sat",
+            "expiry": 1234567890,
+            "pubkey": "02e6642fd69bd211f93f7f1f36ca51a26a5290eb2dd1b0d8279a87bb0d480c8443",
+            "keyset_id": "009a1f293253e41e"
+        }"#;
+        assert!(serde_json::from_str::<MintQuoteMiningShareResponse<String>>(stateless).is_err());
+
+        let legacy = r#"{
+            "quote": "quote-id",
+            "request": "test_header_hash",
+            "amount": 100,
+            "unit": "sat",
+            "state": "PAID",
+            "expiry": 1234567890,
+            "pubkey": "02e6642fd69bd211f93f7f1f36ca51a26a5290eb2dd1b0d8279a87bb0d480c8443",
+            "keyset_id": "009a1f293253e41e"
+        }"#;
+        let response: MintQuoteMiningShareResponse<String> = serde_json::from_str(legacy).unwrap();
+        assert_eq!(response.state, QuoteState::Paid);
+        assert_eq!(response.amount_issued, Amount::ZERO);
+        assert_eq!(response.keyset_id.to_string(), "009a1f293253e41e");
+        assert_eq!(response.difficulty, None);
+
+        // The state and issued amount are always written, the difficulty only
+        // if known
+        let json = serde_json::to_value(&response).unwrap();
+        assert_eq!(json["state"], "PAID");
+        assert_eq!(json["amount_issued"], 0);
+        assert!(json.get("difficulty").is_none());
+        assert_eq!(
+            serde_json::from_value::<MintQuoteMiningShareResponse<String>>(json).unwrap(),
+            response
+        );
+
+        let full = r#"{
+            "quote": "quote-id",
+            "request": "test_header_hash",
+            "amount": 100,
+            "unit": "sat",
+            "state": "ISSUED",
+            "expiry": 1234567890,
+            "pubkey": "02e6642fd69bd211f93f7f1f36ca51a26a5290eb2dd1b0d8279a87bb0d480c8443",
+            "keyset_id": "009a1f293253e41e",
+            "amount_issued": 100
+        }"#;
+        let response: MintQuoteMiningShareResponse<String> = serde_json::from_str(full).unwrap();
+        assert_eq!(response.state, QuoteState::Issued);
+        assert_eq!(response.amount_issued, Amount::from(100));
+        assert!(response.is_fully_issued());
+        assert_eq!(
+            serde_json::to_value(&response).unwrap(),
+            serde_json::from_str::<serde_json::Value>(full).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_batch_quote_status_response_serialization() {
+        let quote = r#"{
+            "quote": "quote-id",
+            "request": "test_header_hash",
+            "amount": 100,
+            "unit": "sat",
+            "state": "PAID",
+            "expiry": 1234567890,
+            "pubkey": "02e6642fd69bd211f93f7f1f36ca51a26a5290eb2dd1b0d8279a87bb0d480c8443",
+            "keyset_id": "009a1f293253e41e",
+            "amount_issued": 0
+        }"#;
+        let json =
+            format!(r#"{{"quotes":[{quote},{{"quote":"unknown","error":"Unknown quote"}}]}}"#);
+
+        let response: BatchQuoteStatusResponse<String> = serde_json::from_str(&json).unwrap();
+        assert_eq!(response.quotes.len(), 2);
+        match &response.quotes[0] {
+            BatchQuoteStatusItem::Quote(quote) => assert_eq!(quote.quote, "quote-id"),
+            other => panic!("Expected a quote, got {other:?}"),
+        }
+        assert_eq!(
+            response.quotes[1],
+            BatchQuoteStatusItem::Error {
+                quote: "unknown".to_string(),
+                error: "Unknown quote".to_string(),
+            }
+        );
+
+        let round_trip: BatchQuoteStatusResponse<String> =
+            serde_json::from_str(&serde_json::to_string(&response).unwrap()).unwrap();
+        assert_eq!(round_trip, response);
+    }
+
+    #[test]
+    fn test_batch_mint_quote_mining_share_response_serialization() {
+        let quote = r#"{
+            "quote": "quote-id",
+            "request": "test_header_hash",
+            "amount": 100,
+            "unit": "sat",
+            "state": "PAID",
+            "expiry": 1234567890,
+            "pubkey": "02e6642fd69bd211f93f7f1f36ca51a26a5290eb2dd1b0d8279a87bb0d480c8443",
+            "keyset_id": "009a1f293253e41e",
+            "amount_issued": 0
+        }"#;
+        let header_hash = sha256::Hash::hash(b"duplicate share");
+        let json = format!(
+            r#"{{"quotes":[{quote},{{"header_hash":"{header_hash}","error":"Duplicate share"}}]}}"#
+        );
+
+        let response: BatchMintQuoteMiningShareResponse<String> =
+            serde_json::from_str(&json).unwrap();
+        assert_eq!(response.quotes.len(), 2);
+        match &response.quotes[0] {
+            BatchMintQuoteMiningShareItem::Quote(quote) => assert_eq!(quote.quote, "quote-id"),
+            other => panic!("Expected a quote, got {other:?}"),
+        }
+        assert_eq!(
+            response.quotes[1],
+            BatchMintQuoteMiningShareItem::Error {
+                header_hash,
+                error: "Duplicate share".to_string(),
+            }
+        );
+
+        let round_trip: BatchMintQuoteMiningShareResponse<String> =
+            serde_json::from_str(&serde_json::to_string(&response).unwrap()).unwrap();
+        assert_eq!(round_trip, response);
+    }
+
     #[test]
     fn test_mining_share_quote_response_to_string_id() {
         let mut id_bytes = vec![0x01]; // v2 version (KeySetVersion::Version01)
@@ -339,6 +907,7 @@ mod tests {
             pubkey,
             keyset_id,
             amount_issued: Amount::from(25),
+            difficulty: None,
         };
 
         let string_response = response.to_string_id();
@@ -370,6 +939,7 @@ mod tests {
             pubkey,
             keyset_id,
             amount_issued: Amount::from(50),
+            difficulty: None,
         };
 
         assert!(!response.is_fully_issued());
@@ -406,6 +976,7 @@ mod tests {
             header_hash,
             description: None,
             pubkey,
+            difficulty: None,
         };
 
         assert!(valid_request.validate().is_ok());
@@ -426,6 +997,32 @@ mod tests {
 
         assert!(invalid_large.validate().is_err());
 
+        // The default maximum is inclusive
+        let at_default_max = MintQuoteMiningShareRequest {
+            amount: Amount::from(DEFAULT_MAX_MINING_SHARE_AMOUNT),
+            ..valid_request.clone()
+        };
+
+        assert!(at_default_max.validate().is_ok());
+
+        // A higher configured maximum admits larger amounts
+        let max_amount = Amount::from(1024);
+        assert!(invalid_large.validate_with_max(max_amount).is_ok());
+
+        let at_max = MintQuoteMiningShareRequest {
+            amount: max_amount,
+            ..valid_request.clone()
+        };
+
+        assert!(at_max.validate_with_max(max_amount).is_ok());
+
+        let above_max = MintQuoteMiningShareRequest {
+            amount: Amount::from(1025),
+            ..valid_request.clone()
+        };
+
+        assert!(above_max.validate_with_max(max_amount).is_err());
+
         // Invalid header hash (all zeros)
         let invalid_hash = MintQuoteMiningShareRequest {
             header_hash: sha256::Hash::from_byte_array([0u8; 32]),
@@ -435,15 +1032,52 @@ mod tests {
         assert!(invalid_hash.validate().is_err());
     }
 
+    #[test]
+    fn test_mining_share_request_difficulty_json() {
+        let legacy = r#"{
+            "amount": 64,
+            "unit": "sat",
+            "header_hash": "0101010101010101010101010101010101010101010101010101010101010101",
+            "pubkey": "02e6642fd69bd211f93f7f1f36ca51a26a5290eb2dd1b0d8279a87bb0d480c8443"
+        }"#;
+        let request: MintQuoteMiningShareRequest = serde_json::from_str(legacy).unwrap();
+        assert_eq!(request.difficulty, None);
+        assert!(serde_json::to_value(&request)
+            .unwrap()
+            .get("difficulty")
+            .is_none());
+
+        let request = MintQuoteMiningShareRequest {
+            difficulty: Some(6),
+            ..request
+        };
+        let json = serde_json::to_value(&request).unwrap();
+        assert_eq!(json["difficulty"], 6);
+        assert_eq!(
+            serde_json::from_value::<MintQuoteMiningShareRequest>(json).unwrap(),
+            request
+        );
+    }
+
     #[test]
     fn test_quote_state_string_conversion() {
         assert_eq!(QuoteState::Unpaid.to_string(), "UNPAID");
         assert_eq!(QuoteState::Paid.to_string(), "PAID");
         assert_eq!(QuoteState::Issued.to_string(), "ISSUED");
+        assert_eq!(QuoteState::Converted.to_string(), "CONVERTED");
+        assert_eq!(QuoteState::Cancelled.to_string(), "CANCELLED");
 
         assert_eq!("UNPAID".parse::<QuoteState>().unwrap(), QuoteState::Unpaid);
         assert_eq!("PAID".parse::<QuoteState>().unwrap(), QuoteState::Paid);
         assert_eq!("ISSUED".parse::<QuoteState>().unwrap(), QuoteState::Issued);
+        assert_eq!(
+            "CONVERTED".parse::<QuoteState>().unwrap(),
+            QuoteState::Converted
+        );
+        assert_eq!(
+            "CANCELLED".parse::<QuoteState>().unwrap(),
+            QuoteState::Cancelled
+        );
 
         // Case insensitive
         assert_eq!("unpaid".parse::<QuoteState>().unwrap(), QuoteState::Unpaid);
@@ -453,4 +1087,80 @@ mod tests {
         // Invalid state
         assert!("INVALID".parse::<QuoteState>().is_err());
     }
+
+    #[test]
+    fn test_quote_state_mint_quote_state_round_trip() {
+        use super::super::nut23::QuoteState as MintQuoteState;
+
+        for state in [
+            QuoteState::Unpaid,
+            QuoteState::Paid,
+            QuoteState::Issued,
+            QuoteState::Converted,
+            QuoteState::Cancelled,
+        ] {
+            let mint_state = MintQuoteState::from(state);
+            assert_eq!(QuoteState::from(mint_state), state);
+            assert_eq!(
+                serde_json::to_value(state).unwrap(),
+                serde_json::to_value(mint_state).unwrap()
+            );
+        }
+
+        // A state that is not known is never mintable
+        assert_eq!(QuoteState::default(), QuoteState::Unpaid);
+    }
+
+    #[test]
+    fn test_max_outputs() {
+        let denominations: Vec<u64> = (0..32).map(|order| 1 << order).collect();
+
+        assert_eq!(minimal_output_count(Amount::from(3), &denominations), 2);
+        assert_eq!(minimal_output_count(Amount::from(64), &denominations), 1);
+        assert_eq!(minimal_output_count(Amount::from(3), &[]), 2);
+        // Amounts above the largest denomination need several of it
+        assert_eq!(minimal_output_count(Amount::from(9), &[1, 2, 4]), 3);
+
+        let settings = Settings::default();
+        assert_eq!(
+            settings.max_amount(),
+            Amount::from(DEFAULT_MAX_MINING_SHARE_AMOUNT)
+        );
+        assert_eq!(settings.max_outputs(Amount::from(3), &denominations), 4);
+
+        let settings = Settings {
+            output_slack: Some(0),
+            ..Default::default()
+        };
+        assert_eq!(settings.max_outputs(Amount::from(3), &denominations), 2);
+
+        // The slack is omitted from the mint info unless configured
+        let json = serde_json::to_string(&Settings::default()).unwrap();
+        assert_eq!(json, r#"{"cbor":false,"quote_cancel":false}"#);
+    }
+
+    #[test]
+    fn test_quote_conversion_request_serialization() {
+        let request = MintQuoteConversionRequest {
+            quote: "quote-id".to_string(),
+            method: PaymentMethod::MiningShare,
+            params: QuoteConversionParams {
+                header_hash: Some(sha256::Hash::from_byte_array([1u8; 32])),
+                description: None,
+            },
+        };
+
+        let json = serde_json::to_value(&request).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "quote": "quote-id",
+                "method": "mining_share",
+                "header_hash": "0101010101010101010101010101010101010101010101010101010101010101"
+            })
+        );
+
+        let decoded: MintQuoteConversionRequest<String> = serde_json::from_value(json).unwrap();
+        assert_eq!(decoded, request);
+    }
 }
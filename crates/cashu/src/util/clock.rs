@@ -0,0 +1,85 @@
+//! Clock abstraction
+//!
+//! Expiry and TTL decisions read the current time through a [`Clock`] so tests
+//! can control time instead of crafting quotes with fake timestamps.
+
+use std::fmt::Debug;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use super::unix_time;
+
+/// Source of the current time
+pub trait Clock: Debug + Send + Sync {
+    /// Seconds since unix epoch
+    fn now_unix(&self) -> u64;
+}
+
+/// [`Clock`] reading the system time
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now_unix(&self) -> u64 {
+        unix_time()
+    }
+}
+
+/// Manually advanced [`Clock`] for tests
+///
+/// Clones share the same time, so a test can keep a handle to advance the
+/// clock it handed to a wallet or mint.
+#[derive(Debug, Clone, Default)]
+pub struct MockClock {
+    now: Arc<AtomicU64>,
+}
+
+impl MockClock {
+    /// Create new [`MockClock`] starting at `now` seconds since unix epoch
+    pub fn new(now: u64) -> Self {
+        Self {
+            now: Arc::new(AtomicU64::new(now)),
+        }
+    }
+
+    /// Set the current time
+    pub fn set(&self, now: u64) {
+        self.now.store(now, Ordering::SeqCst);
+    }
+
+    /// Move the clock forward by `secs` seconds
+    pub fn advance(&self, secs: u64) {
+        self.now.fetch_add(secs, Ordering::SeqCst);
+    }
+}
+
+impl Clock for MockClock {
+    fn now_unix(&self) -> u64 {
+        self.now.load(Ordering::SeqCst)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mock_clock() {
+        let clock = MockClock::new(1_000);
+        let handle = clock.clone();
+        assert_eq!(clock.now_unix(), 1_000);
+
+        handle.advance(60);
+        assert_eq!(clock.now_unix(), 1_060);
+
+        handle.set(5);
+        assert_eq!(clock.now_unix(), 5);
+    }
+
+    #[test]
+    fn test_system_clock() {
+        let before = unix_time();
+        let now = SystemClock.now_unix();
+        assert!(now >= before && now <= unix_time());
+    }
+}
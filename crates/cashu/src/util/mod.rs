@@ -1,5 +1,6 @@
 //! Cashu utils
 
+pub mod clock;
 pub mod hex;
 
 use bitcoin::secp256k1::{rand, All, Secp256k1};
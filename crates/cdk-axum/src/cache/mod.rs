@@ -12,6 +12,7 @@ use std::ops::Deref;
 use std::sync::Arc;
 use std::time::Duration;
 
+use cdk::canonical::{hash_canonical_json, to_canonical_json};
 use serde::de::DeserializeOwned;
 use serde::Serialize;
 use sha2::{Digest, Sha256};
@@ -139,11 +140,14 @@ impl HttpCache {
     /// double hash to have a predictable key size, although it may open the
     /// window for CPU attacks with large payloads, but it is a trade-off.
     /// Perhaps upper layer have a protection against large payloads.
+    ///
+    /// The input is serialized as canonical JSON, so the same request with its
+    /// fields in another order gets the same key.
     pub fn calculate_key<K>(&self, key: &K) -> Option<HttpCacheKey>
     where
         K: Serialize,
     {
-        let json_value = match serde_json::to_vec(key) {
+        let json_value = match to_canonical_json(key) {
             Ok(value) => value,
             Err(err) => {
                 tracing::warn!("Failed to serialize key: {:?}", err);
@@ -156,7 +160,7 @@ impl HttpCache {
             return None;
         }
 
-        let first_hash = Sha256::digest(json_value);
+        let first_hash = hash_canonical_json(&json_value);
         let second_hash = Sha256::digest(first_hash);
         Some(HttpCacheKey(second_hash.into()))
     }
@@ -0,0 +1,130 @@
+//! Content negotiation between JSON and the compact CBOR encoding
+//!
+//! Mining share endpoints accept and return either `application/json` or
+//! `application/cbor` bodies. Both encodings are derived from the same serde
+//! structures, so handlers work with the decoded values and never see the
+//! wire format.
+
+use std::convert::Infallible;
+
+use axum::body::Bytes;
+use axum::extract::{FromRequest, FromRequestParts, Request};
+use axum::http::header::{ACCEPT, CONTENT_TYPE};
+use axum::http::request::Parts;
+use axum::http::{HeaderMap, HeaderName, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use cdk::nuts::nutXX::CBOR_CONTENT_TYPE;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// Body encoding of a request or response
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) enum Encoding {
+    /// `application/json`
+    #[default]
+    Json,
+    /// `application/cbor`
+    Cbor,
+}
+
+impl Encoding {
+    /// Encoding named by the given header, defaulting to JSON
+    fn from_header(headers: &HeaderMap, name: HeaderName) -> Self {
+        let is_cbor = headers
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| {
+                value
+                    .split(',')
+                    .any(|media_type| media_type.trim().starts_with(CBOR_CONTENT_TYPE))
+            })
+            .unwrap_or_default();
+
+        match is_cbor {
+            true => Encoding::Cbor,
+            false => Encoding::Json,
+        }
+    }
+}
+
+/// Response encoding requested through the `Accept` header
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Accept(pub Encoding);
+
+impl<S> FromRequestParts<S> for Accept
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(Accept(Encoding::from_header(&parts.headers, ACCEPT)))
+    }
+}
+
+/// Request body decoded from either JSON or CBOR depending on its `Content-Type`
+#[derive(Debug, Clone)]
+pub(crate) struct JsonOrCbor<T>(pub T);
+
+impl<T, S> FromRequest<S> for JsonOrCbor<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        match Encoding::from_header(req.headers(), CONTENT_TYPE) {
+            Encoding::Cbor => {
+                let bytes = Bytes::from_request(req, state)
+                    .await
+                    .map_err(IntoResponse::into_response)?;
+
+                let payload = ciborium::from_reader(bytes.as_ref()).map_err(|err| {
+                    tracing::warn!("Could not decode cbor request body: {}", err);
+                    (StatusCode::BAD_REQUEST, "Invalid cbor request body").into_response()
+                })?;
+
+                Ok(JsonOrCbor(payload))
+            }
+            Encoding::Json => {
+                let Json(payload) = Json::<T>::from_request(req, state)
+                    .await
+                    .map_err(IntoResponse::into_response)?;
+
+                Ok(JsonOrCbor(payload))
+            }
+        }
+    }
+}
+
+/// Response body serialized with the negotiated encoding
+#[derive(Debug, Clone)]
+pub(crate) struct Encoded<T>(pub Encoding, pub T);
+
+impl<T> IntoResponse for Encoded<T>
+where
+    T: Serialize,
+{
+    fn into_response(self) -> Response {
+        let Encoded(encoding, payload) = self;
+
+        match encoding {
+            Encoding::Json => Json(payload).into_response(),
+            Encoding::Cbor => {
+                let mut body = Vec::new();
+                if let Err(err) = ciborium::into_writer(&payload, &mut body) {
+                    tracing::error!("Could not encode cbor response: {}", err);
+                    return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+                }
+
+                (
+                    [(CONTENT_TYPE, HeaderValue::from_static(CBOR_CONTENT_TYPE))],
+                    body,
+                )
+                    .into_response()
+            }
+        }
+    }
+}
@@ -15,6 +15,7 @@ use axum::routing::{get, post};
 use axum::Router;
 use cache::HttpCache;
 use cdk::mint::Mint;
+use rate_limit::RateLimiter;
 use router_handlers::*;
 
 mod metrics;
@@ -23,9 +24,14 @@ mod metrics;
 mod auth;
 mod bolt12_router;
 pub mod cache;
+mod encoding;
+mod rate_limit;
 mod router_handlers;
 mod ws;
 
+pub use rate_limit::RateLimit;
+pub use ws::WsDrain;
+
 #[cfg(feature = "swagger")]
 mod swagger_imports {
     pub use cdk::amount::Amount;
@@ -64,10 +70,89 @@ use crate::bolt12_router::{
 };
 
 /// CDK Mint State
+///
+/// The state of every router returned by this crate. Routers from
+/// [`all_routes`] are [`Router<MintState>`] and need it attached with
+/// [`Router::with_state`].
 #[derive(Clone)]
 pub struct MintState {
     mint: Arc<Mint>,
     cache: Arc<cache::HttpCache>,
+    ws_drain: WsDrain,
+    mining_share_rate_limiter: Option<Arc<RateLimiter>>,
+}
+
+impl MintState {
+    /// Create [`MintState`] for a mint with the given cache
+    pub fn new(mint: Arc<Mint>, cache: HttpCache) -> Self {
+        Self {
+            mint,
+            cache: Arc::new(cache),
+            ws_drain: WsDrain::default(),
+            mining_share_rate_limiter: None,
+        }
+    }
+
+    /// Drain websocket connections with `ws_drain`
+    ///
+    /// Call [`WsDrain::drain`] on shutdown to close websocket connections
+    /// gracefully before stopping the server.
+    pub fn with_ws_drain(mut self, ws_drain: WsDrain) -> Self {
+        self.ws_drain = ws_drain;
+        self
+    }
+
+    /// Limit the requests each client sends to the mining share endpoints
+    ///
+    /// Rate limiting is disabled by default. Clients over the limit are
+    /// answered `429 Too Many Requests` with a `Retry-After` header. Serve
+    /// the router with [`Router::into_make_service_with_connect_info`] to
+    /// limit each client IP separately.
+    pub fn with_mining_share_rate_limit(mut self, limit: RateLimit) -> Self {
+        self.mining_share_rate_limiter = Some(Arc::new(RateLimiter::new(limit)));
+        self
+    }
+}
+
+/// Route groups served by [`all_routes`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RoutesConfig {
+    /// Serve the bolt12 mint and melt endpoints
+    pub bolt12: bool,
+    /// Serve the mining share quote lookup and mint endpoints
+    pub mining_share: bool,
+    /// Serve the websocket endpoint
+    pub ws: bool,
+}
+
+impl Default for RoutesConfig {
+    fn default() -> Self {
+        Self {
+            bolt12: false,
+            mining_share: true,
+            ws: true,
+        }
+    }
+}
+
+impl RoutesConfig {
+    /// Serve the bolt12 mint and melt endpoints
+    pub fn bolt12(mut self, bolt12: bool) -> Self {
+        self.bolt12 = bolt12;
+        self
+    }
+
+    /// Serve the mining share quote lookup and mint endpoints
+    pub fn mining_share(mut self, mining_share: bool) -> Self {
+        self.mining_share = mining_share;
+        self
+    }
+
+    /// Serve the websocket endpoint
+    pub fn ws(mut self, ws: bool) -> Self {
+        self.ws = ws;
+        self
+    }
 }
 
 #[cfg(feature = "swagger")]
@@ -285,12 +370,59 @@ pub async fn create_mint_router_with_custom_cache(
     cache: HttpCache,
     include_bolt12: bool,
 ) -> Result<Router> {
-    let state = MintState {
-        mint,
-        cache: Arc::new(cache),
-    };
+    create_mint_router_with_ws_drain(mint, cache, include_bolt12, WsDrain::default()).await
+}
+
+/// Create mint [`Router`] with a custom backend for cache and websocket draining
+///
+/// Call [`WsDrain::drain`] on shutdown to close websocket connections gracefully
+/// before stopping the server.
+pub async fn create_mint_router_with_ws_drain(
+    mint: Arc<Mint>,
+    cache: HttpCache,
+    include_bolt12: bool,
+    ws_drain: WsDrain,
+) -> Result<Router> {
+    let state = MintState::new(mint, cache).with_ws_drain(ws_drain);
 
-    let v1_router = Router::new()
+    let mint_router = all_routes(RoutesConfig::default().bolt12(include_bolt12));
+
+    #[cfg(feature = "prometheus")]
+    let mint_router = mint_router.layer(axum::middleware::from_fn_with_state(
+        state.clone(),
+        metrics::global_metrics_middleware,
+    ));
+
+    let mint_router = mint_router
+        .layer(from_fn(cors_middleware))
+        .with_state(state);
+
+    Ok(mint_router)
+}
+
+/// All cashu routes of the mint under `/v1`, without state or middleware
+///
+/// Lets a host application serve the mint next to its own routes, under a
+/// prefix and behind its own layers. The standalone routers of this crate
+/// serve the same routes with CORS headers added.
+///
+/// ```no_run
+/// use std::sync::Arc;
+///
+/// use axum::routing::get;
+/// use axum::Router;
+/// use cdk::mint::Mint;
+/// use cdk_axum::{all_routes, MintState, RoutesConfig};
+///
+/// fn host_router(mint: Arc<Mint>) -> Router {
+///     Router::new()
+///         .route("/health", get(|| async { "ok" }))
+///         .nest("/cashu", all_routes(RoutesConfig::default()))
+///         .with_state(MintState::new(mint, Default::default()))
+/// }
+/// ```
+pub fn all_routes(config: RoutesConfig) -> Router<MintState> {
+    let mut v1_router = Router::new()
         .route("/keys", get(get_keys))
         .route("/keysets", get(get_keysets))
         .route("/keys/{keyset_id}", get(get_keyset_pubkeys))
@@ -298,16 +430,10 @@ pub async fn create_mint_router_with_custom_cache(
         .route("/mint/quote/bolt11", post(post_mint_bolt11_quote))
         .route(
             "/mint/quote/bolt11/{quote_id}",
-            get(get_check_mint_bolt11_quote),
-        )
-        .route(
-            "/mint/quote/mining_share/{quote_id}",
-            get(get_check_mint_quote_mining_share),
+            get(get_check_mint_bolt11_quote).delete(delete_mint_bolt11_quote),
         )
         .route("/mint/bolt11", post(cache_post_mint_bolt11))
-        .route("/mint/mining_share", post(cache_post_mint_mining_share))
         .route("/melt/quote/bolt11", post(post_melt_bolt11_quote))
-        .route("/ws", get(ws_handler))
         .route(
             "/melt/quote/bolt11/{quote_id}",
             get(get_check_melt_bolt11_quote),
@@ -317,38 +443,41 @@ pub async fn create_mint_router_with_custom_cache(
         .route("/info", get(get_mint_info))
         .route("/restore", post(post_restore));
 
-    let mint_router = Router::new().nest("/v1", v1_router);
-
-    #[cfg(feature = "auth")]
-    let mint_router = {
-        let auth_router = create_auth_router(state.clone());
-        mint_router.nest("/v1", auth_router)
-    };
-
-    // Conditionally create and merge bolt12_router
-    let mint_router = if include_bolt12 {
-        let bolt12_router = create_bolt12_router(state.clone());
-        mint_router.nest("/v1", bolt12_router)
-    } else {
-        mint_router
-    };
+    if config.mining_share {
+        v1_router = v1_router
+            .route("/mint/quote/convert", post(post_mint_quote_conversion))
+            .route(
+                "/mint/quote/mining_share/{quote_id}",
+                get(get_check_mint_quote_mining_share).delete(delete_mint_mining_share_quote),
+            )
+            .route(
+                "/mint/quote/mining_share/check",
+                post(post_check_mint_quotes_mining_share),
+            )
+            .route(
+                "/mint/quote/mining_share/batch",
+                post(post_mint_quotes_mining_share_batch),
+            )
+            .route("/mint/mining_share", post(cache_post_mint_mining_share));
+    }
 
-    // Add hashpool router for quote lookup functionality
+    if config.ws {
+        v1_router = v1_router.route("/ws", get(ws_handler));
+    }
 
-    #[cfg(feature = "prometheus")]
-    let mint_router = mint_router.layer(axum::middleware::from_fn_with_state(
-        state.clone(),
-        metrics::global_metrics_middleware,
-    ));
+    #[cfg(feature = "auth")]
+    {
+        v1_router = v1_router.merge(create_auth_router());
+    }
 
-    let mint_router = mint_router
-        .layer(from_fn(cors_middleware))
-        .with_state(state);
+    if config.bolt12 {
+        v1_router = v1_router.merge(create_bolt12_router());
+    }
 
-    Ok(mint_router)
+    Router::new().nest("/v1", v1_router)
 }
 
-fn create_bolt12_router(state: MintState) -> Router<MintState> {
+fn create_bolt12_router() -> Router<MintState> {
     Router::new()
         .route("/melt/quote/bolt12", post(post_melt_bolt12_quote))
         .route(
@@ -362,5 +491,4 @@ fn create_bolt12_router(state: MintState) -> Router<MintState> {
             get(get_check_mint_bolt12_quote),
         )
         .route("/mint/bolt12", post(cache_post_mint_bolt12))
-        .with_state(state)
 }
@@ -0,0 +1,275 @@
+//! Per client rate limiting of the mining share endpoints
+//!
+//! Each client gets a token bucket keyed by its IP address. Clients are only
+//! told apart when the router is served with
+//! [`axum::Router::into_make_service_with_connect_info`], otherwise all of
+//! them share one bucket.
+//!
+//! At most [`MAX_TRACKED_CLIENTS`] buckets are kept. Buckets idle long enough
+//! to be full again are dropped, since a new bucket starts full, and the
+//! least recently used bucket is evicted to make room for a new client.
+
+use std::collections::{BTreeSet, HashMap};
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use axum::extract::{ConnectInfo, FromRequestParts};
+use axum::http::header::RETRY_AFTER;
+use axum::http::request::Parts;
+use axum::http::HeaderValue;
+use axum::response::Response;
+use cdk::Error;
+
+use crate::{into_response, MintState};
+
+/// Most buckets kept, the least recently used is evicted beyond
+pub(crate) const MAX_TRACKED_CLIENTS: usize = 10_000;
+
+/// Requests a client may send to the mining share endpoints
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    /// Requests allowed in a burst
+    pub burst: u32,
+    /// Time to regain one request
+    pub refill_interval: Duration,
+}
+
+impl RateLimit {
+    /// Allow bursts of `burst` requests, regaining one every `refill_interval`
+    pub fn new(burst: u32, refill_interval: Duration) -> Self {
+        Self {
+            burst,
+            refill_interval,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Buckets by client, with their clients ordered by last update
+#[derive(Debug, Default)]
+struct Buckets {
+    by_client: HashMap<Option<IpAddr>, Bucket>,
+    by_update: BTreeSet<(Instant, Option<IpAddr>)>,
+}
+
+impl Buckets {
+    /// Take the bucket of `client` out, to be put back by [`Self::insert`]
+    fn take(&mut self, client: Option<IpAddr>) -> Option<Bucket> {
+        let bucket = self.by_client.remove(&client)?;
+        self.by_update.remove(&(bucket.updated, client));
+        Some(bucket)
+    }
+
+    fn insert(&mut self, client: Option<IpAddr>, bucket: Bucket) {
+        self.by_update.insert((bucket.updated, client));
+        self.by_client.insert(client, bucket);
+    }
+
+    /// Drop the buckets last updated before `cutoff`
+    fn prune(&mut self, cutoff: Instant) {
+        while let Some(&(updated, client)) = self.by_update.first() {
+            if updated >= cutoff {
+                break;
+            }
+            self.take(client);
+        }
+    }
+
+    /// Drop the least recently updated bucket
+    fn evict(&mut self) {
+        if let Some(&(_, client)) = self.by_update.first() {
+            tracing::debug!("Evicting rate limit bucket of {:?}", client);
+            self.take(client);
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.by_client.len()
+    }
+}
+
+/// Token buckets of the clients of the mining share endpoints
+#[derive(Debug)]
+pub(crate) struct RateLimiter {
+    limit: RateLimit,
+    max_clients: usize,
+    buckets: Mutex<Buckets>,
+}
+
+impl RateLimiter {
+    pub(crate) fn new(limit: RateLimit) -> Self {
+        Self::with_max_clients(limit, MAX_TRACKED_CLIENTS)
+    }
+
+    /// Limiter keeping at most `max_clients` buckets
+    pub(crate) fn with_max_clients(limit: RateLimit, max_clients: usize) -> Self {
+        Self {
+            limit,
+            max_clients: max_clients.max(1),
+            buckets: Mutex::new(Buckets::default()),
+        }
+    }
+
+    /// Time for an empty bucket to be full again
+    fn time_to_full(&self) -> Option<Duration> {
+        self.limit.refill_interval.checked_mul(self.limit.burst)
+    }
+
+    /// Tokens regained since `bucket` was last updated, capped at the burst
+    fn refill(&self, bucket: &Bucket, now: Instant) -> f64 {
+        let interval = self.limit.refill_interval.as_secs_f64();
+        let regained = match interval > 0.0 {
+            true => now.duration_since(bucket.updated).as_secs_f64() / interval,
+            false => f64::INFINITY,
+        };
+
+        (bucket.tokens + regained).min(self.limit.burst as f64)
+    }
+
+    /// Take a request from the bucket of `client`
+    ///
+    /// Returns how long to wait before retrying when the bucket is empty.
+    pub(crate) fn check(&self, client: Option<IpAddr>) -> Result<(), Duration> {
+        self.check_at(client, Instant::now())
+    }
+
+    fn check_at(&self, client: Option<IpAddr>, now: Instant) -> Result<(), Duration> {
+        let mut buckets = self.buckets.lock().unwrap_or_else(|err| err.into_inner());
+
+        // Buckets idle for this long are full, the same as a new one
+        if let Some(cutoff) = self
+            .time_to_full()
+            .and_then(|time_to_full| now.checked_sub(time_to_full))
+        {
+            buckets.prune(cutoff);
+        }
+
+        let mut bucket = match buckets.take(client) {
+            Some(bucket) => bucket,
+            None => {
+                if buckets.len() >= self.max_clients {
+                    buckets.evict();
+                }
+                Bucket {
+                    tokens: self.limit.burst as f64,
+                    updated: now,
+                }
+            }
+        };
+        bucket.tokens = self.refill(&bucket, now);
+        bucket.updated = now;
+
+        let result = match bucket.tokens >= 1.0 {
+            true => {
+                bucket.tokens -= 1.0;
+                Ok(())
+            }
+            false => Err(self.limit.refill_interval.mul_f64(1.0 - bucket.tokens)),
+        };
+        buckets.insert(client, bucket);
+
+        result
+    }
+
+    /// Number of buckets kept
+    #[cfg(test)]
+    fn tracked_clients(&self) -> usize {
+        self.buckets
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .len()
+    }
+}
+
+/// Rejects requests of clients over the mining share rate limit of
+/// [`MintState`]
+///
+/// Answers `429 Too Many Requests` with a `Retry-After` header.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct MiningShareRateLimit;
+
+impl FromRequestParts<MintState> for MiningShareRateLimit {
+    type Rejection = Response;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &MintState,
+    ) -> Result<Self, Self::Rejection> {
+        let Some(limiter) = &state.mining_share_rate_limiter else {
+            return Ok(MiningShareRateLimit);
+        };
+
+        let client = parts
+            .extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip());
+
+        limiter.check(client).map_err(|wait| {
+            // Round up so a client retrying right on time is let through
+            let retry_after = wait.as_secs() + u64::from(wait.subsec_nanos() > 0);
+            tracing::debug!(
+                "Rate limited mining share request of {:?}, retry after {}s",
+                client,
+                retry_after
+            );
+
+            let mut response = into_response(Error::RateLimited(Some(retry_after)));
+            response
+                .headers_mut()
+                .insert(RETRY_AFTER, HeaderValue::from(retry_after));
+            response
+        })?;
+
+        Ok(MiningShareRateLimit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use super::*;
+
+    fn client(n: u32) -> Option<IpAddr> {
+        Some(IpAddr::V4(Ipv4Addr::from(n)))
+    }
+
+    #[test]
+    fn test_tracked_clients_capped() {
+        let limiter = RateLimiter::with_max_clients(RateLimit::new(1, Duration::from_secs(60)), 3);
+        let start = Instant::now();
+
+        for n in 0..10 {
+            let now = start + Duration::from_millis(n.into());
+            assert!(limiter.check_at(client(n), now).is_ok());
+            assert!(limiter.tracked_clients() <= 3);
+        }
+
+        // The most recent clients are still limited, the oldest were evicted
+        let now = start + Duration::from_millis(10);
+        assert!(limiter.check_at(client(9), now).is_err());
+        assert!(limiter.check_at(client(0), now).is_ok());
+    }
+
+    #[test]
+    fn test_idle_buckets_pruned() {
+        let limiter = RateLimiter::new(RateLimit::new(2, Duration::from_secs(1)));
+        let start = Instant::now();
+
+        for n in 0..5 {
+            assert!(limiter.check_at(client(n), start).is_ok());
+        }
+        assert_eq!(limiter.tracked_clients(), 5);
+
+        // Two seconds refill a bucket of two, the idle buckets are dropped
+        let later = start + Duration::from_secs(3);
+        assert!(limiter.check_at(client(0), later).is_ok());
+        assert_eq!(limiter.tracked_clients(), 1);
+    }
+}
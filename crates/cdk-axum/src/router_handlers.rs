@@ -1,26 +1,33 @@
 use anyhow::Result;
 use axum::extract::ws::WebSocketUpgrade;
-use axum::extract::{Json, Path, State};
+use axum::extract::{Json, Path, Query, State};
 use axum::http::StatusCode;
 use axum::response::{IntoResponse, Response};
 use cdk::error::{ErrorCode, ErrorResponse};
 use cdk::mint::QuoteId;
 #[cfg(feature = "auth")]
 use cdk::nuts::nut21::{Method, ProtectedEndpoint, RoutePath};
-use cdk::nuts::nutXX::MintQuoteMiningShareResponse;
+use cdk::nuts::nutXX::{
+    BatchMintQuoteMiningShareRequest, BatchMintQuoteMiningShareResponse, BatchQuoteStatusRequest,
+    BatchQuoteStatusResponse, MintQuoteCancelRequest, MintQuoteCancelResponse,
+    MintQuoteConversionRequest, MintQuoteConversionResponse, MintQuoteMiningShareResponse,
+};
 use cdk::nuts::{
     CheckStateRequest, CheckStateResponse, Id, KeysResponse, KeysetResponse,
     MeltQuoteBolt11Request, MeltQuoteBolt11Response, MeltRequest, MintInfo, MintQuoteBolt11Request,
-    MintQuoteBolt11Response, MintRequest, MintResponse, RestoreRequest, RestoreResponse,
-    SwapRequest, SwapResponse,
+    MintQuoteBolt11Response, MintRequest, MintResponse, PaymentMethod, RestoreRequest,
+    RestoreResponse, SwapRequest, SwapResponse,
 };
 use cdk::util::unix_time;
 use paste::paste;
+use serde::Deserialize;
 use tracing::instrument;
 use uuid::Uuid;
 
 #[cfg(feature = "auth")]
 use crate::auth::AuthHeader;
+use crate::encoding::{Accept, Encoded, JsonOrCbor};
+use crate::rate_limit::MiningShareRateLimit;
 use crate::ws::main_websocket;
 use crate::MintState;
 
@@ -65,13 +72,45 @@ macro_rules! post_cache_wrapper {
 
 post_cache_wrapper!(post_swap, SwapRequest, SwapResponse);
 post_cache_wrapper!(post_mint_bolt11, MintRequest<QuoteId>, MintResponse);
-post_cache_wrapper!(post_mint_mining_share, MintRequest<String>, MintResponse);
 post_cache_wrapper!(
     post_melt_bolt11,
     MeltRequest<QuoteId>,
     MeltQuoteBolt11Response<QuoteId>
 );
 
+/// Cache wrapper for [`post_mint_mining_share`] accepting JSON or CBOR bodies
+///
+/// The cache key is calculated from the decoded request, so a JSON request and
+/// its CBOR equivalent share the same cache entry.
+pub(crate) async fn cache_post_mint_mining_share(
+    _rate_limit: MiningShareRateLimit,
+    #[cfg(feature = "auth")] auth: AuthHeader,
+    state: State<MintState>,
+    Accept(encoding): Accept,
+    JsonOrCbor(payload): JsonOrCbor<MintRequest<String>>,
+) -> Result<Encoded<MintResponse>, Response> {
+    let State(mint_state) = state.clone();
+    let cache_key = match mint_state.cache.calculate_key(&payload) {
+        Some(key) => key,
+        None => {
+            #[cfg(feature = "auth")]
+            let Json(response) = post_mint_mining_share(auth, state, Json(payload)).await?;
+            #[cfg(not(feature = "auth"))]
+            let Json(response) = post_mint_mining_share(state, Json(payload)).await?;
+            return Ok(Encoded(encoding, response));
+        }
+    };
+    if let Some(cached_response) = mint_state.cache.get::<MintResponse>(&cache_key).await {
+        return Ok(Encoded(encoding, cached_response));
+    }
+    #[cfg(feature = "auth")]
+    let Json(response) = post_mint_mining_share(auth, state, Json(payload)).await?;
+    #[cfg(not(feature = "auth"))]
+    let Json(response) = post_mint_mining_share(state, Json(payload)).await?;
+    mint_state.cache.set(cache_key, &response).await;
+    Ok(Encoded(encoding, response))
+}
+
 #[cfg_attr(feature = "swagger", utoipa::path(
     get,
     context_path = "/v1",
@@ -225,6 +264,7 @@ pub(crate) async fn get_check_mint_bolt11_quote(
 /// Get mint quote state for mining share quotes.
 #[instrument(skip_all, fields(quote_id = ?quote_id))]
 pub(crate) async fn get_check_mint_quote_mining_share(
+    _rate_limit: MiningShareRateLimit,
     #[cfg(feature = "auth")] auth: AuthHeader,
     State(state): State<MintState>,
     Path(quote_id): Path<Uuid>,
@@ -259,12 +299,215 @@ pub(crate) async fn get_check_mint_quote_mining_share(
     Ok(Json(mining_quote))
 }
 
-#[instrument(skip_all)]
-pub(crate) async fn ws_handler(
+#[cfg_attr(feature = "swagger", utoipa::path(
+    post,
+    context_path = "/v1",
+    path = "/mint/quote/mining_share/check",
+    request_body(content = BatchQuoteStatusRequest, description = "Quote IDs", content_type = "application/json"),
+    responses(
+        (status = 200, description = "Successful response", body = BatchQuoteStatusResponse<String>, content_type = "application/json"),
+        (status = 500, description = "Server error", body = ErrorResponse, content_type = "application/json")
+    )
+))]
+/// Check mining share mint quotes
+///
+/// Get the state of up to 100 mining share quotes, in the order they were
+/// requested. Quotes that cannot be checked get an error entry.
+#[instrument(skip_all, fields(quotes = payload.quotes.len()))]
+pub(crate) async fn post_check_mint_quotes_mining_share(
+    _rate_limit: MiningShareRateLimit,
+    #[cfg(feature = "auth")] auth: AuthHeader,
+    State(state): State<MintState>,
+    Json(payload): Json<BatchQuoteStatusRequest>,
+) -> Result<Json<BatchQuoteStatusResponse<QuoteId>>, Response> {
+    #[cfg(feature = "auth")]
+    {
+        state
+            .mint
+            .verify_auth(
+                auth.into(),
+                &ProtectedEndpoint::new(Method::Get, RoutePath::MintQuoteBolt11), // Use Bolt11 auth for now
+            )
+            .await
+            .map_err(into_response)?;
+    }
+
+    let response = state
+        .mint
+        .check_mint_quotes_mining_share(payload)
+        .await
+        .map_err(into_response)?;
+
+    Ok(Json(response))
+}
+
+#[cfg_attr(feature = "swagger", utoipa::path(
+    post,
+    context_path = "/v1",
+    path = "/mint/quote/mining_share/batch",
+    request_body(content = BatchMintQuoteMiningShareRequest, description = "Mining share quote requests", content_type = "application/json"),
+    responses(
+        (status = 200, description = "Successful response", body = BatchMintQuoteMiningShareResponse<String>, content_type = "application/json"),
+        (status = 500, description = "Server error", body = ErrorResponse, content_type = "application/json")
+    )
+))]
+/// Request several mining share mint quotes
+///
+/// Create up to 100 mining share quotes, answered in the order they were
+/// requested. Shares that cannot be quoted get an error entry.
+#[instrument(skip_all, fields(quotes = payload.quotes.len()))]
+pub(crate) async fn post_mint_quotes_mining_share_batch(
+    _rate_limit: MiningShareRateLimit,
+    #[cfg(feature = "auth")] auth: AuthHeader,
     State(state): State<MintState>,
-    ws: WebSocketUpgrade,
-) -> impl IntoResponse {
+    Json(payload): Json<BatchMintQuoteMiningShareRequest>,
+) -> Result<Json<BatchMintQuoteMiningShareResponse<QuoteId>>, Response> {
+    #[cfg(feature = "auth")]
+    {
+        state
+            .mint
+            .verify_auth(
+                auth.into(),
+                &ProtectedEndpoint::new(Method::Post, RoutePath::MintQuoteBolt11), // Use Bolt11 auth for now
+            )
+            .await
+            .map_err(into_response)?;
+    }
+
+    let response = state
+        .mint
+        .create_mint_mining_share_quotes(payload)
+        .await
+        .map_err(into_response)?;
+
+    Ok(Json(response))
+}
+
+/// Convert a mint quote to another payment method
+///
+/// Closes an unpaid mint quote and creates a quote of another payment method
+/// for the same amount, unit and pubkey.
+#[instrument(skip_all, fields(quote_id = ?payload.quote, method = ?payload.method))]
+pub(crate) async fn post_mint_quote_conversion(
+    #[cfg(feature = "auth")] auth: AuthHeader,
+    State(state): State<MintState>,
+    Json(payload): Json<MintQuoteConversionRequest<QuoteId>>,
+) -> Result<Json<MintQuoteConversionResponse<QuoteId>>, Response> {
+    #[cfg(feature = "auth")]
+    {
+        state
+            .mint
+            .verify_auth(
+                auth.into(),
+                &ProtectedEndpoint::new(Method::Post, RoutePath::MintQuoteBolt11), // Use Bolt11 auth for now
+            )
+            .await
+            .map_err(into_response)?;
+    }
+
+    let quote = state
+        .mint
+        .convert_quote_method(&payload.quote, payload.method, payload.params)
+        .await
+        .map_err(|err| {
+            tracing::error!("Could not convert mint quote {}: {}", payload.quote, err);
+            into_response(err)
+        })?;
+
+    Ok(Json(quote))
+}
+
+/// Query parameters of a mint quote cancellation
+#[derive(Debug, Deserialize)]
+pub(crate) struct CancelMintQuoteParams {
+    /// Signature of the quote id by the NUT-20 pubkey of the quote
+    signature: Option<String>,
+}
+
+/// Cancel a mint quote of the given payment method
+async fn cancel_mint_quote(
+    state: &MintState,
+    method: PaymentMethod,
+    quote_id: QuoteId,
+    params: CancelMintQuoteParams,
+) -> Result<Json<MintQuoteCancelResponse<QuoteId>>, Response> {
+    let request = MintQuoteCancelRequest {
+        quote: quote_id.clone(),
+        signature: params.signature,
+    };
+    let response = state
+        .mint
+        .cancel_mint_quote(&method, request)
+        .await
+        .map_err(|err| {
+            tracing::error!("Could not cancel mint quote {}: {}", quote_id, err);
+            into_response(err)
+        })?;
+
+    Ok(Json(response))
+}
+
+/// Cancel a bolt11 mint quote
+///
+/// Abandons a mint quote that has no ecash issued for it. A quote locked to a
+/// pubkey needs the `signature` of its quote id by that key.
+#[instrument(skip_all, fields(quote_id = ?quote_id))]
+pub(crate) async fn delete_mint_bolt11_quote(
+    #[cfg(feature = "auth")] auth: AuthHeader,
+    State(state): State<MintState>,
+    Path(quote_id): Path<QuoteId>,
+    Query(params): Query<CancelMintQuoteParams>,
+) -> Result<Json<MintQuoteCancelResponse<QuoteId>>, Response> {
+    #[cfg(feature = "auth")]
+    {
+        state
+            .mint
+            .verify_auth(
+                auth.into(),
+                &ProtectedEndpoint::new(Method::Post, RoutePath::MintQuoteBolt11), // Use Bolt11 auth for now
+            )
+            .await
+            .map_err(into_response)?;
+    }
+
+    cancel_mint_quote(&state, PaymentMethod::Bolt11, quote_id, params).await
+}
+
+/// Cancel a mining share mint quote
+///
+/// Abandons a mint quote that has no ecash issued for it. The `signature` of
+/// the quote id by the quote pubkey is required.
+#[instrument(skip_all, fields(quote_id = ?quote_id))]
+pub(crate) async fn delete_mint_mining_share_quote(
+    _rate_limit: MiningShareRateLimit,
+    #[cfg(feature = "auth")] auth: AuthHeader,
+    State(state): State<MintState>,
+    Path(quote_id): Path<QuoteId>,
+    Query(params): Query<CancelMintQuoteParams>,
+) -> Result<Json<MintQuoteCancelResponse<QuoteId>>, Response> {
+    #[cfg(feature = "auth")]
+    {
+        state
+            .mint
+            .verify_auth(
+                auth.into(),
+                &ProtectedEndpoint::new(Method::Post, RoutePath::MintQuoteBolt11), // Use Bolt11 auth for now
+            )
+            .await
+            .map_err(into_response)?;
+    }
+
+    cancel_mint_quote(&state, PaymentMethod::MiningShare, quote_id, params).await
+}
+
+#[instrument(skip_all)]
+pub(crate) async fn ws_handler(State(state): State<MintState>, ws: WebSocketUpgrade) -> Response {
+    if state.ws_drain.is_draining() {
+        return (StatusCode::SERVICE_UNAVAILABLE, "Mint is shutting down").into_response();
+    }
+
     ws.on_upgrade(|ws| main_websocket(ws, state))
+        .into_response()
 }
 
 /// Mint tokens by paying a BOLT11 Lightning invoice.
@@ -316,7 +559,8 @@ pub(crate) async fn post_mint_bolt11(
 ///
 /// Requests the minting of tokens belonging to a paid mining share quote.
 ///
-/// Call this endpoint after `POST /v1/mint/quote/mining_share`.
+/// Call this endpoint after `POST /v1/mint/quote/mining_share`. The request and
+/// response may use `application/cbor` in place of JSON.
 #[cfg_attr(feature = "swagger", utoipa::path(
     post,
     context_path = "/v1",
@@ -663,6 +907,9 @@ where
         | ErrorCode::AmountOutofLimitRange
         | ErrorCode::WitnessMissingOrInvalid
         | ErrorCode::DuplicateSignature
+        | ErrorCode::DuplicateShareHash
+        | ErrorCode::TooManyPendingQuotes
+        | ErrorCode::BatchTooLarge
         | ErrorCode::DuplicateInputs
         | ErrorCode::DuplicateOutputs
         | ErrorCode::MultipleUnits
@@ -670,6 +917,9 @@ where
         | ErrorCode::ClearAuthRequired
         | ErrorCode::BlindAuthRequired => StatusCode::BAD_REQUEST,
 
+        // Quota exhausted (429 Too Many Requests)
+        ErrorCode::RateLimited => StatusCode::TOO_MANY_REQUESTS,
+
         // Auth failures (401 Unauthorized)
         ErrorCode::ClearAuthFailed | ErrorCode::BlindAuthFailed => StatusCode::UNAUTHORIZED,
 
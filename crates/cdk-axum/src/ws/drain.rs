@@ -0,0 +1,95 @@
+//! Graceful draining of websocket connections on shutdown
+//!
+//! Once draining starts the server rejects new websocket connections, tells every
+//! connected client when to reconnect, flushes the notifications already queued for
+//! it and closes the connection with a `going away` close code.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use rand::Rng;
+use tokio::sync::watch;
+
+/// Extra time given to connections to send their close frame after the deadline
+const CLOSE_GRACE: Duration = Duration::from_secs(1);
+
+/// Websocket draining configuration and trigger
+#[derive(Debug, Clone)]
+pub struct WsDrain {
+    signal: Arc<watch::Sender<bool>>,
+    deadline: Duration,
+    reconnect_after: Duration,
+    reconnect_jitter: Duration,
+}
+
+impl Default for WsDrain {
+    fn default() -> Self {
+        let (signal, _) = watch::channel(false);
+
+        Self {
+            signal: Arc::new(signal),
+            deadline: Duration::from_secs(5),
+            reconnect_after: Duration::from_secs(2),
+            reconnect_jitter: Duration::from_secs(3),
+        }
+    }
+}
+
+impl WsDrain {
+    /// Create new [`WsDrain`] with the default configuration
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set how long each connection may spend flushing queued notifications
+    pub fn deadline(mut self, deadline: Duration) -> Self {
+        self.deadline = deadline;
+        self
+    }
+
+    /// Set the reconnect delay suggested to clients
+    ///
+    /// Each client gets `reconnect_after` plus a random delay of up to `jitter`, so
+    /// they do not all reconnect at once.
+    pub fn reconnect_after(mut self, reconnect_after: Duration, jitter: Duration) -> Self {
+        self.reconnect_after = reconnect_after;
+        self.reconnect_jitter = jitter;
+        self
+    }
+
+    /// Whether draining has started
+    pub fn is_draining(&self) -> bool {
+        *self.signal.borrow()
+    }
+
+    /// Start draining and wait until every connection is closed or the deadline passes
+    pub async fn drain(&self) {
+        self.signal.send_replace(true);
+
+        if tokio::time::timeout(self.deadline + CLOSE_GRACE, self.signal.closed())
+            .await
+            .is_err()
+        {
+            tracing::warn!(
+                "{} websocket connections still open after the drain deadline",
+                self.signal.receiver_count()
+            );
+        }
+    }
+
+    /// Receiver notified once draining starts
+    pub(crate) fn subscribe(&self) -> watch::Receiver<bool> {
+        self.signal.subscribe()
+    }
+
+    /// Time available to flush queued notifications
+    pub(crate) fn flush_deadline(&self) -> Duration {
+        self.deadline
+    }
+
+    /// Reconnect delay suggested to a single client, jitter included
+    pub(crate) fn reconnect_after_ms(&self) -> u64 {
+        let jitter = self.reconnect_jitter.as_millis() as u64;
+        self.reconnect_after.as_millis() as u64 + rand::rng().random_range(0..=jitter)
+    }
+}
@@ -15,20 +15,31 @@ pub enum WsError {
     InvalidParams,
     /// Internal JSON-RPC error.
     InternalError,
+    /// Subscription filters that are not valid for the subscription kind
+    InvalidFilters(Vec<String>),
     /// Custom error
     ServerError(i32, String),
 }
 
 impl From<WsError> for WsErrorBody {
     fn from(val: WsError) -> Self {
-        let (id, message) = match val {
-            WsError::ParseError => (-32700, "Parse error".to_string()),
-            WsError::InvalidRequest => (-32600, "Invalid Request".to_string()),
-            WsError::MethodNotFound => (-32601, "Method not found".to_string()),
-            WsError::InvalidParams => (-32602, "Invalid params".to_string()),
-            WsError::InternalError => (-32603, "Internal error".to_string()),
-            WsError::ServerError(code, message) => (code, message),
+        let (id, message, data) = match val {
+            WsError::ParseError => (-32700, "Parse error".to_string(), None),
+            WsError::InvalidRequest => (-32600, "Invalid Request".to_string(), None),
+            WsError::MethodNotFound => (-32601, "Method not found".to_string(), None),
+            WsError::InvalidParams => (-32602, "Invalid params".to_string(), None),
+            WsError::InvalidFilters(filters) => (
+                -32602,
+                "Invalid params: filters do not match the subscription kind".to_string(),
+                Some(serde_json::json!({ "invalid_filters": filters })),
+            ),
+            WsError::InternalError => (-32603, "Internal error".to_string(), None),
+            WsError::ServerError(code, message) => (code, message, None),
         };
-        WsErrorBody { code: id, message }
+        WsErrorBody {
+            code: id,
+            message,
+            data,
+        }
     }
 }
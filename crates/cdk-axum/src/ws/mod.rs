@@ -1,18 +1,20 @@
 use std::collections::HashMap;
+use std::time::Duration;
 
 use axum::extract::ws::{CloseFrame, Message, WebSocket};
 use cdk::mint::QuoteId;
 use cdk::nuts::nut17::NotificationPayload;
 use cdk::pub_sub::SubId;
 use cdk::ws::{
-    notification_to_ws_message, NotificationInner, WsErrorBody, WsMessageOrResponse,
-    WsMethodRequest, WsRequest,
+    draining_ws_message, notification_to_ws_message, NotificationInner, WsErrorBody,
+    WsMessageOrResponse, WsMethodRequest, WsRequest,
 };
 use futures::StreamExt;
 use tokio::sync::mpsc;
 
 use crate::MintState;
 
+mod drain;
 mod error;
 mod subscribe;
 mod unsubscribe;
@@ -32,8 +34,12 @@ async fn process(
     serde_json::to_value(response)
 }
 
+pub use drain::WsDrain;
 pub use error::WsError;
 
+/// How long a draining connection waits for more queued notifications
+const DRAIN_IDLE_TIMEOUT: Duration = Duration::from_millis(50);
+
 pub struct WsContext {
     state: MintState,
     subscriptions: HashMap<SubId, tokio::task::JoinHandle<()>>,
@@ -53,29 +59,20 @@ pub async fn main_websocket(mut socket: WebSocket, state: MintState) {
         subscriptions: HashMap::new(),
         publisher,
     };
+    let mut drain = context.state.ws_drain.subscribe();
 
     loop {
         tokio::select! {
             Some((sub_id, payload)) = subscriber.recv() => {
-                if !context.subscriptions.contains_key(&sub_id) {
-                    // It may be possible an incoming message has come from a dropped Subscriptions that has not yet been
-                    // unsubscribed from the subscription manager, just ignore it.
-                    continue;
+                if let Err(err) = send_notification(&mut socket, &context, sub_id, payload).await {
+                    tracing::error!("Could not send websocket message: {}", err);
+                    break;
                 }
-                let notification = notification_to_ws_message(NotificationInner {
-                    sub_id,
-                    payload,
-                });
-                let message = match serde_json::to_string(&notification) {
-                    Ok(message) => message,
-                    Err(err) => {
-                        tracing::error!("Could not serialize notification: {}", err);
-                        continue;
-                    }
-                };
+            }
 
-                if let Err(err)= socket.send(Message::Text(message.into())).await {
-                    tracing::error!("Could not send websocket message: {}", err);
+            Ok(()) = drain.changed() => {
+                if *drain.borrow_and_update() {
+                    drain_websocket(&mut socket, &context, &mut subscriber).await;
                     break;
                 }
             }
@@ -148,3 +145,73 @@ pub async fn main_websocket(mut socket: WebSocket, state: MintState) {
         }
     }
 }
+
+/// Send a notification to the client, skipping subscriptions that are no longer active
+async fn send_notification(
+    socket: &mut WebSocket,
+    context: &WsContext,
+    sub_id: SubId,
+    payload: NotificationPayload<QuoteId>,
+) -> Result<(), axum::Error> {
+    if !context.subscriptions.contains_key(&sub_id) {
+        // It may be possible an incoming message has come from a dropped Subscriptions that has not yet been
+        // unsubscribed from the subscription manager, just ignore it.
+        return Ok(());
+    }
+    let notification = notification_to_ws_message(NotificationInner { sub_id, payload });
+    let message = match serde_json::to_string(&notification) {
+        Ok(message) => message,
+        Err(err) => {
+            tracing::error!("Could not serialize notification: {}", err);
+            return Ok(());
+        }
+    };
+
+    socket.send(Message::Text(message.into())).await
+}
+
+/// Tell the client the server is draining, flush its queued notifications and close
+async fn drain_websocket(
+    socket: &mut WebSocket,
+    context: &WsContext,
+    subscriber: &mut mpsc::Receiver<(SubId, NotificationPayload<QuoteId>)>,
+) {
+    let ws_drain = &context.state.ws_drain;
+    let deadline = tokio::time::Instant::now() + ws_drain.flush_deadline();
+
+    let draining = draining_ws_message(ws_drain.reconnect_after_ms());
+    match serde_json::to_string(&draining) {
+        Ok(message) => {
+            if let Err(err) = socket.send(Message::Text(message.into())).await {
+                tracing::error!("Could not send draining message: {}", err);
+                return;
+            }
+        }
+        Err(err) => tracing::error!("Could not serialize draining message: {}", err),
+    }
+
+    let flush = async {
+        while let Ok(Some((sub_id, payload))) =
+            tokio::time::timeout(DRAIN_IDLE_TIMEOUT, subscriber.recv()).await
+        {
+            if let Err(err) = send_notification(socket, context, sub_id, payload).await {
+                tracing::error!("Could not send websocket message: {}", err);
+                return false;
+            }
+        }
+        true
+    };
+
+    match tokio::time::timeout_at(deadline, flush).await {
+        Ok(false) => return,
+        Ok(true) => {}
+        Err(_) => tracing::warn!("Drain deadline reached with notifications still queued"),
+    }
+
+    let _ = socket
+        .send(Message::Close(Some(CloseFrame {
+            code: axum::extract::ws::close_code::AWAY,
+            reason: "server draining".into(),
+        })))
+        .await;
+}
@@ -1,3 +1,4 @@
+use cdk::nuts::nut17;
 use cdk::subscription::{IndexableParams, Params};
 use cdk::ws::{WsResponseResult, WsSubscribeResponse};
 
@@ -23,7 +24,10 @@ pub(crate) async fn handle(
         .pubsub_manager()
         .try_subscribe(params)
         .await
-        .map_err(|_| WsError::ParseError)?;
+        .map_err(|err| match err {
+            nut17::Error::InvalidFilters { filters, .. } => WsError::InvalidFilters(filters),
+            _ => WsError::ParseError,
+        })?;
 
     let publisher = context.publisher.clone();
     context.subscriptions.insert(
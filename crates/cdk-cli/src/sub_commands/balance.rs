@@ -25,7 +25,7 @@ pub async fn mint_balances(
         .enumerate()
     {
         let mint_url = mint_url.clone();
-        println!("{i}: {mint_url} {amount} {unit}");
+        println!("{i}: {mint_url} {}", amount.display(unit));
         wallets_vec.push((mint_url, *amount))
     }
     Ok(wallets_vec)
@@ -1,7 +1,6 @@
 use anyhow::Result;
 use cdk::mint_url::MintUrl;
-use cdk::wallet::MintConnector;
-use cdk::HttpClient;
+use cdk::wallet::{HttpClient, MintConnector};
 use clap::Args;
 use url::Url;
 
@@ -2,6 +2,14 @@
 
 use thiserror::Error;
 
+/// `pay` error codes of lightningd, see `common/jsonrpc_errors.h`
+mod pay_codes {
+    /// Another payment of the invoice is in progress
+    pub const PAY_IN_PROGRESS: i32 = 200;
+    /// The invoice was already paid
+    pub const PAY_RHASH_ALREADY_USED: i32 = 201;
+}
+
 /// CLN Error
 #[derive(Debug, Error)]
 pub enum Error {
@@ -32,10 +40,89 @@ pub enum Error {
     /// Bolt12 Error
     #[error("Bolt12 error: {0}")]
     Bolt12(String),
+    /// Payment failed and can be retried
+    #[error("Payment failed: {0}")]
+    PaymentFailed(String),
+    /// Payment is still in progress
+    #[error("Payment is pending")]
+    PaymentPending,
+    /// Invoice was already paid
+    #[error("Invoice already paid")]
+    InvoiceAlreadyPaid,
+}
+
+impl Error {
+    /// Map an error returned by `pay` onto the payment outcome it reports
+    ///
+    /// Errors without a code did not come from the payment itself, e.g. the
+    /// RPC connection failed, and leave the payment state unknown.
+    pub fn from_pay_error(err: cln_rpc::RpcError) -> Self {
+        match err.code {
+            Some(pay_codes::PAY_IN_PROGRESS) => Self::PaymentPending,
+            Some(pay_codes::PAY_RHASH_ALREADY_USED) => Self::InvoiceAlreadyPaid,
+            Some(_) => Self::PaymentFailed(err.message),
+            None => Self::ClnRpc(err),
+        }
+    }
 }
 
 impl From<Error> for cdk_common::payment::Error {
     fn from(e: Error) -> Self {
-        Self::Lightning(Box::new(e))
+        match e {
+            Error::InvoiceAlreadyPaid => Self::InvoiceAlreadyPaid,
+            Error::PaymentPending => Self::InvoicePaymentPending,
+            e => Self::Lightning(Box::new(e)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cdk_common::payment;
+
+    use super::*;
+
+    fn rpc_error(code: Option<i32>, message: &str) -> cln_rpc::RpcError {
+        cln_rpc::RpcError {
+            code,
+            message: message.to_string(),
+            data: None,
+        }
+    }
+
+    #[test]
+    fn test_from_pay_error() {
+        assert!(matches!(
+            Error::from_pay_error(rpc_error(Some(200), "in progress")),
+            Error::PaymentPending
+        ));
+        assert!(matches!(
+            Error::from_pay_error(rpc_error(Some(201), "already paid")),
+            Error::InvoiceAlreadyPaid
+        ));
+        assert!(matches!(
+            Error::from_pay_error(rpc_error(Some(205), "Ran out of routes to try")),
+            Error::PaymentFailed(reason) if reason == "Ran out of routes to try"
+        ));
+        assert!(matches!(
+            Error::from_pay_error(rpc_error(None, "connection closed")),
+            Error::ClnRpc(_)
+        ));
+    }
+
+    #[test]
+    fn test_into_payment_error() {
+        assert!(matches!(
+            payment::Error::from(Error::InvoiceAlreadyPaid),
+            payment::Error::InvoiceAlreadyPaid
+        ));
+        assert!(matches!(
+            payment::Error::from(Error::PaymentPending),
+            payment::Error::InvoicePaymentPending
+        ));
+        assert!(matches!(
+            payment::Error::from(Error::PaymentFailed("no route".to_string())),
+            payment::Error::Lightning(_)
+        ));
     }
 }
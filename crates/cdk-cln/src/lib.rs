@@ -489,7 +489,7 @@ impl MintPayment for Cln {
             }
             Err(err) => {
                 tracing::error!("Could not pay invoice: {}", err);
-                return Err(Error::ClnRpc(err).into());
+                return Err(Error::from_pay_error(err).into());
             }
         };
 
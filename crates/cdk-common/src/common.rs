@@ -1,5 +1,8 @@
 //! Types
 
+use std::fmt;
+use std::str::FromStr;
+
 use serde::{Deserialize, Serialize};
 
 use crate::error::Error;
@@ -76,6 +79,9 @@ pub struct ProofInfo {
     pub spending_condition: Option<SpendingConditions>,
     /// Unit
     pub unit: CurrencyUnit,
+    /// How the proof was received
+    #[serde(default)]
+    pub source: ProofSource,
 }
 
 impl ProofInfo {
@@ -97,9 +103,16 @@ impl ProofInfo {
             state,
             spending_condition,
             unit,
+            source: ProofSource::Unknown,
         })
     }
 
+    /// Set how the proof was received
+    pub fn with_source(mut self, source: ProofSource) -> Self {
+        self.source = source;
+        self
+    }
+
     /// Check if [`Proof`] matches conditions
     pub fn matches_conditions(
         &self,
@@ -145,6 +158,55 @@ impl ProofInfo {
     }
 }
 
+/// How a [`ProofInfo`] came into the wallet
+#[derive(
+    Debug, Clone, Copy, Default, Hash, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize,
+)]
+pub enum ProofSource {
+    /// Stored before the source was recorded
+    #[default]
+    Unknown,
+    /// Minted from a bolt11 quote
+    Bolt11Mint,
+    /// Minted from a bolt12 quote
+    Bolt12Mint,
+    /// Minted from a mining share quote
+    MiningShareMint,
+    /// Received from a swap of the wallet's own proofs
+    Swap,
+    /// Received in a token
+    Receive,
+}
+
+impl fmt::Display for ProofSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProofSource::Unknown => write!(f, "Unknown"),
+            ProofSource::Bolt11Mint => write!(f, "Bolt11Mint"),
+            ProofSource::Bolt12Mint => write!(f, "Bolt12Mint"),
+            ProofSource::MiningShareMint => write!(f, "MiningShareMint"),
+            ProofSource::Swap => write!(f, "Swap"),
+            ProofSource::Receive => write!(f, "Receive"),
+        }
+    }
+}
+
+impl FromStr for ProofSource {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "Unknown" => Ok(Self::Unknown),
+            "Bolt11Mint" => Ok(Self::Bolt11Mint),
+            "Bolt12Mint" => Ok(Self::Bolt12Mint),
+            "MiningShareMint" => Ok(Self::MiningShareMint),
+            "Swap" => Ok(Self::Swap),
+            "Receive" => Ok(Self::Receive),
+            _ => Err(Error::InvalidProofSource),
+        }
+    }
+}
+
 /// Key used in hashmap of ln backends to identify what unit and payment method
 /// it is for
 #[derive(Debug, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
@@ -193,7 +255,7 @@ mod tests {
 
     use cashu::SecretKey;
 
-    use super::{Melted, ProofInfo};
+    use super::{Melted, ProofInfo, ProofSource};
     use crate::mint_url::MintUrl;
     use crate::nuts::{CurrencyUnit, Id, Proof, PublicKey, SpendingConditions, State};
     use crate::secret::Secret;
@@ -341,6 +403,47 @@ mod tests {
         };
         assert!(!proof_info.matches_conditions(&None, &None, &None, &Some(vec![dummy_condition])));
     }
+
+    #[test]
+    fn test_proof_info_source() {
+        let keyset_id = Id::from_str("00deadbeef123456").unwrap();
+        let proof = Proof::new(
+            Amount::from(64),
+            keyset_id,
+            Secret::new("test_secret"),
+            PublicKey::from_hex(
+                "02deadbeefdeadbeefdeadbeefdeadbeefdeadbeefdeadbeefdeadbeefdeadbeef",
+            )
+            .unwrap(),
+        );
+
+        let mint_url = MintUrl::from_str("https://example.com").unwrap();
+        let proof_info =
+            ProofInfo::new(proof, mint_url, State::Unspent, CurrencyUnit::Sat).unwrap();
+        assert_eq!(proof_info.source, ProofSource::Unknown);
+
+        let proof_info = proof_info.with_source(ProofSource::MiningShareMint);
+        let json = serde_json::to_value(&proof_info).unwrap();
+        assert_eq!(json["source"], "MiningShareMint");
+
+        // Proof infos stored before the source was recorded still load
+        let mut old = json;
+        old.as_object_mut().unwrap().remove("source");
+        let loaded: ProofInfo = serde_json::from_value(old).unwrap();
+        assert_eq!(loaded.source, ProofSource::Unknown);
+
+        for source in [
+            ProofSource::Unknown,
+            ProofSource::Bolt11Mint,
+            ProofSource::Bolt12Mint,
+            ProofSource::MiningShareMint,
+            ProofSource::Swap,
+            ProofSource::Receive,
+        ] {
+            assert_eq!(ProofSource::from_str(&source.to_string()).unwrap(), source);
+        }
+        assert!(ProofSource::from_str("Melt").is_err());
+    }
 }
 
 /// Mint Fee Reserve
@@ -1,4 +1,15 @@
 //! CDK Database
+//!
+//! # Read replicas
+//!
+//! Every method is either a replica read or a primary operation:
+//!
+//! - Methods of the `*Database` traits that take `&self` (other than
+//!   `begin_transaction`) are replica reads. An implementation may serve them
+//!   from a read replica, so they can lag behind recent writes.
+//! - `begin_transaction` and every method of the `*Transaction` traits run on
+//!   the primary. Code that has to read its own writes, like issuance and
+//!   melting, reads inside a transaction.
 
 use std::collections::HashMap;
 
@@ -69,6 +80,7 @@ use cashu::{Amount, MintInfo};
 use super::Error;
 use crate::common::QuoteTTL;
 use crate::mint::{self, MintKeySetInfo, MintQuote as MintMintQuote};
+use crate::nuts::nutXX::MiningShareStats;
 use crate::nuts::{
     BlindSignature, CurrencyUnit, Id, MeltQuoteState, Proof, Proofs, PublicKey, State,
 };
@@ -94,6 +106,8 @@ pub trait KeysDatabaseTransaction<'a, Error>: DbTransactionFinalizer<Err = Error
 }
 
 /// Mint Keys Database trait
+///
+/// Getters are replica reads, see the [module documentation](self).
 #[async_trait]
 pub trait KeysDatabase {
     /// Mint Keys Database Error
@@ -143,8 +157,27 @@ pub trait QuotesTransaction<'a> {
         quote_id: &QuoteId,
         amount_issued: Amount,
     ) -> Result<Amount, Self::Err>;
-    /// Remove [`MintMintQuote`]
+    /// Remove [`MintMintQuote`] and the payments recorded for it
     async fn remove_mint_quote(&mut self, quote_id: &QuoteId) -> Result<(), Self::Err>;
+    /// Remove the mining share [`MintMintQuote`]s that expired at or before `now`
+    /// without any ecash issued for them, with the payments recorded for them
+    ///
+    /// Returns the number of quotes removed.
+    async fn remove_expired_mining_share_quotes(&mut self, now: u64) -> Result<u64, Self::Err>;
+    /// Mark an unpaid [`MintMintQuote`] as converted into another quote
+    ///
+    /// Fails with [`Error::QuoteNotFound`] if the quote does not exist, is paid or
+    /// was already converted.
+    async fn convert_mint_quote(
+        &mut self,
+        quote_id: &QuoteId,
+        converted_to: &QuoteId,
+    ) -> Result<(), Self::Err>;
+    /// Mark a [`MintMintQuote`] without ecash issued for it as cancelled at `now`
+    ///
+    /// Fails with [`Error::QuoteNotFound`] if the quote does not exist, has ecash
+    /// issued for it, or was already converted or cancelled.
+    async fn cancel_mint_quote(&mut self, quote_id: &QuoteId, now: u64) -> Result<(), Self::Err>;
     /// Get [`mint::MeltQuote`] and lock it for update in this transaction
     async fn get_melt_quote(
         &mut self,
@@ -182,9 +215,21 @@ pub trait QuotesTransaction<'a> {
         &mut self,
         request_lookup_id: &PaymentIdentifier,
     ) -> Result<Option<MintMintQuote>, Self::Err>;
+
+    /// Count the pending [`MintMintQuote`]s locked to a NUT-20 pubkey
+    ///
+    /// A quote is pending while it is not fully issued, not converted and its
+    /// expiry is after `now`.
+    async fn count_pending_mint_quotes_by_pubkey(
+        &mut self,
+        pubkey: &PublicKey,
+        now: u64,
+    ) -> Result<u64, Self::Err>;
 }
 
 /// Mint Quote Database trait
+///
+/// Replica reads, see the [module documentation](self).
 #[async_trait]
 pub trait QuotesDatabase {
     /// Mint Quotes Database Error
@@ -205,6 +250,13 @@ pub trait QuotesDatabase {
     ) -> Result<Option<MintMintQuote>, Self::Err>;
     /// Get Mint Quotes
     async fn get_mint_quotes(&self) -> Result<Vec<MintMintQuote>, Self::Err>;
+    /// Aggregate the mining share [`MintMintQuote`]s created between `since` and
+    /// `until`, both inclusive
+    async fn mining_share_stats(
+        &self,
+        since: u64,
+        until: u64,
+    ) -> Result<MiningShareStats, Self::Err>;
     /// Get [`mint::MeltQuote`]
     async fn get_melt_quote(
         &self,
@@ -245,6 +297,8 @@ pub trait ProofsTransaction<'a> {
 }
 
 /// Mint Proof Database trait
+///
+/// Replica reads, see the [module documentation](self).
 #[async_trait]
 pub trait ProofsDatabase {
     /// Mint Proof Database Error
@@ -289,6 +343,8 @@ pub trait SignaturesTransaction<'a> {
 
 #[async_trait]
 /// Mint Signatures Database trait
+///
+/// Replica reads, see the [module documentation](self).
 pub trait SignaturesDatabase {
     /// Mint Signature Database Error
     type Err: Into<Error> + From<Error>;
@@ -376,6 +432,8 @@ pub trait Transaction<'a, Error>:
 }
 
 /// Key-Value Store Database trait
+///
+/// Replica reads, see the [module documentation](self).
 #[async_trait]
 pub trait KVStoreDatabase {
     /// KV Store Database Error
@@ -407,6 +465,8 @@ pub trait KVStore: KVStoreDatabase {
 }
 
 /// Mint Database trait
+///
+/// Getters are replica reads, see the [module documentation](self).
 #[async_trait]
 pub trait Database<Error>:
     KVStoreDatabase<Err = Error>
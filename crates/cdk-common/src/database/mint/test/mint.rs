@@ -414,3 +414,389 @@ where
         .await
         .is_err());
 }
+
+/// Only unissued, unconverted and unexpired quotes of the pubkey are pending
+pub async fn count_pending_mint_quotes_by_pubkey<DB>(db: DB)
+where
+    DB: Database<Error> + KeysDatabase<Err = Error>,
+{
+    let pubkey = cashu::SecretKey::generate().public_key();
+    let other_pubkey = cashu::SecretKey::generate().public_key();
+    let now = 1_000;
+
+    let quote = |pubkey, expiry| {
+        MintQuote::new(
+            None,
+            "".to_owned(),
+            cashu::CurrencyUnit::Sat,
+            Some(100.into()),
+            expiry,
+            PaymentIdentifier::CustomId(unique_string()),
+            Some(pubkey),
+            0.into(),
+            0.into(),
+            cashu::PaymentMethod::MiningShare,
+            0,
+            vec![],
+            vec![],
+            None,
+        )
+    };
+
+    let unpaid = quote(pubkey, now + 60);
+    let paid = quote(pubkey, now + 60);
+    let issued = quote(pubkey, now + 60);
+    let expired = quote(pubkey, now);
+    let converted = quote(pubkey, now + 60);
+    let other = quote(other_pubkey, now + 60);
+
+    let mut tx = Database::begin_transaction(&db).await.unwrap();
+    for mint_quote in [&unpaid, &paid, &issued, &expired, &converted, &other] {
+        tx.add_mint_quote(mint_quote.clone()).await.unwrap();
+    }
+    for mint_quote in [&paid, &issued] {
+        tx.increment_mint_quote_amount_paid(&mint_quote.id, 100.into(), unique_string())
+            .await
+            .unwrap();
+    }
+    tx.increment_mint_quote_amount_issued(&issued.id, 100.into())
+        .await
+        .unwrap();
+    tx.convert_mint_quote(&converted.id, &unpaid.id)
+        .await
+        .unwrap();
+    tx.commit().await.unwrap();
+
+    let mut tx = Database::begin_transaction(&db).await.unwrap();
+    assert_eq!(
+        tx.count_pending_mint_quotes_by_pubkey(&pubkey, now)
+            .await
+            .unwrap(),
+        2
+    );
+    assert_eq!(
+        tx.count_pending_mint_quotes_by_pubkey(&other_pubkey, now)
+            .await
+            .unwrap(),
+        1
+    );
+    // Once they expire no quote is pending anymore
+    assert_eq!(
+        tx.count_pending_mint_quotes_by_pubkey(&pubkey, now + 60)
+            .await
+            .unwrap(),
+        0
+    );
+    tx.commit().await.unwrap();
+}
+
+/// Removing a quote frees its lookup id and payment ids for a new quote
+pub async fn remove_mint_quote_frees_payments<DB>(db: DB)
+where
+    DB: Database<Error> + KeysDatabase<Err = Error>,
+{
+    let lookup_id = PaymentIdentifier::CustomId(unique_string());
+    let payment_id = unique_string();
+    let new_quote = || {
+        MintQuote::new(
+            None,
+            "".to_owned(),
+            cashu::CurrencyUnit::Sat,
+            None,
+            0,
+            lookup_id.clone(),
+            None,
+            0.into(),
+            0.into(),
+            cashu::PaymentMethod::Bolt12,
+            0,
+            vec![],
+            vec![],
+            None,
+        )
+    };
+
+    let first = new_quote();
+    let mut tx = Database::begin_transaction(&db).await.unwrap();
+    tx.add_mint_quote(first.clone()).await.unwrap();
+    tx.increment_mint_quote_amount_paid(&first.id, 100.into(), payment_id.clone())
+        .await
+        .unwrap();
+    tx.commit().await.unwrap();
+
+    let second = new_quote();
+    let mut tx = Database::begin_transaction(&db).await.unwrap();
+    tx.remove_mint_quote(&first.id).await.unwrap();
+    tx.add_mint_quote(second.clone()).await.unwrap();
+    tx.increment_mint_quote_amount_paid(&second.id, 100.into(), payment_id)
+        .await
+        .unwrap();
+    tx.commit().await.unwrap();
+
+    assert!(db.get_mint_quote(&first.id).await.unwrap().is_none());
+    let second_from_db = db
+        .get_mint_quote_by_request_lookup_id(&lookup_id)
+        .await
+        .unwrap()
+        .expect("second quote");
+    assert_eq!(second_from_db.id, second.id);
+    assert_eq!(second_from_db.amount_paid(), 100.into());
+}
+
+/// Only mining share quotes that expired without issuance are swept
+pub async fn remove_expired_mining_share_quotes<DB>(db: DB)
+where
+    DB: Database<Error> + KeysDatabase<Err = Error>,
+{
+    let now = 1_000;
+    let new_quote = |expiry: u64, payment_method: cashu::PaymentMethod| {
+        MintQuote::new(
+            None,
+            "".to_owned(),
+            cashu::CurrencyUnit::Sat,
+            Some(100.into()),
+            expiry,
+            PaymentIdentifier::CustomId(unique_string()),
+            None,
+            0.into(),
+            0.into(),
+            payment_method,
+            0,
+            vec![],
+            vec![],
+            None,
+        )
+    };
+
+    let expired = new_quote(now - 1, cashu::PaymentMethod::MiningShare);
+    let expires_now = new_quote(now, cashu::PaymentMethod::MiningShare);
+    let expired_issued = new_quote(now - 1, cashu::PaymentMethod::MiningShare);
+    let live = new_quote(now + 1, cashu::PaymentMethod::MiningShare);
+    let no_expiry = new_quote(0, cashu::PaymentMethod::MiningShare);
+    let expired_bolt12 = new_quote(now - 1, cashu::PaymentMethod::Bolt12);
+    let quotes = [
+        &expired,
+        &expires_now,
+        &expired_issued,
+        &live,
+        &no_expiry,
+        &expired_bolt12,
+    ];
+
+    let mut tx = Database::begin_transaction(&db).await.unwrap();
+    for quote in quotes {
+        tx.add_mint_quote(quote.clone()).await.unwrap();
+        tx.increment_mint_quote_amount_paid(&quote.id, 100.into(), unique_string())
+            .await
+            .unwrap();
+    }
+    tx.increment_mint_quote_amount_issued(&expired_issued.id, 50.into())
+        .await
+        .unwrap();
+    tx.commit().await.unwrap();
+
+    let mut tx = Database::begin_transaction(&db).await.unwrap();
+    assert_eq!(tx.remove_expired_mining_share_quotes(now).await.unwrap(), 2);
+    tx.commit().await.unwrap();
+
+    assert!(db.get_mint_quote(&expired.id).await.unwrap().is_none());
+    assert!(db.get_mint_quote(&expires_now.id).await.unwrap().is_none());
+    for quote in [&expired_issued, &live, &no_expiry, &expired_bolt12] {
+        assert!(db.get_mint_quote(&quote.id).await.unwrap().is_some());
+    }
+
+    // Sweeping again finds nothing
+    let mut tx = Database::begin_transaction(&db).await.unwrap();
+    assert_eq!(tx.remove_expired_mining_share_quotes(now).await.unwrap(), 0);
+    tx.commit().await.unwrap();
+}
+
+/// Only quotes without ecash issued can be cancelled, and only once
+pub async fn cancel_mint_quote<DB>(db: DB)
+where
+    DB: Database<Error> + KeysDatabase<Err = Error>,
+{
+    let new_quote = || {
+        MintQuote::new(
+            None,
+            "".to_owned(),
+            cashu::CurrencyUnit::Sat,
+            Some(100.into()),
+            0,
+            PaymentIdentifier::CustomId(unique_string()),
+            None,
+            0.into(),
+            0.into(),
+            cashu::PaymentMethod::Bolt12,
+            0,
+            vec![],
+            vec![],
+            None,
+        )
+    };
+
+    let unpaid = new_quote();
+    let paid = new_quote();
+    let issued = new_quote();
+
+    let mut tx = Database::begin_transaction(&db).await.unwrap();
+    for quote in [&unpaid, &paid, &issued] {
+        tx.add_mint_quote(quote.clone()).await.unwrap();
+    }
+    for quote in [&paid, &issued] {
+        tx.increment_mint_quote_amount_paid(&quote.id, 100.into(), unique_string())
+            .await
+            .unwrap();
+    }
+    tx.increment_mint_quote_amount_issued(&issued.id, 50.into())
+        .await
+        .unwrap();
+    tx.commit().await.unwrap();
+
+    let mut tx = Database::begin_transaction(&db).await.unwrap();
+    tx.cancel_mint_quote(&unpaid.id, 10).await.unwrap();
+    tx.cancel_mint_quote(&paid.id, 10).await.unwrap();
+    assert!(tx.cancel_mint_quote(&issued.id, 10).await.is_err());
+    tx.commit().await.unwrap();
+
+    for quote in [&unpaid, &paid] {
+        let quote = db.get_mint_quote(&quote.id).await.unwrap().unwrap();
+        assert_eq!(quote.cancelled_at, Some(10));
+        assert_eq!(quote.state(), cashu::MintQuoteState::Cancelled);
+    }
+    let issued = db.get_mint_quote(&issued.id).await.unwrap().unwrap();
+    assert_eq!(issued.cancelled_at, None);
+
+    // A cancelled quote is not cancelled again
+    let mut tx = Database::begin_transaction(&db).await.unwrap();
+    assert!(tx.cancel_mint_quote(&unpaid.id, 20).await.is_err());
+    tx.rollback().await.unwrap();
+}
+
+/// The share difficulty of a mint quote is stored with it
+pub async fn mint_quote_difficulty<DB>(db: DB)
+where
+    DB: Database<Error> + KeysDatabase<Err = Error>,
+{
+    let new_quote = |difficulty: Option<u64>| {
+        let mut quote = MintQuote::new(
+            None,
+            "".to_owned(),
+            cashu::CurrencyUnit::Sat,
+            Some(64.into()),
+            0,
+            PaymentIdentifier::CustomId(unique_string()),
+            None,
+            0.into(),
+            0.into(),
+            cashu::PaymentMethod::MiningShare,
+            0,
+            vec![],
+            vec![],
+            None,
+        );
+        quote.difficulty = difficulty;
+        quote
+    };
+
+    let with_difficulty = new_quote(Some(6));
+    let without_difficulty = new_quote(None);
+
+    let mut tx = Database::begin_transaction(&db).await.unwrap();
+    tx.add_mint_quote(with_difficulty.clone()).await.unwrap();
+    tx.add_mint_quote(without_difficulty.clone()).await.unwrap();
+    tx.commit().await.unwrap();
+
+    let stored = db
+        .get_mint_quote(&with_difficulty.id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(stored.difficulty, Some(6));
+    let stored = db
+        .get_mint_quote(&without_difficulty.id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(stored.difficulty, None);
+}
+
+/// Mining share stats aggregate the mining share quotes created in the range
+pub async fn mining_share_stats<DB>(db: DB)
+where
+    DB: Database<Error> + KeysDatabase<Err = Error>,
+{
+    let pubkey = cashu::SecretKey::generate().public_key();
+    let other_pubkey = cashu::SecretKey::generate().public_key();
+    let (first, second) = (1_000, 2_000);
+
+    let quote = |amount: u64, pubkey, method, created_time| {
+        MintQuote::new(
+            None,
+            "".to_owned(),
+            cashu::CurrencyUnit::Sat,
+            Some(amount.into()),
+            10_000,
+            PaymentIdentifier::CustomId(unique_string()),
+            pubkey,
+            0.into(),
+            0.into(),
+            method,
+            created_time,
+            vec![],
+            vec![],
+            None,
+        )
+    };
+    let mining_share = cashu::PaymentMethod::MiningShare;
+
+    let issued = quote(100, Some(pubkey), mining_share.clone(), first);
+    let unpaid = quote(50, Some(pubkey), mining_share.clone(), first);
+    let other_issued = quote(30, Some(other_pubkey), mining_share.clone(), first);
+    let later_issued = quote(200, Some(other_pubkey), mining_share.clone(), second);
+    let later_unlocked = quote(10, None, mining_share, second);
+    let bolt11 = quote(1_000, Some(pubkey), cashu::PaymentMethod::Bolt11, first);
+
+    let mut tx = Database::begin_transaction(&db).await.unwrap();
+    for mint_quote in [
+        &issued,
+        &unpaid,
+        &other_issued,
+        &later_issued,
+        &later_unlocked,
+        &bolt11,
+    ] {
+        tx.add_mint_quote(mint_quote.clone()).await.unwrap();
+    }
+    for mint_quote in [&issued, &other_issued, &later_issued, &bolt11] {
+        let amount = mint_quote.amount.unwrap();
+        tx.increment_mint_quote_amount_paid(&mint_quote.id, amount, unique_string())
+            .await
+            .unwrap();
+        tx.increment_mint_quote_amount_issued(&mint_quote.id, amount)
+            .await
+            .unwrap();
+    }
+    tx.commit().await.unwrap();
+
+    let stats = db.mining_share_stats(first, first).await.unwrap();
+    assert_eq!(stats.quotes_created, 3);
+    assert_eq!(stats.quotes_issued, 2);
+    assert_eq!(stats.amount_quoted, 180.into());
+    assert_eq!(stats.amount_issued, 130.into());
+    assert_eq!(stats.distinct_pubkeys, 2);
+
+    let stats = db.mining_share_stats(first, second).await.unwrap();
+    assert_eq!(stats.quotes_created, 5);
+    assert_eq!(stats.quotes_issued, 3);
+    assert_eq!(stats.amount_quoted, 390.into());
+    assert_eq!(stats.amount_issued, 330.into());
+    assert_eq!(stats.distinct_pubkeys, 2);
+
+    assert_eq!(
+        db.mining_share_stats(second + 1, second + 1_000)
+            .await
+            .unwrap(),
+        Default::default()
+    );
+}
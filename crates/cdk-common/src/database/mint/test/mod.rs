@@ -235,7 +235,13 @@ macro_rules! mint_db_test {
             reject_over_issue_same_tx,
             reject_over_issue_different_tx,
             reject_over_issue_with_payment,
-            reject_over_issue_with_payment_different_tx
+            reject_over_issue_with_payment_different_tx,
+            count_pending_mint_quotes_by_pubkey,
+            remove_mint_quote_frees_payments,
+            remove_expired_mining_share_quotes,
+            cancel_mint_quote,
+            mint_quote_difficulty,
+            mining_share_stats
         );
     };
     ($make_db_fn:ident, $($name:ident),+ $(,)?) => {
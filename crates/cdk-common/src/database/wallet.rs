@@ -13,7 +13,8 @@ use crate::nuts::{
     CurrencyUnit, Id, KeySetInfo, Keys, MintInfo, PublicKey, SpendingConditions, State,
 };
 use crate::wallet::{
-    self, MintQuote as WalletMintQuote, Transaction, TransactionDirection, TransactionId,
+    self, DeadLetter, MintQuote as WalletMintQuote, PendingMintOperation, RestoreProgress,
+    Transaction, TransactionDirection, TransactionId, WalletSnapshot,
 };
 
 /// Wallet Database trait
@@ -109,6 +110,53 @@ pub trait Database: Debug {
     /// Atomically increment Keyset counter and return new value
     async fn increment_keyset_counter(&self, keyset_id: &Id, count: u32) -> Result<u32, Self::Err>;
 
+    /// Add or replace the restore progress of a keyset
+    async fn add_restore_progress(&self, progress: RestoreProgress) -> Result<(), Self::Err>;
+    /// Get the restore progress of a keyset
+    async fn get_restore_progress(
+        &self,
+        keyset_id: &Id,
+    ) -> Result<Option<RestoreProgress>, Self::Err>;
+    /// Remove the restore progress of a keyset
+    async fn remove_restore_progress(&self, keyset_id: &Id) -> Result<(), Self::Err>;
+
+    /// Add dead letter to storage
+    ///
+    /// The id of `dead_letter` is ignored, the store assigns the next one.
+    async fn add_dead_letter(&self, dead_letter: DeadLetter) -> Result<(), Self::Err>;
+    /// Get dead letters from storage, optionally of one mint, oldest first
+    async fn get_dead_letters(
+        &self,
+        mint_url: Option<MintUrl>,
+    ) -> Result<Vec<DeadLetter>, Self::Err>;
+    /// Remove dead letter from storage
+    async fn remove_dead_letter(&self, id: u64) -> Result<(), Self::Err>;
+
+    /// Add pending mint operation to storage, replacing one with the same id
+    async fn add_pending_mint_operation(
+        &self,
+        operation: PendingMintOperation,
+    ) -> Result<(), Self::Err>;
+    /// Get pending mint operations from storage, oldest first
+    async fn get_pending_mint_operations(&self) -> Result<Vec<PendingMintOperation>, Self::Err>;
+    /// Remove pending mint operation from storage
+    async fn remove_pending_mint_operation(&self, id: &str) -> Result<(), Self::Err>;
+
+    /// Add wallet snapshot to storage and return its id
+    ///
+    /// The id of `snapshot` is ignored, the store assigns the next one.
+    async fn add_wallet_snapshot(&self, snapshot: WalletSnapshot) -> Result<u64, Self::Err>;
+    /// Get wallet snapshot from storage
+    async fn get_wallet_snapshot(&self, id: u64) -> Result<Option<WalletSnapshot>, Self::Err>;
+    /// Get the wallet snapshots of a mint and unit from storage, oldest first
+    async fn get_wallet_snapshots(
+        &self,
+        mint_url: MintUrl,
+        unit: CurrencyUnit,
+    ) -> Result<Vec<WalletSnapshot>, Self::Err>;
+    /// Remove wallet snapshot from storage
+    async fn remove_wallet_snapshot(&self, id: u64) -> Result<(), Self::Err>;
+
     /// Add transaction to storage
     async fn add_transaction(&self, transaction: Transaction) -> Result<(), Self::Err>;
     /// Get transaction from storage
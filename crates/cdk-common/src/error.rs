@@ -159,6 +159,92 @@ pub enum Error {
     /// Quote has already been paid
     #[error("Quote is already paid")]
     PaidQuote,
+    /// Quote was converted to a quote of another payment method
+    #[error("Quote was converted to another payment method")]
+    ConvertedQuote,
+    /// Quote was cancelled
+    #[error("Quote was cancelled")]
+    CancelledQuote,
+    /// Mint does not accept cancelling mint quotes
+    #[error("Quote cancellation is not supported")]
+    QuoteCancelUnsupported,
+    /// Pubkey already holds the most pending quotes the mint allows
+    #[error("Pubkey already has `{0}` pending quotes, the most allowed")]
+    TooManyPendingQuotes(u32),
+    /// Mining share stats time range ends before it starts or is too long
+    #[error("Invalid mining share stats range `{since}` to `{until}`")]
+    InvalidMiningShareStatsRange {
+        /// Start of the range
+        since: u64,
+        /// End of the range
+        until: u64,
+    },
+    /// Mining share request has no difficulty to check its amount against
+    #[error("Mining share difficulty required")]
+    MiningShareDifficultyMissing,
+    /// Mining share amount differs from the amount allowed for its difficulty
+    #[error(
+        "Mining share amount `{amount}` does not match `{allowed}` allowed for its difficulty"
+    )]
+    MiningShareAmountMismatch {
+        /// Amount requested
+        amount: Amount,
+        /// Amount allowed for the difficulty
+        allowed: Amount,
+    },
+    /// A live quote already exists for the mining share header hash
+    #[error("Mining share already quoted by quote `{0}`")]
+    DuplicateShareHash(String),
+    /// Mint could not quote a mining share
+    #[error("Mining share `{header_hash}` could not be quoted: {reason}")]
+    MiningShareQuoteFailed {
+        /// Block header hash of the share
+        header_hash: String,
+        /// Why the quote could not be created
+        reason: String,
+    },
+    /// Mint request uses more outputs than the mining share quote amount needs
+    #[error("Mining share quote allows at most `{max}` outputs, got `{outputs}`")]
+    MiningShareTooManyOutputs {
+        /// Most outputs allowed
+        max: usize,
+        /// Outputs in the request
+        outputs: usize,
+    },
+    /// Quote status batch asks for more quotes than allowed
+    #[error("Quote status batch allows at most `{max}` quotes, got `{quotes}`")]
+    BatchQuoteStatusTooManyQuotes {
+        /// Most quotes allowed
+        max: usize,
+        /// Quotes in the request
+        quotes: usize,
+    },
+    /// Mining share quote batch asks for more quotes than allowed
+    #[error("Mining share quote batch allows at most `{max}` quotes, got `{quotes}`")]
+    BatchMintQuoteTooManyQuotes {
+        /// Most quotes allowed
+        max: usize,
+        /// Quotes in the request
+        quotes: usize,
+    },
+    /// Mining share quote batch asks for more in total than allowed
+    #[error("Mining share quote batch allows at most `{max}` in total, got `{amount}`")]
+    BatchMintQuoteAmountTooLarge {
+        /// Largest total allowed
+        max: Amount,
+        /// Total of the request
+        amount: Amount,
+    },
+    /// Mint response does not match what the wallet requested or stored
+    #[error("Invalid mint response: `{field}` expected `{expected}`, got `{got}`")]
+    MintResponseInvalid {
+        /// Response field
+        field: &'static str,
+        /// Value the wallet expected
+        expected: String,
+        /// Value the mint returned
+        got: String,
+    },
     /// Payment state is unknown
     #[error("Payment state is unknown")]
     UnknownPaymentState,
@@ -240,6 +326,9 @@ pub enum Error {
     /// Invalid DLEQ proof
     #[error("Could not verify DLEQ proof")]
     CouldNotVerifyDleq,
+    /// Invalid DLEQ proof of a proof in a list
+    #[error("Could not verify DLEQ proof of proof at index `{0}`")]
+    InvalidProofDleq(usize),
     /// Dleq Proof not provided for signature
     #[error("Dleq proof not provided for signature")]
     DleqProofNotProvided,
@@ -253,9 +342,79 @@ pub enum Error {
     /// Preimage not provided
     #[error("Preimage not provided")]
     PreimageNotProvided,
+    /// Payment preimage returned by the mint does not match the payment hash
+    #[error("Invalid payment preimage for quote `{quote_id}`")]
+    InvalidPaymentPreimage {
+        /// Melt quote id
+        quote_id: String,
+    },
+    /// Quote unit does not match the wallet unit
+    ///
+    /// Kept apart from [`Error::UnitMismatch`], which is the NUT error
+    /// 11010 returned by the mint and carries no units.
+    #[error("Quote unit `{quote}` does not match wallet unit `{wallet}`")]
+    QuoteUnitMismatch {
+        /// Unit of the quote
+        quote: CurrencyUnit,
+        /// Unit of the wallet
+        wallet: CurrencyUnit,
+    },
+    /// Amount without unit passed to a wallet with strict amounts
+    ///
+    /// Holds the checked method to use instead.
+    #[error("Amounts without unit are refused with strict amounts, use `{0}`")]
+    StrictAmounts(&'static str),
+    /// Split target cannot be satisfied by the amount to mint
+    #[error("Split target of `{requested}` cannot be satisfied by `{available}` to mint")]
+    InvalidSplitTarget {
+        /// Amount the split target asks for
+        requested: Amount,
+        /// Amount available to mint
+        available: Amount,
+    },
+    /// Amount to mint exceeds what is left of the quote
+    #[error("Cannot mint `{requested}`, only `{mintable}` left to mint")]
+    AmountExceedsMintable {
+        /// Amount asked to mint
+        requested: Amount,
+        /// Amount left to mint of the quote
+        mintable: Amount,
+    },
+    /// Mint does not advertise a capability the request needs
+    #[error("Mint does not support {0}")]
+    MintDoesNotSupport(&'static str),
+    /// Pending quote export written in a format version this wallet cannot read
+    #[error("Unsupported quote export version `{0}`")]
+    UnsupportedQuoteExportVersion(u32),
+    /// Full proof export was not acknowledged
+    #[error("Exporting proofs with their secrets must be acknowledged")]
+    ExportNotAcknowledged,
+    /// Keyset shares keys with another keyset of the mint
+    #[error("Keyset `{0}` reuses keys of another keyset, trust it explicitly to use it")]
+    KeysetKeyReuse(Id),
+    /// Unknown wallet snapshot
+    #[error("Unknown snapshot")]
+    UnknownSnapshot,
+    /// Rollback would undo changes already visible outside the wallet
+    #[error("Rollback refused: {0} since the snapshot")]
+    SnapshotRollbackRefused(String),
+    /// Invoice of a melt quote expired
+    #[error("Invoice expired at `{0}`")]
+    InvoiceExpired(u64),
+    /// Mint could not be reached, the mint request was stored to be sent again
+    #[error("Mint unreachable, mint request for quote `{0}` queued for retry")]
+    QueuedForRetry(String),
     /// Insufficient Funds
     #[error("Insufficient funds")]
     InsufficientFunds,
+    /// Proof selection needs more inputs than the wallet allows
+    #[error("Selection needs `{inputs}` inputs, at most `{max}` allowed")]
+    MaxInputsExceeded {
+        /// Most inputs allowed
+        max: usize,
+        /// Inputs the selection needs
+        inputs: usize,
+    },
     /// Unexpected proof state
     #[error("Unexpected proof state")]
     UnexpectedProofState,
@@ -271,9 +430,15 @@ pub enum Error {
     /// Invalid transaction direction
     #[error("Invalid transaction direction")]
     InvalidTransactionDirection,
+    /// Invalid proof source
+    #[error("Invalid proof source")]
+    InvalidProofSource,
     /// Invalid transaction id
     #[error("Invalid transaction id")]
     InvalidTransactionId,
+    /// Invalid dead letter reason
+    #[error("Invalid dead letter reason")]
+    InvalidDeadLetterReason,
     /// Transaction not found
     #[error("Transaction not found")]
     TransactionNotFound,
@@ -312,6 +477,9 @@ pub enum Error {
     /// Http transport error
     #[error("Http transport error {0:?}: {1}")]
     HttpError(Option<u16>, String),
+    /// Mint rate limited the request
+    #[error("Rate limited by mint, retry after {0:?} seconds")]
+    RateLimited(Option<u64>),
     #[cfg(feature = "wallet")]
     // Crate error conversions
     /// Cashu Url Error
@@ -357,6 +525,9 @@ pub enum Error {
     /// NUT14 Error
     #[error(transparent)]
     NUT14(#[from] crate::nuts::nut14::Error),
+    /// NUT17 Error
+    #[error(transparent)]
+    NUT17(#[from] crate::nuts::nut17::Error),
     /// NUT18 Error
     #[error(transparent)]
     NUT18(#[from] crate::nuts::nut18::Error),
@@ -588,6 +759,28 @@ impl From<Error> for ErrorResponse {
                 error: Some(err.to_string()),
                 detail: None,
             },
+            Error::TooManyPendingQuotes(limit) => ErrorResponse {
+                code: ErrorCode::TooManyPendingQuotes,
+                error: Some(err.to_string()),
+                detail: Some(limit.to_string()),
+            },
+            Error::DuplicateShareHash(ref quote_id) => ErrorResponse {
+                code: ErrorCode::DuplicateShareHash,
+                error: Some(err.to_string()),
+                detail: Some(quote_id.clone()),
+            },
+            Error::BatchQuoteStatusTooManyQuotes { .. }
+            | Error::BatchMintQuoteTooManyQuotes { .. }
+            | Error::BatchMintQuoteAmountTooLarge { .. } => ErrorResponse {
+                code: ErrorCode::BatchTooLarge,
+                error: Some(err.to_string()),
+                detail: None,
+            },
+            Error::RateLimited(_) => ErrorResponse {
+                code: ErrorCode::RateLimited,
+                error: Some(err.to_string()),
+                detail: None,
+            },
             _ => ErrorResponse {
                 code: ErrorCode::Unknown(9999),
                 error: Some(err.to_string()),
@@ -646,6 +839,16 @@ impl From<ErrorResponse> for Error {
             ErrorCode::ClearAuthRequired => Self::ClearAuthRequired,
             ErrorCode::BlindAuthRequired => Self::BlindAuthRequired,
             ErrorCode::DuplicateSignature => Self::DuplicateSignatureError,
+            ErrorCode::TooManyPendingQuotes => Self::TooManyPendingQuotes(
+                err.detail
+                    .as_deref()
+                    .and_then(|limit| limit.parse().ok())
+                    .unwrap_or_default(),
+            ),
+            ErrorCode::DuplicateShareHash => {
+                Self::DuplicateShareHash(err.detail.clone().unwrap_or_default())
+            }
+            ErrorCode::RateLimited => Self::RateLimited(None),
             _ => Self::UnknownErrorResponse(err.to_string()),
         }
     }
@@ -707,6 +910,14 @@ pub enum ErrorCode {
     BlindAuthFailed,
     /// Duplicate signature from same pubkey
     DuplicateSignature,
+    /// Too many pending quotes for the pubkey
+    TooManyPendingQuotes,
+    /// Mining share header hash already quoted
+    DuplicateShareHash,
+    /// Batch request holds more items than allowed
+    BatchTooLarge,
+    /// Too many requests from the client
+    RateLimited,
     /// Unknown error code
     Unknown(u16),
 }
@@ -737,6 +948,10 @@ impl ErrorCode {
             20007 => Self::QuoteExpired,
             20008 => Self::WitnessMissingOrInvalid,
             20009 => Self::DuplicateSignature,
+            20010 => Self::TooManyPendingQuotes,
+            20011 => Self::DuplicateShareHash,
+            20012 => Self::BatchTooLarge,
+            20013 => Self::RateLimited,
             30001 => Self::ClearAuthRequired,
             30002 => Self::ClearAuthFailed,
             31001 => Self::BlindAuthRequired,
@@ -770,6 +985,10 @@ impl ErrorCode {
             Self::QuoteExpired => 20007,
             Self::WitnessMissingOrInvalid => 20008,
             Self::DuplicateSignature => 20009,
+            Self::TooManyPendingQuotes => 20010,
+            Self::DuplicateShareHash => 20011,
+            Self::BatchTooLarge => 20012,
+            Self::RateLimited => 20013,
             Self::ClearAuthRequired => 30001,
             Self::ClearAuthFailed => 30002,
             Self::BlindAuthRequired => 31001,
@@ -54,6 +54,15 @@ pub struct MintQuote {
     /// Keyset ID for mining share quotes
     #[serde(default)]
     pub keyset_id: Option<Id>,
+    /// Quote this quote was converted into
+    #[serde(default)]
+    pub converted_to: Option<QuoteId>,
+    /// Unix time the quote was cancelled at
+    #[serde(default)]
+    pub cancelled_at: Option<u64>,
+    /// Difficulty of the share of a mining share quote, if given
+    #[serde(default)]
+    pub difficulty: Option<u64>,
 }
 
 impl MintQuote {
@@ -92,6 +101,9 @@ impl MintQuote {
             payments,
             issuance,
             keyset_id,
+            converted_to: None,
+            cancelled_at: None,
+            difficulty: None,
         }
     }
 
@@ -168,6 +180,14 @@ impl MintQuote {
     /// Compute quote state
     #[instrument(skip(self))]
     fn compute_quote_state(&self) -> MintQuoteState {
+        if self.converted_to.is_some() {
+            return MintQuoteState::Converted;
+        }
+
+        if self.cancelled_at.is_some() {
+            return MintQuoteState::Cancelled;
+        }
+
         if self.amount_paid == Amount::ZERO && self.amount_issued == Amount::ZERO {
             return MintQuoteState::Unpaid;
         }
@@ -348,7 +368,7 @@ impl From<MintQuote> for MintQuoteBolt11Response<QuoteId> {
     fn from(mint_quote: crate::mint::MintQuote) -> MintQuoteBolt11Response<QuoteId> {
         MintQuoteBolt11Response {
             quote: mint_quote.id.clone(),
-            state: mint_quote.state(),
+            state: mint_quote.state().to_spec(),
             request: mint_quote.request,
             expiry: Some(mint_quote.expiry),
             pubkey: mint_quote.pubkey,
@@ -407,6 +427,7 @@ impl TryFrom<crate::mint::MintQuote> for MintQuoteMiningShareResponse<QuoteId> {
             amount_issued,
             pubkey,
             keyset_id,
+            difficulty,
             ..
         } = mint_quote;
 
@@ -423,6 +444,7 @@ impl TryFrom<crate::mint::MintQuote> for MintQuoteMiningShareResponse<QuoteId> {
             pubkey,
             keyset_id,
             amount_issued,
+            difficulty,
         })
     }
 }
@@ -452,6 +474,7 @@ impl TryFrom<crate::mint::MintQuote> for MintQuoteMiningShareResponse<uuid::Uuid
             amount_issued,
             pubkey,
             keyset_id,
+            difficulty,
             ..
         } = mint_quote;
 
@@ -474,6 +497,7 @@ impl TryFrom<crate::mint::MintQuote> for MintQuoteMiningShareResponse<uuid::Uuid
             pubkey,
             keyset_id,
             amount_issued,
+            difficulty,
         })
     }
 }
@@ -1,14 +1,11 @@
 //! Subscription types and traits
-#[cfg(feature = "mint")]
-use std::str::FromStr;
-
 use cashu::nut17::{self};
 #[cfg(feature = "mint")]
-use cashu::nut17::{Error, Kind, Notification};
+use cashu::nut17::{Error, Notification};
 #[cfg(feature = "mint")]
 use cashu::quote_id::QuoteId;
 #[cfg(feature = "mint")]
-use cashu::{NotificationPayload, PublicKey};
+use cashu::NotificationPayload;
 #[cfg(feature = "mint")]
 use serde::{Deserialize, Serialize};
 
@@ -39,26 +36,26 @@ impl TryFrom<IndexableParams> for Vec<Index<Notification>> {
     fn try_from(params: IndexableParams) -> Result<Self, Self::Error> {
         let sub_id: SubscriptionGlobalId = Default::default();
         let params = params.0;
-        params
-            .filters
-            .into_iter()
-            .map(|filter| {
-                let idx = match params.kind {
-                    Kind::Bolt11MeltQuote => {
-                        Notification::MeltQuoteBolt11(QuoteId::from_str(&filter)?)
-                    }
-                    Kind::Bolt11MintQuote => {
-                        Notification::MintQuoteBolt11(QuoteId::from_str(&filter)?)
-                    }
-                    Kind::ProofState => Notification::ProofState(PublicKey::from_str(&filter)?),
-                    Kind::Bolt12MintQuote => {
-                        Notification::MintQuoteBolt12(QuoteId::from_str(&filter)?)
-                    }
-                };
+        let mut indexes = Vec::with_capacity(params.filters.len());
+        let mut invalid = Vec::new();
+
+        for filter in &params.filters {
+            match Notification::from_filter(params.kind, filter) {
+                Ok(notification) => {
+                    indexes.push(Index::from((notification, params.id.clone(), sub_id)))
+                }
+                Err(_) => invalid.push(filter.clone()),
+            }
+        }
+
+        if !invalid.is_empty() {
+            return Err(Error::InvalidFilters {
+                kind: params.kind,
+                filters: invalid,
+            });
+        }
 
-                Ok(Index::from((idx, params.id.clone(), sub_id)))
-            })
-            .collect::<Result<_, _>>()
+        Ok(indexes)
     }
 }
 
@@ -94,9 +91,15 @@ impl Indexable for NotificationPayload<QuoteId> {
                 ))]
             }
             NotificationPayload::MintQuoteMiningShareResponse(mint_quote) => {
-                vec![Index::from(Notification::MintQuoteMiningShare(
+                let mut indexes = vec![Index::from(Notification::MintQuoteMiningShare(
                     mint_quote.quote.clone(),
-                ))]
+                ))];
+                if let Ok(header_hash) = mint_quote.request.parse() {
+                    indexes.push(Index::from(Notification::MintQuoteMiningShareHash(
+                        header_hash,
+                    )));
+                }
+                indexes
             }
         }
     }
@@ -6,9 +6,10 @@ use std::str::FromStr;
 
 use bitcoin::hashes::{sha256, Hash, HashEngine};
 use cashu::util::hex;
-use cashu::{nut00, PaymentMethod, Proofs, PublicKey};
+use cashu::{nut00, MintRequest, PaymentMethod, PreMintSecrets, Proofs, PublicKey};
 use serde::{Deserialize, Serialize};
 
+use crate::common::ProofInfo;
 use crate::mint_url::MintUrl;
 use crate::nuts::{CurrencyUnit, Id, MeltQuoteState, MintQuoteState, SecretKey};
 use crate::{Amount, Error};
@@ -66,6 +67,18 @@ pub struct MintQuote {
     /// Keyset ID for mining share quotes
     #[serde(default)]
     pub keyset_id: Option<Id>,
+    /// Quote this quote was converted from
+    #[serde(default)]
+    pub converted_from: Option<String>,
+    /// Keyset the premint secrets of an in-flight mint were derived for
+    #[serde(default)]
+    pub premint_keyset_id: Option<Id>,
+    /// Counter the premint secrets of an in-flight mint were derived from
+    #[serde(default)]
+    pub premint_counter: Option<u32>,
+    /// Output amounts of the premint secrets of an in-flight mint
+    #[serde(default)]
+    pub premint_amounts: Option<Vec<Amount>>,
 }
 
 /// Melt Quote Info
@@ -118,6 +131,10 @@ impl MintQuote {
             amount_issued: Amount::ZERO,
             amount_paid: Amount::ZERO,
             keyset_id: None,
+            converted_from: None,
+            premint_keyset_id: None,
+            premint_counter: None,
+            premint_amounts: None,
         }
     }
 
@@ -185,6 +202,178 @@ impl SendKind {
     }
 }
 
+/// Progress of a seed restore in a keyset
+///
+/// Stored after every scanned batch so an interrupted restore resumes at
+/// `next_counter` instead of counter zero.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RestoreProgress {
+    /// Keyset id
+    pub keyset_id: Id,
+    /// Next counter to scan
+    pub next_counter: u32,
+    /// Counters scanned since the last signature found
+    pub gap: u32,
+    /// Signatures found
+    pub signatures_found: u32,
+    /// Unspent amount recovered
+    pub amount_recovered: Amount,
+    /// Whether the scan of the keyset finished
+    pub complete: bool,
+}
+
+impl RestoreProgress {
+    /// Create [`RestoreProgress`] for a keyset not scanned yet
+    pub fn new(keyset_id: Id) -> Self {
+        Self {
+            keyset_id,
+            next_counter: 0,
+            gap: 0,
+            signatures_found: 0,
+            amount_recovered: Amount::ZERO,
+            complete: false,
+        }
+    }
+}
+
+/// Reason a notification could not be applied by the wallet
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum DeadLetterReason {
+    /// The notified quote is not stored by the wallet
+    UnknownQuote,
+    /// The notification references a keyset the wallet does not know
+    UnknownKeyset,
+    /// The notification could not be parsed
+    InvalidPayload,
+}
+
+impl std::fmt::Display for DeadLetterReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DeadLetterReason::UnknownQuote => write!(f, "UnknownQuote"),
+            DeadLetterReason::UnknownKeyset => write!(f, "UnknownKeyset"),
+            DeadLetterReason::InvalidPayload => write!(f, "InvalidPayload"),
+        }
+    }
+}
+
+impl FromStr for DeadLetterReason {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "UnknownQuote" => Ok(Self::UnknownQuote),
+            "UnknownKeyset" => Ok(Self::UnknownKeyset),
+            "InvalidPayload" => Ok(Self::InvalidPayload),
+            _ => Err(Error::InvalidDeadLetterReason),
+        }
+    }
+}
+
+/// Notification the wallet received but could not apply
+///
+/// Kept so it can be inspected and applied again once the wallet knows the
+/// quote or keyset it references.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeadLetter {
+    /// Position in the dead letter store, increasing with arrival
+    ///
+    /// Assigned by the store when the dead letter is added.
+    pub id: u64,
+    /// Mint the notification came from
+    pub mint_url: MintUrl,
+    /// Subscription the notification arrived on
+    pub sub_id: Option<String>,
+    /// Raw notification payload
+    pub payload: String,
+    /// Why the notification could not be applied
+    pub reason: DeadLetterReason,
+    /// Unix timestamp the notification arrived at
+    pub timestamp: u64,
+}
+
+/// Proofs, quotes, transactions and keyset counters of a wallet at a point in
+/// time
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WalletSnapshotContent {
+    /// Proofs of the wallet's mint and unit
+    pub proofs: Vec<ProofInfo>,
+    /// Mint quotes of the wallet's mint and unit
+    pub mint_quotes: Vec<MintQuote>,
+    /// Melt quotes of the wallet's unit
+    pub melt_quotes: Vec<MeltQuote>,
+    /// Transactions of the wallet's mint and unit
+    pub transactions: Vec<Transaction>,
+    /// Counters of the wallet's keysets
+    pub counters: HashMap<Id, u32>,
+}
+
+/// Snapshot of a wallet taken before risky maintenance, to roll back to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WalletSnapshot {
+    /// Snapshot id, increasing with creation
+    ///
+    /// Assigned by the store when the snapshot is added.
+    pub id: u64,
+    /// Mint of the wallet
+    pub mint_url: MintUrl,
+    /// Unit of the wallet
+    pub unit: CurrencyUnit,
+    /// Label given when the snapshot was created
+    pub label: String,
+    /// Unix timestamp the snapshot was created at
+    pub created_at: u64,
+    /// State of the wallet
+    pub content: WalletSnapshotContent,
+}
+
+/// Mint request that could not reach the mint
+///
+/// Kept with the secrets of its outputs so it can be sent again once the mint
+/// is reachable, and the proofs of the signatures it returns constructed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PendingMintOperation {
+    /// Id of the operation, see [`PendingMintOperation::outputs_id`]
+    pub id: String,
+    /// Quote minted
+    pub quote_id: String,
+    /// Mint Url
+    pub mint_url: MintUrl,
+    /// Payment method of the quote
+    pub payment_method: PaymentMethod,
+    /// Unit of the proofs
+    pub unit: CurrencyUnit,
+    /// Keyset of the outputs
+    pub keyset_id: Id,
+    /// Mint request as sent, signed if the quote requires it
+    pub request: MintRequest<String>,
+    /// Secrets the outputs of the request were blinded from
+    pub premint_secrets: PreMintSecrets,
+    /// Unix timestamp the quote expires at, 0 if it does not
+    pub expiry: u64,
+    /// Unix timestamp the operation was queued at
+    pub created_time: u64,
+}
+
+impl PendingMintOperation {
+    /// Id of the operation minting `outputs`
+    ///
+    /// Hash of the blinded secrets of the outputs, so part mints of the same
+    /// quote are kept apart.
+    pub fn outputs_id(outputs: &[nut00::BlindedMessage]) -> String {
+        let mut hasher = sha256::Hash::engine();
+        for output in outputs {
+            hasher.input(&output.blinded_secret.to_bytes());
+        }
+        hex::encode(sha256::Hash::from_engine(hasher).to_byte_array())
+    }
+
+    /// Whether the quote expired at `now`
+    pub fn is_expired(&self, now: u64) -> bool {
+        self.expiry != 0 && self.expiry <= now
+    }
+}
+
 /// Wallet Transaction
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct Transaction {
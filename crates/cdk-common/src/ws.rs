@@ -42,6 +42,9 @@ pub type WsErrorBody = nut17::ws::WsErrorBody;
 /// Either a websocket message or a response
 pub type WsMessageOrResponse = nut17::ws::WsMessageOrResponse<SubId>;
 
+/// Parameters of the draining notification
+pub type WsDraining = nut17::ws::WsDraining;
+
 /// Inner content of a notification with generic payload type
 pub type NotificationInner<T> = nut17::ws::NotificationInner<T, SubId>;
 
@@ -79,3 +82,13 @@ pub fn notification_to_ws_message(notification: NotificationInner<QuoteId>) -> W
         params: notification_uuid_to_notification_string(notification),
     })
 }
+
+#[cfg(feature = "mint")]
+/// Creates the websocket message telling a client the server is draining connections
+pub fn draining_ws_message(reconnect_after_ms: u64) -> WsMessageOrResponse {
+    nut17::ws::WsMessageOrResponse::Draining(nut17::ws::WsNotification {
+        jsonrpc: JSON_RPC_VERSION.to_owned(),
+        method: nut17::ws::DRAINING_METHOD.to_string(),
+        params: WsDraining { reconnect_after_ms },
+    })
+}
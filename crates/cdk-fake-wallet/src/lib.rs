@@ -256,6 +256,9 @@ pub struct FakeInvoiceDescription {
     pub pay_err: bool,
     /// Should check failure
     pub check_err: bool,
+    /// Hex encoded preimage to return for a successful payment
+    #[serde(default)]
+    pub payment_preimage: Option<String>,
 }
 
 impl Default for FakeInvoiceDescription {
@@ -265,6 +268,7 @@ impl Default for FakeInvoiceDescription {
             check_payment_state: MeltQuoteState::Paid,
             pay_err: false,
             check_err: false,
+            payment_preimage: None,
         }
     }
 }
@@ -424,6 +428,11 @@ impl MintPayment for FakeWallet {
 
                 payment_states.insert(payment_hash.clone(), checkout_going_status);
 
+                let payment_preimage = status
+                    .as_ref()
+                    .and_then(|s| s.payment_preimage.clone())
+                    .unwrap_or_default();
+
                 if let Some(description) = status {
                     if description.check_err {
                         let mut fail = self.failed_payment_check.lock().await;
@@ -445,7 +454,7 @@ impl MintPayment for FakeWallet {
                 let total_spent = to_unit(amount_msat, &CurrencyUnit::Msat, unit)?;
 
                 Ok(MakePaymentResponse {
-                    payment_proof: Some("".to_string()),
+                    payment_proof: Some(payment_preimage),
                     payment_lookup_id: PaymentIdentifier::PaymentHash(
                         *bolt11.payment_hash().as_ref(),
                     ),
@@ -650,6 +659,32 @@ impl MintPayment for FakeWallet {
 /// Create fake invoice
 #[instrument]
 pub fn create_fake_invoice(amount_msat: u64, description: String) -> Bolt11Invoice {
+    use bitcoin::secp256k1::rand::rngs::OsRng;
+    use bitcoin::secp256k1::rand::Rng;
+    let mut rng = OsRng;
+    let mut random_bytes = [0u8; 32];
+    rng.fill(&mut random_bytes);
+
+    let payment_hash = sha256::Hash::from_slice(&random_bytes).unwrap();
+
+    build_fake_invoice(amount_msat, description, payment_hash)
+}
+
+/// Create fake invoice whose payment hash commits to `preimage`
+#[instrument]
+pub fn create_fake_invoice_with_preimage(
+    amount_msat: u64,
+    description: String,
+    preimage: [u8; 32],
+) -> Bolt11Invoice {
+    build_fake_invoice(amount_msat, description, sha256::Hash::hash(&preimage))
+}
+
+fn build_fake_invoice(
+    amount_msat: u64,
+    description: String,
+    payment_hash: sha256::Hash,
+) -> Bolt11Invoice {
     let private_key = SecretKey::from_slice(
         &[
             0xe1, 0x26, 0xf6, 0x8f, 0x7e, 0xaf, 0xcc, 0x8b, 0x74, 0xf5, 0x4d, 0x26, 0x9f, 0xe2,
@@ -659,13 +694,6 @@ pub fn create_fake_invoice(amount_msat: u64, description: String) -> Bolt11Invoi
     )
     .unwrap();
 
-    use bitcoin::secp256k1::rand::rngs::OsRng;
-    use bitcoin::secp256k1::rand::Rng;
-    let mut rng = OsRng;
-    let mut random_bytes = [0u8; 32];
-    rng.fill(&mut random_bytes);
-
-    let payment_hash = sha256::Hash::from_slice(&random_bytes).unwrap();
     let payment_secret = PaymentSecret([42u8; 32]);
 
     InvoiceBuilder::new(Currency::Bitcoin)
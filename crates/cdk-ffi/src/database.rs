@@ -127,6 +127,62 @@ pub trait WalletDatabase: Send + Sync {
     /// Increment Keyset counter
     async fn increment_keyset_counter(&self, keyset_id: Id, count: u32) -> Result<u32, FfiError>;
 
+    // Restore Progress Management
+    /// Add or replace the restore progress of a keyset
+    async fn add_restore_progress(&self, progress: RestoreProgress) -> Result<(), FfiError>;
+
+    /// Get the restore progress of a keyset
+    async fn get_restore_progress(
+        &self,
+        keyset_id: Id,
+    ) -> Result<Option<RestoreProgress>, FfiError>;
+
+    /// Remove the restore progress of a keyset
+    async fn remove_restore_progress(&self, keyset_id: Id) -> Result<(), FfiError>;
+
+    // Dead Letter Management
+    /// Add dead letter to storage, the store assigns its id
+    async fn add_dead_letter(&self, dead_letter: DeadLetter) -> Result<(), FfiError>;
+
+    /// Get dead letters from storage, optionally of one mint, oldest first
+    async fn get_dead_letters(
+        &self,
+        mint_url: Option<MintUrl>,
+    ) -> Result<Vec<DeadLetter>, FfiError>;
+
+    /// Remove dead letter from storage
+    async fn remove_dead_letter(&self, id: u64) -> Result<(), FfiError>;
+
+    // Pending Mint Operation Management
+    /// Add pending mint operation to storage, replacing one with the same id
+    async fn add_pending_mint_operation(
+        &self,
+        operation: PendingMintOperation,
+    ) -> Result<(), FfiError>;
+
+    /// Get pending mint operations from storage, oldest first
+    async fn get_pending_mint_operations(&self) -> Result<Vec<PendingMintOperation>, FfiError>;
+
+    /// Remove pending mint operation from storage
+    async fn remove_pending_mint_operation(&self, id: String) -> Result<(), FfiError>;
+
+    // Wallet Snapshot Management
+    /// Add wallet snapshot to storage and return its id, the store assigns it
+    async fn add_wallet_snapshot(&self, snapshot: WalletSnapshot) -> Result<u64, FfiError>;
+
+    /// Get wallet snapshot from storage
+    async fn get_wallet_snapshot(&self, id: u64) -> Result<Option<WalletSnapshot>, FfiError>;
+
+    /// Get the wallet snapshots of a mint and unit from storage, oldest first
+    async fn get_wallet_snapshots(
+        &self,
+        mint_url: MintUrl,
+        unit: CurrencyUnit,
+    ) -> Result<Vec<WalletSnapshot>, FfiError>;
+
+    /// Remove wallet snapshot from storage
+    async fn remove_wallet_snapshot(&self, id: u64) -> Result<(), FfiError>;
+
     // Transaction Management
     /// Add transaction to storage
     async fn add_transaction(&self, transaction: Transaction) -> Result<(), FfiError>;
@@ -475,6 +531,7 @@ impl CdkWalletDatabase for WalletDatabaseBridge {
                                 cdk_common::database::Error::Database(e.to_string().into())
                             })?,
                         unit: info.unit.into(),
+                        source: info.source.into(),
                     })
                 })
                 .collect();
@@ -525,6 +582,168 @@ impl CdkWalletDatabase for WalletDatabaseBridge {
             .map_err(|e| cdk_common::database::Error::Database(e.to_string().into()))
     }
 
+    // Restore Progress Management
+    async fn add_restore_progress(
+        &self,
+        progress: cdk_common::wallet::RestoreProgress,
+    ) -> Result<(), Self::Err> {
+        self.ffi_db
+            .add_restore_progress(progress.into())
+            .await
+            .map_err(|e| cdk_common::database::Error::Database(e.to_string().into()))
+    }
+
+    async fn get_restore_progress(
+        &self,
+        keyset_id: &cdk_common::nuts::Id,
+    ) -> Result<Option<cdk_common::wallet::RestoreProgress>, Self::Err> {
+        let ffi_id = (*keyset_id).into();
+        let result = self
+            .ffi_db
+            .get_restore_progress(ffi_id)
+            .await
+            .map_err(|e| cdk_common::database::Error::Database(e.to_string().into()))?;
+        Ok(result.map(Into::into))
+    }
+
+    async fn remove_restore_progress(
+        &self,
+        keyset_id: &cdk_common::nuts::Id,
+    ) -> Result<(), Self::Err> {
+        let ffi_id = (*keyset_id).into();
+        self.ffi_db
+            .remove_restore_progress(ffi_id)
+            .await
+            .map_err(|e| cdk_common::database::Error::Database(e.to_string().into()))
+    }
+
+    // Dead Letter Management
+    async fn add_dead_letter(
+        &self,
+        dead_letter: cdk_common::wallet::DeadLetter,
+    ) -> Result<(), Self::Err> {
+        self.ffi_db
+            .add_dead_letter(dead_letter.into())
+            .await
+            .map_err(|e| cdk_common::database::Error::Database(e.to_string().into()))
+    }
+
+    async fn get_dead_letters(
+        &self,
+        mint_url: Option<cdk_common::mint_url::MintUrl>,
+    ) -> Result<Vec<cdk_common::wallet::DeadLetter>, Self::Err> {
+        let result = self
+            .ffi_db
+            .get_dead_letters(mint_url.map(Into::into))
+            .await
+            .map_err(|e| cdk_common::database::Error::Database(e.to_string().into()))?;
+
+        result
+            .into_iter()
+            .map(|dead_letter| dead_letter.try_into())
+            .collect::<Result<Vec<_>, FfiError>>()
+            .map_err(|e| cdk_common::database::Error::Database(e.to_string().into()))
+    }
+
+    async fn remove_dead_letter(&self, id: u64) -> Result<(), Self::Err> {
+        self.ffi_db
+            .remove_dead_letter(id)
+            .await
+            .map_err(|e| cdk_common::database::Error::Database(e.to_string().into()))
+    }
+
+    // Pending Mint Operation Management
+    async fn add_pending_mint_operation(
+        &self,
+        operation: cdk_common::wallet::PendingMintOperation,
+    ) -> Result<(), Self::Err> {
+        let ffi_operation = operation
+            .try_into()
+            .map_err(|e: FfiError| cdk_common::database::Error::Database(e.to_string().into()))?;
+        self.ffi_db
+            .add_pending_mint_operation(ffi_operation)
+            .await
+            .map_err(|e| cdk_common::database::Error::Database(e.to_string().into()))
+    }
+
+    async fn get_pending_mint_operations(
+        &self,
+    ) -> Result<Vec<cdk_common::wallet::PendingMintOperation>, Self::Err> {
+        let result = self
+            .ffi_db
+            .get_pending_mint_operations()
+            .await
+            .map_err(|e| cdk_common::database::Error::Database(e.to_string().into()))?;
+
+        result
+            .into_iter()
+            .map(|operation| operation.try_into())
+            .collect::<Result<Vec<_>, FfiError>>()
+            .map_err(|e| cdk_common::database::Error::Database(e.to_string().into()))
+    }
+
+    async fn remove_pending_mint_operation(&self, id: &str) -> Result<(), Self::Err> {
+        self.ffi_db
+            .remove_pending_mint_operation(id.to_string())
+            .await
+            .map_err(|e| cdk_common::database::Error::Database(e.to_string().into()))
+    }
+
+    // Wallet Snapshot Management
+    async fn add_wallet_snapshot(
+        &self,
+        snapshot: cdk_common::wallet::WalletSnapshot,
+    ) -> Result<u64, Self::Err> {
+        let ffi_snapshot = snapshot
+            .try_into()
+            .map_err(|e: FfiError| cdk_common::database::Error::Database(e.to_string().into()))?;
+        self.ffi_db
+            .add_wallet_snapshot(ffi_snapshot)
+            .await
+            .map_err(|e| cdk_common::database::Error::Database(e.to_string().into()))
+    }
+
+    async fn get_wallet_snapshot(
+        &self,
+        id: u64,
+    ) -> Result<Option<cdk_common::wallet::WalletSnapshot>, Self::Err> {
+        let result = self
+            .ffi_db
+            .get_wallet_snapshot(id)
+            .await
+            .map_err(|e| cdk_common::database::Error::Database(e.to_string().into()))?;
+
+        result
+            .map(|snapshot| snapshot.try_into())
+            .transpose()
+            .map_err(|e: FfiError| cdk_common::database::Error::Database(e.to_string().into()))
+    }
+
+    async fn get_wallet_snapshots(
+        &self,
+        mint_url: cdk_common::mint_url::MintUrl,
+        unit: cdk_common::nuts::CurrencyUnit,
+    ) -> Result<Vec<cdk_common::wallet::WalletSnapshot>, Self::Err> {
+        let result = self
+            .ffi_db
+            .get_wallet_snapshots(mint_url.into(), unit.into())
+            .await
+            .map_err(|e| cdk_common::database::Error::Database(e.to_string().into()))?;
+
+        result
+            .into_iter()
+            .map(|snapshot| snapshot.try_into())
+            .collect::<Result<Vec<_>, FfiError>>()
+            .map_err(|e| cdk_common::database::Error::Database(e.to_string().into()))
+    }
+
+    async fn remove_wallet_snapshot(&self, id: u64) -> Result<(), Self::Err> {
+        self.ffi_db
+            .remove_wallet_snapshot(id)
+            .await
+            .map_err(|e| cdk_common::database::Error::Database(e.to_string().into()))
+    }
+
     // Transaction Management
     async fn add_transaction(
         &self,
@@ -838,6 +1057,7 @@ impl WalletDatabase for WalletSqliteDatabase {
                         .map(|sc| sc.try_into())
                         .transpose()?,
                     unit: info.unit.into(),
+                    source: info.source.into(),
                 })
             })
             .collect();
@@ -922,6 +1142,127 @@ impl WalletDatabase for WalletSqliteDatabase {
             .map_err(|e| FfiError::Database { msg: e.to_string() })
     }
 
+    // Restore Progress Management
+    async fn add_restore_progress(&self, progress: RestoreProgress) -> Result<(), FfiError> {
+        self.inner
+            .add_restore_progress(progress.into())
+            .await
+            .map_err(|e| FfiError::Database { msg: e.to_string() })
+    }
+
+    async fn get_restore_progress(
+        &self,
+        keyset_id: Id,
+    ) -> Result<Option<RestoreProgress>, FfiError> {
+        let cdk_id = keyset_id.into();
+        let result = self
+            .inner
+            .get_restore_progress(&cdk_id)
+            .await
+            .map_err(|e| FfiError::Database { msg: e.to_string() })?;
+        Ok(result.map(Into::into))
+    }
+
+    async fn remove_restore_progress(&self, keyset_id: Id) -> Result<(), FfiError> {
+        let cdk_id = keyset_id.into();
+        self.inner
+            .remove_restore_progress(&cdk_id)
+            .await
+            .map_err(|e| FfiError::Database { msg: e.to_string() })
+    }
+
+    // Dead Letter Management
+    async fn add_dead_letter(&self, dead_letter: DeadLetter) -> Result<(), FfiError> {
+        self.inner
+            .add_dead_letter(dead_letter.try_into()?)
+            .await
+            .map_err(|e| FfiError::Database { msg: e.to_string() })
+    }
+
+    async fn get_dead_letters(
+        &self,
+        mint_url: Option<MintUrl>,
+    ) -> Result<Vec<DeadLetter>, FfiError> {
+        let cdk_mint_url = mint_url.map(|u| u.try_into()).transpose()?;
+        let result = self
+            .inner
+            .get_dead_letters(cdk_mint_url)
+            .await
+            .map_err(|e| FfiError::Database { msg: e.to_string() })?;
+        Ok(result.into_iter().map(Into::into).collect())
+    }
+
+    async fn remove_dead_letter(&self, id: u64) -> Result<(), FfiError> {
+        self.inner
+            .remove_dead_letter(id)
+            .await
+            .map_err(|e| FfiError::Database { msg: e.to_string() })
+    }
+
+    // Pending Mint Operation Management
+    async fn add_pending_mint_operation(
+        &self,
+        operation: PendingMintOperation,
+    ) -> Result<(), FfiError> {
+        self.inner
+            .add_pending_mint_operation(operation.try_into()?)
+            .await
+            .map_err(|e| FfiError::Database { msg: e.to_string() })
+    }
+
+    async fn get_pending_mint_operations(&self) -> Result<Vec<PendingMintOperation>, FfiError> {
+        let result = self
+            .inner
+            .get_pending_mint_operations()
+            .await
+            .map_err(|e| FfiError::Database { msg: e.to_string() })?;
+        result.into_iter().map(TryInto::try_into).collect()
+    }
+
+    async fn remove_pending_mint_operation(&self, id: String) -> Result<(), FfiError> {
+        self.inner
+            .remove_pending_mint_operation(&id)
+            .await
+            .map_err(|e| FfiError::Database { msg: e.to_string() })
+    }
+
+    // Wallet Snapshot Management
+    async fn add_wallet_snapshot(&self, snapshot: WalletSnapshot) -> Result<u64, FfiError> {
+        self.inner
+            .add_wallet_snapshot(snapshot.try_into()?)
+            .await
+            .map_err(|e| FfiError::Database { msg: e.to_string() })
+    }
+
+    async fn get_wallet_snapshot(&self, id: u64) -> Result<Option<WalletSnapshot>, FfiError> {
+        let result = self
+            .inner
+            .get_wallet_snapshot(id)
+            .await
+            .map_err(|e| FfiError::Database { msg: e.to_string() })?;
+        result.map(TryInto::try_into).transpose()
+    }
+
+    async fn get_wallet_snapshots(
+        &self,
+        mint_url: MintUrl,
+        unit: CurrencyUnit,
+    ) -> Result<Vec<WalletSnapshot>, FfiError> {
+        let result = self
+            .inner
+            .get_wallet_snapshots(mint_url.try_into()?, unit.into())
+            .await
+            .map_err(|e| FfiError::Database { msg: e.to_string() })?;
+        result.into_iter().map(TryInto::try_into).collect()
+    }
+
+    async fn remove_wallet_snapshot(&self, id: u64) -> Result<(), FfiError> {
+        self.inner
+            .remove_wallet_snapshot(id)
+            .await
+            .map_err(|e| FfiError::Database { msg: e.to_string() })
+    }
+
     // Transaction Management
     async fn add_transaction(&self, transaction: Transaction) -> Result<(), FfiError> {
         // Convert FFI Transaction to CDK Transaction using TryFrom
@@ -4,9 +4,10 @@ use std::sync::Arc;
 use cdk_sqlite::wallet::WalletSqliteDatabase as CdkWalletSqliteDatabase;
 
 use crate::{
-    CurrencyUnit, FfiError, Id, KeySet, KeySetInfo, Keys, MeltQuote, MintInfo, MintQuote, MintUrl,
-    ProofInfo, ProofState, PublicKey, SpendingConditions, Transaction, TransactionDirection,
-    TransactionId, WalletDatabase,
+    CurrencyUnit, DeadLetter, FfiError, Id, KeySet, KeySetInfo, Keys, MeltQuote, MintInfo,
+    MintQuote, MintUrl, PendingMintOperation, ProofInfo, ProofState, PublicKey, RestoreProgress,
+    SpendingConditions, Transaction, TransactionDirection, TransactionId, WalletDatabase,
+    WalletSnapshot,
 };
 
 /// FFI-compatible WalletSqliteDatabase implementation that implements the WalletDatabase trait
@@ -281,6 +282,7 @@ impl WalletDatabase for WalletSqliteDatabase {
                         .map(|sc| sc.try_into())
                         .transpose()?,
                     unit: info.unit.into(),
+                    source: info.source.into(),
                 })
             })
             .collect();
@@ -349,6 +351,127 @@ impl WalletDatabase for WalletSqliteDatabase {
             .map_err(|e| FfiError::Database { msg: e.to_string() })
     }
 
+    // Restore Progress Management
+    async fn add_restore_progress(&self, progress: RestoreProgress) -> Result<(), FfiError> {
+        self.inner
+            .add_restore_progress(progress.into())
+            .await
+            .map_err(|e| FfiError::Database { msg: e.to_string() })
+    }
+
+    async fn get_restore_progress(
+        &self,
+        keyset_id: Id,
+    ) -> Result<Option<RestoreProgress>, FfiError> {
+        let cdk_id = keyset_id.into();
+        let result = self
+            .inner
+            .get_restore_progress(&cdk_id)
+            .await
+            .map_err(|e| FfiError::Database { msg: e.to_string() })?;
+        Ok(result.map(Into::into))
+    }
+
+    async fn remove_restore_progress(&self, keyset_id: Id) -> Result<(), FfiError> {
+        let cdk_id = keyset_id.into();
+        self.inner
+            .remove_restore_progress(&cdk_id)
+            .await
+            .map_err(|e| FfiError::Database { msg: e.to_string() })
+    }
+
+    // Dead Letter Management
+    async fn add_dead_letter(&self, dead_letter: DeadLetter) -> Result<(), FfiError> {
+        self.inner
+            .add_dead_letter(dead_letter.try_into()?)
+            .await
+            .map_err(|e| FfiError::Database { msg: e.to_string() })
+    }
+
+    async fn get_dead_letters(
+        &self,
+        mint_url: Option<MintUrl>,
+    ) -> Result<Vec<DeadLetter>, FfiError> {
+        let cdk_mint_url = mint_url.map(|u| u.try_into()).transpose()?;
+        let result = self
+            .inner
+            .get_dead_letters(cdk_mint_url)
+            .await
+            .map_err(|e| FfiError::Database { msg: e.to_string() })?;
+        Ok(result.into_iter().map(Into::into).collect())
+    }
+
+    async fn remove_dead_letter(&self, id: u64) -> Result<(), FfiError> {
+        self.inner
+            .remove_dead_letter(id)
+            .await
+            .map_err(|e| FfiError::Database { msg: e.to_string() })
+    }
+
+    // Pending Mint Operation Management
+    async fn add_pending_mint_operation(
+        &self,
+        operation: PendingMintOperation,
+    ) -> Result<(), FfiError> {
+        self.inner
+            .add_pending_mint_operation(operation.try_into()?)
+            .await
+            .map_err(|e| FfiError::Database { msg: e.to_string() })
+    }
+
+    async fn get_pending_mint_operations(&self) -> Result<Vec<PendingMintOperation>, FfiError> {
+        let result = self
+            .inner
+            .get_pending_mint_operations()
+            .await
+            .map_err(|e| FfiError::Database { msg: e.to_string() })?;
+        result.into_iter().map(TryInto::try_into).collect()
+    }
+
+    async fn remove_pending_mint_operation(&self, id: String) -> Result<(), FfiError> {
+        self.inner
+            .remove_pending_mint_operation(&id)
+            .await
+            .map_err(|e| FfiError::Database { msg: e.to_string() })
+    }
+
+    // Wallet Snapshot Management
+    async fn add_wallet_snapshot(&self, snapshot: WalletSnapshot) -> Result<u64, FfiError> {
+        self.inner
+            .add_wallet_snapshot(snapshot.try_into()?)
+            .await
+            .map_err(|e| FfiError::Database { msg: e.to_string() })
+    }
+
+    async fn get_wallet_snapshot(&self, id: u64) -> Result<Option<WalletSnapshot>, FfiError> {
+        let result = self
+            .inner
+            .get_wallet_snapshot(id)
+            .await
+            .map_err(|e| FfiError::Database { msg: e.to_string() })?;
+        result.map(TryInto::try_into).transpose()
+    }
+
+    async fn get_wallet_snapshots(
+        &self,
+        mint_url: MintUrl,
+        unit: CurrencyUnit,
+    ) -> Result<Vec<WalletSnapshot>, FfiError> {
+        let result = self
+            .inner
+            .get_wallet_snapshots(mint_url.try_into()?, unit.into())
+            .await
+            .map_err(|e| FfiError::Database { msg: e.to_string() })?;
+        result.into_iter().map(TryInto::try_into).collect()
+    }
+
+    async fn remove_wallet_snapshot(&self, id: u64) -> Result<(), FfiError> {
+        self.inner
+            .remove_wallet_snapshot(id)
+            .await
+            .map_err(|e| FfiError::Database { msg: e.to_string() })
+    }
+
     // Transaction Management
     async fn add_transaction(&self, transaction: Transaction) -> Result<(), FfiError> {
         // Convert FFI Transaction to CDK Transaction using TryFrom
@@ -103,6 +103,7 @@ pub enum CurrencyUnit {
     Usd,
     Eur,
     Auth,
+    Hash,
     Custom { unit: String },
 }
 
@@ -114,6 +115,7 @@ impl From<CdkCurrencyUnit> for CurrencyUnit {
             CdkCurrencyUnit::Usd => CurrencyUnit::Usd,
             CdkCurrencyUnit::Eur => CurrencyUnit::Eur,
             CdkCurrencyUnit::Auth => CurrencyUnit::Auth,
+            CdkCurrencyUnit::Hash => CurrencyUnit::Hash,
             CdkCurrencyUnit::Custom(s) => CurrencyUnit::Custom { unit: s },
             _ => CurrencyUnit::Sat, // Default for unknown units
         }
@@ -128,7 +130,8 @@ impl From<CurrencyUnit> for CdkCurrencyUnit {
             CurrencyUnit::Usd => CdkCurrencyUnit::Usd,
             CurrencyUnit::Eur => CdkCurrencyUnit::Eur,
             CurrencyUnit::Auth => CdkCurrencyUnit::Auth,
-            CurrencyUnit::Custom { unit } => CdkCurrencyUnit::Custom(unit),
+            CurrencyUnit::Hash => CdkCurrencyUnit::Hash,
+            CurrencyUnit::Custom { unit } => CdkCurrencyUnit::Custom(unit).canonical(),
         }
     }
 }
@@ -784,6 +787,14 @@ pub struct MintQuote {
     pub secret_key: Option<String>,
     /// Keyset ID (optional)
     pub keyset_id: Option<String>,
+    /// Quote this quote was converted from (optional)
+    pub converted_from: Option<String>,
+    /// Keyset of the in-flight premint secrets (optional)
+    pub premint_keyset_id: Option<String>,
+    /// Counter of the in-flight premint secrets (optional)
+    pub premint_counter: Option<u32>,
+    /// Output amounts of the in-flight premint secrets (optional)
+    pub premint_amounts: Option<Vec<Amount>>,
 }
 
 impl From<cdk::wallet::MintQuote> for MintQuote {
@@ -801,6 +812,12 @@ impl From<cdk::wallet::MintQuote> for MintQuote {
             payment_method: quote.payment_method.into(),
             secret_key: quote.secret_key.map(|sk| sk.to_secret_hex()),
             keyset_id: quote.keyset_id.map(|id| id.to_string()),
+            converted_from: quote.converted_from,
+            premint_keyset_id: quote.premint_keyset_id.map(|id| id.to_string()),
+            premint_counter: quote.premint_counter,
+            premint_amounts: quote
+                .premint_amounts
+                .map(|amounts| amounts.into_iter().map(Into::into).collect()),
         }
     }
 }
@@ -832,6 +849,16 @@ impl TryFrom<MintQuote> for cdk::wallet::MintQuote {
                 .map(|id_str| id_str.parse::<cdk_common::Id>())
                 .transpose()
                 .map_err(|e| FfiError::InvalidCryptographicKey { msg: e.to_string() })?,
+            converted_from: quote.converted_from,
+            premint_keyset_id: quote
+                .premint_keyset_id
+                .map(|id_str| id_str.parse::<cdk_common::Id>())
+                .transpose()
+                .map_err(|e| FfiError::InvalidCryptographicKey { msg: e.to_string() })?,
+            premint_counter: quote.premint_counter,
+            premint_amounts: quote
+                .premint_amounts
+                .map(|amounts| amounts.into_iter().map(Into::into).collect()),
         })
     }
 }
@@ -1054,6 +1081,7 @@ impl From<PaymentMethod> for cdk::nuts::PaymentMethod {
         match method {
             PaymentMethod::Bolt11 => Self::Bolt11,
             PaymentMethod::Bolt12 => Self::Bolt12,
+            PaymentMethod::Custom { method } if method == "mining_share" => Self::MiningShare,
             PaymentMethod::Custom { method } => Self::Custom(method),
         }
     }
@@ -1143,6 +1171,8 @@ pub enum QuoteState {
     Paid,
     Pending,
     Issued,
+    Converted,
+    Cancelled,
 }
 
 impl From<cdk::nuts::nut05::QuoteState> for QuoteState {
@@ -1164,6 +1194,8 @@ impl From<QuoteState> for cdk::nuts::nut05::QuoteState {
             QuoteState::Paid => cdk::nuts::nut05::QuoteState::Paid,
             QuoteState::Pending => cdk::nuts::nut05::QuoteState::Pending,
             QuoteState::Issued => cdk::nuts::nut05::QuoteState::Paid, // Map issued to paid for melt quotes
+            QuoteState::Converted => cdk::nuts::nut05::QuoteState::Unknown,
+            QuoteState::Cancelled => cdk::nuts::nut05::QuoteState::Unknown,
         }
     }
 }
@@ -1174,6 +1206,8 @@ impl From<cdk::nuts::MintQuoteState> for QuoteState {
             cdk::nuts::MintQuoteState::Unpaid => QuoteState::Unpaid,
             cdk::nuts::MintQuoteState::Paid => QuoteState::Paid,
             cdk::nuts::MintQuoteState::Issued => QuoteState::Issued,
+            cdk::nuts::MintQuoteState::Converted => QuoteState::Converted,
+            cdk::nuts::MintQuoteState::Cancelled => QuoteState::Cancelled,
         }
     }
 }
@@ -1185,6 +1219,8 @@ impl From<QuoteState> for cdk::nuts::MintQuoteState {
             QuoteState::Paid => cdk::nuts::MintQuoteState::Paid,
             QuoteState::Issued => cdk::nuts::MintQuoteState::Issued,
             QuoteState::Pending => cdk::nuts::MintQuoteState::Paid, // Map pending to paid
+            QuoteState::Converted => cdk::nuts::MintQuoteState::Converted,
+            QuoteState::Cancelled => cdk::nuts::MintQuoteState::Cancelled,
         }
     }
 }
@@ -2126,6 +2162,8 @@ pub enum SubscriptionKind {
     Bolt12MintQuote,
     /// Proof State
     ProofState,
+    /// Mining Share Mint Quote
+    MiningShareMintQuote,
 }
 
 impl From<SubscriptionKind> for cdk::nuts::nut17::Kind {
@@ -2135,6 +2173,7 @@ impl From<SubscriptionKind> for cdk::nuts::nut17::Kind {
             SubscriptionKind::Bolt11MintQuote => cdk::nuts::nut17::Kind::Bolt11MintQuote,
             SubscriptionKind::Bolt12MintQuote => cdk::nuts::nut17::Kind::Bolt12MintQuote,
             SubscriptionKind::ProofState => cdk::nuts::nut17::Kind::ProofState,
+            SubscriptionKind::MiningShareMintQuote => cdk::nuts::nut17::Kind::MiningShareMintQuote,
         }
     }
 }
@@ -2146,6 +2185,7 @@ impl From<cdk::nuts::nut17::Kind> for SubscriptionKind {
             cdk::nuts::nut17::Kind::Bolt11MintQuote => SubscriptionKind::Bolt11MintQuote,
             cdk::nuts::nut17::Kind::Bolt12MintQuote => SubscriptionKind::Bolt12MintQuote,
             cdk::nuts::nut17::Kind::ProofState => SubscriptionKind::ProofState,
+            cdk::nuts::nut17::Kind::MiningShareMintQuote => SubscriptionKind::MiningShareMintQuote,
         }
     }
 }
@@ -2579,6 +2619,8 @@ pub struct ProofInfo {
     pub spending_condition: Option<SpendingConditions>,
     /// Currency unit
     pub unit: CurrencyUnit,
+    /// How the proof was received
+    pub source: ProofSource,
 }
 
 impl From<cdk_common::common::ProofInfo> for ProofInfo {
@@ -2590,6 +2632,50 @@ impl From<cdk_common::common::ProofInfo> for ProofInfo {
             state: info.state.into(),
             spending_condition: info.spending_condition.map(Into::into),
             unit: info.unit.into(),
+            source: info.source.into(),
+        }
+    }
+}
+
+/// FFI-compatible ProofSource
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, uniffi::Enum)]
+pub enum ProofSource {
+    /// Stored before the source was recorded
+    Unknown,
+    /// Minted from a bolt11 quote
+    Bolt11Mint,
+    /// Minted from a bolt12 quote
+    Bolt12Mint,
+    /// Minted from a mining share quote
+    MiningShareMint,
+    /// Received from a swap of the wallet's own proofs
+    Swap,
+    /// Received in a token
+    Receive,
+}
+
+impl From<cdk_common::common::ProofSource> for ProofSource {
+    fn from(source: cdk_common::common::ProofSource) -> Self {
+        match source {
+            cdk_common::common::ProofSource::Unknown => ProofSource::Unknown,
+            cdk_common::common::ProofSource::Bolt11Mint => ProofSource::Bolt11Mint,
+            cdk_common::common::ProofSource::Bolt12Mint => ProofSource::Bolt12Mint,
+            cdk_common::common::ProofSource::MiningShareMint => ProofSource::MiningShareMint,
+            cdk_common::common::ProofSource::Swap => ProofSource::Swap,
+            cdk_common::common::ProofSource::Receive => ProofSource::Receive,
+        }
+    }
+}
+
+impl From<ProofSource> for cdk_common::common::ProofSource {
+    fn from(source: ProofSource) -> Self {
+        match source {
+            ProofSource::Unknown => cdk_common::common::ProofSource::Unknown,
+            ProofSource::Bolt11Mint => cdk_common::common::ProofSource::Bolt11Mint,
+            ProofSource::Bolt12Mint => cdk_common::common::ProofSource::Bolt12Mint,
+            ProofSource::MiningShareMint => cdk_common::common::ProofSource::MiningShareMint,
+            ProofSource::Swap => cdk_common::common::ProofSource::Swap,
+            ProofSource::Receive => cdk_common::common::ProofSource::Receive,
         }
     }
 }
@@ -2612,12 +2698,246 @@ pub fn encode_proof_info(info: ProofInfo) -> Result<String, FfiError> {
         state: info.state.into(),
         spending_condition: info.spending_condition.and_then(|c| c.try_into().ok()),
         unit: info.unit.into(),
+        source: info.source.into(),
     };
     Ok(serde_json::to_string(&cdk_info)?)
 }
 
 // State enum removed - using ProofState instead
 
+/// FFI-compatible RestoreProgress
+#[derive(Debug, Clone, Serialize, Deserialize, uniffi::Record)]
+pub struct RestoreProgress {
+    /// Keyset id
+    pub keyset_id: Id,
+    /// Next counter to scan
+    pub next_counter: u32,
+    /// Counters scanned since the last signature found
+    pub gap: u32,
+    /// Signatures found
+    pub signatures_found: u32,
+    /// Unspent amount recovered
+    pub amount_recovered: Amount,
+    /// Whether the scan of the keyset finished
+    pub complete: bool,
+}
+
+impl From<cdk_common::wallet::RestoreProgress> for RestoreProgress {
+    fn from(progress: cdk_common::wallet::RestoreProgress) -> Self {
+        Self {
+            keyset_id: progress.keyset_id.into(),
+            next_counter: progress.next_counter,
+            gap: progress.gap,
+            signatures_found: progress.signatures_found,
+            amount_recovered: progress.amount_recovered.into(),
+            complete: progress.complete,
+        }
+    }
+}
+
+impl From<RestoreProgress> for cdk_common::wallet::RestoreProgress {
+    fn from(progress: RestoreProgress) -> Self {
+        Self {
+            keyset_id: progress.keyset_id.into(),
+            next_counter: progress.next_counter,
+            gap: progress.gap,
+            signatures_found: progress.signatures_found,
+            amount_recovered: progress.amount_recovered.into(),
+            complete: progress.complete,
+        }
+    }
+}
+
+/// FFI-compatible DeadLetterReason
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, uniffi::Enum)]
+pub enum DeadLetterReason {
+    /// The notified quote is not stored by the wallet
+    UnknownQuote,
+    /// The notification references a keyset the wallet does not know
+    UnknownKeyset,
+    /// The notification could not be parsed
+    InvalidPayload,
+}
+
+impl From<cdk_common::wallet::DeadLetterReason> for DeadLetterReason {
+    fn from(reason: cdk_common::wallet::DeadLetterReason) -> Self {
+        match reason {
+            cdk_common::wallet::DeadLetterReason::UnknownQuote => DeadLetterReason::UnknownQuote,
+            cdk_common::wallet::DeadLetterReason::UnknownKeyset => DeadLetterReason::UnknownKeyset,
+            cdk_common::wallet::DeadLetterReason::InvalidPayload => {
+                DeadLetterReason::InvalidPayload
+            }
+        }
+    }
+}
+
+impl From<DeadLetterReason> for cdk_common::wallet::DeadLetterReason {
+    fn from(reason: DeadLetterReason) -> Self {
+        match reason {
+            DeadLetterReason::UnknownQuote => cdk_common::wallet::DeadLetterReason::UnknownQuote,
+            DeadLetterReason::UnknownKeyset => cdk_common::wallet::DeadLetterReason::UnknownKeyset,
+            DeadLetterReason::InvalidPayload => {
+                cdk_common::wallet::DeadLetterReason::InvalidPayload
+            }
+        }
+    }
+}
+
+/// FFI-compatible DeadLetter
+#[derive(Debug, Clone, Serialize, Deserialize, uniffi::Record)]
+pub struct DeadLetter {
+    /// Position in the dead letter store, increasing with arrival
+    pub id: u64,
+    /// Mint the notification came from
+    pub mint_url: MintUrl,
+    /// Subscription the notification arrived on
+    pub sub_id: Option<String>,
+    /// Raw notification payload
+    pub payload: String,
+    /// Why the notification could not be applied
+    pub reason: DeadLetterReason,
+    /// Unix timestamp the notification arrived at
+    pub timestamp: u64,
+}
+
+impl From<cdk_common::wallet::DeadLetter> for DeadLetter {
+    fn from(dead_letter: cdk_common::wallet::DeadLetter) -> Self {
+        Self {
+            id: dead_letter.id,
+            mint_url: dead_letter.mint_url.into(),
+            sub_id: dead_letter.sub_id,
+            payload: dead_letter.payload,
+            reason: dead_letter.reason.into(),
+            timestamp: dead_letter.timestamp,
+        }
+    }
+}
+
+impl TryFrom<DeadLetter> for cdk_common::wallet::DeadLetter {
+    type Error = FfiError;
+
+    fn try_from(dead_letter: DeadLetter) -> Result<Self, Self::Error> {
+        Ok(Self {
+            id: dead_letter.id,
+            mint_url: dead_letter.mint_url.try_into()?,
+            sub_id: dead_letter.sub_id,
+            payload: dead_letter.payload,
+            reason: dead_letter.reason.into(),
+            timestamp: dead_letter.timestamp,
+        })
+    }
+}
+
+/// FFI-compatible WalletSnapshot
+#[derive(Debug, Clone, Serialize, Deserialize, uniffi::Record)]
+pub struct WalletSnapshot {
+    /// Snapshot id, increasing with creation
+    pub id: u64,
+    /// Mint of the wallet
+    pub mint_url: MintUrl,
+    /// Unit of the wallet
+    pub unit: CurrencyUnit,
+    /// Label given when the snapshot was created
+    pub label: String,
+    /// Unix timestamp the snapshot was created at
+    pub created_at: u64,
+    /// Proofs, quotes, transactions and keyset counters, JSON encoded
+    pub content: String,
+}
+
+impl TryFrom<cdk_common::wallet::WalletSnapshot> for WalletSnapshot {
+    type Error = FfiError;
+
+    fn try_from(snapshot: cdk_common::wallet::WalletSnapshot) -> Result<Self, Self::Error> {
+        Ok(Self {
+            id: snapshot.id,
+            mint_url: snapshot.mint_url.into(),
+            unit: snapshot.unit.into(),
+            label: snapshot.label,
+            created_at: snapshot.created_at,
+            content: serde_json::to_string(&snapshot.content)?,
+        })
+    }
+}
+
+impl TryFrom<WalletSnapshot> for cdk_common::wallet::WalletSnapshot {
+    type Error = FfiError;
+
+    fn try_from(snapshot: WalletSnapshot) -> Result<Self, Self::Error> {
+        Ok(Self {
+            id: snapshot.id,
+            mint_url: snapshot.mint_url.try_into()?,
+            unit: snapshot.unit.into(),
+            label: snapshot.label,
+            created_at: snapshot.created_at,
+            content: serde_json::from_str(&snapshot.content)?,
+        })
+    }
+}
+
+/// FFI-compatible PendingMintOperation
+#[derive(Debug, Clone, Serialize, Deserialize, uniffi::Record)]
+pub struct PendingMintOperation {
+    /// Id of the operation, a hash of the blinded secrets of its outputs
+    pub id: String,
+    /// Quote minted
+    pub quote_id: String,
+    /// Mint Url
+    pub mint_url: MintUrl,
+    /// Payment method of the quote
+    pub payment_method: PaymentMethod,
+    /// Unit of the proofs
+    pub unit: CurrencyUnit,
+    /// Keyset of the outputs
+    pub keyset_id: Id,
+    /// Mint request as sent, JSON encoded
+    pub request: String,
+    /// Secrets the outputs of the request were blinded from, JSON encoded
+    pub premint_secrets: String,
+    /// Unix timestamp the quote expires at, 0 if it does not
+    pub expiry: u64,
+    /// Unix timestamp the operation was queued at
+    pub created_time: u64,
+}
+
+impl TryFrom<cdk_common::wallet::PendingMintOperation> for PendingMintOperation {
+    type Error = FfiError;
+
+    fn try_from(operation: cdk_common::wallet::PendingMintOperation) -> Result<Self, Self::Error> {
+        Ok(Self {
+            id: operation.id,
+            quote_id: operation.quote_id,
+            mint_url: operation.mint_url.into(),
+            payment_method: operation.payment_method.into(),
+            unit: operation.unit.into(),
+            keyset_id: operation.keyset_id.into(),
+            request: serde_json::to_string(&operation.request)?,
+            premint_secrets: serde_json::to_string(&operation.premint_secrets)?,
+            expiry: operation.expiry,
+            created_time: operation.created_time,
+        })
+    }
+}
+
+impl TryFrom<PendingMintOperation> for cdk_common::wallet::PendingMintOperation {
+    type Error = FfiError;
+
+    fn try_from(operation: PendingMintOperation) -> Result<Self, Self::Error> {
+        Ok(Self {
+            id: operation.id,
+            quote_id: operation.quote_id,
+            mint_url: operation.mint_url.try_into()?,
+            payment_method: operation.payment_method.into(),
+            unit: operation.unit.into(),
+            keyset_id: operation.keyset_id.into(),
+            request: serde_json::from_str(&operation.request)?,
+            premint_secrets: serde_json::from_str(&operation.premint_secrets)?,
+            expiry: operation.expiry,
+            created_time: operation.created_time,
+        })
+    }
+}
+
 /// FFI-compatible Id (for keyset IDs)
 #[derive(Debug, Clone, Serialize, Deserialize, uniffi::Record)]
 #[serde(transparent)]
@@ -2,6 +2,7 @@ use std::collections::{HashMap, HashSet};
 use std::fmt::{Debug, Formatter};
 use std::path::PathBuf;
 use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::{env, fs};
 
@@ -15,12 +16,14 @@ use cdk::cdk_database::{self, MintDatabase, WalletDatabase};
 use cdk::mint::{MintBuilder, MintMeltLimits};
 use cdk::nuts::nut00::ProofsMethods;
 use cdk::nuts::{
-    CheckStateRequest, CheckStateResponse, CurrencyUnit, Id, KeySet, KeysetResponse,
-    MeltQuoteBolt11Request, MeltQuoteBolt11Response, MeltRequest, MintInfo, MintQuoteBolt11Request,
-    MintQuoteBolt11Response, MintRequest, MintResponse, PaymentMethod, RestoreRequest,
-    RestoreResponse, SwapRequest, SwapResponse,
+    BatchMintQuoteMiningShareRequest, BatchMintQuoteMiningShareResponse, BatchQuoteStatusRequest,
+    BatchQuoteStatusResponse, CheckStateRequest, CheckStateResponse, CurrencyUnit, Id, KeySet,
+    KeysetResponse, MeltQuoteBolt11Request, MeltQuoteBolt11Response, MeltRequest, MintInfo,
+    MintQuoteBolt11Request, MintQuoteBolt11Response, MintQuoteMiningShareResponse, MintRequest,
+    MintResponse, PaymentMethod, RestoreRequest, RestoreResponse, SwapRequest, SwapResponse,
 };
 use cdk::types::{FeeReserve, QuoteTTL};
+use cdk::util::clock::{Clock, MockClock, SystemClock};
 use cdk::util::unix_time;
 use cdk::wallet::{AuthWallet, MintConnector, Wallet, WalletBuilder};
 use cdk::{Amount, Error, Mint, StreamExt};
@@ -32,6 +35,15 @@ use uuid::Uuid;
 pub struct DirectMintConnection {
     pub mint: Mint,
     auth_wallet: Arc<RwLock<Option<AuthWallet>>>,
+    /// Restore requests received, including failed ones
+    pub restore_requests: Arc<AtomicUsize>,
+    restore_limit: Option<usize>,
+    /// Mint requests received, including those whose response was dropped
+    pub mint_requests: Arc<AtomicUsize>,
+    dropped_mint_responses: usize,
+    unreachable_mint_requests: Arc<AtomicUsize>,
+    mint_request_delay: Option<(MockClock, u64)>,
+    mining_share_quote_tamper: Option<fn(&mut MintQuoteMiningShareResponse<String>)>,
 }
 
 impl DirectMintConnection {
@@ -39,8 +51,83 @@ impl DirectMintConnection {
         Self {
             mint,
             auth_wallet: Arc::new(RwLock::new(None)),
+            restore_requests: Arc::new(AtomicUsize::new(0)),
+            restore_limit: None,
+            mint_requests: Arc::new(AtomicUsize::new(0)),
+            dropped_mint_responses: 0,
+            unreachable_mint_requests: Arc::new(AtomicUsize::new(0)),
+            mint_request_delay: None,
+            mining_share_quote_tamper: None,
         }
     }
+
+    /// Fail every restore request after the first `limit`
+    pub fn with_restore_limit(mut self, limit: usize) -> Self {
+        self.restore_limit = Some(limit);
+        self
+    }
+
+    /// Process the first `count` mint requests but fail them with a timeout,
+    /// as if the response was lost on the way back
+    pub fn with_dropped_mint_responses(mut self, count: usize) -> Self {
+        self.dropped_mint_responses = count;
+        self
+    }
+
+    /// Fail the first `count` mint requests before they reach the mint, as if
+    /// it could not be connected to
+    pub fn with_unreachable_mint_requests(self, count: usize) -> Self {
+        self.unreachable_mint_requests
+            .store(count, Ordering::SeqCst);
+        self
+    }
+
+    /// Advance `clock` by `secs` before every mint request reaches the mint,
+    /// as if it was delayed on the way
+    pub fn with_mint_request_delay(mut self, clock: MockClock, secs: u64) -> Self {
+        self.mint_request_delay = Some((clock, secs));
+        self
+    }
+
+    /// Alter every mining share quote response with `tamper` before
+    /// returning it, to act as a buggy mint
+    pub fn with_mining_share_quote_tamper(
+        mut self,
+        tamper: fn(&mut MintQuoteMiningShareResponse<String>),
+    ) -> Self {
+        self.mining_share_quote_tamper = Some(tamper);
+        self
+    }
+
+    /// Fail with a connection error while unreachable mint requests are left,
+    /// otherwise let the delay of the request pass
+    fn connect_mint_request(&self) -> Result<(), Error> {
+        if self
+            .unreachable_mint_requests
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |left| {
+                left.checked_sub(1)
+            })
+            .is_ok()
+        {
+            return Err(Error::HttpError(None, "Connection refused".to_string()));
+        }
+
+        if let Some((clock, secs)) = &self.mint_request_delay {
+            clock.advance(*secs);
+        }
+
+        Ok(())
+    }
+
+    fn tamper_mining_share_quote(
+        &self,
+        mut response: MintQuoteMiningShareResponse<String>,
+    ) -> MintQuoteMiningShareResponse<String> {
+        if let Some(tamper) = self.mining_share_quote_tamper {
+            tamper(&mut response);
+        }
+        response
+    }
 }
 
 impl Debug for DirectMintConnection {
@@ -89,8 +176,16 @@ impl MintConnector for DirectMintConnection {
     }
 
     async fn post_mint(&self, request: MintRequest<String>) -> Result<MintResponse, Error> {
+        self.connect_mint_request()?;
         let request_id: MintRequest<QuoteId> = request.try_into().unwrap();
-        self.mint.process_mint_request(request_id).await
+        let response = self.mint.process_mint_request(request_id).await?;
+
+        let served = self.mint_requests.fetch_add(1, Ordering::SeqCst);
+        if served < self.dropped_mint_responses {
+            return Err(Error::Timeout);
+        }
+
+        Ok(response)
     }
 
     async fn post_melt_quote(
@@ -137,6 +232,11 @@ impl MintConnector for DirectMintConnection {
     }
 
     async fn post_restore(&self, request: RestoreRequest) -> Result<RestoreResponse, Error> {
+        let served = self.restore_requests.fetch_add(1, Ordering::SeqCst);
+        if self.restore_limit.is_some_and(|limit| served >= limit) {
+            return Err(Error::Custom("Restore limit reached".to_string()));
+        }
+
         self.mint.restore(request).await
     }
 
@@ -203,28 +303,90 @@ impl MintConnector for DirectMintConnection {
         Err(Error::UnsupportedPaymentMethod)
     }
 
-    /// Mint Quote for Mining Share [NUT-XX] (Not implemented in tests)
+    /// Mint Quote for Mining Share [NUT-XX]
     async fn post_mint_quote_mining_share(
         &self,
-        _request: cdk::nuts::MintQuoteMiningShareRequest,
-    ) -> Result<cdk::nuts::MintQuoteMiningShareResponse<String>, Error> {
-        unimplemented!("Mining share quotes not supported in test client")
+        request: cdk::nuts::MintQuoteMiningShareRequest,
+    ) -> Result<MintQuoteMiningShareResponse<String>, Error> {
+        let quote = self.mint.create_mint_mining_share_quote(request).await?;
+        Ok(self.tamper_mining_share_quote(quote.try_into()?))
+    }
+
+    /// Mint Quotes for several Mining Shares [NUT-XX]
+    async fn post_mint_quotes_mining_share_batch(
+        &self,
+        request: BatchMintQuoteMiningShareRequest,
+    ) -> Result<BatchMintQuoteMiningShareResponse<String>, Error> {
+        Ok(self
+            .mint
+            .create_mint_mining_share_quotes(request)
+            .await?
+            .into())
     }
 
-    /// Mint Quote status for Mining Share [NUT-XX] (Not implemented in tests)
+    /// Mint Quote status for Mining Share [NUT-XX]
     async fn get_mint_quote_status_mining_share(
         &self,
-        _quote_id: &str,
-    ) -> Result<cdk::nuts::MintQuoteMiningShareResponse<String>, Error> {
-        unimplemented!("Mining share quote status not supported in test client")
+        quote_id: &str,
+    ) -> Result<MintQuoteMiningShareResponse<String>, Error> {
+        let response: MintQuoteMiningShareResponse<QuoteId> = self
+            .mint
+            .check_mint_quote(&QuoteId::from_str(quote_id)?)
+            .await?
+            .try_into()?;
+        Ok(self.tamper_mining_share_quote(response.into()))
+    }
+
+    /// Mint Quote status of several Mining Share quotes [NUT-XX]
+    async fn post_mint_quote_status_mining_share_batch(
+        &self,
+        request: BatchQuoteStatusRequest,
+    ) -> Result<BatchQuoteStatusResponse<String>, Error> {
+        Ok(self
+            .mint
+            .check_mint_quotes_mining_share(request)
+            .await?
+            .into())
     }
 
-    /// Mint Tokens for Mining Share [NUT-XX] (Not implemented in tests)
+    /// Mint Tokens for Mining Share [NUT-XX]
     async fn post_mint_mining_share(
         &self,
-        _request: cdk::nuts::MintRequest<String>,
+        request: cdk::nuts::MintRequest<String>,
     ) -> Result<cdk::nuts::MintResponse, Error> {
-        unimplemented!("Mining share minting not supported in test client")
+        self.connect_mint_request()?;
+        let request_id: MintRequest<QuoteId> = request.try_into().unwrap();
+        let response = self.mint.process_mint_request(request_id).await?;
+
+        let served = self.mint_requests.fetch_add(1, Ordering::SeqCst);
+        if served < self.dropped_mint_responses {
+            return Err(Error::Timeout);
+        }
+
+        Ok(response)
+    }
+
+    /// Convert a Mint Quote to another payment method [NUT-XX]
+    async fn post_mint_quote_conversion(
+        &self,
+        request: cdk::nuts::MintQuoteConversionRequest<String>,
+    ) -> Result<cdk::nuts::MintQuoteConversionResponse<String>, Error> {
+        let request: cdk::nuts::MintQuoteConversionRequest<QuoteId> = request.try_into()?;
+        self.mint
+            .convert_quote_method(&request.quote, request.method, request.params)
+            .await
+            .map(Into::into)
+    }
+    async fn delete_mint_quote(
+        &self,
+        method: PaymentMethod,
+        request: cdk::nuts::MintQuoteCancelRequest<String>,
+    ) -> Result<cdk::nuts::MintQuoteCancelResponse<String>, Error> {
+        let request: cdk::nuts::MintQuoteCancelRequest<QuoteId> = request.try_into()?;
+        self.mint
+            .cancel_mint_quote(&method, request)
+            .await
+            .map(Into::into)
     }
 }
 
@@ -250,6 +412,33 @@ pub async fn create_and_start_test_mint() -> Result<Mint> {
 pub async fn create_and_start_test_mint_with_unit(
     unit: CurrencyUnit,
     payment_methods: &[PaymentMethod],
+) -> Result<Mint> {
+    create_and_start_test_mint_with_clock(unit, payment_methods, Arc::new(SystemClock)).await
+}
+
+pub async fn create_and_start_test_mint_with_clock(
+    unit: CurrencyUnit,
+    payment_methods: &[PaymentMethod],
+    clock: Arc<dyn Clock>,
+) -> Result<Mint> {
+    let payment_processors: Vec<_> = payment_methods
+        .iter()
+        .map(|payment_method| (unit.clone(), payment_method.clone()))
+        .collect();
+
+    start_test_mint(&payment_processors, clock).await
+}
+
+/// Creates a mint with a fake payment processor for each unit and payment method
+pub async fn create_and_start_test_mint_with_units(
+    payment_processors: &[(CurrencyUnit, PaymentMethod)],
+) -> Result<Mint> {
+    start_test_mint(payment_processors, Arc::new(SystemClock)).await
+}
+
+async fn start_test_mint(
+    payment_processors: &[(CurrencyUnit, PaymentMethod)],
+    clock: Arc<dyn Clock>,
 ) -> Result<Mint> {
     // Read environment variable to determine database type
     let db_type = env::var("CDK_TEST_DB_TYPE").expect("Database type set");
@@ -275,7 +464,7 @@ pub async fn create_and_start_test_mint_with_unit(
         percent_fee_reserve: 1.0,
     };
 
-    for payment_method in payment_methods {
+    for (unit, payment_method) in payment_processors {
         let fake_backend = FakeWallet::new(
             fee_reserve.clone(),
             HashMap::default(),
@@ -299,7 +488,8 @@ pub async fn create_and_start_test_mint_with_unit(
     mint_builder = mint_builder
         .with_name("pure test mint".to_string())
         .with_description("pure test mint".to_string())
-        .with_urls(vec!["https://aaa".to_string()]);
+        .with_urls(vec!["https://aaa".to_string()])
+        .with_clock(clock);
 
     let tx_localstore = localstore.clone();
     let mut tx = tx_localstore.begin_transaction().await?;
@@ -325,9 +515,28 @@ pub async fn create_test_wallet_for_mint_with_unit(
     mint: Mint,
     unit: CurrencyUnit,
 ) -> Result<Wallet> {
-    let connector = DirectMintConnection::new(mint.clone());
+    create_test_wallet_for_mint_with_clock(mint, unit, Arc::new(SystemClock)).await
+}
 
-    let mint_info = mint.mint_info().await?;
+pub async fn create_test_wallet_for_mint_with_clock(
+    mint: Mint,
+    unit: CurrencyUnit,
+    clock: Arc<dyn Clock>,
+) -> Result<Wallet> {
+    let seed = Mnemonic::generate(12)?.to_seed_normalized("");
+
+    create_test_wallet_with_connection(DirectMintConnection::new(mint), unit, seed, clock).await
+}
+
+/// Create a wallet with an empty localstore from a seed, talking to the mint
+/// through `connector`
+pub async fn create_test_wallet_with_connection(
+    connector: DirectMintConnection,
+    unit: CurrencyUnit,
+    seed: [u8; 64],
+    clock: Arc<dyn Clock>,
+) -> Result<Wallet> {
+    let mint_info = connector.mint.mint_info().await?;
     let mint_url = mint_info
         .urls
         .as_ref()
@@ -335,8 +544,6 @@ pub async fn create_test_wallet_for_mint_with_unit(
         .first()
         .ok_or(anyhow!("Test mint has empty URLs list"))?;
 
-    let seed = Mnemonic::generate(12)?.to_seed_normalized("");
-
     // Read environment variable to determine database type
     let db_type = env::var("CDK_TEST_DB_TYPE").expect("Database type set");
 
@@ -368,12 +575,15 @@ pub async fn create_test_wallet_for_mint_with_unit(
             }
         };
 
+    // The direct connection has no websocket, even if the mint advertises one
     let wallet = WalletBuilder::new()
         .mint_url(mint_url.parse().unwrap())
         .unit(unit)
         .localstore(localstore)
         .seed(seed)
         .client(connector)
+        .clock(clock)
+        .use_http_subscription()
         .build()?;
 
     Ok(wallet)
@@ -24,10 +24,13 @@ use cdk::nuts::{
     CurrencyUnit, MeltQuoteState, MeltRequest, MintRequest, PreMintSecrets, Proofs, SecretKey,
     State, SwapRequest,
 };
+use cdk::util::hex;
 use cdk::wallet::types::TransactionDirection;
-use cdk::wallet::{HttpClient, MintConnector, Wallet};
+use cdk::wallet::{HttpClient, MintConnector, Wallet, WalletBuilder};
 use cdk::StreamExt;
-use cdk_fake_wallet::{create_fake_invoice, FakeInvoiceDescription};
+use cdk_fake_wallet::{
+    create_fake_invoice, create_fake_invoice_with_preimage, FakeInvoiceDescription,
+};
 use cdk_integration_tests::attempt_to_swap_pending;
 use cdk_sqlite::wallet::memory;
 
@@ -60,6 +63,7 @@ async fn test_fake_tokens_pending() {
         check_payment_state: MeltQuoteState::Pending,
         pay_err: false,
         check_err: false,
+        payment_preimage: None,
     };
 
     let invoice = create_fake_invoice(1000, serde_json::to_string(&fake_description).unwrap());
@@ -101,6 +105,7 @@ async fn test_fake_melt_payment_fail() {
         check_payment_state: MeltQuoteState::Unknown,
         pay_err: true,
         check_err: false,
+        payment_preimage: None,
     };
 
     let invoice = create_fake_invoice(1000, serde_json::to_string(&fake_description).unwrap());
@@ -116,6 +121,7 @@ async fn test_fake_melt_payment_fail() {
         check_payment_state: MeltQuoteState::Failed,
         pay_err: true,
         check_err: false,
+        payment_preimage: None,
     };
 
     let invoice = create_fake_invoice(1000, serde_json::to_string(&fake_description).unwrap());
@@ -165,6 +171,7 @@ async fn test_fake_melt_payment_fail_and_check() {
         check_payment_state: MeltQuoteState::Unknown,
         pay_err: true,
         check_err: true,
+        payment_preimage: None,
     };
 
     let invoice = create_fake_invoice(7000, serde_json::to_string(&fake_description).unwrap());
@@ -212,6 +219,7 @@ async fn test_fake_melt_payment_return_fail_status() {
         check_payment_state: MeltQuoteState::Failed,
         pay_err: false,
         check_err: false,
+        payment_preimage: None,
     };
 
     let invoice = create_fake_invoice(7000, serde_json::to_string(&fake_description).unwrap());
@@ -227,6 +235,7 @@ async fn test_fake_melt_payment_return_fail_status() {
         check_payment_state: MeltQuoteState::Unknown,
         pay_err: false,
         check_err: false,
+        payment_preimage: None,
     };
 
     let invoice = create_fake_invoice(7000, serde_json::to_string(&fake_description).unwrap());
@@ -274,6 +283,7 @@ async fn test_fake_melt_payment_error_unknown() {
         check_payment_state: MeltQuoteState::Unknown,
         pay_err: true,
         check_err: false,
+        payment_preimage: None,
     };
 
     let invoice = create_fake_invoice(7000, serde_json::to_string(&fake_description).unwrap());
@@ -289,6 +299,7 @@ async fn test_fake_melt_payment_error_unknown() {
         check_payment_state: MeltQuoteState::Unknown,
         pay_err: true,
         check_err: false,
+        payment_preimage: None,
     };
 
     let invoice = create_fake_invoice(7000, serde_json::to_string(&fake_description).unwrap());
@@ -336,6 +347,7 @@ async fn test_fake_melt_payment_err_paid() {
         check_payment_state: MeltQuoteState::Paid,
         pay_err: true,
         check_err: false,
+        payment_preimage: None,
     };
 
     let invoice = create_fake_invoice(7000, serde_json::to_string(&fake_description).unwrap());
@@ -418,6 +430,202 @@ async fn test_fake_melt_change_in_quote() {
     assert_eq!(melt_change, check);
 }
 
+/// Tests that a melt whose preimage matches the invoice payment hash completes
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_fake_melt_preimage_verified() {
+    let wallet = Wallet::new(
+        MINT_URL,
+        CurrencyUnit::Sat,
+        Arc::new(memory::empty().await.unwrap()),
+        Mnemonic::generate(12).unwrap().to_seed_normalized(""),
+        None,
+    )
+    .expect("Failed to create new wallet");
+
+    let mint_quote = wallet.mint_quote(100.into(), None).await.unwrap();
+
+    let mut proof_streams = wallet.proof_stream(mint_quote.clone(), SplitTarget::default(), None);
+
+    let _proofs = proof_streams
+        .next()
+        .await
+        .expect("payment")
+        .expect("no error");
+
+    let preimage: [u8; 32] = rand::random();
+
+    let fake_description = FakeInvoiceDescription {
+        payment_preimage: Some(hex::encode(preimage)),
+        ..Default::default()
+    };
+
+    let invoice = create_fake_invoice_with_preimage(
+        9000,
+        serde_json::to_string(&fake_description).unwrap(),
+        preimage,
+    );
+
+    let melt_quote = wallet.melt_quote(invoice.to_string(), None).await.unwrap();
+
+    let melted = wallet.melt(&melt_quote.id).await.unwrap();
+    assert_eq!(melted.state, MeltQuoteState::Paid);
+    assert_eq!(melted.preimage, Some(hex::encode(preimage)));
+
+    let transaction = wallet
+        .list_transactions(Some(TransactionDirection::Outgoing))
+        .await
+        .unwrap()
+        .pop()
+        .expect("No transaction found");
+    assert!(!transaction.metadata.contains_key("preimage_status"));
+    assert!(wallet.get_pending_proofs().await.unwrap().is_empty());
+}
+
+/// Tests that a melt whose preimage does not match the invoice payment hash is not
+/// marked complete and the input proofs stay pending
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_fake_melt_preimage_invalid() {
+    let wallet = Wallet::new(
+        MINT_URL,
+        CurrencyUnit::Sat,
+        Arc::new(memory::empty().await.unwrap()),
+        Mnemonic::generate(12).unwrap().to_seed_normalized(""),
+        None,
+    )
+    .expect("Failed to create new wallet");
+
+    let mint_quote = wallet.mint_quote(100.into(), None).await.unwrap();
+
+    let mut proof_streams = wallet.proof_stream(mint_quote.clone(), SplitTarget::default(), None);
+
+    let _proofs = proof_streams
+        .next()
+        .await
+        .expect("payment")
+        .expect("no error");
+
+    let preimage: [u8; 32] = rand::random();
+    let wrong_preimage: [u8; 32] = rand::random();
+
+    let fake_description = FakeInvoiceDescription {
+        payment_preimage: Some(hex::encode(wrong_preimage)),
+        ..Default::default()
+    };
+
+    let invoice = create_fake_invoice_with_preimage(
+        9000,
+        serde_json::to_string(&fake_description).unwrap(),
+        preimage,
+    );
+
+    let melt_quote = wallet.melt_quote(invoice.to_string(), None).await.unwrap();
+
+    let melt = wallet.melt(&melt_quote.id).await;
+    match melt {
+        Err(cdk::Error::InvalidPaymentPreimage { quote_id }) => {
+            assert_eq!(quote_id, melt_quote.id)
+        }
+        other => panic!("Expected invalid payment preimage, got {:?}", other),
+    }
+
+    assert!(!wallet.get_pending_proofs().await.unwrap().is_empty());
+
+    let transaction = wallet
+        .list_transactions(Some(TransactionDirection::Outgoing))
+        .await
+        .unwrap()
+        .pop()
+        .expect("No transaction found");
+    assert_eq!(
+        transaction.metadata.get("preimage_status"),
+        Some(&"invalid".to_string())
+    );
+
+    // The pending melt resolver reports the same discrepancy on every run
+    for _ in 0..2 {
+        let check = wallet.check_pending_melt_quotes().await;
+        assert!(matches!(
+            check,
+            Err(cdk::Error::InvalidPaymentPreimage { .. })
+        ));
+    }
+
+    // but records the melt only once
+    let transactions = wallet
+        .list_transactions(Some(TransactionDirection::Outgoing))
+        .await
+        .unwrap();
+    assert_eq!(
+        transactions
+            .iter()
+            .filter(|transaction| transaction.quote_id.as_deref() == Some(&melt_quote.id))
+            .count(),
+        1
+    );
+}
+
+/// Tests that a paid melt without a preimage is recorded as such, and refused by a
+/// wallet that requires payment preimages
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_fake_melt_preimage_missing() {
+    let localstore = Arc::new(memory::empty().await.unwrap());
+    let seed = Mnemonic::generate(12).unwrap().to_seed_normalized("");
+
+    let wallet = Wallet::new(MINT_URL, CurrencyUnit::Sat, localstore.clone(), seed, None)
+        .expect("Failed to create new wallet");
+
+    let mint_quote = wallet.mint_quote(100.into(), None).await.unwrap();
+
+    let mut proof_streams = wallet.proof_stream(mint_quote.clone(), SplitTarget::default(), None);
+
+    let _proofs = proof_streams
+        .next()
+        .await
+        .expect("payment")
+        .expect("no error");
+
+    let fake_description = FakeInvoiceDescription::default();
+
+    let invoice = create_fake_invoice(9000, serde_json::to_string(&fake_description).unwrap());
+
+    let melt_quote = wallet.melt_quote(invoice.to_string(), None).await.unwrap();
+
+    let melted = wallet.melt(&melt_quote.id).await.unwrap();
+    assert_eq!(melted.state, MeltQuoteState::Paid);
+
+    let transaction = wallet
+        .list_transactions(Some(TransactionDirection::Outgoing))
+        .await
+        .unwrap()
+        .pop()
+        .expect("No transaction found");
+    assert_eq!(
+        transaction.metadata.get("preimage_status"),
+        Some(&"missing".to_string())
+    );
+
+    let strict_wallet = WalletBuilder::new()
+        .mint_url(MINT_URL.parse().unwrap())
+        .unit(CurrencyUnit::Sat)
+        .localstore(localstore)
+        .seed(seed)
+        .require_payment_preimage(true)
+        .build()
+        .expect("Failed to create new wallet");
+
+    let invoice = create_fake_invoice(9000, serde_json::to_string(&fake_description).unwrap());
+
+    let melt_quote = strict_wallet
+        .melt_quote(invoice.to_string(), None)
+        .await
+        .unwrap();
+
+    let melt = strict_wallet.melt(&melt_quote.id).await;
+    assert!(matches!(melt, Err(cdk::Error::PreimageNotProvided)));
+
+    assert!(!strict_wallet.get_pending_proofs().await.unwrap().is_empty());
+}
+
 /// Tests minting tokens with a valid witness signature
 #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
 async fn test_fake_mint_with_witness() {
@@ -1281,3 +1489,108 @@ async fn test_fake_mint_duplicate_proofs_melt() {
         }
     }
 }
+
+/// Tests that a wallet refuses to mint a quote denominated in another unit
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_fake_mint_quote_unit_mismatch() {
+    // Both wallets share one store, like a multi-unit application would
+    let localstore = Arc::new(memory::empty().await.unwrap());
+    let seed = Mnemonic::generate(12).unwrap().to_seed_normalized("");
+
+    let wallet_sat = Wallet::new(MINT_URL, CurrencyUnit::Sat, localstore.clone(), seed, None)
+        .expect("failed to create new wallet");
+
+    let wallet_usd = Wallet::new(MINT_URL, CurrencyUnit::Usd, localstore, seed, None)
+        .expect("failed to create new wallet");
+
+    let mint_quote = wallet_usd.mint_quote(100.into(), None).await.unwrap();
+
+    let mut payment_streams = wallet_usd.payment_stream(&mint_quote);
+
+    payment_streams
+        .next()
+        .await
+        .expect("payment")
+        .expect("no error");
+
+    let response = wallet_sat
+        .mint(&mint_quote.id, SplitTarget::default(), None)
+        .await;
+
+    match response {
+        Err(cdk::Error::QuoteUnitMismatch { quote, wallet }) => {
+            assert_eq!(quote, CurrencyUnit::Usd);
+            assert_eq!(wallet, CurrencyUnit::Sat);
+        }
+        other => panic!("Expected unit mismatch, got {:?}", other),
+    }
+
+    // The rejected quote is left untouched for the wallet of the right unit
+    assert!(wallet_sat
+        .localstore
+        .get_mint_quote(&mint_quote.id)
+        .await
+        .unwrap()
+        .is_some());
+    assert_eq!(wallet_sat.total_balance().await.unwrap(), Amount::ZERO);
+}
+
+/// Tests that foreign unit quotes can be minted explicitly without affecting the wallet balance
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_fake_mint_foreign_unit() {
+    let localstore = Arc::new(memory::empty().await.unwrap());
+    let seed = Mnemonic::generate(12).unwrap().to_seed_normalized("");
+
+    let wallet_sat = Wallet::new(MINT_URL, CurrencyUnit::Sat, localstore.clone(), seed, None)
+        .expect("failed to create new wallet");
+
+    let wallet_usd = Wallet::new(MINT_URL, CurrencyUnit::Usd, localstore, seed, None)
+        .expect("failed to create new wallet");
+
+    let mint_quote = wallet_usd.mint_quote(100.into(), None).await.unwrap();
+
+    let mut payment_streams = wallet_usd.payment_stream(&mint_quote);
+
+    payment_streams
+        .next()
+        .await
+        .expect("payment")
+        .expect("no error");
+
+    let proofs = wallet_sat
+        .mint_foreign_unit(&mint_quote.id)
+        .await
+        .expect("foreign unit mint");
+
+    assert_eq!(proofs.total_amount().unwrap(), Amount::from(100));
+
+    // Proofs are tagged with the quote unit
+    assert_eq!(wallet_sat.total_balance().await.unwrap(), Amount::ZERO);
+    assert_eq!(wallet_usd.total_balance().await.unwrap(), Amount::from(100));
+
+    // So is the transaction
+    let transactions = wallet_usd
+        .list_transactions(Some(TransactionDirection::Incoming))
+        .await
+        .unwrap();
+    assert!(transactions
+        .iter()
+        .any(|t| t.quote_id.as_deref() == Some(mint_quote.id.as_str())));
+
+    // Minting quotes of the wallet unit is unaffected
+    let mint_quote = wallet_sat.mint_quote(64.into(), None).await.unwrap();
+
+    let proofs = wallet_sat
+        .wait_and_mint_quote(
+            mint_quote,
+            SplitTarget::default(),
+            None,
+            tokio::time::Duration::from_secs(60),
+        )
+        .await
+        .expect("sat mint");
+
+    assert_eq!(proofs.total_amount().unwrap(), Amount::from(64));
+    assert_eq!(wallet_sat.total_balance().await.unwrap(), Amount::from(64));
+    assert_eq!(wallet_usd.total_balance().await.unwrap(), Amount::from(100));
+}
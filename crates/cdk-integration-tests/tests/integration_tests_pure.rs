@@ -13,24 +13,47 @@ use std::assert_eq;
 use std::collections::{HashMap, HashSet};
 use std::hash::RandomState;
 use std::str::FromStr;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
 use std::time::Duration;
 
-use cashu::amount::SplitTarget;
+use bip39::Mnemonic;
+use bitcoin::hashes::{sha256, Hash};
+use cashu::amount::{SplitTarget, UnitAmount};
 use cashu::dhke::construct_proofs;
 use cashu::mint_url::MintUrl;
+use cashu::nuts::nutXX;
 use cashu::{
-    CurrencyUnit, Id, MeltRequest, NotificationPayload, PreMintSecrets, ProofState, SecretKey,
-    SpendingConditions, State, SwapRequest,
+    BatchMintQuoteMiningShareItem, BatchMintQuoteMiningShareRequest, BatchQuoteStatusItem,
+    BatchQuoteStatusRequest, CurrencyUnit, Id, KeySet, Keys, MeltOptions, MeltQuoteState,
+    MeltRequest, MintMethodSettings, MintQuoteCancelRequest, MintQuoteMiningShareRequest,
+    MintQuoteMiningShareResponse, MintQuoteState, MintRequest, NotificationPayload, PaymentMethod,
+    PreMintSecrets, ProofState, Proofs, QuoteConversionParams, SecretKey, SpendingConditions,
+    State, SwapRequest,
 };
-use cdk::mint::Mint;
+use cdk::cdk_payment::{PaymentIdentifier, WaitPaymentResponse};
+use cdk::mint::{Mint, MintQuote, QuoteId};
 use cdk::nuts::nut00::ProofsMethods;
 use cdk::subscription::{IndexableParams, Params};
-use cdk::wallet::types::{TransactionDirection, TransactionId};
-use cdk::wallet::{ReceiveOptions, SendMemo, SendOptions};
+use cdk::types::{ProofSource, QuoteTTL};
+use cdk::util::clock::{Clock, MockClock, SystemClock};
+use cdk::util::unix_time;
+use cdk::wallet::types::{
+    DeadLetterReason, MintQuote as WalletMintQuote, TransactionDirection, TransactionId,
+};
+use cdk::wallet::{
+    AutoMintEvent, Capability, ConsolidateOptions, DisclosureLevel, ExportFilter, MintConnector,
+    MintInfoWarning, QuoteConflict, QuoteStateSource, ReceiveOptions, RefreshOptions,
+    RestoreOptions, SelectionStrategy, SendMemo, SendOptions, SnapshotPolicy, Wallet,
+    WalletBuilder, ESTIMATED_METADATA_KEY, MAX_DEAD_LETTERS, MAX_MOTD_LEN,
+    PENDING_QUOTE_EXPORT_VERSION, REBUILT_METADATA_KEY,
+};
 use cdk::Amount;
 use cdk_fake_wallet::create_fake_invoice;
 use cdk_integration_tests::init_pure_tests::*;
+use futures::StreamExt;
 use tokio::time::sleep;
+use tokio_util::sync::CancellationToken;
 
 /// Tests the token swap and send functionality:
 /// 1. Alice gets funded with 64 sats
@@ -886,6 +909,4853 @@ async fn test_concurrent_double_spend_melt() {
     }
 }
 
+/// Tests that quote expiry follows the injected clock:
+/// 1. Mint and melt quotes expire one TTL after the mock time they were created at
+/// 2. Advancing the clock past the TTL drops the quotes from the active lists
+/// 3. Melting the expired quote fails locally before reaching the mint, as the
+///    invoice expired as well and the quote cannot be replaced
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_quote_expiry_with_mock_clock() {
+    setup_tracing();
+    let clock = MockClock::new(unix_time());
+
+    let mint_bob = create_and_start_test_mint_with_clock(
+        CurrencyUnit::Sat,
+        &[PaymentMethod::Bolt11],
+        Arc::new(clock.clone()),
+    )
+    .await
+    .expect("Failed to create test mint");
+    let quote_ttl = mint_bob.quote_ttl().await.unwrap();
+
+    let wallet_alice = create_test_wallet_for_mint_with_clock(
+        mint_bob,
+        CurrencyUnit::Sat,
+        Arc::new(clock.clone()),
+    )
+    .await
+    .expect("Failed to create test wallet");
+
+    fund_wallet(wallet_alice.clone(), 100, None)
+        .await
+        .expect("Failed to fund wallet");
+
+    let mint_quote = wallet_alice.mint_quote(10.into(), None).await.unwrap();
+    assert_eq!(mint_quote.expiry, clock.now_unix() + quote_ttl.mint_ttl);
+
+    let fake_invoice = create_fake_invoice(1000, "".to_string());
+    let melt_quote = wallet_alice
+        .melt_quote(fake_invoice.to_string(), None)
+        .await
+        .unwrap();
+    assert_eq!(melt_quote.expiry, clock.now_unix() + quote_ttl.melt_ttl);
+
+    assert_eq!(
+        wallet_alice.get_active_mint_quotes().await.unwrap().len(),
+        1
+    );
+    assert_eq!(
+        wallet_alice.get_active_melt_quotes().await.unwrap().len(),
+        1
+    );
+
+    clock.advance(quote_ttl.mint_ttl.max(quote_ttl.melt_ttl) + 1);
+
+    assert!(wallet_alice
+        .get_active_mint_quotes()
+        .await
+        .unwrap()
+        .is_empty());
+    assert!(wallet_alice
+        .get_active_melt_quotes()
+        .await
+        .unwrap()
+        .is_empty());
+
+    let proofs = wallet_alice.get_unspent_proofs().await.unwrap();
+    let result = wallet_alice.melt_proofs(&melt_quote.id, proofs).await;
+    assert!(
+        matches!(result, Err(cdk::Error::InvoiceExpired(expires_at)) if expires_at < clock.now_unix()),
+        "Expected InvoiceExpired, got {:?}",
+        result
+    );
+
+    // The proofs were never sent to the mint
+    assert_eq!(wallet_alice.total_balance().await.unwrap(), 100.into());
+}
+
+/// Tests exporting proofs with their secrets:
+/// 1. Alice exports her full balance, which reserves the proofs
+/// 2. Alice can no longer spend the exported proofs
+/// 3. Carol imports the export and receives the full value
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_export_full_proofs() {
+    setup_tracing();
+    let mint_bob = create_and_start_test_mint()
+        .await
+        .expect("Failed to create test mint");
+    let wallet_alice = create_test_wallet_for_mint(mint_bob.clone())
+        .await
+        .expect("Failed to create test wallet");
+    let wallet_carol = create_test_wallet_for_mint(mint_bob.clone())
+        .await
+        .expect("Failed to create test wallet");
+
+    fund_wallet(wallet_alice.clone(), 64, None)
+        .await
+        .expect("Failed to fund wallet");
+
+    // A full export has to be acknowledged
+    assert!(matches!(
+        wallet_alice
+            .export_proofs(
+                ExportFilter::default(),
+                DisclosureLevel::Full {
+                    acknowledge_transfer: false
+                }
+            )
+            .await,
+        Err(cdk::Error::ExportNotAcknowledged)
+    ));
+
+    let export = wallet_alice
+        .export_proofs(
+            ExportFilter::default(),
+            DisclosureLevel::Full {
+                acknowledge_transfer: true,
+            },
+        )
+        .await
+        .unwrap();
+    let json = export.to_json().unwrap();
+
+    // Standard NUT-00 proof array
+    let proofs: Proofs = serde_json::from_str(&json).unwrap();
+    assert_eq!(proofs.total_amount().unwrap(), 64.into());
+
+    assert_eq!(wallet_alice.total_balance().await.unwrap(), Amount::ZERO);
+    assert_eq!(
+        wallet_alice.total_reserved_balance().await.unwrap(),
+        64.into()
+    );
+    assert!(wallet_alice
+        .prepare_send(Amount::from(1), SendOptions::default())
+        .await
+        .is_err());
+
+    let received = wallet_carol
+        .import_exported_proofs(&json, ReceiveOptions::default())
+        .await
+        .unwrap();
+    assert_eq!(received, 64.into());
+    assert_eq!(wallet_carol.total_balance().await.unwrap(), 64.into());
+
+    // The exported proofs are spent now
+    let states = wallet_alice.check_proofs_spent(proofs).await.unwrap();
+    assert!(states.iter().all(|state| state.state == State::Spent));
+}
+
+/// Tests that exports for audits and statistics contain no secret material
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_export_redacted_proofs() {
+    setup_tracing();
+    let mint_bob = create_and_start_test_mint()
+        .await
+        .expect("Failed to create test mint");
+    let wallet_alice = create_test_wallet_for_mint(mint_bob.clone())
+        .await
+        .expect("Failed to create test wallet");
+
+    fund_wallet(wallet_alice.clone(), 100, None)
+        .await
+        .expect("Failed to fund wallet");
+    let proofs = wallet_alice.get_unspent_proofs().await.unwrap();
+
+    let json = wallet_alice
+        .export_proofs(ExportFilter::default(), DisclosureLevel::YsOnly)
+        .await
+        .unwrap()
+        .to_json()
+        .unwrap();
+
+    let entries: Vec<serde_json::Value> = serde_json::from_str(&json).unwrap();
+    assert_eq!(entries.len(), proofs.len());
+    for entry in &entries {
+        let fields: HashSet<&str> = entry
+            .as_object()
+            .unwrap()
+            .keys()
+            .map(String::as_str)
+            .collect();
+        assert_eq!(fields, HashSet::from(["id", "amount", "Y"]));
+    }
+
+    let exported_ys: HashSet<String> = entries
+        .iter()
+        .map(|entry| entry["Y"].as_str().unwrap().to_string())
+        .collect();
+    let ys: HashSet<String> = proofs.ys().unwrap().iter().map(|y| y.to_hex()).collect();
+    assert_eq!(exported_ys, ys);
+
+    let json = wallet_alice
+        .export_proofs(ExportFilter::default(), DisclosureLevel::Redacted)
+        .await
+        .unwrap()
+        .to_json()
+        .unwrap();
+
+    let buckets: Vec<serde_json::Value> = serde_json::from_str(&json).unwrap();
+    let count: u64 = buckets
+        .iter()
+        .map(|bucket| bucket["count"].as_u64().unwrap())
+        .sum();
+    assert_eq!(count, proofs.len() as u64);
+    assert!(buckets.iter().all(|bucket| bucket.get("Y").is_none()));
+
+    // Neither export reserves proofs
+    assert_eq!(wallet_alice.total_balance().await.unwrap(), 100.into());
+}
+
+/// Tests that consolidating many one-unit proofs:
+/// 1. Swaps nothing when the fee cap does not cover the first swap
+/// 2. Brings the proof count below the target in several capped swaps
+/// 3. Keeps the balance less the fees paid, recorded as a zero amount transaction
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_consolidate_proofs() {
+    setup_tracing();
+    let mint_bob = create_and_start_test_mint()
+        .await
+        .expect("Failed to create test mint");
+    mint_bob
+        .rotate_keyset(CurrencyUnit::Sat, 32, 100)
+        .await
+        .unwrap();
+    let wallet_alice = create_test_wallet_for_mint(mint_bob.clone())
+        .await
+        .expect("Failed to create test wallet");
+
+    fund_wallet(
+        wallet_alice.clone(),
+        200,
+        Some(SplitTarget::Value(1.into())),
+    )
+    .await
+    .expect("Failed to fund wallet");
+    assert_eq!(wallet_alice.get_unspent_proofs().await.unwrap().len(), 200);
+
+    let summary = wallet_alice
+        .consolidate_proofs(
+            10,
+            ConsolidateOptions {
+                max_inputs_per_swap: 50,
+                fee_cap: Some(Amount::ZERO),
+            },
+        )
+        .await
+        .unwrap();
+    assert!(summary.fee_cap_reached);
+    assert_eq!(summary.swaps_completed, 0);
+    assert_eq!(wallet_alice.get_unspent_proofs().await.unwrap().len(), 200);
+
+    let summary = wallet_alice
+        .consolidate_proofs(
+            10,
+            ConsolidateOptions {
+                max_inputs_per_swap: 50,
+                fee_cap: None,
+            },
+        )
+        .await
+        .unwrap();
+    assert!(summary.swaps_completed > 1);
+    assert!(summary.fees_paid > Amount::ZERO);
+    assert!(!summary.fee_cap_reached);
+
+    let proofs = wallet_alice.get_unspent_proofs().await.unwrap();
+    assert!(proofs.len() <= 10, "{} proofs left", proofs.len());
+    assert_eq!(summary.proofs_remaining, proofs.len());
+    assert_eq!(
+        wallet_alice.total_balance().await.unwrap(),
+        Amount::from(200) - summary.fees_paid
+    );
+
+    let transactions = wallet_alice
+        .list_transactions(Some(TransactionDirection::Outgoing))
+        .await
+        .unwrap();
+    assert_eq!(transactions.len(), summary.swaps_completed);
+    assert!(transactions.iter().all(|tx| tx.amount == Amount::ZERO));
+    assert_eq!(
+        transactions
+            .iter()
+            .fold(Amount::ZERO, |total, tx| total + tx.fee),
+        summary.fees_paid
+    );
+}
+
+/// Tests that refreshing the balance conserves its value and replaces every proof
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_refresh_all_proofs() {
+    setup_tracing();
+    let mint_bob = create_and_start_test_mint()
+        .await
+        .expect("Failed to create test mint");
+    let wallet_alice = create_test_wallet_for_mint(mint_bob.clone())
+        .await
+        .expect("Failed to create test wallet");
+
+    fund_wallet(
+        wallet_alice.clone(),
+        100,
+        Some(SplitTarget::Value(4.into())),
+    )
+    .await
+    .expect("Failed to fund wallet");
+
+    let original_ys = wallet_alice
+        .get_unspent_proofs()
+        .await
+        .unwrap()
+        .ys()
+        .unwrap();
+
+    let summary = wallet_alice
+        .refresh_all_proofs(RefreshOptions {
+            min_batch_size: 2,
+            max_batch_size: 5,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(summary.proofs_refreshed, original_ys.len());
+    assert_eq!(summary.amount_refreshed, 100.into());
+    assert_eq!(summary.fees_paid, Amount::ZERO);
+    assert!(summary.remaining.is_empty());
+    assert!(!summary.fee_cap_reached);
+
+    assert_eq!(wallet_alice.total_balance().await.unwrap(), 100.into());
+
+    let refreshed_ys = wallet_alice
+        .get_unspent_proofs()
+        .await
+        .unwrap()
+        .ys()
+        .unwrap();
+    assert!(refreshed_ys.iter().all(|y| !original_ys.contains(y)));
+}
+
+/// Tests that an interrupted refresh can be resumed without losing value
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_refresh_proofs_resume() {
+    setup_tracing();
+    let mint_bob = create_and_start_test_mint()
+        .await
+        .expect("Failed to create test mint");
+    let wallet_alice = create_test_wallet_for_mint(mint_bob.clone())
+        .await
+        .expect("Failed to create test wallet");
+
+    fund_wallet(
+        wallet_alice.clone(),
+        100,
+        Some(SplitTarget::Value(4.into())),
+    )
+    .await
+    .expect("Failed to fund wallet");
+
+    let original_ys = wallet_alice
+        .get_unspent_proofs()
+        .await
+        .unwrap()
+        .ys()
+        .unwrap();
+
+    let wallet = wallet_alice.clone();
+    let refresh = tokio::spawn(async move {
+        wallet
+            .refresh_all_proofs(RefreshOptions {
+                min_batch_size: 1,
+                max_batch_size: 3,
+                min_delay: Duration::from_secs(1),
+                max_delay: Duration::from_secs(1),
+                ..Default::default()
+            })
+            .await
+    });
+
+    sleep(Duration::from_millis(300)).await;
+    refresh.abort();
+    assert!(refresh.await.unwrap_err().is_cancelled());
+
+    // Leave some proofs reserved, as a batch interrupted mid swap would
+    let untouched: Vec<_> = wallet_alice
+        .get_unspent_proofs()
+        .await
+        .unwrap()
+        .ys()
+        .unwrap()
+        .into_iter()
+        .filter(|y| original_ys.contains(y))
+        .collect();
+    assert!(untouched.len() < original_ys.len());
+    wallet_alice
+        .localstore
+        .update_proofs_state(untouched[..2].to_vec(), State::Reserved)
+        .await
+        .unwrap();
+
+    let summary = wallet_alice
+        .refresh_proofs(original_ys.clone(), RefreshOptions::default())
+        .await
+        .unwrap();
+
+    assert_eq!(summary.proofs_refreshed, untouched.len());
+    assert!(summary.remaining.is_empty());
+    assert_eq!(wallet_alice.total_balance().await.unwrap(), 100.into());
+
+    let refreshed_ys = wallet_alice
+        .get_unspent_proofs()
+        .await
+        .unwrap()
+        .ys()
+        .unwrap();
+    assert!(refreshed_ys.iter().all(|y| !original_ys.contains(y)));
+}
+
+/// Tests that a refresh stops before exceeding its fee cap
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_refresh_proofs_fee_cap() {
+    setup_tracing();
+    let mint_bob = create_and_start_test_mint()
+        .await
+        .expect("Failed to create test mint");
+
+    mint_bob
+        .rotate_keyset(CurrencyUnit::Sat, 32, 1000)
+        .await
+        .unwrap();
+
+    let wallet_alice = create_test_wallet_for_mint(mint_bob.clone())
+        .await
+        .expect("Failed to create test wallet");
+
+    fund_wallet(
+        wallet_alice.clone(),
+        100,
+        Some(SplitTarget::Value(4.into())),
+    )
+    .await
+    .expect("Failed to fund wallet");
+
+    let proof_count = wallet_alice.get_unspent_proofs().await.unwrap().len();
+
+    let summary = wallet_alice
+        .refresh_all_proofs(RefreshOptions {
+            min_batch_size: 2,
+            max_batch_size: 2,
+            fee_cap: Some(3.into()),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    // Each proof costs 1 sat, so only the first batch of two fits under the cap
+    assert!(summary.fee_cap_reached);
+    assert_eq!(summary.batches_completed, 1);
+    assert_eq!(summary.fees_paid, 2.into());
+    assert_eq!(summary.remaining.len(), proof_count - 2);
+    assert_eq!(wallet_alice.total_balance().await.unwrap(), 98.into());
+}
+
+/// Tests that a keyset reusing keys of another keyset is refused until trusted
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_wallet_keyset_key_reuse() {
+    setup_tracing();
+    let mint_bob = create_and_start_test_mint()
+        .await
+        .expect("Failed to create test mint");
+    let wallet_alice = create_test_wallet_for_mint(mint_bob.clone())
+        .await
+        .expect("Failed to create test wallet");
+
+    // Disjoint keysets raise no warning
+    let mint_keyset_id = wallet_alice.fetch_active_keyset().await.unwrap().id;
+    assert!(wallet_alice.suspect_keysets().unwrap().is_empty());
+
+    // Fabricate a keyset sharing the amount 1 key with the mint's keyset
+    let mint_keys = mint_bob.pubkeys().keysets.first().unwrap().keys.clone();
+    let keys = Keys::new(
+        mint_keys
+            .iter()
+            .map(|(amount, key)| {
+                let key = if *amount == Amount::from(1) {
+                    *key
+                } else {
+                    SecretKey::generate().public_key()
+                };
+                (*amount, key)
+            })
+            .collect(),
+    );
+    let reused_id = Id::v1_from_keys(&keys);
+
+    wallet_alice
+        .add_keyset(
+            KeySet {
+                id: reused_id,
+                unit: CurrencyUnit::Sat,
+                keys,
+                final_expiry: None,
+            },
+            true,
+            0,
+        )
+        .await
+        .unwrap();
+
+    // Only the keyset stored last is suspect
+    assert_eq!(wallet_alice.suspect_keysets().unwrap(), vec![reused_id]);
+    wallet_alice
+        .ensure_keyset_trusted(mint_keyset_id)
+        .await
+        .unwrap();
+
+    match wallet_alice.ensure_keyset_trusted(reused_id).await {
+        Err(cdk::Error::KeysetKeyReuse(id)) => assert_eq!(id, reused_id),
+        other => panic!("Expected key reuse error, got {:?}", other),
+    }
+
+    wallet_alice.trust_keyset(reused_id).unwrap();
+    wallet_alice.ensure_keyset_trusted(reused_id).await.unwrap();
+}
+
+/// Tests rolling a wallet back after destructive local maintenance:
+/// 1. Alice snapshots her funded wallet with an open mint quote
+/// 2. A cleanup removes proofs, the quote and the mint transaction
+/// 3. After a restart, rolling back restores balance, quote and transactions,
+///    counters are unchanged
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_wallet_snapshot_rollback() {
+    setup_tracing();
+    let mint_bob = create_and_start_test_mint()
+        .await
+        .expect("Failed to create test mint");
+    let wallet_alice = create_test_wallet_for_mint(mint_bob.clone())
+        .await
+        .expect("Failed to create test wallet");
+
+    fund_wallet(wallet_alice.clone(), 100, None)
+        .await
+        .expect("Failed to fund wallet");
+    let quote = wallet_alice.mint_quote(10.into(), None).await.unwrap();
+    let keyset_id = get_keyset_id(&mint_bob).await;
+    let counter = wallet_alice
+        .localstore
+        .increment_keyset_counter(&keyset_id, 0)
+        .await
+        .unwrap();
+
+    let snapshot = wallet_alice
+        .create_snapshot("before cleanup")
+        .await
+        .unwrap();
+    let info = wallet_alice.list_snapshots().await.unwrap();
+    assert_eq!(info.len(), 1);
+    assert_eq!(info[0].id, snapshot);
+    assert_eq!(info[0].label, "before cleanup");
+    assert!(info[0].size > 0);
+
+    // A broken cleanup drops most of the wallet
+    let unspent = wallet_alice.get_unspent_proofs().await.unwrap();
+    let removed: Proofs = unspent.iter().skip(1).cloned().collect();
+    wallet_alice
+        .localstore
+        .update_proofs(vec![], removed.ys().unwrap())
+        .await
+        .unwrap();
+    wallet_alice
+        .localstore
+        .remove_mint_quote(&quote.id)
+        .await
+        .unwrap();
+    for transaction in wallet_alice.list_transactions(None).await.unwrap() {
+        wallet_alice
+            .localstore
+            .remove_transaction(transaction.id())
+            .await
+            .unwrap();
+    }
+    assert!(wallet_alice.total_balance().await.unwrap() < 100.into());
+
+    // The snapshot is kept in the localstore, a restarted wallet finds it
+    let wallet_alice = WalletBuilder::new()
+        .mint_url(wallet_alice.mint_url.clone())
+        .unit(CurrencyUnit::Sat)
+        .localstore(wallet_alice.localstore.clone())
+        .seed(Mnemonic::generate(12).unwrap().to_seed_normalized(""))
+        .client(DirectMintConnection::new(mint_bob.clone()))
+        .build()
+        .unwrap();
+    assert_eq!(wallet_alice.list_snapshots().await.unwrap(), info);
+
+    wallet_alice
+        .rollback_to_snapshot(snapshot, false)
+        .await
+        .unwrap();
+
+    assert_eq!(wallet_alice.total_balance().await.unwrap(), 100.into());
+    assert_eq!(
+        wallet_alice
+            .localstore
+            .get_mint_quote(&quote.id)
+            .await
+            .unwrap()
+            .map(|restored| restored.id),
+        Some(quote.id)
+    );
+    assert_eq!(wallet_alice.list_transactions(None).await.unwrap().len(), 1);
+    assert_eq!(
+        wallet_alice
+            .localstore
+            .increment_keyset_counter(&keyset_id, 0)
+            .await
+            .unwrap(),
+        counter
+    );
+
+    wallet_alice.remove_snapshot(snapshot).await.unwrap();
+    assert!(matches!(
+        wallet_alice.rollback_to_snapshot(snapshot, false).await,
+        Err(cdk::Error::UnknownSnapshot)
+    ));
+}
+
+/// Tests that a rollback is refused once proofs left the wallet:
+/// 1. Alice snapshots her wallet and then sends a token
+/// 2. Rolling back is refused
+/// 3. A forced rollback restores the snapshot without rewinding counters
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_wallet_snapshot_rollback_refused() {
+    setup_tracing();
+    let mint_bob = create_and_start_test_mint()
+        .await
+        .expect("Failed to create test mint");
+    let wallet_alice = create_test_wallet_for_mint(mint_bob.clone())
+        .await
+        .expect("Failed to create test wallet");
+
+    fund_wallet(wallet_alice.clone(), 64, None)
+        .await
+        .expect("Failed to fund wallet");
+    let snapshot = wallet_alice.create_snapshot("before send").await.unwrap();
+
+    wallet_alice
+        .prepare_send(Amount::from(40), SendOptions::default())
+        .await
+        .unwrap()
+        .confirm(None)
+        .await
+        .unwrap();
+
+    let keyset_id = get_keyset_id(&mint_bob).await;
+    let counter = wallet_alice
+        .localstore
+        .increment_keyset_counter(&keyset_id, 0)
+        .await
+        .unwrap();
+
+    assert!(matches!(
+        wallet_alice.rollback_to_snapshot(snapshot, false).await,
+        Err(cdk::Error::SnapshotRollbackRefused(_))
+    ));
+
+    wallet_alice
+        .rollback_to_snapshot(snapshot, true)
+        .await
+        .unwrap();
+    assert_eq!(wallet_alice.total_balance().await.unwrap(), 64.into());
+    assert_eq!(
+        wallet_alice
+            .localstore
+            .increment_keyset_counter(&keyset_id, 0)
+            .await
+            .unwrap(),
+        counter
+    );
+}
+
+/// Tests pruning snapshots by count
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_wallet_snapshot_prune() {
+    setup_tracing();
+    let mint_bob = create_and_start_test_mint()
+        .await
+        .expect("Failed to create test mint");
+    let wallet_alice = create_test_wallet_for_mint(mint_bob.clone())
+        .await
+        .expect("Failed to create test wallet");
+
+    let mut ids = Vec::new();
+    for label in ["first", "second", "third"] {
+        ids.push(wallet_alice.create_snapshot(label).await.unwrap());
+    }
+
+    let pruned = wallet_alice
+        .prune_snapshots(&SnapshotPolicy {
+            keep_last: Some(2),
+            max_age: None,
+        })
+        .await
+        .unwrap();
+    assert_eq!(pruned, 1);
+    assert_eq!(
+        wallet_alice
+            .list_snapshots()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|info| info.id)
+            .collect::<Vec<_>>(),
+        ids[1..].to_vec()
+    );
+}
+
+/// Add an unpaid bolt11 quote to the mint and the wallet
+///
+/// The quote is not known to the fake backend, so it is never paid on its own.
+async fn add_unpaid_bolt11_quote(
+    mint: &Mint,
+    wallet: &Wallet,
+    amount: u64,
+) -> (QuoteId, SecretKey, PaymentIdentifier) {
+    let secret_key = SecretKey::generate();
+    let request = create_fake_invoice(amount * 1_000, "".to_string()).to_string();
+    let request_lookup_id = PaymentIdentifier::PaymentHash(SecretKey::generate().to_secret_bytes());
+    let expiry = unix_time() + 3600;
+
+    let quote = MintQuote::new(
+        None,
+        request.clone(),
+        CurrencyUnit::Sat,
+        Some(amount.into()),
+        expiry,
+        request_lookup_id.clone(),
+        Some(secret_key.public_key()),
+        Amount::ZERO,
+        Amount::ZERO,
+        PaymentMethod::Bolt11,
+        unix_time(),
+        vec![],
+        vec![],
+        None,
+    );
+
+    let mut tx = mint.localstore().begin_transaction().await.unwrap();
+    tx.add_mint_quote(quote.clone()).await.unwrap();
+    tx.commit().await.unwrap();
+
+    wallet
+        .localstore
+        .add_mint_quote(WalletMintQuote {
+            id: quote.id.to_string(),
+            mint_url: wallet.mint_url.clone(),
+            payment_method: PaymentMethod::Bolt11,
+            amount: Some(amount.into()),
+            unit: CurrencyUnit::Sat,
+            request,
+            state: MintQuoteState::Unpaid,
+            expiry,
+            secret_key: Some(secret_key.clone()),
+            amount_issued: Amount::ZERO,
+            amount_paid: Amount::ZERO,
+            keyset_id: None,
+            converted_from: None,
+            premint_keyset_id: None,
+            premint_counter: None,
+            premint_amounts: None,
+        })
+        .await
+        .unwrap();
+
+    (quote.id, secret_key, request_lookup_id)
+}
+
+/// Tests converting an unpaid bolt11 quote into a mining share quote:
+/// 1. The new quote keeps the amount and NUT-20 pubkey of the original and is
+///    unpaid until the pool reports its share
+/// 2. The original quote is closed and can no longer be paid or minted
+/// 3. Minting the new quote records the original in the wallet history
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_convert_bolt11_quote_to_mining_share() {
+    setup_tracing();
+    let mint_bob = create_and_start_test_mint_with_unit(
+        CurrencyUnit::Sat,
+        &[PaymentMethod::Bolt11, PaymentMethod::MiningShare],
+    )
+    .await
+    .expect("Failed to create test mint");
+    let wallet_alice = create_test_wallet_for_mint(mint_bob.clone())
+        .await
+        .expect("Failed to create test wallet");
+
+    let (quote_id, secret_key, request_lookup_id) =
+        add_unpaid_bolt11_quote(&mint_bob, &wallet_alice, 100).await;
+
+    let converted = wallet_alice
+        .convert_quote(
+            &quote_id.to_string(),
+            PaymentMethod::MiningShare,
+            QuoteConversionParams {
+                header_hash: Some(sha256::Hash::hash(b"share")),
+                description: None,
+            },
+        )
+        .await
+        .expect("Failed to convert quote");
+
+    assert_eq!(converted.payment_method, PaymentMethod::MiningShare);
+    assert_eq!(converted.amount, Some(Amount::from(100)));
+    assert_eq!(converted.converted_from, Some(quote_id.to_string()));
+    assert_eq!(
+        converted.secret_key.as_ref().map(|key| key.public_key()),
+        Some(secret_key.public_key())
+    );
+
+    let new_quote = mint_bob
+        .localstore()
+        .get_mint_quote(&QuoteId::from_str(&converted.id).unwrap())
+        .await
+        .unwrap()
+        .expect("Converted quote stored by the mint");
+    assert_eq!(new_quote.pubkey, Some(secret_key.public_key()));
+    assert_eq!(new_quote.amount, Some(Amount::from(100)));
+    assert_eq!(new_quote.amount_paid(), Amount::ZERO);
+    assert_eq!(new_quote.state(), MintQuoteState::Unpaid);
+    assert_eq!(converted.amount_paid, Amount::ZERO);
+
+    let original = mint_bob
+        .localstore()
+        .get_mint_quote(&quote_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(original.state(), MintQuoteState::Converted);
+
+    // Wallets only see the final NUT-04 state
+    let response: cashu::MintQuoteBolt11Response<QuoteId> = mint_bob
+        .check_mint_quote(&quote_id)
+        .await
+        .unwrap()
+        .try_into()
+        .unwrap();
+    assert_eq!(response.state, MintQuoteState::Issued);
+    assert_eq!(
+        wallet_alice
+            .localstore
+            .get_mint_quote(&quote_id.to_string())
+            .await
+            .unwrap()
+            .unwrap()
+            .state,
+        MintQuoteState::Converted
+    );
+
+    // A late payment of the original request is not credited
+    mint_bob
+        .pay_mint_quote_for_request_id(WaitPaymentResponse {
+            payment_identifier: request_lookup_id,
+            payment_amount: 100.into(),
+            unit: CurrencyUnit::Sat,
+            payment_id: "late-payment".to_string(),
+        })
+        .await
+        .unwrap();
+    let original = mint_bob
+        .localstore()
+        .get_mint_quote(&quote_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(original.amount_paid(), Amount::ZERO);
+
+    let keyset_id = get_keyset_id(&mint_bob).await;
+    let pre_mint = PreMintSecrets::random(keyset_id, 100.into(), &SplitTarget::default()).unwrap();
+    let mint_request = MintRequest {
+        quote: quote_id.clone(),
+        outputs: pre_mint.blinded_messages(),
+        signature: None,
+    };
+    match mint_bob.process_mint_request(mint_request).await {
+        Err(cdk::Error::ConvertedQuote) => (),
+        other => panic!("Converted quote should not be minted: {other:?}"),
+    }
+
+    // Converting again is refused
+    assert!(wallet_alice
+        .convert_quote(
+            &quote_id.to_string(),
+            PaymentMethod::MiningShare,
+            QuoteConversionParams::default(),
+        )
+        .await
+        .is_err());
+
+    // The pool credits the share once it validated it
+    mint_bob
+        .pay_mint_quote_for_request_id(WaitPaymentResponse {
+            payment_identifier: PaymentIdentifier::MiningShareHash(
+                sha256::Hash::hash(b"share").to_string(),
+            ),
+            payment_amount: 100.into(),
+            unit: CurrencyUnit::Sat,
+            payment_id: "share".to_string(),
+        })
+        .await
+        .unwrap();
+
+    let proofs = wallet_alice
+        .mint_mining_share(
+            &converted.id,
+            100.into(),
+            converted
+                .keyset_id
+                .expect("Mining share quote has a keyset"),
+            secret_key,
+            None,
+        )
+        .await
+        .expect("Failed to mint converted quote");
+    assert_eq!(proofs.total_amount().unwrap(), Amount::from(100));
+
+    let transactions = wallet_alice
+        .list_transactions(Some(TransactionDirection::Incoming))
+        .await
+        .unwrap();
+    let transaction = transactions
+        .iter()
+        .find(|tx| tx.quote_id.as_deref() == Some(converted.id.as_str()))
+        .expect("Mint transaction recorded");
+    assert_eq!(
+        transaction.metadata.get("converted_from"),
+        Some(&quote_id.to_string())
+    );
+}
+
+/// Tests that paid quotes refuse conversion
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_convert_paid_quote_refused() {
+    setup_tracing();
+    let mint_bob = create_and_start_test_mint_with_unit(
+        CurrencyUnit::Sat,
+        &[PaymentMethod::Bolt11, PaymentMethod::MiningShare],
+    )
+    .await
+    .expect("Failed to create test mint");
+    let wallet_alice = create_test_wallet_for_mint(mint_bob.clone())
+        .await
+        .expect("Failed to create test wallet");
+
+    let (quote_id, _, request_lookup_id) =
+        add_unpaid_bolt11_quote(&mint_bob, &wallet_alice, 100).await;
+
+    mint_bob
+        .pay_mint_quote_for_request_id(WaitPaymentResponse {
+            payment_identifier: request_lookup_id,
+            payment_amount: 100.into(),
+            unit: CurrencyUnit::Sat,
+            payment_id: "payment".to_string(),
+        })
+        .await
+        .unwrap();
+
+    match mint_bob
+        .convert_quote_method(
+            &quote_id,
+            PaymentMethod::MiningShare,
+            QuoteConversionParams {
+                header_hash: Some(sha256::Hash::hash(b"share")),
+                description: None,
+            },
+        )
+        .await
+    {
+        Err(cdk::Error::PaidQuote) => (),
+        other => panic!("Paid quote should not be converted: {other:?}"),
+    }
+
+    let quote = mint_bob
+        .localstore()
+        .get_mint_quote(&quote_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(quote.state(), MintQuoteState::Paid);
+    assert!(quote.converted_to.is_none());
+}
+
+/// Tests that one wallet mints and holds sat and hash proofs side by side:
+/// 1. A sat wallet mints 100 sats with bolt11
+/// 2. The same wallet mints a 64 hash mining share quote in the hash unit
+/// 3. Both balances are reported per unit and spending sats leaves the hashes untouched
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_wallet_multiple_units() {
+    setup_tracing();
+    let mint_bob = create_and_start_test_mint_with_units(&[
+        (CurrencyUnit::Sat, PaymentMethod::Bolt11),
+        (CurrencyUnit::Hash, PaymentMethod::MiningShare),
+    ])
+    .await
+    .expect("Failed to create test mint");
+    let wallet_alice = create_test_wallet_for_mint(mint_bob.clone())
+        .await
+        .expect("Failed to create test wallet");
+
+    fund_wallet(wallet_alice.clone(), 100, None)
+        .await
+        .expect("Failed to fund wallet");
+
+    let pool_key = SecretKey::generate();
+    let quote = mint_bob
+        .create_mint_mining_share_quote(MintQuoteMiningShareRequest {
+            amount: 64.into(),
+            unit: CurrencyUnit::Hash,
+            header_hash: sha256::Hash::hash(b"share"),
+            description: None,
+            pubkey: pool_key.public_key(),
+            difficulty: None,
+        })
+        .await
+        .expect("Failed to create mining share quote");
+    let keyset_id = quote.keyset_id.expect("Mining share quote has a keyset");
+
+    // The default unit refuses the hash keyset
+    match wallet_alice
+        .mint_mining_share(
+            &quote.id.to_string(),
+            64.into(),
+            keyset_id,
+            pool_key.clone(),
+            None,
+        )
+        .await
+    {
+        Err(cdk::Error::QuoteUnitMismatch { .. }) => (),
+        other => panic!("Expected unit mismatch, got {other:?}"),
+    }
+
+    let proofs = wallet_alice
+        .mint_mining_share_with_unit(
+            &quote.id.to_string(),
+            64.into(),
+            keyset_id,
+            pool_key,
+            &CurrencyUnit::Hash,
+            None,
+        )
+        .await
+        .expect("Failed to mint mining share");
+    assert_eq!(proofs.total_amount().unwrap(), Amount::from(64));
+
+    assert_eq!(
+        wallet_alice.total_balance().await.unwrap(),
+        Amount::from(100)
+    );
+    assert_eq!(
+        wallet_alice
+            .total_balance_with_unit(&CurrencyUnit::Hash)
+            .await
+            .unwrap(),
+        Amount::from(64)
+    );
+
+    let breakdowns = wallet_alice.balance_breakdowns().await.unwrap();
+    assert_eq!(breakdowns.len(), 2);
+    for breakdown in breakdowns {
+        let expected = match breakdown.unit {
+            CurrencyUnit::Sat => Amount::from(100),
+            CurrencyUnit::Hash => Amount::from(64),
+            unit => panic!("Unexpected unit {unit}"),
+        };
+        assert_eq!(breakdown.spendable, expected);
+        assert_eq!(breakdown.total, expected);
+    }
+
+    // Spending sats only selects sat proofs
+    let prepared_send = wallet_alice
+        .prepare_send(40.into(), SendOptions::default())
+        .await
+        .expect("Failed to prepare send");
+    prepared_send.confirm(None).await.expect("Failed to send");
+
+    assert_eq!(
+        wallet_alice.total_balance().await.unwrap(),
+        Amount::from(60)
+    );
+    assert_eq!(
+        wallet_alice
+            .total_balance_with_unit(&CurrencyUnit::Hash)
+            .await
+            .unwrap(),
+        Amount::from(64)
+    );
+}
+
+/// Tests that melt quotes expiring during a payout batch are re-quoted:
+/// 1. Alice quotes three invoices with a short melt quote TTL
+/// 2. The first melt goes through, then the clock passes the quote expiry
+/// 3. The other melts get fresh quotes for the same invoices and are paid
+/// 4. The transactions record both quote ids and no proofs stay reserved
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_melt_requotes_expired_quote() {
+    setup_tracing();
+    let clock = MockClock::new(unix_time());
+
+    let mint_bob = create_and_start_test_mint_with_clock(
+        CurrencyUnit::Sat,
+        &[PaymentMethod::Bolt11],
+        Arc::new(clock.clone()),
+    )
+    .await
+    .expect("Failed to create test mint");
+    mint_bob
+        .set_quote_ttl(QuoteTTL::new(10_000, 120))
+        .await
+        .unwrap();
+
+    let wallet_alice = create_test_wallet_for_mint_with_clock(
+        mint_bob,
+        CurrencyUnit::Sat,
+        Arc::new(clock.clone()),
+    )
+    .await
+    .expect("Failed to create test wallet");
+
+    fund_wallet(wallet_alice.clone(), 100, None)
+        .await
+        .expect("Failed to fund wallet");
+
+    let mut melt_quotes = Vec::new();
+    for _ in 0..3 {
+        let invoice = create_fake_invoice(10_000, "".to_string());
+        melt_quotes.push(
+            wallet_alice
+                .melt_quote(invoice.to_string(), None)
+                .await
+                .unwrap(),
+        );
+    }
+
+    wallet_alice.melt(&melt_quotes[0].id).await.unwrap();
+
+    // The remaining quotes lapse while the invoices stay valid
+    clock.advance(121);
+
+    for melt_quote in &melt_quotes[1..] {
+        let melted = wallet_alice.melt(&melt_quote.id).await.unwrap();
+        assert_eq!(melted.state, MeltQuoteState::Paid);
+        assert_eq!(melted.amount, Amount::from(10));
+
+        // The expired quote is replaced
+        assert!(wallet_alice
+            .localstore
+            .get_melt_quote(&melt_quote.id)
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    let transactions = wallet_alice
+        .list_transactions(Some(TransactionDirection::Outgoing))
+        .await
+        .unwrap();
+    assert_eq!(transactions.len(), 3);
+    for melt_quote in &melt_quotes[1..] {
+        let transaction = transactions
+            .iter()
+            .find(|tx| tx.metadata.get("requoted_from") == Some(&melt_quote.id))
+            .expect("Re-quoted melt transaction recorded");
+        assert_ne!(transaction.quote_id.as_ref(), Some(&melt_quote.id));
+        assert!(transaction.quote_id.is_some());
+    }
+
+    assert!(wallet_alice.get_pending_proofs().await.unwrap().is_empty());
+    assert!(wallet_alice.get_reserved_proofs().await.unwrap().is_empty());
+}
+
+/// Tests that a melt quote whose invoice expired is not re-quoted:
+/// 1. Alice quotes an invoice
+/// 2. The clock passes the expiry of the invoice
+/// 3. Melting fails with an expired invoice error and leaves the balance untouched
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_melt_expired_invoice_not_requoted() {
+    setup_tracing();
+    let clock = MockClock::new(unix_time());
+
+    let mint_bob = create_and_start_test_mint_with_clock(
+        CurrencyUnit::Sat,
+        &[PaymentMethod::Bolt11],
+        Arc::new(clock.clone()),
+    )
+    .await
+    .expect("Failed to create test mint");
+
+    let wallet_alice = create_test_wallet_for_mint_with_clock(
+        mint_bob,
+        CurrencyUnit::Sat,
+        Arc::new(clock.clone()),
+    )
+    .await
+    .expect("Failed to create test wallet");
+
+    fund_wallet(wallet_alice.clone(), 100, None)
+        .await
+        .expect("Failed to fund wallet");
+
+    let invoice = create_fake_invoice(10_000, "".to_string());
+    let expires_at = invoice.expires_at().unwrap().as_secs();
+    let melt_quote = wallet_alice
+        .melt_quote(invoice.to_string(), None)
+        .await
+        .unwrap();
+
+    clock.set(expires_at.max(melt_quote.expiry) + 1);
+
+    match wallet_alice.melt(&melt_quote.id).await {
+        Err(cdk::Error::InvoiceExpired(at)) => assert_eq!(at, expires_at),
+        other => panic!("Expected expired invoice, got {other:?}"),
+    }
+
+    assert_eq!(
+        wallet_alice.total_balance().await.unwrap(),
+        Amount::from(100)
+    );
+    assert!(wallet_alice.get_pending_proofs().await.unwrap().is_empty());
+    assert!(wallet_alice.get_reserved_proofs().await.unwrap().is_empty());
+}
+
+/// Tests that a stale quote state cannot move a stored quote backwards
+///
+/// 1. A notification marks the quote as paid
+/// 2. A late notification claiming the quote is unpaid is rejected and recorded
+/// 3. The quote still moves forward to issued
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_quote_state_regression_rejected() {
+    setup_tracing();
+    let mint_bob = create_and_start_test_mint()
+        .await
+        .expect("Failed to create test mint");
+    let wallet_alice = create_test_wallet_for_mint(mint_bob)
+        .await
+        .expect("Failed to create test wallet");
+
+    let mint_quote = wallet_alice.mint_quote(100.into(), None).await.unwrap();
+    let notification = |state| {
+        NotificationPayload::MintQuoteBolt11Response(cashu::MintQuoteBolt11Response {
+            quote: mint_quote.id.clone(),
+            request: mint_quote.request.clone(),
+            amount: mint_quote.amount,
+            unit: Some(mint_quote.unit.clone()),
+            state,
+            expiry: Some(mint_quote.expiry),
+            pubkey: None,
+        })
+    };
+    let stored_state = || async {
+        wallet_alice
+            .localstore
+            .get_mint_quote(&mint_quote.id)
+            .await
+            .unwrap()
+            .expect("Quote stored")
+            .state
+    };
+
+    wallet_alice
+        .apply_quote_notification(&notification(MintQuoteState::Paid))
+        .await
+        .unwrap();
+    assert_eq!(stored_state().await, MintQuoteState::Paid);
+    assert!(wallet_alice.quote_state_conflicts().unwrap().is_empty());
+
+    wallet_alice
+        .apply_quote_notification(&notification(MintQuoteState::Unpaid))
+        .await
+        .unwrap();
+    assert_eq!(stored_state().await, MintQuoteState::Paid);
+
+    let conflicts = wallet_alice.quote_state_conflicts().unwrap();
+    assert_eq!(conflicts.len(), 1);
+    assert_eq!(conflicts[0].quote_id, mint_quote.id);
+    assert_eq!(conflicts[0].stored_state, MintQuoteState::Paid);
+    assert_eq!(
+        conflicts[0].stored_source,
+        Some(QuoteStateSource::Websocket)
+    );
+    assert_eq!(conflicts[0].rejected_state, MintQuoteState::Unpaid);
+    assert_eq!(conflicts[0].rejected_source, QuoteStateSource::Websocket);
+
+    wallet_alice
+        .apply_quote_notification(&notification(MintQuoteState::Issued))
+        .await
+        .unwrap();
+    assert_eq!(stored_state().await, MintQuoteState::Issued);
+    assert_eq!(wallet_alice.quote_state_conflicts().unwrap().len(), 1);
+}
+
+/// Tests that inconsistent mint info is reported and bounded before storing
+///
+/// The mint advertises a URL other than the one the wallet queried, a mint
+/// method in a unit it has no keyset for and an oversized motd.
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_mint_info_validation() {
+    setup_tracing();
+    let mint_bob = create_and_start_test_mint()
+        .await
+        .expect("Failed to create test mint");
+    let wallet_alice = create_test_wallet_for_mint(mint_bob.clone())
+        .await
+        .expect("Failed to create test wallet");
+    wallet_alice.refresh_keysets().await.unwrap();
+
+    let mut mint_info = mint_bob.mint_info().await.unwrap();
+    mint_info.urls = Some(vec!["https://proxy.example.com".to_string()]);
+    mint_info.motd = Some("a".repeat(MAX_MOTD_LEN + 1));
+    mint_info.nuts.nut04.methods.push(MintMethodSettings {
+        method: PaymentMethod::Bolt11,
+        unit: CurrencyUnit::Usd,
+        min_amount: None,
+        max_amount: None,
+        options: None,
+    });
+    mint_bob.set_mint_info(mint_info).await.unwrap();
+
+    let (stored_info, report) = wallet_alice
+        .fetch_mint_info_with_report()
+        .await
+        .unwrap()
+        .expect("Mint info fetched");
+
+    let alias = MintUrl::from_str("https://proxy.example.com").unwrap();
+    assert!(report
+        .warnings
+        .contains(&MintInfoWarning::AdvertisedUrlMismatch {
+            queried: wallet_alice.mint_url.clone(),
+            advertised: vec!["https://proxy.example.com".to_string()],
+        }));
+    assert_eq!(report.aliases, vec![alias.clone()]);
+    assert_eq!(wallet_alice.mint_aliases().unwrap(), vec![alias.clone()]);
+
+    // Storage stays keyed by the queried URL
+    assert!(wallet_alice
+        .localstore
+        .get_mint(wallet_alice.mint_url.clone())
+        .await
+        .unwrap()
+        .is_some());
+    assert!(wallet_alice
+        .localstore
+        .get_mint(alias)
+        .await
+        .unwrap()
+        .is_none());
+
+    assert!(report
+        .warnings
+        .contains(&MintInfoWarning::UnitWithoutKeyset {
+            method: PaymentMethod::Bolt11,
+            unit: CurrencyUnit::Usd,
+        }));
+    assert!(!report
+        .warnings
+        .contains(&MintInfoWarning::UnitWithoutKeyset {
+            method: PaymentMethod::Bolt11,
+            unit: CurrencyUnit::Sat,
+        }));
+
+    assert!(report.warnings.contains(&MintInfoWarning::FieldTruncated {
+        field: "motd",
+        len: MAX_MOTD_LEN + 1,
+    }));
+    assert_eq!(stored_info.motd.map(|motd| motd.len()), Some(MAX_MOTD_LEN));
+}
+
+/// Tests that an interrupted restore resumes from the stored progress
+///
+/// 1. Alice funds proofs in two keysets
+/// 2. A restore from her seed is interrupted after the first keyset by a
+///    client that fails further restore requests
+/// 3. The resumed restore does not rescan any counter and recovers the same
+///    amount as a restore that was never interrupted
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_restore_resumes_after_interruption() {
+    setup_tracing();
+    let mint_bob = create_and_start_test_mint()
+        .await
+        .expect("Failed to create test mint");
+    let seed = Mnemonic::generate(12).unwrap().to_seed_normalized("");
+
+    let wallet_alice = create_test_wallet_with_connection(
+        DirectMintConnection::new(mint_bob.clone()),
+        CurrencyUnit::Sat,
+        seed,
+        Arc::new(SystemClock),
+    )
+    .await
+    .expect("Failed to create test wallet");
+    fund_wallet(wallet_alice.clone(), 100, None).await.unwrap();
+    mint_bob
+        .rotate_keyset(CurrencyUnit::Sat, 32, 0)
+        .await
+        .unwrap();
+    fund_wallet(wallet_alice.clone(), 50, None).await.unwrap();
+
+    let options = RestoreOptions::default().parallelism(1);
+
+    // Restore that is never interrupted
+    let connection = DirectMintConnection::new(mint_bob.clone());
+    let uninterrupted_requests = connection.restore_requests.clone();
+    let wallet_uninterrupted = create_test_wallet_with_connection(
+        connection,
+        CurrencyUnit::Sat,
+        seed,
+        Arc::new(SystemClock),
+    )
+    .await
+    .unwrap();
+    let expected = wallet_uninterrupted
+        .restore_with_options(options.clone())
+        .await
+        .unwrap();
+    assert_eq!(expected, Amount::from(150));
+    let uninterrupted_requests = uninterrupted_requests.load(Ordering::SeqCst);
+
+    // A keyset with signatures takes one batch with signatures and three
+    // empty batches, fail once the first keyset is done
+    let connection = DirectMintConnection::new(mint_bob.clone()).with_restore_limit(4);
+    let wallet_bob = create_test_wallet_with_connection(
+        connection,
+        CurrencyUnit::Sat,
+        seed,
+        Arc::new(SystemClock),
+    )
+    .await
+    .unwrap();
+    assert!(wallet_bob
+        .restore_with_options(options.clone())
+        .await
+        .is_err());
+
+    let keysets = wallet_bob.load_mint_keysets().await.unwrap();
+    let mut stored_progress = HashMap::new();
+    for keyset in &keysets {
+        if let Some(progress) = wallet_bob
+            .localstore
+            .get_restore_progress(&keyset.id)
+            .await
+            .unwrap()
+        {
+            stored_progress.insert(keyset.id, progress);
+        }
+    }
+    assert!(stored_progress.values().any(|progress| progress.complete));
+
+    // Resume with the same localstore and a working client
+    let connection = DirectMintConnection::new(mint_bob.clone());
+    let resumed_requests = connection.restore_requests.clone();
+    let wallet_bob = WalletBuilder::new()
+        .mint_url(wallet_bob.mint_url.clone())
+        .unit(CurrencyUnit::Sat)
+        .localstore(wallet_bob.localstore.clone())
+        .seed(seed)
+        .client(connection)
+        .build()
+        .unwrap();
+
+    let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+    let restored = wallet_bob
+        .restore_with_options(options.progress(sender))
+        .await
+        .unwrap();
+    assert_eq!(restored, expected);
+
+    // Every request of the interrupted run that succeeded is not repeated
+    assert_eq!(
+        4 + resumed_requests.load(Ordering::SeqCst),
+        uninterrupted_requests
+    );
+
+    let mut first_update = HashMap::new();
+    while let Ok(update) = receiver.try_recv() {
+        first_update
+            .entry(update.keyset_id)
+            .or_insert(update.counters_scanned);
+    }
+    for (keyset_id, progress) in &stored_progress {
+        assert!(!progress.complete || !first_update.contains_key(keyset_id));
+        if let Some(counters_scanned) = first_update.get(keyset_id) {
+            assert!(*counters_scanned > progress.next_counter);
+        }
+    }
+
+    // Progress is cleared once the restore completed
+    for keyset in &keysets {
+        assert!(wallet_bob
+            .localstore
+            .get_restore_progress(&keyset.id)
+            .await
+            .unwrap()
+            .is_none());
+    }
+}
+
+/// Tests that the restore gap limit is configurable
+///
+/// Alice leaves a gap of 500 counters between two fundings, larger than the
+/// default gap limit of 300. A restore with the default limit stops before the
+/// second funding, a restore with a raised limit recovers both.
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_restore_with_raised_gap_limit() {
+    setup_tracing();
+    let mint_bob = create_and_start_test_mint()
+        .await
+        .expect("Failed to create test mint");
+    let seed = Mnemonic::generate(12).unwrap().to_seed_normalized("");
+
+    let wallet_alice = create_test_wallet_with_connection(
+        DirectMintConnection::new(mint_bob.clone()),
+        CurrencyUnit::Sat,
+        seed,
+        Arc::new(SystemClock),
+    )
+    .await
+    .expect("Failed to create test wallet");
+    fund_wallet(wallet_alice.clone(), 100, None).await.unwrap();
+    let keyset_id = wallet_alice.fetch_active_keyset().await.unwrap().id;
+    wallet_alice
+        .localstore
+        .increment_keyset_counter(&keyset_id, 500)
+        .await
+        .unwrap();
+    fund_wallet(wallet_alice.clone(), 50, None).await.unwrap();
+
+    let wallet_default = create_test_wallet_with_connection(
+        DirectMintConnection::new(mint_bob.clone()),
+        CurrencyUnit::Sat,
+        seed,
+        Arc::new(SystemClock),
+    )
+    .await
+    .unwrap();
+    assert_eq!(wallet_default.restore().await.unwrap(), Amount::from(100));
+
+    let wallet_raised = create_test_wallet_with_connection(
+        DirectMintConnection::new(mint_bob.clone()),
+        CurrencyUnit::Sat,
+        seed,
+        Arc::new(SystemClock),
+    )
+    .await
+    .unwrap();
+    let restored = wallet_raised
+        .restore_with_options(RestoreOptions::default().gap_limit(600))
+        .await
+        .unwrap();
+    assert_eq!(restored, Amount::from(150));
+    assert_eq!(
+        wallet_raised.total_balance().await.unwrap(),
+        Amount::from(150)
+    );
+
+    // New secrets are derived past the highest restored counter
+    let counter = wallet_raised
+        .localstore
+        .increment_keyset_counter(&keyset_id, 0)
+        .await
+        .unwrap();
+    assert!(counter > 500);
+}
+
+/// Tests that a wallet subscribed to a mining share quote is notified of its state changes:
+/// 1. The mint advertises mining share quote subscriptions
+/// 2. The pool creates a paid mining share quote
+/// 3. The subscription yields the paid quote
+/// 4. After minting, the subscription yields the issued quote
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_subscribe_mining_share_quotes() {
+    setup_tracing();
+    let mint_bob = create_and_start_test_mint_with_unit(
+        CurrencyUnit::Sat,
+        &[PaymentMethod::Bolt11, PaymentMethod::MiningShare],
+    )
+    .await
+    .expect("Failed to create test mint");
+    let wallet_alice = create_test_wallet_for_mint(mint_bob.clone())
+        .await
+        .expect("Failed to create test wallet");
+
+    let mint_info = wallet_alice
+        .fetch_mint_info()
+        .await
+        .expect("Failed to fetch mint info")
+        .expect("Mint info");
+    assert!(mint_info.nuts.nut17.supported.iter().any(|supported| {
+        supported.method == PaymentMethod::MiningShare
+            && supported.unit == CurrencyUnit::Sat
+            && supported
+                .commands
+                .contains(&cdk::nuts::nut17::WsCommand::MiningShareMintQuote)
+    }));
+
+    let pool_key = SecretKey::generate();
+    let quote = mint_bob
+        .create_mint_mining_share_quote(MintQuoteMiningShareRequest {
+            amount: 64.into(),
+            unit: CurrencyUnit::Sat,
+            header_hash: sha256::Hash::hash(b"subscribed share"),
+            description: None,
+            pubkey: pool_key.public_key(),
+            difficulty: None,
+        })
+        .await
+        .expect("Failed to create mining share quote");
+    let quote_id = quote.id.to_string();
+    let keyset_id = quote.keyset_id.expect("Mining share quote has a keyset");
+
+    let mut subscription = wallet_alice
+        .subscribe_mining_share_quotes(vec![quote_id.clone()])
+        .await;
+
+    let update = tokio::time::timeout(Duration::from_secs(30), subscription.recv())
+        .await
+        .expect("Timeout waiting for paid quote")
+        .expect("Subscription closed");
+    assert_eq!(update.quote, quote_id);
+    assert_eq!(update.state, nutXX::QuoteState::Paid);
+
+    wallet_alice
+        .mint_mining_share(&quote_id, 64.into(), keyset_id, pool_key, None)
+        .await
+        .expect("Failed to mint mining share");
+
+    let update = tokio::time::timeout(Duration::from_secs(30), subscription.recv())
+        .await
+        .expect("Timeout waiting for issued quote")
+        .expect("Subscription closed");
+    assert_eq!(update.quote, quote_id);
+    assert_eq!(update.state, nutXX::QuoteState::Issued);
+    assert_eq!(update.amount_issued, Amount::from(64));
+}
+
+/// Tests that a notification for an unknown quote is kept as a dead letter
+///
+/// 1. A paid notification arrives for a quote the wallet no longer stores
+/// 2. Retrying leaves the dead letter in place while the quote is unknown
+/// 3. Once the quote is stored again the retry applies it and clears the entry
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_unknown_quote_notification_dead_lettered() {
+    setup_tracing();
+    let mint_bob = create_and_start_test_mint()
+        .await
+        .expect("Failed to create test mint");
+    let wallet_alice = create_test_wallet_for_mint(mint_bob)
+        .await
+        .expect("Failed to create test wallet");
+
+    let mint_quote = wallet_alice.mint_quote(100.into(), None).await.unwrap();
+    wallet_alice
+        .localstore
+        .remove_mint_quote(&mint_quote.id)
+        .await
+        .unwrap();
+
+    let notification =
+        NotificationPayload::MintQuoteBolt11Response(cashu::MintQuoteBolt11Response {
+            quote: mint_quote.id.clone(),
+            request: mint_quote.request.clone(),
+            amount: mint_quote.amount,
+            unit: Some(mint_quote.unit.clone()),
+            state: MintQuoteState::Paid,
+            expiry: Some(mint_quote.expiry),
+            pubkey: None,
+        });
+
+    wallet_alice
+        .handle_notification(Some("sub".to_string()), &notification)
+        .await
+        .unwrap();
+
+    let dead_letters = wallet_alice.dead_letters().await.unwrap();
+    assert_eq!(dead_letters.len(), 1);
+    assert_eq!(dead_letters[0].reason, DeadLetterReason::UnknownQuote);
+    assert_eq!(dead_letters[0].sub_id.as_deref(), Some("sub"));
+
+    assert_eq!(wallet_alice.retry_dead_letters().await.unwrap(), 0);
+    assert_eq!(wallet_alice.dead_letters().await.unwrap().len(), 1);
+
+    wallet_alice
+        .localstore
+        .add_mint_quote(mint_quote.clone())
+        .await
+        .unwrap();
+
+    assert_eq!(wallet_alice.retry_dead_letters().await.unwrap(), 1);
+    assert!(wallet_alice.dead_letters().await.unwrap().is_empty());
+
+    let stored = wallet_alice
+        .localstore
+        .get_mint_quote(&mint_quote.id)
+        .await
+        .unwrap()
+        .expect("Quote stored");
+    assert_eq!(stored.state, MintQuoteState::Paid);
+}
+
+/// Tests that a flood of unprocessable notifications evicts the oldest dead
+/// letters once the cap is reached
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_dead_letters_capped() {
+    setup_tracing();
+    let mint_bob = create_and_start_test_mint()
+        .await
+        .expect("Failed to create test mint");
+    let wallet_alice = create_test_wallet_for_mint(mint_bob)
+        .await
+        .expect("Failed to create test wallet");
+
+    let flood = MAX_DEAD_LETTERS + 5;
+    for i in 0..flood {
+        let notification =
+            NotificationPayload::MintQuoteBolt11Response(cashu::MintQuoteBolt11Response {
+                quote: format!("unknown-{i}"),
+                request: String::new(),
+                amount: None,
+                unit: None,
+                state: MintQuoteState::Paid,
+                expiry: None,
+                pubkey: None,
+            });
+        wallet_alice
+            .handle_notification(None, &notification)
+            .await
+            .unwrap();
+    }
+
+    let dead_letters = wallet_alice.dead_letters().await.unwrap();
+    assert_eq!(dead_letters.len(), MAX_DEAD_LETTERS);
+    assert!(dead_letters[0].payload.contains("\"unknown-5\""));
+    assert!(dead_letters
+        .last()
+        .expect("Dead letters stored")
+        .payload
+        .contains(&format!("\"unknown-{}\"", flood - 1)));
+    assert!(dead_letters.windows(2).all(|pair| pair[0].id < pair[1].id));
+}
+
+/// Tests that a terminal notification for a quote the wallet removed is
+/// ignored rather than kept as a dead letter
+///
+/// 1. An issued notification arrives for a quote the wallet no longer stores
+/// 2. The notification is applied without error and no dead letter is stored
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_terminal_notification_for_removed_quote_ignored() {
+    setup_tracing();
+    let mint_bob = create_and_start_test_mint()
+        .await
+        .expect("Failed to create test mint");
+    let wallet_alice = create_test_wallet_for_mint(mint_bob)
+        .await
+        .expect("Failed to create test wallet");
+
+    let mint_quote = wallet_alice.mint_quote(100.into(), None).await.unwrap();
+    wallet_alice
+        .localstore
+        .remove_mint_quote(&mint_quote.id)
+        .await
+        .unwrap();
+
+    let notification =
+        NotificationPayload::MintQuoteBolt11Response(cashu::MintQuoteBolt11Response {
+            quote: mint_quote.id.clone(),
+            request: mint_quote.request.clone(),
+            amount: mint_quote.amount,
+            unit: Some(mint_quote.unit.clone()),
+            state: MintQuoteState::Issued,
+            expiry: Some(mint_quote.expiry),
+            pubkey: None,
+        });
+
+    wallet_alice
+        .handle_notification(Some("sub".to_string()), &notification)
+        .await
+        .unwrap();
+
+    assert!(wallet_alice.dead_letters().await.unwrap().is_empty());
+    assert!(wallet_alice
+        .localstore
+        .get_mint_quote(&mint_quote.id)
+        .await
+        .unwrap()
+        .is_none());
+}
+
+/// Corrupts a field of a mining share quote response
+type MiningShareQuoteTamper = fn(&mut MintQuoteMiningShareResponse<String>);
+
+/// Tests that a mining share quote response not matching the request is
+/// rejected before the quote is stored
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_mining_share_quote_response_validated() {
+    setup_tracing();
+    let mint_bob = create_and_start_test_mint_with_unit(
+        CurrencyUnit::Sat,
+        &[PaymentMethod::Bolt11, PaymentMethod::MiningShare],
+    )
+    .await
+    .expect("Failed to create test mint");
+
+    let corruptions: [(&str, MiningShareQuoteTamper); 6] = [
+        ("amount", |response| {
+            response.amount = Some(Amount::from(65))
+        }),
+        ("unit", |response| response.unit = Some(CurrencyUnit::Msat)),
+        ("request", |response| response.request = "other".to_string()),
+        ("pubkey", |response| {
+            response.pubkey = SecretKey::generate().public_key()
+        }),
+        ("amount_issued", |response| {
+            response.amount_issued = Amount::from(1)
+        }),
+        ("expiry", |response| response.expiry = Some(0)),
+    ];
+
+    for (field, tamper) in corruptions {
+        let connection =
+            DirectMintConnection::new(mint_bob.clone()).with_mining_share_quote_tamper(tamper);
+        let wallet = create_test_wallet_with_connection(
+            connection,
+            CurrencyUnit::Sat,
+            Mnemonic::generate(12).unwrap().to_seed_normalized(""),
+            Arc::new(SystemClock),
+        )
+        .await
+        .expect("Failed to create test wallet");
+
+        let result = wallet
+            .mint_quote_mining_share(64.into(), sha256::Hash::hash(field.as_bytes()), None)
+            .await;
+        match result {
+            Err(cdk::Error::MintResponseInvalid { field: invalid, .. }) => {
+                assert_eq!(invalid, field)
+            }
+            other => panic!("Expected invalid {field}, got {other:?}"),
+        }
+
+        assert!(wallet
+            .localstore
+            .get_mint_quotes()
+            .await
+            .unwrap()
+            .is_empty());
+    }
+}
+
+/// Tests that a mining share quote status not matching the stored quote, or
+/// inconsistent in itself, is rejected and leaves the localstore untouched
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_mining_share_quote_status_validated() {
+    setup_tracing();
+    let mint_bob = create_and_start_test_mint_with_unit(
+        CurrencyUnit::Sat,
+        &[PaymentMethod::Bolt11, PaymentMethod::MiningShare],
+    )
+    .await
+    .expect("Failed to create test mint");
+    let wallet_alice = create_test_wallet_for_mint(mint_bob.clone())
+        .await
+        .expect("Failed to create test wallet");
+
+    let quote = wallet_alice
+        .mint_quote_mining_share(64.into(), sha256::Hash::hash(b"validated share"), None)
+        .await
+        .unwrap();
+    assert_eq!(quote.state, MintQuoteState::Paid);
+    assert!(quote.secret_key.is_some());
+
+    let corruptions: [(&str, MiningShareQuoteTamper); 5] = [
+        ("amount", |response| {
+            response.amount = Some(Amount::from(128))
+        }),
+        ("unit", |response| response.unit = Some(CurrencyUnit::Msat)),
+        ("request", |response| response.request = "other".to_string()),
+        ("pubkey", |response| {
+            response.pubkey = SecretKey::generate().public_key()
+        }),
+        ("amount_issued", |response| {
+            response.amount_issued = Amount::from(65)
+        }),
+    ];
+
+    for (field, tamper) in corruptions {
+        let wallet = WalletBuilder::new()
+            .mint_url(wallet_alice.mint_url.clone())
+            .unit(CurrencyUnit::Sat)
+            .localstore(wallet_alice.localstore.clone())
+            .seed(Mnemonic::generate(12).unwrap().to_seed_normalized(""))
+            .client(
+                DirectMintConnection::new(mint_bob.clone()).with_mining_share_quote_tamper(tamper),
+            )
+            .build()
+            .unwrap();
+
+        match wallet.mint_quote_state_mining_share(&quote.id).await {
+            Err(cdk::Error::MintResponseInvalid { field: invalid, .. }) => {
+                assert_eq!(invalid, field)
+            }
+            other => panic!("Expected invalid {field}, got {other:?}"),
+        }
+
+        assert_eq!(
+            wallet_alice
+                .localstore
+                .get_mint_quote(&quote.id)
+                .await
+                .unwrap(),
+            Some(quote.clone())
+        );
+    }
+
+    // A quote learned by lookup is checked before it is stored
+    let connection = DirectMintConnection::new(mint_bob.clone())
+        .with_mining_share_quote_tamper(|response| response.amount_issued = Amount::from(65));
+    let wallet_carol = create_test_wallet_with_connection(
+        connection,
+        CurrencyUnit::Sat,
+        Mnemonic::generate(12).unwrap().to_seed_normalized(""),
+        Arc::new(SystemClock),
+    )
+    .await
+    .expect("Failed to create test wallet");
+    assert!(matches!(
+        wallet_carol.mint_quote_state_mining_share(&quote.id).await,
+        Err(cdk::Error::MintResponseInvalid {
+            field: "amount_issued",
+            ..
+        })
+    ));
+    assert!(wallet_carol
+        .localstore
+        .get_mint_quote(&quote.id)
+        .await
+        .unwrap()
+        .is_none());
+
+    let status = wallet_alice
+        .mint_quote_state_mining_share(&quote.id)
+        .await
+        .unwrap();
+    assert_eq!(status.response.amount, Some(Amount::from(64)));
+}
+
+/// Tests that the transaction history is rebuilt after it was lost:
+/// 1. Alice mints two mining share quotes and sends 40 sats
+/// 2. All transactions but the first mint are dropped
+/// 3. The rebuild restores the other mint exactly and estimates the send
+/// 4. Rebuilding again adds nothing and the survivor is untouched
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_rebuild_transactions() {
+    setup_tracing();
+    let mint_bob = create_and_start_test_mint_with_unit(
+        CurrencyUnit::Sat,
+        &[PaymentMethod::Bolt11, PaymentMethod::MiningShare],
+    )
+    .await
+    .expect("Failed to create test mint");
+    let wallet_alice = create_test_wallet_for_mint(mint_bob.clone())
+        .await
+        .expect("Failed to create test wallet");
+
+    for (amount, share) in [(64u64, b"first share"), (32, b"other share")] {
+        let quote = wallet_alice
+            .mint_quote_mining_share(amount.into(), sha256::Hash::hash(share), None)
+            .await
+            .unwrap();
+        wallet_alice
+            .mint_mining_share(
+                &quote.id,
+                amount.into(),
+                quote.keyset_id.expect("Mining share quote has a keyset"),
+                quote
+                    .secret_key
+                    .expect("Mining share quote has a secret key"),
+                None,
+            )
+            .await
+            .expect("Failed to mint mining share");
+    }
+
+    wallet_alice
+        .prepare_send(Amount::from(40), SendOptions::default())
+        .await
+        .unwrap()
+        .confirm(None)
+        .await
+        .unwrap();
+
+    let original = wallet_alice
+        .list_transactions(Some(TransactionDirection::Incoming))
+        .await
+        .unwrap();
+    assert_eq!(original.len(), 2);
+
+    // A corrupted store loses every transaction but one
+    let survivor = original[0].clone();
+    for transaction in wallet_alice.list_transactions(None).await.unwrap() {
+        if transaction.id() != survivor.id() {
+            wallet_alice
+                .localstore
+                .remove_transaction(transaction.id())
+                .await
+                .unwrap();
+        }
+    }
+
+    let summary = wallet_alice.rebuild_transactions().await.unwrap();
+    assert_eq!(summary.kept, 1);
+    assert_eq!(summary.from_mint_quotes, 1);
+    assert_eq!(summary.from_melt_quotes, 0);
+    assert_eq!(summary.from_spent_proofs, 1);
+    assert_eq!(summary.unattributed_incoming, Amount::ZERO);
+    assert_eq!(summary.unattributed_outgoing, Amount::ZERO);
+
+    let incoming = wallet_alice
+        .list_transactions(Some(TransactionDirection::Incoming))
+        .await
+        .unwrap();
+    assert_eq!(incoming.len(), 2);
+    assert!(incoming.contains(&survivor));
+    for transaction in &original {
+        let rebuilt = incoming
+            .iter()
+            .find(|rebuilt| rebuilt.quote_id == transaction.quote_id)
+            .expect("Incoming transaction rebuilt");
+        assert_eq!(rebuilt.amount, transaction.amount);
+        assert_eq!(rebuilt.fee, transaction.fee);
+        assert_eq!(rebuilt.unit, transaction.unit);
+        assert_eq!(
+            rebuilt.metadata.contains_key(REBUILT_METADATA_KEY),
+            rebuilt != &survivor
+        );
+    }
+
+    let outgoing = wallet_alice
+        .list_transactions(Some(TransactionDirection::Outgoing))
+        .await
+        .unwrap();
+    assert!(!outgoing.is_empty());
+    assert!(outgoing.iter().all(|transaction| {
+        transaction.metadata.contains_key(REBUILT_METADATA_KEY)
+            && transaction.metadata.contains_key(ESTIMATED_METADATA_KEY)
+    }));
+    assert_eq!(
+        Amount::try_sum(outgoing.iter().map(|transaction| transaction.amount)).unwrap(),
+        Amount::from(40)
+    );
+
+    let summary = wallet_alice.rebuild_transactions().await.unwrap();
+    assert_eq!(summary.kept, 3);
+    assert!(summary.transactions.is_empty());
+    assert!(wallet_alice
+        .list_transactions(Some(TransactionDirection::Incoming))
+        .await
+        .unwrap()
+        .contains(&survivor));
+}
+
+/// Tests that amounts are checked against the unit of the operation:
+/// 1. A sat wallet refuses amounts in msat passed to the checked methods
+/// 2. Without strict amounts the methods taking bare amounts still work
+/// 3. A wallet with strict amounts refuses bare amounts but accepts checked ones
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_strict_amounts() {
+    setup_tracing();
+    let mint_bob = create_and_start_test_mint_with_unit(
+        CurrencyUnit::Sat,
+        &[PaymentMethod::Bolt11, PaymentMethod::MiningShare],
+    )
+    .await
+    .expect("Failed to create test mint");
+    let wallet_alice = create_test_wallet_for_mint(mint_bob.clone())
+        .await
+        .expect("Failed to create test wallet");
+    fund_wallet(wallet_alice.clone(), 100, None)
+        .await
+        .expect("Failed to fund wallet");
+    assert!(!wallet_alice.strict_amounts());
+
+    let msat = UnitAmount::new(10_000u64, CurrencyUnit::Msat);
+    let invoice = create_fake_invoice(10_000, "".to_string()).to_string();
+    assert!(matches!(
+        wallet_alice
+            .mint_quote_checked(msat.clone(), None, None)
+            .await,
+        Err(cdk::Error::UnitMismatch)
+    ));
+    assert!(matches!(
+        wallet_alice
+            .prepare_send_checked(msat.clone(), SendOptions::default())
+            .await,
+        Err(cdk::Error::UnitMismatch)
+    ));
+    assert!(matches!(
+        wallet_alice
+            .melt_quote_checked(invoice.clone(), Some(msat.clone()))
+            .await,
+        Err(cdk::Error::UnitMismatch)
+    ));
+
+    // Units only change through an explicit conversion
+    let sat = msat.convert_to(&CurrencyUnit::Sat).unwrap();
+    let quote = wallet_alice
+        .mint_quote_checked(sat, None, None)
+        .await
+        .unwrap();
+    assert_eq!(quote.amount, Some(Amount::from(10)));
+
+    wallet_alice.mint_quote(10.into(), None).await.unwrap();
+    wallet_alice
+        .prepare_send(10.into(), SendOptions::default())
+        .await
+        .unwrap()
+        .cancel()
+        .await
+        .unwrap();
+
+    let wallet_strict = WalletBuilder::new()
+        .mint_url(wallet_alice.mint_url.clone())
+        .unit(CurrencyUnit::Sat)
+        .localstore(wallet_alice.localstore.clone())
+        .seed(Mnemonic::generate(12).unwrap().to_seed_normalized(""))
+        .client(DirectMintConnection::new(mint_bob.clone()))
+        .strict_amounts(true)
+        .build()
+        .unwrap();
+    assert!(wallet_strict.strict_amounts());
+
+    let (quote_id, _, _) = add_unpaid_bolt11_quote(&mint_bob, &wallet_strict, 100).await;
+    let conversion = QuoteConversionParams {
+        header_hash: Some(sha256::Hash::hash(b"strict share")),
+        description: None,
+    };
+
+    let refusals = [
+        (
+            "mint_quote_checked",
+            wallet_strict.mint_quote(10.into(), None).await.err(),
+        ),
+        (
+            "prepare_send_checked",
+            wallet_strict
+                .prepare_send(10.into(), SendOptions::default())
+                .await
+                .err(),
+        ),
+        (
+            "melt_quote_checked",
+            wallet_strict
+                .melt_quote(invoice.clone(), Some(MeltOptions::new_mpp(5_000)))
+                .await
+                .err(),
+        ),
+        (
+            "convert_quote_checked",
+            wallet_strict
+                .convert_quote(
+                    &quote_id.to_string(),
+                    PaymentMethod::MiningShare,
+                    conversion.clone(),
+                )
+                .await
+                .err(),
+        ),
+    ];
+    for (checked, err) in refusals {
+        match err {
+            Some(cdk::Error::StrictAmounts(method)) => assert_eq!(method, checked),
+            other => panic!("Expected {checked} to be required, got {other:?}"),
+        }
+    }
+
+    let quote = wallet_strict
+        .mint_quote_checked(UnitAmount::new(10u64, CurrencyUnit::Sat), None, None)
+        .await
+        .unwrap();
+    assert_eq!(quote.amount, Some(Amount::from(10)));
+
+    let send = wallet_strict
+        .prepare_send_checked(
+            UnitAmount::new(10u64, CurrencyUnit::Sat),
+            SendOptions::default(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(send.amount(), Amount::from(10));
+    send.cancel().await.unwrap();
+
+    assert!(matches!(
+        wallet_strict
+            .convert_quote_checked(
+                &quote_id.to_string(),
+                UnitAmount::new(100_000u64, CurrencyUnit::Msat),
+                PaymentMethod::MiningShare,
+                conversion.clone(),
+            )
+            .await,
+        Err(cdk::Error::UnitMismatch)
+    ));
+    assert!(matches!(
+        wallet_strict
+            .convert_quote_checked(
+                &quote_id.to_string(),
+                UnitAmount::new(50u64, CurrencyUnit::Sat),
+                PaymentMethod::MiningShare,
+                conversion.clone(),
+            )
+            .await,
+        Err(cdk::Error::IncorrectQuoteAmount)
+    ));
+    let converted = wallet_strict
+        .convert_quote_checked(
+            &quote_id.to_string(),
+            UnitAmount::new(100u64, CurrencyUnit::Sat),
+            PaymentMethod::MiningShare,
+            conversion,
+        )
+        .await
+        .unwrap();
+    assert_eq!(converted.payment_method, PaymentMethod::MiningShare);
+}
+
+/// Tests that a pubkey cannot hold more pending mining share quotes than the mint allows
+#[tokio::test]
+async fn test_max_pending_quotes_per_pubkey() {
+    setup_tracing();
+    let mint_bob =
+        create_and_start_test_mint_with_unit(CurrencyUnit::Sat, &[PaymentMethod::MiningShare])
+            .await
+            .expect("Failed to create test mint");
+    let wallet_alice = create_test_wallet_for_mint(mint_bob.clone())
+        .await
+        .expect("Failed to create test wallet");
+
+    let mut mint_info = mint_bob.mint_info().await.unwrap();
+    mint_info.nuts = mint_info.nuts.nutxx(nutXX::Settings {
+        max_pending_quotes_per_pubkey: Some(2),
+        ..Default::default()
+    });
+    mint_bob.set_mint_info(mint_info).await.unwrap();
+
+    let pool_key = SecretKey::generate();
+    let request = |share: &[u8], pubkey| MintQuoteMiningShareRequest {
+        amount: 64.into(),
+        unit: CurrencyUnit::Sat,
+        header_hash: sha256::Hash::hash(share),
+        description: None,
+        pubkey,
+        difficulty: None,
+    };
+
+    // One under the limit is accepted
+    let first = mint_bob
+        .create_mint_mining_share_quote(request(b"share 1", pool_key.public_key()))
+        .await
+        .expect("First quote is under the limit");
+    mint_bob
+        .create_mint_mining_share_quote(request(b"share 2", pool_key.public_key()))
+        .await
+        .expect("Second quote reaches the limit");
+
+    // At the limit the pubkey is refused, other pubkeys are not
+    let err = mint_bob
+        .create_mint_mining_share_quote(request(b"share 3", pool_key.public_key()))
+        .await
+        .expect_err("Third quote is over the limit");
+    assert!(matches!(err, cdk::Error::TooManyPendingQuotes(2)));
+    let response = cdk::error::ErrorResponse::from(err);
+    assert_eq!(response.code, cdk::error::ErrorCode::TooManyPendingQuotes);
+    assert!(matches!(
+        cdk::Error::from(response),
+        cdk::Error::TooManyPendingQuotes(2)
+    ));
+
+    mint_bob
+        .create_mint_mining_share_quote(request(b"other share", SecretKey::generate().public_key()))
+        .await
+        .expect("Other pubkeys have their own limit");
+
+    // Issued quotes no longer count
+    wallet_alice
+        .mint_mining_share(
+            &first.id.to_string(),
+            64.into(),
+            first.keyset_id.expect("Mining share quote has a keyset"),
+            pool_key.clone(),
+            None,
+        )
+        .await
+        .expect("Failed to mint mining share");
+
+    mint_bob
+        .create_mint_mining_share_quote(request(b"share 4", pool_key.public_key()))
+        .await
+        .expect("Issued quote freed a slot");
+    assert!(matches!(
+        mint_bob
+            .create_mint_mining_share_quote(request(b"share 5", pool_key.public_key()))
+            .await,
+        Err(cdk::Error::TooManyPendingQuotes(2))
+    ));
+}
+
+/// Tests that concurrent mints on one wallet derive disjoint secrets
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_concurrent_mints_disjoint_secrets() {
+    setup_tracing();
+    let mint_bob = create_and_start_test_mint()
+        .await
+        .expect("Failed to create test mint");
+    let wallet_alice = create_test_wallet_for_mint(mint_bob.clone())
+        .await
+        .expect("Failed to create test wallet");
+
+    let tasks: Vec<_> = (0..10)
+        .map(|_| tokio::spawn(fund_wallet(wallet_alice.clone(), 15, None)))
+        .collect();
+    for task in tasks {
+        let minted = task
+            .await
+            .expect("Mint task panicked")
+            .expect("Failed to mint");
+        assert_eq!(minted, Amount::from(15));
+    }
+
+    let proofs = wallet_alice.get_unspent_proofs().await.unwrap();
+    assert_eq!(proofs.total_amount().unwrap(), Amount::from(150));
+
+    let secrets: HashSet<_> = proofs.iter().map(|proof| proof.secret.clone()).collect();
+    assert_eq!(secrets.len(), proofs.len());
+}
+
+/// Tests checking the state of several mining share quotes in one request
+#[tokio::test]
+async fn test_mint_quote_states_mining_share() {
+    setup_tracing();
+    let mint_bob = create_and_start_test_mint_with_unit(
+        CurrencyUnit::Sat,
+        &[PaymentMethod::Bolt11, PaymentMethod::MiningShare],
+    )
+    .await
+    .expect("Failed to create test mint");
+    let wallet_alice = create_test_wallet_for_mint(mint_bob.clone())
+        .await
+        .expect("Failed to create test wallet");
+
+    let pool_key = SecretKey::generate();
+    let mut quotes = Vec::new();
+    for share in 0..3u8 {
+        let quote = mint_bob
+            .create_mint_mining_share_quote(MintQuoteMiningShareRequest {
+                amount: 64.into(),
+                unit: CurrencyUnit::Sat,
+                header_hash: sha256::Hash::hash(&[share]),
+                description: None,
+                pubkey: pool_key.public_key(),
+                difficulty: None,
+            })
+            .await
+            .expect("Failed to create mining share quote");
+        quotes.push(quote);
+    }
+
+    wallet_alice
+        .mint_mining_share(
+            &quotes[0].id.to_string(),
+            64.into(),
+            quotes[0]
+                .keyset_id
+                .expect("Mining share quote has a keyset"),
+            pool_key,
+            None,
+        )
+        .await
+        .expect("Failed to mint mining share");
+
+    let bolt11_quote = wallet_alice.mint_quote(100.into(), None).await.unwrap();
+    let unknown = QuoteId::new_uuid().to_string();
+
+    let mut quote_ids: Vec<String> = quotes.iter().map(|quote| quote.id.to_string()).collect();
+    quote_ids.push(unknown.clone());
+    quote_ids.push(bolt11_quote.id.clone());
+
+    // The mint answers in order, with error entries for quotes it cannot check
+    let batch = DirectMintConnection::new(mint_bob.clone())
+        .post_mint_quote_status_mining_share_batch(BatchQuoteStatusRequest {
+            quotes: quote_ids.clone(),
+        })
+        .await
+        .expect("Failed to check quotes");
+    assert_eq!(batch.quotes.len(), 5);
+    assert!(matches!(
+        &batch.quotes[3],
+        BatchQuoteStatusItem::Error { quote, .. } if *quote == unknown
+    ));
+    assert!(matches!(
+        &batch.quotes[4],
+        BatchQuoteStatusItem::Error { quote, .. } if *quote == bolt11_quote.id
+    ));
+
+    let responses: Vec<_> = wallet_alice
+        .mint_quote_states_mining_share(&quote_ids)
+        .await
+        .expect("Failed to check quote states")
+        .into_iter()
+        .map(|update| update.response)
+        .collect();
+    assert_eq!(responses.len(), 3);
+
+    for (quote, response) in quotes.iter().zip(&responses) {
+        assert_eq!(response.quote, quote.id.to_string());
+
+        let local = wallet_alice
+            .localstore
+            .get_mint_quote(&response.quote)
+            .await
+            .unwrap()
+            .expect("Local quote stored");
+        assert_eq!(local.payment_method, PaymentMethod::MiningShare);
+        assert_eq!(local.keyset_id, quote.keyset_id);
+        assert_eq!(local.state, MintQuoteState::from(response.state));
+    }
+    assert_eq!(responses[0].state, nutXX::QuoteState::Issued);
+    assert_eq!(responses[1].state, nutXX::QuoteState::Paid);
+    assert_eq!(responses[2].state, nutXX::QuoteState::Paid);
+
+    // Too many quotes are refused by the mint but paged by the wallet
+    let many: Vec<String> = (0..150).map(|_| QuoteId::new_uuid().to_string()).collect();
+    assert!(matches!(
+        mint_bob
+            .check_mint_quotes_mining_share(BatchQuoteStatusRequest {
+                quotes: many.clone(),
+            })
+            .await,
+        Err(cdk::Error::BatchQuoteStatusTooManyQuotes {
+            max: 100,
+            quotes: 150
+        })
+    ));
+    assert!(wallet_alice
+        .mint_quote_states_mining_share(&many)
+        .await
+        .unwrap()
+        .is_empty());
+}
+
+/// Mining share quotes are bounded by the maximum amount advertised in the mint info
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_max_mining_share_amount() {
+    setup_tracing();
+    let mint_bob =
+        create_and_start_test_mint_with_unit(CurrencyUnit::Sat, &[PaymentMethod::MiningShare])
+            .await
+            .expect("Failed to create test mint");
+
+    let pool_key = SecretKey::generate();
+    let request = |share: &[u8], amount: u64| MintQuoteMiningShareRequest {
+        amount: amount.into(),
+        unit: CurrencyUnit::Sat,
+        header_hash: sha256::Hash::hash(share),
+        description: None,
+        pubkey: pool_key.public_key(),
+        difficulty: None,
+    };
+
+    // Without a configured maximum the default applies
+    mint_bob
+        .create_mint_mining_share_quote(request(b"share 1", 256))
+        .await
+        .expect("Default maximum is inclusive");
+    assert!(matches!(
+        mint_bob
+            .create_mint_mining_share_quote(request(b"share 2", 257))
+            .await,
+        Err(cdk::Error::InvalidPaymentRequest)
+    ));
+
+    let mut mint_info = mint_bob.mint_info().await.unwrap();
+    mint_info.nuts = mint_info.nuts.nutxx(nutXX::Settings {
+        max_amount: Some(Amount::from(1024)),
+        ..Default::default()
+    });
+    mint_bob.set_mint_info(mint_info).await.unwrap();
+
+    let max_amount = mint_bob
+        .mint_info()
+        .await
+        .unwrap()
+        .nuts
+        .nutxx
+        .expect("Mining share settings are advertised")
+        .max_amount;
+    assert_eq!(max_amount, Some(Amount::from(1024)));
+
+    mint_bob
+        .create_mint_mining_share_quote(request(b"share 3", 1000))
+        .await
+        .expect("Below the configured maximum");
+    mint_bob
+        .create_mint_mining_share_quote(request(b"share 4", 1024))
+        .await
+        .expect("Configured maximum is inclusive");
+    assert!(matches!(
+        mint_bob
+            .create_mint_mining_share_quote(request(b"share 5", 1025))
+            .await,
+        Err(cdk::Error::InvalidPaymentRequest)
+    ));
+}
+
+/// Tests that a mining share header hash is only quoted once:
+/// 1. A second quote for a live share is refused with the id of the first quote
+/// 2. The quote id survives the error response so a client can recover the quote
+/// 3. Once the first quote expires unissued the share can be quoted again
+/// 4. An issued share stays taken after its quote expires
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_duplicate_share_hash() {
+    setup_tracing();
+    let clock = MockClock::new(unix_time());
+    let mint_bob = create_and_start_test_mint_with_clock(
+        CurrencyUnit::Sat,
+        &[PaymentMethod::MiningShare],
+        Arc::new(clock.clone()),
+    )
+    .await
+    .expect("Failed to create test mint");
+    let quote_ttl = mint_bob.quote_ttl().await.unwrap();
+    let wallet_alice = create_test_wallet_for_mint_with_clock(
+        mint_bob.clone(),
+        CurrencyUnit::Sat,
+        Arc::new(clock.clone()),
+    )
+    .await
+    .expect("Failed to create test wallet");
+
+    let pool_key = SecretKey::generate();
+    let request = |share: &[u8]| MintQuoteMiningShareRequest {
+        amount: 64.into(),
+        unit: CurrencyUnit::Sat,
+        header_hash: sha256::Hash::hash(share),
+        description: None,
+        pubkey: pool_key.public_key(),
+        difficulty: None,
+    };
+
+    let first = mint_bob
+        .create_mint_mining_share_quote(request(b"share"))
+        .await
+        .expect("Failed to create mining share quote");
+
+    let err = mint_bob
+        .create_mint_mining_share_quote(request(b"share"))
+        .await
+        .expect_err("Share is already quoted");
+    assert!(matches!(&err, cdk::Error::DuplicateShareHash(id) if *id == first.id.to_string()));
+
+    let response = cdk::error::ErrorResponse::from(err);
+    assert_eq!(response.code, cdk::error::ErrorCode::DuplicateShareHash);
+    match cdk::Error::from(response) {
+        cdk::Error::DuplicateShareHash(id) => {
+            let recovered = mint_bob
+                .localstore()
+                .get_mint_quote(&QuoteId::from_str(&id).unwrap())
+                .await
+                .unwrap()
+                .expect("Original quote is still stored");
+            assert_eq!(recovered.id, first.id);
+        }
+        other => panic!("Expected DuplicateShareHash, got {other:?}"),
+    }
+
+    clock.advance(quote_ttl.mint_ttl + 1);
+
+    let second = mint_bob
+        .create_mint_mining_share_quote(request(b"share"))
+        .await
+        .expect("Expired quote no longer holds the share");
+    assert_ne!(second.id, first.id);
+    assert!(mint_bob
+        .localstore()
+        .get_mint_quote(&first.id)
+        .await
+        .unwrap()
+        .is_none());
+
+    wallet_alice
+        .mint_mining_share(
+            &second.id.to_string(),
+            64.into(),
+            second.keyset_id.expect("Mining share quote has a keyset"),
+            pool_key.clone(),
+            None,
+        )
+        .await
+        .expect("Failed to mint mining share");
+
+    clock.advance(quote_ttl.mint_ttl + 1);
+
+    assert!(matches!(
+        mint_bob
+            .create_mint_mining_share_quote(request(b"share"))
+            .await,
+        Err(cdk::Error::DuplicateShareHash(id)) if id == second.id.to_string()
+    ));
+}
+
+/// Tests checking all mint quotes of a wallet holding several payment methods:
+/// 1. A paid bolt11 quote and a mining share quote are minted with their own endpoints
+/// 2. A quote the mint does not know is reported without stopping the others
+/// 3. The unknown quote is kept for the next check
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_check_all_mint_quotes_mixed_methods() {
+    setup_tracing();
+    let mint_bob = create_and_start_test_mint_with_unit(
+        CurrencyUnit::Sat,
+        &[PaymentMethod::Bolt11, PaymentMethod::MiningShare],
+    )
+    .await
+    .expect("Failed to create test mint");
+    let wallet_alice = create_test_wallet_for_mint(mint_bob.clone())
+        .await
+        .expect("Failed to create test wallet");
+
+    let (bolt11_quote_id, _, request_lookup_id) =
+        add_unpaid_bolt11_quote(&mint_bob, &wallet_alice, 100).await;
+    mint_bob
+        .pay_mint_quote_for_request_id(WaitPaymentResponse {
+            payment_identifier: request_lookup_id,
+            payment_amount: 100.into(),
+            unit: CurrencyUnit::Sat,
+            payment_id: "payment".to_string(),
+        })
+        .await
+        .unwrap();
+
+    let mining_share_quote = wallet_alice
+        .mint_quote_mining_share(64.into(), sha256::Hash::hash(b"share"), None)
+        .await
+        .unwrap();
+
+    // Stored by the wallet only, the mint cannot check it
+    let unknown_quote_id = QuoteId::new_uuid().to_string();
+    wallet_alice
+        .localstore
+        .add_mint_quote(WalletMintQuote {
+            id: unknown_quote_id.clone(),
+            mint_url: wallet_alice.mint_url.clone(),
+            payment_method: PaymentMethod::MiningShare,
+            amount: Some(32.into()),
+            unit: CurrencyUnit::Sat,
+            request: sha256::Hash::hash(b"unknown share").to_string(),
+            state: MintQuoteState::Paid,
+            expiry: unix_time() + 3600,
+            secret_key: Some(SecretKey::generate()),
+            amount_issued: Amount::ZERO,
+            amount_paid: 32.into(),
+            keyset_id: None,
+            converted_from: None,
+            premint_keyset_id: None,
+            premint_counter: None,
+            premint_amounts: None,
+        })
+        .await
+        .unwrap();
+
+    let summary = wallet_alice.check_all_mint_quotes().await.unwrap();
+
+    assert_eq!(summary.total_minted, Amount::from(164));
+    let mut minted_quotes = summary.minted_quotes.clone();
+    minted_quotes.sort();
+    let mut expected = vec![bolt11_quote_id.to_string(), mining_share_quote.id.clone()];
+    expected.sort();
+    assert_eq!(minted_quotes, expected);
+
+    assert_eq!(summary.failed_quotes.len(), 1);
+    assert_eq!(summary.failed_quotes[0].0, unknown_quote_id);
+    assert_eq!(wallet_alice.total_balance().await.unwrap(), 164.into());
+
+    assert!(wallet_alice
+        .localstore
+        .get_mint_quote(&unknown_quote_id)
+        .await
+        .unwrap()
+        .is_some());
+
+    // Minted quotes are not minted twice
+    let summary = wallet_alice.check_all_mint_quotes().await.unwrap();
+    assert_eq!(summary.total_minted, Amount::ZERO);
+    assert!(summary.minted_quotes.is_empty());
+    assert_eq!(summary.failed_quotes.len(), 1);
+}
+
+/// Tests minting a mining share quote in parts:
+/// 1. Asking for more than the quote is refused before contacting the mint
+/// 2. A first part is minted and recorded on the stored quote
+/// 3. Passing zero mints the rest, after which nothing is left
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_mining_share_partial_issuance() {
+    setup_tracing();
+    let mint_bob =
+        create_and_start_test_mint_with_unit(CurrencyUnit::Sat, &[PaymentMethod::MiningShare])
+            .await
+            .expect("Failed to create test mint");
+    let wallet_alice = create_test_wallet_for_mint(mint_bob.clone())
+        .await
+        .expect("Failed to create test wallet");
+
+    let quote = wallet_alice
+        .mint_quote_mining_share(64.into(), sha256::Hash::hash(b"share"), None)
+        .await
+        .unwrap();
+    let keyset_id = quote.keyset_id.expect("Mining share quote has a keyset");
+    let secret_key = quote
+        .secret_key
+        .clone()
+        .expect("Mining share quote has a secret key");
+
+    assert!(matches!(
+        wallet_alice
+            .mint_mining_share(&quote.id, 65.into(), keyset_id, secret_key.clone(), None)
+            .await,
+        Err(cdk::Error::AmountExceedsMintable { requested, mintable })
+            if requested == 65.into() && mintable == 64.into()
+    ));
+
+    let proofs = wallet_alice
+        .mint_mining_share(&quote.id, 40.into(), keyset_id, secret_key.clone(), None)
+        .await
+        .expect("Failed to mint first part");
+    assert_eq!(proofs.total_amount().unwrap(), Amount::from(40));
+
+    let stored = wallet_alice
+        .localstore
+        .get_mint_quote(&quote.id)
+        .await
+        .unwrap()
+        .expect("Quote is stored");
+    assert_eq!(stored.amount_issued, Amount::from(40));
+    assert_eq!(stored.amount_mintable(), Amount::from(24));
+    assert_eq!(stored.state, MintQuoteState::Paid);
+
+    assert!(matches!(
+        wallet_alice
+            .mint_mining_share(&quote.id, 25.into(), keyset_id, secret_key.clone(), None)
+            .await,
+        Err(cdk::Error::AmountExceedsMintable { requested, mintable })
+            if requested == 25.into() && mintable == 24.into()
+    ));
+
+    let proofs = wallet_alice
+        .mint_mining_share(&quote.id, Amount::ZERO, keyset_id, secret_key.clone(), None)
+        .await
+        .expect("Failed to mint the rest");
+    assert_eq!(proofs.total_amount().unwrap(), Amount::from(24));
+    assert_eq!(wallet_alice.total_balance().await.unwrap(), 64.into());
+
+    let stored = wallet_alice
+        .localstore
+        .get_mint_quote(&quote.id)
+        .await
+        .unwrap()
+        .expect("Quote is stored");
+    assert_eq!(stored.amount_issued, Amount::from(64));
+    assert_eq!(stored.amount_mintable(), Amount::ZERO);
+    assert_eq!(stored.state, MintQuoteState::Issued);
+
+    assert!(matches!(
+        wallet_alice
+            .mint_mining_share(&quote.id, 1.into(), keyset_id, secret_key, None)
+            .await,
+        Err(cdk::Error::AmountExceedsMintable { requested, mintable })
+            if requested == 1.into() && mintable == 0.into()
+    ));
+}
+
+/// Tests creating several mining share quotes in one request:
+/// 1. Valid shares are quoted and invalid or duplicate shares get error entries, in request order
+/// 2. The wallet stores the quotes the mint created and can mint them
+/// 3. Batches above the limit are refused
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_mining_share_quote_batch() {
+    setup_tracing();
+    let mint_bob =
+        create_and_start_test_mint_with_unit(CurrencyUnit::Sat, &[PaymentMethod::MiningShare])
+            .await
+            .expect("Failed to create test mint");
+    let wallet_alice = create_test_wallet_for_mint(mint_bob.clone())
+        .await
+        .expect("Failed to create test wallet");
+
+    let pool_key = SecretKey::generate();
+    let request = |amount: u64, share: &[u8]| MintQuoteMiningShareRequest {
+        amount: amount.into(),
+        unit: CurrencyUnit::Sat,
+        header_hash: sha256::Hash::hash(share),
+        description: None,
+        pubkey: pool_key.public_key(),
+        difficulty: None,
+    };
+
+    let response = mint_bob
+        .create_mint_mining_share_quotes(BatchMintQuoteMiningShareRequest {
+            quotes: vec![
+                request(64, b"first"),
+                request(0, b"zero"),
+                request(32, b"first"),
+                request(300, b"too large"),
+                request(16, b"second"),
+            ],
+        })
+        .await
+        .expect("Failed to create mining share quotes");
+    assert_eq!(response.quotes.len(), 5);
+
+    let first = match &response.quotes[0] {
+        BatchMintQuoteMiningShareItem::Quote(quote) => quote.clone(),
+        other => panic!("Expected a quote, got {other:?}"),
+    };
+    assert_eq!(first.amount, Some(64.into()));
+    assert_eq!(first.request, sha256::Hash::hash(b"first").to_string());
+
+    for (index, share) in [
+        (1, &b"zero"[..]),
+        (2, &b"first"[..]),
+        (3, &b"too large"[..]),
+    ] {
+        match &response.quotes[index] {
+            BatchMintQuoteMiningShareItem::Error { header_hash, .. } => {
+                assert_eq!(*header_hash, sha256::Hash::hash(share))
+            }
+            other => panic!("Expected an error for entry {index}, got {other:?}"),
+        }
+    }
+
+    match &response.quotes[4] {
+        BatchMintQuoteMiningShareItem::Quote(quote) => {
+            assert_eq!(quote.amount, Some(16.into()));
+            assert!(mint_bob
+                .localstore()
+                .get_mint_quote(&quote.quote)
+                .await
+                .unwrap()
+                .is_some());
+        }
+        other => panic!("Expected a quote, got {other:?}"),
+    }
+
+    let quotes = wallet_alice
+        .mint_quotes_mining_share(&[
+            (32.into(), sha256::Hash::hash(b"wallet first")),
+            (64.into(), sha256::Hash::hash(b"first")),
+            (8.into(), sha256::Hash::hash(b"wallet second")),
+        ])
+        .await
+        .expect("Failed to request mining share quotes");
+    assert_eq!(quotes.len(), 2);
+    assert_eq!(quotes[0].amount, Some(32.into()));
+    assert_eq!(quotes[1].amount, Some(8.into()));
+
+    for quote in &quotes {
+        let stored = wallet_alice
+            .localstore
+            .get_mint_quote(&quote.id)
+            .await
+            .unwrap()
+            .expect("Quote is stored by the wallet");
+        assert_eq!(stored.secret_key, quote.secret_key);
+    }
+
+    let proofs = wallet_alice
+        .mint_mining_share(
+            &quotes[0].id,
+            Amount::ZERO,
+            quotes[0]
+                .keyset_id
+                .expect("Mining share quotes have a keyset"),
+            quotes[0].secret_key.clone().expect("Quote is locked"),
+            None,
+        )
+        .await
+        .expect("Failed to mint mining share quote");
+    assert_eq!(proofs.total_amount().unwrap(), 32.into());
+
+    let too_many = (0..=nutXX::MAX_BATCH_MINT_QUOTE_MINING_SHARE)
+        .map(|i| request(1, &i.to_be_bytes()))
+        .collect();
+    assert!(matches!(
+        mint_bob
+            .create_mint_mining_share_quotes(BatchMintQuoteMiningShareRequest { quotes: too_many })
+            .await,
+        Err(cdk::Error::BatchMintQuoteTooManyQuotes { .. })
+    ));
+}
+
+/// Tests that a mining share quote batch is stored in one transaction:
+/// 1. A share whose payment id is already recorded fails while storing
+/// 2. The whole batch is refused and the valid share before it is not stored
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_mining_share_quote_batch_is_atomic() {
+    setup_tracing();
+    let mint_bob = create_and_start_test_mint_with_unit(
+        CurrencyUnit::Sat,
+        &[PaymentMethod::Bolt11, PaymentMethod::MiningShare],
+    )
+    .await
+    .expect("Failed to create test mint");
+    let wallet_alice = create_test_wallet_for_mint(mint_bob.clone())
+        .await
+        .expect("Failed to create test wallet");
+
+    // Record a payment whose id collides with the payment of a share
+    let taken = sha256::Hash::hash(b"taken");
+    let (_, _, request_lookup_id) = add_unpaid_bolt11_quote(&mint_bob, &wallet_alice, 100).await;
+    mint_bob
+        .pay_mint_quote_for_request_id(WaitPaymentResponse {
+            payment_identifier: request_lookup_id,
+            payment_amount: 100.into(),
+            unit: CurrencyUnit::Sat,
+            payment_id: taken.to_string(),
+        })
+        .await
+        .unwrap();
+
+    let pool_key = SecretKey::generate();
+    let request = |share: sha256::Hash| MintQuoteMiningShareRequest {
+        amount: 64.into(),
+        unit: CurrencyUnit::Sat,
+        header_hash: share,
+        description: None,
+        pubkey: pool_key.public_key(),
+        difficulty: None,
+    };
+    let valid = sha256::Hash::hash(b"valid");
+
+    assert!(mint_bob
+        .create_mint_mining_share_quotes(BatchMintQuoteMiningShareRequest {
+            quotes: vec![request(valid), request(taken)],
+        })
+        .await
+        .is_err());
+
+    assert!(mint_bob
+        .localstore()
+        .get_mint_quote_by_request_lookup_id(&PaymentIdentifier::MiningShareHash(valid.to_string()))
+        .await
+        .unwrap()
+        .is_none());
+}
+
+/// Tests minting a mining share quote after the mint rotated its keyset:
+/// 1. The quote is created in the keyset active at the time
+/// 2. The mint rotates the keyset before the quote is minted
+/// 3. The proofs are minted in the new keyset and the stored quote is moved to it
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_mining_share_keyset_rotation() {
+    setup_tracing();
+    let mint_bob =
+        create_and_start_test_mint_with_unit(CurrencyUnit::Sat, &[PaymentMethod::MiningShare])
+            .await
+            .expect("Failed to create test mint");
+    let wallet_alice = create_test_wallet_for_mint(mint_bob.clone())
+        .await
+        .expect("Failed to create test wallet");
+
+    let quote = wallet_alice
+        .mint_quote_mining_share(64.into(), sha256::Hash::hash(b"rotated share"), None)
+        .await
+        .expect("Failed to create mining share quote");
+    let old_keyset_id = quote.keyset_id.expect("Mining share quotes have a keyset");
+
+    mint_bob
+        .rotate_keyset(CurrencyUnit::Sat, 32, 0)
+        .await
+        .unwrap();
+    let new_keyset_id = mint_bob.get_active_keysets()[&CurrencyUnit::Sat];
+    assert_ne!(new_keyset_id, old_keyset_id);
+
+    let proofs = wallet_alice
+        .mint_mining_share(
+            &quote.id,
+            Amount::ZERO,
+            old_keyset_id,
+            quote.secret_key.clone().expect("Quote is locked"),
+            None,
+        )
+        .await
+        .expect("Failed to mint mining share after keyset rotation");
+    assert_eq!(proofs.total_amount().unwrap(), 64.into());
+    assert!(proofs.iter().all(|proof| proof.keyset_id == new_keyset_id));
+
+    let stored = wallet_alice
+        .localstore
+        .get_mint_quote(&quote.id)
+        .await
+        .unwrap()
+        .expect("Quote is stored by the wallet");
+    assert_eq!(stored.keyset_id, Some(new_keyset_id));
+    assert_eq!(stored.amount_issued, 64.into());
+    assert_eq!(wallet_alice.total_balance().await.unwrap(), 64.into());
+}
+
+/// Tests linking mining share transactions back to their shares:
+/// 1. Three mining share quotes are requested in one batch and minted
+/// 2. Each transaction records its quote id and share header hash
+/// 3. The transaction of one share is found by its header hash
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_mining_share_transactions_by_header_hash() {
+    setup_tracing();
+    let mint_bob =
+        create_and_start_test_mint_with_unit(CurrencyUnit::Sat, &[PaymentMethod::MiningShare])
+            .await
+            .expect("Failed to create test mint");
+    let wallet_alice = create_test_wallet_for_mint(mint_bob.clone())
+        .await
+        .expect("Failed to create test wallet");
+
+    let shares = [
+        (Amount::from(16), sha256::Hash::hash(b"share one")),
+        (Amount::from(32), sha256::Hash::hash(b"share two")),
+        (Amount::from(64), sha256::Hash::hash(b"share three")),
+    ];
+    let quotes = wallet_alice
+        .mint_quotes_mining_share(&shares)
+        .await
+        .expect("Failed to request mining share quotes");
+    assert_eq!(quotes.len(), 3);
+
+    for quote in &quotes {
+        wallet_alice
+            .mint_mining_share(
+                &quote.id,
+                Amount::ZERO,
+                quote.keyset_id.expect("Mining share quotes have a keyset"),
+                quote.secret_key.clone().expect("Quote is locked"),
+                None,
+            )
+            .await
+            .expect("Failed to mint mining share quote");
+    }
+
+    let header_hash = shares[1].1.to_string();
+    let transactions = wallet_alice
+        .list_transactions_for_share_hash(&header_hash)
+        .await
+        .unwrap();
+    assert_eq!(transactions.len(), 1);
+
+    let transaction = &transactions[0];
+    assert_eq!(transaction.amount, Amount::from(32));
+    assert_eq!(transaction.quote_id.as_deref(), Some(quotes[1].id.as_str()));
+    assert_eq!(transaction.metadata.get("quote.0.id"), Some(&quotes[1].id));
+    assert_eq!(
+        transaction.metadata.get("quote.0.header_hash"),
+        Some(&header_hash)
+    );
+
+    assert!(wallet_alice
+        .list_transactions_for_share_hash(&sha256::Hash::hash(b"unknown").to_string())
+        .await
+        .unwrap()
+        .is_empty());
+}
+
+/// Tests removing stale mining share quotes:
+/// 1. An expired quote that was never minted is refused with QuoteExpired
+/// 2. The sweep removes it but keeps partly minted and live quotes
+/// 3. The partly minted quote can still be minted after the sweep
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_expire_stale_mining_share_quotes() {
+    setup_tracing();
+    let clock = MockClock::new(unix_time());
+    let mint_bob = create_and_start_test_mint_with_clock(
+        CurrencyUnit::Sat,
+        &[PaymentMethod::MiningShare],
+        Arc::new(clock.clone()),
+    )
+    .await
+    .expect("Failed to create test mint");
+    let quote_ttl = mint_bob.quote_ttl().await.unwrap();
+    let wallet_alice = create_test_wallet_for_mint_with_clock(
+        mint_bob.clone(),
+        CurrencyUnit::Sat,
+        Arc::new(clock.clone()),
+    )
+    .await
+    .expect("Failed to create test wallet");
+
+    let stale = wallet_alice
+        .mint_quote_mining_share(64.into(), sha256::Hash::hash(b"stale share"), None)
+        .await
+        .expect("Failed to create mining share quote");
+    let partial = wallet_alice
+        .mint_quote_mining_share(64.into(), sha256::Hash::hash(b"partial share"), None)
+        .await
+        .expect("Failed to create mining share quote");
+    let keyset_id = partial
+        .keyset_id
+        .expect("Mining share quotes have a keyset");
+    wallet_alice
+        .mint_mining_share(
+            &partial.id,
+            32.into(),
+            keyset_id,
+            partial.secret_key.clone().expect("Quote is locked"),
+            None,
+        )
+        .await
+        .expect("Failed to mint part of the quote");
+
+    clock.advance(quote_ttl.mint_ttl + 1);
+
+    let live = wallet_alice
+        .mint_quote_mining_share(64.into(), sha256::Hash::hash(b"live share"), None)
+        .await
+        .expect("Failed to create mining share quote");
+
+    assert!(matches!(
+        wallet_alice
+            .mint_mining_share(
+                &stale.id,
+                Amount::ZERO,
+                keyset_id,
+                stale.secret_key.clone().expect("Quote is locked"),
+                None,
+            )
+            .await,
+        Err(cdk::Error::ExpiredQuote(_, _))
+    ));
+
+    let now = clock.now_unix();
+    assert_eq!(
+        mint_bob
+            .expire_stale_mining_share_quotes(now)
+            .await
+            .unwrap(),
+        1
+    );
+    assert_eq!(
+        mint_bob
+            .expire_stale_mining_share_quotes(now)
+            .await
+            .unwrap(),
+        0
+    );
+
+    let stored = |id: &str| {
+        let localstore = mint_bob.localstore();
+        let id = QuoteId::from_str(id).unwrap();
+        async move { localstore.get_mint_quote(&id).await.unwrap() }
+    };
+    assert!(stored(&stale.id).await.is_none());
+    assert!(stored(&partial.id).await.is_some());
+    assert!(stored(&live.id).await.is_some());
+
+    let proofs = wallet_alice
+        .mint_mining_share(
+            &partial.id,
+            Amount::ZERO,
+            keyset_id,
+            partial.secret_key.clone().expect("Quote is locked"),
+            None,
+        )
+        .await
+        .expect("Partly minted quote survives the sweep");
+    assert_eq!(proofs.total_amount().unwrap(), 32.into());
+}
+
+/// Tests a mining share quote expiring while its mint request is on the way:
+/// 1. The quote is live when the wallet builds and sends the mint request
+/// 2. The request reaches the mint only after the quote expired, and the
+///    mint refuses it with ExpiredQuote
+/// 3. Nothing is stored or queued for retry, the quote keeps its reserved
+///    premint counter
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_mining_share_quote_expires_during_mint_request() {
+    setup_tracing();
+    let clock = MockClock::new(unix_time());
+    let mint_bob = create_and_start_test_mint_with_clock(
+        CurrencyUnit::Sat,
+        &[PaymentMethod::MiningShare],
+        Arc::new(clock.clone()),
+    )
+    .await
+    .expect("Failed to create test mint");
+    let quote_ttl = mint_bob.quote_ttl().await.unwrap();
+    let seed = Mnemonic::generate(12).unwrap().to_seed_normalized("");
+
+    let connection = DirectMintConnection::new(mint_bob.clone())
+        .with_mint_request_delay(clock.clone(), quote_ttl.mint_ttl + 1);
+    let wallet_alice = create_test_wallet_with_connection(
+        connection,
+        CurrencyUnit::Sat,
+        seed,
+        Arc::new(clock.clone()),
+    )
+    .await
+    .expect("Failed to create test wallet");
+
+    let quote = wallet_alice
+        .mint_quote_mining_share(64.into(), sha256::Hash::hash(b"late share"), None)
+        .await
+        .expect("Failed to create mining share quote");
+    assert!(quote.expiry > clock.now_unix());
+
+    let result = wallet_alice
+        .mint_mining_share(
+            &quote.id,
+            Amount::ZERO,
+            quote.keyset_id.expect("Mining share quotes have a keyset"),
+            quote.secret_key.clone().expect("Quote is locked"),
+            None,
+        )
+        .await;
+    assert!(
+        matches!(result, Err(cdk::Error::ExpiredQuote(expiry, now)) if expiry == quote.expiry && now > expiry),
+        "Expected ExpiredQuote, got {:?}",
+        result
+    );
+
+    assert_eq!(wallet_alice.total_balance().await.unwrap(), Amount::ZERO);
+    assert!(wallet_alice
+        .pending_mint_operations()
+        .await
+        .unwrap()
+        .is_empty());
+    let stored = wallet_alice
+        .localstore
+        .get_mint_quote(&quote.id)
+        .await
+        .unwrap()
+        .expect("Refused quote is kept");
+    assert_eq!(stored.premint_counter, quote.premint_counter);
+    assert_eq!(stored.amount_issued, Amount::ZERO);
+}
+
+/// Tests retrying a mint whose response was lost after the mint signed it:
+/// 1. The first attempt is signed by the mint but fails on the wallet side,
+///    leaving the premint counter and output amounts recorded on the quote
+///    and the request queued
+/// 2. The retry, even with another split target, resubmits the same outputs
+///    and recovers the signatures without a second mint request
+/// 3. The proofs are the ones derived from the recorded counter, the quote
+///    and the queued request are removed once they are stored
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_mint_retry_reuses_premint_secrets() {
+    setup_tracing();
+    let mint_bob =
+        create_and_start_test_mint_with_unit(CurrencyUnit::Sat, &[PaymentMethod::Bolt11])
+            .await
+            .expect("Failed to create test mint");
+    let seed = Mnemonic::generate(12).unwrap().to_seed_normalized("");
+
+    let connection = DirectMintConnection::new(mint_bob.clone()).with_dropped_mint_responses(1);
+    let mint_requests = connection.mint_requests.clone();
+    let wallet_alice = create_test_wallet_with_connection(
+        connection,
+        CurrencyUnit::Sat,
+        seed,
+        Arc::new(SystemClock),
+    )
+    .await
+    .expect("Failed to create test wallet");
+
+    let (quote_id, _, request_lookup_id) =
+        add_unpaid_bolt11_quote(&mint_bob, &wallet_alice, 100).await;
+    mint_bob
+        .pay_mint_quote_for_request_id(WaitPaymentResponse {
+            payment_identifier: request_lookup_id,
+            payment_amount: 100.into(),
+            unit: CurrencyUnit::Sat,
+            payment_id: "payment".to_string(),
+        })
+        .await
+        .unwrap();
+
+    let quote_id = quote_id.to_string();
+    assert!(matches!(
+        wallet_alice
+            .mint(&quote_id, SplitTarget::default(), None)
+            .await,
+        Err(cdk::Error::QueuedForRetry(_))
+    ));
+    assert_eq!(
+        mint_bob
+            .localstore()
+            .get_mint_quote(&QuoteId::from_str(&quote_id).unwrap())
+            .await
+            .unwrap()
+            .unwrap()
+            .state(),
+        MintQuoteState::Issued
+    );
+
+    let quote = wallet_alice
+        .localstore
+        .get_mint_quote(&quote_id)
+        .await
+        .unwrap()
+        .expect("Quote kept after the failed attempt");
+    let keyset_id = quote.premint_keyset_id.expect("Premint keyset recorded");
+    let counter = quote.premint_counter.expect("Premint counter recorded");
+    assert_eq!(
+        quote.premint_amounts,
+        Some(vec![Amount::from(4), Amount::from(32), Amount::from(64)])
+    );
+
+    let proofs = wallet_alice
+        .mint(&quote_id, SplitTarget::Value(Amount::from(1)), None)
+        .await
+        .expect("Retry recovers the issued signatures");
+    assert_eq!(mint_requests.load(Ordering::SeqCst), 1);
+    assert_eq!(proofs.total_amount().unwrap(), Amount::from(100));
+
+    let expected = PreMintSecrets::from_seed(
+        keyset_id,
+        counter,
+        &seed,
+        Amount::from(100),
+        &SplitTarget::default(),
+    )
+    .unwrap();
+    assert_eq!(
+        proofs
+            .iter()
+            .map(|proof| proof.secret.clone())
+            .collect::<Vec<_>>(),
+        expected.secrets()
+    );
+
+    assert!(wallet_alice
+        .localstore
+        .get_mint_quote(&quote_id)
+        .await
+        .unwrap()
+        .is_none());
+    assert!(wallet_alice
+        .pending_mint_operations()
+        .await
+        .unwrap()
+        .is_empty());
+    assert_eq!(
+        wallet_alice.total_balance().await.unwrap(),
+        Amount::from(100)
+    );
+}
+
+/// Tests minting a mining share quote into proofs locked to a miner:
+/// 1. Every minted proof carries the P2PK condition on the miner's key
+/// 2. The keyset counter is left untouched by the random secrets
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_mining_share_spending_conditions() {
+    setup_tracing();
+    let mint_bob =
+        create_and_start_test_mint_with_unit(CurrencyUnit::Sat, &[PaymentMethod::MiningShare])
+            .await
+            .expect("Failed to create test mint");
+    let wallet_pool = create_test_wallet_for_mint(mint_bob.clone())
+        .await
+        .expect("Failed to create test wallet");
+
+    let quote = wallet_pool
+        .mint_quote_mining_share(64.into(), sha256::Hash::hash(b"share"), None)
+        .await
+        .unwrap();
+    let keyset_id = quote.keyset_id.expect("Mining share quote has a keyset");
+
+    let miner_key = SecretKey::generate();
+    let spending_conditions = SpendingConditions::new_p2pk(miner_key.public_key(), None);
+
+    let proofs = wallet_pool
+        .mint_mining_share(
+            &quote.id,
+            Amount::ZERO,
+            keyset_id,
+            quote.secret_key.clone().expect("Quote is locked"),
+            Some(spending_conditions.clone()),
+        )
+        .await
+        .expect("Failed to mint locked mining share proofs");
+    assert_eq!(proofs.total_amount().unwrap(), 64.into());
+
+    for proof in &proofs {
+        assert_eq!(
+            SpendingConditions::try_from(&proof.secret).unwrap(),
+            spending_conditions
+        );
+    }
+
+    assert_eq!(
+        wallet_pool
+            .localstore
+            .increment_keyset_counter(&keyset_id, 0)
+            .await
+            .unwrap(),
+        0
+    );
+}
+
+/// Advertise mint quote cancellation and let the wallet pick it up
+async fn enable_quote_cancel(mint: &Mint, wallet: &Wallet) {
+    let mut mint_info = mint.mint_info().await.unwrap();
+    mint_info.nuts = mint_info.nuts.nutxx(nutXX::Settings {
+        quote_cancel: true,
+        ..Default::default()
+    });
+    mint.set_mint_info(mint_info).await.unwrap();
+    wallet.fetch_mint_info().await.unwrap();
+}
+
+/// Tests cancelling mint quotes that have no ecash issued:
+/// 1. An unpaid quote is cancelled at the mint and removed from the wallet
+/// 2. A late payment of the cancelled quote is not credited
+/// 3. A paid but unissued quote is only cancelled with a signature of its
+///    quote id by the quote key, which the wallet adds
+/// 4. A cancelled quote cannot be cancelled again
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_cancel_mint_quote() {
+    setup_tracing();
+    let mint_bob =
+        create_and_start_test_mint_with_unit(CurrencyUnit::Sat, &[PaymentMethod::Bolt11])
+            .await
+            .expect("Failed to create test mint");
+    let wallet_alice = create_test_wallet_for_mint(mint_bob.clone())
+        .await
+        .expect("Failed to create test wallet");
+    enable_quote_cancel(&mint_bob, &wallet_alice).await;
+
+    let (unpaid_id, _, unpaid_lookup_id) =
+        add_unpaid_bolt11_quote(&mint_bob, &wallet_alice, 100).await;
+    wallet_alice
+        .cancel_mint_quote(&unpaid_id.to_string())
+        .await
+        .expect("Unpaid quote cancelled");
+    assert!(wallet_alice
+        .localstore
+        .get_mint_quote(&unpaid_id.to_string())
+        .await
+        .unwrap()
+        .is_none());
+
+    mint_bob
+        .pay_mint_quote_for_request_id(WaitPaymentResponse {
+            payment_identifier: unpaid_lookup_id,
+            payment_amount: 100.into(),
+            unit: CurrencyUnit::Sat,
+            payment_id: "late-payment".to_string(),
+        })
+        .await
+        .unwrap();
+    let unpaid = mint_bob
+        .localstore()
+        .get_mint_quote(&unpaid_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(unpaid.state(), MintQuoteState::Cancelled);
+    assert_eq!(unpaid.amount_paid(), Amount::ZERO);
+
+    let (paid_id, paid_key, paid_lookup_id) =
+        add_unpaid_bolt11_quote(&mint_bob, &wallet_alice, 100).await;
+    mint_bob
+        .pay_mint_quote_for_request_id(WaitPaymentResponse {
+            payment_identifier: paid_lookup_id,
+            payment_amount: 100.into(),
+            unit: CurrencyUnit::Sat,
+            payment_id: "payment".to_string(),
+        })
+        .await
+        .unwrap();
+
+    let unsigned = MintQuoteCancelRequest {
+        quote: paid_id.clone(),
+        signature: None,
+    };
+    assert!(matches!(
+        mint_bob
+            .cancel_mint_quote(&PaymentMethod::Bolt11, unsigned.clone())
+            .await,
+        Err(cdk::Error::NUT20(_))
+    ));
+    let mut wrongly_signed = unsigned.clone();
+    wrongly_signed.sign(SecretKey::generate()).unwrap();
+    assert!(matches!(
+        mint_bob
+            .cancel_mint_quote(&PaymentMethod::Bolt11, wrongly_signed)
+            .await,
+        Err(cdk::Error::NUT20(_))
+    ));
+    assert_eq!(
+        mint_bob
+            .localstore()
+            .get_mint_quote(&paid_id)
+            .await
+            .unwrap()
+            .unwrap()
+            .state(),
+        MintQuoteState::Paid
+    );
+
+    wallet_alice
+        .cancel_mint_quote(&paid_id.to_string())
+        .await
+        .expect("Paid but unissued quote cancelled");
+    let paid = mint_bob
+        .localstore()
+        .get_mint_quote(&paid_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(paid.state(), MintQuoteState::Cancelled);
+    assert_eq!(paid.amount_issued(), Amount::ZERO);
+
+    let mut signed = unsigned;
+    signed.sign(paid_key).unwrap();
+    assert!(matches!(
+        mint_bob
+            .cancel_mint_quote(&PaymentMethod::Bolt11, signed)
+            .await,
+        Err(cdk::Error::CancelledQuote)
+    ));
+}
+
+/// Tests cancelling a mint quote that already has ecash issued:
+/// 1. The mint refuses to cancel the quote
+/// 2. The wallet keeps its local quote and can still recover the ecash
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_cancel_issued_mint_quote() {
+    setup_tracing();
+    let mint_bob =
+        create_and_start_test_mint_with_unit(CurrencyUnit::Sat, &[PaymentMethod::Bolt11])
+            .await
+            .expect("Failed to create test mint");
+    let wallet_alice = create_test_wallet_with_connection(
+        DirectMintConnection::new(mint_bob.clone()).with_dropped_mint_responses(1),
+        CurrencyUnit::Sat,
+        Mnemonic::generate(12).unwrap().to_seed_normalized(""),
+        Arc::new(SystemClock),
+    )
+    .await
+    .expect("Failed to create test wallet");
+    enable_quote_cancel(&mint_bob, &wallet_alice).await;
+
+    let (quote_id, _, request_lookup_id) =
+        add_unpaid_bolt11_quote(&mint_bob, &wallet_alice, 100).await;
+    mint_bob
+        .pay_mint_quote_for_request_id(WaitPaymentResponse {
+            payment_identifier: request_lookup_id,
+            payment_amount: 100.into(),
+            unit: CurrencyUnit::Sat,
+            payment_id: "payment".to_string(),
+        })
+        .await
+        .unwrap();
+
+    // The mint issues the ecash but the wallet never sees the response
+    let quote_id = quote_id.to_string();
+    assert!(wallet_alice
+        .mint(&quote_id, SplitTarget::default(), None)
+        .await
+        .is_err());
+
+    assert!(matches!(
+        wallet_alice.cancel_mint_quote(&quote_id).await,
+        Err(cdk::Error::IssuedQuote)
+    ));
+    assert!(wallet_alice
+        .localstore
+        .get_mint_quote(&quote_id)
+        .await
+        .unwrap()
+        .is_some());
+
+    let proofs = wallet_alice
+        .mint(&quote_id, SplitTarget::default(), None)
+        .await
+        .expect("Issued ecash recovered after the refused cancel");
+    assert_eq!(proofs.total_amount().unwrap(), Amount::from(100));
+}
+
+/// Tests minting a quote locked to a caller-provided key:
+/// 1. The quote is locked to the pubkey of the key and stores the key
+/// 2. The plain mint path signs the mint request with the stored key
+/// 3. A quote locked to a bare pubkey stores no key
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_mint_quote_with_secret_key() {
+    setup_tracing();
+    let mint_bob =
+        create_and_start_test_mint_with_unit(CurrencyUnit::Sat, &[PaymentMethod::Bolt11])
+            .await
+            .expect("Failed to create test mint");
+    let wallet_alice = create_test_wallet_for_mint(mint_bob.clone())
+        .await
+        .expect("Failed to create test wallet");
+
+    let secret_key = SecretKey::generate();
+    let quote = wallet_alice
+        .mint_quote_with_secret_key(100.into(), None, secret_key.clone())
+        .await
+        .unwrap();
+    assert_eq!(quote.secret_key, Some(secret_key.clone()));
+
+    let mint_quote = mint_bob
+        .localstore()
+        .get_mint_quote(&QuoteId::from_str(&quote.id).unwrap())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(mint_quote.pubkey, Some(secret_key.public_key()));
+
+    let proofs = wallet_alice
+        .wait_and_mint_quote(quote, SplitTarget::default(), None, Duration::from_secs(60))
+        .await
+        .expect("Quote minted with the stored key");
+    assert_eq!(proofs.total_amount().unwrap(), Amount::from(100));
+
+    let miner_key = SecretKey::generate();
+    let quote = wallet_alice
+        .mint_quote_with_pubkey(100.into(), None, Some(miner_key.public_key()))
+        .await
+        .unwrap();
+    assert_eq!(quote.secret_key, None);
+}
+
+/// Tests that mining share stats aggregate the quotes created in the range:
+/// 1. Quotes are created at two mock times a day apart, one of them is issued
+/// 2. A range covering only the first time counts only its quotes
+/// 3. A range covering both times counts all quotes and their distinct pubkeys
+/// 4. Ranges ending before they start or longer than 90 days are refused
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_mining_share_stats() {
+    setup_tracing();
+    let clock = MockClock::new(unix_time());
+    let mint_bob = create_and_start_test_mint_with_clock(
+        CurrencyUnit::Sat,
+        &[PaymentMethod::MiningShare],
+        Arc::new(clock.clone()),
+    )
+    .await
+    .expect("Failed to create test mint");
+    let wallet_alice = create_test_wallet_for_mint_with_clock(
+        mint_bob.clone(),
+        CurrencyUnit::Sat,
+        Arc::new(clock.clone()),
+    )
+    .await
+    .expect("Failed to create test wallet");
+
+    let pool_key = SecretKey::generate();
+    let other_key = SecretKey::generate();
+    let request = |share: &[u8], amount: u64, pubkey| MintQuoteMiningShareRequest {
+        amount: amount.into(),
+        unit: CurrencyUnit::Sat,
+        header_hash: sha256::Hash::hash(share),
+        description: None,
+        pubkey,
+        difficulty: None,
+    };
+
+    let first_time = clock.now_unix();
+    let issued = mint_bob
+        .create_mint_mining_share_quote(request(b"share 1", 64, pool_key.public_key()))
+        .await
+        .unwrap();
+    mint_bob
+        .create_mint_mining_share_quote(request(b"share 2", 32, other_key.public_key()))
+        .await
+        .unwrap();
+    wallet_alice
+        .mint_mining_share(
+            &issued.id.to_string(),
+            64.into(),
+            issued.keyset_id.expect("Mining share quote has a keyset"),
+            pool_key.clone(),
+            None,
+        )
+        .await
+        .expect("Failed to mint mining share");
+
+    clock.advance(24 * 60 * 60);
+    let second_time = clock.now_unix();
+    mint_bob
+        .create_mint_mining_share_quote(request(b"share 3", 16, pool_key.public_key()))
+        .await
+        .unwrap();
+
+    let stats = mint_bob
+        .mining_share_stats(first_time, first_time)
+        .await
+        .unwrap();
+    assert_eq!(stats.quotes_created, 2);
+    assert_eq!(stats.quotes_issued, 1);
+    assert_eq!(stats.amount_quoted, 96.into());
+    assert_eq!(stats.amount_issued, 64.into());
+    assert_eq!(stats.distinct_pubkeys, 2);
+
+    let stats = mint_bob
+        .mining_share_stats(first_time, second_time)
+        .await
+        .unwrap();
+    assert_eq!(stats.quotes_created, 3);
+    assert_eq!(stats.quotes_issued, 1);
+    assert_eq!(stats.amount_quoted, 112.into());
+    assert_eq!(stats.amount_issued, 64.into());
+    assert_eq!(stats.distinct_pubkeys, 2);
+
+    assert!(matches!(
+        mint_bob.mining_share_stats(second_time, first_time).await,
+        Err(cdk::Error::InvalidMiningShareStatsRange { .. })
+    ));
+    assert!(matches!(
+        mint_bob
+            .mining_share_stats(
+                first_time,
+                first_time + nutXX::MAX_MINING_SHARE_STATS_WINDOW + 1
+            )
+            .await,
+        Err(cdk::Error::InvalidMiningShareStatsRange { .. })
+    ));
+}
+
+/// Tests that auto mint mints a quote as soon as it is paid:
+/// 1. The task picks up the unpaid quote stored before it started
+/// 2. Paying the quote on the mint sends a minted event with the quote amount
+/// 3. The proofs are in the wallet once the event arrives
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_auto_mint_on_paid() {
+    setup_tracing();
+    let mint_bob = create_and_start_test_mint()
+        .await
+        .expect("Failed to create test mint");
+    let wallet_alice = create_test_wallet_for_mint(mint_bob.clone())
+        .await
+        .expect("Failed to create test wallet");
+
+    let (quote_id, _, request_lookup_id) =
+        add_unpaid_bolt11_quote(&mint_bob, &wallet_alice, 100).await;
+
+    let auto_mint = wallet_alice.start_auto_mint();
+    let mut events = auto_mint.subscribe();
+
+    mint_bob
+        .pay_mint_quote_for_request_id(WaitPaymentResponse {
+            payment_identifier: request_lookup_id,
+            payment_amount: 100.into(),
+            unit: CurrencyUnit::Sat,
+            payment_id: "payment".to_string(),
+        })
+        .await
+        .unwrap();
+
+    let event = tokio::time::timeout(Duration::from_secs(30), events.recv())
+        .await
+        .expect("Quote was not minted in time")
+        .unwrap();
+    assert_eq!(
+        event,
+        AutoMintEvent::Minted {
+            quote_id: quote_id.to_string(),
+            payment_method: PaymentMethod::Bolt11,
+            amount: 100.into(),
+        }
+    );
+    assert_eq!(wallet_alice.total_balance().await.unwrap(), 100.into());
+
+    auto_mint.stop().await;
+}
+
+/// Tests that the DLEQ proofs of a wallet's proofs are verified:
+/// 1. Proofs from two keysets verify with their own keys
+/// 2. A tampered DLEQ proof fails with the index of its proof
+/// 3. A missing DLEQ proof only fails in strict mode
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_verify_proofs_dleq() {
+    setup_tracing();
+    let mint_bob = create_and_start_test_mint()
+        .await
+        .expect("Failed to create test mint");
+    let wallet_alice = create_test_wallet_for_mint(mint_bob.clone())
+        .await
+        .expect("Failed to create test wallet");
+
+    fund_wallet(wallet_alice.clone(), 64, None).await.unwrap();
+    mint_bob
+        .rotate_keyset(CurrencyUnit::Sat, 32, 0)
+        .await
+        .unwrap();
+    wallet_alice.refresh_keysets().await.unwrap();
+    fund_wallet(wallet_alice.clone(), 32, None).await.unwrap();
+
+    let mut proofs = wallet_alice.get_unspent_proofs().await.unwrap();
+    proofs.sort_by_key(|proof| proof.keyset_id);
+    let keyset_ids: HashSet<Id> = proofs.iter().map(|proof| proof.keyset_id).collect();
+    assert_eq!(keyset_ids.len(), 2);
+
+    wallet_alice
+        .verify_proofs_dleq(&proofs, true)
+        .await
+        .unwrap();
+
+    // Tampered DLEQ on the last proof, which is from the other keyset than
+    // the first
+    let last = proofs.len() - 1;
+    let mut tampered = proofs.clone();
+    let dleq = tampered[last]
+        .dleq
+        .as_mut()
+        .expect("Mint returns DLEQ proofs");
+    dleq.e = SecretKey::generate();
+    match wallet_alice.verify_proofs_dleq(&tampered, false).await {
+        Err(cdk::Error::InvalidProofDleq(index)) => assert_eq!(index, last),
+        res => panic!("Tampered DLEQ was accepted: {:?}", res),
+    }
+
+    // Missing DLEQ
+    let mut missing = proofs.clone();
+    missing[0].dleq = None;
+    wallet_alice
+        .verify_proofs_dleq(&missing, false)
+        .await
+        .expect("Missing DLEQ passes when not strict");
+    match wallet_alice.verify_proofs_dleq(&missing, true).await {
+        Err(cdk::Error::InvalidProofDleq(index)) => assert_eq!(index, 0),
+        res => panic!("Missing DLEQ was accepted in strict mode: {:?}", res),
+    }
+}
+
+/// Tests that mining share quotes are refused before contacting a mint that
+/// does not advertise them:
+/// 1. A bolt11 only mint does not support mining shares
+/// 2. Single and batch mining share quotes fail with `MintDoesNotSupport`
+/// 3. A mint with the mining share method supports them
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_mint_supports_mining_share() {
+    setup_tracing();
+    let mint_bob = create_and_start_test_mint()
+        .await
+        .expect("Failed to create test mint");
+    let wallet_alice = create_test_wallet_for_mint(mint_bob.clone())
+        .await
+        .expect("Failed to create test wallet");
+
+    assert!(!wallet_alice
+        .mint_supports(Capability::MiningShare)
+        .await
+        .unwrap());
+
+    match wallet_alice
+        .mint_quote_mining_share(64.into(), sha256::Hash::hash(b"share"), None)
+        .await
+    {
+        Err(cdk::Error::MintDoesNotSupport(capability)) => {
+            assert_eq!(capability, Capability::MiningShare.name())
+        }
+        res => panic!("Mining share quote was requested: {:?}", res),
+    }
+    assert!(matches!(
+        wallet_alice
+            .mint_quotes_mining_share(&[(64.into(), sha256::Hash::hash(b"share"))])
+            .await,
+        Err(cdk::Error::MintDoesNotSupport(_))
+    ));
+    assert!(wallet_alice
+        .get_active_mint_quotes()
+        .await
+        .unwrap()
+        .is_empty());
+
+    let mint_carol =
+        create_and_start_test_mint_with_unit(CurrencyUnit::Sat, &[PaymentMethod::MiningShare])
+            .await
+            .expect("Failed to create test mint");
+    let wallet_carol = create_test_wallet_for_mint(mint_carol.clone())
+        .await
+        .expect("Failed to create test wallet");
+
+    assert!(wallet_carol
+        .mint_supports(Capability::MiningShare)
+        .await
+        .unwrap());
+    wallet_carol
+        .mint_quote_mining_share(64.into(), sha256::Hash::hash(b"share"), None)
+        .await
+        .expect("Mining share quote");
+}
+
+/// Tests that bolt11 and mining share minting finalize proofs the same way:
+/// 1. A bolt11 mint stores the proofs, records a transaction and removes the quote
+/// 2. A mining share mint records a transaction and marks the quote issued
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_mint_records_transaction() {
+    setup_tracing();
+    let mint_bob = create_and_start_test_mint_with_unit(
+        CurrencyUnit::Sat,
+        &[PaymentMethod::Bolt11, PaymentMethod::MiningShare],
+    )
+    .await
+    .expect("Failed to create test mint");
+    let wallet_alice = create_test_wallet_for_mint(mint_bob.clone())
+        .await
+        .expect("Failed to create test wallet");
+
+    fund_wallet(wallet_alice.clone(), 100, None).await.unwrap();
+    let proofs = wallet_alice.get_unspent_proofs().await.unwrap();
+
+    let transactions = wallet_alice
+        .list_transactions(Some(TransactionDirection::Incoming))
+        .await
+        .unwrap();
+    assert_eq!(transactions.len(), 1);
+    assert_eq!(transactions[0].amount, 100.into());
+    assert_eq!(
+        transactions[0].ys.iter().collect::<HashSet<_>>(),
+        proofs.ys().unwrap().iter().collect::<HashSet<_>>()
+    );
+    let quote_id = transactions[0].quote_id.clone().expect("Quote recorded");
+    assert!(wallet_alice
+        .localstore
+        .get_mint_quote(&quote_id)
+        .await
+        .unwrap()
+        .is_none());
+
+    let quote = wallet_alice
+        .mint_quote_mining_share(64.into(), sha256::Hash::hash(b"finalized share"), None)
+        .await
+        .unwrap();
+    let proofs = wallet_alice
+        .mint_mining_share(
+            &quote.id,
+            Amount::ZERO,
+            quote.keyset_id.expect("Mining share quotes have a keyset"),
+            quote.secret_key.clone().expect("Quote is locked"),
+            None,
+        )
+        .await
+        .unwrap();
+
+    let transaction = wallet_alice
+        .list_transactions(Some(TransactionDirection::Incoming))
+        .await
+        .unwrap()
+        .into_iter()
+        .find(|transaction| transaction.quote_id.as_deref() == Some(quote.id.as_str()))
+        .expect("Mining share mint is recorded");
+    assert_eq!(transaction.amount, 64.into());
+    assert_eq!(transaction.ys, proofs.ys().unwrap());
+
+    let stored = wallet_alice
+        .localstore
+        .get_mint_quote(&quote.id)
+        .await
+        .unwrap()
+        .expect("Quote is stored by the wallet");
+    assert_eq!(stored.amount_issued, 64.into());
+    assert_eq!(stored.state, MintQuoteState::Issued);
+}
+
+/// Tests streaming mining share quote creation for a large backlog:
+/// 1. 500 shares at concurrency 4 yield one quote per share in input order
+/// 2. Every quote is stored by the wallet
+/// 3. A cancelled stream yields nothing
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_mint_quotes_mining_share_stream() {
+    setup_tracing();
+    let mint_bob =
+        create_and_start_test_mint_with_unit(CurrencyUnit::Sat, &[PaymentMethod::MiningShare])
+            .await
+            .expect("Failed to create test mint");
+    let wallet_alice = create_test_wallet_for_mint(mint_bob.clone())
+        .await
+        .expect("Failed to create test wallet");
+
+    let shares: Vec<(Amount, sha256::Hash)> = (0..500u32)
+        .map(|index| (Amount::ONE, sha256::Hash::hash(&index.to_be_bytes())))
+        .collect();
+
+    let results: Vec<_> = wallet_alice
+        .mint_quotes_mining_share_stream(shares.clone(), 4, CancellationToken::new())
+        .collect()
+        .await;
+    assert_eq!(results.len(), shares.len());
+
+    for ((_, header_hash), result) in shares.iter().zip(results) {
+        let quote = result.expect("Share was quoted");
+        assert_eq!(quote.request, header_hash.to_string());
+        assert!(wallet_alice
+            .localstore
+            .get_mint_quote(&quote.id)
+            .await
+            .unwrap()
+            .is_some());
+    }
+
+    let cancel_token = CancellationToken::new();
+    cancel_token.cancel();
+    let results: Vec<_> = wallet_alice
+        .mint_quotes_mining_share_stream(shares, 4, cancel_token)
+        .collect()
+        .await;
+    assert!(results.is_empty());
+}
+
+/// Tests moving pending mining share quotes to another wallet:
+/// 1. The export holds the quote but not its secret key
+/// 2. Another wallet with its own localstore imports it once
+/// 3. The importing wallet mints the quote with the secret key
+/// 4. Exports of an unknown version are refused
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_export_import_pending_quotes() {
+    setup_tracing();
+    let mint_bob =
+        create_and_start_test_mint_with_unit(CurrencyUnit::Sat, &[PaymentMethod::MiningShare])
+            .await
+            .expect("Failed to create test mint");
+    let wallet_proxy = create_test_wallet_for_mint(mint_bob.clone())
+        .await
+        .expect("Failed to create test wallet");
+    let wallet_phone = create_test_wallet_for_mint(mint_bob.clone())
+        .await
+        .expect("Failed to create test wallet");
+
+    let quote = wallet_proxy
+        .mint_quote_mining_share(64.into(), sha256::Hash::hash(b"exported share"), None)
+        .await
+        .unwrap();
+    let secret_key = quote.secret_key.clone().expect("Quote is locked");
+
+    let export = wallet_proxy.export_pending_quotes().await.unwrap();
+    assert_eq!(export.version, PENDING_QUOTE_EXPORT_VERSION);
+    assert_eq!(export.quotes.len(), 1);
+    assert_eq!(export.quotes[0].id, quote.id);
+    assert_eq!(export.quotes[0].pubkey, Some(secret_key.public_key()));
+    let json = serde_json::to_string(&export).unwrap();
+    assert!(!json.contains(&secret_key.to_secret_hex()));
+
+    let imported = wallet_phone
+        .import_pending_quotes(export.clone(), Some(secret_key.clone()))
+        .await
+        .unwrap();
+    assert_eq!(imported.len(), 1);
+    assert_eq!(imported[0].secret_key, Some(secret_key.clone()));
+    assert!(wallet_phone
+        .import_pending_quotes(export.clone(), Some(secret_key.clone()))
+        .await
+        .unwrap()
+        .is_empty());
+
+    let proofs = wallet_phone
+        .mint_mining_share(
+            &quote.id,
+            Amount::ZERO,
+            imported[0]
+                .keyset_id
+                .expect("Mining share quotes have a keyset"),
+            secret_key,
+            None,
+        )
+        .await
+        .expect("Imported quote is minted");
+    assert_eq!(proofs.total_amount().unwrap(), 64.into());
+    assert_eq!(wallet_phone.total_balance().await.unwrap(), 64.into());
+
+    let mut unknown_version = export;
+    unknown_version.version = PENDING_QUOTE_EXPORT_VERSION + 1;
+    assert!(matches!(
+        wallet_phone
+            .import_pending_quotes(unknown_version, None)
+            .await,
+        Err(cdk::Error::UnsupportedQuoteExportVersion(_))
+    ));
+}
+
+/// Tests the balance breakdown by proof source and keyset:
+/// 1. Proofs minted from bolt11 and mining share quotes are counted by source
+/// 2. Proofs received from a swap are counted as swapped
+/// 3. The totals by source and by keyset both add up to the balance
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_balance_by_origin() {
+    setup_tracing();
+    let mint_bob = create_and_start_test_mint_with_unit(
+        CurrencyUnit::Sat,
+        &[PaymentMethod::Bolt11, PaymentMethod::MiningShare],
+    )
+    .await
+    .expect("Failed to create test mint");
+    let wallet_alice = create_test_wallet_for_mint(mint_bob.clone())
+        .await
+        .expect("Failed to create test wallet");
+
+    fund_wallet(wallet_alice.clone(), 100, None).await.unwrap();
+
+    let quote = wallet_alice
+        .mint_quote_mining_share(64.into(), sha256::Hash::hash(b"origin share"), None)
+        .await
+        .unwrap();
+    wallet_alice
+        .mint_mining_share(
+            &quote.id,
+            Amount::ZERO,
+            quote.keyset_id.expect("Mining share quotes have a keyset"),
+            quote.secret_key.clone().expect("Quote is locked"),
+            None,
+        )
+        .await
+        .unwrap();
+
+    let origins = wallet_alice.balance_by_origin().await.unwrap();
+    assert_eq!(origins.total, 164.into());
+    assert_eq!(
+        origins.by_source,
+        [
+            (ProofSource::Bolt11Mint, Amount::from(100)),
+            (ProofSource::MiningShareMint, Amount::from(64)),
+        ]
+        .into_iter()
+        .collect()
+    );
+    assert_eq!(
+        Amount::try_sum(origins.by_keyset.values().copied()).unwrap(),
+        origins.total
+    );
+
+    let bolt11_proofs: Proofs = wallet_alice
+        .localstore
+        .get_proofs(None, None, Some(vec![State::Unspent]), None)
+        .await
+        .unwrap()
+        .into_iter()
+        .filter(|info| info.source == ProofSource::Bolt11Mint)
+        .map(|info| info.proof)
+        .collect();
+    wallet_alice
+        .swap(None, SplitTarget::None, bolt11_proofs, None, false)
+        .await
+        .unwrap();
+
+    let origins = wallet_alice.balance_by_origin().await.unwrap();
+    assert_eq!(origins.total, 164.into());
+    assert_eq!(origins.by_source.get(&ProofSource::Bolt11Mint), None);
+    assert_eq!(
+        origins.by_source.get(&ProofSource::Swap),
+        Some(&Amount::from(100))
+    );
+    assert_eq!(
+        origins.by_source.get(&ProofSource::MiningShareMint),
+        Some(&Amount::from(64))
+    );
+    assert_eq!(wallet_alice.total_balance().await.unwrap(), origins.total);
+}
+
+/// Tests requesting a mining share quote that reserves its premint secrets:
+/// 1. The quote is locked to the given key and records the reserved counter
+///    range, whose size is returned with the quote
+/// 2. Minting it survives a lost response by recovering the signatures of
+///    the reserved outputs without a second mint request
+/// 3. The proofs are the ones derived from the reserved counter
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_mining_share_quote_reserves_premint_secrets() {
+    setup_tracing();
+    let mint_bob = create_and_start_test_mint_with_unit(
+        CurrencyUnit::Sat,
+        &[PaymentMethod::Bolt11, PaymentMethod::MiningShare],
+    )
+    .await
+    .expect("Failed to create test mint");
+    let seed = Mnemonic::generate(12).unwrap().to_seed_normalized("");
+
+    let connection = DirectMintConnection::new(mint_bob.clone()).with_dropped_mint_responses(1);
+    let mint_requests = connection.mint_requests.clone();
+    let wallet_alice = create_test_wallet_with_connection(
+        connection,
+        CurrencyUnit::Sat,
+        seed,
+        Arc::new(SystemClock),
+    )
+    .await
+    .expect("Failed to create test wallet");
+
+    let secret_key = SecretKey::generate();
+    let (quote, num_secrets) = wallet_alice
+        .mint_quote_mining_share_with_secret_key(
+            100.into(),
+            sha256::Hash::hash(b"reserved share"),
+            None,
+            Some(secret_key.clone()),
+        )
+        .await
+        .unwrap();
+    assert_eq!(quote.secret_key, Some(secret_key.clone()));
+    assert_eq!(num_secrets, 3);
+
+    let keyset_id = quote.premint_keyset_id.expect("Premint keyset recorded");
+    let counter = quote.premint_counter.expect("Premint counter recorded");
+    assert_eq!(quote.keyset_id, Some(keyset_id));
+    assert_eq!(
+        wallet_alice
+            .localstore
+            .increment_keyset_counter(&keyset_id, 0)
+            .await
+            .unwrap(),
+        counter + num_secrets
+    );
+
+    let proofs = wallet_alice
+        .mint_mining_share(&quote.id, Amount::ZERO, keyset_id, secret_key, None)
+        .await
+        .expect("Lost response is recovered");
+    assert_eq!(mint_requests.load(Ordering::SeqCst), 1);
+    assert_eq!(proofs.total_amount().unwrap(), Amount::from(100));
+    assert_eq!(
+        wallet_alice.total_balance().await.unwrap(),
+        Amount::from(100)
+    );
+
+    let expected = PreMintSecrets::from_seed(
+        keyset_id,
+        counter,
+        &seed,
+        Amount::from(100),
+        &SplitTarget::default(),
+    )
+    .unwrap();
+    assert_eq!(
+        proofs
+            .iter()
+            .map(|proof| proof.secret.clone())
+            .collect::<Vec<_>>(),
+        expected.secrets()
+    );
+}
+
+/// Tests a mining share quote minted by a second device sharing the seed:
+/// 1. Alice's phone requests a quote, her laptop mints it from the same
+///    counter the phone reserved
+/// 2. Checking the quote on the phone reports it issued elsewhere
+/// 3. Reconciling recovers the proofs from the reserved premint counter
+/// 4. Checking again reports no conflict
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_quote_issued_elsewhere() {
+    setup_tracing();
+    let mint_bob = create_and_start_test_mint_with_unit(
+        CurrencyUnit::Sat,
+        &[PaymentMethod::Bolt11, PaymentMethod::MiningShare],
+    )
+    .await
+    .expect("Failed to create test mint");
+    let seed = Mnemonic::generate(12).unwrap().to_seed_normalized("");
+
+    let wallet_phone = create_test_wallet_with_connection(
+        DirectMintConnection::new(mint_bob.clone()),
+        CurrencyUnit::Sat,
+        seed,
+        Arc::new(SystemClock),
+    )
+    .await
+    .expect("Failed to create test wallet");
+    let wallet_laptop = create_test_wallet_with_connection(
+        DirectMintConnection::new(mint_bob.clone()),
+        CurrencyUnit::Sat,
+        seed,
+        Arc::new(SystemClock),
+    )
+    .await
+    .expect("Failed to create test wallet");
+
+    let quote = wallet_phone
+        .mint_quote_mining_share(64.into(), sha256::Hash::hash(b"shared share"), None)
+        .await
+        .unwrap();
+    let laptop_proofs = wallet_laptop
+        .mint_mining_share(
+            &quote.id,
+            64.into(),
+            quote.keyset_id.expect("Mining share quotes have a keyset"),
+            quote.secret_key.clone().expect("Quote is locked"),
+            None,
+        )
+        .await
+        .unwrap();
+
+    let update = wallet_phone
+        .mint_quote_state_mining_share(&quote.id)
+        .await
+        .unwrap();
+    assert_eq!(update.response.state, nutXX::QuoteState::Issued);
+    assert_eq!(
+        update.conflict,
+        Some(QuoteConflict::IssuedElsewhere {
+            quote_id: quote.id.clone(),
+            stored_state: MintQuoteState::Paid,
+        })
+    );
+
+    assert_eq!(
+        wallet_phone
+            .reconcile_issued_quote(&quote.id)
+            .await
+            .unwrap(),
+        Amount::from(64)
+    );
+    assert_eq!(
+        wallet_phone.total_balance().await.unwrap(),
+        Amount::from(64)
+    );
+    let phone_proofs = wallet_phone
+        .localstore
+        .get_proofs(None, None, Some(vec![State::Unspent]), None)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|info| info.proof.secret)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        phone_proofs,
+        laptop_proofs
+            .into_iter()
+            .map(|proof| proof.secret)
+            .collect::<Vec<_>>()
+    );
+
+    let update = wallet_phone
+        .mint_quote_state_mining_share(&quote.id)
+        .await
+        .unwrap();
+    assert_eq!(update.conflict, None);
+}
+
+/// Tests that finalizing a mint again after it stopped before the quote
+/// update completes it without duplicating anything:
+/// 1. Alice mints a mining share quote
+/// 2. The stored quote is reset as if the wallet stopped before updating it
+/// 3. Reconciling updates the quote and keeps one set of proofs and one
+///    transaction
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_finalize_issued_proofs_repeatable() {
+    setup_tracing();
+    let mint_bob = create_and_start_test_mint_with_unit(
+        CurrencyUnit::Sat,
+        &[PaymentMethod::Bolt11, PaymentMethod::MiningShare],
+    )
+    .await
+    .expect("Failed to create test mint");
+    let wallet_alice = create_test_wallet_for_mint(mint_bob.clone())
+        .await
+        .expect("Failed to create test wallet");
+
+    let quote = wallet_alice
+        .mint_quote_mining_share(64.into(), sha256::Hash::hash(b"interrupted share"), None)
+        .await
+        .unwrap();
+    wallet_alice
+        .mint_mining_share(
+            &quote.id,
+            64.into(),
+            quote.keyset_id.expect("Mining share quotes have a keyset"),
+            quote.secret_key.clone().expect("Quote is locked"),
+            None,
+        )
+        .await
+        .unwrap();
+
+    let mut stored = wallet_alice
+        .localstore
+        .get_mint_quote(&quote.id)
+        .await
+        .unwrap()
+        .expect("Quote stored");
+    assert_eq!(stored.amount_issued, Amount::from(64));
+    stored.amount_issued = Amount::ZERO;
+    stored.state = MintQuoteState::Paid;
+    wallet_alice
+        .localstore
+        .add_mint_quote(stored)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        wallet_alice
+            .reconcile_issued_quote(&quote.id)
+            .await
+            .unwrap(),
+        Amount::from(64)
+    );
+
+    let stored = wallet_alice
+        .localstore
+        .get_mint_quote(&quote.id)
+        .await
+        .unwrap()
+        .expect("Quote stored");
+    assert_eq!(stored.amount_issued, Amount::from(64));
+    assert_eq!(stored.state, MintQuoteState::Issued);
+    assert_eq!(
+        wallet_alice.total_balance().await.unwrap(),
+        Amount::from(64)
+    );
+    assert_eq!(wallet_alice.list_transactions(None).await.unwrap().len(), 1);
+}
+
+/// Tests that sending from a wallet of many one-unit proofs with max inputs:
+/// 1. Consolidates the selected proofs before the send
+/// 2. Sends with at most the max inputs
+/// 3. Produces a token the receiver can redeem
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_send_within_max_inputs() {
+    setup_tracing();
+    let mint_bob = create_and_start_test_mint()
+        .await
+        .expect("Failed to create test mint");
+    let wallet_alice = create_test_wallet_for_mint(mint_bob.clone())
+        .await
+        .expect("Failed to create test wallet");
+
+    fund_wallet(
+        wallet_alice.clone(),
+        10_000,
+        Some(SplitTarget::Value(1.into())),
+    )
+    .await
+    .expect("Failed to fund wallet");
+    assert_eq!(
+        wallet_alice.get_unspent_proofs().await.unwrap().len(),
+        10_000
+    );
+
+    let max_inputs = 100;
+    let wallet_limited = WalletBuilder::new()
+        .mint_url(wallet_alice.mint_url.clone())
+        .unit(CurrencyUnit::Sat)
+        .localstore(wallet_alice.localstore.clone())
+        .seed(Mnemonic::generate(12).unwrap().to_seed_normalized(""))
+        .client(DirectMintConnection::new(mint_bob.clone()))
+        .selection_strategy(SelectionStrategy::SmallestFirst)
+        .max_inputs(max_inputs)
+        .build()
+        .unwrap();
+
+    let prepared = wallet_limited
+        .prepare_send(512.into(), SendOptions::default())
+        .await
+        .unwrap();
+    let inputs = prepared.proofs_to_swap().len() + prepared.proofs_to_send().len();
+    assert!(inputs <= max_inputs, "{} inputs selected", inputs);
+
+    let token = prepared.confirm(None).await.unwrap();
+    let keysets_info = wallet_limited.get_mint_keysets().await.unwrap();
+    let token_proofs = token.proofs(&keysets_info).unwrap();
+    assert_eq!(token_proofs.total_amount().unwrap(), 512.into());
+
+    let wallet_carol = create_test_wallet_for_mint(mint_bob.clone())
+        .await
+        .expect("Failed to create test wallet");
+    let received = wallet_carol
+        .receive_proofs(token_proofs, ReceiveOptions::default(), None)
+        .await
+        .unwrap();
+    assert_eq!(received, 512.into());
+    assert!(wallet_alice.total_balance().await.unwrap() <= Amount::from(10_000 - 512));
+}
+
+/// Tests that the mint publishes mining share quote updates to subscribers:
+/// 1. A subscriber listens on the header hash before the quote exists
+/// 2. Creating the quote publishes it as paid
+/// 3. Minting it publishes it as issued, after the creation
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_mint_publishes_mining_share_quote_updates() {
+    setup_tracing();
+    let mint_bob = create_and_start_test_mint_with_unit(
+        CurrencyUnit::Sat,
+        &[PaymentMethod::Bolt11, PaymentMethod::MiningShare],
+    )
+    .await
+    .expect("Failed to create test mint");
+    let wallet_alice = create_test_wallet_for_mint(mint_bob.clone())
+        .await
+        .expect("Failed to create test wallet");
+
+    let header_hash = sha256::Hash::hash(b"published share");
+    let mut listener = mint_bob
+        .pubsub_manager()
+        .try_subscribe::<IndexableParams>(
+            Params {
+                kind: cdk::nuts::nut17::Kind::MiningShareMintQuote,
+                filters: vec![header_hash.to_string()],
+                id: "mining_share".into(),
+            }
+            .into(),
+        )
+        .await
+        .expect("valid subscription");
+
+    let pool_key = SecretKey::generate();
+    let quote = mint_bob
+        .create_mint_mining_share_quote(MintQuoteMiningShareRequest {
+            amount: 64.into(),
+            unit: CurrencyUnit::Sat,
+            header_hash,
+            description: None,
+            pubkey: pool_key.public_key(),
+            difficulty: None,
+        })
+        .await
+        .expect("Failed to create mining share quote");
+    let keyset_id = quote.keyset_id.expect("Mining share quote has a keyset");
+
+    wallet_alice
+        .mint_mining_share(&quote.id.to_string(), 64.into(), keyset_id, pool_key, None)
+        .await
+        .expect("Failed to mint mining share");
+
+    sleep(Duration::from_secs(1)).await;
+
+    let mut updates = Vec::new();
+    while let Ok((sub_id, msg)) = listener.try_recv() {
+        assert_eq!(sub_id, "mining_share".into());
+        match msg {
+            NotificationPayload::MintQuoteMiningShareResponse(response) => {
+                assert_eq!(response.quote, quote.id);
+                updates.push((response.state, response.amount_issued));
+            }
+            _ => panic!("Wrong message received"),
+        }
+    }
+
+    let created = updates
+        .iter()
+        .position(|update| *update == (nutXX::QuoteState::Paid, Amount::ZERO))
+        .expect("Creation published");
+    let issued = updates
+        .iter()
+        .position(|update| *update == (nutXX::QuoteState::Issued, Amount::from(64)))
+        .expect("Issuance published");
+    assert!(created < issued, "updates out of order: {:?}", updates);
+}
+
+/// Tests that mining share quote batches with amounts near the maximum fail cleanly:
+/// 1. The mint refuses batches whose amounts overflow
+/// 2. The mint refuses a batch over its advertised batch maximum
+/// 3. The wallet splits shares at the batch maximum, also when streaming
+/// 4. The wallet refuses a share over the batch maximum without contacting the mint
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_mining_share_batch_amount_limits() {
+    setup_tracing();
+    let mint_bob = create_and_start_test_mint_with_unit(
+        CurrencyUnit::Sat,
+        &[PaymentMethod::Bolt11, PaymentMethod::MiningShare],
+    )
+    .await
+    .expect("Failed to create test mint");
+    let wallet_alice = create_test_wallet_for_mint(mint_bob.clone())
+        .await
+        .expect("Failed to create test wallet");
+
+    let pool_key = SecretKey::generate();
+    let request = |share: &[u8], amount: u64| MintQuoteMiningShareRequest {
+        amount: amount.into(),
+        unit: CurrencyUnit::Sat,
+        header_hash: sha256::Hash::hash(share),
+        description: None,
+        pubkey: pool_key.public_key(),
+        difficulty: None,
+    };
+
+    for offset in [0, 1, 2, 255, u64::MAX / 2] {
+        let quotes = vec![
+            request(b"large share", u64::MAX - offset),
+            request(b"overflowing share", offset + 1),
+        ];
+        assert!(matches!(
+            mint_bob
+                .create_mint_mining_share_quotes(BatchMintQuoteMiningShareRequest { quotes })
+                .await,
+            Err(cdk::Error::AmountOverflow)
+        ));
+    }
+
+    let mut mint_info = mint_bob.mint_info().await.unwrap();
+    mint_info.nuts = mint_info.nuts.nutxx(nutXX::Settings {
+        max_batch_amount: Some(128.into()),
+        ..Default::default()
+    });
+    mint_bob.set_mint_info(mint_info).await.unwrap();
+    wallet_alice.fetch_mint_info().await.unwrap();
+
+    let quotes = vec![
+        request(b"share 1", 64),
+        request(b"share 2", 64),
+        request(b"share 3", 1),
+    ];
+    assert!(matches!(
+        mint_bob
+            .create_mint_mining_share_quotes(BatchMintQuoteMiningShareRequest { quotes })
+            .await,
+        Err(cdk::Error::BatchMintQuoteAmountTooLarge { max, amount })
+            if max == 128.into() && amount == 129.into()
+    ));
+
+    let shares: Vec<(Amount, sha256::Hash)> = (0..3u8)
+        .map(|i| (Amount::from(64), sha256::Hash::hash(&[i])))
+        .collect();
+    let quotes = wallet_alice
+        .mint_quotes_mining_share(&shares)
+        .await
+        .unwrap();
+    assert_eq!(quotes.len(), 3);
+
+    let shares: Vec<(Amount, sha256::Hash)> = (3..6u8)
+        .map(|i| (Amount::from(64), sha256::Hash::hash(&[i])))
+        .collect();
+    let results: Vec<_> = wallet_alice
+        .mint_quotes_mining_share_stream(shares, 2, CancellationToken::new())
+        .collect()
+        .await;
+    assert_eq!(results.len(), 3);
+    assert!(results.iter().all(Result::is_ok));
+
+    for amount in [129, u64::MAX - 1, u64::MAX] {
+        let shares = [(Amount::from(amount), sha256::Hash::hash(b"oversized share"))];
+        assert!(matches!(
+            wallet_alice.mint_quotes_mining_share(&shares).await,
+            Err(cdk::Error::BatchMintQuoteAmountTooLarge { .. })
+        ));
+    }
+}
+
+/// Tests replaying mint requests queued while the mint was unreachable:
+/// 1. A bolt11 mint whose response is lost and a mining share mint that never
+///    reaches the mint both fail with `QueuedForRetry` and are stored
+/// 2. A wallet with a new client replays both: the lost response is recovered
+///    without a new mint request, the mining share request is sent once
+/// 3. The stored proofs are the queued outputs and replaying again mints
+///    nothing
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_process_pending_mint_operations() {
+    setup_tracing();
+    let mint_bob = create_and_start_test_mint_with_unit(
+        CurrencyUnit::Sat,
+        &[PaymentMethod::Bolt11, PaymentMethod::MiningShare],
+    )
+    .await
+    .expect("Failed to create test mint");
+    let seed = Mnemonic::generate(12).unwrap().to_seed_normalized("");
+    let wallet_with = |wallet: &Wallet, connection: DirectMintConnection| {
+        WalletBuilder::new()
+            .mint_url(wallet.mint_url.clone())
+            .unit(CurrencyUnit::Sat)
+            .localstore(wallet.localstore.clone())
+            .seed(seed)
+            .client(connection)
+            .build()
+            .unwrap()
+    };
+
+    let connection = DirectMintConnection::new(mint_bob.clone()).with_dropped_mint_responses(1);
+    let dropped_requests = connection.mint_requests.clone();
+    let wallet_alice = create_test_wallet_with_connection(
+        connection,
+        CurrencyUnit::Sat,
+        seed,
+        Arc::new(SystemClock),
+    )
+    .await
+    .expect("Failed to create test wallet");
+
+    let (bolt11_quote_id, _, request_lookup_id) =
+        add_unpaid_bolt11_quote(&mint_bob, &wallet_alice, 100).await;
+    mint_bob
+        .pay_mint_quote_for_request_id(WaitPaymentResponse {
+            payment_identifier: request_lookup_id,
+            payment_amount: 100.into(),
+            unit: CurrencyUnit::Sat,
+            payment_id: "payment".to_string(),
+        })
+        .await
+        .unwrap();
+    let bolt11_quote_id = bolt11_quote_id.to_string();
+    assert!(matches!(
+        wallet_alice
+            .mint(&bolt11_quote_id, SplitTarget::default(), None)
+            .await,
+        Err(cdk::Error::QueuedForRetry(quote_id)) if quote_id == bolt11_quote_id
+    ));
+    assert_eq!(dropped_requests.load(Ordering::SeqCst), 1);
+
+    // The client is replaced, the mint cannot be reached by the new one
+    let connection = DirectMintConnection::new(mint_bob.clone()).with_unreachable_mint_requests(1);
+    let unreachable_requests = connection.mint_requests.clone();
+    let wallet_alice = wallet_with(&wallet_alice, connection);
+
+    let pool_key = SecretKey::generate();
+    let quote = mint_bob
+        .create_mint_mining_share_quote(MintQuoteMiningShareRequest {
+            amount: 64.into(),
+            unit: CurrencyUnit::Sat,
+            header_hash: sha256::Hash::hash(b"queued share"),
+            description: None,
+            pubkey: pool_key.public_key(),
+            difficulty: None,
+        })
+        .await
+        .expect("Failed to create mining share quote");
+    let share_quote_id = quote.id.to_string();
+    assert!(matches!(
+        wallet_alice
+            .mint_mining_share(
+                &share_quote_id,
+                64.into(),
+                quote.keyset_id.expect("Mining share quote has a keyset"),
+                pool_key,
+                None,
+            )
+            .await,
+        Err(cdk::Error::QueuedForRetry(quote_id)) if quote_id == share_quote_id
+    ));
+    assert_eq!(unreachable_requests.load(Ordering::SeqCst), 0);
+
+    let mut queued_quotes = vec![bolt11_quote_id.clone(), share_quote_id.clone()];
+    queued_quotes.sort();
+    let operations = wallet_alice.pending_mint_operations().await.unwrap();
+    let mut operation_quotes: Vec<_> = operations
+        .iter()
+        .map(|operation| operation.quote_id.clone())
+        .collect();
+    operation_quotes.sort();
+    assert_eq!(operation_quotes, queued_quotes);
+    assert_eq!(wallet_alice.total_balance().await.unwrap(), Amount::ZERO);
+
+    // The mint is reachable again with yet another client
+    let connection = DirectMintConnection::new(mint_bob.clone());
+    let replayed_requests = connection.mint_requests.clone();
+    let wallet_alice = wallet_with(&wallet_alice, connection);
+
+    let mut summary = wallet_alice.process_pending_operations().await.unwrap();
+    assert_eq!(summary.total_minted, Amount::from(164));
+    summary.minted_quotes.sort();
+    assert_eq!(summary.minted_quotes, queued_quotes);
+    assert!(summary.pending_quotes.is_empty());
+    assert!(summary.expired_quotes.is_empty());
+    assert!(summary.failed_quotes.is_empty());
+    assert_eq!(replayed_requests.load(Ordering::SeqCst), 1);
+
+    let mut expected_secrets: Vec<_> = operations
+        .iter()
+        .flat_map(|operation| operation.premint_secrets.secrets())
+        .collect();
+    let mut stored_secrets: Vec<_> = wallet_alice
+        .get_unspent_proofs()
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|proof| proof.secret)
+        .collect();
+    expected_secrets.sort();
+    stored_secrets.sort();
+    assert_eq!(stored_secrets, expected_secrets);
+    assert_eq!(
+        wallet_alice.total_balance().await.unwrap(),
+        Amount::from(164)
+    );
+
+    // Nothing is left to replay
+    assert!(wallet_alice
+        .pending_mint_operations()
+        .await
+        .unwrap()
+        .is_empty());
+    let summary = wallet_alice.process_pending_operations().await.unwrap();
+    assert!(summary.minted_quotes.is_empty());
+    assert_eq!(replayed_requests.load(Ordering::SeqCst), 1);
+    assert_eq!(
+        wallet_alice.total_balance().await.unwrap(),
+        Amount::from(164)
+    );
+}
+
+/// Tests queueing two part mints of the same mining share quote:
+/// 1. Both part mints fail to reach the mint and are queued side by side
+/// 2. Replaying mints both parts, the quote is fully issued
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_pending_part_mints_of_one_quote() {
+    setup_tracing();
+    let mint_bob = create_and_start_test_mint_with_unit(
+        CurrencyUnit::Sat,
+        &[PaymentMethod::Bolt11, PaymentMethod::MiningShare],
+    )
+    .await
+    .expect("Failed to create test mint");
+    let seed = Mnemonic::generate(12).unwrap().to_seed_normalized("");
+    let wallet_alice = create_test_wallet_with_connection(
+        DirectMintConnection::new(mint_bob.clone()).with_unreachable_mint_requests(2),
+        CurrencyUnit::Sat,
+        seed,
+        Arc::new(SystemClock),
+    )
+    .await
+    .expect("Failed to create test wallet");
+
+    let pool_key = SecretKey::generate();
+    let quote = mint_bob
+        .create_mint_mining_share_quote(MintQuoteMiningShareRequest {
+            amount: 64.into(),
+            unit: CurrencyUnit::Sat,
+            header_hash: sha256::Hash::hash(b"share minted in parts"),
+            description: None,
+            pubkey: pool_key.public_key(),
+            difficulty: None,
+        })
+        .await
+        .expect("Failed to create mining share quote");
+    let quote_id = quote.id.to_string();
+    let keyset_id = quote.keyset_id.expect("Mining share quote has a keyset");
+
+    for _ in 0..2 {
+        assert!(matches!(
+            wallet_alice
+                .mint_mining_share(&quote_id, 32.into(), keyset_id, pool_key.clone(), None)
+                .await,
+            Err(cdk::Error::QueuedForRetry(_))
+        ));
+    }
+
+    let operations = wallet_alice.pending_mint_operations().await.unwrap();
+    assert_eq!(operations.len(), 2);
+    assert!(operations
+        .iter()
+        .all(|operation| operation.quote_id == quote_id));
+    assert_ne!(operations[0].id, operations[1].id);
+
+    let summary = wallet_alice.process_pending_operations().await.unwrap();
+    assert_eq!(summary.total_minted, Amount::from(64));
+    assert_eq!(summary.minted_quotes, vec![quote_id.clone(), quote_id]);
+    assert!(wallet_alice
+        .pending_mint_operations()
+        .await
+        .unwrap()
+        .is_empty());
+    assert_eq!(
+        wallet_alice.total_balance().await.unwrap(),
+        Amount::from(64)
+    );
+}
+
 async fn get_keyset_id(mint: &Mint) -> Id {
     let keys = mint.pubkeys().keysets.first().unwrap().clone();
     keys.verify_id()
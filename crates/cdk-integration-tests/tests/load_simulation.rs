@@ -0,0 +1,643 @@
+//! Mining Share Load Simulation
+//!
+//! This file contains a load harness for mining share issuance. A pure in-memory mint is
+//! served in-process over HTTP and driven with the wallet HTTP client, so the axum handlers
+//! and serde layers are part of every measurement.
+//!
+//! Workloads:
+//! - Mining share quote creation
+//! - Mining share quote lookups
+//! - Batch issuance of mining share quotes
+//! - Websocket subscribers
+//!
+//! Latencies and error counts are recorded in a prometheus registry and printed as a JSON
+//! summary. `test_load_simulation_smoke` runs a short, fixed workload with generous latency
+//! bounds to catch large regressions. `test_load_simulation` is ignored by default and reads
+//! its parameters from `CDK_LOAD_*` environment variables, see [`LoadConfig::from_env`]:
+//!
+//! ```sh
+//! CDK_LOAD_DURATION_SECS=60 CDK_LOAD_QUOTE_RATE=500 CDK_LOAD_ISSUANCE_RATE=50 \
+//!     cargo test -p cdk-integration-tests --release --test load_simulation -- --ignored --nocapture
+//! ```
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::env;
+use std::future::Future;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Context, Result};
+use bip39::Mnemonic;
+use bitcoin::hashes::{sha256, Hash};
+use cdk::amount::SplitTarget;
+use cdk::cdk_database::MintDatabase;
+use cdk::mint::{MintBuilder, MintMeltLimits};
+use cdk::mint_url::MintUrl;
+use cdk::nuts::{
+    CurrencyUnit, MintQuoteMiningShareRequest, MintRequest, PaymentMethod, PreMintSecrets,
+    SecretKey,
+};
+use cdk::types::{FeeReserve, QuoteTTL};
+use cdk::wallet::{HttpClient, MintConnector};
+use cdk::Amount;
+use cdk_fake_wallet::FakeWallet;
+use cdk_sqlite::mint::memory;
+use futures::{SinkExt, StreamExt};
+use prometheus::{HistogramOpts, HistogramVec, IntCounterVec, Opts, Registry};
+use serde::Serialize;
+use serde_json::json;
+use tokio::sync::{RwLock, Semaphore};
+use tokio::task::JoinSet;
+use tokio::time::MissedTickBehavior;
+use tokio_tungstenite::connect_async;
+use tokio_tungstenite::tungstenite::protocol::Message;
+
+/// Load simulation parameters
+#[derive(Debug, Clone, Serialize)]
+struct LoadConfig {
+    /// How long each workload runs
+    duration_secs: u64,
+    /// Mining share quotes created per second
+    quote_rate: u64,
+    /// Quote lookups per second
+    lookup_rate: u64,
+    /// Batch issuances per second
+    issuance_rate: u64,
+    /// Outputs per batch issuance
+    batch_size: u64,
+    /// Number of websocket subscribers
+    subscribers: usize,
+    /// Subscriptions per second per websocket subscriber
+    subscribe_rate: u64,
+    /// Maximum number of concurrent requests per workload
+    max_in_flight: usize,
+    /// Input fee of the mint keyset
+    input_fee_ppk: u64,
+    /// Maximum mining share quote amount accepted by the mint
+    max_amount: u64,
+    /// Fail when the p99 latency of any operation exceeds this bound
+    max_p99_ms: Option<u64>,
+}
+
+impl LoadConfig {
+    /// Short workload that completes within test time bounds
+    fn smoke() -> Self {
+        Self {
+            duration_secs: 3,
+            quote_rate: 20,
+            lookup_rate: 20,
+            issuance_rate: 5,
+            batch_size: 16,
+            subscribers: 2,
+            subscribe_rate: 5,
+            max_in_flight: 32,
+            input_fee_ppk: 0,
+            max_amount: 10_000,
+            // Far above normal latencies, only meant to catch order of magnitude regressions
+            max_p99_ms: Some(1_000),
+        }
+    }
+
+    /// Target production workload, overridable with `CDK_LOAD_*` environment variables
+    ///
+    /// `CDK_LOAD_DURATION_SECS`, `CDK_LOAD_QUOTE_RATE`, `CDK_LOAD_LOOKUP_RATE`,
+    /// `CDK_LOAD_ISSUANCE_RATE`, `CDK_LOAD_BATCH_SIZE`, `CDK_LOAD_SUBSCRIBERS`,
+    /// `CDK_LOAD_SUBSCRIBE_RATE`, `CDK_LOAD_MAX_IN_FLIGHT`, `CDK_LOAD_INPUT_FEE_PPK`,
+    /// `CDK_LOAD_MAX_AMOUNT` and `CDK_LOAD_MAX_P99_MS`.
+    fn from_env() -> Result<Self> {
+        Ok(Self {
+            duration_secs: env_var("CDK_LOAD_DURATION_SECS")?.unwrap_or(30),
+            quote_rate: env_var("CDK_LOAD_QUOTE_RATE")?.unwrap_or(500),
+            lookup_rate: env_var("CDK_LOAD_LOOKUP_RATE")?.unwrap_or(100),
+            issuance_rate: env_var("CDK_LOAD_ISSUANCE_RATE")?.unwrap_or(50),
+            batch_size: env_var("CDK_LOAD_BATCH_SIZE")?.unwrap_or(64),
+            subscribers: env_var("CDK_LOAD_SUBSCRIBERS")?.unwrap_or(10),
+            subscribe_rate: env_var("CDK_LOAD_SUBSCRIBE_RATE")?.unwrap_or(1),
+            max_in_flight: env_var("CDK_LOAD_MAX_IN_FLIGHT")?.unwrap_or(256),
+            input_fee_ppk: env_var("CDK_LOAD_INPUT_FEE_PPK")?.unwrap_or(0),
+            max_amount: env_var("CDK_LOAD_MAX_AMOUNT")?.unwrap_or(10_000),
+            max_p99_ms: env_var("CDK_LOAD_MAX_P99_MS")?,
+        })
+    }
+}
+
+/// Read and parse an optional environment variable
+fn env_var<T>(name: &str) -> Result<Option<T>>
+where
+    T: FromStr,
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    match env::var(name) {
+        Ok(value) => Ok(Some(
+            value
+                .parse()
+                .with_context(|| format!("Invalid value for {name}"))?,
+        )),
+        Err(_) => Ok(None),
+    }
+}
+
+/// Latency and error metrics of the simulated client operations
+struct LoadMetrics {
+    registry: Registry,
+    operations: IntCounterVec,
+    latency: HistogramVec,
+}
+
+impl LoadMetrics {
+    fn new() -> Result<Self> {
+        let registry = Registry::new();
+
+        let operations = IntCounterVec::new(
+            Opts::new("cdk_load_operations_total", "Total number of operations"),
+            &["operation", "status"],
+        )?;
+        registry.register(Box::new(operations.clone()))?;
+
+        // 100µs up to ~20s, fine enough to read percentiles of in-process requests
+        let latency = HistogramVec::new(
+            HistogramOpts::new(
+                "cdk_load_operation_duration_seconds",
+                "Duration of successful operations in seconds",
+            )
+            .buckets(prometheus::exponential_buckets(0.0001, 1.5, 30)?),
+            &["operation"],
+        )?;
+        registry.register(Box::new(latency.clone()))?;
+
+        Ok(Self {
+            registry,
+            operations,
+            latency,
+        })
+    }
+
+    /// Record the outcome of one operation
+    fn record(&self, operation: &str, result: &Result<Duration>) {
+        match result {
+            Ok(elapsed) => {
+                self.operations
+                    .with_label_values(&[operation, "success"])
+                    .inc();
+                self.latency
+                    .with_label_values(&[operation])
+                    .observe(elapsed.as_secs_f64());
+            }
+            Err(err) => {
+                tracing::warn!("{} failed: {}", operation, err);
+                self.operations
+                    .with_label_values(&[operation, "error"])
+                    .inc();
+            }
+        }
+    }
+
+    /// Summarize every recorded operation from the registry
+    fn summary(&self, elapsed: Duration) -> BTreeMap<String, OperationSummary> {
+        let mut summaries: BTreeMap<String, OperationSummary> = BTreeMap::new();
+
+        for family in self.registry.gather() {
+            for metric in family.get_metric() {
+                let label = |name: &str| {
+                    metric
+                        .get_label()
+                        .iter()
+                        .find(|label| label.get_name() == name)
+                        .map(|label| label.get_value().to_string())
+                        .unwrap_or_default()
+                };
+                let summary = summaries.entry(label("operation")).or_default();
+
+                match family.get_name() {
+                    "cdk_load_operations_total" => {
+                        let count = metric.get_counter().get_value() as u64;
+                        match label("status").as_str() {
+                            "success" => summary.count = count,
+                            _ => summary.errors = count,
+                        }
+                    }
+                    "cdk_load_operation_duration_seconds" => {
+                        let histogram = metric.get_histogram();
+                        let buckets: Vec<(f64, u64)> = histogram
+                            .get_bucket()
+                            .iter()
+                            .map(|b| (b.get_upper_bound(), b.get_cumulative_count()))
+                            .collect();
+                        let count = histogram.get_sample_count();
+
+                        summary.p50_ms = quantile_ms(0.5, &buckets, count);
+                        summary.p90_ms = quantile_ms(0.9, &buckets, count);
+                        summary.p99_ms = quantile_ms(0.99, &buckets, count);
+                        summary.throughput_per_sec = count as f64 / elapsed.as_secs_f64();
+                    }
+                    _ => (),
+                }
+            }
+        }
+
+        summaries
+    }
+}
+
+/// Summary of one operation type
+#[derive(Debug, Default, Serialize)]
+struct OperationSummary {
+    count: u64,
+    errors: u64,
+    throughput_per_sec: f64,
+    p50_ms: f64,
+    p90_ms: f64,
+    p99_ms: f64,
+}
+
+/// Estimate a quantile in milliseconds from cumulative histogram buckets
+///
+/// Interpolates linearly within the bucket holding the quantile, like prometheus'
+/// `histogram_quantile`.
+fn quantile_ms(quantile: f64, buckets: &[(f64, u64)], count: u64) -> f64 {
+    if count == 0 {
+        return 0.0;
+    }
+
+    let rank = quantile * count as f64;
+    let mut lower = (0.0, 0);
+
+    for &(upper_bound, cumulative) in buckets {
+        if cumulative as f64 >= rank {
+            let (lower_bound, lower_count) = lower;
+            let in_bucket = (cumulative - lower_count) as f64;
+            let fraction = (rank - lower_count as f64) / in_bucket;
+            return (lower_bound + (upper_bound - lower_bound) * fraction) * 1000.0;
+        }
+        lower = (upper_bound, cumulative);
+    }
+
+    // Beyond the largest bucket
+    lower.0 * 1000.0
+}
+
+/// Time a single request
+async fn timed<T, F>(request: F) -> Result<(T, Duration)>
+where
+    F: Future<Output = Result<T, cdk::Error>>,
+{
+    let start = Instant::now();
+    let response = request.await?;
+    Ok((response, start.elapsed()))
+}
+
+/// Run `operation` `rate` times per second until `deadline`
+///
+/// Operations are started on schedule regardless of how long earlier ones take, up to
+/// `max_in_flight` concurrent operations.
+async fn drive<F, Fut>(
+    metrics: Arc<LoadMetrics>,
+    name: &'static str,
+    rate: u64,
+    deadline: Instant,
+    max_in_flight: usize,
+    operation: F,
+) where
+    F: Fn() -> Fut,
+    Fut: Future<Output = Result<Duration>> + Send + 'static,
+{
+    if rate == 0 {
+        return;
+    }
+
+    let in_flight = Arc::new(Semaphore::new(max_in_flight));
+    let mut ticker = tokio::time::interval(Duration::from_secs_f64(1.0 / rate as f64));
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+    let mut tasks = JoinSet::new();
+
+    while Instant::now() < deadline {
+        ticker.tick().await;
+
+        let permit = Arc::clone(&in_flight)
+            .acquire_owned()
+            .await
+            .expect("semaphore is never closed");
+        let operation = operation();
+        let metrics = Arc::clone(&metrics);
+
+        tasks.spawn(async move {
+            let result = operation.await;
+            metrics.record(name, &result);
+            drop(permit);
+        });
+    }
+
+    while tasks.join_next().await.is_some() {}
+}
+
+/// Quote request with a random header hash
+fn quote_request(amount: Amount, secret_key: &SecretKey) -> MintQuoteMiningShareRequest {
+    MintQuoteMiningShareRequest {
+        amount,
+        unit: CurrencyUnit::Sat,
+        header_hash: sha256::Hash::hash(&SecretKey::generate().to_secret_bytes()),
+        description: None,
+        pubkey: secret_key.public_key(),
+        difficulty: None,
+    }
+}
+
+/// Create a quote and issue it in one batch, timing only the issuance
+async fn issue_batch(client: Arc<HttpClient>, batch_size: u64) -> Result<Duration> {
+    let secret_key = SecretKey::generate();
+    let amount = Amount::from(batch_size);
+
+    let quote = client
+        .post_mint_quote_mining_share(quote_request(amount, &secret_key))
+        .await?;
+
+    // One output per unit of amount
+    let premint_secrets =
+        PreMintSecrets::random(quote.keyset_id, amount, &SplitTarget::Value(Amount::ONE))?;
+
+    let mut request = MintRequest {
+        quote: quote.quote,
+        outputs: premint_secrets.blinded_messages(),
+        signature: None,
+    };
+    request.sign(secret_key)?;
+
+    let (response, elapsed) = timed(client.post_mint_mining_share(request)).await?;
+
+    if response.signatures.len() != premint_secrets.blinded_messages().len() {
+        bail!(
+            "Expected {} signatures, got {}",
+            premint_secrets.blinded_messages().len(),
+            response.signatures.len()
+        );
+    }
+
+    Ok(elapsed)
+}
+
+/// Subscribe to proof states over one websocket connection `rate` times per second
+///
+/// Each subscription is timed until the mint acknowledges it.
+async fn subscriber(
+    metrics: Arc<LoadMetrics>,
+    mint_url: MintUrl,
+    subscriber_id: usize,
+    rate: u64,
+    deadline: Instant,
+) -> Result<()> {
+    if rate == 0 {
+        return Ok(());
+    }
+
+    let ws_url = format!("{}/v1/ws", mint_url.to_string().replacen("http", "ws", 1));
+    let (ws_stream, _) = connect_async(ws_url).await?;
+    let (mut write, mut reader) = ws_stream.split();
+
+    let mut ticker = tokio::time::interval(Duration::from_secs_f64(1.0 / rate as f64));
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+    let mut request_id = 0u64;
+
+    while Instant::now() < deadline {
+        ticker.tick().await;
+        request_id += 1;
+
+        let start = Instant::now();
+        let result: Result<Duration> = async {
+            write
+                .send(Message::Text(
+                    serde_json::to_string(&json!({
+                        "jsonrpc": "2.0",
+                        "id": request_id,
+                        "method": "subscribe",
+                        "params": {
+                            "kind": "proof_state",
+                            "filters": [SecretKey::generate().public_key().to_hex()],
+                            "subId": format!("load-{subscriber_id}-{request_id}"),
+                        }
+                    }))?
+                    .into(),
+                ))
+                .await?;
+
+            // Skip notifications until the response to this request arrives
+            loop {
+                let message = reader.next().await.context("websocket closed")??;
+                if !message.is_text() {
+                    continue;
+                }
+
+                let response: serde_json::Value = serde_json::from_str(message.to_text()?)?;
+
+                if response.get("id").and_then(|id| id.as_u64()) != Some(request_id) {
+                    continue;
+                }
+
+                if let Some(error) = response.get("error") {
+                    bail!("Subscription rejected: {}", error);
+                }
+
+                return Ok(start.elapsed());
+            }
+        }
+        .await;
+
+        metrics.record("subscribe", &result);
+    }
+
+    Ok(())
+}
+
+/// Start a mint accepting mining shares and serve it over HTTP
+async fn start_mint(config: &LoadConfig) -> Result<MintUrl> {
+    let fee_reserve = FeeReserve {
+        min_fee_reserve: 1.into(),
+        percent_fee_reserve: 1.0,
+    };
+
+    let fake_wallet = FakeWallet::new(
+        fee_reserve,
+        HashMap::default(),
+        HashSet::default(),
+        0,
+        CurrencyUnit::Sat,
+    );
+
+    let localstore = Arc::new(memory::empty().await?);
+    let mut mint_builder = MintBuilder::new(localstore.clone());
+
+    mint_builder
+        .add_payment_processor(
+            CurrencyUnit::Sat,
+            PaymentMethod::MiningShare,
+            MintMeltLimits::new(1, config.max_amount),
+            Arc::new(fake_wallet),
+        )
+        .await?;
+    mint_builder.set_unit_fee(&CurrencyUnit::Sat, config.input_fee_ppk)?;
+
+    mint_builder = mint_builder
+        .with_name("load simulation mint".to_string())
+        .with_description("load simulation mint".to_string());
+
+    let mnemonic = Mnemonic::generate(12)?;
+    let mint = mint_builder
+        .build_with_seed(localstore.clone(), &mnemonic.to_seed_normalized(""))
+        .await?;
+
+    let mut tx = localstore.begin_transaction().await?;
+    tx.set_quote_ttl(QuoteTTL::new(10000, 10000)).await?;
+    tx.commit().await?;
+
+    mint.start().await?;
+
+    let router = cdk_axum::create_mint_router(Arc::new(mint), false).await?;
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+    let addr = listener.local_addr()?;
+
+    tokio::spawn(async move {
+        axum::serve(listener, router).await.expect("server running");
+    });
+
+    Ok(MintUrl::from_str(&format!("http://{addr}"))?)
+}
+
+/// Run every workload concurrently and summarize the results
+async fn run_load_simulation(config: &LoadConfig) -> Result<BTreeMap<String, OperationSummary>> {
+    if config.batch_size == 0 || config.batch_size > config.max_amount {
+        bail!("Batch size must be between 1 and {}", config.max_amount);
+    }
+
+    let mint_url = start_mint(config).await?;
+    let client = Arc::new(HttpClient::new(mint_url.clone(), None));
+    let metrics = Arc::new(LoadMetrics::new()?);
+
+    // Seed one quote so lookups have something to find from the start
+    let seed_quote = client
+        .post_mint_quote_mining_share(quote_request(Amount::ONE, &SecretKey::generate()))
+        .await?;
+    let quote_ids = Arc::new(RwLock::new(vec![seed_quote.quote]));
+    let lookups = Arc::new(AtomicUsize::new(0));
+
+    let start = Instant::now();
+    let deadline = start + Duration::from_secs(config.duration_secs);
+
+    let quotes = drive(
+        Arc::clone(&metrics),
+        "quote",
+        config.quote_rate,
+        deadline,
+        config.max_in_flight,
+        || {
+            let client = Arc::clone(&client);
+            let quote_ids = Arc::clone(&quote_ids);
+            async move {
+                let request = quote_request(Amount::ONE, &SecretKey::generate());
+                let (quote, elapsed) = timed(client.post_mint_quote_mining_share(request)).await?;
+                quote_ids.write().await.push(quote.quote);
+                Ok(elapsed)
+            }
+        },
+    );
+
+    let lookup = drive(
+        Arc::clone(&metrics),
+        "lookup",
+        config.lookup_rate,
+        deadline,
+        config.max_in_flight,
+        || {
+            let client = Arc::clone(&client);
+            let quote_ids = Arc::clone(&quote_ids);
+            let lookups = Arc::clone(&lookups);
+            async move {
+                let quote_id = {
+                    let quote_ids = quote_ids.read().await;
+                    quote_ids[lookups.fetch_add(1, Ordering::Relaxed) % quote_ids.len()].clone()
+                };
+                let (_, elapsed) =
+                    timed(client.get_mint_quote_status_mining_share(&quote_id)).await?;
+                Ok(elapsed)
+            }
+        },
+    );
+
+    let issuance = drive(
+        Arc::clone(&metrics),
+        "issuance",
+        config.issuance_rate,
+        deadline,
+        config.max_in_flight,
+        || issue_batch(Arc::clone(&client), config.batch_size),
+    );
+
+    let subscribers = futures::future::join_all((0..config.subscribers).map(|subscriber_id| {
+        subscriber(
+            Arc::clone(&metrics),
+            mint_url.clone(),
+            subscriber_id,
+            config.subscribe_rate,
+            deadline,
+        )
+    }));
+
+    let (_, _, _, subscribers) = tokio::join!(quotes, lookup, issuance, subscribers);
+    for subscriber in subscribers {
+        subscriber?;
+    }
+
+    Ok(metrics.summary(start.elapsed()))
+}
+
+/// Print the summary and check it against the configured bounds
+fn report(config: &LoadConfig, summary: &BTreeMap<String, OperationSummary>) {
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&json!({
+            "config": config,
+            "operations": summary,
+        }))
+        .expect("summary serializes")
+    );
+
+    if let Some(max_p99_ms) = config.max_p99_ms {
+        for (operation, summary) in summary {
+            assert!(
+                summary.p99_ms <= max_p99_ms as f64,
+                "{operation} p99 latency {:.2}ms exceeds {max_p99_ms}ms",
+                summary.p99_ms
+            );
+        }
+    }
+}
+
+/// Tests that a short load run completes without errors and within generous latency bounds
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_load_simulation_smoke() {
+    let config = LoadConfig::smoke();
+    let summary = run_load_simulation(&config).await.unwrap();
+
+    report(&config, &summary);
+
+    for operation in ["quote", "lookup", "issuance", "subscribe"] {
+        let operation_summary = summary
+            .get(operation)
+            .unwrap_or_else(|| panic!("{operation} was not exercised"));
+        assert!(operation_summary.count > 0, "{operation} never succeeded");
+        assert_eq!(operation_summary.errors, 0, "{operation} had errors");
+    }
+}
+
+/// Full load simulation, configured through `CDK_LOAD_*` environment variables
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+#[ignore = "long running load simulation, run explicitly with --ignored"]
+async fn test_load_simulation() {
+    let config = LoadConfig::from_env().unwrap();
+    let summary = run_load_simulation(&config).await.unwrap();
+
+    report(&config, &summary);
+}
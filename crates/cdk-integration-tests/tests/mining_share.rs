@@ -0,0 +1,607 @@
+//! Mining Share Encoding Tests
+//!
+//! This file contains tests for the compact CBOR encoding of the mining share mint endpoint
+//! and for the mining share flow from quote to miner payout. The mint is served in-process
+//! over HTTP so the tests exercise the real axum handlers and the wallet HTTP client.
+//!
+//! Test Categories:
+//! - Encoded size of large mining share mint requests
+//! - NUT-19 cache hits across encodings
+//! - Fallback to JSON against mints without CBOR support
+//! - CBOR support read from mint info the client has not fetched yet
+//! - Quote, issuance and P2PK-locked payout to a miner wallet
+//! - Outputs per mining share quote bounded by the quote amount
+//! - Mining share amounts audited against the share difficulty
+
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+use std::sync::Arc;
+
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::IntoResponse;
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use bip39::Mnemonic;
+use bitcoin::hashes::{sha256, Hash};
+use cdk::amount::SplitTarget;
+use cdk::cdk_database::MintDatabase;
+use cdk::mint::{Mint, MintBuilder, MintMeltLimits};
+use cdk::mint_url::MintUrl;
+use cdk::nuts::nutXX::{QuoteState, Settings, CBOR_CONTENT_TYPE, DEFAULT_OUTPUT_SLACK};
+use cdk::nuts::{
+    BlindedMessage, CurrencyUnit, Id, MintInfo, MintQuoteMiningShareRequest, MintRequest,
+    MintResponse, PaymentMethod, PreMintSecrets, ProofsMethods, SecretKey, SpendingConditions,
+};
+use cdk::types::{FeeReserve, QuoteTTL};
+use cdk::wallet::types::TransactionDirection;
+use cdk::wallet::{HttpClient, MintConnector, ReceiveOptions, SendOptions, Wallet};
+use cdk::Amount;
+use cdk_fake_wallet::FakeWallet;
+use cdk_sqlite::mint::memory;
+use reqwest::header::{ACCEPT, CONTENT_TYPE};
+
+/// Serve the router on a random local port
+async fn serve(router: Router) -> MintUrl {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("bind listener");
+    let addr = listener.local_addr().expect("local addr");
+
+    tokio::spawn(async move {
+        axum::serve(listener, router).await.expect("server running");
+    });
+
+    MintUrl::from_str(&format!("http://{addr}")).expect("valid mint url")
+}
+
+/// Start a mint accepting mining shares and serve it over HTTP
+async fn start_mining_share_mint(cbor: bool) -> (Mint, MintUrl) {
+    start_mining_share_mint_with_slack(cbor, None).await
+}
+
+/// Start a mint accepting mining shares with the given output slack
+async fn start_mining_share_mint_with_slack(
+    cbor: bool,
+    output_slack: Option<u32>,
+) -> (Mint, MintUrl) {
+    start_configured_mining_share_mint(cbor, |mint_builder| match output_slack {
+        Some(slack) => mint_builder.with_mining_share_output_slack(slack),
+        None => mint_builder,
+    })
+    .await
+}
+
+/// Start a mint accepting mining shares, with further builder settings
+async fn start_configured_mining_share_mint(
+    cbor: bool,
+    configure: impl FnOnce(MintBuilder) -> MintBuilder,
+) -> (Mint, MintUrl) {
+    let fee_reserve = FeeReserve {
+        min_fee_reserve: 1.into(),
+        percent_fee_reserve: 1.0,
+    };
+
+    let fake_wallet = FakeWallet::new(
+        fee_reserve,
+        HashMap::default(),
+        HashSet::default(),
+        0,
+        CurrencyUnit::Sat,
+    );
+
+    let localstore = Arc::new(memory::empty().await.expect("valid db instance"));
+    let mut mint_builder = MintBuilder::new(localstore.clone());
+
+    mint_builder
+        .add_payment_processor(
+            CurrencyUnit::Sat,
+            PaymentMethod::MiningShare,
+            MintMeltLimits::new(1, 10_000),
+            Arc::new(fake_wallet),
+        )
+        .await
+        .unwrap();
+
+    mint_builder = mint_builder
+        .with_name("mining share mint".to_string())
+        .with_description("mining share mint".to_string())
+        .with_mining_share_cbor(cbor);
+    mint_builder = configure(mint_builder);
+
+    let mnemonic = Mnemonic::generate(12).unwrap();
+    let mint = mint_builder
+        .build_with_seed(localstore.clone(), &mnemonic.to_seed_normalized(""))
+        .await
+        .unwrap();
+
+    let mut tx = localstore.begin_transaction().await.unwrap();
+    tx.set_quote_ttl(QuoteTTL::new(10000, 10000)).await.unwrap();
+    tx.commit().await.unwrap();
+
+    mint.start().await.unwrap();
+
+    let router = cdk_axum::create_mint_router(Arc::new(mint.clone()), false)
+        .await
+        .unwrap();
+
+    (mint, serve(router).await)
+}
+
+/// Create a paid mining share quote and a signed mint request for it
+async fn mining_share_mint_request(mint: &Mint, amount: Amount) -> MintRequest<String> {
+    mining_share_mint_request_with_outputs(mint, amount, |keyset_id| {
+        PreMintSecrets::random(keyset_id, amount, &SplitTarget::default())
+            .unwrap()
+            .blinded_messages()
+    })
+    .await
+}
+
+/// Create a paid mining share quote and a signed mint request with the given outputs
+async fn mining_share_mint_request_with_outputs(
+    mint: &Mint,
+    amount: Amount,
+    outputs: impl FnOnce(Id) -> Vec<BlindedMessage>,
+) -> MintRequest<String> {
+    let secret_key = SecretKey::generate();
+
+    let quote = mint
+        .create_mint_mining_share_quote(MintQuoteMiningShareRequest {
+            amount,
+            unit: CurrencyUnit::Sat,
+            header_hash: sha256::Hash::hash(&SecretKey::generate().to_secret_bytes()),
+            description: None,
+            pubkey: secret_key.public_key(),
+            difficulty: None,
+        })
+        .await
+        .unwrap();
+
+    let keyset_id = quote.keyset_id.expect("mining share quote has keyset");
+
+    let mut request = MintRequest {
+        quote: quote.id.to_string(),
+        outputs: outputs(keyset_id),
+        signature: None,
+    };
+    request.sign(secret_key).unwrap();
+
+    request
+}
+
+/// Tests that a large mining share mint request is meaningfully smaller in CBOR
+#[test]
+fn test_mining_share_cbor_smaller_than_json() {
+    let keyset_id = Id::from_str("00456a94ab4e1c46").unwrap();
+
+    let outputs: Vec<BlindedMessage> = (0..300)
+        .map(|_| {
+            BlindedMessage::new(
+                Amount::from(1),
+                keyset_id,
+                SecretKey::generate().public_key(),
+            )
+        })
+        .collect();
+
+    let request = MintRequest {
+        quote: "8f0f3a8e-1b6c-4f07-9c11-9e1fcb2e8a51".to_string(),
+        outputs,
+        signature: None,
+    };
+
+    let json = serde_json::to_vec(&request).unwrap();
+    let mut cbor = Vec::new();
+    ciborium::into_writer(&request, &mut cbor).unwrap();
+
+    // At least a 30% reduction
+    assert!(
+        cbor.len() * 10 < json.len() * 7,
+        "cbor {} bytes, json {} bytes",
+        cbor.len(),
+        json.len()
+    );
+
+    let decoded: MintRequest<String> = ciborium::from_reader(&cbor[..]).unwrap();
+    assert_eq!(decoded, request);
+}
+
+/// Tests that a CBOR replay of a JSON mining share mint request returns the cached signatures
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_mining_share_cbor_replay_hits_cache() {
+    let (mint, mint_url) = start_mining_share_mint(true).await;
+    let request = mining_share_mint_request(&mint, Amount::from(64)).await;
+
+    let json_client = HttpClient::new(mint_url.clone(), None);
+    let json_response = json_client
+        .post_mint_mining_share(request.clone())
+        .await
+        .unwrap();
+    assert!(!json_response.signatures.is_empty());
+
+    // The quote is now issued, so only a cache hit can return signatures
+    let mut body = Vec::new();
+    ciborium::into_writer(&request, &mut body).unwrap();
+
+    let response = reqwest::Client::new()
+        .post(
+            mint_url
+                .join_paths(&["v1", "mint", "mining_share"])
+                .unwrap(),
+        )
+        .header(CONTENT_TYPE, CBOR_CONTENT_TYPE)
+        .header(ACCEPT, CBOR_CONTENT_TYPE)
+        .body(body)
+        .send()
+        .await
+        .unwrap();
+
+    assert!(response.status().is_success());
+    assert_eq!(
+        response.headers().get(CONTENT_TYPE).unwrap(),
+        CBOR_CONTENT_TYPE
+    );
+
+    let bytes = response.bytes().await.unwrap();
+    let cbor_response: MintResponse = ciborium::from_reader(bytes.as_ref()).unwrap();
+    assert_eq!(cbor_response, json_response);
+
+    // The wallet client uses CBOR once the mint advertises it
+    let cbor_client = HttpClient::new(mint_url, None).prefer_cbor(true);
+    let info = cbor_client.get_mint_info().await.unwrap();
+    assert!(info.nuts.nutxx.is_some_and(|settings| settings.cbor));
+
+    let client_response = cbor_client.post_mint_mining_share(request).await.unwrap();
+    assert_eq!(client_response, json_response);
+}
+
+/// Tests that a client preferring CBOR sends JSON to a mint that does not advertise it
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_mining_share_cbor_falls_back_to_json() {
+    // Minimal mint that only understands JSON bodies
+    let router = Router::new()
+        .route("/v1/info", get(|| async { Json(MintInfo::default()) }))
+        .route(
+            "/v1/mint/mining_share",
+            post(|Json(_request): Json<MintRequest<String>>| async {
+                Json(MintResponse { signatures: vec![] })
+            }),
+        );
+    let mint_url = serve(router).await;
+
+    let client = HttpClient::new(mint_url, None).prefer_cbor(true);
+    let info = client.get_mint_info().await.unwrap();
+    assert!(info.nuts.nutxx.is_none());
+
+    let request = MintRequest {
+        quote: "8f0f3a8e-1b6c-4f07-9c11-9e1fcb2e8a51".to_string(),
+        outputs: vec![],
+        signature: None,
+    };
+
+    let response = client.post_mint_mining_share(request).await.unwrap();
+    assert!(response.signatures.is_empty());
+}
+
+/// Tests that a client preferring CBOR uses it before it fetched the mint info itself
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_mining_share_cbor_without_fetched_info() {
+    // Minimal mint that only understands CBOR bodies
+    let router = Router::new()
+        .route(
+            "/v1/info",
+            get(|| async {
+                let mut info = MintInfo::default();
+                info.nuts = info.nuts.nutxx(Settings { cbor: true });
+                Json(info)
+            }),
+        )
+        .route(
+            "/v1/mint/mining_share",
+            post(|headers: HeaderMap| async move {
+                let is_cbor = headers
+                    .get(CONTENT_TYPE)
+                    .is_some_and(|value| value == CBOR_CONTENT_TYPE);
+                if !is_cbor {
+                    return StatusCode::UNSUPPORTED_MEDIA_TYPE.into_response();
+                }
+
+                let mut body = Vec::new();
+                ciborium::into_writer(&MintResponse { signatures: vec![] }, &mut body).unwrap();
+                ([(CONTENT_TYPE, CBOR_CONTENT_TYPE)], body).into_response()
+            }),
+        );
+    let mint_url = serve(router).await;
+
+    let client = HttpClient::new(mint_url, None).prefer_cbor(true);
+
+    let request = MintRequest {
+        quote: "8f0f3a8e-1b6c-4f07-9c11-9e1fcb2e8a51".to_string(),
+        outputs: vec![],
+        signature: None,
+    };
+
+    let response = client.post_mint_mining_share(request).await.unwrap();
+    assert!(response.signatures.is_empty());
+}
+
+/// Tests the mining share flow using only public APIs:
+/// 1. The pool creates a paid mining share quote locked to its key
+/// 2. The pool wallet looks up the quote and mints it
+/// 3. The pool pays the miner with a token locked to the miner's key
+/// 4. The miner wallet receives the token with its key
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_mining_share_quote_to_miner_payout() {
+    let (mint, mint_url) = start_mining_share_mint(false).await;
+
+    let pool_key = SecretKey::generate();
+    let miner_key = SecretKey::generate();
+    let amount = Amount::from(64);
+
+    let quote = mint
+        .create_mint_mining_share_quote(MintQuoteMiningShareRequest {
+            amount,
+            unit: CurrencyUnit::Sat,
+            header_hash: sha256::Hash::hash(&SecretKey::generate().to_secret_bytes()),
+            description: None,
+            pubkey: pool_key.public_key(),
+            difficulty: None,
+        })
+        .await
+        .unwrap();
+    let quote_id = quote.id.to_string();
+
+    let wallet_pool = Wallet::new(
+        &mint_url.to_string(),
+        CurrencyUnit::Sat,
+        Arc::new(cdk_sqlite::wallet::memory::empty().await.unwrap()),
+        Mnemonic::generate(12).unwrap().to_seed_normalized(""),
+        None,
+    )
+    .unwrap();
+    let wallet_miner = Wallet::new(
+        &mint_url.to_string(),
+        CurrencyUnit::Sat,
+        Arc::new(cdk_sqlite::wallet::memory::empty().await.unwrap()),
+        Mnemonic::generate(12).unwrap().to_seed_normalized(""),
+        None,
+    )
+    .unwrap();
+
+    let quote_state = wallet_pool
+        .mint_quote_state_mining_share(&quote_id)
+        .await
+        .unwrap()
+        .response;
+    assert_eq!(quote_state.state, QuoteState::Paid);
+    assert_eq!(quote_state.amount, Some(amount));
+    assert_eq!(quote_state.pubkey, pool_key.public_key());
+
+    let proofs = wallet_pool
+        .mint_mining_share(&quote_id, amount, quote_state.keyset_id, pool_key, None)
+        .await
+        .unwrap();
+    assert_eq!(proofs.total_amount().unwrap(), amount);
+
+    let prepared_send = wallet_pool
+        .prepare_send(
+            amount,
+            SendOptions {
+                conditions: Some(SpendingConditions::new_p2pk(miner_key.public_key(), None)),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+    let token = prepared_send.confirm(None).await.unwrap();
+
+    let received = wallet_miner
+        .receive(
+            &token.to_string(),
+            ReceiveOptions {
+                p2pk_signing_keys: vec![miner_key],
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+    assert_eq!(received, amount);
+
+    assert_eq!(wallet_pool.total_balance().await.unwrap(), Amount::ZERO);
+    assert_eq!(wallet_miner.total_balance().await.unwrap(), amount);
+
+    // The issuance is attributed to the quote
+    let incoming = wallet_pool
+        .list_transactions(Some(TransactionDirection::Incoming))
+        .await
+        .unwrap();
+    assert_eq!(incoming.len(), 1);
+    assert_eq!(incoming[0].amount, amount);
+    assert_eq!(incoming[0].quote_id.as_deref(), Some(quote_id.as_str()));
+
+    let outgoing = wallet_pool
+        .list_transactions(Some(TransactionDirection::Outgoing))
+        .await
+        .unwrap();
+    assert_eq!(outgoing.len(), 1);
+    assert_eq!(outgoing[0].amount, amount);
+
+    let quote_state = wallet_pool
+        .mint_quote_state_mining_share(&quote_id)
+        .await
+        .unwrap()
+        .response;
+    assert_eq!(quote_state.state, QuoteState::Issued);
+    assert_eq!(quote_state.amount_issued, amount);
+}
+
+/// Outputs of amount one in the given keyset
+fn unit_outputs(keyset_id: Id, count: usize) -> Vec<BlindedMessage> {
+    (0..count)
+        .map(|_| {
+            BlindedMessage::new(
+                Amount::from(1),
+                keyset_id,
+                SecretKey::generate().public_key(),
+            )
+        })
+        .collect()
+}
+
+/// Tests that the mint bounds the outputs of a mining share mint request by the quote amount
+///
+/// Amount 3 splits into two outputs, the default slack allows two more.
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_mining_share_outputs_bounded_by_amount() {
+    let (mint, _mint_url) = start_mining_share_mint(false).await;
+    let amount = Amount::from(3);
+
+    // Dust outputs are rejected before signing
+    let request = mining_share_mint_request_with_outputs(&mint, amount, |keyset_id| {
+        unit_outputs(keyset_id, 50)
+    })
+    .await;
+    match mint.process_mint_request(request.try_into().unwrap()).await {
+        Err(cdk::Error::MiningShareTooManyOutputs { max, outputs }) => {
+            assert_eq!(max, 2 + DEFAULT_OUTPUT_SLACK as usize);
+            assert_eq!(outputs, 50);
+        }
+        other => panic!("Too many outputs should be rejected: {other:?}"),
+    }
+
+    // The minimal split is accepted
+    let request = mining_share_mint_request(&mint, amount).await;
+    assert_eq!(request.outputs.len(), 2);
+    let response = mint
+        .process_mint_request(request.try_into().unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.signatures.len(), 2);
+
+    // Outputs within the slack are accepted
+    let request = mining_share_mint_request_with_outputs(&mint, amount, |keyset_id| {
+        unit_outputs(keyset_id, 3)
+    })
+    .await;
+    let response = mint
+        .process_mint_request(request.try_into().unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.signatures.len(), 3);
+}
+
+/// Tests that the configured output slack is enforced and advertised to wallets
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_mining_share_output_slack_configured() {
+    let (mint, mint_url) = start_mining_share_mint_with_slack(false, Some(0)).await;
+    let amount = Amount::from(3);
+
+    let info = mint.mint_info().await.unwrap();
+    assert_eq!(
+        info.nuts.nutxx.and_then(|settings| settings.output_slack),
+        Some(0)
+    );
+
+    // Without slack a split of three ones is one output too many
+    let request = mining_share_mint_request_with_outputs(&mint, amount, |keyset_id| {
+        unit_outputs(keyset_id, 3)
+    })
+    .await;
+    match mint.process_mint_request(request.try_into().unwrap()).await {
+        Err(cdk::Error::MiningShareTooManyOutputs { max, outputs }) => {
+            assert_eq!(max, 2);
+            assert_eq!(outputs, 3);
+        }
+        other => panic!("Outputs beyond the slack should be rejected: {other:?}"),
+    }
+
+    let request = mining_share_mint_request(&mint, amount).await;
+    assert!(mint
+        .process_mint_request(request.try_into().unwrap())
+        .await
+        .is_ok());
+
+    // The wallet reads the limit from the mint info
+    let wallet = Wallet::new(
+        &mint_url.to_string(),
+        CurrencyUnit::Sat,
+        Arc::new(cdk_sqlite::wallet::memory::empty().await.unwrap()),
+        Mnemonic::generate(12).unwrap().to_seed_normalized(""),
+        None,
+    )
+    .unwrap();
+    let keyset_id = wallet.fetch_active_keyset().await.unwrap().id;
+    assert_eq!(
+        wallet
+            .max_mining_share_outputs(amount, keyset_id)
+            .await
+            .unwrap(),
+        2
+    );
+}
+
+/// Amount the share amount policy of the tests allows for a difficulty
+fn share_amount(difficulty: u64) -> Amount {
+    Amount::from(1u64 << difficulty.min(63))
+}
+
+/// Tests that a share amount policy audits mining share quote amounts:
+/// 1. A quote whose amount matches its difficulty is created
+/// 2. The quote state lookup returns the difficulty
+/// 3. A quote whose amount disagrees with its difficulty is refused
+/// 4. A quote without difficulty is refused
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_mining_share_amount_policy() {
+    let (mint, mint_url) = start_configured_mining_share_mint(false, |mint_builder| {
+        mint_builder.with_share_amount_policy(share_amount)
+    })
+    .await;
+
+    let pool_key = SecretKey::generate();
+    let request = |amount: u64, difficulty: Option<u64>| MintQuoteMiningShareRequest {
+        amount: Amount::from(amount),
+        unit: CurrencyUnit::Sat,
+        header_hash: sha256::Hash::hash(&SecretKey::generate().to_secret_bytes()),
+        description: None,
+        pubkey: pool_key.public_key(),
+        difficulty,
+    };
+
+    let quote = mint
+        .create_mint_mining_share_quote(request(64, Some(6)))
+        .await
+        .unwrap();
+    assert_eq!(quote.difficulty, Some(6));
+
+    let wallet = Wallet::new(
+        &mint_url.to_string(),
+        CurrencyUnit::Sat,
+        Arc::new(cdk_sqlite::wallet::memory::empty().await.unwrap()),
+        Mnemonic::generate(12).unwrap().to_seed_normalized(""),
+        None,
+    )
+    .unwrap();
+    let quote_state = wallet
+        .mint_quote_state_mining_share(&quote.id.to_string())
+        .await
+        .unwrap()
+        .response;
+    assert_eq!(quote_state.amount, Some(Amount::from(64)));
+    assert_eq!(quote_state.difficulty, Some(6));
+
+    match mint
+        .create_mint_mining_share_quote(request(32, Some(6)))
+        .await
+    {
+        Err(cdk::Error::MiningShareAmountMismatch { amount, allowed }) => {
+            assert_eq!(amount, Amount::from(32));
+            assert_eq!(allowed, Amount::from(64));
+        }
+        other => panic!("Expected an amount mismatch, got {other:?}"),
+    }
+
+    assert!(matches!(
+        mint.create_mint_mining_share_quote(request(64, None)).await,
+        Err(cdk::Error::MiningShareDifficultyMissing)
+    ));
+}
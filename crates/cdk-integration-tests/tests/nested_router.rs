@@ -0,0 +1,316 @@
+//! Nested Router Tests
+//!
+//! This file contains tests for serving the mint routes from a host application.
+//! The routes from `cdk_axum::all_routes` are nested under a prefix next to the
+//! host routes and behind a host middleware, and served in-process over HTTP.
+//!
+//! Test Categories:
+//! - Quote creation and mining share quote lookup through the nested routes
+//! - Original paths of the standalone router
+//! - Rate limiting and batch size validation of the mining share endpoints
+
+use std::collections::{HashMap, HashSet};
+use std::net::SocketAddr;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::extract::Request;
+use axum::http::HeaderValue;
+use axum::middleware::{from_fn, Next};
+use axum::response::Response;
+use axum::routing::get;
+use axum::Router;
+use bip39::Mnemonic;
+use bitcoin::hashes::{sha256, Hash};
+use cdk::cdk_database::MintDatabase;
+use cdk::error::{ErrorCode, ErrorResponse};
+use cdk::mint::{Mint, MintBuilder, MintMeltLimits};
+use cdk::mint_url::MintUrl;
+use cdk::nuts::nutXX::{BatchQuoteStatusRequest, QuoteState, MAX_BATCH_QUOTE_STATUS};
+use cdk::nuts::{
+    CurrencyUnit, MintQuoteBolt11Request, MintQuoteBolt11Response, MintQuoteMiningShareRequest,
+    MintQuoteMiningShareResponse, PaymentMethod, SecretKey,
+};
+use cdk::types::{FeeReserve, QuoteTTL};
+use cdk::Amount;
+use cdk_axum::{all_routes, MintState, RateLimit, RoutesConfig};
+use cdk_fake_wallet::FakeWallet;
+use cdk_sqlite::mint::memory;
+
+const HOST_HEADER: &str = "x-host-app";
+
+/// Serve the router on a random local port
+async fn serve(router: Router) -> MintUrl {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("bind listener");
+    let addr = listener.local_addr().expect("local addr");
+
+    tokio::spawn(async move {
+        axum::serve(listener, router).await.expect("server running");
+    });
+
+    MintUrl::from_str(&format!("http://{addr}")).expect("valid mint url")
+}
+
+/// Host middleware marking every response it handled
+async fn host_middleware(req: Request, next: Next) -> Response {
+    let mut response = next.run(req).await;
+    response
+        .headers_mut()
+        .insert(HOST_HEADER, HeaderValue::from_static("pool"));
+    response
+}
+
+/// Create a mint accepting bolt11 payments and mining shares
+async fn create_mint() -> Mint {
+    let localstore = Arc::new(memory::empty().await.expect("valid db instance"));
+    let mut mint_builder = MintBuilder::new(localstore.clone());
+
+    for method in [PaymentMethod::Bolt11, PaymentMethod::MiningShare] {
+        let fake_wallet = FakeWallet::new(
+            FeeReserve {
+                min_fee_reserve: 1.into(),
+                percent_fee_reserve: 1.0,
+            },
+            HashMap::default(),
+            HashSet::default(),
+            0,
+            CurrencyUnit::Sat,
+        );
+
+        mint_builder
+            .add_payment_processor(
+                CurrencyUnit::Sat,
+                method,
+                MintMeltLimits::new(1, 10_000),
+                Arc::new(fake_wallet),
+            )
+            .await
+            .unwrap();
+    }
+
+    let mnemonic = Mnemonic::generate(12).unwrap();
+    let mint = mint_builder
+        .with_name("nested mint".to_string())
+        .with_description("nested mint".to_string())
+        .build_with_seed(localstore.clone(), &mnemonic.to_seed_normalized(""))
+        .await
+        .unwrap();
+
+    let mut tx = localstore.begin_transaction().await.unwrap();
+    tx.set_quote_ttl(QuoteTTL::new(10000, 10000)).await.unwrap();
+    tx.commit().await.unwrap();
+
+    mint.start().await.unwrap();
+
+    mint
+}
+
+/// Create a paid mining share quote and return its id
+async fn create_mining_share_quote(mint: &Mint) -> String {
+    let quote = mint
+        .create_mint_mining_share_quote(MintQuoteMiningShareRequest {
+            amount: Amount::from(64),
+            unit: CurrencyUnit::Sat,
+            header_hash: sha256::Hash::hash(&SecretKey::generate().to_secret_bytes()),
+            description: None,
+            pubkey: SecretKey::generate().public_key(),
+            difficulty: None,
+        })
+        .await
+        .unwrap();
+
+    quote.id.to_string()
+}
+
+/// Create a bolt11 quote under `base`, returning the host header and the quote
+async fn post_bolt11_quote(base: &str) -> (Option<String>, MintQuoteBolt11Response<String>) {
+    let response = reqwest::Client::new()
+        .post(format!("{base}/v1/mint/quote/bolt11"))
+        .json(&MintQuoteBolt11Request {
+            amount: Amount::from(100),
+            unit: CurrencyUnit::Sat,
+            description: None,
+            pubkey: None,
+        })
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+
+    let host = host_header(&response);
+    (host, response.json().await.unwrap())
+}
+
+/// Look up a mining share quote under `base`, returning the host header and the quote
+async fn get_mining_share_quote(
+    base: &str,
+    quote_id: &str,
+) -> (Option<String>, MintQuoteMiningShareResponse<String>) {
+    let response = reqwest::get(format!("{base}/v1/mint/quote/mining_share/{quote_id}"))
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+
+    let host = host_header(&response);
+    (host, response.json().await.unwrap())
+}
+
+fn host_header(response: &reqwest::Response) -> Option<String> {
+    response
+        .headers()
+        .get(HOST_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_owned)
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_routes_nested_in_host_app() {
+    let mint = create_mint().await;
+    let quote_id = create_mining_share_quote(&mint).await;
+
+    let host_app = Router::new()
+        .route("/health", get(|| async { "ok" }))
+        .nest("/cashu", all_routes(RoutesConfig::default()))
+        .layer(from_fn(host_middleware))
+        .with_state(MintState::new(Arc::new(mint), Default::default()));
+    let host_url = serve(host_app).await.to_string();
+    let base = format!("{host_url}/cashu");
+
+    let health = reqwest::get(format!("{host_url}/health")).await.unwrap();
+    assert_eq!(health.text().await.unwrap(), "ok");
+
+    let (host, quote) = post_bolt11_quote(&base).await;
+    assert_eq!(host.as_deref(), Some("pool"));
+    assert_eq!(quote.amount, Some(Amount::from(100)));
+
+    let (host, quote) = get_mining_share_quote(&base, &quote_id).await;
+    assert_eq!(host.as_deref(), Some("pool"));
+    assert_eq!(quote.quote, quote_id);
+    assert_eq!(quote.state, QuoteState::Paid);
+
+    // The routes are only served under the prefix
+    let response = reqwest::get(format!("{host_url}/v1/info")).await.unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::NOT_FOUND);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_routes_disabled_by_config() {
+    let mint = create_mint().await;
+    let quote_id = create_mining_share_quote(&mint).await;
+
+    let config = RoutesConfig::default().mining_share(false).ws(false);
+    let host_app = Router::new()
+        .nest("/cashu", all_routes(config))
+        .with_state(MintState::new(Arc::new(mint), Default::default()));
+    let base = format!("{}/cashu", serve(host_app).await);
+
+    let response = reqwest::get(format!("{base}/v1/mint/quote/mining_share/{quote_id}"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::NOT_FOUND);
+
+    let response = reqwest::get(format!("{base}/v1/info")).await.unwrap();
+    assert!(response.status().is_success());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_standalone_router_paths_unchanged() {
+    let mint = create_mint().await;
+    let quote_id = create_mining_share_quote(&mint).await;
+
+    let router = cdk_axum::create_mint_router(Arc::new(mint), false)
+        .await
+        .unwrap();
+    let mint_url = serve(router).await.to_string();
+
+    let (host, quote) = post_bolt11_quote(&mint_url).await;
+    assert_eq!(host, None);
+    assert_eq!(quote.amount, Some(Amount::from(100)));
+
+    let (host, quote) = get_mining_share_quote(&mint_url, &quote_id).await;
+    assert_eq!(host, None);
+    assert_eq!(quote.state, QuoteState::Paid);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_mining_share_rate_limit() {
+    let mint = create_mint().await;
+    let quote_id = create_mining_share_quote(&mint).await;
+
+    let state = MintState::new(Arc::new(mint), Default::default())
+        .with_mining_share_rate_limit(RateLimit::new(3, Duration::from_secs(60)));
+    let router = all_routes(RoutesConfig::default()).with_state(state);
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("bind listener");
+    let addr = listener.local_addr().expect("local addr");
+    tokio::spawn(async move {
+        axum::serve(
+            listener,
+            router.into_make_service_with_connect_info::<SocketAddr>(),
+        )
+        .await
+        .expect("server running");
+    });
+    let base = format!("http://{addr}");
+
+    // Staying within the burst succeeds
+    for _ in 0..3 {
+        let (_, quote) = get_mining_share_quote(&base, &quote_id).await;
+        assert_eq!(quote.quote, quote_id);
+    }
+
+    let response = reqwest::get(format!("{base}/v1/mint/quote/mining_share/{quote_id}"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::TOO_MANY_REQUESTS);
+    let retry_after: u64 = response
+        .headers()
+        .get(reqwest::header::RETRY_AFTER)
+        .expect("Retry-After header")
+        .to_str()
+        .unwrap()
+        .parse()
+        .unwrap();
+    assert!((1..=60).contains(&retry_after));
+    let error = ErrorResponse::from_json(&response.text().await.unwrap()).unwrap();
+    assert_eq!(error.code, ErrorCode::RateLimited);
+
+    // Other endpoints are not limited
+    let (_, quote) = post_bolt11_quote(&base).await;
+    assert_eq!(quote.amount, Some(Amount::from(100)));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_mining_share_batch_too_large() {
+    let mint = create_mint().await;
+    let quote_id = create_mining_share_quote(&mint).await;
+
+    let router = cdk_axum::create_mint_router(Arc::new(mint), false)
+        .await
+        .unwrap();
+    let mint_url = serve(router).await.to_string();
+
+    let check = |quotes: Vec<String>| {
+        reqwest::Client::new()
+            .post(format!("{mint_url}/v1/mint/quote/mining_share/check"))
+            .json(&BatchQuoteStatusRequest { quotes })
+            .send()
+    };
+
+    let response = check(vec![quote_id.clone(); MAX_BATCH_QUOTE_STATUS])
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+
+    let response = check(vec![quote_id; MAX_BATCH_QUOTE_STATUS + 1])
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+    let error = ErrorResponse::from_json(&response.text().await.unwrap()).unwrap();
+    assert_eq!(error.code, ErrorCode::BatchTooLarge);
+}
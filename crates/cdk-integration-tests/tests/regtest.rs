@@ -420,7 +420,7 @@ async fn test_attempt_to_mint_unpaid() {
 
     let state = wallet.mint_quote_state(&mint_quote.id).await.unwrap();
 
-    assert!(state.state == MintQuoteState::Unpaid);
+    assert!(state.response.state == MintQuoteState::Unpaid);
 
     let proofs = wallet
         .mint(&mint_quote.id, SplitTarget::default(), None)
@@ -0,0 +1,205 @@
+//! Request scheduler tests
+//!
+//! Runs the wallet HTTP client behind a [`RequestScheduler`] against minimal
+//! mints that rate limit their clients.
+
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use axum::http::{header, StatusCode};
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::{Json, Router};
+use cdk::mint_url::MintUrl;
+use cdk::nuts::{KeysetResponse, MintInfo};
+use cdk::wallet::{
+    HttpClient, MintConnector, RateLimit, RequestPriority, RequestScheduler, ScheduledMintConnector,
+};
+
+/// Serve the router on a random local port
+async fn serve(router: Router) -> MintUrl {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("bind listener");
+    let addr = listener.local_addr().expect("local addr");
+
+    tokio::spawn(async move {
+        axum::serve(listener, router).await.expect("server running");
+    });
+
+    MintUrl::from_str(&format!("http://{addr}")).expect("valid mint url")
+}
+
+/// Mint answering `/v1/keysets` with a 429 for the first `limited` requests
+async fn rate_limiting_mint(limited: usize, hits: Arc<AtomicUsize>) -> MintUrl {
+    let router = Router::new().route(
+        "/v1/keysets",
+        get(move || {
+            let hits = hits.clone();
+            async move {
+                if hits.fetch_add(1, Ordering::SeqCst) < limited {
+                    return (
+                        StatusCode::TOO_MANY_REQUESTS,
+                        [(header::RETRY_AFTER, "1")],
+                        "rate limited",
+                    )
+                        .into_response();
+                }
+
+                Json(KeysetResponse { keysets: vec![] }).into_response()
+            }
+        }),
+    );
+
+    serve(router).await
+}
+
+/// Mint answering `/v1/info`
+async fn info_mint() -> MintUrl {
+    let router = Router::new().route("/v1/info", get(|| async { Json(MintInfo::default()) }));
+
+    serve(router).await
+}
+
+fn scheduled_client(
+    mint_url: MintUrl,
+    rate_limit: RateLimit,
+) -> Arc<ScheduledMintConnector<HttpClient>> {
+    Arc::new(ScheduledMintConnector::new(
+        HttpClient::new(mint_url, None),
+        RequestScheduler::new(rate_limit),
+    ))
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_rate_limited_request_error() {
+    let hits = Arc::new(AtomicUsize::new(0));
+    let mint_url = rate_limiting_mint(1, hits.clone()).await;
+
+    let client = HttpClient::new(mint_url, None);
+
+    match client.get_mint_keysets().await {
+        Err(cdk::Error::RateLimited(Some(1))) => (),
+        other => panic!("Expected rate limit error, got {other:?}"),
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_backs_off_per_retry_after() {
+    let hits = Arc::new(AtomicUsize::new(0));
+    let mint_url = rate_limiting_mint(2, hits.clone()).await;
+
+    let client = scheduled_client(
+        mint_url,
+        RateLimit {
+            requests_per_second: 100.0,
+            burst: 10,
+            max_retries: 3,
+        },
+    );
+
+    let started = Instant::now();
+    client
+        .get_mint_keysets()
+        .await
+        .expect("Request succeeds after backing off");
+
+    // Two 429s with a Retry-After of a second each
+    assert_eq!(hits.load(Ordering::SeqCst), 3);
+    assert!(started.elapsed() >= Duration::from_secs(2));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_gives_up_after_max_retries() {
+    let hits = Arc::new(AtomicUsize::new(0));
+    let mint_url = rate_limiting_mint(usize::MAX, hits.clone()).await;
+
+    let client = scheduled_client(
+        mint_url,
+        RateLimit {
+            requests_per_second: 100.0,
+            burst: 10,
+            max_retries: 1,
+        },
+    );
+
+    assert!(matches!(
+        client.get_mint_keysets().await,
+        Err(cdk::Error::RateLimited(Some(1)))
+    ));
+    assert_eq!(hits.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_throughput_converges_to_rate() {
+    let mint_url = info_mint().await;
+
+    let client = scheduled_client(
+        mint_url,
+        RateLimit {
+            requests_per_second: 20.0,
+            burst: 1,
+            max_retries: 0,
+        },
+    );
+
+    let started = Instant::now();
+    for _ in 0..21 {
+        client.get_mint_info().await.unwrap();
+    }
+    let elapsed = started.elapsed();
+
+    // The burst covers the first request, the other 20 take a second
+    assert!(elapsed >= Duration::from_millis(950), "{elapsed:?}");
+    assert!(elapsed < Duration::from_secs(2), "{elapsed:?}");
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_interactive_requests_jump_the_queue() {
+    let mint_url = info_mint().await;
+
+    let client = scheduled_client(
+        mint_url,
+        RateLimit {
+            requests_per_second: 10.0,
+            burst: 1,
+            max_retries: 0,
+        },
+    );
+    let order = Arc::new(Mutex::new(Vec::new()));
+
+    // Use up the burst so the sync below has to queue
+    client.get_mint_info().await.unwrap();
+
+    let mut sync = Vec::new();
+    for i in 0..5 {
+        let client = client.clone();
+        let order = order.clone();
+        sync.push(tokio::spawn(RequestScheduler::with_priority(
+            RequestPriority::Background,
+            async move {
+                client.get_mint_info().await.unwrap();
+                order.lock().unwrap().push(format!("background-{i}"));
+            },
+        )));
+    }
+
+    while client.scheduler().queue_depth().background < 5 {
+        tokio::time::sleep(Duration::from_millis(1)).await;
+    }
+
+    client.get_mint_info().await.unwrap();
+    order.lock().unwrap().push("interactive".to_string());
+
+    for task in sync {
+        task.await.unwrap();
+    }
+
+    let order = order.lock().unwrap();
+    let position = order.iter().position(|entry| entry == "interactive");
+    // At most the background request already holding the next token goes first
+    assert!(position.is_some_and(|position| position <= 1), "{order:?}");
+    assert_eq!(client.scheduler().queue_depth().total(), 0);
+}
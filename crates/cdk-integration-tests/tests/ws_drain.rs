@@ -0,0 +1,245 @@
+//! Websocket Draining Tests
+//!
+//! This file contains tests for draining mint websocket connections on shutdown.
+//! The mint is served in-process over HTTP so the tests exercise the real websocket
+//! handler against plain websocket clients.
+//!
+//! Test Categories:
+//! - Draining notification and close frame delivered to every connected client
+//! - Notifications queued before the drain arriving before the close
+//! - New connections rejected while draining
+
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Context, Result};
+use bip39::Mnemonic;
+use cdk::mint::{Mint, MintBuilder, MintMeltLimits};
+use cdk::mint_url::MintUrl;
+use cdk::nuts::{CurrencyUnit, NotificationPayload, PaymentMethod, PublicKey, SecretKey, State};
+use cdk::types::{FeeReserve, QuoteTTL};
+use cdk::ws::{WsDraining, WsMessageOrResponse};
+use cdk_axum::WsDrain;
+use cdk_fake_wallet::FakeWallet;
+use cdk_sqlite::mint::memory;
+use futures::{SinkExt, StreamExt};
+use serde_json::json;
+use tokio::net::TcpStream;
+use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
+use tokio_tungstenite::tungstenite::protocol::{CloseFrame, Message};
+use tokio_tungstenite::{connect_async, MaybeTlsStream, WebSocketStream};
+
+type WsClient = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// Start a mint whose websocket connections drain through `ws_drain`
+async fn start_mint(ws_drain: WsDrain) -> (Mint, MintUrl) {
+    let fee_reserve = FeeReserve {
+        min_fee_reserve: 1.into(),
+        percent_fee_reserve: 1.0,
+    };
+
+    let fake_wallet = FakeWallet::new(
+        fee_reserve,
+        HashMap::default(),
+        HashSet::default(),
+        0,
+        CurrencyUnit::Sat,
+    );
+
+    let localstore = Arc::new(memory::empty().await.expect("valid db instance"));
+    let mut mint_builder = MintBuilder::new(localstore.clone());
+
+    mint_builder
+        .add_payment_processor(
+            CurrencyUnit::Sat,
+            PaymentMethod::Bolt11,
+            MintMeltLimits::new(1, 10_000),
+            Arc::new(fake_wallet),
+        )
+        .await
+        .unwrap();
+
+    mint_builder = mint_builder
+        .with_name("ws drain mint".to_string())
+        .with_description("ws drain mint".to_string());
+
+    let mnemonic = Mnemonic::generate(12).unwrap();
+    let mint = mint_builder
+        .build_with_seed(localstore.clone(), &mnemonic.to_seed_normalized(""))
+        .await
+        .unwrap();
+
+    let mut tx = localstore.begin_transaction().await.unwrap();
+    tx.set_quote_ttl(QuoteTTL::new(10000, 10000)).await.unwrap();
+    tx.commit().await.unwrap();
+
+    mint.start().await.unwrap();
+
+    let router = cdk_axum::create_mint_router_with_ws_drain(
+        Arc::new(mint.clone()),
+        Default::default(),
+        false,
+        ws_drain,
+    )
+    .await
+    .unwrap();
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("bind listener");
+    let addr = listener.local_addr().expect("local addr");
+
+    tokio::spawn(async move {
+        axum::serve(listener, router).await.expect("server running");
+    });
+
+    let mint_url = MintUrl::from_str(&format!("http://{addr}")).expect("valid mint url");
+
+    (mint, mint_url)
+}
+
+fn ws_url(mint_url: &MintUrl) -> String {
+    format!("{}/v1/ws", mint_url.to_string().replacen("http", "ws", 1))
+}
+
+/// Connect to the mint and subscribe to the state of the given proofs
+async fn connect_and_subscribe(mint_url: &MintUrl, ys: &[PublicKey]) -> Result<WsClient> {
+    let (mut ws, _) = connect_async(ws_url(mint_url)).await?;
+
+    ws.send(Message::Text(
+        serde_json::to_string(&json!({
+            "jsonrpc": "2.0",
+            "id": 0,
+            "method": "subscribe",
+            "params": {
+                "kind": "proof_state",
+                "filters": ys.iter().map(|y| y.to_hex()).collect::<Vec<_>>(),
+                "subId": "drain-test",
+            }
+        }))?
+        .into(),
+    ))
+    .await?;
+
+    let message = ws.next().await.context("websocket closed")??;
+    match serde_json::from_str::<WsMessageOrResponse>(message.to_text()?)? {
+        WsMessageOrResponse::Response(_) => Ok(ws),
+        other => bail!("Unexpected subscribe response: {:?}", other),
+    }
+}
+
+/// Messages received before the close frame, and the close frame itself
+struct DrainedConnection {
+    notifications: Vec<PublicKey>,
+    draining: Vec<WsDraining>,
+    close: Option<CloseFrame>,
+}
+
+/// Read from the connection until the server closes it
+async fn read_until_close(mut ws: WsClient) -> Result<DrainedConnection> {
+    let mut drained = DrainedConnection {
+        notifications: Vec::new(),
+        draining: Vec::new(),
+        close: None,
+    };
+
+    while let Some(message) = ws.next().await {
+        match message? {
+            Message::Text(text) => match serde_json::from_str::<WsMessageOrResponse>(&text)? {
+                WsMessageOrResponse::Notification(notification) => {
+                    match notification.params.payload {
+                        NotificationPayload::ProofState(state) => {
+                            drained.notifications.push(state.y)
+                        }
+                        other => bail!("Unexpected notification: {:?}", other),
+                    }
+                }
+                WsMessageOrResponse::Draining(draining) => drained.draining.push(draining.params),
+                other => bail!("Unexpected message: {:?}", other),
+            },
+            Message::Close(frame) => {
+                drained.close = frame;
+                break;
+            }
+            _ => continue,
+        }
+    }
+
+    Ok(drained)
+}
+
+/// Tests that every connected client gets the draining notification and a going away close
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_ws_drain_notifies_all_clients() {
+    let ws_drain = WsDrain::new()
+        .deadline(Duration::from_secs(2))
+        .reconnect_after(Duration::from_millis(1_000), Duration::from_millis(500));
+    let (_mint, mint_url) = start_mint(ws_drain.clone()).await;
+
+    let mut clients = Vec::new();
+    for _ in 0..5 {
+        let y = SecretKey::generate().public_key();
+        clients.push(connect_and_subscribe(&mint_url, &[y]).await.unwrap());
+    }
+
+    let readers: Vec<_> = clients
+        .into_iter()
+        .map(|ws| tokio::spawn(read_until_close(ws)))
+        .collect();
+
+    let start = Instant::now();
+    ws_drain.drain().await;
+    assert!(start.elapsed() < Duration::from_secs(3));
+
+    for reader in readers {
+        let drained = reader.await.unwrap().unwrap();
+
+        assert_eq!(drained.draining.len(), 1);
+        let reconnect_after_ms = drained.draining[0].reconnect_after_ms;
+        assert!(
+            (1_000..=1_500).contains(&reconnect_after_ms),
+            "reconnect after {reconnect_after_ms}ms"
+        );
+
+        let close = drained.close.expect("close frame");
+        assert_eq!(close.code, CloseCode::Away);
+    }
+}
+
+/// Tests that notifications published before the drain are delivered before the close
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_ws_drain_flushes_queued_notifications() {
+    let ws_drain = WsDrain::new().deadline(Duration::from_secs(2));
+    let (mint, mint_url) = start_mint(ws_drain.clone()).await;
+
+    let ys: Vec<PublicKey> = (0..5).map(|_| SecretKey::generate().public_key()).collect();
+    let ws = connect_and_subscribe(&mint_url, &ys).await.unwrap();
+
+    let pubsub_manager = mint.pubsub_manager();
+    for y in &ys {
+        pubsub_manager.proof_state((*y, State::Spent));
+    }
+
+    ws_drain.drain().await;
+
+    let drained = read_until_close(ws).await.unwrap();
+
+    let received: HashSet<_> = drained.notifications.into_iter().collect();
+    assert_eq!(received, ys.into_iter().collect());
+    assert_eq!(drained.draining.len(), 1);
+    assert_eq!(drained.close.expect("close frame").code, CloseCode::Away);
+}
+
+/// Tests that new websocket connections are rejected once draining started
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_ws_drain_rejects_new_connections() {
+    let ws_drain = WsDrain::new();
+    let (_mint, mint_url) = start_mint(ws_drain.clone()).await;
+
+    ws_drain.drain().await;
+    assert!(ws_drain.is_draining());
+
+    assert!(connect_async(ws_url(&mint_url)).await.is_err());
+}
@@ -98,6 +98,8 @@ enum Commands {
     UpdateNut04QuoteState(subcommands::UpdateNut04QuoteCommand),
     /// Rotate next keyset
     RotateNextKeyset(subcommands::RotateNextKeysetCommand),
+    /// Get mining share quote stats
+    GetMiningShareStats(subcommands::GetMiningShareStatsCommand),
 }
 
 #[tokio::main]
@@ -227,6 +229,9 @@ async fn main() -> Result<()> {
         Commands::RotateNextKeyset(sub_command_args) => {
             subcommands::rotate_next_keyset(&mut client, &sub_command_args).await?;
         }
+        Commands::GetMiningShareStats(sub_command_args) => {
+            subcommands::get_mining_share_stats(&mut client, &sub_command_args).await?;
+        }
     }
 
     Ok(())
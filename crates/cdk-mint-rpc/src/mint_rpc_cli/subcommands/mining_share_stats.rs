@@ -0,0 +1,57 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::Result;
+use clap::Args;
+use tonic::transport::Channel;
+use tonic::Request;
+
+use crate::cdk_mint_client::CdkMintClient;
+use crate::GetMiningShareStatsRequest;
+
+/// Command to get aggregates of the mining share quotes created in a time range
+///
+/// The range is inclusive and may span at most 90 days.
+#[derive(Args)]
+pub struct GetMiningShareStatsCommand {
+    /// Unix time the range starts at
+    #[arg(long)]
+    since: u64,
+    /// Unix time the range ends at, now if not set
+    #[arg(long)]
+    until: Option<u64>,
+}
+
+/// Executes the get_mining_share_stats command against the mint server
+///
+/// This function sends an RPC request for the mining share quote stats of the
+/// given range and prints them.
+///
+/// # Arguments
+/// * `client` - The RPC client used to communicate with the mint
+/// * `sub_command_args` - The time range to get the stats of
+pub async fn get_mining_share_stats(
+    client: &mut CdkMintClient<Channel>,
+    sub_command_args: &GetMiningShareStatsCommand,
+) -> Result<()> {
+    let until = match sub_command_args.until {
+        Some(until) => until,
+        None => SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
+    };
+
+    let response = client
+        .get_mining_share_stats(Request::new(GetMiningShareStatsRequest {
+            since: sub_command_args.since,
+            until,
+        }))
+        .await?;
+
+    let stats = response.into_inner();
+
+    println!("Quotes created: {}", stats.quotes_created);
+    println!("Quotes issued: {}", stats.quotes_issued);
+    println!("Amount quoted: {}", stats.amount_quoted);
+    println!("Amount issued: {}", stats.amount_issued);
+    println!("Distinct pubkeys: {}", stats.distinct_pubkeys);
+
+    Ok(())
+}
@@ -1,3 +1,5 @@
+/// Module for getting mining share quote stats
+mod mining_share_stats;
 /// Module for rotating to the next keyset
 mod rotate_next_keyset;
 /// Module for updating mint contact information
@@ -23,6 +25,7 @@ mod update_ttl;
 /// Module for managing mint URLs
 mod update_urls;
 
+pub use mining_share_stats::{get_mining_share_stats, GetMiningShareStatsCommand};
 pub use rotate_next_keyset::{rotate_next_keyset, RotateNextKeysetCommand};
 pub use update_contact::{add_contact, remove_contact, AddContactCommand, RemoveContactCommand};
 pub use update_icon_url::{update_icon_url, UpdateIconUrlCommand};
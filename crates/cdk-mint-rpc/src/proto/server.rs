@@ -19,11 +19,11 @@ use tonic::{Request, Response, Status};
 
 use crate::cdk_mint_server::{CdkMint, CdkMintServer};
 use crate::{
-    ContactInfo, GetInfoRequest, GetInfoResponse, GetQuoteTtlRequest, GetQuoteTtlResponse,
-    RotateNextKeysetRequest, RotateNextKeysetResponse, UpdateContactRequest,
-    UpdateDescriptionRequest, UpdateIconUrlRequest, UpdateMotdRequest, UpdateNameRequest,
-    UpdateNut04QuoteRequest, UpdateNut04Request, UpdateNut05Request, UpdateQuoteTtlRequest,
-    UpdateResponse, UpdateUrlRequest,
+    ContactInfo, GetInfoRequest, GetInfoResponse, GetMiningShareStatsRequest,
+    GetMiningShareStatsResponse, GetQuoteTtlRequest, GetQuoteTtlResponse, RotateNextKeysetRequest,
+    RotateNextKeysetResponse, UpdateContactRequest, UpdateDescriptionRequest, UpdateIconUrlRequest,
+    UpdateMotdRequest, UpdateNameRequest, UpdateNut04QuoteRequest, UpdateNut04Request,
+    UpdateNut05Request, UpdateQuoteTtlRequest, UpdateResponse, UpdateUrlRequest,
 };
 
 /// Error
@@ -665,7 +665,8 @@ impl CdkMint for MintRPCServer {
                     .await
                     .map_err(|_| Status::internal("Could not start db transaction".to_string()))?;
 
-                self.mint
+                let total_paid = self
+                    .mint
                     .pay_mint_quote(&mut tx, &mint_quote, response)
                     .await
                     .map_err(|_| Status::internal("Could not process payment".to_string()))?;
@@ -673,6 +674,12 @@ impl CdkMint for MintRPCServer {
                 tx.commit()
                     .await
                     .map_err(|_| Status::internal("Could not commit db transaction".to_string()))?;
+
+                if let Some(total_paid) = total_paid {
+                    self.mint
+                        .pubsub_manager()
+                        .mint_quote_payment(&mint_quote, total_paid);
+                }
             }
             _ => {
                 // Create a new quote with the same values
@@ -748,4 +755,31 @@ impl CdkMint for MintRPCServer {
             input_fee_ppk: keyset_info.input_fee_ppk,
         }))
     }
+
+    /// Gets aggregates of the mining share quotes created in a time range
+    async fn get_mining_share_stats(
+        &self,
+        request: Request<GetMiningShareStatsRequest>,
+    ) -> Result<Response<GetMiningShareStatsResponse>, Status> {
+        let request = request.into_inner();
+
+        let stats = self
+            .mint
+            .mining_share_stats(request.since, request.until)
+            .await
+            .map_err(|err| match err {
+                cdk::Error::InvalidMiningShareStatsRange { .. } => {
+                    Status::invalid_argument(err.to_string())
+                }
+                err => Status::internal(err.to_string()),
+            })?;
+
+        Ok(Response::new(GetMiningShareStatsResponse {
+            quotes_created: stats.quotes_created,
+            quotes_issued: stats.quotes_issued,
+            amount_quoted: stats.amount_quoted.into(),
+            amount_issued: stats.amount_issued.into(),
+            distinct_pubkeys: stats.distinct_pubkeys,
+        }))
+    }
 }
@@ -365,6 +365,8 @@ pub struct PostgresConfig {
     pub tls_mode: Option<String>,
     pub max_connections: Option<usize>,
     pub connection_timeout_seconds: Option<u64>,
+    /// Read replica serving quote status and lookup reads
+    pub read_replica_url: Option<String>,
 }
 
 impl Default for PostgresConfig {
@@ -374,6 +376,7 @@ impl Default for PostgresConfig {
             tls_mode: Some("disable".to_string()),
             max_connections: Some(20),
             connection_timeout_seconds: Some(10),
+            read_replica_url: None,
         }
     }
 }
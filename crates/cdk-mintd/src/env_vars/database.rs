@@ -8,6 +8,7 @@ pub const ENV_POSTGRES_URL: &str = "CDK_MINTD_POSTGRES_URL";
 pub const ENV_POSTGRES_TLS_MODE: &str = "CDK_MINTD_POSTGRES_TLS_MODE";
 pub const ENV_POSTGRES_MAX_CONNECTIONS: &str = "CDK_MINTD_POSTGRES_MAX_CONNECTIONS";
 pub const ENV_POSTGRES_CONNECTION_TIMEOUT: &str = "CDK_MINTD_POSTGRES_CONNECTION_TIMEOUT_SECONDS";
+pub const ENV_POSTGRES_READ_REPLICA_URL: &str = "CDK_MINTD_POSTGRES_READ_REPLICA_URL";
 
 impl PostgresConfig {
     pub fn from_env(mut self) -> Self {
@@ -35,6 +36,10 @@ impl PostgresConfig {
             }
         }
 
+        if let Ok(read_replica_url) = env::var(ENV_POSTGRES_READ_REPLICA_URL) {
+            self.read_replica_url = Some(read_replica_url);
+        }
+
         self
     }
 }
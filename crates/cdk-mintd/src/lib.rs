@@ -282,7 +282,14 @@ async fn setup_database(
             }
 
             #[cfg(feature = "postgres")]
-            let pg_db = Arc::new(MintPgDatabase::new(pg_config.url.as_str()).await?);
+            let mut pg_db = MintPgDatabase::new(pg_config.url.as_str()).await?;
+            #[cfg(feature = "postgres")]
+            if let Some(read_replica_url) = pg_config.read_replica_url.as_ref() {
+                tracing::info!("Serving database reads from the read replica");
+                pg_db = pg_db.with_read_replica(read_replica_url.as_str());
+            }
+            #[cfg(feature = "postgres")]
+            let pg_db = Arc::new(pg_db);
             #[cfg(feature = "postgres")]
             let localstore: Arc<dyn MintDatabase<cdk_database::Error> + Send + Sync> =
                 pg_db.clone();
@@ -922,9 +929,14 @@ async fn start_services_with_shutdown(
     let bolt12_supported = nut04_methods.contains(&&PaymentMethod::Bolt12)
         || nut05_methods.contains(&&PaymentMethod::Bolt12);
 
-    let v1_service =
-        cdk_axum::create_mint_router_with_custom_cache(Arc::clone(&mint), cache, bolt12_supported)
-            .await?;
+    let ws_drain = cdk_axum::WsDrain::default();
+    let v1_service = cdk_axum::create_mint_router_with_ws_drain(
+        Arc::clone(&mint),
+        cache,
+        bolt12_supported,
+        ws_drain.clone(),
+    )
+    .await?;
 
     let mut mint_service = Router::new()
         .merge(v1_service)
@@ -1013,6 +1025,8 @@ async fn start_services_with_shutdown(
     let mut axum_shutdown_rx = shutdown_tx.subscribe();
     let axum_shutdown = async move {
         let _ = axum_shutdown_rx.recv().await;
+        // Let websocket clients know to reconnect later before connections are dropped
+        ws_drain.drain().await;
     };
 
     // Wait for axum server to complete with custom shutdown signal
@@ -1135,6 +1149,9 @@ pub async fn run_mintd_with_shutdown(
     // Pending melt quotes where the payment has **failed** inputs are reset to unspent
     mint.check_pending_melt_quotes().await?;
 
+    // Warns about keysets sharing amount keys
+    mint.check_keyset_key_reuse();
+
     let result = start_services_with_shutdown(
         mint.clone(),
         settings,
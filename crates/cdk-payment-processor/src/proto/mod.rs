@@ -223,6 +223,8 @@ impl From<QuoteState> for cdk_common::nuts::MeltQuoteState {
             QuoteState::Unknown => Self::Unknown,
             QuoteState::Failed => Self::Failed,
             QuoteState::Issued => Self::Unknown,
+            QuoteState::Converted => Self::Unknown,
+            QuoteState::Cancelled => Self::Unknown,
         }
     }
 }
@@ -245,6 +247,8 @@ impl From<cdk_common::nuts::MintQuoteState> for QuoteState {
             cdk_common::nuts::MintQuoteState::Unpaid => Self::Unpaid,
             cdk_common::nuts::MintQuoteState::Paid => Self::Paid,
             cdk_common::nuts::MintQuoteState::Issued => Self::Issued,
+            cdk_common::nuts::MintQuoteState::Converted => Self::Converted,
+            cdk_common::nuts::MintQuoteState::Cancelled => Self::Cancelled,
         }
     }
 }
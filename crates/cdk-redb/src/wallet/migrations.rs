@@ -11,7 +11,10 @@ use redb::{
 };
 
 use super::Error;
-use crate::wallet::{KEYSETS_TABLE, KEYSET_COUNTER, KEYSET_U32_MAPPING, MINT_KEYS_TABLE};
+use crate::wallet::{
+    DEAD_LETTERS_TABLE, KEYSETS_TABLE, KEYSET_COUNTER, KEYSET_U32_MAPPING, MINT_KEYS_TABLE,
+    PENDING_MINT_OPERATIONS_TABLE, RESTORE_PROGRESS_TABLE, WALLET_SNAPSHOTS_TABLE,
+};
 
 // <Mint_url, Info>
 const MINTS_TABLE: TableDefinition<&str, &str> = TableDefinition::new("mints_table");
@@ -200,3 +203,57 @@ pub(crate) fn migrate_03_to_04(db: Arc<Database>) -> Result<u32, Error> {
 
     Ok(4)
 }
+
+pub(crate) fn migrate_04_to_05(db: Arc<Database>) -> Result<u32, Error> {
+    let write_txn = db.begin_write().map_err(Error::from)?;
+    let _ = write_txn
+        .open_table(RESTORE_PROGRESS_TABLE)
+        .map_err(Error::from)?;
+    write_txn.commit()?;
+
+    Ok(5)
+}
+
+pub(crate) fn migrate_05_to_06(db: Arc<Database>) -> Result<u32, Error> {
+    let write_txn = db.begin_write().map_err(Error::from)?;
+    let _ = write_txn
+        .open_table(DEAD_LETTERS_TABLE)
+        .map_err(Error::from)?;
+    write_txn.commit()?;
+
+    Ok(6)
+}
+
+pub(crate) fn migrate_06_to_07(db: Arc<Database>) -> Result<u32, Error> {
+    let write_txn = db.begin_write().map_err(Error::from)?;
+    let _ = write_txn
+        .open_table(PENDING_MINT_OPERATIONS_TABLE)
+        .map_err(Error::from)?;
+    write_txn.commit()?;
+
+    Ok(7)
+}
+
+/// Drop the dead letters stored before they were scoped by mint
+pub(crate) fn migrate_07_to_08(db: Arc<Database>) -> Result<u32, Error> {
+    let write_txn = db.begin_write().map_err(Error::from)?;
+    write_txn
+        .delete_table(DEAD_LETTERS_TABLE)
+        .map_err(Error::from)?;
+    let _ = write_txn
+        .open_table(DEAD_LETTERS_TABLE)
+        .map_err(Error::from)?;
+    write_txn.commit()?;
+
+    Ok(8)
+}
+
+pub(crate) fn migrate_08_to_09(db: Arc<Database>) -> Result<u32, Error> {
+    let write_txn = db.begin_write().map_err(Error::from)?;
+    let _ = write_txn
+        .open_table(WALLET_SNAPSHOTS_TABLE)
+        .map_err(Error::from)?;
+    write_txn.commit()?;
+
+    Ok(9)
+}
@@ -11,7 +11,10 @@ use cdk_common::common::ProofInfo;
 use cdk_common::database::WalletDatabase;
 use cdk_common::mint_url::MintUrl;
 use cdk_common::util::unix_time;
-use cdk_common::wallet::{self, MintQuote, Transaction, TransactionDirection, TransactionId};
+use cdk_common::wallet::{
+    self, DeadLetter, MintQuote, PendingMintOperation, RestoreProgress, Transaction,
+    TransactionDirection, TransactionId, WalletSnapshot,
+};
 use cdk_common::{
     database, CurrencyUnit, Id, KeySet, KeySetInfo, Keys, MintInfo, PublicKey, SpendingConditions,
     State,
@@ -21,7 +24,10 @@ use tracing::instrument;
 
 use super::error::Error;
 use crate::migrations::migrate_00_to_01;
-use crate::wallet::migrations::{migrate_01_to_02, migrate_02_to_03, migrate_03_to_04};
+use crate::wallet::migrations::{
+    migrate_01_to_02, migrate_02_to_03, migrate_03_to_04, migrate_04_to_05, migrate_05_to_06,
+    migrate_06_to_07, migrate_07_to_08, migrate_08_to_09,
+};
 
 mod migrations;
 
@@ -45,8 +51,18 @@ const KEYSET_COUNTER: TableDefinition<&str, u32> = TableDefinition::new("keyset_
 const TRANSACTIONS_TABLE: TableDefinition<&[u8], &str> = TableDefinition::new("transactions");
 
 const KEYSET_U32_MAPPING: TableDefinition<u32, &str> = TableDefinition::new("keyset_u32_mapping");
-
-const DATABASE_VERSION: u32 = 4;
+// <Keyset_id, RestoreProgress>
+const RESTORE_PROGRESS_TABLE: TableDefinition<&str, &str> =
+    TableDefinition::new("restore_progress");
+// <Dead_letter_id, DeadLetter>
+const DEAD_LETTERS_TABLE: TableDefinition<u64, &str> = TableDefinition::new("dead_letters");
+// <Operation_id, PendingMintOperation>
+const PENDING_MINT_OPERATIONS_TABLE: TableDefinition<&str, &str> =
+    TableDefinition::new("pending_mint_operations");
+// <Snapshot_id, WalletSnapshot>
+const WALLET_SNAPSHOTS_TABLE: TableDefinition<u64, &str> = TableDefinition::new("wallet_snapshots");
+
+const DATABASE_VERSION: u32 = 9;
 
 /// Wallet Redb Database
 #[derive(Debug, Clone)]
@@ -100,6 +116,26 @@ impl WalletRedbDatabase {
                                 current_file_version = migrate_03_to_04(Arc::clone(&db))?;
                             }
 
+                            if current_file_version == 4 {
+                                current_file_version = migrate_04_to_05(Arc::clone(&db))?;
+                            }
+
+                            if current_file_version == 5 {
+                                current_file_version = migrate_05_to_06(Arc::clone(&db))?;
+                            }
+
+                            if current_file_version == 6 {
+                                current_file_version = migrate_06_to_07(Arc::clone(&db))?;
+                            }
+
+                            if current_file_version == 7 {
+                                current_file_version = migrate_07_to_08(Arc::clone(&db))?;
+                            }
+
+                            if current_file_version == 8 {
+                                current_file_version = migrate_08_to_09(Arc::clone(&db))?;
+                            }
+
                             if current_file_version != DATABASE_VERSION {
                                 tracing::warn!(
                                     "Database upgrade did not complete at {} current is {}",
@@ -147,6 +183,10 @@ impl WalletRedbDatabase {
                         let _ = write_txn.open_table(KEYSET_COUNTER)?;
                         let _ = write_txn.open_table(TRANSACTIONS_TABLE)?;
                         let _ = write_txn.open_table(KEYSET_U32_MAPPING)?;
+                        let _ = write_txn.open_table(RESTORE_PROGRESS_TABLE)?;
+                        let _ = write_txn.open_table(DEAD_LETTERS_TABLE)?;
+                        let _ = write_txn.open_table(PENDING_MINT_OPERATIONS_TABLE)?;
+                        let _ = write_txn.open_table(WALLET_SNAPSHOTS_TABLE)?;
                         table.insert("db_version", DATABASE_VERSION.to_string().as_str())?;
                     }
 
@@ -805,6 +845,278 @@ impl WalletDatabase for WalletRedbDatabase {
         Ok(new_counter)
     }
 
+    #[instrument(skip(self))]
+    async fn add_restore_progress(&self, progress: RestoreProgress) -> Result<(), Self::Err> {
+        let write_txn = self.db.begin_write().map_err(Error::from)?;
+
+        {
+            let mut table = write_txn
+                .open_table(RESTORE_PROGRESS_TABLE)
+                .map_err(Error::from)?;
+            table
+                .insert(
+                    progress.keyset_id.to_string().as_str(),
+                    serde_json::to_string(&progress)
+                        .map_err(Error::from)?
+                        .as_str(),
+                )
+                .map_err(Error::from)?;
+        }
+
+        write_txn.commit().map_err(Error::from)?;
+
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn get_restore_progress(
+        &self,
+        keyset_id: &Id,
+    ) -> Result<Option<RestoreProgress>, Self::Err> {
+        let read_txn = self.db.begin_read().map_err(Error::from)?;
+        let table = read_txn
+            .open_table(RESTORE_PROGRESS_TABLE)
+            .map_err(Error::from)?;
+
+        if let Some(progress) = table
+            .get(keyset_id.to_string().as_str())
+            .map_err(Error::from)?
+        {
+            return Ok(serde_json::from_str(progress.value()).map_err(Error::from)?);
+        }
+
+        Ok(None)
+    }
+
+    #[instrument(skip(self))]
+    async fn remove_restore_progress(&self, keyset_id: &Id) -> Result<(), Self::Err> {
+        let write_txn = self.db.begin_write().map_err(Error::from)?;
+
+        {
+            let mut table = write_txn
+                .open_table(RESTORE_PROGRESS_TABLE)
+                .map_err(Error::from)?;
+            table
+                .remove(keyset_id.to_string().as_str())
+                .map_err(Error::from)?;
+        }
+
+        write_txn.commit().map_err(Error::from)?;
+
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn add_dead_letter(&self, mut dead_letter: DeadLetter) -> Result<(), Self::Err> {
+        let write_txn = self.db.begin_write().map_err(Error::from)?;
+
+        {
+            let mut table = write_txn
+                .open_table(DEAD_LETTERS_TABLE)
+                .map_err(Error::from)?;
+
+            // Numbered within the write transaction, so concurrent writers
+            // cannot take the same id
+            dead_letter.id = table
+                .last()
+                .map_err(Error::from)?
+                .map_or(0, |(id, _)| id.value() + 1);
+
+            table
+                .insert(
+                    dead_letter.id,
+                    serde_json::to_string(&dead_letter)
+                        .map_err(Error::from)?
+                        .as_str(),
+                )
+                .map_err(Error::from)?;
+        }
+
+        write_txn.commit().map_err(Error::from)?;
+
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn get_dead_letters(
+        &self,
+        mint_url: Option<MintUrl>,
+    ) -> Result<Vec<DeadLetter>, Self::Err> {
+        let read_txn = self.db.begin_read().map_err(Error::from)?;
+        let table = read_txn
+            .open_table(DEAD_LETTERS_TABLE)
+            .map_err(Error::from)?;
+
+        Ok(table
+            .iter()
+            .map_err(Error::from)?
+            .flatten()
+            .flat_map(|(_id, dead_letter)| serde_json::from_str::<DeadLetter>(dead_letter.value()))
+            .filter(|dead_letter| {
+                mint_url
+                    .as_ref()
+                    .is_none_or(|mint_url| dead_letter.mint_url == *mint_url)
+            })
+            .collect())
+    }
+
+    #[instrument(skip(self))]
+    async fn remove_dead_letter(&self, id: u64) -> Result<(), Self::Err> {
+        let write_txn = self.db.begin_write().map_err(Error::from)?;
+
+        {
+            let mut table = write_txn
+                .open_table(DEAD_LETTERS_TABLE)
+                .map_err(Error::from)?;
+            table.remove(id).map_err(Error::from)?;
+        }
+
+        write_txn.commit().map_err(Error::from)?;
+
+        Ok(())
+    }
+
+    #[instrument(skip(self, operation))]
+    async fn add_pending_mint_operation(
+        &self,
+        operation: PendingMintOperation,
+    ) -> Result<(), Self::Err> {
+        let write_txn = self.db.begin_write().map_err(Error::from)?;
+
+        {
+            let mut table = write_txn
+                .open_table(PENDING_MINT_OPERATIONS_TABLE)
+                .map_err(Error::from)?;
+            table
+                .insert(
+                    operation.id.as_str(),
+                    serde_json::to_string(&operation)
+                        .map_err(Error::from)?
+                        .as_str(),
+                )
+                .map_err(Error::from)?;
+        }
+
+        write_txn.commit().map_err(Error::from)?;
+
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn get_pending_mint_operations(&self) -> Result<Vec<PendingMintOperation>, Self::Err> {
+        let read_txn = self.db.begin_read().map_err(Error::from)?;
+        let table = read_txn
+            .open_table(PENDING_MINT_OPERATIONS_TABLE)
+            .map_err(Error::from)?;
+
+        let mut operations: Vec<PendingMintOperation> = table
+            .iter()
+            .map_err(Error::from)?
+            .flatten()
+            .flat_map(|(_id, operation)| serde_json::from_str(operation.value()))
+            .collect();
+
+        operations.sort_by(|a, b| (a.created_time, &a.id).cmp(&(b.created_time, &b.id)));
+
+        Ok(operations)
+    }
+
+    #[instrument(skip(self))]
+    async fn remove_pending_mint_operation(&self, id: &str) -> Result<(), Self::Err> {
+        let write_txn = self.db.begin_write().map_err(Error::from)?;
+
+        {
+            let mut table = write_txn
+                .open_table(PENDING_MINT_OPERATIONS_TABLE)
+                .map_err(Error::from)?;
+            table.remove(id).map_err(Error::from)?;
+        }
+
+        write_txn.commit().map_err(Error::from)?;
+
+        Ok(())
+    }
+
+    #[instrument(skip(self, snapshot), fields(label = %snapshot.label))]
+    async fn add_wallet_snapshot(&self, mut snapshot: WalletSnapshot) -> Result<u64, Self::Err> {
+        let write_txn = self.db.begin_write().map_err(Error::from)?;
+
+        {
+            let mut table = write_txn
+                .open_table(WALLET_SNAPSHOTS_TABLE)
+                .map_err(Error::from)?;
+
+            // Numbered within the write transaction, so concurrent writers
+            // cannot take the same id
+            snapshot.id = table
+                .last()
+                .map_err(Error::from)?
+                .map_or(0, |(id, _)| id.value() + 1);
+
+            table
+                .insert(
+                    snapshot.id,
+                    serde_json::to_string(&snapshot)
+                        .map_err(Error::from)?
+                        .as_str(),
+                )
+                .map_err(Error::from)?;
+        }
+
+        write_txn.commit().map_err(Error::from)?;
+
+        Ok(snapshot.id)
+    }
+
+    #[instrument(skip(self))]
+    async fn get_wallet_snapshot(&self, id: u64) -> Result<Option<WalletSnapshot>, Self::Err> {
+        let read_txn = self.db.begin_read().map_err(Error::from)?;
+        let table = read_txn
+            .open_table(WALLET_SNAPSHOTS_TABLE)
+            .map_err(Error::from)?;
+
+        match table.get(id).map_err(Error::from)? {
+            Some(snapshot) => Ok(serde_json::from_str(snapshot.value()).map_err(Error::from)?),
+            None => Ok(None),
+        }
+    }
+
+    #[instrument(skip(self))]
+    async fn get_wallet_snapshots(
+        &self,
+        mint_url: MintUrl,
+        unit: CurrencyUnit,
+    ) -> Result<Vec<WalletSnapshot>, Self::Err> {
+        let read_txn = self.db.begin_read().map_err(Error::from)?;
+        let table = read_txn
+            .open_table(WALLET_SNAPSHOTS_TABLE)
+            .map_err(Error::from)?;
+
+        Ok(table
+            .iter()
+            .map_err(Error::from)?
+            .flatten()
+            .flat_map(|(_id, snapshot)| serde_json::from_str::<WalletSnapshot>(snapshot.value()))
+            .filter(|snapshot| snapshot.mint_url == mint_url && snapshot.unit == unit)
+            .collect())
+    }
+
+    #[instrument(skip(self))]
+    async fn remove_wallet_snapshot(&self, id: u64) -> Result<(), Self::Err> {
+        let write_txn = self.db.begin_write().map_err(Error::from)?;
+
+        {
+            let mut table = write_txn
+                .open_table(WALLET_SNAPSHOTS_TABLE)
+                .map_err(Error::from)?;
+            table.remove(id).map_err(Error::from)?;
+        }
+
+        write_txn.commit().map_err(Error::from)?;
+
+        Ok(())
+    }
+
     #[instrument(skip(self))]
     async fn add_transaction(&self, transaction: Transaction) -> Result<(), Self::Err> {
         let write_txn = self.db.begin_write().map_err(Error::from)?;
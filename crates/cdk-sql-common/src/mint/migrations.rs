@@ -5,6 +5,10 @@ pub static MIGRATIONS: &[(&str, &str, &str)] = &[
     ("postgres", "2_remove_request_lookup_kind_constraints.sql", include_str!(r#"./migrations/postgres/2_remove_request_lookup_kind_constraints.sql"#)),
     ("postgres", "20250901090000_add_kv_store.sql", include_str!(r#"./migrations/postgres/20250901090000_add_kv_store.sql"#)),
     ("postgres", "20250903200000_add_signatory_amounts.sql", include_str!(r#"./migrations/postgres/20250903200000_add_signatory_amounts.sql"#)),
+    ("postgres", "20250910120000_add_converted_to_mint_quote.sql", include_str!(r#"./migrations/postgres/20250910120000_add_converted_to_mint_quote.sql"#)),
+    ("postgres", "20250925120000_add_mint_quote_pubkey_index.sql", include_str!(r#"./migrations/postgres/20250925120000_add_mint_quote_pubkey_index.sql"#)),
+    ("postgres", "20250930120000_add_cancelled_at_mint_quote.sql", include_str!(r#"./migrations/postgres/20250930120000_add_cancelled_at_mint_quote.sql"#)),
+    ("postgres", "20251005120000_add_difficulty_mint_quote.sql", include_str!(r#"./migrations/postgres/20251005120000_add_difficulty_mint_quote.sql"#)),
     ("sqlite", "1_fix_sqlx_migration.sql", include_str!(r#"./migrations/sqlite/1_fix_sqlx_migration.sql"#)),
     ("sqlite", "20240612124932_init.sql", include_str!(r#"./migrations/sqlite/20240612124932_init.sql"#)),
     ("sqlite", "20240618195700_quote_state.sql", include_str!(r#"./migrations/sqlite/20240618195700_quote_state.sql"#)),
@@ -32,4 +36,8 @@ pub static MIGRATIONS: &[(&str, &str, &str)] = &[
     ("sqlite", "20250819200000_remove_request_lookup_kind_constraints.sql", include_str!(r#"./migrations/sqlite/20250819200000_remove_request_lookup_kind_constraints.sql"#)),
     ("sqlite", "20250901090000_add_kv_store.sql", include_str!(r#"./migrations/sqlite/20250901090000_add_kv_store.sql"#)),
     ("sqlite", "20250903200000_add_signatory_amounts.sql", include_str!(r#"./migrations/sqlite/20250903200000_add_signatory_amounts.sql"#)),
+    ("sqlite", "20250910120000_add_converted_to_mint_quote.sql", include_str!(r#"./migrations/sqlite/20250910120000_add_converted_to_mint_quote.sql"#)),
+    ("sqlite", "20250925120000_add_mint_quote_pubkey_index.sql", include_str!(r#"./migrations/sqlite/20250925120000_add_mint_quote_pubkey_index.sql"#)),
+    ("sqlite", "20250930120000_add_cancelled_at_mint_quote.sql", include_str!(r#"./migrations/sqlite/20250930120000_add_cancelled_at_mint_quote.sql"#)),
+    ("sqlite", "20251005120000_add_difficulty_mint_quote.sql", include_str!(r#"./migrations/sqlite/20251005120000_add_difficulty_mint_quote.sql"#)),
 ];
@@ -0,0 +1,2 @@
+-- Pending quotes are counted per NUT-20 pubkey
+CREATE INDEX IF NOT EXISTS mint_quote_pubkey_index ON mint_quote(pubkey);
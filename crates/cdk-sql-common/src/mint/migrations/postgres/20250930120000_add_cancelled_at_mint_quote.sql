@@ -0,0 +1,2 @@
+-- Unix time a mint quote was cancelled at
+ALTER TABLE mint_quote ADD COLUMN cancelled_at INTEGER;
@@ -0,0 +1,2 @@
+-- Quote a mint quote was converted into
+ALTER TABLE mint_quote ADD COLUMN converted_to TEXT;
@@ -0,0 +1,2 @@
+-- Difficulty of the share a mining share quote was created for
+ALTER TABLE mint_quote ADD COLUMN difficulty INTEGER;
@@ -26,6 +26,7 @@ use cdk_common::mint::{
     self, IncomingPayment, Issuance, MeltPaymentRequest, MeltQuote, MintKeySetInfo, MintQuote,
 };
 use cdk_common::nut00::ProofsMethods;
+use cdk_common::nutXX::MiningShareStats;
 use cdk_common::payment::PaymentIdentifier;
 use cdk_common::quote_id::QuoteId;
 use cdk_common::secret::Secret;
@@ -62,12 +63,17 @@ pub use auth::SQLMintAuthDatabase;
 use cdk_prometheus::METRICS;
 
 /// Mint SQL Database
+///
+/// Transactions always run on the primary pool. Replica reads, as classified in
+/// [`cdk_common::database::mint`], run on the read pool, which is the primary
+/// pool unless a replica is set with [`SQLMintDatabase::with_read_replica`].
 #[derive(Debug, Clone)]
 pub struct SQLMintDatabase<RM>
 where
     RM: DatabasePool + 'static,
 {
     pool: Arc<Pool<RM>>,
+    read_pool: Arc<Pool<RM>>,
 }
 
 /// SQL Transaction Writer
@@ -136,7 +142,32 @@ where
 
         Self::migrate(pool.get().map_err(|e| Error::Database(Box::new(e)))?).await?;
 
-        Ok(Self { pool })
+        Ok(Self {
+            read_pool: pool.clone(),
+            pool,
+        })
+    }
+
+    /// Serve replica reads from a read replica
+    ///
+    /// The replica is not migrated, its schema is expected to follow the
+    /// primary. Replica reads may return data older than the last committed
+    /// transaction; anything that has to read its own writes does so inside a
+    /// transaction, which always uses the primary.
+    pub fn with_read_replica<X>(mut self, db: X) -> Self
+    where
+        X: Into<RM::Config>,
+    {
+        self.read_pool = Pool::new(db.into());
+        self
+    }
+
+    /// Connection for a replica read
+    #[inline(always)]
+    fn read_conn(&self) -> Result<PooledResource<RM>, Error> {
+        self.read_pool
+            .get()
+            .map_err(|e| Error::Database(Box::new(e)))
     }
 
     /// Migrate
@@ -152,7 +183,7 @@ where
     where
         R: serde::de::DeserializeOwned,
     {
-        let conn = self.pool.get().map_err(|e| Error::Database(Box::new(e)))?;
+        let conn = self.read_conn()?;
         let value = column_as_string!(query(r#"SELECT value FROM config WHERE id = :id LIMIT 1"#)?
             .bind("id", id.to_owned())
             .pluck(&*conn)
@@ -473,7 +504,7 @@ where
     }
 
     async fn get_active_keyset_id(&self, unit: &CurrencyUnit) -> Result<Option<Id>, Self::Err> {
-        let conn = self.pool.get().map_err(|e| Error::Database(Box::new(e)))?;
+        let conn = self.read_conn()?;
         Ok(
             query(r#" SELECT id FROM keyset WHERE active = :active AND unit = :unit"#)?
                 .bind("active", true)
@@ -490,7 +521,7 @@ where
     }
 
     async fn get_active_keysets(&self) -> Result<HashMap<CurrencyUnit, Id>, Self::Err> {
-        let conn = self.pool.get().map_err(|e| Error::Database(Box::new(e)))?;
+        let conn = self.read_conn()?;
         Ok(
             query(r#"SELECT id, unit FROM keyset WHERE active = :active"#)?
                 .bind("active", true)
@@ -508,7 +539,7 @@ where
     }
 
     async fn get_keyset_info(&self, id: &Id) -> Result<Option<MintKeySetInfo>, Self::Err> {
-        let conn = self.pool.get().map_err(|e| Error::Database(Box::new(e)))?;
+        let conn = self.read_conn()?;
         Ok(query(
             r#"SELECT
                 id,
@@ -533,7 +564,7 @@ where
     }
 
     async fn get_keyset_infos(&self) -> Result<Vec<MintKeySetInfo>, Self::Err> {
-        let conn = self.pool.get().map_err(|e| Error::Database(Box::new(e)))?;
+        let conn = self.read_conn()?;
         Ok(query(
             r#"SELECT
                 id,
@@ -754,10 +785,10 @@ VALUES (:quote_id, :amount, :timestamp);
         query(
             r#"
                 INSERT INTO mint_quote (
-                id, amount, unit, request, expiry, request_lookup_id, pubkey, created_time, payment_method, request_lookup_id_kind, keyset_id
+                id, amount, unit, request, expiry, request_lookup_id, pubkey, created_time, payment_method, request_lookup_id_kind, keyset_id, converted_to, cancelled_at, difficulty
                 )
                 VALUES (
-                :id, :amount, :unit, :request, :expiry, :request_lookup_id, :pubkey, :created_time, :payment_method, :request_lookup_id_kind, :keyset_id
+                :id, :amount, :unit, :request, :expiry, :request_lookup_id, :pubkey, :created_time, :payment_method, :request_lookup_id_kind, :keyset_id, :converted_to, :cancelled_at, :difficulty
                 )
             "#,
         )?
@@ -775,6 +806,9 @@ VALUES (:quote_id, :amount, :timestamp);
         .bind("payment_method", quote.payment_method.to_string())
         .bind("request_lookup_id_kind", quote.request_lookup_id.kind())
         .bind("keyset_id", quote.keyset_id.map(|k| k.to_string()))
+        .bind("converted_to", quote.converted_to.map(|q| q.to_string()))
+        .bind("cancelled_at", quote.cancelled_at.map(|t| t as i64))
+        .bind("difficulty", quote.difficulty.map(|d| d as i64))
         .execute(&self.inner)
         .await?;
 
@@ -782,6 +816,11 @@ VALUES (:quote_id, :amount, :timestamp);
     }
 
     async fn remove_mint_quote(&mut self, quote_id: &QuoteId) -> Result<(), Self::Err> {
+        // Free the payment ids so the payments can be recorded on a new quote
+        query(r#"DELETE FROM mint_quote_payments WHERE quote_id=:id"#)?
+            .bind("id", quote_id.to_string())
+            .execute(&self.inner)
+            .await?;
         query(r#"DELETE FROM mint_quote WHERE id=:id"#)?
             .bind("id", quote_id.to_string())
             .execute(&self.inner)
@@ -789,6 +828,90 @@ VALUES (:quote_id, :amount, :timestamp);
         Ok(())
     }
 
+    async fn remove_expired_mining_share_quotes(&mut self, now: u64) -> Result<u64, Self::Err> {
+        // Free the payment ids so the shares can be quoted again
+        query(
+            r#"
+            DELETE FROM mint_quote_payments
+            WHERE quote_id IN (
+                SELECT id
+                FROM mint_quote
+                WHERE payment_method = :payment_method
+                AND expiry != 0
+                AND expiry <= :now
+                AND amount_issued = 0
+            )
+            "#,
+        )?
+        .bind("payment_method", PaymentMethod::MiningShare.to_string())
+        .bind("now", now as i64)
+        .execute(&self.inner)
+        .await?;
+
+        let removed = query(
+            r#"
+            DELETE FROM mint_quote
+            WHERE payment_method = :payment_method
+            AND expiry != 0
+            AND expiry <= :now
+            AND amount_issued = 0
+            "#,
+        )?
+        .bind("payment_method", PaymentMethod::MiningShare.to_string())
+        .bind("now", now as i64)
+        .execute(&self.inner)
+        .await?;
+
+        Ok(removed as u64)
+    }
+
+    async fn convert_mint_quote(
+        &mut self,
+        quote_id: &QuoteId,
+        converted_to: &QuoteId,
+    ) -> Result<(), Self::Err> {
+        let updated = query(
+            r#"
+            UPDATE mint_quote
+            SET converted_to = :converted_to
+            WHERE id = :id AND converted_to IS NULL AND cancelled_at IS NULL AND amount_paid = 0
+            "#,
+        )?
+        .bind("converted_to", converted_to.to_string())
+        .bind("id", quote_id.to_string())
+        .execute(&self.inner)
+        .await?;
+
+        if updated == 0 {
+            return Err(Error::QuoteNotFound);
+        }
+
+        Ok(())
+    }
+
+    async fn cancel_mint_quote(&mut self, quote_id: &QuoteId, now: u64) -> Result<(), Self::Err> {
+        let updated = query(
+            r#"
+            UPDATE mint_quote
+            SET cancelled_at = :cancelled_at
+            WHERE id = :id
+            AND cancelled_at IS NULL
+            AND converted_to IS NULL
+            AND amount_issued = 0
+            "#,
+        )?
+        .bind("cancelled_at", now as i64)
+        .bind("id", quote_id.to_string())
+        .execute(&self.inner)
+        .await?;
+
+        if updated == 0 {
+            return Err(Error::QuoteNotFound);
+        }
+
+        Ok(())
+    }
+
     async fn add_melt_quote(&mut self, quote: mint::MeltQuote) -> Result<(), Self::Err> {
         // First try to find and replace any expired UNPAID quotes with the same request_lookup_id
 
@@ -954,7 +1077,10 @@ VALUES (:quote_id, :amount, :timestamp);
                 amount_issued,
                 payment_method,
                 request_lookup_id_kind,
-                keyset_id
+                keyset_id,
+                converted_to,
+                cancelled_at,
+                difficulty
             FROM
                 mint_quote
             WHERE id = :id
@@ -1021,7 +1147,10 @@ VALUES (:quote_id, :amount, :timestamp);
                 amount_issued,
                 payment_method,
                 request_lookup_id_kind,
-                keyset_id
+                keyset_id,
+                converted_to,
+                cancelled_at,
+                difficulty
             FROM
                 mint_quote
             WHERE request = :request
@@ -1063,7 +1192,10 @@ VALUES (:quote_id, :amount, :timestamp);
                 amount_issued,
                 payment_method,
                 request_lookup_id_kind,
-                keyset_id
+                keyset_id,
+                converted_to,
+                cancelled_at,
+                difficulty
             FROM
                 mint_quote
             WHERE request_lookup_id = :request_lookup_id
@@ -1087,6 +1219,36 @@ VALUES (:quote_id, :amount, :timestamp);
 
         Ok(mint_quote)
     }
+
+    #[instrument(skip(self))]
+    async fn count_pending_mint_quotes_by_pubkey(
+        &mut self,
+        pubkey: &PublicKey,
+        now: u64,
+    ) -> Result<u64, Self::Err> {
+        let count = query(
+            r#"
+            SELECT
+                COUNT(*)
+            FROM
+                mint_quote
+            WHERE pubkey = :pubkey
+            AND converted_to IS NULL
+            AND cancelled_at IS NULL
+            AND expiry > :now
+            AND (amount_paid = 0 OR amount_issued < amount_paid)
+            "#,
+        )?
+        .bind("pubkey", pubkey.to_string())
+        .bind("now", now as i64)
+        .pluck(&self.inner)
+        .await?
+        .map(|count| Ok::<u64, Error>(column_as_number!(count)))
+        .transpose()?
+        .unwrap_or_default();
+
+        Ok(count)
+    }
 }
 
 #[async_trait]
@@ -1102,7 +1264,7 @@ where
 
         #[cfg(feature = "prometheus")]
         let start_time = std::time::Instant::now();
-        let conn = self.pool.get().map_err(|e| Error::Database(Box::new(e)))?;
+        let conn = self.read_conn()?;
 
         let result: Result<Option<MintQuote>, Error> = async {
             let payments = get_mint_quote_payments(&*conn, quote_id).await?;
@@ -1123,7 +1285,10 @@ where
                     amount_issued,
                     payment_method,
                     request_lookup_id_kind,
-                    keyset_id
+                    keyset_id,
+                    converted_to,
+                    cancelled_at,
+                    difficulty
                 FROM
                     mint_quote
                 WHERE id = :id"#,
@@ -1158,7 +1323,7 @@ where
         &self,
         request: &str,
     ) -> Result<Option<MintQuote>, Self::Err> {
-        let conn = self.pool.get().map_err(|e| Error::Database(Box::new(e)))?;
+        let conn = self.read_conn()?;
         let mut mint_quote = query(
             r#"
             SELECT
@@ -1174,7 +1339,10 @@ where
                 amount_issued,
                 payment_method,
                 request_lookup_id_kind,
-                keyset_id
+                keyset_id,
+                converted_to,
+                cancelled_at,
+                difficulty
             FROM
                 mint_quote
             WHERE request = :request"#,
@@ -1199,7 +1367,7 @@ where
         &self,
         request_lookup_id: &PaymentIdentifier,
     ) -> Result<Option<MintQuote>, Self::Err> {
-        let conn = self.pool.get().map_err(|e| Error::Database(Box::new(e)))?;
+        let conn = self.read_conn()?;
         let mut mint_quote = query(
             r#"
             SELECT
@@ -1215,7 +1383,10 @@ where
                 amount_issued,
                 payment_method,
                 request_lookup_id_kind,
-                keyset_id
+                keyset_id,
+                converted_to,
+                cancelled_at,
+                difficulty
             FROM
                 mint_quote
             WHERE request_lookup_id = :request_lookup_id
@@ -1241,7 +1412,7 @@ where
     }
 
     async fn get_mint_quotes(&self) -> Result<Vec<MintQuote>, Self::Err> {
-        let conn = self.pool.get().map_err(|e| Error::Database(Box::new(e)))?;
+        let conn = self.read_conn()?;
         let mut mint_quotes = query(
             r#"
             SELECT
@@ -1257,7 +1428,10 @@ where
                 amount_issued,
                 payment_method,
                 request_lookup_id_kind,
-                keyset_id
+                keyset_id,
+                converted_to,
+                cancelled_at,
+                difficulty
             FROM
                 mint_quote
             "#,
@@ -1278,6 +1452,58 @@ where
         Ok(mint_quotes)
     }
 
+    async fn mining_share_stats(
+        &self,
+        since: u64,
+        until: u64,
+    ) -> Result<MiningShareStats, Self::Err> {
+        let conn = self.read_conn()?;
+        let Some(row) = query(
+            r#"
+            SELECT
+                COUNT(*),
+                COUNT(CASE WHEN amount_issued > 0 THEN 1 END),
+                CAST(COALESCE(SUM(amount), 0) AS BIGINT),
+                CAST(COALESCE(SUM(amount_issued), 0) AS BIGINT),
+                COUNT(DISTINCT pubkey)
+            FROM
+                mint_quote
+            WHERE payment_method = :payment_method
+            AND created_time >= :since
+            AND created_time <= :until
+            "#,
+        )?
+        .bind("payment_method", PaymentMethod::MiningShare.to_string())
+        .bind("since", since as i64)
+        .bind("until", until as i64)
+        .fetch_one(&*conn)
+        .await?
+        else {
+            return Ok(MiningShareStats::default());
+        };
+
+        unpack_into!(
+            let (
+                quotes_created,
+                quotes_issued,
+                amount_quoted,
+                amount_issued,
+                distinct_pubkeys
+            ) = row
+        );
+
+        let amount_quoted: u64 = column_as_number!(amount_quoted);
+        let amount_issued: u64 = column_as_number!(amount_issued);
+
+        Ok(MiningShareStats {
+            quotes_created: column_as_number!(quotes_created),
+            quotes_issued: column_as_number!(quotes_issued),
+            amount_quoted: Amount::from(amount_quoted),
+            amount_issued: Amount::from(amount_issued),
+            distinct_pubkeys: column_as_number!(distinct_pubkeys),
+        })
+    }
+
     async fn get_melt_quote(
         &self,
         quote_id: &QuoteId,
@@ -1287,7 +1513,7 @@ where
 
         #[cfg(feature = "prometheus")]
         let start_time = std::time::Instant::now();
-        let conn = self.pool.get().map_err(|e| Error::Database(Box::new(e)))?;
+        let conn = self.read_conn()?;
 
         let result = async {
             query(
@@ -1338,7 +1564,7 @@ where
     }
 
     async fn get_melt_quotes(&self) -> Result<Vec<mint::MeltQuote>, Self::Err> {
-        let conn = self.pool.get().map_err(|e| Error::Database(Box::new(e)))?;
+        let conn = self.read_conn()?;
         Ok(query(
             r#"
             SELECT
@@ -1376,7 +1602,7 @@ where
     type Err = Error;
 
     async fn get_proofs_by_ys(&self, ys: &[PublicKey]) -> Result<Vec<Option<Proof>>, Self::Err> {
-        let conn = self.pool.get().map_err(|e| Error::Database(Box::new(e)))?;
+        let conn = self.read_conn()?;
         let mut proofs = query(
             r#"
             SELECT
@@ -1415,7 +1641,7 @@ where
         &self,
         quote_id: &QuoteId,
     ) -> Result<Vec<PublicKey>, Self::Err> {
-        let conn = self.pool.get().map_err(|e| Error::Database(Box::new(e)))?;
+        let conn = self.read_conn()?;
         Ok(query(
             r#"
             SELECT
@@ -1440,7 +1666,7 @@ where
     }
 
     async fn get_proofs_states(&self, ys: &[PublicKey]) -> Result<Vec<Option<State>>, Self::Err> {
-        let conn = self.pool.get().map_err(|e| Error::Database(Box::new(e)))?;
+        let conn = self.read_conn()?;
         let mut current_states = get_current_states(&*conn, ys).await?;
 
         Ok(ys.iter().map(|y| current_states.remove(y)).collect())
@@ -1450,7 +1676,7 @@ where
         &self,
         keyset_id: &Id,
     ) -> Result<(Proofs, Vec<Option<State>>), Self::Err> {
-        let conn = self.pool.get().map_err(|e| Error::Database(Box::new(e)))?;
+        let conn = self.read_conn()?;
         Ok(query(
             r#"
             SELECT
@@ -1581,7 +1807,7 @@ where
         &self,
         blinded_messages: &[PublicKey],
     ) -> Result<Vec<Option<BlindSignature>>, Self::Err> {
-        let conn = self.pool.get().map_err(|e| Error::Database(Box::new(e)))?;
+        let conn = self.read_conn()?;
         let mut blinded_signatures = query(
             r#"SELECT
                 keyset_id,
@@ -1626,7 +1852,7 @@ where
         &self,
         keyset_id: &Id,
     ) -> Result<Vec<BlindSignature>, Self::Err> {
-        let conn = self.pool.get().map_err(|e| Error::Database(Box::new(e)))?;
+        let conn = self.read_conn()?;
         Ok(query(
             r#"
             SELECT
@@ -1654,7 +1880,7 @@ where
         &self,
         quote_id: &QuoteId,
     ) -> Result<Vec<BlindSignature>, Self::Err> {
-        let conn = self.pool.get().map_err(|e| Error::Database(Box::new(e)))?;
+        let conn = self.read_conn()?;
         Ok(query(
             r#"
             SELECT
@@ -1821,7 +2047,7 @@ where
         // Validate parameters according to KV store requirements
         validate_kvstore_params(primary_namespace, secondary_namespace, key)?;
 
-        let conn = self.pool.get().map_err(|e| Error::Database(Box::new(e)))?;
+        let conn = self.read_conn()?;
         Ok(query(
             r#"
             SELECT value
@@ -1858,7 +2084,7 @@ where
             ));
         }
 
-        let conn = self.pool.get().map_err(|e| Error::Database(Box::new(e)))?;
+        let conn = self.read_conn()?;
         Ok(query(
             r#"
             SELECT key
@@ -2009,7 +2235,8 @@ fn sql_row_to_mint_quote(
     unpack_into!(
         let (
             id, amount, unit, request, expiry, request_lookup_id,
-            pubkey, created_time, amount_paid, amount_issued, payment_method, request_lookup_id_kind, keyset_id
+            pubkey, created_time, amount_paid, amount_issued, payment_method, request_lookup_id_kind, keyset_id,
+            converted_to, cancelled_at, difficulty
         ) = row
     );
 
@@ -2033,8 +2260,11 @@ fn sql_row_to_mint_quote(
     let keyset_id = column_as_nullable_string!(keyset_id)
         .map(|k| Id::from_str(&k))
         .transpose()?;
+    let converted_to = column_as_nullable_string!(converted_to)
+        .map(|q| QuoteId::from_str(&q))
+        .transpose()?;
 
-    Ok(MintQuote::new(
+    let mut quote = MintQuote::new(
         Some(QuoteId::from_str(&id)?),
         request_str,
         column_as_string!(unit, CurrencyUnit::from_str),
@@ -2050,7 +2280,12 @@ fn sql_row_to_mint_quote(
         payments,
         issueances,
         keyset_id,
-    ))
+    );
+    quote.converted_to = converted_to;
+    quote.cancelled_at = column_as_nullable_number!(cancelled_at);
+    quote.difficulty = column_as_nullable_number!(difficulty);
+
+    Ok(quote)
 }
 
 fn sql_row_to_melt_quote(row: Vec<Column>) -> Result<mint::MeltQuote, Error> {
@@ -4,6 +4,15 @@ pub static MIGRATIONS: &[(&str, &str, &str)] = &[
     ("postgres", "1_initial.sql", include_str!(r#"./migrations/postgres/1_initial.sql"#)),
     ("postgres", "20250831215438_melt_quote_method.sql", include_str!(r#"./migrations/postgres/20250831215438_melt_quote_method.sql"#)),
     ("postgres", "20250906200000_add_transaction_quote_id.sql", include_str!(r#"./migrations/postgres/20250906200000_add_transaction_quote_id.sql"#)),
+    ("postgres", "20250910120000_add_converted_from_mint_quote.sql", include_str!(r#"./migrations/postgres/20250910120000_add_converted_from_mint_quote.sql"#)),
+    ("postgres", "20250915120000_restore_progress.sql", include_str!(r#"./migrations/postgres/20250915120000_restore_progress.sql"#)),
+    ("postgres", "20250920120000_dead_letters.sql", include_str!(r#"./migrations/postgres/20250920120000_dead_letters.sql"#)),
+    ("postgres", "20250925120000_mint_quote_premint_counter.sql", include_str!(r#"./migrations/postgres/20250925120000_mint_quote_premint_counter.sql"#)),
+    ("postgres", "20250930120000_proof_source.sql", include_str!(r#"./migrations/postgres/20250930120000_proof_source.sql"#)),
+    ("postgres", "20251005120000_pending_mint_operations.sql", include_str!(r#"./migrations/postgres/20251005120000_pending_mint_operations.sql"#)),
+    ("postgres", "20251010120000_mint_quote_premint_amounts.sql", include_str!(r#"./migrations/postgres/20251010120000_mint_quote_premint_amounts.sql"#)),
+    ("postgres", "20251015120000_dead_letter_mint_url.sql", include_str!(r#"./migrations/postgres/20251015120000_dead_letter_mint_url.sql"#)),
+    ("postgres", "20251020120000_wallet_snapshots.sql", include_str!(r#"./migrations/postgres/20251020120000_wallet_snapshots.sql"#)),
     ("sqlite", "1_fix_sqlx_migration.sql", include_str!(r#"./migrations/sqlite/1_fix_sqlx_migration.sql"#)),
     ("sqlite", "20240612132920_init.sql", include_str!(r#"./migrations/sqlite/20240612132920_init.sql"#)),
     ("sqlite", "20240618200350_quote_state.sql", include_str!(r#"./migrations/sqlite/20240618200350_quote_state.sql"#)),
@@ -27,4 +36,13 @@ pub static MIGRATIONS: &[(&str, &str, &str)] = &[
     ("sqlite", "20250820042069_add_keyset_id_to_mint_quote.sql", include_str!(r#"./migrations/sqlite/20250820042069_add_keyset_id_to_mint_quote.sql"#)),
     ("sqlite", "20250831215438_melt_quote_method.sql", include_str!(r#"./migrations/sqlite/20250831215438_melt_quote_method.sql"#)),
     ("sqlite", "20250906200000_add_transaction_quote_id.sql", include_str!(r#"./migrations/sqlite/20250906200000_add_transaction_quote_id.sql"#)),
+    ("sqlite", "20250910120000_add_converted_from_mint_quote.sql", include_str!(r#"./migrations/sqlite/20250910120000_add_converted_from_mint_quote.sql"#)),
+    ("sqlite", "20250915120000_restore_progress.sql", include_str!(r#"./migrations/sqlite/20250915120000_restore_progress.sql"#)),
+    ("sqlite", "20250920120000_dead_letters.sql", include_str!(r#"./migrations/sqlite/20250920120000_dead_letters.sql"#)),
+    ("sqlite", "20250925120000_mint_quote_premint_counter.sql", include_str!(r#"./migrations/sqlite/20250925120000_mint_quote_premint_counter.sql"#)),
+    ("sqlite", "20250930120000_proof_source.sql", include_str!(r#"./migrations/sqlite/20250930120000_proof_source.sql"#)),
+    ("sqlite", "20251005120000_pending_mint_operations.sql", include_str!(r#"./migrations/sqlite/20251005120000_pending_mint_operations.sql"#)),
+    ("sqlite", "20251010120000_mint_quote_premint_amounts.sql", include_str!(r#"./migrations/sqlite/20251010120000_mint_quote_premint_amounts.sql"#)),
+    ("sqlite", "20251015120000_dead_letter_mint_url.sql", include_str!(r#"./migrations/sqlite/20251015120000_dead_letter_mint_url.sql"#)),
+    ("sqlite", "20251020120000_wallet_snapshots.sql", include_str!(r#"./migrations/sqlite/20251020120000_wallet_snapshots.sql"#)),
 ];
@@ -0,0 +1,9 @@
+-- Progress of seed restores per keyset
+CREATE TABLE IF NOT EXISTS restore_progress (
+    keyset_id TEXT PRIMARY KEY,
+    next_counter INTEGER NOT NULL,
+    gap INTEGER NOT NULL,
+    signatures_found INTEGER NOT NULL,
+    amount_recovered INTEGER NOT NULL,
+    complete BOOL NOT NULL
+);
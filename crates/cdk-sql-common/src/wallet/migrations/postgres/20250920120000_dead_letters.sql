@@ -0,0 +1,8 @@
+-- Notifications the wallet could not apply
+CREATE TABLE IF NOT EXISTS dead_letter (
+    id BIGINT PRIMARY KEY,
+    sub_id TEXT,
+    payload TEXT NOT NULL,
+    reason TEXT NOT NULL,
+    timestamp BIGINT NOT NULL
+);
@@ -0,0 +1,3 @@
+-- Premint secrets of an in-flight mint, kept to resubmit the same outputs on retry
+ALTER TABLE mint_quote ADD COLUMN premint_keyset_id TEXT;
+ALTER TABLE mint_quote ADD COLUMN premint_counter INTEGER;
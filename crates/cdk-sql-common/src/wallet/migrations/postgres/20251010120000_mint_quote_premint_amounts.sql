@@ -0,0 +1,2 @@
+-- Output amounts of an in-flight mint, so a retry derives the same outputs
+ALTER TABLE mint_quote ADD COLUMN premint_amounts TEXT;
@@ -0,0 +1,2 @@
+-- Quote a mint quote was converted from
+ALTER TABLE mint_quote ADD COLUMN converted_from TEXT;
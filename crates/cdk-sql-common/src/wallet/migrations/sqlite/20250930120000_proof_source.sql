@@ -0,0 +1,2 @@
+-- How each proof was received, NULL for proofs stored before it was recorded
+ALTER TABLE proof ADD COLUMN source TEXT;
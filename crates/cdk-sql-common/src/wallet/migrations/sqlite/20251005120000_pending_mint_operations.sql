@@ -0,0 +1,13 @@
+-- Mint requests that could not reach the mint
+CREATE TABLE IF NOT EXISTS pending_mint_operation (
+    id TEXT PRIMARY KEY,
+    quote_id TEXT NOT NULL,
+    mint_url TEXT NOT NULL,
+    payment_method TEXT NOT NULL,
+    unit TEXT NOT NULL,
+    keyset_id TEXT NOT NULL,
+    request TEXT NOT NULL,
+    premint_secrets TEXT NOT NULL,
+    expiry INTEGER NOT NULL,
+    created_time INTEGER NOT NULL
+);
@@ -0,0 +1,12 @@
+-- Dead letters are scoped by mint and numbered by the database. Stored ones
+-- cannot be attributed to a mint and are dropped.
+DROP TABLE IF EXISTS dead_letter;
+CREATE TABLE dead_letter (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    mint_url TEXT NOT NULL,
+    sub_id TEXT,
+    payload TEXT NOT NULL,
+    reason TEXT NOT NULL,
+    timestamp INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS dead_letter_mint_url_index ON dead_letter(mint_url);
@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS wallet_snapshot (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    mint_url TEXT NOT NULL,
+    unit TEXT NOT NULL,
+    label TEXT NOT NULL,
+    created_at INTEGER NOT NULL,
+    content TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS wallet_snapshot_mint_url_unit_index ON wallet_snapshot(mint_url, unit);
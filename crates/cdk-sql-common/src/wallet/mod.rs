@@ -6,12 +6,15 @@ use std::str::FromStr;
 use std::sync::Arc;
 
 use async_trait::async_trait;
-use cdk_common::common::ProofInfo;
+use cdk_common::common::{ProofInfo, ProofSource};
 use cdk_common::database::{ConversionError, Error, WalletDatabase};
 use cdk_common::mint_url::MintUrl;
 use cdk_common::nuts::{MeltQuoteState, MintQuoteState};
 use cdk_common::secret::Secret;
-use cdk_common::wallet::{self, MintQuote, Transaction, TransactionDirection, TransactionId};
+use cdk_common::wallet::{
+    self, DeadLetter, DeadLetterReason, MintQuote, PendingMintOperation, RestoreProgress,
+    Transaction, TransactionDirection, TransactionId, WalletSnapshot,
+};
 use cdk_common::{
     database, Amount, CurrencyUnit, Id, KeySet, KeySetInfo, Keys, MintInfo, PaymentMethod, Proof,
     ProofDleq, PublicKey, SecretKey, SpendingConditions, State,
@@ -477,9 +480,9 @@ ON CONFLICT(mint_url) DO UPDATE SET
         query(
             r#"
 INSERT INTO mint_quote
-(id, mint_url, amount, unit, request, state, expiry, secret_key, payment_method, amount_issued, amount_paid, keyset_id)
+(id, mint_url, amount, unit, request, state, expiry, secret_key, payment_method, amount_issued, amount_paid, keyset_id, converted_from, premint_keyset_id, premint_counter, premint_amounts)
 VALUES
-(:id, :mint_url, :amount, :unit, :request, :state, :expiry, :secret_key, :payment_method, :amount_issued, :amount_paid, :keyset_id)
+(:id, :mint_url, :amount, :unit, :request, :state, :expiry, :secret_key, :payment_method, :amount_issued, :amount_paid, :keyset_id, :converted_from, :premint_keyset_id, :premint_counter, :premint_amounts)
 ON CONFLICT(id) DO UPDATE SET
     mint_url = excluded.mint_url,
     amount = excluded.amount,
@@ -491,7 +494,11 @@ ON CONFLICT(id) DO UPDATE SET
     payment_method = excluded.payment_method,
     amount_issued = excluded.amount_issued,
     amount_paid = excluded.amount_paid,
-    keyset_id = excluded.keyset_id
+    keyset_id = excluded.keyset_id,
+    converted_from = excluded.converted_from,
+    premint_keyset_id = excluded.premint_keyset_id,
+    premint_counter = excluded.premint_counter,
+    premint_amounts = excluded.premint_amounts
 ;
         "#,
         )?
@@ -507,6 +514,17 @@ ON CONFLICT(id) DO UPDATE SET
         .bind("amount_issued", quote.amount_issued.to_i64())
         .bind("amount_paid", quote.amount_paid.to_i64())
         .bind("keyset_id", quote.keyset_id.map(|k| k.to_string()))
+        .bind("converted_from", quote.converted_from)
+        .bind("premint_keyset_id", quote.premint_keyset_id.map(|k| k.to_string()))
+        .bind("premint_counter", quote.premint_counter.map(|c| c as i64))
+        .bind(
+            "premint_amounts",
+            quote
+                .premint_amounts
+                .map(|amounts| serde_json::to_string(&amounts))
+                .transpose()
+                .map_err(Error::from)?,
+        )
         .execute(&*conn).await?;
 
         Ok(())
@@ -529,7 +547,11 @@ ON CONFLICT(id) DO UPDATE SET
                 payment_method,
                 amount_issued,
                 amount_paid,
-                keyset_id
+                keyset_id,
+                converted_from,
+                premint_keyset_id,
+                premint_counter,
+                premint_amounts
             FROM
                 mint_quote
             WHERE
@@ -560,7 +582,11 @@ ON CONFLICT(id) DO UPDATE SET
                 payment_method,
                 amount_issued,
                 amount_paid,
-                keyset_id
+                keyset_id,
+                converted_from,
+                premint_keyset_id,
+                premint_counter,
+                premint_amounts
             FROM
                 mint_quote
             "#,
@@ -729,9 +755,9 @@ ON CONFLICT(id) DO UPDATE SET
             query(
                 r#"
     INSERT INTO proof
-    (y, mint_url, state, spending_condition, unit, amount, keyset_id, secret, c, witness, dleq_e, dleq_s, dleq_r)
+    (y, mint_url, state, spending_condition, unit, amount, keyset_id, secret, c, witness, dleq_e, dleq_s, dleq_r, source)
     VALUES
-    (:y, :mint_url, :state, :spending_condition, :unit, :amount, :keyset_id, :secret, :c, :witness, :dleq_e, :dleq_s, :dleq_r)
+    (:y, :mint_url, :state, :spending_condition, :unit, :amount, :keyset_id, :secret, :c, :witness, :dleq_e, :dleq_s, :dleq_r, :source)
     ON CONFLICT(y) DO UPDATE SET
         mint_url = excluded.mint_url,
         state = excluded.state,
@@ -744,7 +770,8 @@ ON CONFLICT(id) DO UPDATE SET
         witness = excluded.witness,
         dleq_e = excluded.dleq_e,
         dleq_s = excluded.dleq_s,
-        dleq_r = excluded.dleq_r
+        dleq_r = excluded.dleq_r,
+        source = excluded.source
     ;
             "#,
             )?
@@ -781,6 +808,7 @@ ON CONFLICT(id) DO UPDATE SET
                 "dleq_r",
                 proof.proof.dleq.as_ref().map(|dleq| dleq.r.to_secret_bytes().to_vec()),
             )
+            .bind("source", proof.source.to_string())
             .execute(&tx).await?;
         }
 
@@ -821,7 +849,8 @@ ON CONFLICT(id) DO UPDATE SET
                 y,
                 mint_url,
                 state,
-                spending_condition
+                spending_condition,
+                source
             FROM proof
         "#,
         )?
@@ -961,6 +990,334 @@ ON CONFLICT(id) DO UPDATE SET
         Ok(new_counter)
     }
 
+    #[instrument(skip(self))]
+    async fn add_restore_progress(&self, progress: RestoreProgress) -> Result<(), Self::Err> {
+        let conn = self.pool.get().map_err(|e| Error::Database(Box::new(e)))?;
+
+        query(
+            r#"
+INSERT INTO restore_progress
+(keyset_id, next_counter, gap, signatures_found, amount_recovered, complete)
+VALUES
+(:keyset_id, :next_counter, :gap, :signatures_found, :amount_recovered, :complete)
+ON CONFLICT(keyset_id) DO UPDATE SET
+    next_counter = excluded.next_counter,
+    gap = excluded.gap,
+    signatures_found = excluded.signatures_found,
+    amount_recovered = excluded.amount_recovered,
+    complete = excluded.complete
+;
+        "#,
+        )?
+        .bind("keyset_id", progress.keyset_id.to_string())
+        .bind("next_counter", progress.next_counter)
+        .bind("gap", progress.gap)
+        .bind("signatures_found", progress.signatures_found)
+        .bind(
+            "amount_recovered",
+            u64::from(progress.amount_recovered) as i64,
+        )
+        .bind("complete", progress.complete)
+        .execute(&*conn)
+        .await?;
+
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn get_restore_progress(
+        &self,
+        keyset_id: &Id,
+    ) -> Result<Option<RestoreProgress>, Self::Err> {
+        let conn = self.pool.get().map_err(|e| Error::Database(Box::new(e)))?;
+        Ok(query(
+            r#"
+            SELECT
+                keyset_id,
+                next_counter,
+                gap,
+                signatures_found,
+                amount_recovered,
+                complete
+            FROM
+                restore_progress
+            WHERE keyset_id = :keyset_id
+            "#,
+        )?
+        .bind("keyset_id", keyset_id.to_string())
+        .fetch_one(&*conn)
+        .await?
+        .map(sql_row_to_restore_progress)
+        .transpose()?)
+    }
+
+    #[instrument(skip(self))]
+    async fn remove_restore_progress(&self, keyset_id: &Id) -> Result<(), Self::Err> {
+        let conn = self.pool.get().map_err(|e| Error::Database(Box::new(e)))?;
+
+        query(r#"DELETE FROM restore_progress WHERE keyset_id=:keyset_id"#)?
+            .bind("keyset_id", keyset_id.to_string())
+            .execute(&*conn)
+            .await?;
+
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn add_dead_letter(&self, dead_letter: DeadLetter) -> Result<(), Self::Err> {
+        let conn = self.pool.get().map_err(|e| Error::Database(Box::new(e)))?;
+
+        query(
+            r#"
+INSERT INTO dead_letter
+(mint_url, sub_id, payload, reason, timestamp)
+VALUES
+(:mint_url, :sub_id, :payload, :reason, :timestamp)
+;
+        "#,
+        )?
+        .bind("mint_url", dead_letter.mint_url.to_string())
+        .bind("sub_id", dead_letter.sub_id)
+        .bind("payload", dead_letter.payload)
+        .bind("reason", dead_letter.reason.to_string())
+        .bind("timestamp", dead_letter.timestamp as i64)
+        .execute(&*conn)
+        .await?;
+
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn get_dead_letters(
+        &self,
+        mint_url: Option<MintUrl>,
+    ) -> Result<Vec<DeadLetter>, Self::Err> {
+        let conn = self.pool.get().map_err(|e| Error::Database(Box::new(e)))?;
+
+        Ok(query(
+            r#"
+            SELECT
+                id,
+                mint_url,
+                sub_id,
+                payload,
+                reason,
+                timestamp
+            FROM
+                dead_letter
+            ORDER BY id
+            "#,
+        )?
+        .fetch_all(&*conn)
+        .await?
+        .into_iter()
+        .map(sql_row_to_dead_letter)
+        .filter(|dead_letter| match (dead_letter, &mint_url) {
+            (Ok(dead_letter), Some(mint_url)) => dead_letter.mint_url == *mint_url,
+            _ => true,
+        })
+        .collect::<Result<Vec<_>, _>>()?)
+    }
+
+    #[instrument(skip(self))]
+    async fn remove_dead_letter(&self, id: u64) -> Result<(), Self::Err> {
+        let conn = self.pool.get().map_err(|e| Error::Database(Box::new(e)))?;
+
+        query(r#"DELETE FROM dead_letter WHERE id=:id"#)?
+            .bind("id", id as i64)
+            .execute(&*conn)
+            .await?;
+
+        Ok(())
+    }
+
+    #[instrument(skip(self, operation))]
+    async fn add_pending_mint_operation(
+        &self,
+        operation: PendingMintOperation,
+    ) -> Result<(), Self::Err> {
+        let conn = self.pool.get().map_err(|e| Error::Database(Box::new(e)))?;
+
+        query(
+            r#"
+INSERT INTO pending_mint_operation
+(id, quote_id, mint_url, payment_method, unit, keyset_id, request, premint_secrets, expiry, created_time)
+VALUES
+(:id, :quote_id, :mint_url, :payment_method, :unit, :keyset_id, :request, :premint_secrets, :expiry, :created_time)
+ON CONFLICT(id) DO UPDATE SET
+    quote_id = excluded.quote_id,
+    mint_url = excluded.mint_url,
+    payment_method = excluded.payment_method,
+    unit = excluded.unit,
+    keyset_id = excluded.keyset_id,
+    request = excluded.request,
+    premint_secrets = excluded.premint_secrets,
+    expiry = excluded.expiry,
+    created_time = excluded.created_time
+;
+        "#,
+        )?
+        .bind("id", operation.id)
+        .bind("quote_id", operation.quote_id)
+        .bind("mint_url", operation.mint_url.to_string())
+        .bind("payment_method", operation.payment_method.to_string())
+        .bind("unit", operation.unit.to_string())
+        .bind("keyset_id", operation.keyset_id.to_string())
+        .bind(
+            "request",
+            serde_json::to_string(&operation.request).map_err(Error::from)?,
+        )
+        .bind(
+            "premint_secrets",
+            serde_json::to_string(&operation.premint_secrets).map_err(Error::from)?,
+        )
+        .bind("expiry", operation.expiry as i64)
+        .bind("created_time", operation.created_time as i64)
+        .execute(&*conn)
+        .await?;
+
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn get_pending_mint_operations(&self) -> Result<Vec<PendingMintOperation>, Self::Err> {
+        let conn = self.pool.get().map_err(|e| Error::Database(Box::new(e)))?;
+
+        Ok(query(
+            r#"
+            SELECT
+                id,
+                quote_id,
+                mint_url,
+                payment_method,
+                unit,
+                keyset_id,
+                request,
+                premint_secrets,
+                expiry,
+                created_time
+            FROM
+                pending_mint_operation
+            ORDER BY created_time, id
+            "#,
+        )?
+        .fetch_all(&*conn)
+        .await?
+        .into_iter()
+        .map(sql_row_to_pending_mint_operation)
+        .collect::<Result<Vec<_>, _>>()?)
+    }
+
+    #[instrument(skip(self))]
+    async fn remove_pending_mint_operation(&self, id: &str) -> Result<(), Self::Err> {
+        let conn = self.pool.get().map_err(|e| Error::Database(Box::new(e)))?;
+
+        query(r#"DELETE FROM pending_mint_operation WHERE id=:id"#)?
+            .bind("id", id.to_string())
+            .execute(&*conn)
+            .await?;
+
+        Ok(())
+    }
+
+    #[instrument(skip(self, snapshot), fields(label = %snapshot.label))]
+    async fn add_wallet_snapshot(&self, snapshot: WalletSnapshot) -> Result<u64, Self::Err> {
+        let conn = self.pool.get().map_err(|e| Error::Database(Box::new(e)))?;
+
+        let id = query(
+            r#"
+INSERT INTO wallet_snapshot
+(mint_url, unit, label, created_at, content)
+VALUES
+(:mint_url, :unit, :label, :created_at, :content)
+RETURNING id
+;
+        "#,
+        )?
+        .bind("mint_url", snapshot.mint_url.to_string())
+        .bind("unit", snapshot.unit.to_string())
+        .bind("label", snapshot.label)
+        .bind("created_at", snapshot.created_at as i64)
+        .bind("content", serde_json::to_string(&snapshot.content)?)
+        .pluck(&*conn)
+        .await?
+        .ok_or(Error::Internal("Snapshot id not returned".to_owned()))?;
+
+        Ok(column_as_number!(id))
+    }
+
+    #[instrument(skip(self))]
+    async fn get_wallet_snapshot(&self, id: u64) -> Result<Option<WalletSnapshot>, Self::Err> {
+        let conn = self.pool.get().map_err(|e| Error::Database(Box::new(e)))?;
+
+        Ok(query(
+            r#"
+            SELECT
+                id,
+                mint_url,
+                unit,
+                label,
+                created_at,
+                content
+            FROM
+                wallet_snapshot
+            WHERE
+                id = :id
+            "#,
+        )?
+        .bind("id", id as i64)
+        .fetch_one(&*conn)
+        .await?
+        .map(sql_row_to_wallet_snapshot)
+        .transpose()?)
+    }
+
+    #[instrument(skip(self))]
+    async fn get_wallet_snapshots(
+        &self,
+        mint_url: MintUrl,
+        unit: CurrencyUnit,
+    ) -> Result<Vec<WalletSnapshot>, Self::Err> {
+        let conn = self.pool.get().map_err(|e| Error::Database(Box::new(e)))?;
+
+        Ok(query(
+            r#"
+            SELECT
+                id,
+                mint_url,
+                unit,
+                label,
+                created_at,
+                content
+            FROM
+                wallet_snapshot
+            WHERE
+                mint_url = :mint_url
+                AND unit = :unit
+            ORDER BY id
+            "#,
+        )?
+        .bind("mint_url", mint_url.to_string())
+        .bind("unit", unit.to_string())
+        .fetch_all(&*conn)
+        .await?
+        .into_iter()
+        .map(sql_row_to_wallet_snapshot)
+        .collect::<Result<_, _>>()?)
+    }
+
+    #[instrument(skip(self))]
+    async fn remove_wallet_snapshot(&self, id: u64) -> Result<(), Self::Err> {
+        let conn = self.pool.get().map_err(|e| Error::Database(Box::new(e)))?;
+
+        query(r#"DELETE FROM wallet_snapshot WHERE id=:id"#)?
+            .bind("id", id as i64)
+            .execute(&*conn)
+            .await?;
+
+        Ok(())
+    }
+
     #[instrument(skip(self))]
     async fn add_transaction(&self, transaction: Transaction) -> Result<(), Self::Err> {
         let conn = self.pool.get().map_err(|e| Error::Database(Box::new(e)))?;
@@ -1159,6 +1516,88 @@ fn sql_row_to_keyset(row: Vec<Column>) -> Result<KeySetInfo, Error> {
     })
 }
 
+fn sql_row_to_restore_progress(row: Vec<Column>) -> Result<RestoreProgress, Error> {
+    unpack_into!(
+        let (
+            keyset_id,
+            next_counter,
+            gap,
+            signatures_found,
+            amount_recovered,
+            complete
+        ) = row
+    );
+
+    let amount_recovered: u64 = column_as_number!(amount_recovered);
+
+    Ok(RestoreProgress {
+        keyset_id: column_as_string!(keyset_id, Id::from_str, Id::from_bytes),
+        next_counter: column_as_number!(next_counter),
+        gap: column_as_number!(gap),
+        signatures_found: column_as_number!(signatures_found),
+        amount_recovered: Amount::from(amount_recovered),
+        complete: matches!(complete, Column::Integer(1)),
+    })
+}
+
+fn sql_row_to_dead_letter(row: Vec<Column>) -> Result<DeadLetter, Error> {
+    unpack_into!(let (id, mint_url, sub_id, payload, reason, timestamp) = row);
+
+    Ok(DeadLetter {
+        id: column_as_number!(id),
+        mint_url: column_as_string!(mint_url, MintUrl::from_str),
+        sub_id: column_as_nullable_string!(sub_id),
+        payload: column_as_string!(payload),
+        reason: column_as_string!(reason, DeadLetterReason::from_str),
+        timestamp: column_as_number!(timestamp),
+    })
+}
+
+fn sql_row_to_wallet_snapshot(row: Vec<Column>) -> Result<WalletSnapshot, Error> {
+    unpack_into!(let (id, mint_url, unit, label, created_at, content) = row);
+
+    Ok(WalletSnapshot {
+        id: column_as_number!(id),
+        mint_url: column_as_string!(mint_url, MintUrl::from_str),
+        unit: column_as_string!(unit, CurrencyUnit::from_str),
+        label: column_as_string!(label),
+        created_at: column_as_number!(created_at),
+        content: serde_json::from_str(&column_as_string!(content))?,
+    })
+}
+
+fn sql_row_to_pending_mint_operation(row: Vec<Column>) -> Result<PendingMintOperation, Error> {
+    unpack_into!(
+        let (
+            id,
+            quote_id,
+            mint_url,
+            payment_method,
+            unit,
+            keyset_id,
+            request,
+            premint_secrets,
+            expiry,
+            created_time
+        ) = row
+    );
+
+    Ok(PendingMintOperation {
+        id: column_as_string!(id),
+        quote_id: column_as_string!(quote_id),
+        mint_url: column_as_string!(mint_url, MintUrl::from_str),
+        payment_method: PaymentMethod::from_str(&column_as_string!(payment_method))
+            .map_err(Error::from)?,
+        unit: column_as_string!(unit, CurrencyUnit::from_str),
+        keyset_id: column_as_string!(keyset_id, Id::from_str),
+        request: serde_json::from_str(&column_as_string!(request)).map_err(Error::from)?,
+        premint_secrets: serde_json::from_str(&column_as_string!(premint_secrets))
+            .map_err(Error::from)?,
+        expiry: column_as_number!(expiry),
+        created_time: column_as_number!(created_time),
+    })
+}
+
 fn sql_row_to_mint_quote(row: Vec<Column>) -> Result<MintQuote, Error> {
     unpack_into!(
         let (
@@ -1173,7 +1612,11 @@ fn sql_row_to_mint_quote(row: Vec<Column>) -> Result<MintQuote, Error> {
             row_method,
             row_amount_minted,
             row_amount_paid,
-            keyset_id
+            keyset_id,
+            converted_from,
+            premint_keyset_id,
+            premint_counter,
+            premint_amounts
         ) = row
     );
 
@@ -1186,6 +1629,13 @@ fn sql_row_to_mint_quote(row: Vec<Column>) -> Result<MintQuote, Error> {
     let keyset_id = column_as_nullable_string!(keyset_id)
         .map(|k| Id::from_str(&k))
         .transpose()?;
+    let premint_keyset_id = column_as_nullable_string!(premint_keyset_id)
+        .map(|k| Id::from_str(&k))
+        .transpose()?;
+    let premint_amounts = column_as_nullable_string!(premint_amounts)
+        .map(|amounts| serde_json::from_str(&amounts))
+        .transpose()
+        .map_err(Error::from)?;
 
     Ok(MintQuote {
         id: column_as_string!(id),
@@ -1202,6 +1652,10 @@ fn sql_row_to_mint_quote(row: Vec<Column>) -> Result<MintQuote, Error> {
         amount_issued: amount_minted.into(),
         amount_paid: amount_paid.into(),
         keyset_id,
+        converted_from: column_as_nullable_string!(converted_from),
+        premint_keyset_id,
+        premint_counter: column_as_nullable_number!(premint_counter),
+        premint_amounts,
     })
 }
 
@@ -1254,7 +1708,8 @@ fn sql_row_to_proof_info(row: Vec<Column>) -> Result<ProofInfo, Error> {
             y,
             mint_url,
             state,
-            spending_condition
+            spending_condition,
+            source
         ) = row
     );
 
@@ -1296,6 +1751,8 @@ fn sql_row_to_proof_info(row: Vec<Column>) -> Result<ProofInfo, Error> {
             |r| { serde_json::from_slice(&r).ok() }
         ),
         unit: column_as_string!(unit, CurrencyUnit::from_str),
+        source: column_as_nullable_string!(source, |s| ProofSource::from_str(&s).ok())
+            .unwrap_or_default(),
     })
 }
 
@@ -18,7 +18,10 @@ pub type MintSqliteAuthDatabase = SQLMintAuthDatabase<SqliteConnectionManager>;
 mod test {
     use std::fs::remove_file;
 
-    use cdk_common::mint_db_test;
+    use cdk_common::database::{MintDatabase, MintQuotesDatabase};
+    use cdk_common::mint::MintQuote;
+    use cdk_common::payment::PaymentIdentifier;
+    use cdk_common::{mint_db_test, CurrencyUnit, PaymentMethod};
     use cdk_sql_common::pool::Pool;
     use cdk_sql_common::stmt::query;
 
@@ -66,4 +69,110 @@ mod test {
 
         let _ = remove_file(&file);
     }
+
+    fn temp_db_file(name: &str) -> String {
+        let file = format!(
+            "{}/{}-{}.sqlite",
+            std::env::temp_dir().to_str().unwrap_or_default(),
+            name,
+            uuid::Uuid::new_v4()
+        );
+        let _ = remove_file(&file);
+        file
+    }
+
+    async fn open_db(file: &str) -> MintSqliteDatabase {
+        #[cfg(not(feature = "sqlcipher"))]
+        let db = MintSqliteDatabase::new(file).await;
+        #[cfg(feature = "sqlcipher")]
+        let db = MintSqliteDatabase::new((file, "test")).await;
+
+        db.expect("valid db")
+    }
+
+    fn new_mint_quote() -> MintQuote {
+        MintQuote::new(
+            None,
+            "".to_owned(),
+            CurrencyUnit::Sat,
+            None,
+            0,
+            PaymentIdentifier::CustomId(uuid::Uuid::new_v4().to_string()),
+            None,
+            0.into(),
+            0.into(),
+            PaymentMethod::Bolt11,
+            0,
+            vec![],
+            vec![],
+            None,
+        )
+    }
+
+    /// Replica reads go to the replica while transactions stay on the primary
+    #[tokio::test]
+    async fn read_replica_routing() {
+        let primary_file = temp_db_file("primary");
+        let replica_file = temp_db_file("replica");
+
+        // A replica that never receives the primary's writes, so every read
+        // shows which side served it
+        let replica = open_db(&replica_file).await;
+
+        #[cfg(not(feature = "sqlcipher"))]
+        let db = open_db(&primary_file)
+            .await
+            .with_read_replica(replica_file.as_str());
+        #[cfg(feature = "sqlcipher")]
+        let db = open_db(&primary_file)
+            .await
+            .with_read_replica((replica_file.as_str(), "test"));
+
+        let quote = new_mint_quote();
+        let mut tx = MintDatabase::begin_transaction(&db).await.unwrap();
+        tx.add_mint_quote(quote.clone()).await.unwrap();
+        tx.commit().await.unwrap();
+
+        // Status and lookup reads are served by the replica
+        assert!(db.get_mint_quote(&quote.id).await.unwrap().is_none());
+        assert!(db
+            .get_mint_quote_by_request_lookup_id(&quote.request_lookup_id)
+            .await
+            .unwrap()
+            .is_none());
+
+        // Issuance reads its own writes on the primary
+        let mut tx = MintDatabase::begin_transaction(&db).await.unwrap();
+        let stored = tx.get_mint_quote(&quote.id).await.unwrap();
+        assert_eq!(stored.map(|quote| quote.id), Some(quote.id.clone()));
+        tx.increment_mint_quote_amount_paid(&quote.id, 10.into(), "payment".to_owned())
+            .await
+            .unwrap();
+        let issued = tx
+            .increment_mint_quote_amount_issued(&quote.id, 10.into())
+            .await
+            .unwrap();
+        assert_eq!(issued, 10.into());
+        tx.commit().await.unwrap();
+
+        // Nothing was written to the replica
+        assert!(replica.get_mint_quote(&quote.id).await.unwrap().is_none());
+
+        let _ = remove_file(&primary_file);
+        let _ = remove_file(&replica_file);
+    }
+
+    /// Without a replica, reads and transactions share the primary pool
+    #[tokio::test]
+    async fn read_without_replica() {
+        let db = memory::empty().await.unwrap();
+
+        let quote = new_mint_quote();
+        let mut tx = MintDatabase::begin_transaction(&db).await.unwrap();
+        tx.add_mint_quote(quote.clone()).await.unwrap();
+        tx.commit().await.unwrap();
+
+        let stored = db.get_mint_quote(&quote.id).await.unwrap();
+        assert_eq!(stored.map(|quote| quote.id), Some(quote.id));
+    }
 }
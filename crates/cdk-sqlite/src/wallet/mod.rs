@@ -168,6 +168,10 @@ mod tests {
                 amount_issued: Amount::from(0),
                 amount_paid: Amount::from(0),
                 keyset_id: None,
+                converted_from: None,
+                premint_keyset_id: None,
+                premint_counter: None,
+                premint_amounts: None,
             };
 
             // Store the quote
@@ -180,4 +184,215 @@ mod tests {
             assert_eq!(retrieved.amount_paid, Amount::from(0));
         }
     }
+
+    #[tokio::test]
+    async fn test_restore_progress_read_and_write() {
+        use cdk_common::nuts::Id;
+        use cdk_common::wallet::RestoreProgress;
+        use cdk_common::Amount;
+
+        let db = super::memory::empty().await.unwrap();
+        let keyset_id = Id::from_str("00deadbeef123456").unwrap();
+
+        assert!(db.get_restore_progress(&keyset_id).await.unwrap().is_none());
+
+        let mut progress = RestoreProgress::new(keyset_id);
+        db.add_restore_progress(progress.clone()).await.unwrap();
+        assert_eq!(
+            db.get_restore_progress(&keyset_id).await.unwrap(),
+            Some(progress.clone())
+        );
+
+        progress.next_counter = 300;
+        progress.gap = 100;
+        progress.signatures_found = 42;
+        progress.amount_recovered = Amount::from(1234);
+        progress.complete = true;
+        db.add_restore_progress(progress.clone()).await.unwrap();
+        assert_eq!(
+            db.get_restore_progress(&keyset_id).await.unwrap(),
+            Some(progress)
+        );
+
+        db.remove_restore_progress(&keyset_id).await.unwrap();
+        assert!(db.get_restore_progress(&keyset_id).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_dead_letters_read_and_write() {
+        use cdk_common::mint_url::MintUrl;
+        use cdk_common::wallet::{DeadLetter, DeadLetterReason};
+
+        let mint_url = MintUrl::from_str("https://mint.example.com").unwrap();
+        let other_mint_url = MintUrl::from_str("https://other.example.com").unwrap();
+
+        let db = super::memory::empty().await.unwrap();
+        assert!(db.get_dead_letters(None).await.unwrap().is_empty());
+
+        // The store assigns ids in insertion order, starting at 1
+        let mut dead_letters: Vec<DeadLetter> = (1..4)
+            .map(|id| DeadLetter {
+                id,
+                mint_url: mint_url.clone(),
+                sub_id: (id != 1).then(|| format!("sub-{id}")),
+                payload: format!(r#"{{"quote":"{id}"}}"#),
+                reason: if id == 2 {
+                    DeadLetterReason::InvalidPayload
+                } else {
+                    DeadLetterReason::UnknownQuote
+                },
+                timestamp: 1_700_000_000 + id,
+            })
+            .collect();
+        dead_letters[1].mint_url = other_mint_url.clone();
+
+        for dead_letter in &dead_letters {
+            db.add_dead_letter(DeadLetter {
+                id: 0,
+                ..dead_letter.clone()
+            })
+            .await
+            .unwrap();
+        }
+        assert_eq!(db.get_dead_letters(None).await.unwrap(), dead_letters);
+        assert_eq!(
+            db.get_dead_letters(Some(other_mint_url)).await.unwrap(),
+            dead_letters[1..2]
+        );
+
+        db.remove_dead_letter(1).await.unwrap();
+        assert_eq!(
+            db.get_dead_letters(Some(mint_url)).await.unwrap(),
+            dead_letters[2..]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_wallet_snapshots_read_and_write() {
+        use cdk_common::mint_url::MintUrl;
+        use cdk_common::nuts::{CurrencyUnit, Id};
+        use cdk_common::wallet::{WalletSnapshot, WalletSnapshotContent};
+
+        let mint_url = MintUrl::from_str("https://mint.example.com").unwrap();
+        let keyset_id = Id::from_str("00deadbeef123456").unwrap();
+
+        let db = super::memory::empty().await.unwrap();
+
+        let mut snapshots = Vec::new();
+        for (label, unit) in [
+            ("first", CurrencyUnit::Sat),
+            ("other unit", CurrencyUnit::Usd),
+            ("second", CurrencyUnit::Sat),
+        ] {
+            let mut snapshot = WalletSnapshot {
+                id: 0,
+                mint_url: mint_url.clone(),
+                unit,
+                label: label.to_string(),
+                created_at: 1_700_000_000,
+                content: WalletSnapshotContent {
+                    counters: [(keyset_id, 7)].into_iter().collect(),
+                    ..Default::default()
+                },
+            };
+            snapshot.id = db.add_wallet_snapshot(snapshot.clone()).await.unwrap();
+            snapshots.push(snapshot);
+        }
+        assert!(snapshots.windows(2).all(|pair| pair[0].id < pair[1].id));
+
+        assert_eq!(
+            db.get_wallet_snapshot(snapshots[1].id).await.unwrap(),
+            Some(snapshots[1].clone())
+        );
+        assert_eq!(
+            db.get_wallet_snapshots(mint_url.clone(), CurrencyUnit::Sat)
+                .await
+                .unwrap(),
+            vec![snapshots[0].clone(), snapshots[2].clone()]
+        );
+
+        db.remove_wallet_snapshot(snapshots[0].id).await.unwrap();
+        assert!(db
+            .get_wallet_snapshot(snapshots[0].id)
+            .await
+            .unwrap()
+            .is_none());
+        assert_eq!(
+            db.get_wallet_snapshots(mint_url, CurrencyUnit::Sat)
+                .await
+                .unwrap(),
+            vec![snapshots[2].clone()]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_pending_mint_operations_read_and_write() {
+        use cdk_common::amount::SplitTarget;
+        use cdk_common::mint_url::MintUrl;
+        use cdk_common::nuts::{CurrencyUnit, Id, MintRequest, PreMintSecrets};
+        use cdk_common::wallet::PendingMintOperation;
+        use cdk_common::{Amount, PaymentMethod};
+
+        let db = super::memory::empty().await.unwrap();
+        assert!(db.get_pending_mint_operations().await.unwrap().is_empty());
+
+        let keyset_id = Id::from_str("00deadbeef123456").unwrap();
+        // The first two are part mints of the same quote
+        let operations: Vec<PendingMintOperation> = (0..3)
+            .map(|i| {
+                let premint_secrets =
+                    PreMintSecrets::random(keyset_id, Amount::from(21), &SplitTarget::default())
+                        .unwrap();
+                let quote_id = format!("quote-{}", i / 2);
+
+                PendingMintOperation {
+                    id: PendingMintOperation::outputs_id(&premint_secrets.blinded_messages()),
+                    quote_id: quote_id.clone(),
+                    mint_url: MintUrl::from_str("https://example.com").unwrap(),
+                    payment_method: PaymentMethod::MiningShare,
+                    unit: CurrencyUnit::Hash,
+                    keyset_id,
+                    request: MintRequest {
+                        quote: quote_id,
+                        outputs: premint_secrets.blinded_messages(),
+                        signature: Some("signature".to_string()),
+                    },
+                    premint_secrets,
+                    expiry: if i == 0 { 0 } else { 1_700_000_600 },
+                    created_time: 1_700_000_000 + i,
+                }
+            })
+            .collect();
+
+        for operation in operations.iter().rev() {
+            db.add_pending_mint_operation(operation.clone())
+                .await
+                .unwrap();
+        }
+        assert_eq!(db.get_pending_mint_operations().await.unwrap(), operations);
+
+        // An operation for the same outputs replaces the stored one
+        let mut replaced = operations[2].clone();
+        replaced.created_time += 10;
+        db.add_pending_mint_operation(replaced.clone())
+            .await
+            .unwrap();
+        assert_eq!(
+            db.get_pending_mint_operations().await.unwrap(),
+            vec![
+                operations[0].clone(),
+                operations[1].clone(),
+                replaced.clone()
+            ]
+        );
+
+        // Removing one part mint keeps the other of the same quote
+        db.remove_pending_mint_operation(&operations[0].id)
+            .await
+            .unwrap();
+        assert_eq!(
+            db.get_pending_mint_operations().await.unwrap(),
+            vec![operations[1].clone(), replaced]
+        );
+    }
 }
@@ -75,7 +75,7 @@ async fn main() -> anyhow::Result<()> {
     let start = std::time::Instant::now();
 
     while start.elapsed() < timeout {
-        let status = wallet.mint_quote_state(&mint_quote.id).await?;
+        let status = wallet.mint_quote_state(&mint_quote.id).await?.response;
 
         if status.state == MintQuoteState::Paid {
             break;
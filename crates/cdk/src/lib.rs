@@ -20,6 +20,8 @@ pub mod cdk_database {
 #[cfg(feature = "mint")]
 pub mod mint;
 #[cfg(feature = "wallet")]
+pub mod prelude;
+#[cfg(feature = "wallet")]
 pub mod wallet;
 
 #[cfg(feature = "bip353")]
@@ -36,7 +38,7 @@ pub mod pub_sub;
 /// Re-export amount type
 #[doc(hidden)]
 pub use cdk_common::{
-    amount, common as types, dhke, ensure_cdk,
+    amount, canonical, common as types, dhke, ensure_cdk,
     error::{self, Error},
     lightning_invoice, mint_url, nuts, secret, util, ws, Amount, Bolt11Invoice,
 };
@@ -57,9 +59,14 @@ pub use wallet::{Wallet, WalletSubscription};
 
 #[doc(hidden)]
 pub use self::util::SECP256K1;
+/// Wallet HTTP client
 #[cfg(feature = "wallet")]
 #[doc(hidden)]
-pub use self::wallet::HttpClient;
+#[deprecated(
+    since = "0.12.0",
+    note = "use `cdk::wallet::HttpClient` or `cdk::prelude::HttpClient`"
+)]
+pub type HttpClient = wallet::HttpClient;
 
 /// Result
 #[doc(hidden)]
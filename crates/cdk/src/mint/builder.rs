@@ -2,6 +2,7 @@
 
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Duration;
 
 use bitcoin::bip32::DerivationPath;
 use cdk_common::database::{self, MintDatabase, MintKeysDatabase};
@@ -29,6 +30,7 @@ use crate::nuts::{
     MppMethodSettings, PaymentMethod,
 };
 use crate::types::PaymentProcessorKey;
+use crate::util::clock::Clock;
 
 /// Cashu Mint Builder
 pub struct MintBuilder {
@@ -40,6 +42,9 @@ pub struct MintBuilder {
         HashMap<PaymentProcessorKey, Arc<dyn MintPayment<Err = cdk_payment::Error> + Send + Sync>>,
     supported_units: HashMap<CurrencyUnit, (u64, u8)>,
     custom_paths: HashMap<CurrencyUnit, DerivationPath>,
+    clock: Option<Arc<dyn Clock>>,
+    mining_share_sweep_interval: Option<Duration>,
+    share_amount_policy: Option<fn(u64) -> Amount>,
 }
 
 impl MintBuilder {
@@ -65,6 +70,9 @@ impl MintBuilder {
             payment_processors: HashMap::new(),
             supported_units: HashMap::new(),
             custom_paths: HashMap::new(),
+            clock: None,
+            mining_share_sweep_interval: None,
+            share_amount_policy: None,
         }
     }
 
@@ -196,6 +204,94 @@ impl MintBuilder {
         self
     }
 
+    /// Set the clock used for quote expiry
+    ///
+    /// Defaults to [`SystemClock`](crate::util::clock::SystemClock).
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = Some(clock);
+        self
+    }
+
+    /// Remove expired mining share quotes in the background every `period`
+    ///
+    /// Off by default, see [`Mint::expire_stale_mining_share_quotes`].
+    pub fn with_mining_share_quote_sweep_interval(mut self, period: Duration) -> Self {
+        self.mining_share_sweep_interval = Some(period);
+        self
+    }
+
+    /// Check the amount of mining share quotes against their share difficulty
+    ///
+    /// Requests must then carry a difficulty, and are refused unless their
+    /// amount is what `policy` allows for it.
+    pub fn with_share_amount_policy(mut self, policy: fn(u64) -> Amount) -> Self {
+        self.share_amount_policy = Some(policy);
+        self
+    }
+
+    /// Advertise support for the compact CBOR encoding on mining share endpoints
+    pub fn with_mining_share_cbor(mut self, supported: bool) -> Self {
+        let mut settings = self.mint_info.nuts.nutxx.unwrap_or_default();
+        settings.cbor = supported;
+
+        self.mint_info.nuts = self.mint_info.nuts.nutxx(settings);
+
+        self
+    }
+
+    /// Set the outputs a mining share mint request may use beyond the minimal
+    /// split of the quote amount
+    pub fn with_mining_share_output_slack(mut self, slack: u32) -> Self {
+        let mut settings = self.mint_info.nuts.nutxx.unwrap_or_default();
+        settings.output_slack = Some(slack);
+
+        self.mint_info.nuts = self.mint_info.nuts.nutxx(settings);
+
+        self
+    }
+
+    /// Set the most unissued, unexpired mining share quotes a NUT-20 pubkey may
+    /// hold at once
+    pub fn with_max_pending_quotes_per_pubkey(mut self, max: u32) -> Self {
+        let mut settings = self.mint_info.nuts.nutxx.unwrap_or_default();
+        settings.max_pending_quotes_per_pubkey = Some(max);
+
+        self.mint_info.nuts = self.mint_info.nuts.nutxx(settings);
+
+        self
+    }
+
+    /// Advertise support for cancelling mint quotes that have no ecash issued
+    pub fn with_mint_quote_cancel(mut self, supported: bool) -> Self {
+        let mut settings = self.mint_info.nuts.nutxx.unwrap_or_default();
+        settings.quote_cancel = supported;
+
+        self.mint_info.nuts = self.mint_info.nuts.nutxx(settings);
+
+        self
+    }
+
+    /// Set the largest amount a mining share quote may request
+    pub fn with_max_mining_share_amount(mut self, max_amount: Amount) -> Self {
+        let mut settings = self.mint_info.nuts.nutxx.unwrap_or_default();
+        settings.max_amount = Some(max_amount);
+
+        self.mint_info.nuts = self.mint_info.nuts.nutxx(settings);
+
+        self
+    }
+
+    /// Set the largest total amount of the quotes of one mining share quote
+    /// batch
+    pub fn with_max_mining_share_batch_amount(mut self, max_amount: Amount) -> Self {
+        let mut settings = self.mint_info.nuts.nutxx.unwrap_or_default();
+        settings.max_batch_amount = Some(max_amount);
+
+        self.mint_info.nuts = self.mint_info.nuts.nutxx(settings);
+
+        self
+    }
+
     /// Set custom derivation paths for mint units
     pub fn with_custom_derivation_paths(
         mut self,
@@ -248,6 +344,16 @@ impl MintBuilder {
         self.mint_info.nuts.nut04.methods.push(mint_method_settings);
         self.mint_info.nuts.nut04.disabled = false;
 
+        // Mining share quote subscriptions need no backend support, advertise them
+        if method == PaymentMethod::MiningShare {
+            let supported_method = SupportedMethods::default_mining_share(unit.clone());
+            let supported_settings = &mut self.mint_info.nuts.nut17.supported;
+
+            if !supported_settings.contains(&supported_method) {
+                supported_settings.push(supported_method);
+            }
+        }
+
         let melt_method_settings = MeltMethodSettings {
             method,
             unit,
@@ -288,23 +394,43 @@ impl MintBuilder {
         signatory: Arc<dyn Signatory + Send + Sync>,
     ) -> Result<Mint, Error> {
         #[cfg(feature = "auth")]
-        if let Some(auth_localstore) = self.auth_localstore {
-            return Mint::new_with_auth(
-                self.mint_info,
-                signatory,
-                self.localstore,
-                auth_localstore,
-                self.payment_processors,
-            )
-            .await;
-        }
-        Mint::new(
+        let mut mint = match self.auth_localstore {
+            Some(auth_localstore) => {
+                Mint::new_with_auth(
+                    self.mint_info,
+                    signatory,
+                    self.localstore,
+                    auth_localstore,
+                    self.payment_processors,
+                )
+                .await?
+            }
+            None => {
+                Mint::new(
+                    self.mint_info,
+                    signatory,
+                    self.localstore,
+                    self.payment_processors,
+                )
+                .await?
+            }
+        };
+        #[cfg(not(feature = "auth"))]
+        let mut mint = Mint::new(
             self.mint_info,
             signatory,
             self.localstore,
             self.payment_processors,
         )
-        .await
+        .await?;
+
+        if let Some(clock) = self.clock {
+            mint.clock = clock;
+        }
+        mint.mining_share_sweep_interval = self.mining_share_sweep_interval;
+        mint.share_amount_policy = self.share_amount_policy;
+
+        Ok(mint)
     }
 
     /// Build the mint with the provided keystore and seed
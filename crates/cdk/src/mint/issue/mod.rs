@@ -1,16 +1,23 @@
+use std::str::FromStr;
+
 use cdk_common::mint::{IncomingPayment, MintQuote};
-use cdk_common::nuts::nutXX::MintQuoteMiningShareRequest;
+use cdk_common::nuts::nutXX::{
+    BatchMintQuoteMiningShareItem, BatchMintQuoteMiningShareRequest,
+    BatchMintQuoteMiningShareResponse, BatchQuoteStatusItem, BatchQuoteStatusRequest,
+    BatchQuoteStatusResponse, MiningShareStats, MintQuoteCancelRequest, MintQuoteCancelResponse,
+    MintQuoteConversionResponse, MintQuoteMiningShareRequest, QuoteConversionParams,
+    MAX_BATCH_MINT_QUOTE_MINING_SHARE, MAX_BATCH_QUOTE_STATUS, MAX_MINING_SHARE_STATS_WINDOW,
+};
 use cdk_common::payment::{
     Bolt11IncomingPaymentOptions, Bolt11Settings, Bolt12IncomingPaymentOptions,
     CreateIncomingPaymentResponse, IncomingPaymentOptions, PaymentIdentifier, WaitPaymentResponse,
 };
 use cdk_common::quote_id::QuoteId;
-use cdk_common::util::unix_time;
-use cdk_common::MintQuoteMiningShareResponse;
 use cdk_common::{
-    database, ensure_cdk, Amount, CurrencyUnit, Error, MintQuoteBolt11Request,
-    MintQuoteBolt11Response, MintQuoteBolt12Request, MintQuoteBolt12Response, MintQuoteState,
-    MintRequest, MintResponse, NotificationPayload, PaymentMethod, PublicKey,
+    database, ensure_cdk, Amount, BlindedMessage, CurrencyUnit, Error, MintQuoteBolt11Request,
+    MintQuoteBolt11Response, MintQuoteBolt12Request, MintQuoteBolt12Response,
+    MintQuoteMiningShareResponse, MintQuoteState, MintRequest, MintResponse, NotificationPayload,
+    PaymentMethod, PublicKey,
 };
 #[cfg(feature = "prometheus")]
 use cdk_prometheus::METRICS;
@@ -124,6 +131,7 @@ impl TryFrom<MintQuoteResponse> for MintQuoteMiningShareResponse<uuid::Uuid> {
                     pubkey: mining_share_response.pubkey,
                     keyset_id: mining_share_response.keyset_id,
                     amount_issued: mining_share_response.amount_issued,
+                    difficulty: mining_share_response.difficulty,
                 })
             }
             _ => Err(Error::InvalidPaymentMethod),
@@ -276,7 +284,7 @@ impl Mint {
 
                     let mint_ttl = self.localstore.get_quote_ttl().await?.mint_ttl;
 
-                    let quote_expiry = unix_time() + mint_ttl;
+                    let quote_expiry = self.clock.now_unix() + mint_ttl;
 
                     let settings = ln.get_settings().await?;
                     let settings: Bolt11Settings = serde_json::from_value(settings)?;
@@ -332,8 +340,9 @@ impl Mint {
                         })?
                 }
                 MintQuoteRequest::MiningShare(mining_request) => {
+                    let settings = self.mint_info().await?.nuts.nutxx.unwrap_or_default();
                     mining_request
-                        .validate()
+                        .validate_with_max(settings.max_amount())
                         .map_err(|_| Error::InvalidPaymentRequest)?;
 
                     unit = mining_request.unit;
@@ -357,7 +366,7 @@ impl Mint {
                     amount_paid = payment_amount;
                     payments.push(IncomingPayment {
                         amount: payment_amount,
-                        time: unix_time(),
+                        time: self.clock.now_unix(),
                         payment_id: request_lookup_id.to_string(),
                     });
 
@@ -372,7 +381,7 @@ impl Mint {
                     // Mining shares don't use payment processors - they create direct payment identifiers
                     CreateIncomingPaymentResponse {
                         request: header_hash,
-                        expiry: Some(unix_time() + mint_ttl),
+                        expiry: Some(self.clock.now_unix() + mint_ttl),
                         request_lookup_id,
                     }
                 }
@@ -389,7 +398,7 @@ impl Mint {
                 amount_paid,
                 Amount::ZERO,
                 payment_method.clone(),
-                unix_time(),
+                self.clock.now_unix(),
                 payments,
                 vec![],
                 keyset_id,
@@ -405,6 +414,10 @@ impl Mint {
             );
 
             let mut tx = self.localstore.begin_transaction().await?;
+            if payment_method == PaymentMethod::MiningShare {
+                self.ensure_unique_share_hash(&mut tx, &quote.request_lookup_id)
+                    .await?;
+            }
             tx.add_mint_quote(quote.clone()).await?;
             tx.commit().await?;
 
@@ -533,12 +546,15 @@ impl Mint {
 
             let mut tx = self.localstore.begin_transaction().await?;
 
+            let mut paid = None;
             if let Ok(Some(mint_quote)) = tx
                 .get_mint_quote_by_request_lookup_id(&wait_payment_response.payment_identifier)
                 .await
             {
-                self.pay_mint_quote(&mut tx, &mint_quote, wait_payment_response)
-                    .await?;
+                paid = self
+                    .pay_mint_quote(&mut tx, &mint_quote, wait_payment_response)
+                    .await?
+                    .map(|total_paid| (mint_quote, total_paid));
             } else {
                 tracing::warn!(
                     "Could not get request for request lookup id {:?}.",
@@ -548,6 +564,11 @@ impl Mint {
 
             tx.commit().await?;
 
+            if let Some((mint_quote, total_paid)) = paid {
+                self.pubsub_manager
+                    .mint_quote_payment(&mint_quote, total_paid);
+            }
+
             Ok(())
         }
         .await;
@@ -566,15 +587,18 @@ impl Mint {
 
     /// Marks a specific mint quote as paid
     ///
-    /// Updates the mint quote with payment information and broadcasts
-    /// a notification about the payment status change.
+    /// Updates the mint quote with payment information in `tx`. Subscribers
+    /// are not notified, so the caller publishes the payment with
+    /// [`PubSubManager::mint_quote_payment`](crate::mint::subscription::PubSubManager::mint_quote_payment)
+    /// once `tx` is committed.
     ///
     /// # Arguments
     /// * `mint_quote` - The mint quote to mark as paid
     /// * `wait_payment_response` - Payment response containing payment details
     ///
     /// # Returns
-    /// * `Ok(())` if the update was successful
+    /// * The total paid of the quote if the payment was recorded
+    /// * `None` if the payment was already seen or the quote takes no payments
     /// * `Error` if the update fails
     #[instrument(skip_all)]
     pub async fn pay_mint_quote(
@@ -582,20 +606,13 @@ impl Mint {
         tx: &mut Box<dyn database::MintTransaction<'_, database::Error> + Send + Sync + '_>,
         mint_quote: &MintQuote,
         wait_payment_response: WaitPaymentResponse,
-    ) -> Result<(), Error> {
+    ) -> Result<Option<Amount>, Error> {
         #[cfg(feature = "prometheus")]
         METRICS.inc_in_flight_requests("pay_mint_quote");
 
-        let result = async {
-            Self::handle_mint_quote_payment(
-                tx,
-                mint_quote,
-                wait_payment_response,
-                &self.pubsub_manager,
-            )
-            .await
-        }
-        .await;
+        let result =
+            async { Self::handle_mint_quote_payment(tx, mint_quote, wait_payment_response).await }
+                .await;
 
         #[cfg(feature = "prometheus")]
         {
@@ -651,6 +668,49 @@ impl Mint {
         result
     }
 
+    /// Checks the state of several mining share quotes at once
+    ///
+    /// Answers in the order of the request. A quote that is unknown or not a
+    /// mining share quote gets an error entry instead of failing the batch.
+    #[instrument(skip_all, fields(quotes = request.quotes.len()))]
+    pub async fn check_mint_quotes_mining_share(
+        &self,
+        request: BatchQuoteStatusRequest,
+    ) -> Result<BatchQuoteStatusResponse<QuoteId>, Error> {
+        if request.quotes.len() > MAX_BATCH_QUOTE_STATUS {
+            return Err(Error::BatchQuoteStatusTooManyQuotes {
+                max: MAX_BATCH_QUOTE_STATUS,
+                quotes: request.quotes.len(),
+            });
+        }
+
+        let mut quotes = Vec::with_capacity(request.quotes.len());
+        for quote_id in request.quotes {
+            let item = match self.check_mint_quote_mining_share(&quote_id).await {
+                Ok(quote) => BatchQuoteStatusItem::Quote(quote),
+                Err(err) => {
+                    tracing::debug!("Could not check mining share quote {}: {}", quote_id, err);
+                    BatchQuoteStatusItem::Error {
+                        quote: quote_id,
+                        error: err.to_string(),
+                    }
+                }
+            };
+            quotes.push(item);
+        }
+
+        Ok(BatchQuoteStatusResponse { quotes })
+    }
+
+    /// Checks the state of a mining share quote given by its string ID
+    async fn check_mint_quote_mining_share(
+        &self,
+        quote_id: &str,
+    ) -> Result<MintQuoteMiningShareResponse<QuoteId>, Error> {
+        let quote_id = QuoteId::from_str(quote_id)?;
+        self.check_mint_quote(&quote_id).await?.try_into()
+    }
+
     /// Creates a mint quote for mining shares
     ///
     /// This function is called by the mint after the pool validates a share.
@@ -668,11 +728,131 @@ impl Mint {
         &self,
         mint_quote_request: MintQuoteMiningShareRequest,
     ) -> Result<MintQuote, Error> {
-        // Validate the mining share request
+        let quote = self.new_mint_mining_share_quote(mint_quote_request).await?;
+
+        // Store the quote in database
+        let mut tx = self.localstore.begin_transaction().await?;
+        self.check_mining_share_quote_slot(&mut tx, &quote).await?;
+        Self::add_mining_share_quote(&mut tx, &quote).await?;
+        tx.commit().await?;
+
+        // Broadcast notification
+        let res: MintQuoteMiningShareResponse<QuoteId> = quote.clone().try_into()?;
+        self.pubsub_manager
+            .broadcast(NotificationPayload::MintQuoteMiningShareResponse(res));
+
+        Ok(quote)
+    }
+
+    /// Creates several mining share mint quotes in one database transaction
+    ///
+    /// Fails as a whole if the amounts of the request overflow or exceed the
+    /// advertised batch maximum. Answers in the order of the request. A
+    /// request that is invalid, or whose pubkey or header hash is not free,
+    /// gets an error entry instead of failing the batch. If storing a quote
+    /// fails, none of the batch is stored.
+    #[instrument(skip_all, fields(quotes = request.quotes.len()))]
+    pub async fn create_mint_mining_share_quotes(
+        &self,
+        request: BatchMintQuoteMiningShareRequest,
+    ) -> Result<BatchMintQuoteMiningShareResponse<QuoteId>, Error> {
+        if request.quotes.len() > MAX_BATCH_MINT_QUOTE_MINING_SHARE {
+            return Err(Error::BatchMintQuoteTooManyQuotes {
+                max: MAX_BATCH_MINT_QUOTE_MINING_SHARE,
+                quotes: request.quotes.len(),
+            });
+        }
+
+        let total = Amount::try_sum(request.quotes.iter().map(|quote| quote.amount))
+            .map_err(|_| Error::AmountOverflow)?;
+        let settings = self.mint_info().await?.nuts.nutxx.unwrap_or_default();
+        if let Some(max) = settings.max_batch_amount {
+            ensure_cdk!(
+                total <= max,
+                Error::BatchMintQuoteAmountTooLarge { max, amount: total }
+            );
+        }
+
+        let mut new_quotes = Vec::with_capacity(request.quotes.len());
+        for mint_quote_request in request.quotes {
+            let header_hash = mint_quote_request.header_hash;
+            let quote = self.new_mint_mining_share_quote(mint_quote_request).await;
+            new_quotes.push((header_hash, quote));
+        }
+
+        let mut tx = self.localstore.begin_transaction().await?;
+        let mut created = Vec::new();
+        let mut quotes = Vec::with_capacity(new_quotes.len());
+
+        for (header_hash, quote) in new_quotes {
+            let checked = match quote {
+                Ok(quote) => match self.check_mining_share_quote_slot(&mut tx, &quote).await {
+                    Ok(()) => Ok(quote),
+                    Err(err @ (Error::TooManyPendingQuotes(_) | Error::DuplicateShareHash(_))) => {
+                        Err(err)
+                    }
+                    Err(err) => return Err(err),
+                },
+                Err(err) => Err(err),
+            };
+
+            let item = match checked {
+                Ok(quote) => {
+                    Self::add_mining_share_quote(&mut tx, &quote).await?;
+                    let response: MintQuoteMiningShareResponse<QuoteId> =
+                        quote.clone().try_into()?;
+                    created.push(response.clone());
+                    BatchMintQuoteMiningShareItem::Quote(response)
+                }
+                Err(err) => {
+                    tracing::debug!(
+                        "Could not create mining share quote {}: {}",
+                        header_hash,
+                        err
+                    );
+                    BatchMintQuoteMiningShareItem::Error {
+                        header_hash,
+                        error: err.to_string(),
+                    }
+                }
+            };
+            quotes.push(item);
+        }
+
+        tx.commit().await?;
+
+        for response in created {
+            self.pubsub_manager
+                .broadcast(NotificationPayload::MintQuoteMiningShareResponse(response));
+        }
+
+        Ok(BatchMintQuoteMiningShareResponse { quotes })
+    }
+
+    /// Validates a mining share request and builds its quote without storing it
+    async fn new_mint_mining_share_quote(
+        &self,
+        mint_quote_request: MintQuoteMiningShareRequest,
+    ) -> Result<MintQuote, Error> {
+        // Validate the mining share request against the advertised maximum
+        let settings = self.mint_info().await?.nuts.nutxx.unwrap_or_default();
         mint_quote_request
-            .validate()
+            .validate_with_max(settings.max_amount())
             .map_err(|_| Error::InvalidPaymentRequest)?;
 
+        if let Some(policy) = self.share_amount_policy {
+            let difficulty = mint_quote_request
+                .difficulty
+                .ok_or(Error::MiningShareDifficultyMissing)?;
+            let allowed = policy(difficulty);
+            if mint_quote_request.amount != allowed {
+                return Err(Error::MiningShareAmountMismatch {
+                    amount: mint_quote_request.amount,
+                    allowed,
+                });
+            }
+        }
+
         let unit = mint_quote_request.unit;
         let amount = Some(mint_quote_request.amount);
         let pubkey = mint_quote_request.pubkey;
@@ -684,7 +864,7 @@ impl Mint {
             .await?;
 
         let mint_ttl = self.localstore.get_quote_ttl().await?.mint_ttl;
-        let expiry = unix_time() + mint_ttl;
+        let expiry = self.clock.now_unix() + mint_ttl;
 
         // Create payment response for mining shares
         let create_invoice_response = CreateIncomingPaymentResponse {
@@ -697,7 +877,7 @@ impl Mint {
         let payment_amount = amount.expect("Mining share amount is always required");
         let payment = IncomingPayment {
             amount: payment_amount,
-            time: unix_time(),
+            time: self.clock.now_unix(),
             payment_id: create_invoice_response.request_lookup_id.to_string(),
         };
 
@@ -709,7 +889,7 @@ impl Mint {
             .ok_or(Error::NoActiveKeyset)?
             .clone();
 
-        let quote = MintQuote::new(
+        let mut quote = MintQuote::new(
             None,
             create_invoice_response.request.to_string(),
             unit.clone(),
@@ -720,41 +900,521 @@ impl Mint {
             payment_amount, // amount_paid
             Amount::ZERO,   // amount_issued
             payment_method.clone(),
-            unix_time(),
+            self.clock.now_unix(),
             vec![payment],
             vec![],          // issuance
             Some(keyset_id), // keyset_id determined by mint
         );
+        quote.difficulty = mint_quote_request.difficulty;
 
         tracing::debug!(
-            "Created mining share mint quote {} for {} {} with header hash {}",
+            "Created mining share mint quote {} for {} with header hash {}",
             quote.id,
-            payment_amount,
-            unit,
+            payment_amount.display(&unit),
             header_hash,
         );
 
-        // Store the quote in database
-        let mut tx = self.localstore.begin_transaction().await?;
+        Ok(quote)
+    }
+
+    /// Checks, in the transaction the quote is added in, that its NUT-20 pubkey
+    /// may hold another pending quote and its header hash is not quoted yet
+    async fn check_mining_share_quote_slot(
+        &self,
+        tx: &mut Box<dyn database::MintTransaction<'_, database::Error> + Send + Sync + '_>,
+        quote: &MintQuote,
+    ) -> Result<(), Error> {
+        let max_pending = self
+            .mint_info()
+            .await?
+            .nuts
+            .nutxx
+            .and_then(|settings| settings.max_pending_quotes_per_pubkey);
+        if let (Some(max_pending), Some(pubkey)) = (max_pending, quote.pubkey) {
+            let pending = tx
+                .count_pending_mint_quotes_by_pubkey(&pubkey, self.clock.now_unix())
+                .await?;
+            if pending >= u64::from(max_pending) {
+                tracing::debug!(
+                    "Pubkey {} already has {} pending quotes, refusing mining share quote",
+                    pubkey,
+                    pending
+                );
+                return Err(Error::TooManyPendingQuotes(max_pending));
+            }
+        }
+
+        self.ensure_unique_share_hash(tx, &quote.request_lookup_id)
+            .await
+    }
+
+    /// Stores a mining share quote and records its payment
+    async fn add_mining_share_quote(
+        tx: &mut Box<dyn database::MintTransaction<'_, database::Error> + Send + Sync + '_>,
+        quote: &MintQuote,
+    ) -> Result<(), Error> {
         tx.add_mint_quote(quote.clone()).await?;
 
         // Record the payment in mint_quote_payments table (mining shares are immediately paid)
         // TODO add PENDING mining share quote support
         tx.increment_mint_quote_amount_paid(
             &quote.id,
-            payment_amount,
-            create_invoice_response.request_lookup_id.to_string(),
+            quote.amount_paid(),
+            quote.request_lookup_id.to_string(),
         )
         .await?;
 
+        Ok(())
+    }
+
+    /// Removes the mining share quotes that expired at or before `now` without
+    /// any ecash issued for them
+    ///
+    /// Mining share quotes are paid when they are created, so unlike bolt11
+    /// quotes nothing else retires them. The shares of removed quotes can be
+    /// quoted again. A quote that was partly issued is kept so the rest can
+    /// still be minted.
+    ///
+    /// # Returns
+    /// * The number of quotes removed
+    #[instrument(skip(self))]
+    pub async fn expire_stale_mining_share_quotes(&self, now: u64) -> Result<usize, Error> {
+        let mut tx = self.localstore.begin_transaction().await?;
+        let removed = tx.remove_expired_mining_share_quotes(now).await?;
         tx.commit().await?;
 
-        // Broadcast notification
-        let res: MintQuoteMiningShareResponse<QuoteId> = quote.clone().try_into()?;
-        self.pubsub_manager
-            .broadcast(NotificationPayload::MintQuoteMiningShareResponse(res));
+        if removed > 0 {
+            tracing::info!("Removed {} expired mining share quotes", removed);
+        }
 
-        Ok(quote)
+        Ok(removed as usize)
+    }
+
+    /// Aggregates of the mining share quotes created between `since` and
+    /// `until`, both inclusive
+    ///
+    /// Quotes count as issued once any ecash was issued for them. Expired
+    /// quotes removed without ecash issued are not counted.
+    ///
+    /// Fails with [`Error::InvalidMiningShareStatsRange`] if `until` is before
+    /// `since` or the range is longer than [`MAX_MINING_SHARE_STATS_WINDOW`].
+    #[instrument(skip(self))]
+    pub async fn mining_share_stats(
+        &self,
+        since: u64,
+        until: u64,
+    ) -> Result<MiningShareStats, Error> {
+        ensure_cdk!(
+            since <= until && until - since <= MAX_MINING_SHARE_STATS_WINDOW,
+            Error::InvalidMiningShareStatsRange { since, until }
+        );
+
+        Ok(self.localstore.mining_share_stats(since, until).await?)
+    }
+
+    /// Ensures no live quote already holds a mining share header hash
+    ///
+    /// A quote that expired or was converted before any ecash was issued for
+    /// it no longer holds the share, so it is removed and the share can be
+    /// quoted again.
+    ///
+    /// # Returns
+    /// * `Ok(())` if a new quote may be added for the header hash
+    /// * `Error::DuplicateShareHash` with the id of the quote holding the share
+    async fn ensure_unique_share_hash(
+        &self,
+        tx: &mut Box<dyn database::MintTransaction<'_, database::Error> + Send + Sync + '_>,
+        request_lookup_id: &PaymentIdentifier,
+    ) -> Result<(), Error> {
+        let Some(existing) = tx
+            .get_mint_quote_by_request_lookup_id(request_lookup_id)
+            .await?
+        else {
+            return Ok(());
+        };
+
+        let expired = existing.expiry != 0 && existing.expiry <= self.clock.now_unix();
+        let released = expired
+            || matches!(
+                existing.state(),
+                MintQuoteState::Converted | MintQuoteState::Cancelled
+            );
+        if !released || existing.amount_issued() > Amount::ZERO {
+            tracing::debug!(
+                "Mining share {} is already held by quote {}",
+                request_lookup_id,
+                existing.id
+            );
+            return Err(Error::DuplicateShareHash(existing.id.to_string()));
+        }
+
+        tracing::debug!(
+            "Removing released mining share quote {} to quote its share again",
+            existing.id
+        );
+        tx.remove_mint_quote(&existing.id).await?;
+
+        Ok(())
+    }
+
+    /// Converts an unpaid mint quote into a quote of another payment method
+    ///
+    /// The new quote keeps the amount, unit, NUT-20 pubkey and expiry of the
+    /// original quote, and is checked against the limits of the target method.
+    /// The new quote is unpaid, a mining share quote is only credited once the
+    /// pool reports its share through [`Mint::pay_mint_quote_for_request_id`].
+    /// The original quote is left in the terminal
+    /// [`MintQuoteState::Converted`] state, so it can no longer be paid or
+    /// minted.
+    ///
+    /// # Arguments
+    /// * `quote_id` - The quote to convert
+    /// * `to` - Payment method of the new quote
+    /// * `params` - Method specific parameters of the new quote
+    ///
+    /// # Returns
+    /// * `MintQuoteConversionResponse` - The new quote, referencing the original
+    /// * `Error` if the quote is not unpaid or the new quote cannot be created
+    #[instrument(skip(self, params))]
+    pub async fn convert_quote_method(
+        &self,
+        quote_id: &QuoteId,
+        to: PaymentMethod,
+        params: QuoteConversionParams,
+    ) -> Result<MintQuoteConversionResponse<QuoteId>, Error> {
+        #[cfg(feature = "prometheus")]
+        METRICS.inc_in_flight_requests("convert_quote_method");
+
+        let result = async {
+            let mut original = self
+                .localstore
+                .get_mint_quote(quote_id)
+                .await?
+                .ok_or(Error::UnknownQuote)?;
+
+            if original.payment_method == PaymentMethod::Bolt11 {
+                self.check_mint_quote_paid(&mut original).await?;
+            }
+
+            match original.state() {
+                MintQuoteState::Unpaid => (),
+                MintQuoteState::Paid => return Err(Error::PaidQuote),
+                MintQuoteState::Issued => return Err(Error::IssuedQuote),
+                MintQuoteState::Converted => return Err(Error::ConvertedQuote),
+                MintQuoteState::Cancelled => return Err(Error::CancelledQuote),
+            }
+
+            ensure_cdk!(
+                to != original.payment_method,
+                Error::UnsupportedPaymentMethod
+            );
+
+            let now = self.clock.now_unix();
+            ensure_cdk!(
+                original.expiry == 0 || original.expiry > now,
+                Error::ExpiredQuote(original.expiry, now)
+            );
+
+            let unit = original.unit.clone();
+            let amount = original.amount;
+
+            self.check_mint_request_acceptable(amount, &unit, &to)
+                .await?;
+
+            let mint_ttl = self.localstore.get_quote_ttl().await?.mint_ttl;
+            let expiry = match original.expiry {
+                0 => now + mint_ttl,
+                expiry => expiry.min(now + mint_ttl),
+            };
+
+            let mut keyset_id = None;
+
+            let create_invoice_response = match &to {
+                PaymentMethod::MiningShare => {
+                    let header_hash = params
+                        .header_hash
+                        .ok_or(Error::InvalidPaymentRequest)?
+                        .to_string();
+                    ensure_cdk!(amount.is_some(), Error::AmountUndefined);
+                    ensure_cdk!(original.pubkey.is_some(), Error::PubkeyRequired);
+
+                    // Left unpaid, the share is only credited once the pool validated it
+                    let request_lookup_id = PaymentIdentifier::MiningShareHash(header_hash.clone());
+
+                    keyset_id = Some(
+                        self.get_active_keysets()
+                            .get(&unit)
+                            .cloned()
+                            .ok_or(Error::NoActiveKeyset)?,
+                    );
+
+                    CreateIncomingPaymentResponse {
+                        request: header_hash,
+                        expiry: Some(expiry),
+                        request_lookup_id,
+                    }
+                }
+                PaymentMethod::Bolt11 => {
+                    let payment_amount = amount.ok_or(Error::AmountUndefined)?;
+                    let ln = self.get_payment_processor(unit.clone(), PaymentMethod::Bolt11)?;
+
+                    let settings = ln.get_settings().await?;
+                    let settings: Bolt11Settings = serde_json::from_value(settings)?;
+
+                    if params.description.is_some() && !settings.invoice_description {
+                        tracing::error!("Backend does not support invoice description");
+                        return Err(Error::InvoiceDescriptionUnsupported);
+                    }
+
+                    let bolt11_options = Bolt11IncomingPaymentOptions {
+                        description: params.description,
+                        amount: payment_amount,
+                        unix_expiry: Some(expiry),
+                    };
+
+                    ln.create_incoming_payment_request(
+                        &unit,
+                        IncomingPaymentOptions::Bolt11(bolt11_options),
+                    )
+                    .await
+                    .map_err(|err| {
+                        tracing::error!("Could not create invoice: {}", err);
+                        Error::InvalidPaymentRequest
+                    })?
+                }
+                PaymentMethod::Bolt12 | PaymentMethod::Custom(_) => {
+                    return Err(Error::UnsupportedPaymentMethod);
+                }
+            };
+
+            let quote = MintQuote::new(
+                None,
+                create_invoice_response.request.to_string(),
+                unit.clone(),
+                amount,
+                create_invoice_response.expiry.unwrap_or(expiry),
+                create_invoice_response.request_lookup_id.clone(),
+                original.pubkey,
+                Amount::ZERO,
+                Amount::ZERO,
+                to.clone(),
+                now,
+                vec![],
+                vec![],
+                keyset_id,
+            );
+
+            let mut tx = self.localstore.begin_transaction().await?;
+
+            // Lock the original quote and make sure it was not paid in the meantime
+            let original = tx
+                .get_mint_quote(quote_id)
+                .await?
+                .ok_or(Error::UnknownQuote)?;
+
+            match original.state() {
+                MintQuoteState::Unpaid => (),
+                MintQuoteState::Paid => return Err(Error::PaidQuote),
+                MintQuoteState::Issued => return Err(Error::IssuedQuote),
+                MintQuoteState::Converted => return Err(Error::ConvertedQuote),
+                MintQuoteState::Cancelled => return Err(Error::CancelledQuote),
+            }
+
+            if to == PaymentMethod::MiningShare {
+                self.ensure_unique_share_hash(&mut tx, &quote.request_lookup_id)
+                    .await?;
+            }
+
+            tx.convert_mint_quote(quote_id, &quote.id).await?;
+            tx.add_mint_quote(quote.clone()).await?;
+
+            if to == PaymentMethod::MiningShare {
+                tx.increment_mint_quote_amount_paid(
+                    &quote.id,
+                    amount_paid,
+                    create_invoice_response.request_lookup_id.to_string(),
+                )
+                .await?;
+            }
+
+            tx.commit().await?;
+
+            tracing::info!(
+                "Converted {} mint quote {} to {} mint quote {}",
+                original.payment_method,
+                original.id,
+                to,
+                quote.id
+            );
+
+            match to {
+                PaymentMethod::Bolt11 => {
+                    let res: MintQuoteBolt11Response<QuoteId> = quote.clone().into();
+                    self.pubsub_manager
+                        .broadcast(NotificationPayload::MintQuoteBolt11Response(res));
+                }
+                PaymentMethod::MiningShare => {
+                    let res: MintQuoteMiningShareResponse<QuoteId> = quote.clone().try_into()?;
+                    self.pubsub_manager
+                        .broadcast(NotificationPayload::MintQuoteMiningShareResponse(res));
+                }
+                PaymentMethod::Bolt12 | PaymentMethod::Custom(_) => {}
+            }
+
+            Ok(MintQuoteConversionResponse {
+                original_quote: original.id,
+                quote: quote.id.clone(),
+                method: quote.payment_method.clone(),
+                state: quote.state(),
+                request: quote.request,
+                amount: quote.amount,
+                unit: quote.unit,
+                expiry: Some(quote.expiry),
+                pubkey: quote.pubkey,
+                keyset_id: quote.keyset_id,
+                amount_paid: quote.amount_paid(),
+                amount_issued: quote.amount_issued(),
+            })
+        }
+        .await;
+
+        #[cfg(feature = "prometheus")]
+        {
+            METRICS.dec_in_flight_requests("convert_quote_method");
+            METRICS.record_mint_operation("convert_quote_method", result.is_ok());
+            if result.is_err() {
+                METRICS.record_error();
+            }
+        }
+
+        result
+    }
+
+    /// Cancels a mint quote that has no ecash issued for it
+    ///
+    /// The quote is left in the terminal [`MintQuoteState::Cancelled`] state:
+    /// later payments are not credited and it can no longer be minted or
+    /// converted. Refused unless the mint advertises quote cancellation in its
+    /// info.
+    ///
+    /// A quote locked to a NUT-20 pubkey is only cancelled with a signature of
+    /// its quote id by that key, and may then be cancelled while paid as long
+    /// as nothing was issued for it, its payment is forfeited. A quote without
+    /// pubkey can only be cancelled while unpaid.
+    ///
+    /// # Arguments
+    /// * `method` - Payment method of the quote
+    /// * `request` - The quote to cancel and the signature of its quote id
+    #[instrument(skip(self, request), fields(quote_id = %request.quote))]
+    pub async fn cancel_mint_quote(
+        &self,
+        method: &PaymentMethod,
+        request: MintQuoteCancelRequest<QuoteId>,
+    ) -> Result<MintQuoteCancelResponse<QuoteId>, Error> {
+        #[cfg(feature = "prometheus")]
+        METRICS.inc_in_flight_requests("cancel_mint_quote");
+
+        let result = async {
+            let settings = self.mint_info().await?.nuts.nutxx.unwrap_or_default();
+            ensure_cdk!(settings.quote_cancel, Error::QuoteCancelUnsupported);
+
+            let quote_id = &request.quote;
+            let mut tx = self.localstore.begin_transaction().await?;
+
+            let mut quote = tx
+                .get_mint_quote(quote_id)
+                .await?
+                .ok_or(Error::UnknownQuote)?;
+            ensure_cdk!(
+                &quote.payment_method == method,
+                Error::UnsupportedPaymentMethod
+            );
+
+            // Anyone who knows the quote id could otherwise forfeit its payment
+            match quote.pubkey {
+                Some(pubkey) => request.verify_signature(pubkey)?,
+                None => ensure_cdk!(quote.state() == MintQuoteState::Unpaid, Error::PaidQuote),
+            }
+
+            // Quotes minted in parts have ecash out before they are issued in full
+            if quote.amount_issued() > Amount::ZERO {
+                return Err(Error::IssuedQuote);
+            }
+
+            match quote.state() {
+                MintQuoteState::Unpaid | MintQuoteState::Paid => (),
+                MintQuoteState::Issued => return Err(Error::IssuedQuote),
+                MintQuoteState::Converted => return Err(Error::ConvertedQuote),
+                MintQuoteState::Cancelled => return Err(Error::CancelledQuote),
+            }
+
+            let now = self.clock.now_unix();
+            tx.cancel_mint_quote(quote_id, now).await?;
+            tx.commit().await?;
+            quote.cancelled_at = Some(now);
+
+            tracing::info!("Cancelled {} mint quote {}", quote.payment_method, quote.id);
+
+            match quote.payment_method {
+                PaymentMethod::Bolt11 => {
+                    let res: MintQuoteBolt11Response<QuoteId> = quote.clone().into();
+                    self.pubsub_manager
+                        .broadcast(NotificationPayload::MintQuoteBolt11Response(res));
+                }
+                PaymentMethod::MiningShare => {
+                    let res: MintQuoteMiningShareResponse<QuoteId> = quote.clone().try_into()?;
+                    self.pubsub_manager
+                        .broadcast(NotificationPayload::MintQuoteMiningShareResponse(res));
+                }
+                PaymentMethod::Bolt12 | PaymentMethod::Custom(_) => {}
+            }
+
+            Ok(MintQuoteCancelResponse {
+                quote: quote.id.clone(),
+                method: quote.payment_method.clone(),
+                state: quote.state(),
+            })
+        }
+        .await;
+
+        #[cfg(feature = "prometheus")]
+        {
+            METRICS.dec_in_flight_requests("cancel_mint_quote");
+            METRICS.record_mint_operation("cancel_mint_quote", result.is_ok());
+            if result.is_err() {
+                METRICS.record_error();
+            }
+        }
+
+        result
+    }
+
+    /// Checks that a mining share mint request uses at most the minimal split of
+    /// the amount it mints in the keyset of its outputs plus the configured slack
+    #[instrument(skip_all)]
+    pub async fn check_mining_share_outputs(
+        &self,
+        amount: Amount,
+        outputs: &[BlindedMessage],
+    ) -> Result<(), Error> {
+        let Some(keyset_id) = outputs.first().map(|output| output.keyset_id) else {
+            return Ok(());
+        };
+        let keyset = self
+            .get_keyset_info(&keyset_id)
+            .ok_or(Error::UnknownKeySet)?;
+        let settings = self.mint_info().await?.nuts.nutxx.unwrap_or_default();
+
+        let max = settings.max_outputs(amount, &keyset.amounts);
+        ensure_cdk!(
+            outputs.len() <= max,
+            Error::MiningShareTooManyOutputs {
+                max,
+                outputs: outputs.len(),
+            }
+        );
+
+        Ok(())
     }
 
     /// Processes a mint request to issue new tokens
@@ -790,6 +1450,27 @@ impl Mint {
             if mint_quote.payment_method == PaymentMethod::Bolt11 {
                 self.check_mint_quote_paid(&mut mint_quote).await?;
             }
+
+            // Reject dust splits before spending any work on signing them
+            if mint_quote.payment_method == PaymentMethod::MiningShare {
+                // A quote that expired before anything was issued no longer holds its share
+                let now = self.clock.now_unix();
+                if mint_quote.expiry != 0
+                    && mint_quote.expiry <= now
+                    && mint_quote.amount_issued() == Amount::ZERO
+                {
+                    return Err(Error::ExpiredQuote(mint_quote.expiry, now));
+                }
+
+                // Quotes may be minted in parts, bound by the part being minted
+                let remaining = mint_quote
+                    .amount_paid()
+                    .checked_sub(mint_quote.amount_issued())
+                    .unwrap_or_default();
+                let amount = mint_request.total_amount()?.min(remaining);
+                self.check_mining_share_outputs(amount, &mint_request.outputs)
+                    .await?;
+            }
         // get the blind signatures before having starting the db transaction, if there are any
         // rollbacks this blind_signatures will be lost, and the signature is stateless. It is not a
         // good idea to call an external service (which is really a trait, it could be anything
@@ -817,6 +1498,12 @@ impl Mint {
                 return Err(Error::IssuedQuote);
             }
             MintQuoteState::Paid => (),
+            MintQuoteState::Converted => {
+                return Err(Error::ConvertedQuote);
+            }
+            MintQuoteState::Cancelled => {
+                return Err(Error::CancelledQuote);
+            }
         }
 
         if mint_quote.payment_method == PaymentMethod::Bolt12 && mint_quote.pubkey.is_none() {
@@ -826,7 +1513,7 @@ impl Mint {
 
         let mint_amount = match mint_quote.payment_method {
             PaymentMethod::Bolt11 => mint_quote.amount.ok_or(Error::AmountUndefined)?,
-            PaymentMethod::Bolt12 => {
+            PaymentMethod::Bolt12 | PaymentMethod::MiningShare => {
                 if mint_quote.amount_issued() > mint_quote.amount_paid() {
                     tracing::error!(
                             "Quote state should not be issued if issued {} is > paid {}.",
@@ -837,7 +1524,6 @@ impl Mint {
                 }
                 mint_quote.amount_paid() - mint_quote.amount_issued()
             }
-            PaymentMethod::MiningShare => mint_quote.amount.ok_or(Error::AmountUndefined)?,
             _ => return Err(Error::UnsupportedPaymentMethod),
         };
 
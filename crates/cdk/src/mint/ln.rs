@@ -23,6 +23,11 @@ impl Mint {
             return Ok(());
         }
 
+        // A converted or cancelled quote is no longer paid through its request
+        if state == MintQuoteState::Converted || state == MintQuoteState::Cancelled {
+            return Ok(());
+        }
+
         // TODO move this function to mint/mod.rs
         // Mining shares are verified during quote creation and don't need Lightning verification
         if quote.payment_method == PaymentMethod::MiningShare {
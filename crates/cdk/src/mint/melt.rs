@@ -30,7 +30,6 @@ use crate::mint::SigFlag;
 use crate::nuts::nut11::{enforce_sig_flag, EnforceSigFlag};
 use crate::nuts::MeltQuoteState;
 use crate::types::PaymentProcessorKey;
-use crate::util::unix_time;
 use crate::{cdk_payment, ensure_cdk, Amount, Error};
 
 impl Mint {
@@ -205,18 +204,17 @@ impl Mint {
             unit.clone(),
             payment_quote.amount,
             payment_quote.fee,
-            unix_time() + melt_ttl,
+            self.clock.now_unix() + melt_ttl,
             payment_quote.request_lookup_id.clone(),
             *options,
             PaymentMethod::Bolt11,
         );
 
         tracing::debug!(
-            "New {} melt quote {} for {} {} with request id {:?}",
+            "New {} melt quote {} for {} with request id {:?}",
             quote.payment_method,
             quote.id,
-            amount_quote_unit,
-            unit,
+            amount_quote_unit.display(&unit),
             payment_quote.request_lookup_id
         );
 
@@ -306,18 +304,17 @@ impl Mint {
             unit.clone(),
             payment_quote.amount,
             payment_quote.fee,
-            unix_time() + self.quote_ttl().await?.melt_ttl,
+            self.clock.now_unix() + self.quote_ttl().await?.melt_ttl,
             payment_quote.request_lookup_id.clone(),
             *options,
             PaymentMethod::Bolt12,
         );
 
         tracing::debug!(
-            "New {} melt quote {} for {} {} with request id {:?}",
+            "New {} melt quote {} for {} with request id {:?}",
             quote.payment_method,
             quote.id,
-            amount,
-            unit,
+            amount.display(&unit),
             payment_quote.request_lookup_id
         );
 
@@ -476,11 +473,9 @@ impl Mint {
 
         if amount_to_pay + melt_quote.fee_reserve > inputs_amount_quote_unit {
             tracing::debug!(
-                "Not enough inputs provided: {} {} needed {} {}",
-                inputs_amount_quote_unit,
-                melt_quote.unit,
-                amount_to_pay,
-                melt_quote.unit
+                "Not enough inputs provided: {} needed {}",
+                inputs_amount_quote_unit.display(&melt_quote.unit),
+                amount_to_pay.display(&melt_quote.unit)
             );
 
             return Err(Error::TransactionUnbalanced(
@@ -685,7 +680,7 @@ impl Mint {
                         if pay.status == MeltQuoteState::Unknown
                             || pay.status == MeltQuoteState::Failed =>
                     {
-                        tracing::warn!("Got {} status when paying melt quote {} for {} {}. Checking with backend...", pay.status, quote.id, quote.amount, quote.unit);
+                        tracing::warn!("Got {} status when paying melt quote {} for {}. Checking with backend...", pay.status, quote.id, quote.amount.display(&quote.unit));
                         let check_response = if let Ok(ok) =
                             check_payment_state(Arc::clone(ln), &pay.payment_lookup_id).await
                         {
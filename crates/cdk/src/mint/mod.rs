@@ -29,6 +29,7 @@ use crate::cdk_payment::{self, MintPayment};
 use crate::error::Error;
 use crate::fees::calculate_fee;
 use crate::nuts::*;
+use crate::util::clock::{Clock, SystemClock};
 #[cfg(feature = "auth")]
 use crate::OidcClient;
 use crate::{cdk_database, Amount};
@@ -76,6 +77,12 @@ pub struct Mint {
     keysets: Arc<ArcSwap<Vec<SignatoryKeySet>>>,
     /// Background task management
     task_state: Arc<Mutex<TaskState>>,
+    /// Time source for quote expiry
+    clock: Arc<dyn Clock>,
+    /// Period of the background removal of expired mining share quotes
+    mining_share_sweep_interval: Option<Duration>,
+    /// Amount a mining share quote may request for its share difficulty
+    share_amount_policy: Option<fn(u64) -> Amount>,
 }
 
 /// State for managing background tasks
@@ -85,6 +92,8 @@ struct TaskState {
     shutdown_notify: Option<Arc<Notify>>,
     /// Handle to the main supervisor task
     supervisor_handle: Option<JoinHandle<Result<(), Error>>>,
+    /// Handle to the expired mining share quote sweeper
+    sweeper_handle: Option<JoinHandle<()>>,
 }
 
 impl Mint {
@@ -194,6 +203,9 @@ impl Mint {
             auth_localstore,
             keysets: Arc::new(ArcSwap::new(keysets.keysets.into())),
             task_state: Arc::new(Mutex::new(TaskState::default())),
+            clock: Arc::new(SystemClock),
+            mining_share_sweep_interval: None,
+            share_amount_policy: None,
         })
     }
 
@@ -267,6 +279,15 @@ impl Mint {
             .await
         });
 
+        // Spawn the expired mining share quote sweeper, off unless configured
+        if let Some(period) = self.mining_share_sweep_interval {
+            let mint = self.clone();
+            let shutdown = shutdown_notify.clone();
+            task_state.sweeper_handle = Some(tokio::spawn(async move {
+                mint.sweep_mining_share_quotes(period, shutdown).await
+            }));
+        }
+
         // Store the handles
         task_state.shutdown_notify = Some(shutdown_notify);
         task_state.supervisor_handle = Some(supervisor_handle);
@@ -293,6 +314,7 @@ impl Mint {
         // Take the handles out of the state
         let shutdown_notify = task_state.shutdown_notify.take();
         let supervisor_handle = task_state.supervisor_handle.take();
+        let sweeper_handle = task_state.sweeper_handle.take();
 
         // If nothing to stop, return early
         let (shutdown_notify, supervisor_handle) = match (shutdown_notify, supervisor_handle) {
@@ -312,6 +334,12 @@ impl Mint {
         // Signal shutdown
         shutdown_notify.notify_waiters();
 
+        if let Some(sweeper_handle) = sweeper_handle {
+            if let Err(join_error) = sweeper_handle.await {
+                tracing::error!("Mining share quote sweeper panicked: {:?}", join_error);
+            }
+        }
+
         // Wait for supervisor to complete
         let result = match supervisor_handle.await {
             Ok(result) => {
@@ -525,6 +553,25 @@ impl Mint {
         Ok(())
     }
 
+    /// Removes expired mining share quotes every `period` until shutdown
+    async fn sweep_mining_share_quotes(&self, period: Duration, shutdown: Arc<Notify>) {
+        let shutdown = shutdown.notified();
+        tokio::pin!(shutdown);
+        let mut interval = tokio::time::interval(period);
+
+        loop {
+            tokio::select! {
+                _ = &mut shutdown => break,
+                _ = interval.tick() => {
+                    let now = self.clock.now_unix();
+                    if let Err(e) = self.expire_stale_mining_share_quotes(now).await {
+                        tracing::warn!("Could not remove expired mining share quotes: {}", e);
+                    }
+                }
+            }
+        }
+    }
+
     /// Handles payment waiting for a single processor
     #[instrument(skip_all)]
     async fn wait_for_processor_payments(
@@ -585,17 +632,14 @@ impl Mint {
 
         let mut tx = localstore.begin_transaction().await?;
 
+        let mut paid = None;
         if let Ok(Some(mint_quote)) = tx
             .get_mint_quote_by_request_lookup_id(&wait_payment_response.payment_identifier)
             .await
         {
-            Self::handle_mint_quote_payment(
-                &mut tx,
-                &mint_quote,
-                wait_payment_response,
-                pubsub_manager,
-            )
-            .await?;
+            paid = Self::handle_mint_quote_payment(&mut tx, &mint_quote, wait_payment_response)
+                .await?
+                .map(|total_paid| (mint_quote, total_paid));
         } else {
             tracing::warn!(
                 "Could not get request for request lookup id {:?}",
@@ -604,17 +648,25 @@ impl Mint {
         }
 
         tx.commit().await?;
+
+        if let Some((mint_quote, total_paid)) = paid {
+            pubsub_manager.mint_quote_payment(&mint_quote, total_paid);
+        }
+
         Ok(())
     }
 
     /// Handle payment for a specific mint quote (extracted from pay_mint_quote)
+    ///
+    /// Returns the total paid of the quote when the payment was recorded.
+    /// Subscribers are not notified, callers publish the payment once the
+    /// transaction is committed.
     #[instrument(skip_all)]
     async fn handle_mint_quote_payment(
         tx: &mut Box<dyn database::MintTransaction<'_, database::Error> + Send + Sync + '_>,
         mint_quote: &MintQuote,
         wait_payment_response: WaitPaymentResponse,
-        pubsub_manager: &Arc<PubSubManager>,
-    ) -> Result<(), Error> {
+    ) -> Result<Option<Amount>, Error> {
         tracing::debug!(
             "Received payment notification of {} {} for mint quote {} with payment id {}",
             wait_payment_response.payment_amount,
@@ -624,6 +676,22 @@ impl Mint {
         );
 
         let quote_state = mint_quote.state();
+        if quote_state == MintQuoteState::Converted {
+            tracing::warn!(
+                "Received payment for mint quote {} after it was converted, not crediting it",
+                mint_quote.id
+            );
+            return Ok(None);
+        }
+
+        if quote_state == MintQuoteState::Cancelled {
+            tracing::warn!(
+                "Received payment for mint quote {} after it was cancelled, not crediting it",
+                mint_quote.id
+            );
+            return Ok(None);
+        }
+
         if !mint_quote
             .payment_ids()
             .contains(&&wait_payment_response.payment_id)
@@ -657,13 +725,13 @@ impl Mint {
                         wait_payment_response.payment_id,
                     )
                     .await?;
-                pubsub_manager.mint_quote_payment(mint_quote, total_paid);
+                return Ok(Some(total_paid));
             }
         } else {
             tracing::info!("Received payment notification for already seen payment.");
         }
 
-        Ok(())
+        Ok(None)
     }
 
     /// Fee required for proof set
@@ -821,6 +889,14 @@ impl Mint {
             return Err(Error::RequestAlreadyPaid);
         }
 
+        if mint_quote.state() == MintQuoteState::Converted {
+            return Err(Error::ConvertedQuote);
+        }
+
+        if mint_quote.state() == MintQuoteState::Cancelled {
+            return Err(Error::CancelledQuote);
+        }
+
         let inputs_amount_quote_unit = melt_request.inputs_amount().map_err(|_| {
             tracing::error!("Proof inputs in melt quote overflowed");
             Error::AmountOverflow
@@ -980,6 +1056,7 @@ mod tests {
 
     use std::str::FromStr;
 
+    use cdk_signatory::signatory::{RotateKeyArguments, SignatoryKeysets};
     use cdk_sqlite::mint::memory::new_with_state;
 
     use super::*;
@@ -1088,6 +1165,110 @@ mod tests {
         }
     }
 
+    /// Signatory reporting an extra keyset that reuses one key of the first keyset
+    struct KeyReuseSignatory(cdk_signatory::db_signatory::DbSignatory);
+
+    #[async_trait::async_trait]
+    impl Signatory for KeyReuseSignatory {
+        fn name(&self) -> String {
+            self.0.name()
+        }
+
+        async fn blind_sign(
+            &self,
+            blinded_messages: Vec<BlindedMessage>,
+        ) -> Result<Vec<BlindSignature>, Error> {
+            self.0.blind_sign(blinded_messages).await
+        }
+
+        async fn verify_proofs(&self, proofs: Vec<Proof>) -> Result<(), Error> {
+            self.0.verify_proofs(proofs).await
+        }
+
+        async fn keysets(&self) -> Result<SignatoryKeysets, Error> {
+            let mut keysets = self.0.keysets().await?;
+
+            let mut reused = keysets.keysets[0].clone();
+            let keys = reused
+                .keys
+                .iter()
+                .map(|(amount, key)| {
+                    let key = if *amount == Amount::from(1) {
+                        *key
+                    } else {
+                        SecretKey::generate().public_key()
+                    };
+                    (*amount, key)
+                })
+                .collect();
+            reused.keys = Keys::new(keys);
+            reused.id = Id::v1_from_keys(&reused.keys);
+            reused.active = false;
+            keysets.keysets.push(reused);
+
+            Ok(keysets)
+        }
+
+        async fn rotate_keyset(&self, args: RotateKeyArguments) -> Result<SignatoryKeySet, Error> {
+            self.0.rotate_keyset(args).await
+        }
+    }
+
+    #[tokio::test]
+    async fn mint_mod_check_keyset_key_reuse() {
+        let mut supported_units = HashMap::new();
+        supported_units.insert(CurrencyUnit::default(), (0, 32));
+
+        let config = MintConfig::<'_> {
+            supported_units: supported_units.clone(),
+            ..Default::default()
+        };
+        let mint = create_mint(config).await;
+
+        mint.rotate_keyset(CurrencyUnit::default(), 32, 0)
+            .await
+            .expect("test");
+        assert!(mint.check_keyset_key_reuse().is_empty());
+
+        let localstore = Arc::new(
+            new_with_state(
+                HashMap::new(),
+                vec![],
+                vec![],
+                vec![],
+                vec![],
+                vec![],
+                MintInfo::default(),
+            )
+            .await
+            .unwrap(),
+        );
+        let signatory = cdk_signatory::db_signatory::DbSignatory::new(
+            localstore.clone(),
+            &[],
+            supported_units,
+            HashMap::new(),
+        )
+        .await
+        .expect("Failed to create signatory");
+        let mint = Mint::new(
+            MintInfo::default(),
+            Arc::new(KeyReuseSignatory(signatory)),
+            localstore,
+            HashMap::new(),
+        )
+        .await
+        .unwrap();
+
+        let keysets = mint.keysets.load();
+        let shared = mint.check_keyset_key_reuse();
+
+        assert_eq!(shared.len(), 1);
+        assert_eq!(shared[0].keyset_id, keysets[0].id);
+        assert_eq!(shared[0].other_keyset_id, keysets[1].id);
+        assert_eq!(shared[0].amounts, vec![Amount::from(1)]);
+    }
+
     #[tokio::test]
     async fn test_mint_keyset_gen() {
         let seed = bip39::Mnemonic::from_str(
@@ -5,9 +5,30 @@
 
 use super::{Error, Mint};
 use crate::mint::{MeltQuote, MeltQuoteState, PaymentMethod};
+use crate::nuts::{find_shared_keys, SharedKeys};
 use crate::types::PaymentProcessorKey;
 
 impl Mint {
+    /// Checks that no amount key is shared between keysets
+    ///
+    /// Keysets reusing keys of another keyset are logged and returned. Reused
+    /// keys link proofs across keysets, so the keysets should be rotated.
+    pub fn check_keyset_key_reuse(&self) -> Vec<SharedKeys> {
+        let keysets = self.keysets.load();
+        let shared = find_shared_keys(keysets.iter().map(|keyset| (keyset.id, &keyset.keys)));
+
+        for reuse in &shared {
+            tracing::warn!(
+                "Keyset {} reuses {} amount keys of keyset {}",
+                reuse.other_keyset_id,
+                reuse.amounts.len(),
+                reuse.keyset_id
+            );
+        }
+
+        shared
+    }
+
     /// Checks the states of melt quotes that are **PENDING** or **UNKNOWN** to the mint with the ln node
     pub async fn check_pending_melt_quotes(&self) -> Result<(), Error> {
         // TODO: We should have a db query to do this filtering
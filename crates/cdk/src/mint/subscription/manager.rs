@@ -5,8 +5,12 @@ use std::sync::Arc;
 use cdk_common::database::{self, MintDatabase};
 use cdk_common::mint::MintQuote;
 use cdk_common::nut17::Notification;
+use cdk_common::nuts::nutXX;
 use cdk_common::quote_id::QuoteId;
-use cdk_common::{Amount, MintQuoteBolt12Response, NotificationPayload, PaymentMethod};
+use cdk_common::{
+    Amount, MintQuoteBolt12Response, MintQuoteMiningShareResponse, NotificationPayload,
+    PaymentMethod,
+};
 
 use super::OnSubscription;
 use crate::nuts::{
@@ -64,6 +68,9 @@ impl PubSubManager {
                     total_issued,
                 );
             }
+            PaymentMethod::MiningShare => {
+                self.mint_quote_mining_share_status(mint_quote.clone(), total_issued);
+            }
             _ => {
                 // We don't send ws updates for unknown methods
             }
@@ -83,6 +90,9 @@ impl PubSubManager {
                     mint_quote.amount_issued(),
                 );
             }
+            PaymentMethod::MiningShare => {
+                self.mint_quote_mining_share_status(mint_quote.clone(), mint_quote.amount_issued());
+            }
             _ => {
                 // We don't send ws updates for unknown methods
             }
@@ -118,6 +128,24 @@ impl PubSubManager {
         }
     }
 
+    /// Helper function to emit a MintQuoteMiningShareResponse status
+    pub fn mint_quote_mining_share_status<E: TryInto<MintQuoteMiningShareResponse<QuoteId>>>(
+        &self,
+        quote: E,
+        amount_issued: Amount,
+    ) {
+        if let Ok(mut event) = quote.try_into() {
+            event.amount_issued = amount_issued;
+            if event.is_fully_issued() {
+                event.state = nutXX::QuoteState::Issued;
+            }
+
+            self.broadcast(event.into());
+        } else {
+            tracing::warn!("Could not convert quote to MintQuoteMiningShareResponse");
+        }
+    }
+
     /// Helper function to emit a MeltQuoteBolt11Response status
     pub fn melt_quote_status<E: Into<MeltQuoteBolt11Response<QuoteId>>>(
         &self,
@@ -142,7 +170,7 @@ mod test {
     use tokio::time::sleep;
 
     use super::*;
-    use crate::nuts::nut17::Kind;
+    use crate::nuts::nut17::{Error, Kind};
     use crate::nuts::{PublicKey, State};
     use crate::subscription::{IndexableParams, Params};
 
@@ -237,6 +265,43 @@ mod test {
         assert!(subscriptions[1].try_recv().is_err());
     }
 
+    #[tokio::test]
+    async fn reject_filters_of_other_kinds() {
+        let manager = PubSubManager::default();
+
+        let pubkey = "02194603ffa36356f4a56b7df9371fc3192472351453ec7398b8da8117e7c3e104";
+        let quote_id = "5a5c0f0b-3e2f-4a51-9f0e-6b1c1f2d8e41";
+
+        for (kind, filter) in [
+            (Kind::Bolt11MintQuote, pubkey),
+            (Kind::Bolt11MeltQuote, pubkey),
+            (Kind::Bolt12MintQuote, pubkey),
+            (Kind::MiningShareMintQuote, pubkey),
+            (Kind::ProofState, quote_id),
+        ] {
+            let result = manager
+                .try_subscribe::<IndexableParams>(
+                    Params {
+                        kind,
+                        filters: vec![filter.to_owned()],
+                        id: "uno".into(),
+                    }
+                    .into(),
+                )
+                .await;
+
+            match result {
+                Err(Error::InvalidFilters { kind: k, filters }) => {
+                    assert_eq!(k, kind);
+                    assert_eq!(filters, vec![filter.to_owned()]);
+                }
+                _ => panic!("{kind:?} accepted {filter}"),
+            }
+        }
+
+        assert_eq!(0, manager.active_subscriptions());
+    }
+
     #[test]
     fn parsing_request() {
         let json = r#"{"kind":"proof_state","filters":["x"],"subId":"uno"}"#;
@@ -5,9 +5,12 @@ use std::sync::Arc;
 
 use cdk_common::database::{self, MintDatabase};
 use cdk_common::nut17::Notification;
+use cdk_common::payment::PaymentIdentifier;
 use cdk_common::pub_sub::OnNewSubscription;
 use cdk_common::quote_id::QuoteId;
-use cdk_common::{MintQuoteBolt12Response, NotificationPayload, PaymentMethod};
+use cdk_common::{
+    MintQuoteBolt12Response, MintQuoteMiningShareResponse, NotificationPayload, PaymentMethod,
+};
 
 use crate::nuts::{MeltQuoteBolt11Response, MintQuoteBolt11Response, ProofState, PublicKey};
 
@@ -34,6 +37,17 @@ impl OnNewSubscription for OnSubscription {
             return Ok(vec![]);
         };
 
+        // Mining share quotes can be addressed by the header hash of their share
+        let share_lookup_ids = request
+            .iter()
+            .filter_map(|idx| match idx {
+                Notification::MintQuoteMiningShareHash(header_hash) => {
+                    Some(PaymentIdentifier::MiningShareHash(header_hash.to_string()))
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
         let mut to_return = vec![];
         let mut public_keys: Vec<PublicKey> = Vec::new();
         let mut melt_queries = Vec::new();
@@ -57,9 +71,14 @@ impl OnNewSubscription for OnSubscription {
                 Notification::MintQuoteMiningShare(uuid) => {
                     mint_queries.push(datastore.get_mint_quote(uuid))
                 }
+                Notification::MintQuoteMiningShareHash(_) => {}
             }
         }
 
+        for request_lookup_id in &share_lookup_ids {
+            mint_queries.push(datastore.get_mint_quote_by_request_lookup_id(request_lookup_id));
+        }
+
         if !melt_queries.is_empty() {
             to_return.extend(
                 futures::future::try_join_all(melt_queries)
@@ -97,8 +116,14 @@ impl OnNewSubscription for OnSubscription {
                                         Err(_) => None,
                                     },
                                     PaymentMethod::Custom(_) => None,
-                                    // TODO implement websocket support
-                                    PaymentMethod::MiningShare => None,
+                                    PaymentMethod::MiningShare => match x.try_into() {
+                                        Ok(response) => {
+                                            let response: MintQuoteMiningShareResponse<QuoteId> =
+                                                response;
+                                            Some(response.into())
+                                        }
+                                        Err(_) => None,
+                                    },
                                 })
                             })
                             .collect::<Vec<_>>()
@@ -0,0 +1,91 @@
+//! Wallet prelude
+//!
+//! Canonical paths of the types a wallet integrator needs, so a single
+//! `use cdk::prelude::*;` replaces imports from `cdk`, `cdk_common` and
+//! `cashu`. The types are the same ones found under `cdk::nuts`,
+//! `cdk::wallet` and `cdk::types`, re-exported once.
+//!
+//! Names that exist for both the mint and the wallet resolve to the wallet
+//! version: [`MintQuote`] and [`MeltQuote`] are the quotes stored by the
+//! wallet, not `cdk::mint::MintQuote` and `cdk::mint::MeltQuote`. Quote states
+//! are exported as [`MintQuoteState`], [`MeltQuoteState`] and
+//! [`MiningShareQuoteState`].
+//!
+//! ```no_run
+//! #![deny(deprecated)]
+//! use std::sync::Arc;
+//!
+//! use cdk::prelude::*;
+//! use cdk_sqlite::wallet::memory;
+//!
+//! async fn wallet_flow() -> anyhow::Result<()> {
+//!     let localstore: Arc<dyn WalletDatabase<Err = DatabaseError> + Send + Sync> =
+//!         Arc::new(memory::empty().await?);
+//!
+//!     let wallet: Wallet = WalletBuilder::new()
+//!         .mint_url("https://fake.thesimplekid.dev".parse::<MintUrl>()?)
+//!         .unit(CurrencyUnit::Sat)
+//!         .localstore(localstore)
+//!         .seed(rand::random::<[u8; 64]>())
+//!         .build()?;
+//!
+//!     let quote: MintQuote = wallet.mint_quote(Amount::from(100), None).await?;
+//!     let proofs: Proofs = wallet.mint(&quote.id, SplitTarget::default(), None).await?;
+//!     assert_eq!(proofs.total_amount()?, Amount::from(100));
+//!
+//!     let prepared: PreparedSend = wallet
+//!         .prepare_send(Amount::from(10), SendOptions::default())
+//!         .await?;
+//!     let token: Token = prepared.confirm(None).await?;
+//!
+//!     let received: Amount = wallet
+//!         .receive(&token.to_string(), ReceiveOptions::default())
+//!         .await?;
+//!     assert_eq!(received, Amount::from(10));
+//!
+//!     let unspent: Vec<ProofInfo> = wallet
+//!         .localstore
+//!         .get_proofs(None, None, Some(vec![State::Unspent]), None)
+//!         .await?;
+//!     assert!(!unspent.is_empty());
+//!
+//!     let result: Result<Amount, Error> = wallet.total_balance().await;
+//!     assert_eq!(result?, Amount::from(100));
+//!     Ok(())
+//! }
+//! ```
+//!
+//! # Deprecated paths
+//!
+//! Duplicate paths to the types above are deprecated. The root
+//! `cdk::HttpClient` is one of them:
+//!
+//! ```compile_fail
+//! #![deny(deprecated)]
+//! use cdk::HttpClient;
+//! ```
+//!
+//! The canonical path does not warn:
+//!
+//! ```
+//! #![deny(deprecated)]
+//! use cdk::wallet::HttpClient;
+//! ```
+
+pub use crate::amount::SplitTarget;
+pub use crate::cdk_database::{Error as DatabaseError, WalletDatabase};
+pub use crate::error::{Error, ErrorCode, ErrorResponse};
+pub use crate::mint_url::MintUrl;
+pub use crate::nuts::{
+    Conditions, CurrencyUnit, Id, KeySetInfo, MeltOptions, MeltQuoteMiningShareRequest,
+    MeltQuoteMiningShareResponse, MeltQuoteState, MiningShareQuoteState,
+    MintQuoteMiningShareRequest, MintQuoteMiningShareResponse, MintQuoteState, PaymentMethod,
+    Proof, Proofs, ProofsMethods, PublicKey, SecretKey, SpendingConditions, State, Token,
+};
+pub use crate::types::{Melted, ProofInfo};
+pub use crate::wallet::types::{Transaction, TransactionDirection, TransactionId};
+pub use crate::wallet::{
+    HttpClient, MeltQuote, MintConnector, MintQuote, MultiMintWallet, PreparedSend, ReceiveOptions,
+    SendKind, SendMemo, SendOptions, Wallet, WalletBuilder,
+};
+pub use crate::Amount;
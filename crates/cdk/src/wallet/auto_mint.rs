@@ -0,0 +1,308 @@
+//! Auto mint
+//!
+//! Mints the locally stored bolt11 and mining share quotes of a wallet as soon
+//! as the mint reports them paid, so proofs show up without the app polling.
+//! Quote states are followed over NUT-17 subscriptions, which the subscription
+//! manager renews when the websocket reconnects.
+
+use std::collections::HashSet;
+use std::time::Duration;
+
+use cdk_common::amount::SplitTarget;
+use cdk_common::{MiningShareQuoteState, MintQuoteState, NotificationPayload, PaymentMethod};
+use futures::stream::FuturesUnordered;
+use futures::StreamExt;
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+use tracing::instrument;
+
+use super::subscription::ActiveSubscription;
+use crate::nuts::nut00::ProofsMethods;
+use crate::{Amount, Error, Wallet, WalletSubscription};
+
+/// How often the localstore is checked for quotes created while running
+const AUTO_MINT_RESCAN_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Events sent by a [`Wallet::start_auto_mint`] task
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AutoMintEvent {
+    /// Proofs were minted and stored for a paid quote
+    Minted {
+        /// Quote id
+        quote_id: String,
+        /// Payment method of the quote
+        payment_method: PaymentMethod,
+        /// Amount minted
+        amount: Amount,
+    },
+    /// Minting a paid quote failed
+    Failed {
+        /// Quote id
+        quote_id: String,
+        /// Why minting failed
+        error: String,
+    },
+}
+
+/// Handle of a running auto mint task
+///
+/// Dropping the handle leaves the task running until [`Self::stop`] is called
+/// on another handle or the runtime shuts down.
+#[derive(Debug)]
+pub struct AutoMintHandle {
+    events: broadcast::Sender<AutoMintEvent>,
+    cancel_token: CancellationToken,
+    task: JoinHandle<()>,
+}
+
+impl AutoMintHandle {
+    /// Receive the events of the task sent from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<AutoMintEvent> {
+        self.events.subscribe()
+    }
+
+    /// Stop the task and wait for it to finish
+    ///
+    /// A mint in progress is completed first.
+    pub async fn stop(self) {
+        self.cancel_token.cancel();
+        if let Err(err) = self.task.await {
+            tracing::warn!("Auto mint task did not finish cleanly: {}", err);
+        }
+    }
+}
+
+/// State of a running auto mint task
+struct AutoMint {
+    wallet: Wallet,
+    events: broadcast::Sender<AutoMintEvent>,
+    /// Quotes covered by the current subscriptions
+    watched: HashSet<String>,
+    subscriptions: Vec<ActiveSubscription>,
+}
+
+impl AutoMint {
+    /// Subscribe to the pending quotes of the wallet if any are not watched yet
+    ///
+    /// The subscriptions are replaced rather than extended, the mint sends the
+    /// current state of every quote of a new subscription.
+    async fn rescan(&mut self) -> Result<(), Error> {
+        let pending: Vec<_> = self
+            .wallet
+            .get_active_mint_quotes()
+            .await?
+            .into_iter()
+            .filter(|quote| {
+                matches!(
+                    quote.payment_method,
+                    PaymentMethod::Bolt11 | PaymentMethod::MiningShare
+                )
+            })
+            .collect();
+
+        if pending.iter().all(|quote| self.watched.contains(&quote.id)) {
+            return Ok(());
+        }
+
+        let (bolt11, mining_share): (Vec<_>, Vec<_>) = pending
+            .into_iter()
+            .partition(|quote| quote.payment_method == PaymentMethod::Bolt11);
+        let bolt11: Vec<String> = bolt11.into_iter().map(|quote| quote.id).collect();
+        let mining_share: Vec<String> = mining_share.into_iter().map(|quote| quote.id).collect();
+
+        tracing::debug!(
+            "Auto mint watching {} bolt11 and {} mining share quotes",
+            bolt11.len(),
+            mining_share.len()
+        );
+
+        self.watched = bolt11.iter().chain(&mining_share).cloned().collect();
+        self.subscriptions.clear();
+        if !bolt11.is_empty() {
+            self.subscriptions.push(
+                self.wallet
+                    .subscribe(WalletSubscription::Bolt11MintQuoteState(bolt11))
+                    .await,
+            );
+        }
+        if !mining_share.is_empty() {
+            self.subscriptions.push(
+                self.wallet
+                    .subscribe(WalletSubscription::MiningShareMintQuoteState(mining_share))
+                    .await,
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Wait for the next notification of any subscription
+    async fn next_notification(&mut self) -> Option<NotificationPayload<String>> {
+        if self.subscriptions.is_empty() {
+            return std::future::pending().await;
+        }
+
+        let mut notifications: FuturesUnordered<_> = self
+            .subscriptions
+            .iter_mut()
+            .map(|subscription| subscription.recv())
+            .collect();
+
+        notifications.next().await.flatten()
+    }
+
+    /// Mint the quote a notification reports as paid
+    async fn handle(&self, notification: NotificationPayload<String>) {
+        let quote_id = match notification {
+            NotificationPayload::MintQuoteBolt11Response(response)
+                if response.state == MintQuoteState::Paid =>
+            {
+                response.quote
+            }
+            NotificationPayload::MintQuoteMiningShareResponse(response)
+                if response.state == MiningShareQuoteState::Paid =>
+            {
+                response.quote
+            }
+            _ => return,
+        };
+
+        let event = match self.mint(&quote_id).await {
+            Ok(Some((payment_method, amount))) => AutoMintEvent::Minted {
+                quote_id,
+                payment_method,
+                amount,
+            },
+            Ok(None) => return,
+            Err(err) => {
+                tracing::warn!("Auto mint of quote {} failed: {}", quote_id, err);
+                AutoMintEvent::Failed {
+                    quote_id,
+                    error: err.to_string(),
+                }
+            }
+        };
+
+        // Nobody listening is not an error
+        let _ = self.events.send(event);
+    }
+
+    /// Mint a paid quote with the default split target
+    ///
+    /// Returns `None` if the quote was minted in the meantime, e.g. by a manual
+    /// mint. Those reuse the premint counter stored on the quote, so a mint
+    /// racing this one derives the same outputs instead of new ones.
+    async fn mint(&self, quote_id: &str) -> Result<Option<(PaymentMethod, Amount)>, Error> {
+        let Some(quote) = self.wallet.localstore.get_mint_quote(quote_id).await? else {
+            return Ok(None);
+        };
+        if quote.state == MintQuoteState::Issued || quote.amount_mintable() == Amount::ZERO {
+            return Ok(None);
+        }
+
+        let proofs = match quote.payment_method {
+            PaymentMethod::Bolt11 => {
+                self.wallet
+                    .mint_with_unit(&quote.id, SplitTarget::default(), None, &quote.unit)
+                    .await
+            }
+            PaymentMethod::MiningShare => {
+                let keyset_id = quote.keyset_id.ok_or(Error::Custom(
+                    "Mining share quote has no keyset".to_string(),
+                ))?;
+                let secret_key = quote.secret_key.clone().ok_or(Error::Custom(
+                    "Mining share quote has no secret key".to_string(),
+                ))?;
+                self.wallet
+                    .mint_mining_share_with_unit(
+                        &quote.id,
+                        Amount::ZERO,
+                        keyset_id,
+                        secret_key,
+                        &quote.unit,
+                        None,
+                    )
+                    .await
+            }
+            _ => return Err(Error::UnsupportedPaymentMethod),
+        };
+
+        match proofs {
+            Ok(proofs) => Ok(Some((quote.payment_method, proofs.total_amount()?))),
+            Err(err) => {
+                // Lost the race against a manual mint
+                let minted = self
+                    .wallet
+                    .localstore
+                    .get_mint_quote(quote_id)
+                    .await?
+                    .is_none_or(|quote| quote.amount_mintable() == Amount::ZERO);
+                if minted {
+                    return Ok(None);
+                }
+
+                Err(err)
+            }
+        }
+    }
+
+    async fn run(mut self, cancel_token: CancellationToken) {
+        let mut rescan = tokio::time::interval(AUTO_MINT_RESCAN_INTERVAL);
+
+        loop {
+            tokio::select! {
+                _ = cancel_token.cancelled() => break,
+                _ = rescan.tick() => {
+                    if let Err(err) = self.rescan().await {
+                        tracing::warn!("Auto mint could not load pending quotes: {}", err);
+                    }
+                }
+                notification = self.next_notification() => match notification {
+                    Some(notification) => self.handle(notification).await,
+                    // Subscription closed, renew it with the next rescan
+                    None => {
+                        self.watched.clear();
+                        self.subscriptions.clear();
+                    }
+                },
+            }
+        }
+
+        tracing::debug!("Auto mint stopped");
+    }
+}
+
+impl Wallet {
+    /// Mint the wallet's bolt11 and mining share quotes as they get paid
+    ///
+    /// Starts a background task that subscribes to every unissued, unexpired
+    /// quote of this mint stored in the localstore, and picks up quotes
+    /// created while it runs. When the mint reports a quote paid, it is
+    /// minted with the default split target and an [`AutoMintEvent`] is sent
+    /// to the receivers of [`AutoMintHandle::subscribe`].
+    #[instrument(skip(self))]
+    pub fn start_auto_mint(&self) -> AutoMintHandle {
+        let (events, _) = broadcast::channel(100);
+        let cancel_token = CancellationToken::new();
+
+        let auto_mint = AutoMint {
+            wallet: self.clone(),
+            events: events.clone(),
+            watched: HashSet::new(),
+            subscriptions: Vec::new(),
+        };
+
+        #[cfg(target_arch = "wasm32")]
+        let task = tokio::task::spawn_local(auto_mint.run(cancel_token.clone()));
+
+        #[cfg(not(target_arch = "wasm32"))]
+        let task = tokio::spawn(auto_mint.run(cancel_token.clone()));
+
+        AutoMintHandle {
+            events,
+            cancel_token,
+            task,
+        }
+    }
+}
@@ -1,33 +1,160 @@
+use std::collections::{BTreeMap, BTreeSet};
+
 use tracing::instrument;
 
-use crate::nuts::{nut00::ProofsMethods, State};
+use crate::nuts::{CurrencyUnit, Id, State};
+use crate::types::ProofSource;
+use crate::wallet::responses::BalanceResponse;
 use crate::{Amount, Error, Wallet};
 
+/// Unspent balance grouped by how the proofs were received
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BalanceOrigins {
+    /// Unit of the balance
+    pub unit: CurrencyUnit,
+    /// Unspent balance
+    pub total: Amount,
+    /// Unspent balance by the source of the proofs
+    pub by_source: BTreeMap<ProofSource, Amount>,
+    /// Unspent balance by keyset
+    pub by_keyset: BTreeMap<Id, Amount>,
+}
+
 impl Wallet {
     /// Total unspent balance of wallet
     #[instrument(skip(self))]
     pub async fn total_balance(&self) -> Result<Amount, Error> {
-        // Use the efficient balance query instead of fetching all proofs
-        let balance = self
-            .localstore
-            .get_balance(
-                Some(self.mint_url.clone()),
-                Some(self.unit.clone()),
-                Some(vec![State::Unspent]),
-            )
-            .await?;
-        Ok(Amount::from(balance))
+        self.total_balance_with_unit(&self.unit).await
+    }
+
+    /// Total unspent balance of wallet in a unit
+    #[instrument(skip(self))]
+    pub async fn total_balance_with_unit(&self, unit: &CurrencyUnit) -> Result<Amount, Error> {
+        self.balance_in_state(unit, State::Unspent).await
     }
 
     /// Total pending balance
     #[instrument(skip(self))]
     pub async fn total_pending_balance(&self) -> Result<Amount, Error> {
-        Ok(self.get_pending_proofs().await?.total_amount()?)
+        self.total_pending_balance_with_unit(&self.unit).await
+    }
+
+    /// Total pending balance in a unit
+    #[instrument(skip(self))]
+    pub async fn total_pending_balance_with_unit(
+        &self,
+        unit: &CurrencyUnit,
+    ) -> Result<Amount, Error> {
+        self.balance_in_state(unit, State::Pending).await
     }
 
     /// Total reserved balance
     #[instrument(skip(self))]
     pub async fn total_reserved_balance(&self) -> Result<Amount, Error> {
-        Ok(self.get_reserved_proofs().await?.total_amount()?)
+        self.total_reserved_balance_with_unit(&self.unit).await
+    }
+
+    /// Total reserved balance in a unit
+    #[instrument(skip(self))]
+    pub async fn total_reserved_balance_with_unit(
+        &self,
+        unit: &CurrencyUnit,
+    ) -> Result<Amount, Error> {
+        self.balance_in_state(unit, State::Reserved).await
+    }
+
+    /// Balance by proof state
+    #[instrument(skip(self))]
+    pub async fn balance_breakdown(&self) -> Result<BalanceResponse, Error> {
+        self.balance_breakdown_with_unit(&self.unit).await
+    }
+
+    /// Balance by proof state in a unit
+    #[instrument(skip(self))]
+    pub async fn balance_breakdown_with_unit(
+        &self,
+        unit: &CurrencyUnit,
+    ) -> Result<BalanceResponse, Error> {
+        BalanceResponse::new(
+            self.mint_url.clone(),
+            unit.clone(),
+            self.total_balance_with_unit(unit).await?,
+            self.total_pending_balance_with_unit(unit).await?,
+            self.total_reserved_balance_with_unit(unit).await?,
+        )
+    }
+
+    /// Balance by proof state of every unit the wallet holds proofs in
+    ///
+    /// The default unit is always included, the other units only if the
+    /// wallet has proofs in them. Ordered by unit.
+    #[instrument(skip(self))]
+    pub async fn balance_breakdowns(&self) -> Result<Vec<BalanceResponse>, Error> {
+        let mut units: BTreeSet<CurrencyUnit> = self
+            .localstore
+            .get_proofs(Some(self.mint_url.clone()), None, None, None)
+            .await?
+            .into_iter()
+            .map(|info| info.unit)
+            .collect();
+        units.insert(self.unit.clone());
+
+        let mut breakdowns = Vec::with_capacity(units.len());
+        for unit in units {
+            breakdowns.push(self.balance_breakdown_with_unit(&unit).await?);
+        }
+
+        Ok(breakdowns)
+    }
+
+    /// Unspent balance by proof source and by keyset
+    ///
+    /// Proofs stored before their source was recorded are counted as
+    /// [`ProofSource::Unknown`].
+    #[instrument(skip(self))]
+    pub async fn balance_by_origin(&self) -> Result<BalanceOrigins, Error> {
+        let mut origins = BalanceOrigins {
+            unit: self.unit.clone(),
+            ..Default::default()
+        };
+
+        for info in self
+            .localstore
+            .get_proofs(
+                Some(self.mint_url.clone()),
+                Some(self.unit.clone()),
+                Some(vec![State::Unspent]),
+                None,
+            )
+            .await?
+        {
+            let amount = info.proof.amount;
+            origins.total = origins
+                .total
+                .checked_add(amount)
+                .ok_or(Error::AmountOverflow)?;
+
+            let by_source = origins.by_source.entry(info.source).or_default();
+            *by_source = by_source.checked_add(amount).ok_or(Error::AmountOverflow)?;
+
+            let by_keyset = origins.by_keyset.entry(info.proof.keyset_id).or_default();
+            *by_keyset = by_keyset.checked_add(amount).ok_or(Error::AmountOverflow)?;
+        }
+
+        Ok(origins)
+    }
+
+    /// Balance of the proofs in a state
+    async fn balance_in_state(&self, unit: &CurrencyUnit, state: State) -> Result<Amount, Error> {
+        // Use the efficient balance query instead of fetching all proofs
+        let balance = self
+            .localstore
+            .get_balance(
+                Some(self.mint_url.clone()),
+                Some(unit.clone()),
+                Some(vec![state]),
+            )
+            .await?;
+        Ok(Amount::from(balance))
     }
 }
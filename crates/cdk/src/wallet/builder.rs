@@ -12,9 +12,12 @@ use crate::cdk_database::WalletDatabase;
 use crate::error::Error;
 use crate::mint_url::MintUrl;
 use crate::nuts::CurrencyUnit;
+use crate::util::clock::{Clock, SystemClock};
 #[cfg(feature = "auth")]
 use crate::wallet::auth::AuthWallet;
-use crate::wallet::{HttpClient, MintConnector, SubscriptionManager, Wallet};
+use crate::wallet::{
+    HttpClient, HttpClientOptions, MintConnector, SelectionStrategy, SubscriptionManager, Wallet,
+};
 
 /// Builder for creating a new [`Wallet`]
 #[derive(Debug)]
@@ -28,6 +31,12 @@ pub struct WalletBuilder {
     seed: Option<[u8; 64]>,
     use_http_subscription: bool,
     client: Option<Arc<dyn MintConnector + Send + Sync>>,
+    http_client_options: HttpClientOptions,
+    require_payment_preimage: bool,
+    strict_amounts: bool,
+    selection_strategy: SelectionStrategy,
+    max_inputs: Option<usize>,
+    clock: Option<Arc<dyn Clock>>,
 }
 
 impl Default for WalletBuilder {
@@ -41,7 +50,13 @@ impl Default for WalletBuilder {
             auth_wallet: None,
             seed: None,
             client: None,
+            http_client_options: HttpClientOptions::default(),
             use_http_subscription: false,
+            require_payment_preimage: false,
+            strict_amounts: false,
+            selection_strategy: SelectionStrategy::default(),
+            max_inputs: None,
+            clock: None,
         }
     }
 }
@@ -111,6 +126,59 @@ impl WalletBuilder {
         self
     }
 
+    /// Set the retry policy of the default HTTP client
+    ///
+    /// Ignored when a custom client is set with [`WalletBuilder::client`].
+    pub fn http_client_options(mut self, options: HttpClientOptions) -> Self {
+        self.http_client_options = options;
+        self
+    }
+
+    /// Require the mint to return a payment preimage for paid bolt11 melts
+    ///
+    /// Some internal settlements legitimately complete without a preimage, so by
+    /// default a missing preimage is recorded on the transaction but accepted.
+    pub fn require_payment_preimage(mut self, require: bool) -> Self {
+        self.require_payment_preimage = require;
+        self
+    }
+
+    /// Refuse the monetary methods taking amounts without unit
+    ///
+    /// Callers then have to use the `_checked` variants taking a
+    /// [`UnitAmount`](crate::amount::UnitAmount), which are checked against the
+    /// unit of the operation.
+    pub fn strict_amounts(mut self, strict: bool) -> Self {
+        self.strict_amounts = strict;
+        self
+    }
+
+    /// Set the order in which proofs are selected for sends, swaps and melts
+    ///
+    /// Defaults to [`SelectionStrategy::ExactMatchPreferred`].
+    pub fn selection_strategy(mut self, strategy: SelectionStrategy) -> Self {
+        self.selection_strategy = strategy;
+        self
+    }
+
+    /// Limit the number of proofs used as inputs of sends, swaps and melts
+    ///
+    /// When a selection would take more proofs, the selected proofs are first
+    /// consolidated into fewer ones. Without a limit selections are not
+    /// checked.
+    pub fn max_inputs(mut self, max_inputs: usize) -> Self {
+        self.max_inputs = Some(max_inputs);
+        self
+    }
+
+    /// Set the clock used for quote expiry decisions
+    ///
+    /// Defaults to [`SystemClock`].
+    pub fn clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = Some(clock);
+        self
+    }
+
     /// Set auth CAT (Clear Auth Token)
     #[cfg(feature = "auth")]
     pub fn set_auth_cat(mut self, cat: String) -> Self {
@@ -144,14 +212,17 @@ impl WalletBuilder {
             None => {
                 #[cfg(feature = "auth")]
                 {
-                    Arc::new(HttpClient::new(mint_url.clone(), self.auth_wallet.clone()))
-                        as Arc<dyn MintConnector + Send + Sync>
+                    Arc::new(
+                        HttpClient::new(mint_url.clone(), self.auth_wallet.clone())
+                            .with_options(self.http_client_options),
+                    ) as Arc<dyn MintConnector + Send + Sync>
                 }
 
                 #[cfg(not(feature = "auth"))]
                 {
-                    Arc::new(HttpClient::new(mint_url.clone()))
-                        as Arc<dyn MintConnector + Send + Sync>
+                    Arc::new(
+                        HttpClient::new(mint_url.clone()).with_options(self.http_client_options),
+                    ) as Arc<dyn MintConnector + Send + Sync>
                 }
             }
         };
@@ -166,6 +237,14 @@ impl WalletBuilder {
             seed,
             client: client.clone(),
             subscription: SubscriptionManager::new(client, self.use_http_subscription),
+            require_payment_preimage: self.require_payment_preimage,
+            strict_amounts: self.strict_amounts,
+            selection_strategy: self.selection_strategy,
+            max_inputs: self.max_inputs,
+            clock: self.clock.unwrap_or_else(|| Arc::new(SystemClock)),
+            keyset_trust: Default::default(),
+            quote_states: Default::default(),
+            mint_aliases: Default::default(),
         })
     }
 }
@@ -0,0 +1,310 @@
+//! Proof consolidation
+//!
+//! Mining share minting leaves a wallet with many small proofs, which makes
+//! selecting proofs for a send slow. Consolidation swaps the smallest proofs
+//! for the minimal power of two split of their value until the wallet holds no
+//! more than a target number of proofs.
+//!
+//! The same swaps keep selections for sends, swaps and melts within the max
+//! inputs of the wallet.
+
+use std::collections::HashSet;
+
+use tracing::instrument;
+
+use crate::amount::SplitTarget;
+use crate::dhke::construct_proofs;
+use crate::nuts::nut00::ProofsMethods;
+use crate::nuts::{nut10, Proof, Proofs, State};
+use crate::types::{ProofInfo, ProofSource, Transaction, TransactionDirection};
+use crate::util::unix_time;
+use crate::wallet::SelectionStrategy;
+use crate::{ensure_cdk, Amount, Error, Wallet};
+
+/// Options for [`Wallet::consolidate_proofs`]
+#[derive(Debug, Clone)]
+pub struct ConsolidateOptions {
+    /// Most proofs used as inputs of one swap
+    pub max_inputs_per_swap: usize,
+    /// Stop before a swap would take the fees paid above this amount
+    pub fee_cap: Option<Amount>,
+}
+
+impl Default for ConsolidateOptions {
+    fn default() -> Self {
+        Self {
+            max_inputs_per_swap: 100,
+            fee_cap: None,
+        }
+    }
+}
+
+/// Outcome of a proof consolidation
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConsolidateSummary {
+    /// Number of swaps completed
+    pub swaps_completed: usize,
+    /// Number of proofs swapped away
+    pub proofs_consolidated: usize,
+    /// Number of proofs received for them
+    pub proofs_created: usize,
+    /// Value of the proofs swapped away, fees included
+    pub amount_consolidated: Amount,
+    /// Fees paid to the mint
+    pub fees_paid: Amount,
+    /// Unspent proofs of the wallet's unit left afterwards
+    pub proofs_remaining: usize,
+    /// Whether the fee cap stopped the consolidation
+    pub fee_cap_reached: bool,
+}
+
+impl Wallet {
+    /// Swap the smallest unspent proofs for fewer, larger ones until the
+    /// wallet holds at most `target_max_proofs` proofs of its unit
+    ///
+    /// Proofs are swapped in chunks of at most
+    /// [`ConsolidateOptions::max_inputs_per_swap`], smallest first, and each
+    /// chunk is only as large as needed to reach the target. Locked proofs are
+    /// left as they are. Every swap is recorded as an outgoing transaction of
+    /// amount zero carrying the fee paid.
+    ///
+    /// Stops early, without an error, once the fee cap would be exceeded or
+    /// there is nothing left worth swapping.
+    #[instrument(skip(self, options))]
+    pub async fn consolidate_proofs(
+        &self,
+        target_max_proofs: usize,
+        options: ConsolidateOptions,
+    ) -> Result<ConsolidateSummary, Error> {
+        ensure_cdk!(
+            options.max_inputs_per_swap > 1,
+            Error::Custom("Consolidation needs at least two inputs per swap".to_string())
+        );
+
+        self.refresh_keysets().await?;
+
+        let mut unspent = self.get_unspent_proofs().await?;
+        let mut summary = ConsolidateSummary {
+            proofs_remaining: unspent.len(),
+            ..Default::default()
+        };
+
+        // Locked proofs cannot be swapped without their keys
+        unspent.retain(|proof| nut10::Secret::try_from(proof.secret.clone()).is_err());
+        unspent.sort_by_key(|proof| proof.amount);
+
+        let mut candidates = unspent.into_iter().peekable();
+        while summary.proofs_remaining > target_max_proofs && candidates.peek().is_some() {
+            // A swap returns at most one proof per bit of its value, so this
+            // many inputs always cover the excess
+            let excess = summary.proofs_remaining - target_max_proofs;
+            let chunk_size = options
+                .max_inputs_per_swap
+                .min(excess.saturating_add(u64::BITS as usize));
+            let chunk: Proofs = candidates.by_ref().take(chunk_size).collect();
+
+            let chunk_amount = chunk.total_amount()?;
+            let fee = self.get_proofs_fee(&chunk).await?;
+
+            if options
+                .fee_cap
+                .is_some_and(|cap| summary.fees_paid + fee > cap)
+            {
+                tracing::info!("Consolidation fee cap reached, stopping");
+                summary.fee_cap_reached = true;
+                break;
+            }
+
+            if chunk_amount <= fee {
+                // Not worth swapping, and larger proofs follow
+                continue;
+            }
+
+            let new_proofs = self
+                .swap_consolidated(chunk.clone(), chunk_amount - fee)
+                .await?
+                .len();
+
+            summary.swaps_completed += 1;
+            summary.proofs_consolidated += chunk.len();
+            summary.proofs_created += new_proofs;
+            summary.amount_consolidated += chunk_amount;
+            summary.fees_paid += fee;
+            summary.proofs_remaining = summary.proofs_remaining - chunk.len() + new_proofs;
+
+            tracing::debug!(
+                "Consolidated {} proofs into {}, fee {}",
+                chunk.len(),
+                new_proofs,
+                fee.display(&self.unit)
+            );
+        }
+
+        Ok(summary)
+    }
+
+    /// Select inputs from `available` with the selection strategy of the
+    /// wallet, keeping them within its max inputs
+    ///
+    /// `select` runs a selection over the given proofs and `inputs` lists the
+    /// proofs a selection spends. When the selection takes more proofs than
+    /// allowed, they are consolidated and the selection is run again, largest
+    /// first, over the consolidated proofs and the rest of `available`.
+    pub(crate) async fn select_within_max_inputs<T>(
+        &self,
+        available: Proofs,
+        select: impl Fn(Proofs, SelectionStrategy) -> Result<T, Error>,
+        inputs: impl Fn(&T) -> Proofs,
+    ) -> Result<T, Error> {
+        let Some(max_inputs) = self.max_inputs else {
+            return select(available, self.selection_strategy);
+        };
+
+        let selection = select(available.clone(), self.selection_strategy)?;
+        let selected = inputs(&selection);
+        if selected.len() <= max_inputs {
+            return Ok(selection);
+        }
+
+        tracing::info!(
+            "Selection takes {} proofs, consolidating to at most {}",
+            selected.len(),
+            max_inputs
+        );
+
+        let selected: HashSet<Proof> = selected.into_iter().collect();
+        let consolidated = self
+            .consolidate_inputs(selected.iter().cloned().collect(), max_inputs)
+            .await?;
+
+        let candidates = available
+            .into_iter()
+            .filter(|proof| !selected.contains(proof))
+            .chain(consolidated)
+            .collect();
+        let selection = select(candidates, SelectionStrategy::LargestFirst)?;
+
+        let inputs = inputs(&selection).len();
+        ensure_cdk!(
+            inputs <= max_inputs,
+            Error::MaxInputsExceeded {
+                max: max_inputs,
+                inputs,
+            }
+        );
+
+        Ok(selection)
+    }
+
+    /// Swap `proofs` in chunks of `max_inputs` until at most `max_inputs`
+    /// proofs are left
+    ///
+    /// Returns the proofs left, locked proofs included as they are.
+    async fn consolidate_inputs(&self, proofs: Proofs, max_inputs: usize) -> Result<Proofs, Error> {
+        ensure_cdk!(
+            max_inputs > 1,
+            Error::Custom("Consolidation needs at least two inputs per swap".to_string())
+        );
+
+        // Locked proofs cannot be swapped without their keys
+        let (locked, mut proofs): (Proofs, Proofs) = proofs
+            .into_iter()
+            .partition(|proof| nut10::Secret::try_from(proof.secret.clone()).is_ok());
+
+        while locked.len() + proofs.len() > max_inputs {
+            proofs.sort_by_key(|proof| proof.amount);
+
+            let mut consolidated = Proofs::new();
+            for chunk in proofs.chunks(max_inputs) {
+                let chunk = chunk.to_vec();
+                let chunk_amount = chunk.total_amount()?;
+                let fee = self.get_proofs_fee(&chunk).await?;
+
+                if chunk.len() < 2 || chunk_amount <= fee {
+                    consolidated.extend(chunk);
+                    continue;
+                }
+
+                consolidated.extend(self.swap_consolidated(chunk, chunk_amount - fee).await?);
+            }
+
+            // Swaps that do not reduce the count would never end
+            if consolidated.len() >= proofs.len() {
+                return Err(Error::MaxInputsExceeded {
+                    max: max_inputs,
+                    inputs: locked.len() + consolidated.len(),
+                });
+            }
+            proofs = consolidated;
+        }
+
+        proofs.extend(locked);
+        Ok(proofs)
+    }
+
+    /// Swap `proofs` for the minimal split of `amount`, replacing them in the
+    /// localstore
+    ///
+    /// Returns the proofs received.
+    async fn swap_consolidated(&self, proofs: Proofs, amount: Amount) -> Result<Proofs, Error> {
+        let proofs_total = proofs.total_amount()?;
+        let ys = proofs.ys()?;
+
+        let pre_swap = self
+            .create_swap(
+                None,
+                SplitTarget::Values(amount.split()),
+                proofs,
+                None,
+                false,
+            )
+            .await?;
+
+        let swap_response = self.client.post_swap(pre_swap.swap_request).await?;
+
+        let keys = self
+            .load_keyset_keys(pre_swap.pre_mint_secrets.keyset_id)
+            .await?;
+        let new_proofs = construct_proofs(
+            swap_response.signatures,
+            pre_swap.pre_mint_secrets.rs(),
+            pre_swap.pre_mint_secrets.secrets(),
+            &keys,
+        )?;
+        self.verify_proofs_dleq(&new_proofs, false).await?;
+
+        let proof_infos = new_proofs
+            .iter()
+            .map(|proof| {
+                ProofInfo::new(
+                    proof.clone(),
+                    self.mint_url.clone(),
+                    State::Unspent,
+                    self.unit.clone(),
+                )
+                .map(|info| info.with_source(ProofSource::Swap))
+            })
+            .collect::<Result<Vec<ProofInfo>, _>>()?;
+
+        self.localstore
+            .update_proofs(proof_infos, ys.clone())
+            .await?;
+
+        self.localstore
+            .add_transaction(Transaction {
+                mint_url: self.mint_url.clone(),
+                direction: TransactionDirection::Outgoing,
+                amount: Amount::ZERO,
+                fee: proofs_total - amount,
+                unit: self.unit.clone(),
+                ys,
+                timestamp: unix_time(),
+                memo: None,
+                metadata: Default::default(),
+                quote_id: None,
+            })
+            .await?;
+
+        Ok(new_proofs)
+    }
+}
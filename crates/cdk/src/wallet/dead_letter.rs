@@ -0,0 +1,132 @@
+//! Dead letters
+//!
+//! Notifications the wallet cannot apply, because they reference a quote or
+//! keyset it does not know yet or cannot be parsed, are stored instead of
+//! dropped. They are applied again by [`Wallet::retry_dead_letters`], which
+//! the quote sync runs once quotes and keysets are up to date.
+//!
+//! Dead letters are scoped by mint, a wallet only sees and retries the
+//! dead letters of its own mint.
+
+use cdk_common::wallet::{DeadLetter, DeadLetterReason};
+use serde_json::Value;
+use tracing::instrument;
+
+use crate::nuts::NotificationPayload;
+use crate::{Error, Wallet};
+
+/// Most dead letters stored, the oldest are evicted first
+pub const MAX_DEAD_LETTERS: usize = 100;
+
+impl Wallet {
+    /// Apply a notification received on the subscription `sub_id`
+    ///
+    /// A notification for a quote or keyset the wallet does not know is
+    /// stored as a dead letter.
+    #[instrument(skip(self, payload))]
+    pub async fn handle_notification(
+        &self,
+        sub_id: Option<String>,
+        payload: &NotificationPayload<String>,
+    ) -> Result<(), Error> {
+        let reason = match self.apply_quote_notification(payload).await {
+            Err(Error::UnknownQuote) => DeadLetterReason::UnknownQuote,
+            Err(Error::UnknownKeySet) => DeadLetterReason::UnknownKeyset,
+            result => return result,
+        };
+
+        tracing::debug!("Storing notification as dead letter: {}", reason);
+        self.add_dead_letter(sub_id, serde_json::to_string(payload)?, reason)
+            .await
+    }
+
+    /// Store a websocket message that could not be parsed as a dead letter
+    ///
+    /// The payload of the message is stored when the message is a
+    /// notification, the whole message otherwise.
+    pub(crate) async fn handle_invalid_message(&self, message: &str) -> Result<(), Error> {
+        let params = serde_json::from_str::<Value>(message)
+            .ok()
+            .and_then(|value| value.get("params").cloned());
+
+        let sub_id = params
+            .as_ref()
+            .and_then(|params| params.get("subId"))
+            .and_then(Value::as_str)
+            .map(str::to_owned);
+        let payload = params
+            .as_ref()
+            .and_then(|params| params.get("payload"))
+            .map(Value::to_string)
+            .unwrap_or_else(|| message.to_owned());
+
+        self.add_dead_letter(sub_id, payload, DeadLetterReason::InvalidPayload)
+            .await
+    }
+
+    /// Store a dead letter, evicting the oldest beyond [`MAX_DEAD_LETTERS`]
+    ///
+    /// The id is assigned by the store.
+    async fn add_dead_letter(
+        &self,
+        sub_id: Option<String>,
+        payload: String,
+        reason: DeadLetterReason,
+    ) -> Result<(), Error> {
+        self.localstore
+            .add_dead_letter(DeadLetter {
+                id: 0,
+                mint_url: self.mint_url.clone(),
+                sub_id,
+                payload,
+                reason,
+                timestamp: self.clock.now_unix(),
+            })
+            .await?;
+
+        let dead_letters = self.dead_letters().await?;
+        let excess = dead_letters.len().saturating_sub(MAX_DEAD_LETTERS);
+        for dead_letter in dead_letters.iter().take(excess) {
+            self.localstore.remove_dead_letter(dead_letter.id).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Notifications from this mint the wallet could not apply, oldest first
+    #[instrument(skip(self))]
+    pub async fn dead_letters(&self) -> Result<Vec<DeadLetter>, Error> {
+        Ok(self
+            .localstore
+            .get_dead_letters(Some(self.mint_url.clone()))
+            .await?)
+    }
+
+    /// Apply the stored dead letters again
+    ///
+    /// Dead letters that apply are removed, the others are kept. Returns the
+    /// number of dead letters applied.
+    #[instrument(skip(self))]
+    pub async fn retry_dead_letters(&self) -> Result<usize, Error> {
+        let mut applied = 0;
+
+        for dead_letter in self.dead_letters().await? {
+            let payload =
+                match serde_json::from_str::<NotificationPayload<String>>(&dead_letter.payload) {
+                    Ok(payload) => payload,
+                    Err(_) => continue,
+                };
+
+            match self.apply_quote_notification(&payload).await {
+                Ok(()) => {
+                    self.localstore.remove_dead_letter(dead_letter.id).await?;
+                    applied += 1;
+                }
+                Err(Error::UnknownQuote) | Err(Error::UnknownKeySet) => {}
+                Err(err) => return Err(err),
+            }
+        }
+
+        Ok(applied)
+    }
+}
@@ -0,0 +1,219 @@
+//! Proof export
+//!
+//! Exports proofs as JSON for other Cashu tools. How much of each proof is
+//! included is chosen with [`DisclosureLevel`]:
+//!
+//! - [`DisclosureLevel::Full`] produces a standard NUT-00 proof array. The
+//!   proofs can be spent by whoever holds the export, so they are reserved in
+//!   this wallet.
+//! - [`DisclosureLevel::YsOnly`] produces `[{"id", "amount", "Y"}]` entries, enough
+//!   to audit proof states with the mint without being able to spend them.
+//! - [`DisclosureLevel::Redacted`] produces `[{"id", "amount_bucket", "count"}]`
+//!   entries, where `amount_bucket` is the smallest power of ten not below the
+//!   proof amounts it counts.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use tracing::instrument;
+
+use crate::nuts::nut00::ProofsMethods;
+use crate::nuts::{Id, Proofs, PublicKey, State};
+use crate::wallet::ReceiveOptions;
+use crate::{ensure_cdk, Amount, Error, Wallet};
+
+/// How much of each proof [`Wallet::export_proofs`] discloses
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DisclosureLevel {
+    /// Complete proofs, secrets included
+    ///
+    /// Anyone holding the export can spend the proofs, so it has to be
+    /// acknowledged explicitly and the exported proofs are reserved.
+    Full {
+        /// Confirms the export hands over the ability to spend the proofs
+        acknowledge_transfer: bool,
+    },
+    /// Keyset, amount and `Y` of each proof
+    YsOnly,
+    /// Proof counts per keyset and amount bucket
+    Redacted,
+}
+
+/// Proofs selected by [`Wallet::export_proofs`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExportFilter {
+    /// Proof states to export, defaults to unspent proofs
+    ///
+    /// Ignored for [`DisclosureLevel::Full`], which only exports unspent proofs.
+    pub states: Option<Vec<State>>,
+    /// Only export proofs of these keysets
+    pub keyset_ids: Option<Vec<Id>>,
+}
+
+/// Proof without its secret material
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExportedY {
+    /// Keyset id
+    pub id: Id,
+    /// Amount
+    pub amount: Amount,
+    /// Y of the proof
+    #[serde(rename = "Y")]
+    pub y: PublicKey,
+}
+
+/// Number of proofs in an amount bucket
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RedactedBucket {
+    /// Keyset id
+    pub id: Id,
+    /// Smallest power of ten not below the amounts in the bucket
+    pub amount_bucket: u64,
+    /// Number of proofs in the bucket
+    pub count: usize,
+}
+
+/// Exported proofs
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ProofExport {
+    /// Standard NUT-00 proofs
+    Full(Proofs),
+    /// Proofs without secrets
+    YsOnly(Vec<ExportedY>),
+    /// Bucketed proof counts
+    Redacted(Vec<RedactedBucket>),
+}
+
+impl ProofExport {
+    /// Serialize the export to JSON
+    pub fn to_json(&self) -> Result<String, Error> {
+        Ok(serde_json::to_string(self)?)
+    }
+}
+
+/// Smallest power of ten not below `amount`
+fn amount_bucket(amount: u64) -> u64 {
+    let mut bucket = 1u64;
+    while bucket < amount {
+        match bucket.checked_mul(10) {
+            Some(next) => bucket = next,
+            None => return u64::MAX,
+        }
+    }
+    bucket
+}
+
+impl Wallet {
+    /// Export proofs as JSON for other Cashu tools
+    ///
+    /// See [`DisclosureLevel`] for what each level includes. A full export
+    /// reserves the exported proofs so they are not also spent by this wallet.
+    #[instrument(skip(self))]
+    pub async fn export_proofs(
+        &self,
+        filter: ExportFilter,
+        disclosure: DisclosureLevel,
+    ) -> Result<ProofExport, Error> {
+        let states = match disclosure {
+            DisclosureLevel::Full {
+                acknowledge_transfer,
+            } => {
+                ensure_cdk!(acknowledge_transfer, Error::ExportNotAcknowledged);
+                vec![State::Unspent]
+            }
+            DisclosureLevel::YsOnly | DisclosureLevel::Redacted => {
+                filter.states.unwrap_or_else(|| vec![State::Unspent])
+            }
+        };
+
+        let proofs: Proofs = self
+            .get_proofs_with(Some(states), None)
+            .await?
+            .into_iter()
+            .filter(|proof| {
+                filter
+                    .keyset_ids
+                    .as_ref()
+                    .is_none_or(|ids| ids.contains(&proof.keyset_id))
+            })
+            .collect();
+
+        match disclosure {
+            DisclosureLevel::Full { .. } => {
+                self.localstore
+                    .update_proofs_state(proofs.ys()?, State::Reserved)
+                    .await?;
+
+                tracing::info!(
+                    "Exported {} proofs with secrets, proofs reserved",
+                    proofs.len()
+                );
+
+                Ok(ProofExport::Full(proofs))
+            }
+            DisclosureLevel::YsOnly => Ok(ProofExport::YsOnly(
+                proofs
+                    .iter()
+                    .map(|proof| {
+                        Ok(ExportedY {
+                            id: proof.keyset_id,
+                            amount: proof.amount,
+                            y: proof.y()?,
+                        })
+                    })
+                    .collect::<Result<_, Error>>()?,
+            )),
+            DisclosureLevel::Redacted => {
+                let mut buckets: BTreeMap<(Id, u64), usize> = BTreeMap::new();
+                for proof in &proofs {
+                    *buckets
+                        .entry((proof.keyset_id, amount_bucket(proof.amount.into())))
+                        .or_default() += 1;
+                }
+
+                Ok(ProofExport::Redacted(
+                    buckets
+                        .into_iter()
+                        .map(|((id, amount_bucket), count)| RedactedBucket {
+                            id,
+                            amount_bucket,
+                            count,
+                        })
+                        .collect(),
+                ))
+            }
+        }
+    }
+
+    /// Import proofs exported with [`DisclosureLevel::Full`]
+    ///
+    /// The proofs are swapped for new ones, so the exporting wallet can no
+    /// longer spend them.
+    #[instrument(skip_all)]
+    pub async fn import_exported_proofs(
+        &self,
+        json: &str,
+        opts: ReceiveOptions,
+    ) -> Result<Amount, Error> {
+        let proofs: Proofs = serde_json::from_str(json)?;
+
+        self.receive_proofs(proofs, opts, None).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_amount_bucket() {
+        assert_eq!(amount_bucket(0), 1);
+        assert_eq!(amount_bucket(1), 1);
+        assert_eq!(amount_bucket(2), 10);
+        assert_eq!(amount_bucket(10), 10);
+        assert_eq!(amount_bucket(64), 100);
+        assert_eq!(amount_bucket(1024), 10_000);
+        assert_eq!(amount_bucket(u64::MAX), u64::MAX);
+    }
+}
@@ -0,0 +1,51 @@
+//! Mint quote cancellation
+//!
+//! Abandons a mint quote the wallet no longer intends to mint, locally and at
+//! the mint when the mint supports cancelling quotes.
+
+use tracing::instrument;
+
+use crate::nuts::MintQuoteCancelRequest;
+use crate::wallet::Capability;
+use crate::{Error, Wallet};
+
+impl Wallet {
+    /// Cancel a mint quote
+    ///
+    /// If the mint advertises quote cancellation in its stored info, the quote
+    /// is first cancelled at the mint, which refuses quotes that already have
+    /// ecash issued for them. A quote with a NUT-20 signing key is cancelled
+    /// with a signature of its quote id, the mint refuses to cancel a paid
+    /// quote without one. The local quote is then removed together with the
+    /// premint counter range reserved on it.
+    ///
+    /// Payments made to a cancelled quote are not credited by the mint.
+    #[instrument(skip(self))]
+    pub async fn cancel_mint_quote(&self, quote_id: &str) -> Result<(), Error> {
+        let quote = self
+            .localstore
+            .get_mint_quote(quote_id)
+            .await?
+            .ok_or(Error::UnknownQuote)?;
+
+        if self.mint_supports(Capability::QuoteCancel).await? {
+            let mut request = MintQuoteCancelRequest {
+                quote: quote.id.clone(),
+                signature: None,
+            };
+            if let Some(secret_key) = quote.secret_key.clone() {
+                request.sign(secret_key)?;
+            }
+
+            self.client
+                .delete_mint_quote(quote.payment_method.clone(), request)
+                .await?;
+        }
+
+        self.localstore.remove_mint_quote(&quote.id).await?;
+
+        tracing::info!("Cancelled {} mint quote {}", quote.payment_method, quote.id);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,165 @@
+//! Mint quote conversion
+//!
+//! Converts an unpaid mint quote into a quote of another payment method, for
+//! example a bolt11 quote the user decides to earn with mining shares instead.
+
+use std::collections::HashMap;
+
+use cdk_common::wallet::MintQuote;
+use tracing::instrument;
+
+use crate::amount::UnitAmount;
+use crate::nuts::{
+    MintQuoteConversionRequest, MintQuoteState, PaymentMethod, QuoteConversionParams,
+};
+use crate::wallet::QuoteStateSource;
+use crate::{Error, Wallet};
+
+/// Transaction metadata key of the quote a minted quote was converted from
+const CONVERTED_FROM_METADATA_KEY: &str = "converted_from";
+
+/// Transaction metadata linking a minted quote to the quote it was converted from
+pub(crate) fn conversion_metadata(converted_from: Option<&str>) -> HashMap<String, String> {
+    converted_from
+        .map(|quote_id| {
+            (
+                CONVERTED_FROM_METADATA_KEY.to_string(),
+                quote_id.to_string(),
+            )
+        })
+        .into_iter()
+        .collect()
+}
+
+impl Wallet {
+    /// Convert an unpaid mint quote into a quote of another payment method
+    ///
+    /// The mint closes the original quote and creates a new one for the same
+    /// amount, unit and NUT-20 pubkey. Locally the original quote is marked
+    /// [`MintQuoteState::Converted`] and the new quote keeps its signing key
+    /// and records the original in [`MintQuote::converted_from`], which is
+    /// added to the transaction metadata once the new quote is minted.
+    ///
+    /// Refused with strict amounts, use [`Wallet::convert_quote_checked`]
+    /// instead.
+    #[instrument(skip(self, params))]
+    pub async fn convert_quote(
+        &self,
+        quote_id: &str,
+        to: PaymentMethod,
+        params: QuoteConversionParams,
+    ) -> Result<MintQuote, Error> {
+        self.ensure_not_strict("convert_quote_checked")?;
+
+        let original = self
+            .localstore
+            .get_mint_quote(quote_id)
+            .await?
+            .ok_or(Error::UnknownQuote)?;
+
+        self.create_conversion(original, to, params).await
+    }
+
+    /// Convert an unpaid mint quote of `amount` into a quote of another
+    /// payment method
+    ///
+    /// Fails with [`Error::UnitMismatch`] if `amount` is not in the unit of
+    /// the quote, and with [`Error::IncorrectQuoteAmount`] if the quote is for
+    /// another amount.
+    #[instrument(skip(self, params))]
+    pub async fn convert_quote_checked(
+        &self,
+        quote_id: &str,
+        amount: UnitAmount,
+        to: PaymentMethod,
+        params: QuoteConversionParams,
+    ) -> Result<MintQuote, Error> {
+        let original = self
+            .localstore
+            .get_mint_quote(quote_id)
+            .await?
+            .ok_or(Error::UnknownQuote)?;
+
+        self.check_amount_unit(&amount, &original.unit)?;
+        if original.amount != Some(amount.amount) {
+            return Err(Error::IncorrectQuoteAmount);
+        }
+
+        self.create_conversion(original, to, params).await
+    }
+
+    /// Convert the stored quote `original`
+    async fn create_conversion(
+        &self,
+        original: MintQuote,
+        to: PaymentMethod,
+        params: QuoteConversionParams,
+    ) -> Result<MintQuote, Error> {
+        self.check_quote_unit(&original.unit, &self.unit)?;
+
+        let response = self
+            .client
+            .post_mint_quote_conversion(MintQuoteConversionRequest {
+                quote: original.id.clone(),
+                method: to,
+                params,
+            })
+            .await?;
+
+        let quote = MintQuote {
+            id: response.quote,
+            mint_url: self.mint_url.clone(),
+            payment_method: response.method,
+            amount: response.amount,
+            unit: response.unit,
+            request: response.request,
+            state: response.state,
+            expiry: response.expiry.unwrap_or(0),
+            secret_key: original.secret_key.clone(),
+            amount_issued: response.amount_issued,
+            amount_paid: response.amount_paid,
+            keyset_id: response.keyset_id,
+            converted_from: Some(original.id.clone()),
+            premint_keyset_id: None,
+            premint_counter: None,
+            premint_amounts: None,
+        };
+
+        tracing::info!(
+            "Converted {} mint quote {} to {} mint quote {}",
+            original.payment_method,
+            original.id,
+            quote.payment_method,
+            quote.id
+        );
+
+        self.update_mint_quote_state(
+            original,
+            MintQuoteState::Converted,
+            QuoteStateSource::Wallet,
+        )
+        .await?;
+        self.localstore.add_mint_quote(quote.clone()).await?;
+
+        Ok(quote)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conversion_metadata() {
+        assert!(conversion_metadata(None).is_empty());
+
+        let metadata = conversion_metadata(Some("quote-id"));
+        assert_eq!(metadata.len(), 1);
+        assert_eq!(
+            metadata
+                .get(CONVERTED_FROM_METADATA_KEY)
+                .map(String::as_str),
+            Some("quote-id")
+        );
+    }
+}
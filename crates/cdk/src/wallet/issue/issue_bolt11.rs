@@ -1,24 +1,45 @@
-use std::collections::HashMap;
-
 use cdk_common::nut04::MintMethodOptions;
-use cdk_common::wallet::{MintQuote, Transaction, TransactionDirection};
+use cdk_common::wallet::{MintQuote, PendingMintOperation};
 use cdk_common::PaymentMethod;
 use tracing::instrument;
 
-use crate::amount::SplitTarget;
-use crate::dhke::construct_proofs;
+use crate::amount::{SplitTarget, UnitAmount};
 use crate::nuts::nut00::ProofsMethods;
 use crate::nuts::{
-    nut12, MintQuoteBolt11Request, MintQuoteBolt11Response, MintRequest, PreMintSecrets, Proofs,
-    SecretKey, SpendingConditions, State,
+    BlindSignature, CurrencyUnit, Id, MintQuoteBolt11Request, MintQuoteBolt11Response, MintRequest,
+    PreMintSecrets, Proofs, PublicKey, RestoreRequest, SecretKey, SpendingConditions,
+};
+use crate::types::ProofSource;
+use crate::wallet::issue::{
+    check_split_target, conversion_metadata, IssueContext, MintQuotesSummary, QuoteCleanup,
+};
+use crate::wallet::{
+    Capability, MintQuoteState, QuoteStateSource, QuoteStateUpdate, RequestPriority,
+    RequestScheduler,
 };
-use crate::types::ProofInfo;
-use crate::util::unix_time;
-use crate::wallet::MintQuoteState;
 use crate::{Amount, Error, Wallet};
 
+/// NUT-20 key a new mint quote is locked to
+enum QuoteLock {
+    /// A new key generated and stored by the wallet
+    Generated,
+    /// A key provided by the caller and stored by the wallet
+    SecretKey(SecretKey),
+    /// A key held elsewhere, only its pubkey is known
+    Pubkey(PublicKey),
+}
+
+impl QuoteLock {
+    fn from_pubkey(pubkey: Option<PublicKey>) -> Self {
+        pubkey.map_or(Self::Generated, Self::Pubkey)
+    }
+}
+
 impl Wallet {
     /// Mint Quote
+    ///
+    /// Refused with strict amounts, use [`Wallet::mint_quote_checked`] instead.
+    ///
     /// # Synopsis
     /// ```rust,no_run
     /// use std::sync::Arc;
@@ -49,21 +70,99 @@ impl Wallet {
         amount: Amount,
         description: Option<String>,
     ) -> Result<MintQuote, Error> {
-        self.mint_quote_with_pubkey(amount, description, None).await
+        self.ensure_not_strict("mint_quote_checked")?;
+        self.create_mint_quote(amount, description, QuoteLock::Generated, &self.unit)
+            .await
     }
 
     /// Creates new mint quote with optional locking pubkey for NUT-20
+    ///
+    /// No secret key is stored for a provided pubkey, so the quote has to be
+    /// minted by whoever holds its key. Use
+    /// [`Wallet::mint_quote_with_secret_key`] for the wallet to sign the mint
+    /// request itself.
     #[instrument(skip(self, locking_pubkey))]
     pub async fn mint_quote_with_pubkey(
         &self,
         amount: Amount,
         description: Option<String>,
-        locking_pubkey: Option<crate::nuts::PublicKey>,
+        locking_pubkey: Option<PublicKey>,
+    ) -> Result<MintQuote, Error> {
+        self.ensure_not_strict("mint_quote_checked")?;
+        self.create_mint_quote(
+            amount,
+            description,
+            QuoteLock::from_pubkey(locking_pubkey),
+            &self.unit,
+        )
+        .await
+    }
+
+    /// Creates new mint quote locked to the pubkey of `secret_key` for NUT-20
+    ///
+    /// The secret key is stored on the quote, so [`Wallet::mint`] signs the
+    /// mint request with it.
+    #[instrument(skip(self, secret_key))]
+    pub async fn mint_quote_with_secret_key(
+        &self,
+        amount: Amount,
+        description: Option<String>,
+        secret_key: SecretKey,
+    ) -> Result<MintQuote, Error> {
+        self.ensure_not_strict("mint_quote_checked")?;
+        self.create_mint_quote(
+            amount,
+            description,
+            QuoteLock::SecretKey(secret_key),
+            &self.unit,
+        )
+        .await
+    }
+
+    /// Creates new mint quote for an amount in the wallet unit
+    ///
+    /// Fails with [`Error::UnitMismatch`] if `amount` is in another unit.
+    #[instrument(skip(self, locking_pubkey))]
+    pub async fn mint_quote_checked(
+        &self,
+        amount: UnitAmount,
+        description: Option<String>,
+        locking_pubkey: Option<PublicKey>,
+    ) -> Result<MintQuote, Error> {
+        self.check_amount_unit(&amount, &self.unit)?;
+        self.create_mint_quote(
+            amount.amount,
+            description,
+            QuoteLock::from_pubkey(locking_pubkey),
+            &self.unit,
+        )
+        .await
+    }
+
+    /// Creates new mint quote in a unit other than the default wallet unit
+    #[instrument(skip(self))]
+    pub async fn mint_quote_with_unit(
+        &self,
+        amount: Amount,
+        description: Option<String>,
+        unit: &CurrencyUnit,
+    ) -> Result<MintQuote, Error> {
+        self.create_mint_quote(amount, description, QuoteLock::Generated, unit)
+            .await
+    }
+
+    /// Request a bolt11 mint quote in the unit
+    async fn create_mint_quote(
+        &self,
+        amount: Amount,
+        description: Option<String>,
+        lock: QuoteLock,
+        unit: &CurrencyUnit,
     ) -> Result<MintQuote, Error> {
         let mint_url = self.mint_url.clone();
-        let unit = self.unit.clone();
+        let unit = unit.clone();
 
-        self.refresh_keysets().await?;
+        self.refresh_keysets_with_unit(&unit).await?;
 
         // If we have a description, we check that the mint supports it.
         if description.is_some() {
@@ -83,14 +182,14 @@ impl Wallet {
             }
         }
 
-        // Use provided locking pubkey or generate a new one
-        let (pubkey, secret_key) = match locking_pubkey {
-            Some(pk) => (pk, None), // Use provided pubkey, no secret key stored
-            // TODO clean up this broken LLM garbage
-            None => {
+        let (pubkey, secret_key) = match lock {
+            QuoteLock::Generated => {
                 let sk = SecretKey::generate();
                 (sk.public_key(), Some(sk))
             }
+            QuoteLock::SecretKey(sk) => (sk.public_key(), Some(sk)),
+            // The key lives elsewhere, no secret key stored
+            QuoteLock::Pubkey(pk) => (pk, None),
         };
 
         let request = MintQuoteBolt11Request {
@@ -119,50 +218,169 @@ impl Wallet {
     }
 
     /// Check mint quote status
+    ///
+    /// The state returned never moves a known quote backwards, a stale answer
+    /// from the mint is recorded instead, see [`Wallet::quote_state_conflicts`].
+    /// Conflicts with the stored quote are returned with the response, see
+    /// [`crate::wallet::QuoteConflict`].
     #[instrument(skip(self, quote_id))]
     pub async fn mint_quote_state(
         &self,
         quote_id: &str,
-    ) -> Result<MintQuoteBolt11Response<String>, Error> {
-        let response = self
+    ) -> Result<QuoteStateUpdate<MintQuoteBolt11Response<String>>, Error> {
+        let mut response = self
             .client
             .get_mint_quote_status(quote_id, crate::nuts::PaymentMethod::Bolt11)
             .await?;
 
-        match self.localstore.get_mint_quote(quote_id).await? {
+        let conflict = match self.localstore.get_mint_quote(quote_id).await? {
             Some(quote) => {
-                let mut quote = quote;
-
-                quote.state = response.state;
-                self.localstore.add_mint_quote(quote).await?;
+                let (quote, conflict) = self
+                    .check_mint_quote_state(quote, response.state, QuoteStateSource::Http)
+                    .await?;
+                response.state = quote.state;
+                conflict
             }
             None => {
                 tracing::info!("Quote mint {} unknown", quote_id);
+                None
             }
-        }
+        };
 
-        Ok(response)
+        Ok(QuoteStateUpdate { response, conflict })
     }
 
     /// Check status of pending mint quotes
+    ///
+    /// Mints paid quotes and removes expired ones. A quote that cannot be
+    /// checked or minted does not stop the others, it is reported in
+    /// [`MintQuotesSummary::failed_quotes`] and kept for the next check.
+    ///
+    /// Requests are scheduled as [`RequestPriority::Background`].
     #[instrument(skip(self))]
-    pub async fn check_all_mint_quotes(&self) -> Result<Amount, Error> {
+    pub async fn check_all_mint_quotes(&self) -> Result<MintQuotesSummary, Error> {
+        RequestScheduler::with_priority(RequestPriority::Background, self.sync_mint_quotes()).await
+    }
+
+    /// Mint paid quotes, remove expired ones and retry dead letters
+    async fn sync_mint_quotes(&self) -> Result<MintQuotesSummary, Error> {
         let mint_quotes = self.localstore.get_mint_quotes().await?;
-        let mut total_amount = Amount::ZERO;
+        let mut summary = MintQuotesSummary::default();
 
         for mint_quote in mint_quotes {
-            let mint_quote_response = self.mint_quote_state(&mint_quote.id).await?;
+            if mint_quote.mint_url != self.mint_url {
+                continue;
+            }
 
-            if mint_quote_response.state == MintQuoteState::Paid {
-                let proofs = self
-                    .mint(&mint_quote.id, SplitTarget::default(), None)
-                    .await?;
-                total_amount += proofs.total_amount()?;
-            } else if mint_quote.expiry.le(&unix_time()) {
-                self.localstore.remove_mint_quote(&mint_quote.id).await?;
+            match self.sync_mint_quote(&mint_quote).await {
+                Ok(Some(amount)) => {
+                    summary.total_minted = summary
+                        .total_minted
+                        .checked_add(amount)
+                        .ok_or(Error::AmountOverflow)?;
+                    summary.minted_quotes.push(mint_quote.id);
+                }
+                Ok(None) => (),
+                Err(err) => {
+                    tracing::warn!("Could not check mint quote {}: {}", mint_quote.id, err);
+                    summary.failed_quotes.push((mint_quote.id, err));
+                }
+            }
+        }
+
+        // Quotes and keysets are up to date, notifications that arrived
+        // before them may apply now
+        self.retry_dead_letters().await?;
+
+        Ok(summary)
+    }
+
+    /// Check one mint quote with the status endpoint of its payment method
+    ///
+    /// Returns the amount minted if the quote was paid. The quote is only
+    /// removed once the mint confirmed it is unpaid and it has expired.
+    async fn sync_mint_quote(&self, mint_quote: &MintQuote) -> Result<Option<Amount>, Error> {
+        let proofs = match mint_quote.payment_method {
+            PaymentMethod::MiningShare => {
+                let response = self
+                    .mint_quote_state_mining_share(&mint_quote.id)
+                    .await?
+                    .response;
+                let amount = response
+                    .amount
+                    .unwrap_or_default()
+                    .checked_sub(response.amount_issued)
+                    .unwrap_or_default();
+
+                if MintQuoteState::from(response.state) == MintQuoteState::Paid
+                    && amount > Amount::ZERO
+                {
+                    let secret_key = mint_quote
+                        .secret_key
+                        .clone()
+                        .ok_or(Error::SignatureMissingOrInvalid)?;
+
+                    Some(
+                        self.mint_mining_share_with_unit(
+                            &mint_quote.id,
+                            amount,
+                            response.keyset_id,
+                            secret_key,
+                            &mint_quote.unit,
+                            None,
+                        )
+                        .await?,
+                    )
+                } else {
+                    None
+                }
+            }
+            PaymentMethod::Bolt12 => {
+                let response = self.mint_bolt12_quote_state(&mint_quote.id).await?;
+
+                if response.amount_paid > response.amount_issued {
+                    Some(
+                        self.mint_bolt12_with_unit(
+                            &mint_quote.id,
+                            None,
+                            SplitTarget::default(),
+                            None,
+                            &mint_quote.unit,
+                        )
+                        .await?,
+                    )
+                } else {
+                    None
+                }
+            }
+            PaymentMethod::Bolt11 | PaymentMethod::Custom(_) => {
+                let response = self.mint_quote_state(&mint_quote.id).await?.response;
+
+                if response.state == MintQuoteState::Paid {
+                    Some(
+                        self.mint_with_unit(
+                            &mint_quote.id,
+                            SplitTarget::default(),
+                            None,
+                            &mint_quote.unit,
+                        )
+                        .await?,
+                    )
+                } else {
+                    None
+                }
+            }
+        };
+
+        match proofs {
+            Some(proofs) => Ok(Some(proofs.total_amount()?)),
+            None => {
+                if mint_quote.expiry != 0 && mint_quote.expiry.le(&self.clock.now_unix()) {
+                    self.localstore.remove_mint_quote(&mint_quote.id).await?;
+                }
+                Ok(None)
             }
         }
-        Ok(total_amount)
     }
 
     /// Get active mint quotes
@@ -170,11 +388,11 @@ impl Wallet {
     #[instrument(skip(self))]
     pub async fn get_active_mint_quotes(&self) -> Result<Vec<MintQuote>, Error> {
         let mut mint_quotes = self.localstore.get_mint_quotes().await?;
-        let unix_time = unix_time();
+        let now = self.clock.now_unix();
         mint_quotes.retain(|quote| {
             quote.mint_url == self.mint_url
                 && quote.state != MintQuoteState::Issued
-                && quote.expiry > unix_time
+                && quote.expiry > now
         });
         Ok(mint_quotes)
     }
@@ -218,8 +436,27 @@ impl Wallet {
         amount_split_target: SplitTarget,
         spending_conditions: Option<SpendingConditions>,
     ) -> Result<Proofs, Error> {
-        self.refresh_keysets().await?;
+        self.mint_with_unit(
+            quote_id,
+            amount_split_target,
+            spending_conditions,
+            &self.unit,
+        )
+        .await
+    }
 
+    /// Mint a quote denominated in a unit other than the default wallet unit
+    ///
+    /// The quote has to be denominated in `unit`. The proofs count towards the
+    /// balance of `unit`, see [`Self::total_balance_with_unit`].
+    #[instrument(skip(self))]
+    pub async fn mint_with_unit(
+        &self,
+        quote_id: &str,
+        amount_split_target: SplitTarget,
+        spending_conditions: Option<SpendingConditions>,
+        unit: &CurrencyUnit,
+    ) -> Result<Proofs, Error> {
         let quote_info = self
             .localstore
             .get_mint_quote(quote_id)
@@ -230,6 +467,52 @@ impl Wallet {
             return Err(Error::UnsupportedPaymentMethod);
         }
 
+        self.check_quote_unit(&quote_info.unit, unit)?;
+
+        let active_keyset_id = self.fetch_active_keyset_with_unit(unit).await?.id;
+
+        self.mint_quote_proofs(
+            quote_info,
+            active_keyset_id,
+            amount_split_target,
+            spending_conditions,
+        )
+        .await
+    }
+
+    /// Mint a quote in whatever unit it is denominated in
+    ///
+    /// The proofs are stored tagged with the quote unit, so they are only part
+    /// of the default unit balance if the quote is in the default unit.
+    #[instrument(skip(self))]
+    pub async fn mint_foreign_unit(&self, quote_id: &str) -> Result<Proofs, Error> {
+        let quote_info = self
+            .localstore
+            .get_mint_quote(quote_id)
+            .await?
+            .ok_or(Error::UnknownQuote)?;
+
+        self.mint_with_unit(quote_id, SplitTarget::default(), None, &quote_info.unit)
+            .await
+    }
+
+    /// Mint proofs for a bolt11 quote with the given keyset
+    ///
+    /// Proofs and the transaction are recorded in the unit of the quote.
+    ///
+    /// The counter and output amounts the premint secrets are derived from are
+    /// stored on the quote before the request is sent. A retry derives the same
+    /// outputs again whatever split target it is called with, so signatures
+    /// issued for a request whose response was lost are recovered instead of
+    /// the quote being stuck as issued.
+    async fn mint_quote_proofs(
+        &self,
+        mut quote_info: MintQuote,
+        active_keyset_id: Id,
+        amount_split_target: SplitTarget,
+        spending_conditions: Option<SpendingConditions>,
+    ) -> Result<Proofs, Error> {
+        let quote_id = quote_info.id.clone();
         let amount_mintable = quote_info.amount_mintable();
 
         if amount_mintable == Amount::ZERO {
@@ -237,13 +520,15 @@ impl Wallet {
             return Err(Error::AmountUndefined);
         }
 
-        let unix_time = unix_time();
+        check_split_target(&amount_split_target, amount_mintable)?;
 
-        if quote_info.expiry > unix_time {
+        if quote_info.expiry.le(&self.clock.now_unix()) && quote_info.expiry.ne(&0) {
             tracing::warn!("Attempting to mint with expired quote.");
         }
 
-        let active_keyset_id = self.fetch_active_keyset().await?.id;
+        // Outputs derived from a stored counter may have been signed by a
+        // previous attempt whose response never reached us
+        let is_retry = spending_conditions.is_none() && quote_info.premint_counter.is_some();
 
         let premint_secrets = match &spending_conditions {
             Some(spending_conditions) => PreMintSecrets::with_conditions(
@@ -252,102 +537,145 @@ impl Wallet {
                 &amount_split_target,
                 spending_conditions,
             )?,
-            None => {
-                // Calculate how many secrets we'll need
-                let amount_split = amount_mintable.split_targeted(&amount_split_target)?;
-                let num_secrets = amount_split.len() as u32;
-
-                tracing::debug!(
-                    "Incrementing keyset {} counter by {}",
-                    active_keyset_id,
-                    num_secrets
-                );
-
-                // Atomically get the counter range we need
-                let new_counter = self
-                    .localstore
-                    .increment_keyset_counter(&active_keyset_id, num_secrets)
-                    .await?;
-
-                let count = new_counter - num_secrets;
-
-                PreMintSecrets::from_seed(
-                    active_keyset_id,
-                    count,
-                    &self.seed,
-                    amount_mintable,
-                    &amount_split_target,
-                )?
-            }
+            None => match (
+                quote_info.premint_keyset_id,
+                quote_info.premint_counter,
+                quote_info.premint_amounts.clone(),
+            ) {
+                (Some(keyset_id), Some(counter), premint_amounts) => {
+                    // Derive the stored amounts rather than the caller's split,
+                    // which could give other outputs or run into counters
+                    // reserved for other operations. Quotes stored before the
+                    // amounts were recorded have only the caller's split.
+                    let (amount, split_target) = match premint_amounts {
+                        Some(amounts) => (
+                            Amount::try_sum(amounts.iter().copied())?,
+                            SplitTarget::Values(amounts),
+                        ),
+                        None => (amount_mintable, amount_split_target.clone()),
+                    };
+
+                    PreMintSecrets::from_seed(
+                        keyset_id,
+                        counter,
+                        &self.seed,
+                        amount,
+                        &split_target,
+                    )?
+                }
+                _ => {
+                    let amounts = amount_mintable.split_targeted(&amount_split_target)?;
+                    let counter = self
+                        .reserve_counter_range(&active_keyset_id, amounts.len() as u32)
+                        .await?;
+
+                    quote_info.premint_keyset_id = Some(active_keyset_id);
+                    quote_info.premint_counter = Some(counter.start);
+                    quote_info.premint_amounts = Some(amounts);
+                    self.localstore.add_mint_quote(quote_info.clone()).await?;
+
+                    PreMintSecrets::from_seed(
+                        active_keyset_id,
+                        counter.start,
+                        &self.seed,
+                        amount_mintable,
+                        &amount_split_target,
+                    )?
+                }
+            },
         };
+        let keyset_id = premint_secrets.keyset_id;
 
-        let mut request = MintRequest {
-            quote: quote_id.to_string(),
-            outputs: premint_secrets.blinded_messages(),
-            signature: None,
+        let issued = if is_retry {
+            self.issued_signatures(&premint_secrets).await?
+        } else {
+            None
         };
 
-        if let Some(secret_key) = quote_info.secret_key {
-            request.sign(secret_key)?;
-        }
-
-        let mint_res = self.client.post_mint(request).await?;
-
-        let keys = self.load_keyset_keys(active_keyset_id).await?;
+        let signatures = match issued {
+            Some(signatures) => {
+                tracing::info!("Recovered signatures for mint quote {}", quote_id);
+                signatures
+            }
+            None => {
+                let mut request = MintRequest {
+                    quote: quote_id.to_string(),
+                    outputs: premint_secrets.blinded_messages(),
+                    signature: None,
+                };
+
+                if let Some(secret_key) = quote_info.secret_key.clone() {
+                    request.sign(secret_key)?;
+                }
 
-        // Verify the signature DLEQ is valid
-        {
-            for (sig, premint) in mint_res.signatures.iter().zip(&premint_secrets.secrets) {
-                let keys = self.load_keyset_keys(sig.keyset_id).await?;
-                let key = keys.amount_key(sig.amount).ok_or(Error::AmountKey)?;
-                match sig.verify_dleq(key, premint.blinded_message.blinded_secret) {
-                    Ok(_) | Err(nut12::Error::MissingDleqProof) => (),
-                    Err(_) => return Err(Error::CouldNotVerifyDleq),
+                match self.client.post_mint(request.clone()).await {
+                    Ok(response) => response.signatures,
+                    Err(err) => {
+                        let operation = PendingMintOperation {
+                            id: PendingMintOperation::outputs_id(&request.outputs),
+                            quote_id: quote_id.clone(),
+                            mint_url: self.mint_url.clone(),
+                            payment_method: PaymentMethod::Bolt11,
+                            unit: quote_info.unit.clone(),
+                            keyset_id,
+                            request,
+                            premint_secrets: premint_secrets.clone(),
+                            expiry: quote_info.expiry,
+                            created_time: self.clock.now_unix(),
+                        };
+                        self.handle_failed_mint(err, operation, false).await?
+                    }
                 }
             }
+        };
+
+        // Remove filled quote, and with it the premint counter, only once the
+        // proofs are stored
+        self.finalize_issued_proofs(
+            signatures,
+            &premint_secrets,
+            keyset_id,
+            IssueContext {
+                quote_id,
+                unit: quote_info.unit.clone(),
+                cleanup: QuoteCleanup::Remove,
+                source: ProofSource::Bolt11Mint,
+                metadata: conversion_metadata(quote_info.converted_from.as_deref()),
+            },
+        )
+        .await
+    }
+
+    /// Signatures the mint already issued for the outputs of `premint_secrets`
+    ///
+    /// Returns `None` when none of the outputs were signed, or when the mint
+    /// does not support restore [NUT-09] and they cannot be looked up.
+    pub(crate) async fn issued_signatures(
+        &self,
+        premint_secrets: &PreMintSecrets,
+    ) -> Result<Option<Vec<BlindSignature>>, Error> {
+        if !self.mint_supports(Capability::Restore).await? {
+            tracing::debug!("Mint does not support restore, not looking up issued signatures");
+            return Ok(None);
         }
 
-        let proofs = construct_proofs(
-            mint_res.signatures,
-            premint_secrets.rs(),
-            premint_secrets.secrets(),
-            &keys,
-        )?;
-
-        // Remove filled quote from store
-        self.localstore.remove_mint_quote(&quote_info.id).await?;
-
-        let proof_infos = proofs
-            .iter()
-            .map(|proof| {
-                ProofInfo::new(
-                    proof.clone(),
-                    self.mint_url.clone(),
-                    State::Unspent,
-                    quote_info.unit.clone(),
-                )
-            })
-            .collect::<Result<Vec<ProofInfo>, _>>()?;
-
-        // Add new proofs to store
-        self.localstore.update_proofs(proof_infos, vec![]).await?;
-
-        // Add transaction to store
-        self.localstore
-            .add_transaction(Transaction {
-                mint_url: self.mint_url.clone(),
-                direction: TransactionDirection::Incoming,
-                amount: proofs.total_amount()?,
-                fee: Amount::ZERO,
-                unit: self.unit.clone(),
-                ys: proofs.ys()?,
-                timestamp: unix_time,
-                memo: None,
-                metadata: HashMap::new(),
-                quote_id: Some(quote_id.to_string()),
+        let outputs = premint_secrets.blinded_messages();
+        let response = self
+            .client
+            .post_restore(RestoreRequest {
+                outputs: outputs.clone(),
             })
             .await?;
 
-        Ok(proofs)
+        if response.signatures.is_empty() {
+            return Ok(None);
+        }
+
+        // A mint request is signed as a whole, a partial match is not ours
+        if response.signatures.len() != outputs.len() {
+            return Err(Error::BlindedMessageAlreadySigned);
+        }
+
+        Ok(Some(response.signatures))
     }
 }
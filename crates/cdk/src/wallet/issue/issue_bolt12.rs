@@ -2,19 +2,15 @@ use std::collections::HashMap;
 
 use cdk_common::nut04::MintMethodOptions;
 use cdk_common::nut25::MintQuoteBolt12Request;
-use cdk_common::wallet::{Transaction, TransactionDirection};
 use cdk_common::{Proofs, SecretKey};
 use tracing::instrument;
 
 use crate::amount::SplitTarget;
-use crate::dhke::construct_proofs;
-use crate::nuts::nut00::ProofsMethods;
 use crate::nuts::{
-    nut12, MintQuoteBolt12Response, MintRequest, PaymentMethod, PreMintSecrets, SpendingConditions,
-    State,
+    CurrencyUnit, MintQuoteBolt12Response, MintRequest, PaymentMethod, SpendingConditions,
 };
-use crate::types::ProofInfo;
-use crate::util::unix_time;
+use crate::types::ProofSource;
+use crate::wallet::issue::{IssueContext, QuoteCleanup};
 use crate::wallet::MintQuote;
 use crate::{Amount, Error, Wallet};
 
@@ -25,11 +21,22 @@ impl Wallet {
         &self,
         amount: Option<Amount>,
         description: Option<String>,
+    ) -> Result<MintQuote, Error> {
+        self.mint_bolt12_quote_with_unit(amount, description, &self.unit)
+            .await
+    }
+
+    /// Mint Bolt12 quote in a unit other than the default wallet unit
+    #[instrument(skip(self))]
+    pub async fn mint_bolt12_quote_with_unit(
+        &self,
+        amount: Option<Amount>,
+        description: Option<String>,
+        unit: &CurrencyUnit,
     ) -> Result<MintQuote, Error> {
         let mint_url = self.mint_url.clone();
-        let unit = &self.unit;
 
-        self.refresh_keysets().await?;
+        self.refresh_keysets_with_unit(unit).await?;
 
         // If we have a description, we check that the mint supports it.
         if description.is_some() {
@@ -53,7 +60,7 @@ impl Wallet {
 
         let mint_request = MintQuoteBolt12Request {
             amount,
-            unit: self.unit.clone(),
+            unit: unit.clone(),
             description,
             pubkey: secret_key.public_key(),
         };
@@ -85,12 +92,30 @@ impl Wallet {
         amount_split_target: SplitTarget,
         spending_conditions: Option<SpendingConditions>,
     ) -> Result<Proofs, Error> {
-        self.refresh_keysets().await?;
+        self.mint_bolt12_with_unit(
+            quote_id,
+            amount,
+            amount_split_target,
+            spending_conditions,
+            &self.unit,
+        )
+        .await
+    }
 
+    /// Mint bolt12 quote denominated in a unit other than the default wallet unit
+    #[instrument(skip(self))]
+    pub async fn mint_bolt12_with_unit(
+        &self,
+        quote_id: &str,
+        amount: Option<Amount>,
+        amount_split_target: SplitTarget,
+        spending_conditions: Option<SpendingConditions>,
+        unit: &CurrencyUnit,
+    ) -> Result<Proofs, Error> {
         let quote_info = self.localstore.get_mint_quote(quote_id).await?;
 
         let quote_info = if let Some(quote) = quote_info {
-            if quote.expiry.le(&unix_time()) && quote.expiry.ne(&0) {
+            if quote.expiry.le(&self.clock.now_unix()) && quote.expiry.ne(&0) {
                 tracing::info!("Attempting to mint expired quote.");
             }
 
@@ -99,7 +124,9 @@ impl Wallet {
             return Err(Error::UnknownQuote);
         };
 
-        let active_keyset_id = self.fetch_active_keyset().await?.id;
+        self.check_quote_unit(&quote_info.unit, unit)?;
+
+        let active_keyset_id = self.fetch_active_keyset_with_unit(unit).await?.id;
 
         let amount = match amount {
             Some(amount) => amount,
@@ -108,7 +135,10 @@ impl Wallet {
                 // The mint will tell us how much can be minted
                 let state = self.mint_bolt12_quote_state(quote_id).await?;
 
-                state.amount_paid - state.amount_issued
+                state
+                    .amount_paid
+                    .checked_sub(state.amount_issued)
+                    .ok_or(Error::AmountOverflow)?
             }
         };
 
@@ -117,41 +147,14 @@ impl Wallet {
             return Err(Error::UnpaidQuote);
         }
 
-        let premint_secrets = match &spending_conditions {
-            Some(spending_conditions) => PreMintSecrets::with_conditions(
+        let premint_secrets = self
+            .prepare_premint_secrets(
                 active_keyset_id,
                 amount,
                 &amount_split_target,
-                spending_conditions,
-            )?,
-            None => {
-                // Calculate how many secrets we'll need without generating them
-                let amount_split = amount.split_targeted(&amount_split_target)?;
-                let num_secrets = amount_split.len() as u32;
-
-                tracing::debug!(
-                    "Incrementing keyset {} counter by {}",
-                    active_keyset_id,
-                    num_secrets
-                );
-
-                // Atomically get the counter range we need
-                let new_counter = self
-                    .localstore
-                    .increment_keyset_counter(&active_keyset_id, num_secrets)
-                    .await?;
-
-                let count = new_counter - num_secrets;
-
-                PreMintSecrets::from_seed(
-                    active_keyset_id,
-                    count,
-                    &self.seed,
-                    amount,
-                    &amount_split_target,
-                )?
-            }
-        };
+                spending_conditions.as_ref(),
+            )
+            .await?;
 
         let mut request = MintRequest {
             quote: quote_id.to_string(),
@@ -168,69 +171,19 @@ impl Wallet {
 
         let mint_res = self.client.post_mint(request).await?;
 
-        let keys = self.load_keyset_keys(active_keyset_id).await?;
-
-        // Verify the signature DLEQ is valid
-        {
-            for (sig, premint) in mint_res.signatures.iter().zip(&premint_secrets.secrets) {
-                let keys = self.load_keyset_keys(sig.keyset_id).await?;
-                let key = keys.amount_key(sig.amount).ok_or(Error::AmountKey)?;
-                match sig.verify_dleq(key, premint.blinded_message.blinded_secret) {
-                    Ok(_) | Err(nut12::Error::MissingDleqProof) => (),
-                    Err(_) => return Err(Error::CouldNotVerifyDleq),
-                }
-            }
-        }
-
-        let proofs = construct_proofs(
+        self.finalize_issued_proofs(
             mint_res.signatures,
-            premint_secrets.rs(),
-            premint_secrets.secrets(),
-            &keys,
-        )?;
-
-        // Remove filled quote from store
-        let mut quote_info = self
-            .localstore
-            .get_mint_quote(quote_id)
-            .await?
-            .ok_or(Error::UnpaidQuote)?;
-        quote_info.amount_issued += proofs.total_amount()?;
-
-        self.localstore.add_mint_quote(quote_info.clone()).await?;
-
-        let proof_infos = proofs
-            .iter()
-            .map(|proof| {
-                ProofInfo::new(
-                    proof.clone(),
-                    self.mint_url.clone(),
-                    State::Unspent,
-                    quote_info.unit.clone(),
-                )
-            })
-            .collect::<Result<Vec<ProofInfo>, _>>()?;
-
-        // Add new proofs to store
-        self.localstore.update_proofs(proof_infos, vec![]).await?;
-
-        // Add transaction to store
-        self.localstore
-            .add_transaction(Transaction {
-                mint_url: self.mint_url.clone(),
-                direction: TransactionDirection::Incoming,
-                amount: proofs.total_amount()?,
-                fee: Amount::ZERO,
-                unit: self.unit.clone(),
-                ys: proofs.ys()?,
-                timestamp: unix_time(),
-                memo: None,
+            &premint_secrets,
+            active_keyset_id,
+            IssueContext {
+                quote_id: quote_id.to_string(),
+                unit: quote_info.unit.clone(),
+                cleanup: QuoteCleanup::AddIssued,
+                source: ProofSource::Bolt12Mint,
                 metadata: HashMap::new(),
-                quote_id: Some(quote_id.to_string()),
-            })
-            .await?;
-
-        Ok(proofs)
+            },
+        )
+        .await
     }
 
     /// Check mint quote status
@@ -3,24 +3,470 @@
 //! This module implements wallet-side functions for processing
 //! mining share mint quotes.
 
-use cdk_common::nuts::nut12;
-use cdk_common::nuts::{MintRequest, PreMintSecrets, Proof};
-use cdk_common::wallet::{Transaction, TransactionDirection};
 use std::collections::HashMap;
+use std::fmt::Debug;
+use std::iter::Peekable;
+
+use bitcoin::hashes::sha256;
+use cdk_common::nuts::nutXX::{self, MAX_BATCH_MINT_QUOTE_MINING_SHARE, MAX_BATCH_QUOTE_STATUS};
+use cdk_common::nuts::{
+    BatchMintQuoteMiningShareItem, BatchMintQuoteMiningShareRequest, BatchQuoteStatusItem,
+    BatchQuoteStatusRequest, MintQuoteMiningShareRequest, MintRequest, Proof,
+};
+use cdk_common::wallet::{MintQuote, PendingMintOperation, Transaction, TransactionDirection};
+use futures::stream::{self, Stream, StreamExt};
+use tokio_util::sync::CancellationToken;
 use tracing::instrument;
 
-use crate::dhke::construct_proofs;
-use crate::nuts::ProofsMethods;
+use crate::nuts::{PreMintSecrets, SecretKey, SpendingConditions};
 use cdk_common::amount::SplitTarget;
-use cdk_common::common::ProofInfo;
-use cdk_common::nuts::{MintQuoteMiningShareResponse, State};
-use cdk_common::util::unix_time;
+use cdk_common::nuts::{CurrencyUnit, MintQuoteMiningShareResponse};
 use cdk_common::Amount;
 
-use crate::wallet::Error;
+use crate::types::ProofSource;
+use crate::wallet::issue::{conversion_metadata, IssueContext, QuoteCleanup};
+use crate::wallet::{
+    Capability, Error, QuoteStateSource, QuoteStateUpdate, RequestPriority, RequestScheduler,
+};
 use crate::Wallet;
 
+/// Transaction metadata key of the id of the `index`th minted quote
+fn quote_id_metadata_key(index: usize) -> String {
+    format!("quote.{index}.id")
+}
+
+/// Transaction metadata key of the share header hash of the `index`th minted quote
+fn header_hash_metadata_key(index: usize) -> String {
+    format!("quote.{index}.header_hash")
+}
+
+/// Transaction metadata linking minted mining share quotes to their shares
+///
+/// Takes the quote ids with the header hash of their share, when the wallet
+/// has the quote stored.
+pub(super) fn mining_share_metadata<'a>(
+    quotes: impl IntoIterator<Item = (&'a str, Option<&'a str>)>,
+) -> HashMap<String, String> {
+    let mut metadata = HashMap::new();
+    for (index, (quote_id, header_hash)) in quotes.into_iter().enumerate() {
+        metadata.insert(quote_id_metadata_key(index), quote_id.to_string());
+        if let Some(header_hash) = header_hash {
+            metadata.insert(header_hash_metadata_key(index), header_hash.to_string());
+        }
+    }
+
+    metadata
+}
+
+/// Whether a transaction minted a quote of the share with header hash `header_hash`
+fn is_share_transaction(transaction: &Transaction, header_hash: &str) -> bool {
+    transaction.metadata.iter().any(|(key, value)| {
+        key.starts_with("quote.")
+            && key.ends_with(".header_hash")
+            && value.eq_ignore_ascii_case(header_hash)
+    })
+}
+
+/// Check a field of a mint response against the value the wallet expects
+fn check_response_field<T: PartialEq + Debug>(
+    field: &'static str,
+    expected: T,
+    got: T,
+) -> Result<(), Error> {
+    if expected != got {
+        return Err(Error::MintResponseInvalid {
+            field,
+            expected: format!("{expected:?}"),
+            got: format!("{got:?}"),
+        });
+    }
+
+    Ok(())
+}
+
+/// Split shares into quote batches of at most
+/// [`MAX_BATCH_MINT_QUOTE_MINING_SHARE`] shares and `max_amount` in total
+///
+/// A share over `max_amount` on its own gets a batch of its own.
+fn mining_share_batches<I>(shares: I, max_amount: Option<Amount>) -> MiningShareBatches<I::IntoIter>
+where
+    I: IntoIterator<Item = (Amount, sha256::Hash)>,
+{
+    MiningShareBatches {
+        shares: shares.into_iter().peekable(),
+        max_amount,
+    }
+}
+
+/// Iterator over the quote batches of [`mining_share_batches`]
+struct MiningShareBatches<I: Iterator> {
+    shares: Peekable<I>,
+    max_amount: Option<Amount>,
+}
+
+impl<I> Iterator for MiningShareBatches<I>
+where
+    I: Iterator<Item = (Amount, sha256::Hash)>,
+{
+    type Item = Vec<(Amount, sha256::Hash)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut batch = Vec::new();
+        let mut total = Amount::ZERO;
+
+        while let Some((amount, _)) = self.shares.peek() {
+            let with_share = total
+                .checked_add(*amount)
+                .filter(|total| self.max_amount.is_none_or(|max| *total <= max));
+
+            match with_share {
+                Some(with_share) if batch.len() < MAX_BATCH_MINT_QUOTE_MINING_SHARE => {
+                    total = with_share;
+                }
+                _ if batch.is_empty() => total = *amount,
+                _ => break,
+            }
+
+            batch.extend(self.shares.next());
+        }
+
+        (!batch.is_empty()).then_some(batch)
+    }
+}
+
+/// Check the total of a quote batch against the `max_amount` the mint accepts
+fn check_batch_amount(
+    shares: &[(Amount, sha256::Hash)],
+    max_amount: Option<Amount>,
+) -> Result<(), Error> {
+    let total = Amount::try_sum(shares.iter().map(|(amount, _)| *amount))
+        .map_err(|_| Error::AmountOverflow)?;
+
+    match max_amount {
+        Some(max) if total > max => Err(Error::BatchMintQuoteAmountTooLarge { max, amount: total }),
+        _ => Ok(()),
+    }
+}
+
+/// Check a mining share quote response against the request that created it
+fn validate_mining_share_quote(
+    request: &MintQuoteMiningShareRequest,
+    response: &MintQuoteMiningShareResponse<String>,
+    now: u64,
+) -> Result<(), Error> {
+    check_response_field("amount", Some(request.amount), response.amount)?;
+    check_response_field("unit", Some(&request.unit), response.unit.as_ref())?;
+    check_response_field(
+        "request",
+        request.header_hash.to_string(),
+        response.request.clone(),
+    )?;
+    check_response_field("pubkey", request.pubkey, response.pubkey)?;
+    check_response_field("amount_issued", Amount::ZERO, response.amount_issued)?;
+
+    match response.expiry {
+        Some(expiry) if expiry > now => Ok(()),
+        expiry => Err(Error::MintResponseInvalid {
+            field: "expiry",
+            expected: format!("after {now}"),
+            got: format!("{expiry:?}"),
+        }),
+    }
+}
+
+/// Check a mining share quote status response against the stored quote
+///
+/// Without a stored quote only the consistency of the response is checked.
+fn validate_mining_share_status(
+    quote: Option<&MintQuote>,
+    response: &MintQuoteMiningShareResponse<String>,
+) -> Result<(), Error> {
+    if let Some(amount) = response.amount {
+        if response.amount_issued > amount {
+            return Err(Error::MintResponseInvalid {
+                field: "amount_issued",
+                expected: format!("at most {amount}"),
+                got: response.amount_issued.to_string(),
+            });
+        }
+    }
+
+    if let Some(quote) = quote {
+        check_response_field("amount", quote.amount, response.amount)?;
+        check_response_field("unit", Some(&quote.unit), response.unit.as_ref())?;
+        check_response_field("request", &quote.request, &response.request)?;
+        if let Some(secret_key) = &quote.secret_key {
+            check_response_field("pubkey", secret_key.public_key(), response.pubkey)?;
+        }
+    }
+
+    Ok(())
+}
+
 impl Wallet {
+    /// Request a mining share mint quote for a share with block header hash
+    /// `header_hash`
+    ///
+    /// The quote is locked to a new key stored with the quote. The response
+    /// is checked against the request and nothing is stored if it does not
+    /// match, see [`Error::MintResponseInvalid`].
+    #[instrument(skip(self))]
+    pub async fn mint_quote_mining_share(
+        &self,
+        amount: Amount,
+        header_hash: sha256::Hash,
+        description: Option<String>,
+    ) -> Result<MintQuote, Error> {
+        let (quote, _) = self
+            .mint_quote_mining_share_with_secret_key(amount, header_hash, description, None)
+            .await?;
+
+        Ok(quote)
+    }
+
+    /// Request a mining share mint quote locked to the pubkey of `secret_key`
+    ///
+    /// A new key is generated when `secret_key` is `None`. The keyset counter
+    /// range of the outputs is reserved with the quote, so
+    /// [`Wallet::mint_mining_share`] derives the same premint secrets however
+    /// often it is retried. Returns the quote with the number of reserved
+    /// premint secrets.
+    #[instrument(skip(self, secret_key))]
+    pub async fn mint_quote_mining_share_with_secret_key(
+        &self,
+        amount: Amount,
+        header_hash: sha256::Hash,
+        description: Option<String>,
+        secret_key: Option<SecretKey>,
+    ) -> Result<(MintQuote, u32), Error> {
+        self.ensure_mint_supports(Capability::MiningShare).await?;
+
+        let secret_key = secret_key.unwrap_or_else(SecretKey::generate);
+        let request = MintQuoteMiningShareRequest {
+            amount,
+            unit: self.unit.clone(),
+            header_hash,
+            description,
+            pubkey: secret_key.public_key(),
+            difficulty: None,
+        };
+
+        let response = self
+            .client
+            .post_mint_quote_mining_share(request.clone())
+            .await?;
+        validate_mining_share_quote(&request, &response, self.clock.now_unix())?;
+
+        // The counter is kept in the stored keyset
+        if self
+            .localstore
+            .get_keyset_by_id(&response.keyset_id)
+            .await?
+            .is_none()
+        {
+            self.refresh_keysets_with_unit(&request.unit).await?;
+        }
+        self.localstore
+            .get_keyset_by_id(&response.keyset_id)
+            .await?
+            .ok_or(Error::UnknownKeySet)?;
+
+        let amounts = amount.split_targeted(&SplitTarget::default())?;
+        let num_secrets = amounts.len() as u32;
+        let counter = self
+            .reserve_counter_range(&response.keyset_id, num_secrets)
+            .await?;
+
+        let quote = MintQuote {
+            id: response.quote,
+            mint_url: self.mint_url.clone(),
+            payment_method: cdk_common::PaymentMethod::MiningShare,
+            amount: Some(amount),
+            unit: request.unit,
+            request: response.request,
+            state: response.state.into(),
+            expiry: response.expiry.unwrap_or(0),
+            secret_key: Some(secret_key),
+            amount_issued: response.amount_issued,
+            amount_paid: amount,
+            keyset_id: Some(response.keyset_id),
+            converted_from: None,
+            premint_keyset_id: Some(response.keyset_id),
+            premint_counter: Some(counter.start),
+            premint_amounts: Some(amounts),
+        };
+
+        self.localstore.add_mint_quote(quote.clone()).await?;
+
+        Ok((quote, num_secrets))
+    }
+
+    /// Request mining share mint quotes for several shares
+    ///
+    /// Sends up to [`MAX_BATCH_MINT_QUOTE_MINING_SHARE`] shares per request,
+    /// and no more in total than the batch maximum the mint advertises, each
+    /// locked to its own new key as in [`Wallet::mint_quote_mining_share`].
+    /// Every response of a batch is checked before any quote is stored.
+    /// Shares the mint could not quote are left out of the result.
+    #[instrument(skip_all, fields(shares = shares.len()))]
+    pub async fn mint_quotes_mining_share(
+        &self,
+        shares: &[(Amount, sha256::Hash)],
+    ) -> Result<Vec<MintQuote>, Error> {
+        let mut quotes = Vec::with_capacity(shares.len());
+        let max_amount = self.mining_share_settings().await?.max_batch_amount;
+
+        for chunk in mining_share_batches(shares.iter().copied(), max_amount) {
+            quotes.extend(
+                self.mint_quotes_mining_share_batch(&chunk)
+                    .await?
+                    .into_iter()
+                    .filter_map(Result::ok),
+            );
+        }
+
+        Ok(quotes)
+    }
+
+    /// Request mining share mint quotes for a large backlog of shares
+    ///
+    /// Works like [`Wallet::mint_quotes_mining_share`] with up to
+    /// `concurrency` batch requests in flight. Quotes are stored as their
+    /// batch completes and one result per share is yielded, in the order of
+    /// `shares`. A failed batch only fails its own shares, with
+    /// [`Error::MiningShareQuoteFailed`]. The stream ends once `cancel_token`
+    /// is cancelled, batches still in flight are dropped.
+    pub fn mint_quotes_mining_share_stream<'a, I>(
+        &'a self,
+        shares: I,
+        concurrency: usize,
+        cancel_token: CancellationToken,
+    ) -> impl Stream<Item = Result<MintQuote, Error>> + 'a
+    where
+        I: IntoIterator<Item = (Amount, sha256::Hash)>,
+        I::IntoIter: 'a,
+    {
+        let shares = shares.into_iter();
+
+        stream::once(async move {
+            let max_amount = match self.mining_share_settings().await {
+                Ok(settings) => settings.max_batch_amount,
+                Err(err) => {
+                    // Every batch reads the settings again and fails with the error
+                    tracing::warn!("Could not read mining share settings: {}", err);
+                    None
+                }
+            };
+            stream::iter(mining_share_batches(shares, max_amount))
+        })
+        .flatten()
+        .map(move |chunk| async move {
+            let results = match self.mint_quotes_mining_share_batch(&chunk).await {
+                Ok(results) => results,
+                Err(err) => {
+                    tracing::warn!("Mining share quote batch failed: {}", err);
+                    let reason = err.to_string();
+                    chunk
+                        .iter()
+                        .map(|(_, header_hash)| {
+                            Err(Error::MiningShareQuoteFailed {
+                                header_hash: header_hash.to_string(),
+                                reason: reason.clone(),
+                            })
+                        })
+                        .collect()
+                }
+            };
+            stream::iter(results)
+        })
+        .buffered(concurrency.max(1))
+        .flatten()
+        .take_until(async move { cancel_token.cancelled().await })
+    }
+
+    /// Request mining share mint quotes for at most
+    /// [`MAX_BATCH_MINT_QUOTE_MINING_SHARE`] shares in one request
+    ///
+    /// Returns one result per share. The batch fails as a whole if the
+    /// response does not match the request.
+    async fn mint_quotes_mining_share_batch(
+        &self,
+        shares: &[(Amount, sha256::Hash)],
+    ) -> Result<Vec<Result<MintQuote, Error>>, Error> {
+        self.ensure_mint_supports(Capability::MiningShare).await?;
+        check_batch_amount(shares, self.mining_share_settings().await?.max_batch_amount)?;
+
+        let requests: Vec<(SecretKey, MintQuoteMiningShareRequest)> = shares
+            .iter()
+            .map(|(amount, header_hash)| {
+                let secret_key = SecretKey::generate();
+                let request = MintQuoteMiningShareRequest {
+                    amount: *amount,
+                    unit: self.unit.clone(),
+                    header_hash: *header_hash,
+                    description: None,
+                    pubkey: secret_key.public_key(),
+                    difficulty: None,
+                };
+                (secret_key, request)
+            })
+            .collect();
+
+        let batch = self
+            .client
+            .post_mint_quotes_mining_share_batch(BatchMintQuoteMiningShareRequest {
+                quotes: requests.iter().map(|(_, request)| request.clone()).collect(),
+            })
+            .await?;
+        check_response_field("quotes", requests.len(), batch.quotes.len())?;
+
+        let now = self.clock.now_unix();
+        let mut checked = Vec::with_capacity(requests.len());
+        for ((secret_key, request), item) in requests.into_iter().zip(batch.quotes) {
+            match item {
+                BatchMintQuoteMiningShareItem::Quote(response) => {
+                    validate_mining_share_quote(&request, &response, now)?;
+                    checked.push(Ok(MintQuote {
+                        id: response.quote,
+                        mint_url: self.mint_url.clone(),
+                        payment_method: cdk_common::PaymentMethod::MiningShare,
+                        amount: Some(request.amount),
+                        unit: request.unit,
+                        request: response.request,
+                        state: response.state.into(),
+                        expiry: response.expiry.unwrap_or(0),
+                        secret_key: Some(secret_key),
+                        amount_issued: response.amount_issued,
+                        amount_paid: request.amount,
+                        keyset_id: Some(response.keyset_id),
+                        converted_from: None,
+                        premint_keyset_id: None,
+                        premint_counter: None,
+                        premint_amounts: None,
+                    }));
+                }
+                BatchMintQuoteMiningShareItem::Error { header_hash, error } => {
+                    check_response_field("header_hash", request.header_hash, header_hash)?;
+                    tracing::warn!(
+                        "Mint could not quote mining share {}: {}",
+                        header_hash,
+                        error
+                    );
+                    checked.push(Err(Error::MiningShareQuoteFailed {
+                        header_hash: header_hash.to_string(),
+                        reason: error,
+                    }));
+                }
+            }
+        }
+
+        for quote in checked.iter().flatten() {
+            self.localstore.add_mint_quote(quote.clone()).await?;
+        }
+
+        Ok(checked)
+    }
+
     /// Retrieves mining share proofs using stored premint secrets
     ///
     /// Mint tokens directly from mining share quote info
@@ -28,6 +474,17 @@ impl Wallet {
     /// This function implements direct minting without requiring local quote storage.
     /// It takes the quote information from the remote lookup and mints directly.
     /// This follows the same pattern as the standard Bolt11 mint() function.
+    /// Requests are scheduled as [`RequestPriority::Issuance`].
+    ///
+    /// A quote can be minted in several parts. Pass [`Amount::ZERO`] to mint
+    /// what is left of a stored quote; larger amounts than that are refused
+    /// with [`Error::AmountExceedsMintable`] before contacting the mint.
+    ///
+    /// If the mint rotated `keyset_id` out, the proofs are minted in its
+    /// active keyset instead, see [`Wallet::migrate_mining_share_keyset`].
+    ///
+    /// With `spending_conditions` the proofs are locked, e.g. to a miner's
+    /// P2PK key. Their secrets are random, so the keyset counter is not used.
     #[instrument(skip_all)]
     pub async fn mint_mining_share(
         &self,
@@ -35,35 +492,233 @@ impl Wallet {
         amount: Amount,
         keyset_id: crate::nuts::Id,
         secret_key: crate::nuts::SecretKey, // Now mandatory for NUT-20 signing
+        spending_conditions: Option<SpendingConditions>,
     ) -> Result<Vec<Proof>, Error> {
+        self.mint_mining_share_with_unit(
+            quote_id,
+            amount,
+            keyset_id,
+            secret_key,
+            &self.unit,
+            spending_conditions,
+        )
+        .await
+    }
+
+    /// Mint mining share proofs in a unit other than the default wallet unit
+    ///
+    /// Mining share quotes are usually denominated in [`CurrencyUnit::Hash`]
+    /// while the wallet defaults to a bitcoin unit. The keyset has to be of
+    /// `unit`.
+    #[instrument(skip_all)]
+    pub async fn mint_mining_share_with_unit(
+        &self,
+        quote_id: &str,
+        amount: Amount,
+        keyset_id: crate::nuts::Id,
+        secret_key: crate::nuts::SecretKey,
+        unit: &CurrencyUnit,
+        spending_conditions: Option<SpendingConditions>,
+    ) -> Result<Vec<Proof>, Error> {
+        RequestScheduler::with_priority(
+            RequestPriority::Issuance,
+            self.issue_mining_share(
+                quote_id,
+                amount,
+                keyset_id,
+                secret_key,
+                unit,
+                spending_conditions.as_ref(),
+            ),
+        )
+        .await
+    }
+
+    /// List the incoming transactions that minted a quote of the share with
+    /// block header hash `header_hash`
+    ///
+    /// Transactions of every unit of this mint are searched. Only quotes the
+    /// wallet had stored when minting are linked to their share.
+    #[instrument(skip(self))]
+    pub async fn list_transactions_for_share_hash(
+        &self,
+        header_hash: &str,
+    ) -> Result<Vec<Transaction>, Error> {
+        let mut transactions: Vec<Transaction> = self
+            .localstore
+            .list_transactions(
+                Some(self.mint_url.clone()),
+                Some(TransactionDirection::Incoming),
+                None,
+            )
+            .await?
+            .into_iter()
+            .filter(|transaction| is_share_transaction(transaction, header_hash))
+            .collect();
+
+        transactions.sort();
+
+        Ok(transactions)
+    }
+
+    /// Move a stored mining share quote to the active keyset of its unit
+    ///
+    /// A quote records the keyset that was active when the mint created it.
+    /// Once the mint rotates that keyset out, it refuses outputs in it and the
+    /// quote could no longer be minted. [`Wallet::mint_mining_share`] calls
+    /// this on its own when it finds the keyset of a quote inactive.
+    ///
+    /// Blinded messages are derived when minting, from a counter range reserved
+    /// in the new keyset, so no counter of the old keyset is used again.
+    #[instrument(skip(self))]
+    pub async fn migrate_mining_share_keyset(
+        &self,
+        quote_id: &str,
+    ) -> Result<crate::nuts::Id, Error> {
+        let mut quote = self
+            .localstore
+            .get_mint_quote(quote_id)
+            .await?
+            .ok_or(Error::UnknownQuote)?;
+        if quote.payment_method != cdk_common::PaymentMethod::MiningShare {
+            return Err(Error::UnsupportedPaymentMethod);
+        }
+
+        let active_keyset_id = self.fetch_active_keyset_with_unit(&quote.unit).await?.id;
+        if quote.keyset_id != Some(active_keyset_id) {
+            tracing::info!(
+                "Moving mining share quote {} from keyset {:?} to {}",
+                quote_id,
+                quote.keyset_id,
+                active_keyset_id
+            );
+            quote.keyset_id = Some(active_keyset_id);
+            self.localstore.add_mint_quote(quote).await?;
+        }
+
+        Ok(active_keyset_id)
+    }
+
+    /// Mint a mining share quote
+    async fn issue_mining_share(
+        &self,
+        quote_id: &str,
+        amount: Amount,
+        keyset_id: crate::nuts::Id,
+        secret_key: crate::nuts::SecretKey,
+        unit: &CurrencyUnit,
+        spending_conditions: Option<&SpendingConditions>,
+    ) -> Result<Vec<Proof>, Error> {
+        // Reject quotes of another unit before contacting the mint
+        let mut local_quote = self.localstore.get_mint_quote(quote_id).await?;
+        if let Some(quote) = &local_quote {
+            self.check_quote_unit(&quote.unit, unit)?;
+        }
+
+        // Default to the rest of the quote and never ask for more than that
+        let amount = match &local_quote {
+            Some(quote) => {
+                let mintable = quote.amount_mintable();
+                if amount > mintable {
+                    tracing::warn!(
+                        "Cannot mint {} of quote {}, only {} left",
+                        amount.display(unit),
+                        quote_id,
+                        mintable.display(unit)
+                    );
+                    return Err(Error::AmountExceedsMintable {
+                        requested: amount,
+                        mintable,
+                    });
+                }
+
+                if amount == Amount::ZERO {
+                    mintable
+                } else {
+                    amount
+                }
+            }
+            None => amount,
+        };
+        if amount == Amount::ZERO {
+            tracing::debug!("Nothing left to mint for quote {}", quote_id);
+            return Err(Error::AmountUndefined);
+        }
+
         // Ensure we have fresh keysets
-        self.refresh_keysets().await?;
+        let keysets = self.refresh_keysets_with_unit(unit).await?;
+
+        // The mint refuses outputs in a keyset it rotated out
+        let rotated = keysets
+            .iter()
+            .any(|keyset| keyset.id == keyset_id && !keyset.active);
+        let keyset_id = match (rotated, &mut local_quote) {
+            (false, _) => keyset_id,
+            (true, Some(quote)) => {
+                let active_keyset_id = self.migrate_mining_share_keyset(quote_id).await?;
+                quote.keyset_id = Some(active_keyset_id);
+                active_keyset_id
+            }
+            (true, None) => {
+                tracing::info!(
+                    "Keyset {} is no longer active, minting quote {} in the active keyset",
+                    keyset_id,
+                    quote_id
+                );
+                self.fetch_active_keyset_with_unit(unit).await?.id
+            }
+        };
+
+        // The keyset determines the unit of the minted proofs
+        let keyset = self
+            .localstore
+            .get_keyset_by_id(&keyset_id)
+            .await?
+            .ok_or(Error::UnknownKeySet)?;
+        self.check_quote_unit(&keyset.unit, unit)?;
+        self.ensure_keyset_trusted(keyset_id).await?;
 
-        // Generate premint secrets using provided keyset and amount
-        // This follows the same counter management as bolt11 minting
         let amount_split = amount.split_targeted(&SplitTarget::default())?;
-        let num_secrets = amount_split.len() as u32;
 
-        tracing::debug!(
-            "Incrementing keyset {} counter by {}",
-            keyset_id,
-            num_secrets
-        );
+        // Fail locally rather than have the mint reject the outputs
+        let max = self.max_mining_share_outputs(amount, keyset_id).await?;
+        if amount_split.len() > max {
+            return Err(Error::MiningShareTooManyOutputs {
+                max,
+                outputs: amount_split.len(),
+            });
+        }
 
-        // Atomically get the counter range we need
-        let new_counter = self
-            .localstore
-            .increment_keyset_counter(&keyset_id, num_secrets)
-            .await?;
-        let count = new_counter - num_secrets;
+        // Use the counter range reserved with the quote when minting all of it
+        let reserved_counter = match (&local_quote, spending_conditions) {
+            (Some(quote), None)
+                if quote.amount_issued == Amount::ZERO
+                    && quote.amount == Some(amount)
+                    && quote.premint_keyset_id == Some(keyset_id) =>
+            {
+                quote.premint_counter
+            }
+            _ => None,
+        };
 
-        let premint_secrets = PreMintSecrets::from_seed(
-            keyset_id,
-            count,
-            &self.seed,
-            amount,
-            &SplitTarget::default(),
-        )?;
+        let premint_secrets = match reserved_counter {
+            Some(counter) => PreMintSecrets::from_seed(
+                keyset_id,
+                counter,
+                &self.seed,
+                amount,
+                &SplitTarget::default(),
+            )?,
+            None => {
+                self.prepare_premint_secrets(
+                    keyset_id,
+                    amount,
+                    &SplitTarget::default(),
+                    spending_conditions,
+                )
+                .await?
+            }
+        };
 
         // Create and sign mint request (NUT-20 compliance)
         let mut mint_request = MintRequest {
@@ -75,75 +730,100 @@ impl Wallet {
         // Sign the request (mandatory for mining shares with NUT-20)
         mint_request.sign(secret_key.clone())?;
 
-        // Submit the mint request using dedicated mining share endpoint
-        let mint_response = self.client.post_mint_mining_share(mint_request).await?;
-
-        // Load keyset for DLEQ verification
-        let keys = self.load_keyset_keys(keyset_id).await?;
-
-        // Verify DLEQ proofs (same as bolt11)
-        for (sig, premint) in mint_response
-            .signatures
-            .iter()
-            .zip(&premint_secrets.secrets)
+        // Submit the mint request using dedicated mining share endpoint. Outputs
+        // derived from the reserved counter may have been signed by a previous
+        // attempt whose response never reached us.
+        let signatures = match self
+            .client
+            .post_mint_mining_share(mint_request.clone())
+            .await
         {
-            let keys = self.load_keyset_keys(sig.keyset_id).await?;
-            let key = keys.amount_key(sig.amount).ok_or(Error::AmountKey)?;
-            match sig.verify_dleq(key, premint.blinded_message.blinded_secret) {
-                Ok(_) | Err(nut12::Error::MissingDleqProof) => (),
-                Err(_) => return Err(Error::CouldNotVerifyDleq),
+            Ok(response) => response.signatures,
+            Err(err) => {
+                let operation = PendingMintOperation {
+                    id: PendingMintOperation::outputs_id(&mint_request.outputs),
+                    quote_id: quote_id.to_string(),
+                    mint_url: self.mint_url.clone(),
+                    payment_method: cdk_common::PaymentMethod::MiningShare,
+                    unit: unit.clone(),
+                    keyset_id,
+                    request: mint_request,
+                    premint_secrets: premint_secrets.clone(),
+                    expiry: local_quote.as_ref().map_or(0, |quote| quote.expiry),
+                    created_time: self.clock.now_unix(),
+                };
+                self.handle_failed_mint(err, operation, reserved_counter.is_some())
+                    .await?
             }
-        }
-
-        // Construct proofs from signatures and secrets (same as bolt11)
-        let proofs = construct_proofs(
-            mint_response.signatures,
-            premint_secrets.rs(),
-            premint_secrets.secrets(),
-            &keys,
-        )?;
-
-        // Store proofs in wallet
-        let proof_infos = proofs
-            .iter()
-            .map(|proof| {
-                ProofInfo::new(
-                    proof.clone(),
-                    self.mint_url.clone(),
-                    State::Unspent,
-                    self.unit.clone(),
-                )
-            })
-            .collect::<Result<Vec<ProofInfo>, _>>()?;
+        };
 
-        self.localstore.update_proofs(proof_infos, vec![]).await?;
+        // Link the transaction to the quote and, if stored, its share
+        let mut metadata = conversion_metadata(
+            local_quote
+                .as_ref()
+                .and_then(|quote| quote.converted_from.as_deref()),
+        );
+        metadata.extend(mining_share_metadata([(
+            quote_id,
+            local_quote.as_ref().map(|quote| quote.request.as_str()),
+        )]));
 
-        // Add transaction record (same as bolt11)
-        self.localstore
-            .add_transaction(Transaction {
-                mint_url: self.mint_url.clone(),
-                direction: TransactionDirection::Incoming,
-                amount: proofs.total_amount()?,
-                fee: Amount::ZERO,
-                unit: self.unit.clone(),
-                ys: proofs.ys()?,
-                timestamp: unix_time(),
-                memo: None,
-                metadata: HashMap::new(),
-                quote_id: Some(quote_id.to_string()),
-            })
+        // Record the amount issued on the local quote (same as bolt12)
+        let proofs = self
+            .finalize_issued_proofs(
+                signatures,
+                &premint_secrets,
+                keyset_id,
+                IssueContext {
+                    quote_id: quote_id.to_string(),
+                    unit: unit.clone(),
+                    cleanup: QuoteCleanup::AddIssuedUntilPaid,
+                    source: ProofSource::MiningShareMint,
+                    metadata,
+                },
+            )
             .await?;
 
         tracing::debug!(
             "Successfully minted {} mining share proofs for quote {} (amount: {})",
             proofs.len(),
             quote_id,
-            amount
+            amount.display(unit)
         );
 
         Ok(proofs)
     }
 
+    /// Most outputs the mint accepts when minting `amount` of a mining share
+    /// quote in `keyset_id`
+    ///
+    /// Uses the output slack the mint advertises in its stored info.
+    #[instrument(skip(self))]
+    pub async fn max_mining_share_outputs(
+        &self,
+        amount: Amount,
+        keyset_id: crate::nuts::Id,
+    ) -> Result<usize, Error> {
+        let settings = self.mining_share_settings().await?;
+        let keys = self.load_keyset_keys(keyset_id).await?;
+        let denominations: Vec<u64> = keys
+            .iter()
+            .map(|(denomination, _)| u64::from(*denomination))
+            .collect();
+
+        Ok(settings.max_outputs(amount, &denominations))
+    }
+
+    /// Mining share settings of the stored mint info
+    async fn mining_share_settings(&self) -> Result<nutXX::Settings, Error> {
+        Ok(self
+            .localstore
+            .get_mint(self.mint_url.clone())
+            .await?
+            .and_then(|info| info.nuts.nutxx)
+            .unwrap_or_default())
+    }
+
     /// Handles mint errors with appropriate logging and returns whether to skip
     fn handle_mint_error(&self, quote_id: &str, error: &Error) -> bool {
         let error_msg = error.to_string();
@@ -160,23 +840,101 @@ impl Wallet {
     }
 
     /// Check mining share mint quote status
+    ///
+    /// The state returned never moves a known quote backwards, a stale answer
+    /// from the mint is recorded instead, see [`Wallet::quote_state_conflicts`].
+    /// Conflicts with the stored quote are returned with the response, see
+    /// [`crate::wallet::QuoteConflict`]. A response that does not match the
+    /// stored quote is rejected with [`Error::MintResponseInvalid`] before
+    /// anything is stored.
     #[instrument(skip(self, quote_id))]
     pub async fn mint_quote_state_mining_share(
         &self,
         quote_id: &str,
-    ) -> Result<MintQuoteMiningShareResponse<String>, Error> {
+    ) -> Result<QuoteStateUpdate<MintQuoteMiningShareResponse<String>>, Error> {
         let response = self
             .client
             .get_mint_quote_status_mining_share(quote_id)
             .await?;
 
-        match self.localstore.get_mint_quote(quote_id).await? {
+        let local_quote = self.localstore.get_mint_quote(quote_id).await?;
+        validate_mining_share_status(local_quote.as_ref(), &response)?;
+
+        self.store_mining_share_status(quote_id, local_quote, response)
+            .await
+    }
+
+    /// Check the state of several mining share mint quotes
+    ///
+    /// Asks the mint for up to [`MAX_BATCH_QUOTE_STATUS`] quotes per request
+    /// and stores each state as [`Wallet::mint_quote_state_mining_share`]
+    /// does. Every response of a batch is checked before any is stored.
+    /// Quotes the mint could not check are left out of the result.
+    #[instrument(skip_all, fields(quotes = quote_ids.len()))]
+    pub async fn mint_quote_states_mining_share(
+        &self,
+        quote_ids: &[String],
+    ) -> Result<Vec<QuoteStateUpdate<MintQuoteMiningShareResponse<String>>>, Error> {
+        let mut responses = Vec::with_capacity(quote_ids.len());
+
+        for chunk in quote_ids.chunks(MAX_BATCH_QUOTE_STATUS) {
+            let batch = self
+                .client
+                .post_mint_quote_status_mining_share_batch(BatchQuoteStatusRequest {
+                    quotes: chunk.to_vec(),
+                })
+                .await?;
+            check_response_field("quotes", chunk.len(), batch.quotes.len())?;
+
+            let mut checked = Vec::with_capacity(chunk.len());
+            for (quote_id, item) in chunk.iter().zip(batch.quotes) {
+                match item {
+                    BatchQuoteStatusItem::Quote(response) => {
+                        check_response_field("quote", quote_id, &response.quote)?;
+                        let local_quote = self.localstore.get_mint_quote(quote_id).await?;
+                        validate_mining_share_status(local_quote.as_ref(), &response)?;
+                        checked.push((local_quote, response));
+                    }
+                    BatchQuoteStatusItem::Error { quote, error } => {
+                        tracing::warn!(
+                            "Mint could not check mining share quote {}: {}",
+                            quote,
+                            error
+                        );
+                    }
+                }
+            }
+
+            for (local_quote, response) in checked {
+                let quote_id = response.quote.clone();
+                responses.push(
+                    self.store_mining_share_status(&quote_id, local_quote, response)
+                        .await?,
+                );
+            }
+        }
+
+        Ok(responses)
+    }
+
+    /// Store a checked mining share quote state, creating the local quote if
+    /// the wallet does not have it
+    async fn store_mining_share_status(
+        &self,
+        quote_id: &str,
+        local_quote: Option<MintQuote>,
+        mut response: MintQuoteMiningShareResponse<String>,
+    ) -> Result<QuoteStateUpdate<MintQuoteMiningShareResponse<String>>, Error> {
+        let conflict = match local_quote {
             Some(quote) => {
                 // Update existing local quote with current state and keyset_id
                 let mut quote = quote;
-                quote.state = response.state.into();
                 quote.keyset_id = Some(response.keyset_id);
-                self.localstore.add_mint_quote(quote).await?;
+                let (quote, conflict) = self
+                    .check_mint_quote_state(quote, response.state.into(), QuoteStateSource::Http)
+                    .await?;
+                response.state = quote.state.into();
+                conflict
             }
             None => {
                 // Create new local quote record from the API response
@@ -195,12 +953,17 @@ impl Wallet {
                     amount_issued: response.amount_issued,
                     amount_paid: response.amount.unwrap_or(Amount::ZERO),
                     keyset_id: Some(response.keyset_id),
+                    converted_from: None,
+                    premint_keyset_id: None,
+                    premint_counter: None,
+                    premint_amounts: None,
                 };
 
                 self.localstore.add_mint_quote(wallet_quote).await?;
+                None
             }
-        }
+        };
 
-        Ok(response)
+        Ok(QuoteStateUpdate { response, conflict })
     }
 }
@@ -1,3 +1,313 @@
+use std::collections::HashMap;
+use std::ops::Range;
+
+use cdk_common::wallet::{PendingMintOperation, Transaction, TransactionDirection};
+
+use crate::amount::SplitTarget;
+use crate::dhke::construct_proofs;
+use crate::nuts::nut00::ProofsMethods;
+use crate::nuts::{
+    BlindSignature, CurrencyUnit, Id, MintQuoteState, PreMintSecrets, Proofs, SpendingConditions,
+    State,
+};
+use crate::types::{ProofInfo, ProofSource};
+use crate::util::unix_time;
+use crate::{ensure_cdk, Amount, Error, Wallet};
+
+mod cancel;
+mod convert;
 mod issue_bolt11;
 mod issue_bolt12;
 mod issue_mining_share;
+mod pending;
+mod quote_export;
+
+pub(crate) use convert::conversion_metadata;
+pub use pending::PendingOperationsSummary;
+pub use quote_export::{ExportedQuote, PendingQuoteExport, PENDING_QUOTE_EXPORT_VERSION};
+
+/// Outcome of checking the wallet's mint quotes
+#[derive(Debug, Default)]
+pub struct MintQuotesSummary {
+    /// Amount minted from paid quotes
+    pub total_minted: Amount,
+    /// Quotes ecash was minted for
+    pub minted_quotes: Vec<String>,
+    /// Quotes that could not be checked or minted, kept for the next check
+    pub failed_quotes: Vec<(String, Error)>,
+}
+
+/// How the local quote is updated once its proofs are stored
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum QuoteCleanup {
+    /// Remove the quote, and with it its premint counter
+    Remove,
+    /// Add the amount minted to the amount issued of the quote
+    AddIssued,
+    /// As [`QuoteCleanup::AddIssued`], and mark the quote issued once all it
+    /// was paid for is minted
+    AddIssuedUntilPaid,
+}
+
+/// Quote the signatures passed to [`Wallet::finalize_issued_proofs`] were
+/// issued for
+#[derive(Debug, Clone)]
+pub(crate) struct IssueContext {
+    /// Quote id
+    pub quote_id: String,
+    /// Unit of the proofs
+    pub unit: CurrencyUnit,
+    /// Update of the local quote, if stored
+    pub cleanup: QuoteCleanup,
+    /// Source recorded on the proofs
+    pub source: ProofSource,
+    /// Metadata of the transaction record
+    pub metadata: HashMap<String, String>,
+}
+
+/// Check that `split_target` can be met when minting `amount`
+///
+/// Target values that do not add up to the amount are topped up with the
+/// default split, only targets asking for more than the amount are refused.
+fn check_split_target(split_target: &SplitTarget, amount: Amount) -> Result<(), Error> {
+    let requested = match split_target {
+        SplitTarget::None => return Ok(()),
+        SplitTarget::Value(value) => {
+            ensure_cdk!(
+                *value != Amount::ZERO,
+                Error::InvalidSplitTarget {
+                    requested: *value,
+                    available: amount,
+                }
+            );
+            *value
+        }
+        SplitTarget::Values(values) => Amount::try_sum(values.iter().copied())?,
+    };
+
+    ensure_cdk!(
+        requested <= amount,
+        Error::InvalidSplitTarget {
+            requested,
+            available: amount,
+        }
+    );
+
+    Ok(())
+}
+
+impl Wallet {
+    /// Check that a quote is denominated in the unit of the operation
+    ///
+    /// Proofs minted in another unit are not counted in that unit's balance, so
+    /// minting them would make them look lost.
+    fn check_quote_unit(
+        &self,
+        quote_unit: &CurrencyUnit,
+        unit: &CurrencyUnit,
+    ) -> Result<(), Error> {
+        if quote_unit != unit {
+            tracing::warn!(
+                "Quote unit {} does not match wallet unit {}",
+                quote_unit,
+                unit
+            );
+            return Err(Error::QuoteUnitMismatch {
+                quote: quote_unit.clone(),
+                wallet: unit.clone(),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Reserve `count` counter values of a keyset to derive secrets from
+    ///
+    /// The range comes from one atomic increment of the stored counter, so
+    /// concurrent operations on the same wallet get disjoint ranges and never
+    /// derive the same secret.
+    pub(crate) async fn reserve_counter_range(
+        &self,
+        keyset_id: &Id,
+        count: u32,
+    ) -> Result<Range<u32>, Error> {
+        tracing::debug!("Incrementing keyset {} counter by {}", keyset_id, count);
+
+        let end = self
+            .localstore
+            .increment_keyset_counter(keyset_id, count)
+            .await?;
+
+        Ok(end - count..end)
+    }
+
+    /// Turn the signatures of a mint request into stored proofs
+    ///
+    /// Shared by every issue path: constructs the proofs, verifies their DLEQ
+    /// proofs, stores them, records an incoming transaction, removes a pending
+    /// operation queued for the same outputs and finally updates the local
+    /// quote.
+    ///
+    /// The writes are not one database transaction. Instead the quote update
+    /// comes last and every write before it is keyed by the outputs: proofs by
+    /// their `Y`, the transaction by the hash of those and the pending
+    /// operation by the blinded messages. A failure before the quote update
+    /// leaves the quote mintable, and finalizing the same outputs again, as
+    /// [`Wallet::reconcile_issued_quote`] does, overwrites the earlier writes
+    /// instead of duplicating them.
+    pub(crate) async fn finalize_issued_proofs(
+        &self,
+        signatures: Vec<BlindSignature>,
+        premint_secrets: &PreMintSecrets,
+        keyset_id: Id,
+        context: IssueContext,
+    ) -> Result<Proofs, Error> {
+        let keys = self.load_keyset_keys(keyset_id).await?;
+
+        let proofs = construct_proofs(
+            signatures,
+            premint_secrets.rs(),
+            premint_secrets.secrets(),
+            &keys,
+        )?;
+
+        // Verify the DLEQ proofs are valid
+        self.verify_proofs_dleq(&proofs, false).await?;
+
+        let amount = proofs.total_amount()?;
+
+        let proof_infos = proofs
+            .iter()
+            .map(|proof| {
+                ProofInfo::new(
+                    proof.clone(),
+                    self.mint_url.clone(),
+                    State::Unspent,
+                    context.unit.clone(),
+                )
+                .map(|info| info.with_source(context.source))
+            })
+            .collect::<Result<Vec<ProofInfo>, _>>()?;
+
+        // Add new proofs to store
+        self.localstore.update_proofs(proof_infos, vec![]).await?;
+
+        // Add transaction to store
+        self.localstore
+            .add_transaction(Transaction {
+                mint_url: self.mint_url.clone(),
+                direction: TransactionDirection::Incoming,
+                amount,
+                fee: Amount::ZERO,
+                unit: context.unit,
+                ys: proofs.ys()?,
+                timestamp: unix_time(),
+                memo: None,
+                metadata: context.metadata,
+                quote_id: Some(context.quote_id.clone()),
+            })
+            .await?;
+
+        self.localstore
+            .remove_pending_mint_operation(&PendingMintOperation::outputs_id(
+                &premint_secrets.blinded_messages(),
+            ))
+            .await?;
+
+        // Last, so the quote stays mintable until everything else is stored
+        match context.cleanup {
+            QuoteCleanup::Remove => {
+                self.localstore.remove_mint_quote(&context.quote_id).await?;
+            }
+            QuoteCleanup::AddIssued | QuoteCleanup::AddIssuedUntilPaid => {
+                if let Some(mut quote) = self.localstore.get_mint_quote(&context.quote_id).await? {
+                    quote.amount_issued = quote
+                        .amount_issued
+                        .checked_add(amount)
+                        .ok_or(Error::AmountOverflow)?;
+                    if context.cleanup == QuoteCleanup::AddIssuedUntilPaid
+                        && quote.amount_issued >= quote.amount_paid
+                    {
+                        quote.state = MintQuoteState::Issued;
+                    }
+                    self.localstore.add_mint_quote(quote).await?;
+                }
+            }
+        }
+
+        Ok(proofs)
+    }
+
+    /// Derive the premint secrets to mint `amount` in a keyset
+    ///
+    /// The secrets are derived from the seed over a range reserved with
+    /// [`Self::reserve_counter_range`]. Secrets bearing `spending_conditions`
+    /// are random and leave the counter untouched.
+    pub(crate) async fn prepare_premint_secrets(
+        &self,
+        keyset_id: Id,
+        amount: Amount,
+        split_target: &SplitTarget,
+        spending_conditions: Option<&SpendingConditions>,
+    ) -> Result<PreMintSecrets, Error> {
+        check_split_target(split_target, amount)?;
+
+        if let Some(spending_conditions) = spending_conditions {
+            return Ok(PreMintSecrets::with_conditions(
+                keyset_id,
+                amount,
+                split_target,
+                spending_conditions,
+            )?);
+        }
+
+        let num_secrets = amount.split_targeted(split_target)?.len() as u32;
+        let counter = self.reserve_counter_range(&keyset_id, num_secrets).await?;
+
+        Ok(PreMintSecrets::from_seed(
+            keyset_id,
+            counter.start,
+            &self.seed,
+            amount,
+            split_target,
+        )?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_split_target() {
+        let amount = Amount::from(100);
+
+        // Exact fit
+        check_split_target(&SplitTarget::Value(Amount::from(100)), amount).unwrap();
+        check_split_target(
+            &SplitTarget::Values(vec![Amount::from(64), Amount::from(36)]),
+            amount,
+        )
+        .unwrap();
+
+        // Remainders are split by default
+        check_split_target(&SplitTarget::Value(Amount::from(30)), amount).unwrap();
+        check_split_target(&SplitTarget::Values(vec![Amount::from(50)]), amount).unwrap();
+        let split = amount
+            .split_targeted(&SplitTarget::Value(Amount::from(30)))
+            .unwrap();
+        assert_eq!(Amount::try_sum(split).unwrap(), amount);
+
+        // Impossible targets
+        for split_target in [
+            SplitTarget::Value(Amount::from(101)),
+            SplitTarget::Value(Amount::ZERO),
+            SplitTarget::Values(vec![Amount::from(64), Amount::from(64)]),
+        ] {
+            assert!(matches!(
+                check_split_target(&split_target, amount),
+                Err(Error::InvalidSplitTarget { available, .. }) if available == amount
+            ));
+        }
+    }
+}
@@ -0,0 +1,226 @@
+//! Pending mint operations
+//!
+//! A mint request that fails before the mint answered, because the mint is
+//! unreachable, timed out or a gateway in front of it failed, may still have
+//! been processed. The request is stored with the secrets of its outputs
+//! instead of being dropped, and [`Wallet::process_pending_operations`] sends
+//! it again once the mint is reachable.
+//!
+//! Replaying never asks for new signatures on outputs the mint already
+//! signed: their signatures are recovered with a restore request first, the
+//! stored request is only sent again when the mint has none.
+
+use cdk_common::wallet::{MintQuote, PendingMintOperation};
+use cdk_common::PaymentMethod;
+use tracing::instrument;
+
+use crate::nuts::nut00::ProofsMethods;
+use crate::nuts::BlindSignature;
+use crate::types::ProofSource;
+use crate::wallet::issue::issue_mining_share::mining_share_metadata;
+use crate::wallet::issue::{conversion_metadata, IssueContext, QuoteCleanup};
+use crate::{Amount, Error, Wallet};
+
+/// Whether `error` leaves open if a request reached the mint
+///
+/// Protocol errors are answers of the mint and never retried.
+pub(crate) fn is_transport_error(error: &Error) -> bool {
+    match error {
+        Error::Timeout | Error::HttpError(None, _) => true,
+        Error::HttpError(Some(status), _) => (500..=599).contains(status),
+        _ => false,
+    }
+}
+
+/// Outcome of processing the pending mint operations
+#[derive(Debug, Default)]
+pub struct PendingOperationsSummary {
+    /// Amount minted by the operations that completed
+    pub total_minted: Amount,
+    /// Quotes whose operation completed
+    pub minted_quotes: Vec<String>,
+    /// Quotes whose operation is kept as the mint is still unreachable
+    pub pending_quotes: Vec<String>,
+    /// Quotes whose operation was dropped as the quote expired
+    pub expired_quotes: Vec<String>,
+    /// Quotes whose operation the mint refused, the operation is dropped
+    pub failed_quotes: Vec<(String, Error)>,
+}
+
+impl Wallet {
+    /// Handle a mint request for `operation` that failed with `error`
+    ///
+    /// With `recover`, signatures the mint issued for the outputs anyway are
+    /// returned. A request that may not have reached the mint is stored as a
+    /// pending operation and fails with [`Error::QueuedForRetry`], any other
+    /// error is returned as is.
+    pub(crate) async fn handle_failed_mint(
+        &self,
+        error: Error,
+        operation: PendingMintOperation,
+        recover: bool,
+    ) -> Result<Vec<BlindSignature>, Error> {
+        if recover {
+            match self.issued_signatures(&operation.premint_secrets).await {
+                Ok(Some(signatures)) => {
+                    tracing::info!("Recovered signatures for mint quote {}", operation.quote_id);
+                    return Ok(signatures);
+                }
+                Ok(None) => {}
+                Err(err) if is_transport_error(&err) => {}
+                Err(err) => return Err(err),
+            }
+        }
+
+        if !is_transport_error(&error) {
+            return Err(error);
+        }
+
+        tracing::warn!(
+            "Mint request for quote {} failed, queueing it for retry: {}",
+            operation.quote_id,
+            error
+        );
+        let quote_id = operation.quote_id.clone();
+        self.localstore
+            .add_pending_mint_operation(operation)
+            .await?;
+
+        Err(Error::QueuedForRetry(quote_id))
+    }
+
+    /// Mint requests of this mint waiting to be sent again, oldest first
+    #[instrument(skip(self))]
+    pub async fn pending_mint_operations(&self) -> Result<Vec<PendingMintOperation>, Error> {
+        Ok(self
+            .localstore
+            .get_pending_mint_operations()
+            .await?
+            .into_iter()
+            .filter(|operation| operation.mint_url == self.mint_url)
+            .collect())
+    }
+
+    /// Send the pending mint requests of this mint again
+    ///
+    /// Every request is replayed with its original outputs, so the proofs
+    /// minted are the ones the first attempt asked for and none are minted
+    /// twice. An operation is removed once its proofs are stored, or when the
+    /// mint refuses it. One the mint still cannot be reached for is kept until
+    /// its quote expires.
+    #[instrument(skip(self))]
+    pub async fn process_pending_operations(&self) -> Result<PendingOperationsSummary, Error> {
+        let mut summary = PendingOperationsSummary::default();
+
+        for operation in self.pending_mint_operations().await? {
+            let quote_id = operation.quote_id.clone();
+
+            match self.replay_mint_operation(&operation).await {
+                Ok(amount) => {
+                    summary.total_minted = summary
+                        .total_minted
+                        .checked_add(amount)
+                        .ok_or(Error::AmountOverflow)?;
+                    summary.minted_quotes.push(quote_id);
+                }
+                Err(err) if is_transport_error(&err) => {
+                    if operation.is_expired(self.clock.now_unix()) {
+                        tracing::warn!(
+                            "Dropping pending mint operation of expired quote {}",
+                            quote_id
+                        );
+                        self.localstore
+                            .remove_pending_mint_operation(&operation.id)
+                            .await?;
+                        summary.expired_quotes.push(quote_id);
+                    } else {
+                        tracing::debug!("Mint still unreachable for quote {}: {}", quote_id, err);
+                        summary.pending_quotes.push(quote_id);
+                    }
+                }
+                // Kept for the next run, the mint may have signed the outputs
+                Err(err @ Error::Database(_)) => return Err(err),
+                Err(err) => {
+                    tracing::warn!(
+                        "Mint refused pending mint operation of quote {}: {}",
+                        quote_id,
+                        err
+                    );
+                    self.localstore
+                        .remove_pending_mint_operation(&operation.id)
+                        .await?;
+                    summary.failed_quotes.push((quote_id, err));
+                }
+            }
+        }
+
+        Ok(summary)
+    }
+
+    /// Mint the proofs of a pending operation, returning the amount minted
+    async fn replay_mint_operation(
+        &self,
+        operation: &PendingMintOperation,
+    ) -> Result<Amount, Error> {
+        let signatures = match self.issued_signatures(&operation.premint_secrets).await? {
+            Some(signatures) => signatures,
+            None => {
+                let request = operation.request.clone();
+                let response = match operation.payment_method {
+                    PaymentMethod::Bolt11 => self.client.post_mint(request).await?,
+                    PaymentMethod::MiningShare => {
+                        self.client.post_mint_mining_share(request).await?
+                    }
+                    _ => return Err(Error::UnsupportedPaymentMethod),
+                };
+                response.signatures
+            }
+        };
+
+        let quote = self.localstore.get_mint_quote(&operation.quote_id).await?;
+        let proofs = self
+            .finalize_issued_proofs(
+                signatures,
+                &operation.premint_secrets,
+                operation.keyset_id,
+                replay_context(operation, quote.as_ref()),
+            )
+            .await?;
+
+        Ok(proofs.total_amount()?)
+    }
+}
+
+/// Context the proofs of a replayed operation are stored with, as the issue
+/// path the operation was queued by would have
+fn replay_context(operation: &PendingMintOperation, quote: Option<&MintQuote>) -> IssueContext {
+    let converted_from = quote.and_then(|quote| quote.converted_from.as_deref());
+
+    let (cleanup, source, metadata) = match operation.payment_method {
+        PaymentMethod::MiningShare => {
+            let mut metadata = conversion_metadata(converted_from);
+            metadata.extend(mining_share_metadata([(
+                operation.quote_id.as_str(),
+                quote.map(|quote| quote.request.as_str()),
+            )]));
+            (
+                QuoteCleanup::AddIssuedUntilPaid,
+                ProofSource::MiningShareMint,
+                metadata,
+            )
+        }
+        _ => (
+            QuoteCleanup::Remove,
+            ProofSource::Bolt11Mint,
+            conversion_metadata(converted_from),
+        ),
+    };
+
+    IssueContext {
+        quote_id: operation.quote_id.clone(),
+        unit: operation.unit.clone(),
+        cleanup,
+        source,
+        metadata,
+    }
+}
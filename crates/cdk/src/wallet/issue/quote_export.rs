@@ -0,0 +1,159 @@
+//! Pending mining share quote export
+//!
+//! Moves unminted mining share quotes between wallets of the same mint, for
+//! example from a mining proxy that creates the quotes to a phone wallet that
+//! mints them. The export never contains NUT-20 secret keys, the importing
+//! wallet is given the key separately.
+
+use cdk_common::wallet::MintQuote;
+use serde::{Deserialize, Serialize};
+use tracing::instrument;
+
+use crate::mint_url::MintUrl;
+use crate::nuts::{CurrencyUnit, Id, MintQuoteState, PaymentMethod, PublicKey, SecretKey};
+use crate::{ensure_cdk, Amount, Error, Wallet};
+
+/// Version of the [`PendingQuoteExport`] format written by this wallet
+pub const PENDING_QUOTE_EXPORT_VERSION: u32 = 1;
+
+/// Mining share quote in a [`PendingQuoteExport`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExportedQuote {
+    /// Quote id
+    pub id: String,
+    /// Amount of the quote
+    pub amount: Amount,
+    /// Amount already minted
+    pub amount_issued: Amount,
+    /// Unit of the quote
+    pub unit: CurrencyUnit,
+    /// Keyset the quote is minted in
+    pub keyset_id: Id,
+    /// Block header hash of the share
+    pub header_hash: String,
+    /// NUT-20 pubkey the quote is locked to, if known
+    pub pubkey: Option<PublicKey>,
+    /// Expiry of the quote, 0 if it does not expire
+    pub expiry: u64,
+}
+
+/// Pending mining share quotes of a mint, without their secret keys
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PendingQuoteExport {
+    /// Format version, see [`PENDING_QUOTE_EXPORT_VERSION`]
+    pub version: u32,
+    /// Mint the quotes were created at
+    pub mint_url: MintUrl,
+    /// Exported quotes
+    pub quotes: Vec<ExportedQuote>,
+}
+
+impl Wallet {
+    /// Export the mining share quotes of this wallet that still have ecash to
+    /// mint
+    ///
+    /// Expired quotes are left out. The export holds the pubkey each quote is
+    /// locked to but not its secret key.
+    #[instrument(skip(self))]
+    pub async fn export_pending_quotes(&self) -> Result<PendingQuoteExport, Error> {
+        let now = self.clock.now_unix();
+
+        let quotes = self
+            .localstore
+            .get_mint_quotes()
+            .await?
+            .into_iter()
+            .filter(|quote| {
+                quote.mint_url == self.mint_url
+                    && quote.payment_method == PaymentMethod::MiningShare
+                    && quote.amount_mintable() > Amount::ZERO
+                    && (quote.expiry == 0 || quote.expiry > now)
+            })
+            .filter_map(|quote| {
+                Some(ExportedQuote {
+                    amount: quote.amount?,
+                    amount_issued: quote.amount_issued,
+                    unit: quote.unit,
+                    keyset_id: quote.keyset_id?,
+                    header_hash: quote.request,
+                    pubkey: quote.secret_key.map(|secret_key| secret_key.public_key()),
+                    expiry: quote.expiry,
+                    id: quote.id,
+                })
+            })
+            .collect();
+
+        Ok(PendingQuoteExport {
+            version: PENDING_QUOTE_EXPORT_VERSION,
+            mint_url: self.mint_url.clone(),
+            quotes,
+        })
+    }
+
+    /// Import mining share quotes exported by another wallet of the same mint
+    ///
+    /// Quotes already stored are skipped. `secret_key` is stored with the
+    /// quotes locked to its pubkey, and with quotes exported without a
+    /// pubkey, so they can be minted from this wallet. Returns the quotes
+    /// imported.
+    #[instrument(skip(self, export, secret_key))]
+    pub async fn import_pending_quotes(
+        &self,
+        export: PendingQuoteExport,
+        secret_key: Option<SecretKey>,
+    ) -> Result<Vec<MintQuote>, Error> {
+        ensure_cdk!(
+            export.version == PENDING_QUOTE_EXPORT_VERSION,
+            Error::UnsupportedQuoteExportVersion(export.version)
+        );
+        if export.mint_url != self.mint_url {
+            return Err(Error::IncorrectWallet(format!(
+                "Should be {} not {}",
+                self.mint_url, export.mint_url
+            )));
+        }
+
+        let mut imported = Vec::new();
+        for exported in export.quotes {
+            if self
+                .localstore
+                .get_mint_quote(&exported.id)
+                .await?
+                .is_some()
+            {
+                tracing::debug!("Quote {} already stored, skipping", exported.id);
+                continue;
+            }
+
+            let secret_key = secret_key.clone().filter(|secret_key| {
+                exported
+                    .pubkey
+                    .is_none_or(|pubkey| pubkey == secret_key.public_key())
+            });
+
+            let quote = MintQuote {
+                id: exported.id,
+                mint_url: self.mint_url.clone(),
+                payment_method: PaymentMethod::MiningShare,
+                amount: Some(exported.amount),
+                unit: exported.unit,
+                request: exported.header_hash,
+                state: MintQuoteState::Paid,
+                expiry: exported.expiry,
+                secret_key,
+                amount_issued: exported.amount_issued,
+                amount_paid: exported.amount,
+                keyset_id: Some(exported.keyset_id),
+                converted_from: None,
+                premint_keyset_id: None,
+                premint_counter: None,
+                premint_amounts: None,
+            };
+
+            self.localstore.add_mint_quote(quote.clone()).await?;
+            imported.push(quote);
+        }
+
+        Ok(imported)
+    }
+}
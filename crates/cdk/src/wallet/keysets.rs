@@ -1,10 +1,19 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use cdk_common::nut02::{KeySetInfos, KeySetInfosMethods};
 use tracing::instrument;
 
-use crate::nuts::{Id, KeySetInfo, Keys};
-use crate::{Error, Wallet};
+use crate::nuts::{find_shared_keys, CurrencyUnit, Id, KeySetInfo, Keys};
+use crate::{ensure_cdk, Error, Wallet};
+
+/// Key reuse status of the mint's keysets
+#[derive(Debug, Default)]
+pub(crate) struct KeysetTrust {
+    /// Keysets checked for key reuse, and whether they reuse keys
+    checked: HashMap<Id, bool>,
+    /// Keysets trusted even though they reuse keys
+    trusted: HashSet<Id>,
+}
 
 impl Wallet {
     /// Add a keyset to the local database and update keyset info
@@ -17,6 +26,7 @@ impl Wallet {
         active: bool,
         input_fee_ppk: u64,
     ) -> Result<(), Error> {
+        self.check_keyset_key_reuse(keyset.id, &keyset.keys).await?;
         self.localstore.add_keys(keyset.clone()).await?;
 
         let keyset_info = KeySetInfo {
@@ -53,6 +63,7 @@ impl Wallet {
 
             keys.verify_id()?;
 
+            self.check_keyset_key_reuse(keys.id, &keys.keys).await?;
             self.localstore.add_keys(keys.clone()).await?;
 
             keys.keys
@@ -108,6 +119,18 @@ impl Wallet {
     /// keyset information and are willing to go online.
     #[instrument(skip(self))]
     pub async fn refresh_keysets(&self) -> Result<KeySetInfos, Error> {
+        self.refresh_keysets_with_unit(&self.unit).await
+    }
+
+    /// Refresh keysets by fetching the latest from mint for a unit - always goes online
+    ///
+    /// Keysets of every unit are stored, the keysets of `unit` are returned and
+    /// their keys loaded.
+    #[instrument(skip(self))]
+    pub async fn refresh_keysets_with_unit(
+        &self,
+        unit: &CurrencyUnit,
+    ) -> Result<KeySetInfos, Error> {
         tracing::debug!("Refreshing keysets and ensuring we have keys");
         let _ = self.fetch_mint_info().await?;
 
@@ -120,8 +143,8 @@ impl Wallet {
             .add_mint_keysets(self.mint_url.clone(), all_keysets.clone())
             .await?;
 
-        // Filter for active keysets matching our unit
-        let keysets: KeySetInfos = all_keysets.unit(self.unit.clone()).cloned().collect();
+        // Filter for active keysets matching the unit
+        let keysets: KeySetInfos = all_keysets.unit(unit.clone()).cloned().collect();
 
         // Ensure we have keys for all active keysets
         for keyset in &keysets {
@@ -136,14 +159,33 @@ impl Wallet {
     /// This method always goes online to refresh keysets from the mint and then returns
     /// the active keyset with the minimum input fees. Use this when you need the most
     /// up-to-date keyset information for operations.
+    ///
+    /// Fails with [`Error::KeysetKeyReuse`] if the keyset reuses keys of another
+    /// keyset and has not been trusted with [`Wallet::trust_keyset`].
     #[instrument(skip(self))]
     pub async fn fetch_active_keyset(&self) -> Result<KeySetInfo, Error> {
-        self.refresh_keysets()
+        self.fetch_active_keyset_with_unit(&self.unit).await
+    }
+
+    /// Get the active keyset of a unit with the lowest fees - always goes online
+    ///
+    /// See [`Wallet::fetch_active_keyset`].
+    #[instrument(skip(self))]
+    pub async fn fetch_active_keyset_with_unit(
+        &self,
+        unit: &CurrencyUnit,
+    ) -> Result<KeySetInfo, Error> {
+        let keyset = self
+            .refresh_keysets_with_unit(unit)
             .await?
             .active()
             .min_by_key(|k| k.input_fee_ppk)
             .cloned()
-            .ok_or(Error::NoActiveKeyset)
+            .ok_or(Error::NoActiveKeyset)?;
+
+        self.ensure_keyset_trusted(keyset.id).await?;
+
+        Ok(keyset)
     }
 
     /// Get the active keyset with the lowest fees from local database only - offline operation
@@ -151,19 +193,38 @@ impl Wallet {
     /// Returns the active keyset with minimum input fees from cached keysets in the local database.
     /// This is an offline operation that does not contact the mint. If no keysets are found locally,
     /// returns an error. Use this for offline operations or when you want to avoid network calls.
+    ///
+    /// Fails with [`Error::KeysetKeyReuse`] if the keyset reuses keys of another
+    /// keyset and has not been trusted with [`Wallet::trust_keyset`].
     #[instrument(skip(self))]
     pub async fn get_active_keyset(&self) -> Result<KeySetInfo, Error> {
-        match self
+        self.get_active_keyset_with_unit(&self.unit).await
+    }
+
+    /// Get the active keyset of a unit with the lowest fees from local database only - offline operation
+    ///
+    /// See [`Wallet::get_active_keyset`].
+    #[instrument(skip(self))]
+    pub async fn get_active_keyset_with_unit(
+        &self,
+        unit: &CurrencyUnit,
+    ) -> Result<KeySetInfo, Error> {
+        let keyset = match self
             .localstore
             .get_mint_keysets(self.mint_url.clone())
             .await?
         {
             Some(keysets_info) => keysets_info
                 .into_iter()
+                .filter(|k| &k.unit == unit)
                 .min_by_key(|k| k.input_fee_ppk)
-                .ok_or(Error::NoActiveKeyset),
-            None => Err(Error::UnknownKeySet),
-        }
+                .ok_or(Error::NoActiveKeyset)?,
+            None => return Err(Error::UnknownKeySet),
+        };
+
+        self.ensure_keyset_trusted(keyset.id).await?;
+
+        Ok(keyset)
     }
 
     /// Get keyset fees for mint from local database only - offline operation
@@ -198,4 +259,114 @@ impl Wallet {
             .cloned()
             .ok_or(Error::UnknownKeySet)
     }
+
+    /// Trust a keyset that reuses keys of another keyset of the mint
+    ///
+    /// Proofs are only issued onto such a keyset once it is trusted.
+    pub fn trust_keyset(&self, keyset_id: Id) -> Result<(), Error> {
+        let mut trust = self.keyset_trust.write().map_err(|_| Error::Internal)?;
+        tracing::warn!("Trusting keyset {} despite key reuse", keyset_id);
+        trust.trusted.insert(keyset_id);
+
+        Ok(())
+    }
+
+    /// Keysets found to reuse keys of another keyset of the mint
+    pub fn suspect_keysets(&self) -> Result<Vec<Id>, Error> {
+        let trust = self.keyset_trust.read().map_err(|_| Error::Internal)?;
+
+        Ok(trust
+            .checked
+            .iter()
+            .filter(|(_, reuses)| **reuses)
+            .map(|(id, _)| *id)
+            .collect())
+    }
+
+    /// Compare the keys of a keyset against the other stored keysets of the mint
+    ///
+    /// Returns whether the keyset shares keys with another keyset.
+    async fn check_keyset_key_reuse(&self, keyset_id: Id, keys: &Keys) -> Result<bool, Error> {
+        let mut stored = Vec::new();
+        for keyset in self
+            .localstore
+            .get_mint_keysets(self.mint_url.clone())
+            .await?
+            .unwrap_or_default()
+        {
+            if keyset.id == keyset_id {
+                continue;
+            }
+
+            if let Some(keys) = self.localstore.get_keys(&keyset.id).await? {
+                stored.push((keyset.id, keys));
+            }
+        }
+
+        // The checked keyset comes last, so any key it shares is reported
+        // against it
+        let reuses = find_shared_keys(
+            stored
+                .iter()
+                .map(|(id, keys)| (*id, keys))
+                .chain([(keyset_id, keys)]),
+        )
+        .iter()
+        .any(|shared| shared.other_keyset_id == keyset_id);
+
+        if reuses {
+            tracing::warn!(
+                "Keyset {} of mint {} reuses keys of another keyset",
+                keyset_id,
+                self.mint_url
+            );
+        }
+
+        self.keyset_trust
+            .write()
+            .map_err(|_| Error::Internal)?
+            .checked
+            .insert(keyset_id, reuses);
+
+        Ok(reuses)
+    }
+
+    /// Check that proofs can be issued onto a keyset
+    ///
+    /// Fails with [`Error::KeysetKeyReuse`] if the keyset reuses keys of another
+    /// keyset and has not been trusted with [`Wallet::trust_keyset`].
+    pub async fn ensure_keyset_trusted(&self, keyset_id: Id) -> Result<(), Error> {
+        let checked = {
+            let trust = self.keyset_trust.read().map_err(|_| Error::Internal)?;
+            if trust.trusted.contains(&keyset_id) {
+                return Ok(());
+            }
+            trust.checked.get(&keyset_id).copied()
+        };
+
+        let reuses = match checked {
+            Some(reuses) => reuses,
+            None => {
+                let keys = self.load_keyset_keys(keyset_id).await?;
+
+                // Keys fetched from the mint were checked while being stored
+                let checked = self
+                    .keyset_trust
+                    .read()
+                    .map_err(|_| Error::Internal)?
+                    .checked
+                    .get(&keyset_id)
+                    .copied();
+
+                match checked {
+                    Some(reuses) => reuses,
+                    None => self.check_keyset_key_reuse(keyset_id, &keys).await?,
+                }
+            }
+        };
+
+        ensure_cdk!(!reuses, Error::KeysetKeyReuse(keyset_id));
+
+        Ok(())
+    }
 }
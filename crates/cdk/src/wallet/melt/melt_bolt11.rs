@@ -1,4 +1,3 @@
-use std::collections::HashMap;
 use std::str::FromStr;
 
 use cdk_common::amount::SplitTarget;
@@ -7,19 +6,24 @@ use cdk_common::PaymentMethod;
 use lightning_invoice::Bolt11Invoice;
 use tracing::instrument;
 
-use crate::amount::to_unit;
+use super::{PreimageStatus, REQUOTED_FROM_METADATA_KEY};
+use crate::amount::{to_unit, UnitAmount};
 use crate::dhke::construct_proofs;
 use crate::nuts::{
-    CurrencyUnit, MeltOptions, MeltQuoteBolt11Request, MeltQuoteBolt11Response, MeltRequest,
-    PreMintSecrets, Proofs, ProofsMethods, State,
+    CurrencyUnit, MeltOptions, MeltQuoteBolt11Request, MeltQuoteBolt11Response, MeltQuoteState,
+    MeltRequest, PreMintSecrets, Proofs, ProofsMethods, State,
 };
 use crate::types::{Melted, ProofInfo};
 use crate::util::unix_time;
 use crate::wallet::MeltQuote;
-use crate::{ensure_cdk, Amount, Error, Wallet};
+use crate::{Amount, Error, Wallet};
 
 impl Wallet {
     /// Melt Quote
+    ///
+    /// An amount override in `options` is refused with strict amounts, use
+    /// [`Wallet::melt_quote_checked`] instead.
+    ///
     /// # Synopsis
     /// ```rust,no_run
     ///  use std::sync::Arc;
@@ -48,6 +52,45 @@ impl Wallet {
         &self,
         request: String,
         options: Option<MeltOptions>,
+    ) -> Result<MeltQuote, Error> {
+        if options.is_some() {
+            self.ensure_not_strict("melt_quote_checked")?;
+        }
+        self.create_melt_quote(request, options).await
+    }
+
+    /// Melt quote paying `amount` in the wallet unit
+    ///
+    /// The amount is paid of an amountless invoice, or as a partial payment of
+    /// an invoice with an amount. Without amount the invoice amount is paid.
+    /// Fails with [`Error::UnitMismatch`] if `amount` is in another unit.
+    #[instrument(skip(self, request))]
+    pub async fn melt_quote_checked(
+        &self,
+        request: String,
+        amount: Option<UnitAmount>,
+    ) -> Result<MeltQuote, Error> {
+        let options = match amount {
+            Some(amount) => {
+                self.check_amount_unit(&amount, &self.unit)?;
+                let amount_msat = amount.convert_to(&CurrencyUnit::Msat)?.amount;
+
+                match Bolt11Invoice::from_str(&request)?.amount_milli_satoshis() {
+                    Some(_) => Some(MeltOptions::new_mpp(amount_msat)),
+                    None => Some(MeltOptions::new_amountless(amount_msat)),
+                }
+            }
+            None => None,
+        };
+
+        self.create_melt_quote(request, options).await
+    }
+
+    /// Request a bolt11 melt quote
+    pub(crate) async fn create_melt_quote(
+        &self,
+        request: String,
+        options: Option<MeltOptions>,
     ) -> Result<MeltQuote, Error> {
         self.refresh_keysets().await?;
 
@@ -108,11 +151,20 @@ impl Wallet {
             Some(quote) => {
                 let mut quote = quote;
 
-                if let Err(e) = self
+                match self
                     .add_transaction_for_pending_melt(&quote, &response)
                     .await
                 {
-                    tracing::error!("Failed to add transaction for pending melt: {}", e);
+                    Ok(()) => (),
+                    // Keep the quote and its proofs pending so the discrepancy can be escalated
+                    Err(
+                        err @ (Error::InvalidPaymentPreimage { .. } | Error::PreimageNotProvided),
+                    ) => {
+                        return Err(err);
+                    }
+                    Err(e) => {
+                        tracing::error!("Failed to add transaction for pending melt: {}", e);
+                    }
                 }
 
                 quote.state = response.state;
@@ -127,6 +179,9 @@ impl Wallet {
     }
 
     /// Melt specific proofs
+    ///
+    /// A quote that expired or is about to expire is replaced with a fresh quote
+    /// for the same invoice, see [`Self::melt`].
     #[instrument(skip(self, proofs))]
     pub async fn melt_proofs(&self, quote_id: &str, proofs: Proofs) -> Result<Melted, Error> {
         let quote_info = self
@@ -135,10 +190,23 @@ impl Wallet {
             .await?
             .ok_or(Error::UnknownQuote)?;
 
-        ensure_cdk!(
-            quote_info.expiry.gt(&unix_time()),
-            Error::ExpiredQuote(quote_info.expiry, unix_time())
-        );
+        let (quote_info, requoted_from) = self.requote_expiring_melt_quote(quote_info).await?;
+
+        self.melt_quote_proofs(quote_info, proofs, requoted_from)
+            .await
+    }
+
+    /// Melt proofs for a quote
+    ///
+    /// `requoted_from` is the expired quote the quote replaced, recorded on the
+    /// transaction.
+    async fn melt_quote_proofs(
+        &self,
+        quote_info: MeltQuote,
+        proofs: Proofs,
+        requoted_from: Option<String>,
+    ) -> Result<Melted, Error> {
+        let quote_id = quote_info.id.clone();
 
         let proofs_total = proofs.total_amount()?;
         if proofs_total < quote_info.amount + quote_info.fee_reserve {
@@ -180,7 +248,7 @@ impl Wallet {
         };
 
         let request = MeltRequest::new(
-            quote_id.to_string(),
+            quote_id.clone(),
             proofs.clone(),
             Some(premint_secrets.blinded_messages()),
         );
@@ -239,6 +307,18 @@ impl Wallet {
             None => None,
         };
 
+        let preimage_status = match melt_response.state {
+            MeltQuoteState::Paid => {
+                PreimageStatus::check(&quote_info, melt_response.payment_preimage.as_deref())
+            }
+            _ => PreimageStatus::Unchecked,
+        };
+        let mut transaction_metadata =
+            preimage_status.metadata(melt_response.payment_preimage.as_deref());
+        if let Some(requoted_from) = requoted_from {
+            transaction_metadata.insert(REQUOTED_FROM_METADATA_KEY.to_string(), requoted_from);
+        }
+
         let melted = Melted::from_proofs(
             melt_response.state,
             melt_response.payment_preimage,
@@ -251,7 +331,7 @@ impl Wallet {
             Some(change_proofs) => {
                 tracing::debug!(
                     "Change amount returned from melt: {}",
-                    change_proofs.total_amount()?
+                    change_proofs.total_amount()?.display(&self.unit)
                 );
 
                 change_proofs
@@ -269,6 +349,37 @@ impl Wallet {
             None => Vec::new(),
         };
 
+        if let Some(err) = self.melt_preimage_error(&quote_id, preimage_status) {
+            // Do not mark the melt complete: inputs stay pending and the quote is kept so
+            // the discrepancy can be escalated and rechecked
+            self.localstore
+                .update_proofs(change_proof_infos, vec![])
+                .await?;
+
+            let mut quote_info = quote_info;
+            quote_info.state = MeltQuoteState::Pending;
+            self.localstore.add_melt_quote(quote_info).await?;
+
+            if !self.has_melt_transaction(&quote_id).await? {
+                self.localstore
+                    .add_transaction(Transaction {
+                        mint_url: self.mint_url.clone(),
+                        direction: TransactionDirection::Outgoing,
+                        amount: melted.amount,
+                        fee: melted.fee_paid,
+                        unit: self.unit.clone(),
+                        ys: proofs.ys()?,
+                        timestamp: unix_time(),
+                        memo: None,
+                        metadata: transaction_metadata,
+                        quote_id: Some(quote_id.clone()),
+                    })
+                    .await?;
+            }
+
+            return Err(err);
+        }
+
         self.localstore.remove_melt_quote(&quote_info.id).await?;
 
         let deleted_ys = proofs.ys()?;
@@ -287,8 +398,8 @@ impl Wallet {
                 ys: proofs.ys()?,
                 timestamp: unix_time(),
                 memo: None,
-                metadata: HashMap::new(),
-                quote_id: Some(quote_id.to_string()),
+                metadata: transaction_metadata,
+                quote_id: Some(quote_id),
             })
             .await?;
 
@@ -296,6 +407,11 @@ impl Wallet {
     }
 
     /// Melt
+    ///
+    /// An unpaid quote that expired or expires within a few seconds is
+    /// replaced once with a fresh quote for the same invoice before any proofs
+    /// are selected. Both quote ids are recorded on the transaction. Fails with
+    /// [`Error::InvoiceExpired`] if the invoice itself expired.
     /// # Synopsis
     /// ```rust, no_run
     ///  use std::sync::Arc;
@@ -329,10 +445,7 @@ impl Wallet {
             .await?
             .ok_or(Error::UnknownQuote)?;
 
-        ensure_cdk!(
-            quote_info.expiry.gt(&unix_time()),
-            Error::ExpiredQuote(quote_info.expiry, unix_time())
-        );
+        let (quote_info, requoted_from) = self.requote_expiring_melt_quote(quote_info).await?;
 
         let inputs_needed_amount = quote_info.amount + quote_info.fee_reserve;
 
@@ -345,13 +458,26 @@ impl Wallet {
             .map(|k| k.id)
             .collect();
         let keyset_fees = self.get_keyset_fees().await?;
-        let (mut input_proofs, mut exchange) = Wallet::select_exact_proofs(
-            inputs_needed_amount,
-            available_proofs,
-            &active_keyset_ids,
-            &keyset_fees,
-            true,
-        )?;
+        let (mut input_proofs, mut exchange) = self
+            .select_within_max_inputs(
+                available_proofs,
+                |proofs, strategy| {
+                    Wallet::select_exact_proofs_with_strategy(
+                        inputs_needed_amount,
+                        proofs,
+                        &active_keyset_ids,
+                        &keyset_fees,
+                        true,
+                        strategy,
+                    )
+                },
+                |(input_proofs, exchange)| {
+                    let mut inputs = input_proofs.clone();
+                    inputs.extend(exchange.as_ref().map(|(proof, _)| proof.clone()));
+                    inputs
+                },
+            )
+            .await?;
 
         if let Some((proof, exact_amount)) = exchange.take() {
             let new_proofs = self
@@ -371,6 +497,7 @@ impl Wallet {
             input_proofs.extend_from_slice(&new_proofs);
         }
 
-        self.melt_proofs(quote_id, input_proofs).await
+        self.melt_quote_proofs(quote_info, input_proofs, requoted_from)
+            .await
     }
 }
@@ -78,11 +78,20 @@ impl Wallet {
             Some(quote) => {
                 let mut quote = quote;
 
-                if let Err(e) = self
+                match self
                     .add_transaction_for_pending_melt(&quote, &response)
                     .await
                 {
-                    tracing::error!("Failed to add transaction for pending melt: {}", e);
+                    Ok(()) => (),
+                    // Keep the quote and its proofs pending so the discrepancy can be escalated
+                    Err(
+                        err @ (Error::InvalidPaymentPreimage { .. } | Error::PreimageNotProvided),
+                    ) => {
+                        return Err(err);
+                    }
+                    Err(e) => {
+                        tracing::error!("Failed to add transaction for pending melt: {}", e);
+                    }
                 }
 
                 quote.state = response.state;
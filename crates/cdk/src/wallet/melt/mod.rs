@@ -1,36 +1,268 @@
 use std::collections::HashMap;
+use std::str::FromStr;
 
-use cdk_common::util::unix_time;
+use bitcoin::hashes::{sha256, Hash};
+use cdk_common::util::{hex, unix_time};
 use cdk_common::wallet::{MeltQuote, Transaction, TransactionDirection};
-use cdk_common::{Error, MeltQuoteBolt11Response, MeltQuoteState, ProofsMethods};
+use cdk_common::{
+    Bolt11Invoice, CurrencyUnit, Error, MeltOptions, MeltQuoteBolt11Response, MeltQuoteState,
+    PaymentMethod, ProofsMethods,
+};
 use tracing::instrument;
 
-use crate::Wallet;
+use crate::amount::to_unit;
+use crate::{ensure_cdk, Wallet};
 
 #[cfg(feature = "bip353")]
 mod melt_bip353;
 mod melt_bolt11;
 mod melt_bolt12;
 
+/// Transaction metadata key recording the outcome of the payment preimage check
+const PREIMAGE_STATUS_METADATA_KEY: &str = "preimage_status";
+
+/// Transaction metadata key of the expired melt quote a melt was re-quoted from
+pub(crate) const REQUOTED_FROM_METADATA_KEY: &str = "requoted_from";
+
+/// Seconds before its expiry a melt quote is replaced with a fresh quote
+const MELT_QUOTE_EXPIRY_BUFFER: u64 = 30;
+
+/// Outcome of checking the payment preimage returned by the mint for a paid melt
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum PreimageStatus {
+    /// Preimage hashes to the payment hash of the invoice
+    Verified,
+    /// Mint did not return a preimage
+    Missing,
+    /// Preimage does not hash to the payment hash of the invoice
+    Invalid,
+    /// Payment request does not commit to a payment hash the wallet can check
+    Unchecked,
+}
+
+impl PreimageStatus {
+    /// Check the preimage returned for a paid melt against the invoice payment hash
+    ///
+    /// A stored request that does not parse as a bolt11 invoice is not
+    /// checked, the melt itself already went through.
+    pub(crate) fn check(quote: &MeltQuote, preimage: Option<&str>) -> Self {
+        if quote.payment_method != PaymentMethod::Bolt11 {
+            return Self::Unchecked;
+        }
+
+        let invoice = match Bolt11Invoice::from_str(&quote.request) {
+            Ok(invoice) => invoice,
+            Err(err) => {
+                tracing::warn!(
+                    "Could not parse the request of melt quote {} to check its preimage: {}",
+                    quote.id,
+                    err
+                );
+                return Self::Unchecked;
+            }
+        };
+        let payment_hash: [u8; 32] = *invoice.payment_hash().as_ref();
+
+        let status = match preimage.filter(|p| !p.is_empty()) {
+            None => Self::Missing,
+            Some(preimage) => match hex::decode(preimage) {
+                Ok(bytes) if sha256::Hash::hash(&bytes).to_byte_array() == payment_hash => {
+                    Self::Verified
+                }
+                _ => Self::Invalid,
+            },
+        };
+
+        match status {
+            Self::Invalid => tracing::warn!(
+                "Mint returned a preimage for melt quote {} that does not match the invoice payment hash",
+                quote.id
+            ),
+            Self::Missing => {
+                tracing::info!("Mint returned no preimage for paid melt quote {}", quote.id)
+            }
+            Self::Verified | Self::Unchecked => (),
+        }
+
+        status
+    }
+
+    /// Transaction metadata recording a missing or invalid preimage
+    pub(crate) fn metadata(&self, preimage: Option<&str>) -> HashMap<String, String> {
+        let mut metadata = HashMap::new();
+
+        let status = match self {
+            Self::Missing => "missing",
+            Self::Invalid => "invalid",
+            Self::Verified | Self::Unchecked => return metadata,
+        };
+
+        metadata.insert(PREIMAGE_STATUS_METADATA_KEY.to_string(), status.to_string());
+
+        if let Some(preimage) = preimage.filter(|p| !p.is_empty()) {
+            metadata.insert("payment_preimage".to_string(), preimage.to_string());
+        }
+
+        metadata
+    }
+}
+
 impl Wallet {
+    /// Error that prevents a paid melt from being marked complete, if any
+    pub(crate) fn melt_preimage_error(
+        &self,
+        quote_id: &str,
+        status: PreimageStatus,
+    ) -> Option<Error> {
+        match status {
+            PreimageStatus::Invalid => Some(Error::InvalidPaymentPreimage {
+                quote_id: quote_id.to_string(),
+            }),
+            PreimageStatus::Missing if self.require_payment_preimage => {
+                Some(Error::PreimageNotProvided)
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether an outgoing transaction was already recorded for the melt quote
+    ///
+    /// A melt whose preimage check failed keeps its quote pending, so it is
+    /// checked again and must not be recorded every time.
+    pub(crate) async fn has_melt_transaction(&self, quote_id: &str) -> Result<bool, Error> {
+        Ok(self
+            .localstore
+            .list_transactions(
+                Some(self.mint_url.clone()),
+                Some(TransactionDirection::Outgoing),
+                None,
+            )
+            .await?
+            .iter()
+            .any(|transaction| transaction.quote_id.as_deref() == Some(quote_id)))
+    }
+
+    /// Replace a melt quote that expired or is about to expire
+    ///
+    /// Unpaid bolt11 quotes expiring within [`MELT_QUOTE_EXPIRY_BUFFER`] seconds
+    /// are re-quoted once for the same invoice and amount, and the old quote is
+    /// removed. Returns the quote to melt and the id of the quote it replaced.
+    ///
+    /// Fails with [`Error::InvoiceExpired`] if the invoice itself expired, and
+    /// with [`Error::ExpiredQuote`] if an expired quote cannot be re-quoted.
+    pub(crate) async fn requote_expiring_melt_quote(
+        &self,
+        quote: MeltQuote,
+    ) -> Result<(MeltQuote, Option<String>), Error> {
+        let now = self.clock.now_unix();
+        if quote.expiry > now + MELT_QUOTE_EXPIRY_BUFFER {
+            return Ok((quote, None));
+        }
+
+        // Only quotes the mint has not started paying can be replaced
+        if quote.payment_method != PaymentMethod::Bolt11 || quote.state != MeltQuoteState::Unpaid {
+            ensure_cdk!(quote.expiry > now, Error::ExpiredQuote(quote.expiry, now));
+            return Ok((quote, None));
+        }
+
+        let invoice = Bolt11Invoice::from_str(&quote.request)?;
+        if let Some(expires_at) = invoice.expires_at().map(|t| t.as_secs()) {
+            ensure_cdk!(expires_at > now, Error::InvoiceExpired(expires_at));
+        }
+
+        let options = match Self::requote_options(&quote, &invoice)? {
+            Some(options) => options,
+            None => {
+                ensure_cdk!(quote.expiry > now, Error::ExpiredQuote(quote.expiry, now));
+                return Ok((quote, None));
+            }
+        };
+
+        tracing::info!(
+            "Melt quote {} expires at {}, requesting a new quote",
+            quote.id,
+            quote.expiry
+        );
+
+        let new_quote = self
+            .create_melt_quote(quote.request.clone(), options)
+            .await?;
+
+        // A fresh quote is not re-quoted again
+        let now = self.clock.now_unix();
+        ensure_cdk!(
+            new_quote.expiry > now,
+            Error::ExpiredQuote(new_quote.expiry, now)
+        );
+
+        self.localstore.remove_melt_quote(&quote.id).await?;
+
+        tracing::info!("Replaced melt quote {} with {}", quote.id, new_quote.id);
+
+        Ok((new_quote, Some(quote.id)))
+    }
+
+    /// Melt options requesting the same amount as the quote
+    ///
+    /// Returns `None` if the amount of the quote cannot be requested again.
+    fn requote_options(
+        quote: &MeltQuote,
+        invoice: &Bolt11Invoice,
+    ) -> Result<Option<Option<MeltOptions>>, Error> {
+        let invoice_amount_msat = invoice.amount_milli_satoshis();
+
+        if quote.unit != CurrencyUnit::Sat && quote.unit != CurrencyUnit::Msat {
+            // Amounts in other units cannot be compared with the invoice
+            return Ok(invoice_amount_msat.map(|_| None));
+        }
+
+        let quote_amount_msat = to_unit(quote.amount, &quote.unit, &CurrencyUnit::Msat)?;
+
+        let options = match invoice_amount_msat {
+            None => Some(MeltOptions::new_amountless(quote_amount_msat)),
+            Some(amount_msat)
+                if to_unit(amount_msat, &CurrencyUnit::Msat, &quote.unit)? == quote.amount =>
+            {
+                None
+            }
+            // Partial payment of the invoice
+            Some(_) => Some(MeltOptions::new_mpp(quote_amount_msat)),
+        };
+
+        Ok(Some(options))
+    }
+
     /// Check pending melt quotes
     #[instrument(skip_all)]
     pub async fn check_pending_melt_quotes(&self) -> Result<(), Error> {
         let quotes = self.get_pending_melt_quotes().await?;
+        let mut preimage_error = None;
         for quote in quotes {
-            self.melt_quote_status(&quote.id).await?;
+            match self.melt_quote_status(&quote.id).await {
+                Ok(_) => (),
+                Err(err @ (Error::InvalidPaymentPreimage { .. } | Error::PreimageNotProvided)) => {
+                    tracing::error!("Could not resolve pending melt {}: {}", quote.id, err);
+                    preimage_error.get_or_insert(err);
+                }
+                Err(err) => return Err(err),
+            }
+        }
+
+        match preimage_error {
+            Some(err) => Err(err),
+            None => Ok(()),
         }
-        Ok(())
     }
 
     /// Get all active melt quotes from the wallet
     pub async fn get_active_melt_quotes(&self) -> Result<Vec<MeltQuote>, Error> {
         let quotes = self.localstore.get_melt_quotes().await?;
+        let now = self.clock.now_unix();
         Ok(quotes
             .into_iter()
             .filter(|q| {
                 q.state == MeltQuoteState::Pending
-                    || (q.state == MeltQuoteState::Unpaid && q.expiry > unix_time())
+                    || (q.state == MeltQuoteState::Unpaid && q.expiry > now)
             })
             .collect())
     }
@@ -57,28 +289,80 @@ impl Wallet {
                 response.state
             );
             if response.state == MeltQuoteState::Paid {
-                let pending_proofs = self.get_pending_proofs().await?;
-                let proofs_total = pending_proofs.total_amount().unwrap_or_default();
-                let change_total = response.change_amount().unwrap_or_default();
-                self.localstore
-                    .add_transaction(Transaction {
-                        mint_url: self.mint_url.clone(),
-                        direction: TransactionDirection::Outgoing,
-                        amount: response.amount,
-                        fee: proofs_total
-                            .checked_sub(response.amount)
-                            .and_then(|amt| amt.checked_sub(change_total))
-                            .unwrap_or_default(),
-                        unit: quote.unit.clone(),
-                        ys: pending_proofs.ys()?,
-                        timestamp: unix_time(),
-                        memo: None,
-                        metadata: HashMap::new(),
-                        quote_id: Some(quote.id.clone()),
-                    })
-                    .await?;
+                let preimage_status =
+                    PreimageStatus::check(quote, response.payment_preimage.as_deref());
+                if !self.has_melt_transaction(&quote.id).await? {
+                    let pending_proofs = self.get_pending_proofs().await?;
+                    let proofs_total = pending_proofs.total_amount().unwrap_or_default();
+                    let change_total = response.change_amount().unwrap_or_default();
+                    self.localstore
+                        .add_transaction(Transaction {
+                            mint_url: self.mint_url.clone(),
+                            direction: TransactionDirection::Outgoing,
+                            amount: response.amount,
+                            fee: proofs_total
+                                .checked_sub(response.amount)
+                                .and_then(|amt| amt.checked_sub(change_total))
+                                .unwrap_or_default(),
+                            unit: quote.unit.clone(),
+                            ys: pending_proofs.ys()?,
+                            timestamp: unix_time(),
+                            memo: None,
+                            metadata: preimage_status
+                                .metadata(response.payment_preimage.as_deref()),
+                            quote_id: Some(quote.id.clone()),
+                        })
+                        .await?;
+                }
+
+                if let Some(err) = self.melt_preimage_error(&quote.id, preimage_status) {
+                    return Err(err);
+                }
             }
         }
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn melt_quote(request: &str, payment_method: PaymentMethod) -> MeltQuote {
+        MeltQuote {
+            id: "quote".to_string(),
+            unit: CurrencyUnit::Sat,
+            amount: 10.into(),
+            request: request.to_string(),
+            fee_reserve: 1.into(),
+            state: MeltQuoteState::Paid,
+            expiry: 0,
+            payment_preimage: None,
+            payment_method,
+        }
+    }
+
+    #[test]
+    fn test_preimage_unchecked_for_unparseable_request() {
+        let quote = melt_quote("not an invoice", PaymentMethod::Bolt11);
+
+        assert_eq!(
+            PreimageStatus::check(&quote, Some("00")),
+            PreimageStatus::Unchecked
+        );
+        assert_eq!(
+            PreimageStatus::check(&quote, None),
+            PreimageStatus::Unchecked
+        );
+    }
+
+    #[test]
+    fn test_preimage_unchecked_for_other_methods() {
+        let quote = melt_quote("lno1offer", PaymentMethod::Bolt12);
+
+        assert_eq!(
+            PreimageStatus::check(&quote, None),
+            PreimageStatus::Unchecked
+        );
+    }
+}
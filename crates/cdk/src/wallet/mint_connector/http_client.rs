@@ -15,6 +15,7 @@ use tracing::instrument;
 use url::Url;
 use web_time::{Duration, Instant};
 
+use super::retry::HttpClientOptions;
 use super::transport::Transport;
 use super::{Error, MintConnector};
 use crate::error::ErrorResponse;
@@ -23,11 +24,13 @@ use crate::nuts::nut19;
 #[cfg(feature = "auth")]
 use crate::nuts::nut22::MintAuthRequest;
 use crate::nuts::{
-    AuthToken, CheckStateRequest, CheckStateResponse, Id, KeySet, KeysResponse, KeysetResponse,
-    MeltQuoteBolt11Request, MeltQuoteBolt11Response, MeltRequest, MintInfo, MintQuoteBolt11Request,
-    MintQuoteBolt11Response, MintQuoteMiningShareRequest, MintQuoteMiningShareResponse,
-    MintRequest, MintResponse, PaymentMethod, RestoreRequest, RestoreResponse, SwapRequest,
-    SwapResponse,
+    AuthToken, BatchMintQuoteMiningShareRequest, BatchMintQuoteMiningShareResponse,
+    BatchQuoteStatusRequest, BatchQuoteStatusResponse, CheckStateRequest, CheckStateResponse, Id,
+    KeySet, KeysResponse, KeysetResponse, MeltQuoteBolt11Request, MeltQuoteBolt11Response,
+    MeltRequest, MintInfo, MintQuoteBolt11Request, MintQuoteBolt11Response, MintQuoteCancelRequest,
+    MintQuoteCancelResponse, MintQuoteConversionRequest, MintQuoteConversionResponse,
+    MintQuoteMiningShareRequest, MintQuoteMiningShareResponse, MintRequest, MintResponse,
+    PaymentMethod, RestoreRequest, RestoreResponse, SwapRequest, SwapResponse,
 };
 #[cfg(feature = "auth")]
 use crate::wallet::auth::{AuthMintConnector, AuthWallet};
@@ -140,6 +143,9 @@ where
     transport: Arc<T>,
     mint_url: MintUrl,
     cache_support: Arc<StdRwLock<Cache>>,
+    prefer_cbor: bool,
+    cbor_support: Arc<StdRwLock<Option<bool>>>,
+    options: HttpClientOptions,
     #[cfg(feature = "auth")]
     auth_wallet: Arc<RwLock<Option<AuthWallet>>>,
 }
@@ -156,6 +162,9 @@ where
             mint_url,
             auth_wallet: Arc::new(RwLock::new(auth_wallet)),
             cache_support: Default::default(),
+            prefer_cbor: false,
+            cbor_support: Default::default(),
+            options: Default::default(),
         }
     }
 
@@ -165,10 +174,41 @@ where
         Self {
             transport: T::default().into(),
             cache_support: Default::default(),
+            prefer_cbor: false,
+            cbor_support: Default::default(),
+            options: Default::default(),
             mint_url,
         }
     }
 
+    /// Use the compact CBOR encoding for mining share mint requests
+    ///
+    /// Requests fall back to JSON unless the mint info advertises CBOR support.
+    /// The mint info is fetched once for this if the client has not fetched it
+    /// yet.
+    pub fn prefer_cbor(mut self, prefer_cbor: bool) -> Self {
+        self.prefer_cbor = prefer_cbor;
+        self
+    }
+
+    /// Set the retry policy of the client
+    pub fn with_options(mut self, options: HttpClientOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Retry policy of mint requests
+    ///
+    /// Mint requests are only retried if
+    /// [`HttpClientOptions::retry_mint_requests`] is set.
+    fn mint_request_options(&self) -> HttpClientOptions {
+        if self.options.retry_mint_requests {
+            self.options.clone()
+        } else {
+            HttpClientOptions::no_retries()
+        }
+    }
+
     /// Get auth token for a protected endpoint
     #[cfg(feature = "auth")]
     #[instrument(skip(self))]
@@ -205,6 +245,9 @@ where
             #[cfg(feature = "auth")]
             auth_wallet: Arc::new(RwLock::new(None)),
             cache_support: Default::default(),
+            prefer_cbor: false,
+            cbor_support: Default::default(),
+            options: Default::default(),
         })
     }
 
@@ -294,8 +337,8 @@ where
         let url = self.mint_url.join_paths(&["v1", "keys"])?;
 
         Ok(self
-            .transport
-            .http_get::<KeysResponse>(url, None)
+            .options
+            .retry(|| self.transport.http_get::<KeysResponse>(url.clone(), None))
             .await?
             .keysets)
     }
@@ -307,7 +350,10 @@ where
             .mint_url
             .join_paths(&["v1", "keys", &keyset_id.to_string()])?;
 
-        let keys_response = self.transport.http_get::<KeysResponse>(url, None).await?;
+        let keys_response = self
+            .options
+            .retry(|| self.transport.http_get::<KeysResponse>(url.clone(), None))
+            .await?;
 
         Ok(keys_response.keysets.first().unwrap().clone())
     }
@@ -316,7 +362,9 @@ where
     #[instrument(skip(self), fields(mint_url = %self.mint_url))]
     async fn get_mint_keysets(&self) -> Result<KeysetResponse, Error> {
         let url = self.mint_url.join_paths(&["v1", "keysets"])?;
-        self.transport.http_get(url, None).await
+        self.options
+            .retry(|| self.transport.http_get(url.clone(), None))
+            .await
     }
 
     /// Mint Quote [NUT-04]
@@ -379,7 +427,9 @@ where
         #[cfg(not(feature = "auth"))]
         let auth_token = None;
 
-        self.transport.http_get(url, auth_token).await
+        self.options
+            .retry(|| self.transport.http_get(url.clone(), auth_token.clone()))
+            .await
     }
 
     /// Mint Tokens [NUT-04]
@@ -392,13 +442,16 @@ where
 
         #[cfg(not(feature = "auth"))]
         let auth_token = None;
-        self.retriable_http_request(
-            nut19::Method::Post,
-            nut19::Path::MintBolt11,
-            auth_token,
-            &request,
-        )
-        .await
+        self.mint_request_options()
+            .retry(|| {
+                self.retriable_http_request(
+                    nut19::Method::Post,
+                    nut19::Path::MintBolt11,
+                    auth_token.clone(),
+                    &request,
+                )
+            })
+            .await
     }
 
     /// Melt Quote [NUT-05]
@@ -437,7 +490,9 @@ where
 
         #[cfg(not(feature = "auth"))]
         let auth_token = None;
-        self.transport.http_get(url, auth_token).await
+        self.options
+            .retry(|| self.transport.http_get(url.clone(), auth_token.clone()))
+            .await
     }
 
     /// Melt [NUT-05]
@@ -485,7 +540,10 @@ where
     /// Helper to get mint info
     async fn get_mint_info(&self) -> Result<MintInfo, Error> {
         let url = self.mint_url.join_paths(&["v1", "info"])?;
-        let info: MintInfo = self.transport.http_get(url, None).await?;
+        let info: MintInfo = self
+            .options
+            .retry(|| self.transport.http_get(url.clone(), None))
+            .await?;
 
         if let Ok(mut cache_support) = self.cache_support.write() {
             *cache_support = (
@@ -500,9 +558,40 @@ where
             );
         }
 
+        if let Ok(mut cbor_support) = self.cbor_support.write() {
+            *cbor_support = Some(info.nuts.nutxx.is_some_and(|settings| settings.cbor));
+        }
+
         Ok(info)
     }
 
+    /// Whether mining share mint requests are sent as CBOR
+    ///
+    /// Decided from the mint info this client fetched, which is fetched first
+    /// if there is none yet. Falls back to JSON if it cannot be fetched.
+    async fn use_cbor(&self) -> bool {
+        if !self.prefer_cbor {
+            return false;
+        }
+
+        let cbor_support = self
+            .cbor_support
+            .read()
+            .map(|cbor_support| *cbor_support)
+            .unwrap_or_default();
+
+        match cbor_support {
+            Some(cbor_support) => cbor_support,
+            None => match self.get_mint_info().await {
+                Ok(info) => info.nuts.nutxx.is_some_and(|settings| settings.cbor),
+                Err(err) => {
+                    tracing::warn!("Could not fetch mint info, using json: {}", err);
+                    false
+                }
+            },
+        }
+    }
+
     #[cfg(feature = "auth")]
     async fn get_auth_wallet(&self) -> Option<AuthWallet> {
         self.auth_wallet.read().await.clone()
@@ -667,6 +756,27 @@ where
         self.transport.http_post(url, auth_token, &request).await
     }
 
+    /// Mint Quotes for several Mining Shares [NUT-XX]
+    #[instrument(skip(self, request), fields(mint_url = %self.mint_url))]
+    async fn post_mint_quotes_mining_share_batch(
+        &self,
+        request: BatchMintQuoteMiningShareRequest,
+    ) -> Result<BatchMintQuoteMiningShareResponse<String>, Error> {
+        let url = self
+            .mint_url
+            .join_paths(&["v1", "mint", "quote", "mining_share", "batch"])?;
+
+        #[cfg(feature = "auth")]
+        let auth_token = self
+            .get_auth_token(Method::Post, RoutePath::MintQuoteBolt11) // Use Bolt11 auth for now
+            .await?;
+
+        #[cfg(not(feature = "auth"))]
+        let auth_token = None;
+
+        self.transport.http_post(url, auth_token, &request).await
+    }
+
     /// Mint Quote status for Mining Share [NUT-XX]
     #[instrument(skip(self), fields(mint_url = %self.mint_url))]
     async fn get_mint_quote_status_mining_share(
@@ -685,7 +795,35 @@ where
         #[cfg(not(feature = "auth"))]
         let auth_token = None;
 
-        self.transport.http_get(url, auth_token).await
+        self.options
+            .retry(|| self.transport.http_get(url.clone(), auth_token.clone()))
+            .await
+    }
+
+    /// Mint Quote status of several Mining Share quotes [NUT-XX]
+    #[instrument(skip(self, request), fields(mint_url = %self.mint_url))]
+    async fn post_mint_quote_status_mining_share_batch(
+        &self,
+        request: BatchQuoteStatusRequest,
+    ) -> Result<BatchQuoteStatusResponse<String>, Error> {
+        let url = self
+            .mint_url
+            .join_paths(&["v1", "mint", "quote", "mining_share", "check"])?;
+
+        #[cfg(feature = "auth")]
+        let auth_token = self
+            .get_auth_token(Method::Get, RoutePath::MintQuoteBolt11) // Use Bolt11 auth for now
+            .await?;
+
+        #[cfg(not(feature = "auth"))]
+        let auth_token = None;
+
+        self.options
+            .retry(|| {
+                self.transport
+                    .http_post(url.clone(), auth_token.clone(), &request)
+            })
+            .await
     }
 
     /// Mint Tokens for Mining Share [NUT-XX]
@@ -704,8 +842,74 @@ where
         #[cfg(not(feature = "auth"))]
         let auth_token = None;
 
+        let options = self.mint_request_options();
+
+        if self.use_cbor().await {
+            return options
+                .retry(|| {
+                    self.transport
+                        .http_post_cbor(url.clone(), auth_token.clone(), &request)
+                })
+                .await;
+        }
+
+        options
+            .retry(|| {
+                self.transport
+                    .http_post(url.clone(), auth_token.clone(), &request)
+            })
+            .await
+    }
+
+    /// Convert a Mint Quote to another payment method [NUT-XX]
+    #[instrument(skip(self), fields(mint_url = %self.mint_url))]
+    async fn post_mint_quote_conversion(
+        &self,
+        request: MintQuoteConversionRequest<String>,
+    ) -> Result<MintQuoteConversionResponse<String>, Error> {
+        let url = self
+            .mint_url
+            .join_paths(&["v1", "mint", "quote", "convert"])?;
+
+        #[cfg(feature = "auth")]
+        let auth_token = self
+            .get_auth_token(Method::Post, RoutePath::MintQuoteBolt11) // Use Bolt11 auth for now
+            .await?;
+
+        #[cfg(not(feature = "auth"))]
+        let auth_token = None;
+
         self.transport.http_post(url, auth_token, &request).await
     }
+
+    /// Cancel a Mint Quote that has no ecash issued [NUT-XX]
+    #[instrument(skip(self), fields(mint_url = %self.mint_url))]
+    async fn delete_mint_quote(
+        &self,
+        method: PaymentMethod,
+        request: MintQuoteCancelRequest<String>,
+    ) -> Result<MintQuoteCancelResponse<String>, Error> {
+        let mut url = self.mint_url.join_paths(&[
+            "v1",
+            "mint",
+            "quote",
+            &method.to_string(),
+            &request.quote,
+        ])?;
+        if let Some(signature) = &request.signature {
+            url.query_pairs_mut().append_pair("signature", signature);
+        }
+
+        #[cfg(feature = "auth")]
+        let auth_token = self
+            .get_auth_token(Method::Post, RoutePath::MintQuoteBolt11) // Use Bolt11 auth for now
+            .await?;
+
+        #[cfg(not(feature = "auth"))]
+        let auth_token = None;
+
+        self.transport.http_delete(url, auth_token).await
+    }
 }
 
 /// Http Client
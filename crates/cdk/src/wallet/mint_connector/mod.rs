@@ -7,16 +7,20 @@ use cdk_common::{MeltQuoteBolt12Request, MintQuoteBolt12Request, MintQuoteBolt12
 
 use super::Error;
 use crate::nuts::{
-    CheckStateRequest, CheckStateResponse, Id, KeySet, KeysetResponse, MeltQuoteBolt11Request,
-    MeltQuoteBolt11Response, MeltRequest, MintInfo, MintQuoteBolt11Request,
-    MintQuoteBolt11Response, MintQuoteMiningShareRequest, MintQuoteMiningShareResponse,
-    MintRequest, MintResponse, PaymentMethod, RestoreRequest, RestoreResponse, SwapRequest,
-    SwapResponse,
+    BatchMintQuoteMiningShareRequest, BatchMintQuoteMiningShareResponse, BatchQuoteStatusRequest,
+    BatchQuoteStatusResponse, CheckStateRequest, CheckStateResponse, Id, KeySet, KeysetResponse,
+    MeltQuoteBolt11Request, MeltQuoteBolt11Response, MeltRequest, MintInfo, MintQuoteBolt11Request,
+    MintQuoteBolt11Response, MintQuoteCancelRequest, MintQuoteCancelResponse,
+    MintQuoteConversionRequest, MintQuoteConversionResponse, MintQuoteMiningShareRequest,
+    MintQuoteMiningShareResponse, MintRequest, MintResponse, PaymentMethod, RestoreRequest,
+    RestoreResponse, SwapRequest, SwapResponse,
 };
 #[cfg(feature = "auth")]
 use crate::wallet::AuthWallet;
 
 pub mod http_client;
+pub mod retry;
+pub mod scheduler;
 pub mod transport;
 
 /// Auth HTTP Client with async transport
@@ -114,15 +118,38 @@ pub trait MintConnector: Debug {
         &self,
         request: MintQuoteMiningShareRequest,
     ) -> Result<MintQuoteMiningShareResponse<String>, Error>;
+    /// Mint Quotes for several Mining Shares [NUT-XX]
+    async fn post_mint_quotes_mining_share_batch(
+        &self,
+        request: BatchMintQuoteMiningShareRequest,
+    ) -> Result<BatchMintQuoteMiningShareResponse<String>, Error>;
     /// Mint Quote status for Mining Share [NUT-XX]
     async fn get_mint_quote_status_mining_share(
         &self,
         quote_id: &str,
     ) -> Result<MintQuoteMiningShareResponse<String>, Error>;
+    /// Mint Quote status of several Mining Share quotes [NUT-XX]
+    async fn post_mint_quote_status_mining_share_batch(
+        &self,
+        request: BatchQuoteStatusRequest,
+    ) -> Result<BatchQuoteStatusResponse<String>, Error>;
 
     /// Mint Tokens for Mining Share [NUT-XX]
     async fn post_mint_mining_share(
         &self,
         request: MintRequest<String>,
     ) -> Result<MintResponse, Error>;
+
+    /// Convert a Mint Quote to another payment method [NUT-XX]
+    async fn post_mint_quote_conversion(
+        &self,
+        request: MintQuoteConversionRequest<String>,
+    ) -> Result<MintQuoteConversionResponse<String>, Error>;
+
+    /// Cancel a Mint Quote that has no ecash issued [NUT-XX]
+    async fn delete_mint_quote(
+        &self,
+        method: PaymentMethod,
+        request: MintQuoteCancelRequest<String>,
+    ) -> Result<MintQuoteCancelResponse<String>, Error>;
 }
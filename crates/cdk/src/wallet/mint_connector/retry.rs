@@ -0,0 +1,256 @@
+//! Retry policy of the wallet HTTP client
+//!
+//! [`HttpClientOptions`] configures how the [`HttpClient`](super::HttpClient)
+//! retries requests that failed for a transient reason: a gateway error in
+//! front of the mint or the mint not being reachable at all. Retries wait an
+//! exponentially growing backoff.
+//!
+//! Rate limiting is left to the [`RequestScheduler`](super::RequestScheduler),
+//! which pauses every request to the mint for the `Retry-After` it asked for.
+//! The client returns [`Error::RateLimited`] as is, so a request is not
+//! retried by both.
+//!
+//! Only requests that are safe to repeat are retried by default: mint info,
+//! keysets and quote status. Mint requests are only retried once opted in with
+//! [`HttpClientOptions::retry_mint_requests`], as repeating a mint request the
+//! mint already processed fails unless the mint replays its response.
+
+use std::future::Future;
+
+use web_time::Duration;
+
+use super::Error;
+
+/// Transient failure a request is retried on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RetryCondition {
+    /// The mint or a gateway in front of it answered with a `5xx` status
+    ServerError,
+    /// The mint could not be reached
+    Connect,
+}
+
+impl RetryCondition {
+    /// Condition an error falls under, if any
+    fn of(error: &Error) -> Option<Self> {
+        match error {
+            Error::HttpError(Some(status), _) if (500..=599).contains(status) => {
+                Some(Self::ServerError)
+            }
+            Error::HttpError(None, _) => Some(Self::Connect),
+            _ => None,
+        }
+    }
+}
+
+/// Options of the wallet HTTP client
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpClientOptions {
+    /// How often a failed request is retried before the error is returned
+    pub max_retries: u32,
+    /// Wait before the first retry, doubled for every further retry
+    pub base_backoff: Duration,
+    /// Failures that are retried
+    pub retry_on: Vec<RetryCondition>,
+    /// Also retry mint requests
+    ///
+    /// Only enable this for mints that replay the response of a mint request
+    /// they already processed.
+    pub retry_mint_requests: bool,
+}
+
+impl Default for HttpClientOptions {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            base_backoff: Duration::from_millis(250),
+            retry_on: vec![RetryCondition::ServerError, RetryCondition::Connect],
+            retry_mint_requests: false,
+        }
+    }
+}
+
+impl HttpClientOptions {
+    /// Options that never retry a request
+    pub fn no_retries() -> Self {
+        Self {
+            max_retries: 0,
+            ..Default::default()
+        }
+    }
+
+    /// Backoff before retry number `retry`, starting at zero
+    pub fn backoff(&self, retry: u32) -> Duration {
+        self.base_backoff
+            .saturating_mul(2u32.saturating_pow(retry.min(16)))
+    }
+
+    /// How long to wait before retrying a request that failed with `error`
+    ///
+    /// Returns `None` if the error is not retried.
+    fn retry_delay(&self, error: &Error, retry: u32) -> Option<Duration> {
+        let condition = RetryCondition::of(error)?;
+        if retry >= self.max_retries || !self.retry_on.contains(&condition) {
+            return None;
+        }
+
+        Some(self.backoff(retry))
+    }
+
+    /// Make a request, retrying it on the configured failures
+    pub(crate) async fn retry<F, Fut, R>(&self, request: F) -> Result<R, Error>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = Result<R, Error>>,
+    {
+        let mut retries = 0;
+
+        loop {
+            let error = match request().await {
+                Ok(response) => return Ok(response),
+                Err(error) => error,
+            };
+
+            let Some(delay) = self.retry_delay(&error, retries) else {
+                return Err(error);
+            };
+
+            tracing::warn!(
+                "Request to mint failed with {}, retrying in {:?}",
+                error,
+                delay
+            );
+
+            tokio::time::sleep(delay).await;
+            retries += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use web_time::Instant;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_retries_with_exponential_backoff() {
+        let options = HttpClientOptions {
+            base_backoff: Duration::from_millis(50),
+            ..Default::default()
+        };
+        let attempts = Arc::new(Mutex::new(Vec::new()));
+
+        let started = Instant::now();
+        let result = options
+            .retry(|| {
+                let attempts = attempts.clone();
+                async move {
+                    let mut attempts = attempts.lock().unwrap();
+                    attempts.push(started.elapsed());
+                    if attempts.len() <= 2 {
+                        Err(Error::HttpError(
+                            Some(503),
+                            "Service Unavailable".to_string(),
+                        ))
+                    } else {
+                        Ok(attempts.len())
+                    }
+                }
+            })
+            .await;
+
+        assert_eq!(result.unwrap(), 3);
+        let attempts = attempts.lock().unwrap();
+        assert!(
+            attempts[1] - attempts[0] >= Duration::from_millis(50),
+            "{attempts:?}"
+        );
+        assert!(
+            attempts[2] - attempts[1] >= Duration::from_millis(100),
+            "{attempts:?}"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited_not_retried() {
+        let options = HttpClientOptions {
+            base_backoff: Duration::from_millis(1),
+            ..Default::default()
+        };
+        let attempts = Arc::new(Mutex::new(0));
+
+        // Left to the request scheduler
+        let result: Result<(), Error> = options
+            .retry(|| {
+                let attempts = attempts.clone();
+                async move {
+                    *attempts.lock().unwrap() += 1;
+                    Err(Error::RateLimited(Some(1)))
+                }
+            })
+            .await;
+
+        assert!(matches!(result, Err(Error::RateLimited(Some(1)))));
+        assert_eq!(*attempts.lock().unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_errors_not_retried() {
+        let options = HttpClientOptions {
+            base_backoff: Duration::from_millis(1),
+            retry_on: vec![RetryCondition::Connect],
+            ..Default::default()
+        };
+        let attempts = Arc::new(Mutex::new(0));
+
+        let result: Result<(), Error> = options
+            .retry(|| {
+                let attempts = attempts.clone();
+                async move {
+                    *attempts.lock().unwrap() += 1;
+                    Err(Error::HttpError(Some(502), "Bad Gateway".to_string()))
+                }
+            })
+            .await;
+        assert!(matches!(result, Err(Error::HttpError(Some(502), _))));
+
+        let result: Result<(), Error> = options
+            .retry(|| {
+                let attempts = attempts.clone();
+                async move {
+                    *attempts.lock().unwrap() += 1;
+                    Err(Error::HttpError(Some(400), "Bad Request".to_string()))
+                }
+            })
+            .await;
+        assert!(matches!(result, Err(Error::HttpError(Some(400), _))));
+
+        assert_eq!(*attempts.lock().unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_retries_exhausted() {
+        let options = HttpClientOptions {
+            max_retries: 2,
+            base_backoff: Duration::from_millis(1),
+            ..Default::default()
+        };
+        let attempts = Arc::new(Mutex::new(0));
+
+        let result: Result<(), Error> = options
+            .retry(|| {
+                let attempts = attempts.clone();
+                async move {
+                    *attempts.lock().unwrap() += 1;
+                    Err(Error::HttpError(None, "Connection refused".to_string()))
+                }
+            })
+            .await;
+
+        assert!(matches!(result, Err(Error::HttpError(None, _))));
+        assert_eq!(*attempts.lock().unwrap(), 3);
+    }
+}
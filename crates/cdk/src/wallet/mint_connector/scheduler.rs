@@ -0,0 +1,788 @@
+//! Outbound request scheduling
+//!
+//! [`ScheduledMintConnector`] puts a token bucket rate limiter in front of a
+//! [`MintConnector`], so a wallet sharing one mint between syncing, issuance
+//! and user requests stays below the mint's rate limits.
+//!
+//! Waiting requests are served by [`RequestPriority`]: a request is only let
+//! through once no request of a more urgent class is waiting, so a large
+//! background sync cannot starve a user's send. Priorities are assigned per
+//! operation with [`RequestScheduler::with_priority`], every request made by
+//! the operation runs in its class and requests of one operation keep their
+//! order.
+//!
+//! A `429 Too Many Requests` from the mint pauses the bucket for the
+//! `Retry-After` the mint asked for, at most a minute, after which the request
+//! is retried. This is the only layer retrying rate limited requests, the
+//! [`HttpClient`](super::HttpClient) returns them as is.
+
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use cdk_common::{MeltQuoteBolt12Request, MintQuoteBolt12Request, MintQuoteBolt12Response};
+use tokio::sync::Notify;
+use tracing::instrument;
+use web_time::{Duration, Instant};
+
+use super::{Error, MintConnector};
+use crate::nuts::{
+    BatchMintQuoteMiningShareRequest, BatchMintQuoteMiningShareResponse, BatchQuoteStatusRequest,
+    BatchQuoteStatusResponse, CheckStateRequest, CheckStateResponse, Id, KeySet, KeysetResponse,
+    MeltQuoteBolt11Request, MeltQuoteBolt11Response, MeltRequest, MintInfo, MintQuoteBolt11Request,
+    MintQuoteBolt11Response, MintQuoteCancelRequest, MintQuoteCancelResponse,
+    MintQuoteConversionRequest, MintQuoteConversionResponse, MintQuoteMiningShareRequest,
+    MintQuoteMiningShareResponse, MintRequest, MintResponse, PaymentMethod, RestoreRequest,
+    RestoreResponse, SwapRequest, SwapResponse,
+};
+#[cfg(feature = "auth")]
+use crate::wallet::AuthWallet;
+
+/// Pause used when a mint rate limits a request without a `Retry-After`
+const DEFAULT_RETRY_AFTER: Duration = Duration::from_secs(1);
+
+/// Longest pause a `Retry-After` of the mint is followed for
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(60);
+
+/// Lowest rate a [`RequestScheduler`] runs at, one request every 1000 seconds
+const MIN_REQUESTS_PER_SECOND: f64 = 0.001;
+
+/// Highest rate a [`RequestScheduler`] runs at
+const MAX_REQUESTS_PER_SECOND: f64 = 1_000_000.0;
+
+/// Pause for the `Retry-After` seconds a mint asked for, capped at
+/// [`MAX_RETRY_AFTER`]
+fn retry_after_pause(retry_after: Option<u64>) -> Duration {
+    retry_after
+        .map(Duration::from_secs)
+        .unwrap_or(DEFAULT_RETRY_AFTER)
+        .min(MAX_RETRY_AFTER)
+}
+
+tokio::task_local! {
+    static OPERATION_PRIORITY: RequestPriority;
+}
+
+/// Priority class of a request
+///
+/// Classes are ordered from most to least urgent.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum RequestPriority {
+    /// Operations a user is waiting on, e.g. sending or melting
+    #[default]
+    Interactive,
+    /// Minting of quotes
+    Issuance,
+    /// Background syncing of quotes and proof states
+    Background,
+}
+
+impl RequestPriority {
+    fn index(self) -> usize {
+        match self {
+            Self::Interactive => 0,
+            Self::Issuance => 1,
+            Self::Background => 2,
+        }
+    }
+}
+
+/// Rate limit of a [`RequestScheduler`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct RateLimit {
+    /// Sustained number of requests per second
+    pub requests_per_second: f64,
+    /// Number of requests that can be made at once after being idle
+    pub burst: u32,
+    /// How often a rate limited request is retried before the error is returned
+    pub max_retries: u32,
+}
+
+impl Default for RateLimit {
+    fn default() -> Self {
+        Self {
+            requests_per_second: 10.0,
+            burst: 20,
+            max_retries: 3,
+        }
+    }
+}
+
+/// Number of requests waiting in each priority class
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct QueueDepth {
+    /// Waiting interactive requests
+    pub interactive: usize,
+    /// Waiting issuance requests
+    pub issuance: usize,
+    /// Waiting background requests
+    pub background: usize,
+}
+
+impl QueueDepth {
+    /// Total number of waiting requests
+    pub fn total(&self) -> usize {
+        self.interactive + self.issuance + self.background
+    }
+}
+
+#[derive(Debug)]
+struct SchedulerState {
+    tokens: f64,
+    last_refill: Instant,
+    paused_until: Option<Instant>,
+    waiting: [usize; 3],
+}
+
+#[derive(Debug)]
+struct SchedulerInner {
+    rate_limit: RateLimit,
+    state: Mutex<SchedulerState>,
+    notify: Notify,
+}
+
+impl SchedulerInner {
+    fn requests_per_second(&self) -> f64 {
+        self.rate_limit.requests_per_second
+    }
+
+    fn burst(&self) -> f64 {
+        f64::from(self.rate_limit.burst.max(1))
+    }
+
+    /// Take a token for a request of the given priority
+    ///
+    /// Returns how long to wait before trying again if the request has to wait.
+    fn try_take(&self, priority: RequestPriority) -> Result<(), Duration> {
+        let mut state = self.state.lock().expect("Scheduler lock poisoned");
+        let now = Instant::now();
+
+        if let Some(paused_until) = state.paused_until {
+            if now < paused_until {
+                return Err(paused_until - now);
+            }
+            state.paused_until = None;
+            state.last_refill = paused_until;
+        }
+
+        let elapsed = now.saturating_duration_since(state.last_refill);
+        state.tokens =
+            (state.tokens + elapsed.as_secs_f64() * self.requests_per_second()).min(self.burst());
+        state.last_refill = now;
+
+        let token_interval = Duration::from_secs_f64(1.0 / self.requests_per_second());
+
+        // Requests of more urgent classes go first, they wake us once served
+        if state.waiting[..priority.index()]
+            .iter()
+            .any(|count| *count > 0)
+        {
+            return Err(token_interval);
+        }
+
+        if state.tokens >= 1.0 {
+            state.tokens -= 1.0;
+            return Ok(());
+        }
+
+        Err(token_interval.mul_f64(1.0 - state.tokens))
+    }
+}
+
+/// Marks a request as waiting until it is dropped
+struct Waiting<'a> {
+    inner: &'a SchedulerInner,
+    priority: RequestPriority,
+}
+
+impl<'a> Waiting<'a> {
+    fn new(inner: &'a SchedulerInner, priority: RequestPriority) -> Self {
+        inner.state.lock().expect("Scheduler lock poisoned").waiting[priority.index()] += 1;
+
+        Self { inner, priority }
+    }
+}
+
+impl Drop for Waiting<'_> {
+    fn drop(&mut self) {
+        if let Ok(mut state) = self.inner.state.lock() {
+            state.waiting[self.priority.index()] -= 1;
+        }
+        self.inner.notify.notify_waiters();
+    }
+}
+
+/// Token bucket rate limiter with priority classes
+///
+/// Clones share the same bucket, use one scheduler per mint.
+#[derive(Debug, Clone)]
+pub struct RequestScheduler {
+    inner: Arc<SchedulerInner>,
+}
+
+impl RequestScheduler {
+    /// Create new [`RequestScheduler`], starting with a full bucket
+    ///
+    /// The rate is clamped between one request every 1000 seconds and a
+    /// million requests per second, a rate that is not a number runs at the
+    /// lowest rate.
+    pub fn new(mut rate_limit: RateLimit) -> Self {
+        let requests_per_second = rate_limit.requests_per_second;
+        rate_limit.requests_per_second = if requests_per_second.is_nan() {
+            MIN_REQUESTS_PER_SECOND
+        } else {
+            requests_per_second.clamp(MIN_REQUESTS_PER_SECOND, MAX_REQUESTS_PER_SECOND)
+        };
+        if rate_limit.requests_per_second != requests_per_second {
+            tracing::warn!(
+                "Request rate {} out of range, using {}",
+                requests_per_second,
+                rate_limit.requests_per_second
+            );
+        }
+
+        let tokens = f64::from(rate_limit.burst.max(1));
+
+        Self {
+            inner: Arc::new(SchedulerInner {
+                rate_limit,
+                state: Mutex::new(SchedulerState {
+                    tokens,
+                    last_refill: Instant::now(),
+                    paused_until: None,
+                    waiting: [0; 3],
+                }),
+                notify: Notify::new(),
+            }),
+        }
+    }
+
+    /// Run an operation with the given request priority
+    ///
+    /// All requests the operation makes are scheduled in `priority`. An
+    /// operation started inside another one keeps the priority of the outer
+    /// operation.
+    pub async fn with_priority<F>(priority: RequestPriority, operation: F) -> F::Output
+    where
+        F: Future,
+    {
+        if OPERATION_PRIORITY.try_with(|_| ()).is_ok() {
+            return operation.await;
+        }
+
+        OPERATION_PRIORITY.scope(priority, operation).await
+    }
+
+    /// Priority of the running operation
+    pub fn current_priority() -> RequestPriority {
+        OPERATION_PRIORITY
+            .try_with(|priority| *priority)
+            .unwrap_or_default()
+    }
+
+    /// Rate limit of the scheduler
+    pub fn rate_limit(&self) -> &RateLimit {
+        &self.inner.rate_limit
+    }
+
+    /// Number of requests currently waiting
+    pub fn queue_depth(&self) -> QueueDepth {
+        let state = self.inner.state.lock().expect("Scheduler lock poisoned");
+
+        QueueDepth {
+            interactive: state.waiting[RequestPriority::Interactive.index()],
+            issuance: state.waiting[RequestPriority::Issuance.index()],
+            background: state.waiting[RequestPriority::Background.index()],
+        }
+    }
+
+    /// Stop letting requests through for `duration`
+    ///
+    /// The bucket is emptied, so requests resume at the sustained rate.
+    pub fn pause(&self, duration: Duration) {
+        let mut state = self.inner.state.lock().expect("Scheduler lock poisoned");
+        let until = Instant::now() + duration;
+
+        state.paused_until = Some(state.paused_until.map_or(until, |paused| paused.max(until)));
+        state.tokens = 0.0;
+    }
+
+    /// Wait until a request of the given priority may be made
+    pub async fn acquire(&self, priority: RequestPriority) {
+        let _waiting = Waiting::new(&self.inner, priority);
+
+        loop {
+            let wait = match self.inner.try_take(priority) {
+                Ok(()) => return,
+                Err(wait) => wait,
+            };
+
+            let _ = tokio::time::timeout(wait, self.inner.notify.notified()).await;
+        }
+    }
+
+    /// Make a request once the bucket allows it
+    ///
+    /// Requests the mint rate limits are retried after the `Retry-After` it
+    /// returned, capped at a minute, up to [`RateLimit::max_retries`] times.
+    pub async fn run<F, Fut, R>(&self, request: F) -> Result<R, Error>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = Result<R, Error>>,
+    {
+        let priority = Self::current_priority();
+        let mut retries = 0;
+
+        loop {
+            self.acquire(priority).await;
+
+            match request().await {
+                Err(Error::RateLimited(retry_after))
+                    if retries < self.inner.rate_limit.max_retries =>
+                {
+                    let retry_after = retry_after_pause(retry_after);
+
+                    tracing::warn!(
+                        "Rate limited by mint, pausing requests for {:?}",
+                        retry_after
+                    );
+
+                    self.pause(retry_after);
+                    retries += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+/// [`MintConnector`] making its requests through a [`RequestScheduler`]
+#[derive(Debug, Clone)]
+pub struct ScheduledMintConnector<C> {
+    inner: C,
+    scheduler: RequestScheduler,
+}
+
+impl<C> ScheduledMintConnector<C>
+where
+    C: MintConnector,
+{
+    /// Create new [`ScheduledMintConnector`]
+    pub fn new(inner: C, scheduler: RequestScheduler) -> Self {
+        Self { inner, scheduler }
+    }
+
+    /// Scheduler of the connector
+    pub fn scheduler(&self) -> &RequestScheduler {
+        &self.scheduler
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl<C> MintConnector for ScheduledMintConnector<C>
+where
+    C: MintConnector + Send + Sync,
+{
+    #[instrument(skip(self))]
+    async fn get_mint_keys(&self) -> Result<Vec<KeySet>, Error> {
+        self.scheduler.run(|| self.inner.get_mint_keys()).await
+    }
+
+    #[instrument(skip(self))]
+    async fn get_mint_keyset(&self, keyset_id: Id) -> Result<KeySet, Error> {
+        self.scheduler
+            .run(|| self.inner.get_mint_keyset(keyset_id))
+            .await
+    }
+
+    #[instrument(skip(self))]
+    async fn get_mint_keysets(&self) -> Result<KeysetResponse, Error> {
+        self.scheduler.run(|| self.inner.get_mint_keysets()).await
+    }
+
+    #[instrument(skip(self, request))]
+    async fn post_mint_quote(
+        &self,
+        request: MintQuoteBolt11Request,
+    ) -> Result<MintQuoteBolt11Response<String>, Error> {
+        self.scheduler
+            .run(|| self.inner.post_mint_quote(request.clone()))
+            .await
+    }
+
+    #[instrument(skip(self))]
+    async fn get_mint_quote_status(
+        &self,
+        quote_id: &str,
+        payment_method: PaymentMethod,
+    ) -> Result<MintQuoteBolt11Response<String>, Error> {
+        self.scheduler
+            .run(|| {
+                self.inner
+                    .get_mint_quote_status(quote_id, payment_method.clone())
+            })
+            .await
+    }
+
+    #[instrument(skip(self, request))]
+    async fn post_mint(&self, request: MintRequest<String>) -> Result<MintResponse, Error> {
+        self.scheduler
+            .run(|| self.inner.post_mint(request.clone()))
+            .await
+    }
+
+    #[instrument(skip(self, request))]
+    async fn post_melt_quote(
+        &self,
+        request: MeltQuoteBolt11Request,
+    ) -> Result<MeltQuoteBolt11Response<String>, Error> {
+        self.scheduler
+            .run(|| self.inner.post_melt_quote(request.clone()))
+            .await
+    }
+
+    #[instrument(skip(self))]
+    async fn get_melt_quote_status(
+        &self,
+        quote_id: &str,
+    ) -> Result<MeltQuoteBolt11Response<String>, Error> {
+        self.scheduler
+            .run(|| self.inner.get_melt_quote_status(quote_id))
+            .await
+    }
+
+    #[instrument(skip(self, request))]
+    async fn post_melt(
+        &self,
+        request: MeltRequest<String>,
+    ) -> Result<MeltQuoteBolt11Response<String>, Error> {
+        self.scheduler
+            .run(|| self.inner.post_melt(request.clone()))
+            .await
+    }
+
+    #[instrument(skip(self, request))]
+    async fn post_swap(&self, request: SwapRequest) -> Result<SwapResponse, Error> {
+        self.scheduler
+            .run(|| self.inner.post_swap(request.clone()))
+            .await
+    }
+
+    #[instrument(skip(self))]
+    async fn get_mint_info(&self) -> Result<MintInfo, Error> {
+        self.scheduler.run(|| self.inner.get_mint_info()).await
+    }
+
+    #[instrument(skip(self, request))]
+    async fn post_check_state(
+        &self,
+        request: CheckStateRequest,
+    ) -> Result<CheckStateResponse, Error> {
+        self.scheduler
+            .run(|| self.inner.post_check_state(request.clone()))
+            .await
+    }
+
+    #[instrument(skip(self, request))]
+    async fn post_restore(&self, request: RestoreRequest) -> Result<RestoreResponse, Error> {
+        self.scheduler
+            .run(|| self.inner.post_restore(request.clone()))
+            .await
+    }
+
+    #[cfg(feature = "auth")]
+    async fn get_auth_wallet(&self) -> Option<AuthWallet> {
+        self.inner.get_auth_wallet().await
+    }
+
+    #[cfg(feature = "auth")]
+    async fn set_auth_wallet(&self, wallet: Option<AuthWallet>) {
+        self.inner.set_auth_wallet(wallet).await
+    }
+
+    #[instrument(skip(self, request))]
+    async fn post_mint_bolt12_quote(
+        &self,
+        request: MintQuoteBolt12Request,
+    ) -> Result<MintQuoteBolt12Response<String>, Error> {
+        self.scheduler
+            .run(|| self.inner.post_mint_bolt12_quote(request.clone()))
+            .await
+    }
+
+    #[instrument(skip(self))]
+    async fn get_mint_quote_bolt12_status(
+        &self,
+        quote_id: &str,
+    ) -> Result<MintQuoteBolt12Response<String>, Error> {
+        self.scheduler
+            .run(|| self.inner.get_mint_quote_bolt12_status(quote_id))
+            .await
+    }
+
+    #[instrument(skip(self, request))]
+    async fn post_melt_bolt12_quote(
+        &self,
+        request: MeltQuoteBolt12Request,
+    ) -> Result<MeltQuoteBolt11Response<String>, Error> {
+        self.scheduler
+            .run(|| self.inner.post_melt_bolt12_quote(request.clone()))
+            .await
+    }
+
+    #[instrument(skip(self))]
+    async fn get_melt_bolt12_quote_status(
+        &self,
+        quote_id: &str,
+    ) -> Result<MeltQuoteBolt11Response<String>, Error> {
+        self.scheduler
+            .run(|| self.inner.get_melt_bolt12_quote_status(quote_id))
+            .await
+    }
+
+    #[instrument(skip(self, request))]
+    async fn post_melt_bolt12(
+        &self,
+        request: MeltRequest<String>,
+    ) -> Result<MeltQuoteBolt11Response<String>, Error> {
+        self.scheduler
+            .run(|| self.inner.post_melt_bolt12(request.clone()))
+            .await
+    }
+
+    #[instrument(skip(self, request))]
+    async fn post_mint_quote_mining_share(
+        &self,
+        request: MintQuoteMiningShareRequest,
+    ) -> Result<MintQuoteMiningShareResponse<String>, Error> {
+        self.scheduler
+            .run(|| self.inner.post_mint_quote_mining_share(request.clone()))
+            .await
+    }
+
+    #[instrument(skip(self, request))]
+    async fn post_mint_quotes_mining_share_batch(
+        &self,
+        request: BatchMintQuoteMiningShareRequest,
+    ) -> Result<BatchMintQuoteMiningShareResponse<String>, Error> {
+        self.scheduler
+            .run(|| {
+                self.inner
+                    .post_mint_quotes_mining_share_batch(request.clone())
+            })
+            .await
+    }
+
+    #[instrument(skip(self))]
+    async fn get_mint_quote_status_mining_share(
+        &self,
+        quote_id: &str,
+    ) -> Result<MintQuoteMiningShareResponse<String>, Error> {
+        self.scheduler
+            .run(|| self.inner.get_mint_quote_status_mining_share(quote_id))
+            .await
+    }
+
+    #[instrument(skip(self, request))]
+    async fn post_mint_quote_status_mining_share_batch(
+        &self,
+        request: BatchQuoteStatusRequest,
+    ) -> Result<BatchQuoteStatusResponse<String>, Error> {
+        self.scheduler
+            .run(|| {
+                self.inner
+                    .post_mint_quote_status_mining_share_batch(request.clone())
+            })
+            .await
+    }
+
+    #[instrument(skip(self, request))]
+    async fn post_mint_mining_share(
+        &self,
+        request: MintRequest<String>,
+    ) -> Result<MintResponse, Error> {
+        self.scheduler
+            .run(|| self.inner.post_mint_mining_share(request.clone()))
+            .await
+    }
+
+    #[instrument(skip(self, request))]
+    async fn post_mint_quote_conversion(
+        &self,
+        request: MintQuoteConversionRequest<String>,
+    ) -> Result<MintQuoteConversionResponse<String>, Error> {
+        self.scheduler
+            .run(|| self.inner.post_mint_quote_conversion(request.clone()))
+            .await
+    }
+
+    #[instrument(skip(self, request))]
+    async fn delete_mint_quote(
+        &self,
+        method: PaymentMethod,
+        request: MintQuoteCancelRequest<String>,
+    ) -> Result<MintQuoteCancelResponse<String>, Error> {
+        self.scheduler
+            .run(|| {
+                self.inner
+                    .delete_mint_quote(method.clone(), request.clone())
+            })
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex as StdMutex;
+
+    use super::*;
+
+    fn scheduler(requests_per_second: f64, burst: u32) -> RequestScheduler {
+        RequestScheduler::new(RateLimit {
+            requests_per_second,
+            burst,
+            max_retries: 3,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_throughput_converges_to_rate() {
+        let scheduler = scheduler(50.0, 1);
+
+        let started = Instant::now();
+        for _ in 0..26 {
+            scheduler.acquire(RequestPriority::Background).await;
+        }
+        let elapsed = started.elapsed();
+
+        // First request uses the burst, the other 25 come at 50 per second
+        assert!(elapsed >= Duration::from_millis(450), "{elapsed:?}");
+        assert!(elapsed < Duration::from_millis(900), "{elapsed:?}");
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_interactive_requests_jump_the_queue() {
+        let scheduler = scheduler(20.0, 1);
+        let order = Arc::new(StdMutex::new(Vec::new()));
+
+        // Use up the burst so everything below has to queue
+        scheduler.acquire(RequestPriority::Interactive).await;
+
+        let mut tasks = Vec::new();
+        for i in 0..4 {
+            let scheduler = scheduler.clone();
+            let order = order.clone();
+            tasks.push(tokio::spawn(async move {
+                scheduler.acquire(RequestPriority::Background).await;
+                order.lock().unwrap().push(format!("background-{i}"));
+            }));
+        }
+
+        while scheduler.queue_depth().background < 4 {
+            tokio::task::yield_now().await;
+        }
+
+        scheduler.acquire(RequestPriority::Interactive).await;
+        order.lock().unwrap().push("interactive".to_string());
+
+        for task in tasks {
+            task.await.unwrap();
+        }
+
+        let order = order.lock().unwrap();
+        let position = order.iter().position(|entry| entry == "interactive");
+        // At most the background request already holding the next token goes first
+        assert!(position.is_some_and(|position| position <= 1), "{order:?}");
+        assert_eq!(scheduler.queue_depth(), QueueDepth::default());
+    }
+
+    #[tokio::test]
+    async fn test_retry_after_pauses_bucket() {
+        let scheduler = scheduler(100.0, 10);
+        let attempts = Arc::new(StdMutex::new(Vec::new()));
+
+        let started = Instant::now();
+        let result = scheduler
+            .run(|| {
+                let attempts = attempts.clone();
+                async move {
+                    let mut attempts = attempts.lock().unwrap();
+                    attempts.push(started.elapsed());
+                    if attempts.len() == 1 {
+                        Err(Error::RateLimited(Some(1)))
+                    } else {
+                        Ok(attempts.len())
+                    }
+                }
+            })
+            .await;
+
+        assert_eq!(result.unwrap(), 2);
+        let attempts = attempts.lock().unwrap();
+        assert!(attempts[1] >= Duration::from_secs(1), "{attempts:?}");
+    }
+
+    #[tokio::test]
+    async fn test_out_of_range_rate_is_clamped() {
+        for (requested, expected) in [
+            (0.0, MIN_REQUESTS_PER_SECOND),
+            (-5.0, MIN_REQUESTS_PER_SECOND),
+            (f64::MIN_POSITIVE, MIN_REQUESTS_PER_SECOND),
+            (f64::NAN, MIN_REQUESTS_PER_SECOND),
+            (f64::INFINITY, MAX_REQUESTS_PER_SECOND),
+            (25.0, 25.0),
+        ] {
+            let scheduler = scheduler(requested, 1);
+            assert_eq!(scheduler.rate_limit().requests_per_second, expected);
+
+            // Neither the burst nor the wait for the next token panics
+            scheduler.acquire(RequestPriority::Interactive).await;
+            if let Err(wait) = scheduler.inner.try_take(RequestPriority::Interactive) {
+                assert!(wait <= Duration::from_secs(1000), "{wait:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_retry_after_is_capped() {
+        assert_eq!(retry_after_pause(None), DEFAULT_RETRY_AFTER);
+        assert_eq!(retry_after_pause(Some(5)), Duration::from_secs(5));
+        assert_eq!(retry_after_pause(Some(3600)), MAX_RETRY_AFTER);
+        assert_eq!(retry_after_pause(Some(u64::MAX)), MAX_RETRY_AFTER);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited_retries_exhausted() {
+        let scheduler = RequestScheduler::new(RateLimit {
+            requests_per_second: 100.0,
+            burst: 10,
+            max_retries: 0,
+        });
+
+        let result: Result<(), Error> = scheduler
+            .run(|| async { Err(Error::RateLimited(None)) })
+            .await;
+
+        assert!(matches!(result, Err(Error::RateLimited(None))));
+    }
+
+    #[tokio::test]
+    async fn test_operation_priority() {
+        assert_eq!(
+            RequestScheduler::current_priority(),
+            RequestPriority::Interactive
+        );
+
+        let priority = RequestScheduler::with_priority(RequestPriority::Background, async {
+            // Nested operations keep the priority of the outer operation
+            RequestScheduler::with_priority(RequestPriority::Issuance, async {
+                RequestScheduler::current_priority()
+            })
+            .await
+        })
+        .await;
+
+        assert_eq!(priority, RequestPriority::Background);
+    }
+}
@@ -2,13 +2,15 @@
 use std::fmt::Debug;
 
 use cdk_common::AuthToken;
-use reqwest::Client;
+use reqwest::header::{ACCEPT, CONTENT_TYPE, RETRY_AFTER};
+use reqwest::{Client, StatusCode};
 use serde::de::DeserializeOwned;
 use serde::Serialize;
 use url::Url;
 
 use super::Error;
 use crate::error::ErrorResponse;
+use crate::nuts::nutXX::CBOR_CONTENT_TYPE;
 
 /// Expected HTTP Transport
 #[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
@@ -27,6 +29,11 @@ pub trait Transport: Default + Send + Sync + Debug + Clone {
     where
         R: DeserializeOwned;
 
+    /// HTTP Delete request
+    async fn http_delete<R>(&self, url: Url, auth: Option<AuthToken>) -> Result<R, Error>
+    where
+        R: DeserializeOwned;
+
     /// HTTP Post request
     async fn http_post<P, R>(
         &self,
@@ -37,6 +44,50 @@ pub trait Transport: Default + Send + Sync + Debug + Clone {
     where
         P: Serialize + ?Sized + Send + Sync,
         R: DeserializeOwned;
+
+    /// HTTP Post request using the compact CBOR encoding
+    ///
+    /// Transports without CBOR support fall back to [`Transport::http_post`].
+    async fn http_post_cbor<P, R>(
+        &self,
+        url: Url,
+        auth_token: Option<AuthToken>,
+        payload: &P,
+    ) -> Result<R, Error>
+    where
+        P: Serialize + ?Sized + Send + Sync,
+        R: DeserializeOwned,
+    {
+        self.http_post(url, auth_token, payload).await
+    }
+}
+
+/// Fail on responses of a mint that is rate limiting or temporarily unavailable
+///
+/// `429 Too Many Requests`, and `503 Service Unavailable` with a
+/// `Retry-After`, fail with [`Error::RateLimited`]. Other gateway errors fail
+/// with [`Error::HttpError`], as their body is not a mint error response.
+///
+/// Only the delay-seconds form of `Retry-After` is understood.
+fn check_transient_failure(response: &reqwest::Response) -> Result<(), Error> {
+    let status = response.status();
+
+    let retry_after = response
+        .headers()
+        .get(RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse::<u64>().ok());
+
+    match status {
+        StatusCode::TOO_MANY_REQUESTS => Err(Error::RateLimited(retry_after)),
+        StatusCode::SERVICE_UNAVAILABLE if retry_after.is_some() => {
+            Err(Error::RateLimited(retry_after))
+        }
+        StatusCode::BAD_GATEWAY | StatusCode::SERVICE_UNAVAILABLE | StatusCode::GATEWAY_TIMEOUT => {
+            Err(Error::HttpError(Some(status.as_u16()), status.to_string()))
+        }
+        _ => Ok(()),
+    }
 }
 
 /// Async transport for Http
@@ -112,23 +163,56 @@ impl Transport for Async {
             request = request.header(auth.header_key(), auth.to_string());
         }
 
-        let response = request
-            .send()
-            .await
-            .map_err(|e| {
-                Error::HttpError(
-                    e.status().map(|status_code| status_code.as_u16()),
-                    e.to_string(),
-                )
-            })?
-            .text()
-            .await
-            .map_err(|e| {
-                Error::HttpError(
-                    e.status().map(|status_code| status_code.as_u16()),
-                    e.to_string(),
-                )
-            })?;
+        let response = request.send().await.map_err(|e| {
+            Error::HttpError(
+                e.status().map(|status_code| status_code.as_u16()),
+                e.to_string(),
+            )
+        })?;
+
+        check_transient_failure(&response)?;
+
+        let response = response.text().await.map_err(|e| {
+            Error::HttpError(
+                e.status().map(|status_code| status_code.as_u16()),
+                e.to_string(),
+            )
+        })?;
+
+        serde_json::from_str::<R>(&response).map_err(|err| {
+            tracing::warn!("Http Response error: {}", err);
+            match ErrorResponse::from_json(&response) {
+                Ok(ok) => <ErrorResponse as Into<Error>>::into(ok),
+                Err(err) => err.into(),
+            }
+        })
+    }
+
+    async fn http_delete<R>(&self, url: Url, auth: Option<AuthToken>) -> Result<R, Error>
+    where
+        R: DeserializeOwned,
+    {
+        let mut request = self.inner.delete(url);
+
+        if let Some(auth) = auth {
+            request = request.header(auth.header_key(), auth.to_string());
+        }
+
+        let response = request.send().await.map_err(|e| {
+            Error::HttpError(
+                e.status().map(|status_code| status_code.as_u16()),
+                e.to_string(),
+            )
+        })?;
+
+        check_transient_failure(&response)?;
+
+        let response = response.text().await.map_err(|e| {
+            Error::HttpError(
+                e.status().map(|status_code| status_code.as_u16()),
+                e.to_string(),
+            )
+        })?;
 
         serde_json::from_str::<R>(&response).map_err(|err| {
             tracing::warn!("Http Response error: {}", err);
@@ -162,6 +246,8 @@ impl Transport for Async {
             )
         })?;
 
+        check_transient_failure(&response)?;
+
         let response = response.text().await.map_err(|e| {
             Error::HttpError(
                 e.status().map(|status_code| status_code.as_u16()),
@@ -177,4 +263,68 @@ impl Transport for Async {
             }
         })
     }
+
+    async fn http_post_cbor<P, R>(
+        &self,
+        url: Url,
+        auth_token: Option<AuthToken>,
+        payload: &P,
+    ) -> Result<R, Error>
+    where
+        P: Serialize + ?Sized + Send + Sync,
+        R: DeserializeOwned,
+    {
+        let mut body = Vec::new();
+        ciborium::into_writer(payload, &mut body).map_err(|e| Error::Custom(e.to_string()))?;
+
+        let mut request = self
+            .inner
+            .post(url)
+            .header(CONTENT_TYPE, CBOR_CONTENT_TYPE)
+            .header(ACCEPT, CBOR_CONTENT_TYPE)
+            .body(body);
+
+        if let Some(auth) = auth_token {
+            request = request.header(auth.header_key(), auth.to_string());
+        }
+
+        let response = request.send().await.map_err(|e| {
+            Error::HttpError(
+                e.status().map(|status_code| status_code.as_u16()),
+                e.to_string(),
+            )
+        })?;
+
+        check_transient_failure(&response)?;
+
+        let is_cbor = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.starts_with(CBOR_CONTENT_TYPE));
+
+        let response = response.bytes().await.map_err(|e| {
+            Error::HttpError(
+                e.status().map(|status_code| status_code.as_u16()),
+                e.to_string(),
+            )
+        })?;
+
+        if is_cbor {
+            return ciborium::from_reader(response.as_ref()).map_err(|err| {
+                tracing::warn!("Http Response error: {}", err);
+                Error::Custom(err.to_string())
+            });
+        }
+
+        // Error responses are always returned as json
+        let response = String::from_utf8_lossy(&response);
+        serde_json::from_str::<R>(&response).map_err(|err| {
+            tracing::warn!("Http Response error: {}", err);
+            match ErrorResponse::from_json(&response) {
+                Ok(ok) => <ErrorResponse as Into<Error>>::into(ok),
+                Err(err) => err.into(),
+            }
+        })
+    }
 }
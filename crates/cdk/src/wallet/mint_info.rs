@@ -0,0 +1,320 @@
+//! Mint info validation
+//!
+//! The info a mint advertises is checked before the wallet stores it. Storage
+//! stays keyed by the URL the wallet queried, advertised URLs that differ are
+//! recorded as aliases, and free text fields are bounded.
+
+use std::collections::HashSet;
+use std::fmt;
+use std::str::FromStr;
+
+use tracing::instrument;
+
+use crate::mint_url::MintUrl;
+use crate::nuts::{CurrencyUnit, MintInfo, PaymentMethod};
+use crate::{ensure_cdk, Error, Wallet};
+
+/// Longest message of the day stored, in bytes
+pub const MAX_MOTD_LEN: usize = 1024;
+/// Most contact entries stored
+pub const MAX_CONTACT_ENTRIES: usize = 16;
+/// Longest contact method or contact info stored, in bytes
+pub const MAX_CONTACT_FIELD_LEN: usize = 256;
+
+/// Inconsistency in the info advertised by a mint
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MintInfoWarning {
+    /// The mint does not advertise the URL it was queried at
+    AdvertisedUrlMismatch {
+        /// URL the wallet queried
+        queried: MintUrl,
+        /// URLs the mint advertised
+        advertised: Vec<String>,
+    },
+    /// A mint or melt method uses a unit the mint has no keyset for
+    UnitWithoutKeyset {
+        /// Payment method
+        method: PaymentMethod,
+        /// Unit without keyset
+        unit: CurrencyUnit,
+    },
+    /// A field exceeded its bound and was truncated
+    FieldTruncated {
+        /// Field name
+        field: &'static str,
+        /// Length advertised by the mint
+        len: usize,
+    },
+}
+
+impl fmt::Display for MintInfoWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::AdvertisedUrlMismatch {
+                queried,
+                advertised,
+            } => write!(
+                f,
+                "mint queried at {queried} advertises {}",
+                advertised.join(", ")
+            ),
+            Self::UnitWithoutKeyset { method, unit } => {
+                write!(f, "method {method} uses unit {unit} without keyset")
+            }
+            Self::FieldTruncated { field, len } => {
+                write!(f, "{field} of length {len} truncated")
+            }
+        }
+    }
+}
+
+/// Optional feature of a mint the wallet checks before using it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Capability {
+    /// Mining share quotes in the wallet unit
+    MiningShare,
+    /// CBOR bodies on the mining share mint endpoint
+    MiningShareCbor,
+    /// Cancelling mint quotes that have no ecash issued
+    QuoteCancel,
+    /// Looking up signatures issued for outputs [NUT-09]
+    Restore,
+}
+
+impl Capability {
+    /// Name used in [`Error::MintDoesNotSupport`]
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::MiningShare => "mining share quotes",
+            Self::MiningShareCbor => "CBOR mining share requests",
+            Self::QuoteCancel => "mint quote cancellation",
+            Self::Restore => "restore",
+        }
+    }
+
+    /// Whether `info` advertises the capability for `unit`
+    pub fn is_advertised(&self, info: &MintInfo, unit: &CurrencyUnit) -> bool {
+        match self {
+            Self::MiningShare => {
+                !info.nuts.nut04.disabled
+                    && info
+                        .nuts
+                        .nut04
+                        .get_settings(unit, &PaymentMethod::MiningShare)
+                        .is_some()
+            }
+            Self::MiningShareCbor => info.nuts.nutxx.is_some_and(|settings| settings.cbor),
+            Self::QuoteCancel => info
+                .nuts
+                .nutxx
+                .is_some_and(|settings| settings.quote_cancel),
+            Self::Restore => info.nuts.nut09.supported,
+        }
+    }
+}
+
+/// Outcome of validating mint info
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MintInfoReport {
+    /// Inconsistencies found, the info stored has bounded fields
+    pub warnings: Vec<MintInfoWarning>,
+    /// Advertised URLs other than the queried URL
+    pub aliases: Vec<MintUrl>,
+}
+
+/// Truncate `value` to at most `max` bytes on a char boundary
+///
+/// Returns the original length if the value was truncated.
+fn truncate(value: &mut String, max: usize) -> Option<usize> {
+    if value.len() <= max {
+        return None;
+    }
+
+    let len = value.len();
+    let mut end = max;
+    while !value.is_char_boundary(end) {
+        end -= 1;
+    }
+    value.truncate(end);
+
+    Some(len)
+}
+
+/// Check mint info advertised by the mint queried at `queried`
+///
+/// Bounds the free text fields of `info` in place. The unit check is skipped
+/// when the keyset units of the mint are not known.
+pub(crate) fn validate_mint_info(
+    queried: &MintUrl,
+    info: &mut MintInfo,
+    keyset_units: Option<&HashSet<CurrencyUnit>>,
+) -> MintInfoReport {
+    let mut report = MintInfoReport::default();
+
+    if let Some(urls) = info.urls.as_ref().filter(|urls| !urls.is_empty()) {
+        let parsed: Vec<MintUrl> = urls
+            .iter()
+            .filter_map(|url| MintUrl::from_str(url).ok())
+            .collect();
+
+        if !parsed.contains(queried) {
+            report
+                .warnings
+                .push(MintInfoWarning::AdvertisedUrlMismatch {
+                    queried: queried.clone(),
+                    advertised: urls.clone(),
+                });
+        }
+
+        for url in parsed {
+            if &url != queried && !report.aliases.contains(&url) {
+                report.aliases.push(url);
+            }
+        }
+    }
+
+    if let Some(keyset_units) = keyset_units {
+        let methods = info
+            .nuts
+            .nut04
+            .methods
+            .iter()
+            .map(|settings| (&settings.method, &settings.unit))
+            .chain(
+                info.nuts
+                    .nut05
+                    .methods
+                    .iter()
+                    .map(|settings| (&settings.method, &settings.unit)),
+            );
+
+        for (method, unit) in methods {
+            let warning = MintInfoWarning::UnitWithoutKeyset {
+                method: method.clone(),
+                unit: unit.clone(),
+            };
+            if !keyset_units.contains(unit) && !report.warnings.contains(&warning) {
+                report.warnings.push(warning);
+            }
+        }
+    }
+
+    if let Some(len) = info
+        .motd
+        .as_mut()
+        .and_then(|motd| truncate(motd, MAX_MOTD_LEN))
+    {
+        report
+            .warnings
+            .push(MintInfoWarning::FieldTruncated { field: "motd", len });
+    }
+
+    if let Some(contact) = info.contact.as_mut() {
+        if contact.len() > MAX_CONTACT_ENTRIES {
+            report.warnings.push(MintInfoWarning::FieldTruncated {
+                field: "contact",
+                len: contact.len(),
+            });
+            contact.truncate(MAX_CONTACT_ENTRIES);
+        }
+
+        for entry in contact.iter_mut() {
+            if let Some(len) = truncate(&mut entry.method, MAX_CONTACT_FIELD_LEN) {
+                report.warnings.push(MintInfoWarning::FieldTruncated {
+                    field: "contact.method",
+                    len,
+                });
+            }
+            if let Some(len) = truncate(&mut entry.info, MAX_CONTACT_FIELD_LEN) {
+                report.warnings.push(MintInfoWarning::FieldTruncated {
+                    field: "contact.info",
+                    len,
+                });
+            }
+        }
+    }
+
+    report
+}
+
+impl Wallet {
+    /// Validate mint info fetched from the mint and record its aliases
+    pub(crate) async fn check_mint_info(
+        &self,
+        info: &mut MintInfo,
+    ) -> Result<MintInfoReport, Error> {
+        let keyset_units: Option<HashSet<CurrencyUnit>> = self
+            .localstore
+            .get_mint_keysets(self.mint_url.clone())
+            .await?
+            .map(|keysets| keysets.into_iter().map(|keyset| keyset.unit).collect());
+
+        let report = validate_mint_info(&self.mint_url, info, keyset_units.as_ref());
+
+        for warning in &report.warnings {
+            tracing::warn!("Mint info of {}: {}", self.mint_url, warning);
+        }
+
+        let mut aliases = self.mint_aliases.write().map_err(|_| Error::Internal)?;
+        for alias in &report.aliases {
+            if !aliases.contains(alias) {
+                aliases.push(alias.clone());
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Whether the mint advertises `capability` for the wallet unit
+    ///
+    /// Reads the mint info in the localstore, fetching it first if none is
+    /// stored yet. Call [`Wallet::fetch_mint_info`] to pick up changes.
+    #[instrument(skip(self))]
+    pub async fn mint_supports(&self, capability: Capability) -> Result<bool, Error> {
+        let info = match self.localstore.get_mint(self.mint_url.clone()).await? {
+            Some(info) => Some(info),
+            None => self.fetch_mint_info().await?,
+        };
+
+        Ok(info.is_some_and(|info| capability.is_advertised(&info, &self.unit)))
+    }
+
+    /// Fail with [`Error::MintDoesNotSupport`] unless the mint advertises
+    /// `capability`
+    pub(crate) async fn ensure_mint_supports(&self, capability: Capability) -> Result<(), Error> {
+        ensure_cdk!(
+            self.mint_supports(capability).await?,
+            Error::MintDoesNotSupport(capability.name())
+        );
+
+        Ok(())
+    }
+
+    /// URLs the mint advertised other than the wallet mint URL
+    ///
+    /// Candidates for [`Wallet::update_mint_url`] when the mint moves.
+    #[instrument(skip(self))]
+    pub fn mint_aliases(&self) -> Result<Vec<MintUrl>, Error> {
+        Ok(self
+            .mint_aliases
+            .read()
+            .map_err(|_| Error::Internal)?
+            .clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_truncate_on_char_boundary() {
+        let mut value = "aé".to_string();
+        assert_eq!(truncate(&mut value, 2), Some(3));
+        assert_eq!(value, "a");
+
+        let mut value = "abc".to_string();
+        assert_eq!(truncate(&mut value, 3), None);
+        assert_eq!(value, "abc");
+    }
+}
@@ -2,48 +2,61 @@
 
 use std::collections::HashMap;
 use std::str::FromStr;
-use std::sync::Arc;
+use std::sync::{Arc, RwLock as StdRwLock};
 
 use cdk_common::database::{self, WalletDatabase};
 use cdk_common::subscription::Params;
 use getrandom::getrandom;
-use subscription::{ActiveSubscription, SubscriptionManager};
+use subscription::{ActiveSubscription, MiningShareQuoteSubscription, SubscriptionManager};
 #[cfg(feature = "auth")]
 use tokio::sync::RwLock;
 use tracing::instrument;
 use zeroize::Zeroize;
 
 use crate::amount::SplitTarget;
-use crate::dhke::construct_proofs;
 use crate::error::Error;
 use crate::fees::calculate_fee;
 use crate::mint_url::MintUrl;
 use crate::nuts::nut00::token::Token;
-use crate::nuts::nut17::Kind;
+use crate::nuts::nut17::{self, Filter, Kind};
 use crate::nuts::{
-    nut10, CurrencyUnit, Id, Keys, MintInfo, MintQuoteState, PreMintSecrets, Proof, Proofs,
-    RestoreRequest, SpendingConditions, State,
+    nut10, nut12, CurrencyUnit, Id, Keys, MintInfo, MintQuoteState, Proofs, PublicKey,
+    SpendingConditions,
 };
-use crate::types::ProofInfo;
+use crate::util::clock::Clock;
 use crate::util::unix_time;
+use crate::wallet::keysets::KeysetTrust;
+use crate::wallet::quote_state::QuoteStates;
 use crate::Amount;
 #[cfg(feature = "auth")]
 use crate::OidcClient;
 
 #[cfg(feature = "auth")]
 mod auth;
+mod auto_mint;
 mod balance;
 mod builder;
+mod consolidate;
+mod dead_letter;
+mod export;
 mod issue;
 mod keysets;
 mod melt;
 mod mint_connector;
+mod mint_info;
 pub mod multi_mint_wallet;
 mod proofs;
+mod quote_state;
+mod rebuild;
 mod receive;
+mod refresh;
+pub mod responses;
+mod restore;
 mod send;
+mod snapshot;
 #[cfg(not(target_arch = "wasm32"))]
 mod streams;
+mod strict;
 pub mod subscription;
 mod swap;
 mod transactions;
@@ -51,32 +64,55 @@ pub mod util;
 
 #[cfg(feature = "auth")]
 pub use auth::{AuthMintConnector, AuthWallet};
+pub use auto_mint::{AutoMintEvent, AutoMintHandle};
+pub use balance::BalanceOrigins;
 pub use builder::WalletBuilder;
 pub use cdk_common::wallet as types;
+pub use consolidate::{ConsolidateOptions, ConsolidateSummary};
+pub use dead_letter::MAX_DEAD_LETTERS;
+pub use export::{DisclosureLevel, ExportFilter, ExportedY, ProofExport, RedactedBucket};
+pub use issue::{
+    ExportedQuote, MintQuotesSummary, PendingOperationsSummary, PendingQuoteExport,
+    PENDING_QUOTE_EXPORT_VERSION,
+};
 #[cfg(feature = "auth")]
 pub use mint_connector::http_client::AuthHttpClient as BaseAuthHttpClient;
 pub use mint_connector::http_client::HttpClient as BaseHttpClient;
+pub use mint_connector::retry::{HttpClientOptions, RetryCondition};
+pub use mint_connector::scheduler::{
+    QueueDepth, RateLimit, RequestPriority, RequestScheduler, ScheduledMintConnector,
+};
 pub use mint_connector::transport::Transport as HttpTransport;
 #[cfg(feature = "auth")]
 pub use mint_connector::AuthHttpClient;
 pub use mint_connector::{HttpClient, MintConnector};
+pub use mint_info::{
+    Capability, MintInfoReport, MintInfoWarning, MAX_CONTACT_ENTRIES, MAX_CONTACT_FIELD_LEN,
+    MAX_MOTD_LEN,
+};
 pub use multi_mint_wallet::MultiMintWallet;
+pub use proofs::SelectionStrategy;
+pub use quote_state::{QuoteConflict, QuoteStateConflict, QuoteStateSource, QuoteStateUpdate};
+pub use rebuild::{RebuildOptions, RebuildSummary, ESTIMATED_METADATA_KEY, REBUILT_METADATA_KEY};
 pub use receive::ReceiveOptions;
+pub use refresh::{RefreshOptions, RefreshSummary};
+pub use restore::{RestoreOptions, RestoreUpdate};
 pub use send::{PreparedSend, SendMemo, SendOptions};
+pub use snapshot::{SnapshotId, SnapshotInfo, SnapshotPolicy};
 pub use types::{MeltQuote, MintQuote, SendKind};
 
-use crate::nuts::nut00::ProofsMethods;
-
 /// CDK Wallet
 ///
 /// The CDK [`Wallet`] is a high level cashu wallet.
 ///
-/// A [`Wallet`] is for a single mint and single unit.
+/// A [`Wallet`] is for a single mint. Operations use the default unit of the
+/// wallet, the `_with_unit` variants of issuance, keyset and balance
+/// operations work in any unit of the mint.
 #[derive(Debug, Clone)]
 pub struct Wallet {
     /// Mint Url
     pub mint_url: MintUrl,
-    /// Unit
+    /// Default unit
     pub unit: CurrencyUnit,
     /// Storage backend
     pub localstore: Arc<dyn WalletDatabase<Err = database::Error> + Send + Sync>,
@@ -87,12 +123,20 @@ pub struct Wallet {
     seed: [u8; 64],
     client: Arc<dyn MintConnector + Send + Sync>,
     subscription: SubscriptionManager,
+    require_payment_preimage: bool,
+    strict_amounts: bool,
+    selection_strategy: SelectionStrategy,
+    max_inputs: Option<usize>,
+    clock: Arc<dyn Clock>,
+    keyset_trust: Arc<StdRwLock<KeysetTrust>>,
+    quote_states: Arc<StdRwLock<QuoteStates>>,
+    mint_aliases: Arc<StdRwLock<Vec<MintUrl>>>,
 }
 
 const ALPHANUMERIC: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
 
 /// Wallet Subscription filter
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum WalletSubscription {
     /// Proof subscription
     ProofState(Vec<String>),
@@ -102,6 +146,45 @@ pub enum WalletSubscription {
     Bolt11MeltQuoteState(Vec<String>),
     /// Mint bolt12 quote subscription
     Bolt12MintQuoteState(Vec<String>),
+    /// Mining share mint quote subscription
+    MiningShareMintQuoteState(Vec<String>),
+}
+
+impl WalletSubscription {
+    /// Subscription of `kind` to typed filters
+    ///
+    /// The filters are sent in their wire format. Fails with
+    /// [`nut17::Error::InvalidFilters`] listing the filters `kind` does not
+    /// accept, which the mint would reject.
+    pub fn new(kind: Kind, filters: Vec<Filter<String>>) -> Result<Self, Error> {
+        let invalid: Vec<String> = filters
+            .iter()
+            .filter(|filter| !kind.accepts(filter))
+            .map(ToString::to_string)
+            .collect();
+        if !invalid.is_empty() {
+            return Err(nut17::Error::InvalidFilters {
+                kind,
+                filters: invalid,
+            }
+            .into());
+        }
+
+        let filters = filters.iter().map(ToString::to_string).collect();
+
+        Ok(match kind {
+            Kind::ProofState => Self::ProofState(filters),
+            Kind::Bolt11MintQuote => Self::Bolt11MintQuoteState(filters),
+            Kind::Bolt11MeltQuote => Self::Bolt11MeltQuoteState(filters),
+            Kind::Bolt12MintQuote => Self::Bolt12MintQuoteState(filters),
+            Kind::MiningShareMintQuote => Self::MiningShareMintQuoteState(filters),
+        })
+    }
+
+    /// Subscription to the states of the proofs with the given `Y`s
+    pub fn proof_states(ys: impl IntoIterator<Item = PublicKey>) -> Self {
+        Self::ProofState(ys.into_iter().map(|y| y.to_string()).collect())
+    }
 }
 
 impl From<WalletSubscription> for Params {
@@ -139,6 +222,11 @@ impl From<WalletSubscription> for Params {
                 kind: Kind::Bolt12MintQuote,
                 id: id.into(),
             },
+            WalletSubscription::MiningShareMintQuoteState(filters) => Params {
+                filters,
+                kind: Kind::MiningShareMintQuote,
+                id: id.into(),
+            },
         }
     }
 }
@@ -195,6 +283,21 @@ impl Wallet {
             .await
     }
 
+    /// Subscribe to state changes of mining share mint quotes
+    ///
+    /// Uses the NUT-17 websocket of the mint when it is supported and polls
+    /// otherwise. The subscription is renewed under the same id when the
+    /// websocket reconnects.
+    pub async fn subscribe_mining_share_quotes(
+        &self,
+        quote_ids: Vec<String>,
+    ) -> MiningShareQuoteSubscription {
+        MiningShareQuoteSubscription::new(
+            self.subscribe(WalletSubscription::MiningShareMintQuoteState(quote_ids))
+                .await,
+        )
+    }
+
     /// Fee required for proof set
     #[instrument(skip_all)]
     pub async fn get_proofs_fee(&self, proofs: &Proofs) -> Result<Amount, Error> {
@@ -256,8 +359,23 @@ impl Wallet {
     /// Query mint for current mint information
     #[instrument(skip(self))]
     pub async fn fetch_mint_info(&self) -> Result<Option<MintInfo>, Error> {
+        Ok(self
+            .fetch_mint_info_with_report()
+            .await?
+            .map(|(mint_info, _)| mint_info))
+    }
+
+    /// Query mint for current mint information and report inconsistencies
+    ///
+    /// The info is stored under the wallet mint URL even if the mint
+    /// advertises other URLs, see [`Wallet::mint_aliases`], and with its free
+    /// text fields bounded.
+    #[instrument(skip(self))]
+    pub async fn fetch_mint_info_with_report(
+        &self,
+    ) -> Result<Option<(MintInfo, MintInfoReport)>, Error> {
         match self.client.get_mint_info().await {
-            Ok(mint_info) => {
+            Ok(mut mint_info) => {
                 // If mint provides time make sure it is accurate
                 if let Some(mint_unix_time) = mint_info.time {
                     let current_unix_time = unix_time();
@@ -271,6 +389,8 @@ impl Wallet {
                     }
                 }
 
+                let report = self.check_mint_info(&mut mint_info).await?;
+
                 // Create or update auth wallet
                 #[cfg(feature = "auth")]
                 {
@@ -314,7 +434,7 @@ impl Wallet {
 
                 tracing::trace!("Mint info updated for {}", self.mint_url);
 
-                Ok(Some(mint_info))
+                Ok(Some((mint_info, report)))
             }
             Err(err) => {
                 tracing::warn!("Could not get mint info {}", err);
@@ -378,114 +498,6 @@ impl Wallet {
         Ok(SplitTarget::Values(values))
     }
 
-    /// Restore
-    #[instrument(skip(self))]
-    pub async fn restore(&self) -> Result<Amount, Error> {
-        // Check that mint is in store of mints
-        if self
-            .localstore
-            .get_mint(self.mint_url.clone())
-            .await?
-            .is_none()
-        {
-            self.fetch_mint_info().await?;
-        }
-
-        let keysets = self.load_mint_keysets().await?;
-
-        let mut restored_value = Amount::ZERO;
-
-        for keyset in keysets {
-            let keys = self.load_keyset_keys(keyset.id).await?;
-            let mut empty_batch = 0;
-            let mut start_counter = 0;
-
-            while empty_batch.lt(&3) {
-                let premint_secrets = PreMintSecrets::restore_batch(
-                    keyset.id,
-                    &self.seed,
-                    start_counter,
-                    start_counter + 100,
-                )?;
-
-                tracing::debug!(
-                    "Attempting to restore counter {}-{} for mint {} keyset {}",
-                    start_counter,
-                    start_counter + 100,
-                    self.mint_url,
-                    keyset.id
-                );
-
-                let restore_request = RestoreRequest {
-                    outputs: premint_secrets.blinded_messages(),
-                };
-
-                let response = self.client.post_restore(restore_request).await?;
-
-                if response.signatures.is_empty() {
-                    empty_batch += 1;
-                    start_counter += 100;
-                    continue;
-                }
-
-                let premint_secrets: Vec<_> = premint_secrets
-                    .secrets
-                    .iter()
-                    .filter(|p| response.outputs.contains(&p.blinded_message))
-                    .collect();
-
-                // the response outputs and premint secrets should be the same after filtering
-                // blinded messages the mint did not have signatures for
-                assert_eq!(response.outputs.len(), premint_secrets.len());
-
-                let proofs = construct_proofs(
-                    response.signatures,
-                    premint_secrets.iter().map(|p| p.r.clone()).collect(),
-                    premint_secrets.iter().map(|p| p.secret.clone()).collect(),
-                    &keys,
-                )?;
-
-                tracing::debug!("Restored {} proofs", proofs.len());
-
-                self.localstore
-                    .increment_keyset_counter(&keyset.id, proofs.len() as u32)
-                    .await?;
-
-                let states = self.check_proofs_spent(proofs.clone()).await?;
-
-                let unspent_proofs: Vec<Proof> = proofs
-                    .iter()
-                    .zip(states)
-                    .filter(|(_, state)| !state.state.eq(&State::Spent))
-                    .map(|(p, _)| p)
-                    .cloned()
-                    .collect();
-
-                restored_value += unspent_proofs.total_amount()?;
-
-                let unspent_proofs = unspent_proofs
-                    .into_iter()
-                    .map(|proof| {
-                        ProofInfo::new(
-                            proof,
-                            self.mint_url.clone(),
-                            State::Unspent,
-                            keyset.unit.clone(),
-                        )
-                    })
-                    .collect::<Result<Vec<ProofInfo>, _>>()?;
-
-                self.localstore
-                    .update_proofs(unspent_proofs, vec![])
-                    .await?;
-
-                empty_batch = 0;
-                start_counter += 100;
-            }
-        }
-        Ok(restored_value)
-    }
-
     /// Verify all proofs in token have meet the required spend
     /// Can be used to allow a wallet to accept payments offline while reducing
     /// the risk of claiming back to the limits let by the spending_conditions
@@ -641,7 +653,25 @@ impl Wallet {
         // We need the keysets information to properly convert from token proof to proof
         let keysets_info = self.load_mint_keysets().await?;
         let proofs = token.proofs(&keysets_info)?;
-        for proof in proofs {
+
+        self.verify_proofs_dleq(&proofs, true)
+            .await
+            .map_err(|err| match err {
+                Error::InvalidProofDleq(_) => Error::CouldNotVerifyDleq,
+                err => err,
+            })
+    }
+
+    /// Verify the DLEQ proofs of `proofs` against the keys of their keysets
+    ///
+    /// Keys are loaded once per keyset. A proof without a DLEQ proof only fails
+    /// the check if `strict` is set. On failure [`Error::InvalidProofDleq`]
+    /// carries the index of the first proof that did not verify.
+    #[instrument(skip_all)]
+    pub async fn verify_proofs_dleq(&self, proofs: &Proofs, strict: bool) -> Result<(), Error> {
+        let mut keys_cache: HashMap<Id, Keys> = HashMap::new();
+
+        for (index, proof) in proofs.iter().enumerate() {
             let mint_pubkey = match keys_cache.get(&proof.keyset_id) {
                 Some(keys) => keys.amount_key(proof.amount),
                 None => {
@@ -655,9 +685,11 @@ impl Wallet {
             }
             .ok_or(Error::AmountKey)?;
 
-            proof
-                .verify_dleq(mint_pubkey)
-                .map_err(|_| Error::CouldNotVerifyDleq)?;
+            match proof.verify_dleq(mint_pubkey) {
+                Ok(_) => (),
+                Err(nut12::Error::MissingDleqProof) if !strict => (),
+                Err(_) => return Err(Error::InvalidProofDleq(index)),
+            }
         }
 
         Ok(())
@@ -669,3 +701,49 @@ impl Drop for Wallet {
         self.seed.zeroize();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PUBKEY: &str = "02194603ffa36356f4a56b7df9371fc3192472351453ec7398b8da8117e7c3e104";
+    const QUOTE_ID: &str = "5a5c0f0b-3e2f-4a51-9f0e-6b1c1f2d8e41";
+
+    #[test]
+    fn test_subscription_from_typed_filters() {
+        let subscription = WalletSubscription::new(
+            Kind::Bolt11MintQuote,
+            vec![Filter::QuoteId(QUOTE_ID.to_string())],
+        )
+        .unwrap();
+        assert_eq!(
+            subscription,
+            WalletSubscription::Bolt11MintQuoteState(vec![QUOTE_ID.to_string()])
+        );
+
+        let y = PublicKey::from_str(PUBKEY).unwrap();
+        assert_eq!(
+            WalletSubscription::new(Kind::ProofState, vec![Filter::ProofY(PUBKEY.to_string())])
+                .unwrap(),
+            WalletSubscription::proof_states([y])
+        );
+    }
+
+    #[test]
+    fn test_subscription_rejects_mismatched_filters() {
+        let err = WalletSubscription::new(
+            Kind::ProofState,
+            vec![
+                Filter::ProofY(PUBKEY.to_string()),
+                Filter::QuoteId(QUOTE_ID.to_string()),
+            ],
+        )
+        .unwrap_err();
+
+        assert!(matches!(
+            err,
+            Error::NUT17(nut17::Error::InvalidFilters { kind: Kind::ProofState, filters })
+                if filters == vec![QUOTE_ID.to_string()]
+        ));
+    }
+}
@@ -209,12 +209,12 @@ impl MultiMintWallet {
                     .get(&wallet_key)
                     .ok_or(Error::UnknownWallet(wallet_key.clone()))?;
 
-                let amount = wallet.check_all_mint_quotes().await?;
-                amount_minted.insert(wallet.unit.clone(), amount);
+                let summary = wallet.check_all_mint_quotes().await?;
+                amount_minted.insert(wallet.unit.clone(), summary.total_minted);
             }
             None => {
                 for (_, wallet) in self.wallets.read().await.iter() {
-                    let amount = wallet.check_all_mint_quotes().await?;
+                    let amount = wallet.check_all_mint_quotes().await?.total_minted;
 
                     amount_minted
                         .entry(wallet.unit.clone())
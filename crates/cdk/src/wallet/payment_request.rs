@@ -58,7 +58,7 @@ impl Wallet {
             });
 
         let prepared_send = self
-            .prepare_send(
+            .create_send(
                 amount,
                 SendOptions {
                     include_fee: true,
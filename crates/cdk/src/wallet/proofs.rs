@@ -11,8 +11,22 @@ use crate::nuts::{
     CheckStateRequest, Proof, ProofState, Proofs, PublicKey, SpendingConditions, State,
 };
 use crate::types::ProofInfo;
+use crate::wallet::{RequestPriority, RequestScheduler};
 use crate::{ensure_cdk, Amount, Error, Wallet};
 
+/// Order in which proofs are selected as inputs of sends, swaps and melts
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum SelectionStrategy {
+    /// Take the largest proofs first, using as few inputs as possible
+    LargestFirst,
+    /// Take the smallest proofs first, spending small proofs before large ones
+    SmallestFirst,
+    /// Match the power of two split of the amount, falling back to the closest
+    /// larger proofs
+    #[default]
+    ExactMatchPreferred,
+}
+
 impl Wallet {
     /// Get unspent proofs for mint
     #[instrument(skip(self))]
@@ -126,8 +140,16 @@ impl Wallet {
     }
 
     /// Checks pending proofs for spent status
+    ///
+    /// Requests are scheduled as [`RequestPriority::Background`].
     #[instrument(skip(self))]
     pub async fn check_all_pending_proofs(&self) -> Result<Amount, Error> {
+        RequestScheduler::with_priority(RequestPriority::Background, self.sync_pending_proofs())
+            .await
+    }
+
+    /// Settle pending proofs the mint knows are spent
+    async fn sync_pending_proofs(&self) -> Result<Amount, Error> {
         let mut balance = Amount::ZERO;
 
         let proofs = self
@@ -191,8 +213,35 @@ impl Wallet {
         keyset_fees: &HashMap<Id, u64>,
         include_fees: bool,
     ) -> Result<(Proofs, Option<(Proof, Amount)>), Error> {
-        let mut input_proofs =
-            Self::select_proofs(amount, proofs, active_keyset_ids, keyset_fees, include_fees)?;
+        Self::select_exact_proofs_with_strategy(
+            amount,
+            proofs,
+            active_keyset_ids,
+            keyset_fees,
+            include_fees,
+            SelectionStrategy::default(),
+        )
+    }
+
+    /// Select exact proofs in the order of `strategy`
+    ///
+    /// See [`Wallet::select_exact_proofs`].
+    pub fn select_exact_proofs_with_strategy(
+        amount: Amount,
+        proofs: Proofs,
+        active_keyset_ids: &Vec<Id>,
+        keyset_fees: &HashMap<Id, u64>,
+        include_fees: bool,
+        strategy: SelectionStrategy,
+    ) -> Result<(Proofs, Option<(Proof, Amount)>), Error> {
+        let mut input_proofs = Self::select_proofs_with_strategy(
+            amount,
+            proofs,
+            active_keyset_ids,
+            keyset_fees,
+            include_fees,
+            strategy,
+        )?;
         let mut exchange = None;
 
         // How much amounts do we have selected in our proof sets?
@@ -233,6 +282,81 @@ impl Wallet {
         Ok((input_proofs, exchange))
     }
 
+    /// Select proofs in the order of `strategy`
+    ///
+    /// [`SelectionStrategy::ExactMatchPreferred`] is [`Wallet::select_proofs`].
+    /// The other strategies take proofs of inactive keysets first, then the
+    /// active ones sorted by amount, until the amount and, when `include_fees`
+    /// is set, the fees of the selected proofs are covered.
+    #[instrument(skip_all)]
+    pub fn select_proofs_with_strategy(
+        amount: Amount,
+        proofs: Proofs,
+        active_keyset_ids: &Vec<Id>,
+        keyset_fees: &HashMap<Id, u64>,
+        include_fees: bool,
+        strategy: SelectionStrategy,
+    ) -> Result<Proofs, Error> {
+        let largest_first = match strategy {
+            SelectionStrategy::ExactMatchPreferred => {
+                return Self::select_proofs(
+                    amount,
+                    proofs,
+                    active_keyset_ids,
+                    keyset_fees,
+                    include_fees,
+                )
+            }
+            SelectionStrategy::LargestFirst => true,
+            SelectionStrategy::SmallestFirst => false,
+        };
+
+        if amount == Amount::ZERO {
+            return Ok(vec![]);
+        }
+        ensure_cdk!(proofs.total_amount()? >= amount, Error::InsufficientFunds);
+
+        let (mut inactive, mut active): (Proofs, Proofs) = proofs
+            .into_iter()
+            .partition(|p| !p.is_active(active_keyset_ids));
+        for proofs in [&mut inactive, &mut active] {
+            match largest_first {
+                true => proofs.sort_by(|a, b| a.cmp(b).reverse()),
+                false => proofs.sort(),
+            }
+        }
+
+        let mut selected_proofs = Proofs::new();
+        let mut selected_amount = Amount::ZERO;
+        let mut fee_ppk: u64 = 0;
+        let needed = |fee_ppk: u64| match include_fees {
+            true => amount.checked_add(fee_ppk.div_ceil(1000).into()),
+            false => Some(amount),
+        };
+
+        for proof in inactive.into_iter().chain(active) {
+            if needed(fee_ppk).is_some_and(|needed| selected_amount >= needed) {
+                break;
+            }
+
+            fee_ppk = fee_ppk.saturating_add(
+                keyset_fees
+                    .get(&proof.keyset_id)
+                    .copied()
+                    .unwrap_or_default(),
+            );
+            selected_amount = selected_amount
+                .checked_add(proof.amount)
+                .ok_or(Error::AmountOverflow)?;
+            selected_proofs.push(proof);
+        }
+
+        let needed = needed(fee_ppk).ok_or(Error::AmountOverflow)?;
+        ensure_cdk!(selected_amount >= needed, Error::InsufficientFunds);
+
+        Ok(selected_proofs)
+    }
+
     /// Select proofs
     #[instrument(skip_all)]
     pub fn select_proofs(
@@ -483,6 +607,7 @@ mod tests {
     use cdk_common::secret::Secret;
     use cdk_common::{Amount, Id, Proof, PublicKey};
 
+    use super::SelectionStrategy;
     use crate::Wallet;
 
     fn id() -> Id {
@@ -615,4 +740,57 @@ mod tests {
         assert_eq!(selected_proofs.len(), 1);
         assert_eq!(selected_proofs[0].amount, 32.into());
     }
+
+    #[test]
+    fn test_select_proofs_by_strategy() {
+        let proofs = vec![proof(16), proof(1), proof(8), proof(2), proof(4)];
+        let select = |strategy| {
+            let mut selected = Wallet::select_proofs_with_strategy(
+                10.into(),
+                proofs.clone(),
+                &vec![id()],
+                &HashMap::new(),
+                false,
+                strategy,
+            )
+            .unwrap();
+            selected.sort();
+            selected
+                .iter()
+                .map(|p| p.amount.into())
+                .collect::<Vec<u64>>()
+        };
+
+        assert_eq!(select(SelectionStrategy::LargestFirst), vec![16]);
+        assert_eq!(select(SelectionStrategy::SmallestFirst), vec![1, 2, 4, 8]);
+        assert_eq!(select(SelectionStrategy::ExactMatchPreferred), vec![2, 8]);
+    }
+
+    #[test]
+    fn test_select_proofs_smallest_first_with_fees() {
+        let proofs = (0..10).map(|_| proof(1)).collect::<Vec<_>>();
+        let mut keyset_fees = HashMap::new();
+        keyset_fees.insert(id(), 100);
+
+        let selected_proofs = Wallet::select_proofs_with_strategy(
+            3.into(),
+            proofs.clone(),
+            &vec![id()],
+            &keyset_fees,
+            true,
+            SelectionStrategy::SmallestFirst,
+        )
+        .unwrap();
+        assert_eq!(selected_proofs.len(), 4);
+
+        let selected_proofs = Wallet::select_proofs_with_strategy(
+            10.into(),
+            proofs,
+            &vec![id()],
+            &keyset_fees,
+            true,
+            SelectionStrategy::SmallestFirst,
+        );
+        assert!(selected_proofs.is_err());
+    }
 }
@@ -0,0 +1,408 @@
+//! Mint quote state consistency
+//!
+//! Quote states reach the wallet from websocket notifications and from HTTP
+//! status requests, and a lagging mint replica can answer with a state the
+//! wallet has already seen the quote leave. Quote states only move forward,
+//! so writes that would move a quote backwards are rejected and recorded as
+//! conflicts.
+//!
+//! A quote the mint reports issued without this wallet holding its proofs
+//! was minted elsewhere, e.g. by another wallet sharing the seed, see
+//! [`Wallet::reconcile_issued_quote`].
+
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+
+use cdk_common::amount::SplitTarget;
+use cdk_common::wallet::TransactionDirection;
+use tracing::instrument;
+
+use crate::nuts::nut00::ProofsMethods;
+use crate::nuts::{MintQuoteState, NotificationPayload, PaymentMethod, PreMintSecrets, State};
+use crate::types::ProofSource;
+use crate::wallet::issue::{conversion_metadata, IssueContext, QuoteCleanup};
+use crate::wallet::MintQuote;
+use crate::{Amount, Error, Wallet};
+
+/// Number of conflicts kept for [`Wallet::quote_state_conflicts`]
+const MAX_QUOTE_STATE_CONFLICTS: usize = 100;
+
+/// Where a quote state was observed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum QuoteStateSource {
+    /// HTTP quote status request
+    Http,
+    /// Websocket notification
+    Websocket,
+    /// Operation of the wallet itself
+    Wallet,
+}
+
+impl fmt::Display for QuoteStateSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Http => write!(f, "http"),
+            Self::Websocket => write!(f, "websocket"),
+            Self::Wallet => write!(f, "wallet"),
+        }
+    }
+}
+
+/// Quote state write rejected because it would move the quote backwards
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuoteStateConflict {
+    /// Quote id
+    pub quote_id: String,
+    /// State kept
+    pub stored_state: MintQuoteState,
+    /// Source of the state kept, if observed by this wallet instance
+    pub stored_source: Option<QuoteStateSource>,
+    /// Unix time the state kept was observed, if observed by this wallet instance
+    pub stored_at: Option<u64>,
+    /// State rejected
+    pub rejected_state: MintQuoteState,
+    /// Source of the state rejected
+    pub rejected_source: QuoteStateSource,
+    /// Unix time the state rejected was observed
+    pub rejected_at: u64,
+}
+
+/// Conflict between the wallet and the mint found checking a quote state
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QuoteConflict {
+    /// Mint answered with a state the quote already left, the stored state
+    /// is kept
+    Regression(QuoteStateConflict),
+    /// Mint reports the quote issued but the wallet recorded no mint of it
+    IssuedElsewhere {
+        /// Quote id
+        quote_id: String,
+        /// State the wallet had stored
+        stored_state: MintQuoteState,
+    },
+}
+
+/// Quote status response with the conflict found storing its state
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuoteStateUpdate<T> {
+    /// Response of the mint, with the state kept by the wallet
+    pub response: T,
+    /// Conflict with the state stored by the wallet
+    pub conflict: Option<QuoteConflict>,
+}
+
+/// Last accepted observation of each quote and recent conflicts
+#[derive(Debug, Default)]
+pub(crate) struct QuoteStates {
+    observed: HashMap<String, (QuoteStateSource, u64)>,
+    conflicts: VecDeque<QuoteStateConflict>,
+}
+
+/// Position of a state in the life of a quote
+fn state_rank(state: MintQuoteState) -> u8 {
+    match state {
+        MintQuoteState::Unpaid => 0,
+        MintQuoteState::Paid => 1,
+        MintQuoteState::Issued | MintQuoteState::Converted | MintQuoteState::Cancelled => 2,
+    }
+}
+
+/// Whether moving a quote from `stored` to `new` goes backwards
+///
+/// Terminal states are never left, not even for another terminal state.
+fn is_regression(stored: MintQuoteState, new: MintQuoteState) -> bool {
+    stored != new && (state_rank(stored) == 2 || state_rank(new) < state_rank(stored))
+}
+
+impl Wallet {
+    /// Store a quote state unless it moves the quote backwards
+    ///
+    /// Returns the quote with the state kept. A rejected state is logged and
+    /// recorded, see [`Wallet::quote_state_conflicts`].
+    pub(crate) async fn update_mint_quote_state(
+        &self,
+        quote: MintQuote,
+        state: MintQuoteState,
+        source: QuoteStateSource,
+    ) -> Result<MintQuote, Error> {
+        let (quote, _) = self.store_mint_quote_state(quote, state, source).await?;
+
+        Ok(quote)
+    }
+
+    /// Store a quote state from the mint and report conflicts with the wallet
+    ///
+    /// Besides regressions, a quote moving to issued without an incoming
+    /// transaction of this wallet for it is reported as
+    /// [`QuoteConflict::IssuedElsewhere`].
+    pub(crate) async fn check_mint_quote_state(
+        &self,
+        quote: MintQuote,
+        state: MintQuoteState,
+        source: QuoteStateSource,
+    ) -> Result<(MintQuote, Option<QuoteConflict>), Error> {
+        let stored_state = quote.state;
+        let (quote, regression) = self.store_mint_quote_state(quote, state, source).await?;
+
+        if let Some(conflict) = regression {
+            return Ok((quote, Some(QuoteConflict::Regression(conflict))));
+        }
+
+        if stored_state != MintQuoteState::Issued
+            && quote.state == MintQuoteState::Issued
+            && !self.has_mint_transaction(&quote.id).await?
+        {
+            tracing::warn!(
+                "Quote {} issued by the mint but not minted by this wallet, was {}",
+                quote.id,
+                stored_state
+            );
+
+            let conflict = QuoteConflict::IssuedElsewhere {
+                quote_id: quote.id.clone(),
+                stored_state,
+            };
+            return Ok((quote, Some(conflict)));
+        }
+
+        Ok((quote, None))
+    }
+
+    /// Whether the wallet recorded an incoming transaction minting `quote_id`
+    async fn has_mint_transaction(&self, quote_id: &str) -> Result<bool, Error> {
+        Ok(self
+            .localstore
+            .list_transactions(
+                Some(self.mint_url.clone()),
+                Some(TransactionDirection::Incoming),
+                None,
+            )
+            .await?
+            .iter()
+            .any(|transaction| transaction.quote_id.as_deref() == Some(quote_id)))
+    }
+
+    /// Store a quote state unless it moves the quote backwards, returning the
+    /// conflict recorded when it does
+    async fn store_mint_quote_state(
+        &self,
+        mut quote: MintQuote,
+        state: MintQuoteState,
+        source: QuoteStateSource,
+    ) -> Result<(MintQuote, Option<QuoteStateConflict>), Error> {
+        let now = self.clock.now_unix();
+
+        if is_regression(quote.state, state) {
+            let mut quote_states = self.quote_states.write().map_err(|_| Error::Internal)?;
+            let stored = quote_states.observed.get(&quote.id).copied();
+
+            tracing::warn!(
+                "Quote {} state conflict: keeping {} from {}, rejecting {} from {}",
+                quote.id,
+                quote.state,
+                stored
+                    .map(|(source, _)| source.to_string())
+                    .unwrap_or_else(|| "store".to_string()),
+                state,
+                source
+            );
+
+            let conflict = QuoteStateConflict {
+                quote_id: quote.id.clone(),
+                stored_state: quote.state,
+                stored_source: stored.map(|(source, _)| source),
+                stored_at: stored.map(|(_, observed_at)| observed_at),
+                rejected_state: state,
+                rejected_source: source,
+                rejected_at: now,
+            };
+
+            if quote_states.conflicts.len() == MAX_QUOTE_STATE_CONFLICTS {
+                quote_states.conflicts.pop_front();
+            }
+            quote_states.conflicts.push_back(conflict.clone());
+
+            return Ok((quote, Some(conflict)));
+        }
+
+        quote.state = state;
+        self.localstore.add_mint_quote(quote.clone()).await?;
+
+        self.quote_states
+            .write()
+            .map_err(|_| Error::Internal)?
+            .observed
+            .insert(quote.id.clone(), (source, now));
+
+        Ok((quote, None))
+    }
+
+    /// Try to recover the proofs of a quote the mint issued elsewhere
+    ///
+    /// The outputs are first derived again from the premint counter stored
+    /// with the quote and the mint asked for their signatures, which finds
+    /// proofs minted by another wallet sharing the seed from the same
+    /// counter. Failing that, the wallet is restored from its seed.
+    ///
+    /// Returns the unspent amount recovered, zero when nothing was found:
+    /// the proofs were then minted with another seed or already spent.
+    #[instrument(skip(self))]
+    pub async fn reconcile_issued_quote(&self, quote_id: &str) -> Result<Amount, Error> {
+        let quote = self
+            .localstore
+            .get_mint_quote(quote_id)
+            .await?
+            .ok_or(Error::UnknownQuote)?;
+
+        if let (Some(keyset_id), Some(counter), Some(amount)) =
+            (quote.premint_keyset_id, quote.premint_counter, quote.amount)
+        {
+            let (amount, split_target) = match quote.premint_amounts.clone() {
+                Some(amounts) => (
+                    Amount::try_sum(amounts.iter().copied())?,
+                    SplitTarget::Values(amounts),
+                ),
+                None => (amount, SplitTarget::default()),
+            };
+            let premint_secrets =
+                PreMintSecrets::from_seed(keyset_id, counter, &self.seed, amount, &split_target)?;
+
+            if let Some(signatures) = self.issued_signatures(&premint_secrets).await? {
+                let (source, cleanup) = match quote.payment_method {
+                    PaymentMethod::MiningShare => (
+                        ProofSource::MiningShareMint,
+                        QuoteCleanup::AddIssuedUntilPaid,
+                    ),
+                    PaymentMethod::Bolt12 => (ProofSource::Bolt12Mint, QuoteCleanup::AddIssued),
+                    _ => (ProofSource::Bolt11Mint, QuoteCleanup::Remove),
+                };
+
+                let proofs = self
+                    .finalize_issued_proofs(
+                        signatures,
+                        &premint_secrets,
+                        keyset_id,
+                        IssueContext {
+                            quote_id: quote.id.clone(),
+                            unit: quote.unit.clone(),
+                            cleanup,
+                            source,
+                            metadata: conversion_metadata(quote.converted_from.as_deref()),
+                        },
+                    )
+                    .await?;
+
+                // The other wallet may have spent them already
+                let states = self.check_proofs_spent(proofs.clone()).await?;
+                let unspent = proofs
+                    .into_iter()
+                    .zip(states)
+                    .filter(|(_, state)| state.state != State::Spent)
+                    .map(|(proof, _)| proof)
+                    .collect::<Vec<_>>();
+                let amount = unspent.total_amount()?;
+
+                tracing::info!(
+                    "Recovered {} of quote {} from its premint counter",
+                    amount,
+                    quote_id
+                );
+                return Ok(amount);
+            }
+        }
+
+        tracing::info!(
+            "Outputs of quote {} not found at its premint counter, restoring from seed",
+            quote_id
+        );
+        self.restore().await
+    }
+
+    /// Store the quote state carried by a notification
+    ///
+    /// Called for every notification received by a websocket subscription,
+    /// notifications for other payloads are ignored. A terminal state for a
+    /// quote the wallet does not store is ignored as well, the wallet removes
+    /// quotes once they are done. Fails with [`Error::UnknownQuote`] for a
+    /// quote the wallet does not store in any other state and with
+    /// [`Error::UnknownKeySet`] for a mining share quote in a keyset the wallet
+    /// does not know.
+    pub async fn apply_quote_notification(
+        &self,
+        payload: &NotificationPayload<String>,
+    ) -> Result<(), Error> {
+        let (quote_id, state) = match payload {
+            NotificationPayload::MintQuoteBolt11Response(response) => {
+                (&response.quote, response.state)
+            }
+            NotificationPayload::MintQuoteMiningShareResponse(response) => {
+                (&response.quote, response.state.into())
+            }
+            _ => return Ok(()),
+        };
+
+        let quote = match self.localstore.get_mint_quote(quote_id).await? {
+            Some(quote) => quote,
+            None if state_rank(state) == state_rank(MintQuoteState::Issued) => {
+                tracing::debug!(
+                    "Ignoring {} notification for unknown quote {}",
+                    state,
+                    quote_id
+                );
+                return Ok(());
+            }
+            None => return Err(Error::UnknownQuote),
+        };
+
+        if let NotificationPayload::MintQuoteMiningShareResponse(response) = payload {
+            self.localstore
+                .get_keyset_by_id(&response.keyset_id)
+                .await?
+                .ok_or(Error::UnknownKeySet)?;
+        }
+
+        self.update_mint_quote_state(quote, state, QuoteStateSource::Websocket)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Recent quote state writes rejected because they would have moved a
+    /// quote backwards, oldest first
+    ///
+    /// Conflicts point at a mint answering from stale state.
+    pub fn quote_state_conflicts(&self) -> Result<Vec<QuoteStateConflict>, Error> {
+        Ok(self
+            .quote_states
+            .read()
+            .map_err(|_| Error::Internal)?
+            .conflicts
+            .iter()
+            .cloned()
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quote_state_regression() {
+        use MintQuoteState::*;
+
+        assert!(!is_regression(Unpaid, Paid));
+        assert!(!is_regression(Paid, Issued));
+        assert!(!is_regression(Unpaid, Issued));
+        assert!(!is_regression(Unpaid, Converted));
+        assert!(!is_regression(Paid, Cancelled));
+        assert!(!is_regression(Issued, Issued));
+
+        assert!(is_regression(Paid, Unpaid));
+        assert!(is_regression(Issued, Paid));
+        assert!(is_regression(Issued, Unpaid));
+        assert!(is_regression(Converted, Issued));
+        assert!(is_regression(Issued, Converted));
+        assert!(is_regression(Cancelled, Paid));
+    }
+}
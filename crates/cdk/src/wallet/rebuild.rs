@@ -0,0 +1,283 @@
+//! Transaction history rebuild
+//!
+//! Reconstructs transaction records lost to a partially corrupted store from
+//! what the wallet still has: issued mint quotes, paid melt quotes and spent
+//! proofs. Proofs carry no provenance, so records rebuilt from quotes get a
+//! placeholder Y derived from the quote id, and spent proofs no transaction
+//! accounts for are grouped into a single estimated outgoing record.
+//!
+//! Surviving records are never overwritten, and running the rebuild again adds
+//! nothing new.
+
+use std::collections::{HashMap, HashSet};
+
+use tracing::instrument;
+
+use crate::dhke::hash_to_curve;
+use crate::nuts::nut00::ProofsMethods;
+use crate::nuts::{MeltQuoteState, PublicKey, State};
+use crate::util::unix_time;
+use crate::wallet::types::{Transaction, TransactionDirection};
+use crate::{Amount, Error, Wallet};
+
+/// Metadata key set on every rebuilt transaction
+pub const REBUILT_METADATA_KEY: &str = "rebuilt";
+/// Metadata key set on rebuilt transactions whose amount is an estimate
+pub const ESTIMATED_METADATA_KEY: &str = "estimated";
+
+/// Options for [`Wallet::rebuild_transactions`]
+#[derive(Debug, Clone)]
+pub struct RebuildOptions {
+    /// Group spent proofs no transaction accounts for into an outgoing estimate
+    pub estimate_outgoing: bool,
+    /// Report what would be rebuilt without storing it
+    pub dry_run: bool,
+}
+
+impl Default for RebuildOptions {
+    fn default() -> Self {
+        Self {
+            estimate_outgoing: true,
+            dry_run: false,
+        }
+    }
+}
+
+impl RebuildOptions {
+    /// Group spent proofs no transaction accounts for into an outgoing estimate
+    pub fn estimate_outgoing(mut self, estimate_outgoing: bool) -> Self {
+        self.estimate_outgoing = estimate_outgoing;
+        self
+    }
+
+    /// Report what would be rebuilt without storing it
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+}
+
+/// Outcome of a transaction history rebuild
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RebuildSummary {
+    /// Surviving transactions left as they were
+    pub kept: usize,
+    /// Incoming transactions rebuilt from issued mint quotes
+    pub from_mint_quotes: usize,
+    /// Outgoing transactions rebuilt from paid melt quotes
+    pub from_melt_quotes: usize,
+    /// Outgoing estimates rebuilt from spent proofs
+    pub from_spent_proofs: usize,
+    /// Value held beyond what the history accounts for, such as received tokens
+    pub unattributed_incoming: Amount,
+    /// Value the history accounts for beyond what is held
+    pub unattributed_outgoing: Amount,
+    /// Transactions rebuilt
+    pub transactions: Vec<Transaction>,
+}
+
+/// Placeholder Y for a transaction rebuilt from a quote
+///
+/// Keeps the ids of rebuilt transactions distinct and stable across rebuilds.
+fn quote_placeholder_y(
+    direction: TransactionDirection,
+    quote_id: &str,
+) -> Result<PublicKey, Error> {
+    Ok(hash_to_curve(
+        format!("cdk_rebuilt_transaction:{direction}:{quote_id}").as_bytes(),
+    )?)
+}
+
+/// Metadata of a rebuilt transaction
+fn rebuilt_metadata(estimated: bool) -> HashMap<String, String> {
+    let mut metadata = HashMap::from([(REBUILT_METADATA_KEY.to_string(), "true".to_string())]);
+    if estimated {
+        metadata.insert(ESTIMATED_METADATA_KEY.to_string(), "true".to_string());
+    }
+    metadata
+}
+
+impl Wallet {
+    /// Rebuild the transaction history with default [`RebuildOptions`]
+    #[instrument(skip(self))]
+    pub async fn rebuild_transactions(&self) -> Result<RebuildSummary, Error> {
+        self.rebuild_transactions_with_options(RebuildOptions::default())
+            .await
+    }
+
+    /// Rebuild missing transactions from quotes and proofs
+    ///
+    /// Issued mint quotes become incoming transactions for the amount issued,
+    /// and paid melt quotes outgoing transactions for the amount melted, with
+    /// the fee unknown. Quotes the wallet removed after completing them cannot
+    /// be rebuilt. Spent and pending spent proofs no transaction accounts for
+    /// are grouped into one outgoing transaction marked as estimated.
+    #[instrument(skip(self, opts))]
+    pub async fn rebuild_transactions_with_options(
+        &self,
+        opts: RebuildOptions,
+    ) -> Result<RebuildSummary, Error> {
+        let existing = self
+            .localstore
+            .list_transactions(Some(self.mint_url.clone()), None, Some(self.unit.clone()))
+            .await?;
+
+        let covered_quotes: HashSet<&str> = existing
+            .iter()
+            .filter_map(|transaction| transaction.quote_id.as_deref())
+            .collect();
+        let covered_ys: HashSet<PublicKey> = existing
+            .iter()
+            .flat_map(|transaction| transaction.ys.iter().copied())
+            .collect();
+
+        let mut summary = RebuildSummary {
+            kept: existing.len(),
+            ..Default::default()
+        };
+        let timestamp = unix_time();
+
+        for quote in self.localstore.get_mint_quotes().await? {
+            if quote.mint_url != self.mint_url
+                || quote.unit != self.unit
+                || quote.amount_issued == Amount::ZERO
+                || covered_quotes.contains(quote.id.as_str())
+            {
+                continue;
+            }
+
+            summary.from_mint_quotes += 1;
+            summary.transactions.push(Transaction {
+                mint_url: self.mint_url.clone(),
+                direction: TransactionDirection::Incoming,
+                amount: quote.amount_issued,
+                fee: Amount::ZERO,
+                unit: self.unit.clone(),
+                ys: vec![quote_placeholder_y(
+                    TransactionDirection::Incoming,
+                    &quote.id,
+                )?],
+                timestamp,
+                memo: None,
+                metadata: rebuilt_metadata(false),
+                quote_id: Some(quote.id),
+            });
+        }
+
+        for quote in self.localstore.get_melt_quotes().await? {
+            if quote.unit != self.unit
+                || quote.state != MeltQuoteState::Paid
+                || covered_quotes.contains(quote.id.as_str())
+            {
+                continue;
+            }
+
+            summary.from_melt_quotes += 1;
+            summary.transactions.push(Transaction {
+                mint_url: self.mint_url.clone(),
+                direction: TransactionDirection::Outgoing,
+                amount: quote.amount,
+                fee: Amount::ZERO,
+                unit: self.unit.clone(),
+                ys: vec![quote_placeholder_y(
+                    TransactionDirection::Outgoing,
+                    &quote.id,
+                )?],
+                timestamp,
+                memo: None,
+                metadata: rebuilt_metadata(false),
+                quote_id: Some(quote.id),
+            });
+        }
+
+        if opts.estimate_outgoing {
+            let spent: Vec<_> = self
+                .localstore
+                .get_proofs(
+                    Some(self.mint_url.clone()),
+                    Some(self.unit.clone()),
+                    Some(vec![State::Spent, State::PendingSpent]),
+                    None,
+                )
+                .await?
+                .into_iter()
+                .filter(|info| !covered_ys.contains(&info.y))
+                .collect();
+
+            if !spent.is_empty() {
+                summary.from_spent_proofs += 1;
+                summary.transactions.push(Transaction {
+                    mint_url: self.mint_url.clone(),
+                    direction: TransactionDirection::Outgoing,
+                    amount: Amount::try_sum(spent.iter().map(|info| info.proof.amount))?,
+                    fee: Amount::ZERO,
+                    unit: self.unit.clone(),
+                    ys: spent.iter().map(|info| info.y).collect(),
+                    timestamp,
+                    memo: None,
+                    metadata: rebuilt_metadata(true),
+                    quote_id: None,
+                });
+            }
+        }
+
+        let held = self
+            .localstore
+            .get_proofs(
+                Some(self.mint_url.clone()),
+                Some(self.unit.clone()),
+                Some(vec![State::Unspent, State::Pending, State::Reserved]),
+                None,
+            )
+            .await?
+            .into_iter()
+            .map(|info| info.proof)
+            .collect::<Vec<_>>()
+            .total_amount()?;
+
+        let (mut incoming, mut outgoing) = (Amount::ZERO, Amount::ZERO);
+        for transaction in existing.iter().chain(&summary.transactions) {
+            match transaction.direction {
+                TransactionDirection::Incoming => {
+                    incoming = incoming
+                        .checked_add(transaction.amount)
+                        .ok_or(Error::AmountOverflow)?;
+                }
+                TransactionDirection::Outgoing => {
+                    outgoing = outgoing
+                        .checked_add(transaction.amount)
+                        .and_then(|outgoing| outgoing.checked_add(transaction.fee))
+                        .ok_or(Error::AmountOverflow)?;
+                }
+            }
+        }
+
+        // Everything that came in was either spent or is still held
+        let accounted = held.checked_add(outgoing).ok_or(Error::AmountOverflow)?;
+        summary.unattributed_incoming = accounted.checked_sub(incoming).unwrap_or(Amount::ZERO);
+        summary.unattributed_outgoing = incoming.checked_sub(accounted).unwrap_or(Amount::ZERO);
+
+        if !opts.dry_run {
+            for transaction in &summary.transactions {
+                // Never overwrite a record that survived
+                if self
+                    .localstore
+                    .get_transaction(transaction.id())
+                    .await?
+                    .is_none()
+                {
+                    self.localstore.add_transaction(transaction.clone()).await?;
+                }
+            }
+        }
+
+        tracing::info!(
+            "Rebuilt {} transactions for {}, kept {}",
+            summary.transactions.len(),
+            self.mint_url,
+            summary.kept
+        );
+
+        Ok(summary)
+    }
+}
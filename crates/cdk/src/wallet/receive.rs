@@ -13,7 +13,7 @@ use crate::dhke::construct_proofs;
 use crate::nuts::nut00::ProofsMethods;
 use crate::nuts::nut10::Kind;
 use crate::nuts::{Conditions, Proofs, PublicKey, SecretKey, SigFlag, State, Token};
-use crate::types::ProofInfo;
+use crate::types::{ProofInfo, ProofSource};
 use crate::util::hex;
 use crate::{ensure_cdk, Amount, Error, Wallet, SECP256K1};
 
@@ -110,7 +110,10 @@ impl Wallet {
         let proofs_info = proofs
             .clone()
             .into_iter()
-            .map(|p| ProofInfo::new(p, self.mint_url.clone(), State::Pending, self.unit.clone()))
+            .map(|p| {
+                ProofInfo::new(p, self.mint_url.clone(), State::Pending, self.unit.clone())
+                    .map(|info| info.with_source(ProofSource::Receive))
+            })
             .collect::<Result<Vec<ProofInfo>, _>>()?;
         self.localstore
             .update_proofs(proofs_info.clone(), vec![])
@@ -146,7 +149,10 @@ impl Wallet {
 
         let recv_proof_infos = recv_proofs
             .into_iter()
-            .map(|proof| ProofInfo::new(proof, mint_url.clone(), State::Unspent, self.unit.clone()))
+            .map(|proof| {
+                ProofInfo::new(proof, mint_url.clone(), State::Unspent, self.unit.clone())
+                    .map(|info| info.with_source(ProofSource::Receive))
+            })
             .collect::<Result<Vec<ProofInfo>, _>>()?;
         self.localstore
             .update_proofs(
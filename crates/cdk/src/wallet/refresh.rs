@@ -0,0 +1,310 @@
+//! Proof refresh
+//!
+//! Swaps proofs for fresh seed-derived ones so they can no longer be linked to
+//! the issuance or swap that created them. The work is split into randomly
+//! sized batches with random waits in between, so the swaps themselves are not
+//! trivially linkable either.
+
+use std::collections::HashSet;
+use std::str::FromStr;
+use std::time::Duration;
+
+use getrandom::getrandom;
+use tracing::instrument;
+use web_time::Instant;
+
+use crate::amount::SplitTarget;
+use crate::nuts::nut00::ProofsMethods;
+use crate::nuts::nut10::Kind;
+use crate::nuts::{nut10, Conditions, Proof, Proofs, PublicKey, SecretKey, SigFlag, State};
+use crate::{ensure_cdk, Amount, Error, Wallet};
+
+/// Options for [`Wallet::refresh_proofs`]
+#[derive(Debug, Clone)]
+pub struct RefreshOptions {
+    /// Fewest proofs swapped in one batch
+    pub min_batch_size: usize,
+    /// Most proofs swapped in one batch
+    ///
+    /// Batch amounts are re-split into powers of two, so a batch never has more
+    /// outputs than inputs.
+    pub max_batch_size: usize,
+    /// Shortest wait between batches
+    pub min_delay: Duration,
+    /// Longest wait between batches
+    pub max_delay: Duration,
+    /// Spread the batches over this window instead of using the delay bounds
+    pub time_window: Option<Duration>,
+    /// Stop before a batch would take the fees paid above this amount
+    pub fee_cap: Option<Amount>,
+    /// Keys used to sign P2PK locked proofs
+    pub p2pk_signing_keys: Vec<SecretKey>,
+}
+
+impl Default for RefreshOptions {
+    fn default() -> Self {
+        Self {
+            min_batch_size: 1,
+            max_batch_size: 10,
+            min_delay: Duration::ZERO,
+            max_delay: Duration::ZERO,
+            time_window: None,
+            fee_cap: None,
+            p2pk_signing_keys: Vec::new(),
+        }
+    }
+}
+
+/// Outcome of a proof refresh
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RefreshSummary {
+    /// Number of swaps completed
+    pub batches_completed: usize,
+    /// Number of proofs swapped
+    pub proofs_refreshed: usize,
+    /// Value of the proofs swapped, fees included
+    pub amount_refreshed: Amount,
+    /// Fees paid to the mint
+    pub fees_paid: Amount,
+    /// Locked proofs skipped because they could not be signed
+    pub skipped: Vec<PublicKey>,
+    /// Proofs left as they were because of the fee cap
+    pub remaining: Vec<PublicKey>,
+    /// Whether the fee cap stopped the refresh
+    pub fee_cap_reached: bool,
+}
+
+/// Uniformly random value in `min..=max`
+fn random_in(min: u64, max: u64) -> u64 {
+    if max <= min {
+        return min;
+    }
+
+    let mut buffer = [0u8; 8];
+    getrandom(&mut buffer).expect("Failed to generate random bytes");
+
+    min + u64::from_le_bytes(buffer) % (max - min).saturating_add(1)
+}
+
+/// Sign a locked proof with the given keys
+///
+/// Returns `false` if the proof is locked and cannot be spent with the keys.
+fn unlock_proof(proof: &mut Proof, keys: &[SecretKey]) -> Result<bool, Error> {
+    let secret: nut10::Secret = match proof.secret.clone().try_into() {
+        Ok(secret) => secret,
+        Err(_) => return Ok(true),
+    };
+
+    if proof.witness.is_some() {
+        return Ok(true);
+    }
+
+    if secret.kind() != Kind::P2PK {
+        return Ok(false);
+    }
+
+    let Ok(conditions) =
+        Conditions::try_from(secret.secret_data().tags().cloned().unwrap_or_default())
+    else {
+        return Ok(false);
+    };
+
+    // Signing outputs is not supported when refreshing
+    if conditions.sig_flag == SigFlag::SigAll {
+        return Ok(false);
+    }
+
+    let mut pubkeys = conditions.pubkeys.unwrap_or_default();
+    pubkeys.push(PublicKey::from_str(secret.secret_data().data())?);
+
+    let mut signed = false;
+    for key in keys {
+        if pubkeys.contains(&key.public_key()) {
+            proof.sign_p2pk(key.clone())?;
+            signed = true;
+        }
+    }
+
+    Ok(signed)
+}
+
+impl Wallet {
+    /// Refresh the whole unspent balance
+    ///
+    /// See [`Wallet::refresh_proofs`].
+    #[instrument(skip_all)]
+    pub async fn refresh_all_proofs(
+        &self,
+        options: RefreshOptions,
+    ) -> Result<RefreshSummary, Error> {
+        let ys = self.get_unspent_proofs().await?.ys()?;
+
+        self.refresh_proofs(ys, options).await
+    }
+
+    /// Swap the given proofs for fresh ones in randomized batches
+    ///
+    /// Each batch reserves its proofs while it is swapped. An interrupted
+    /// refresh is resumed by calling this again with the same `ys`: proofs
+    /// already swapped are gone from the wallet and skipped, and proofs left
+    /// reserved by the interrupted batch are checked with the mint first and
+    /// either released or dropped as spent. Outputs of a batch the mint
+    /// processed before the interruption can be recovered with
+    /// [`Wallet::restore`].
+    #[instrument(skip_all)]
+    pub async fn refresh_proofs(
+        &self,
+        ys: Vec<PublicKey>,
+        options: RefreshOptions,
+    ) -> Result<RefreshSummary, Error> {
+        ensure_cdk!(
+            options.min_batch_size > 0 && options.min_batch_size <= options.max_batch_size,
+            Error::Custom("Invalid refresh batch size".to_string())
+        );
+
+        self.refresh_keysets().await?;
+
+        let requested: HashSet<PublicKey> = ys.into_iter().collect();
+        let stored: Vec<(Proof, State)> = self
+            .localstore
+            .get_proofs(
+                Some(self.mint_url.clone()),
+                Some(self.unit.clone()),
+                Some(vec![State::Unspent, State::Reserved, State::Pending]),
+                None,
+            )
+            .await?
+            .into_iter()
+            .filter(|info| requested.contains(&info.y))
+            .map(|info| (info.proof, info.state))
+            .collect();
+
+        // Settle proofs left behind by an interrupted batch, spent ones are
+        // removed from the wallet by the state check
+        let (unspent, interrupted): (Vec<_>, Vec<_>) = stored
+            .into_iter()
+            .partition(|(_, state)| *state == State::Unspent);
+        let mut proofs: Proofs = unspent.into_iter().map(|(proof, _)| proof).collect();
+
+        if !interrupted.is_empty() {
+            let interrupted: Proofs = interrupted.into_iter().map(|(proof, _)| proof).collect();
+            let states = self.check_proofs_spent(interrupted.clone()).await?;
+
+            let mut released = Vec::new();
+            for (proof, state) in interrupted.into_iter().zip(states) {
+                if state.state != State::Spent {
+                    released.push(state.y);
+                    proofs.push(proof);
+                }
+            }
+
+            tracing::info!(
+                "Resuming refresh, {} interrupted proofs released",
+                released.len()
+            );
+
+            self.unreserve_proofs(released).await?;
+        }
+
+        let mut summary = RefreshSummary::default();
+
+        let mut refreshable = Proofs::new();
+        for mut proof in proofs {
+            if unlock_proof(&mut proof, &options.p2pk_signing_keys)? {
+                refreshable.push(proof);
+            } else {
+                summary.skipped.push(proof.y()?);
+            }
+        }
+
+        // Shuffle so batches do not follow storage order
+        for i in (1..refreshable.len()).rev() {
+            let j = random_in(0, i as u64) as usize;
+            refreshable.swap(i, j);
+        }
+
+        let average_batch = (options.min_batch_size + options.max_batch_size).div_ceil(2);
+        let expected_batches = refreshable.len().div_ceil(average_batch).max(1) as u32;
+        let started = Instant::now();
+
+        let mut refreshable = refreshable.into_iter().peekable();
+        while refreshable.peek().is_some() {
+            if summary.batches_completed > 0 {
+                let delay = match options.time_window {
+                    Some(window) => {
+                        let remaining = window.saturating_sub(started.elapsed());
+                        let max_delay = (window / expected_batches) * 2;
+                        Duration::from_millis(random_in(0, max_delay.as_millis() as u64))
+                            .min(remaining)
+                    }
+                    None => Duration::from_millis(random_in(
+                        options.min_delay.as_millis() as u64,
+                        options.max_delay.as_millis() as u64,
+                    )),
+                };
+                tokio::time::sleep(delay).await;
+            }
+
+            let batch_size =
+                random_in(options.min_batch_size as u64, options.max_batch_size as u64) as usize;
+            let batch: Proofs = refreshable.by_ref().take(batch_size).collect();
+
+            let batch_amount = batch.total_amount()?;
+            let fee = self.get_proofs_fee(&batch).await?;
+
+            if options
+                .fee_cap
+                .is_some_and(|cap| summary.fees_paid + fee > cap)
+            {
+                tracing::info!("Refresh fee cap reached, stopping");
+                summary.fee_cap_reached = true;
+                summary.remaining.extend(batch.ys()?);
+                summary.remaining.extend(
+                    refreshable
+                        .by_ref()
+                        .map(|proof| proof.y())
+                        .collect::<Result<Vec<_>, _>>()?,
+                );
+                break;
+            }
+
+            if batch_amount <= fee {
+                // Not worth swapping on its own, leave it untouched
+                summary.remaining.extend(batch.ys()?);
+                continue;
+            }
+
+            let split = SplitTarget::Values((batch_amount - fee).split());
+            self.swap(None, split, batch.clone(), None, false).await?;
+
+            summary.batches_completed += 1;
+            summary.proofs_refreshed += batch.len();
+            summary.amount_refreshed += batch_amount;
+            summary.fees_paid += fee;
+
+            tracing::debug!(
+                "Refreshed batch of {} proofs, fee {}",
+                batch.len(),
+                fee.display(&self.unit)
+            );
+        }
+
+        Ok(summary)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_random_in() {
+        assert_eq!(random_in(5, 5), 5);
+        assert_eq!(random_in(7, 3), 7);
+
+        for _ in 0..1_000 {
+            let value = random_in(2, 6);
+            assert!((2..=6).contains(&value));
+        }
+    }
+}
@@ -0,0 +1,436 @@
+//! Machine-readable wallet results
+//!
+//! Serializable summaries of the main wallet operations, meant for scripts and
+//! thin command line frontends that need a stable JSON output.
+//!
+//! The JSON shape of these types is a compatibility surface: field names and
+//! value formats do not change, new fields may be added. Internal result types
+//! like [`Melted`] are free to evolve and are converted into these types instead
+//! of being serialized directly.
+//!
+//! ```rust
+//! use cdk::nuts::CurrencyUnit;
+//! use cdk::wallet::responses::MintResponse;
+//!
+//! // Proofs returned by `Wallet::mint`
+//! let proofs = vec![];
+//! let response = MintResponse::new("quote-id", CurrencyUnit::Sat, &proofs).unwrap();
+//!
+//! assert_eq!(
+//!     serde_json::to_string(&response).unwrap(),
+//!     r#"{"quote_id":"quote-id","unit":"sat","amount":0,"proof_count":0}"#
+//! );
+//! ```
+
+use serde::{Deserialize, Serialize};
+
+use super::{PreparedSend, RefreshSummary, SendMemo};
+use crate::mint_url::MintUrl;
+use crate::nuts::nut00::ProofsMethods;
+use crate::nuts::{CurrencyUnit, MeltQuoteState, Proofs};
+use crate::types::Melted;
+use crate::{Amount, Error};
+
+/// Result of minting proofs for a quote
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MintResponse {
+    /// Mint quote id
+    pub quote_id: String,
+    /// Unit of the minted proofs
+    pub unit: CurrencyUnit,
+    /// Total amount minted
+    pub amount: Amount,
+    /// Number of proofs minted
+    pub proof_count: usize,
+}
+
+impl MintResponse {
+    /// Create new [`MintResponse`] from the proofs minted for a quote
+    pub fn new(quote_id: &str, unit: CurrencyUnit, proofs: &Proofs) -> Result<Self, Error> {
+        Ok(Self {
+            quote_id: quote_id.to_string(),
+            unit,
+            amount: proofs.total_amount()?,
+            proof_count: proofs.len(),
+        })
+    }
+}
+
+/// Result of a send
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SendResponse {
+    /// Encoded token
+    pub token: String,
+    /// Amount sent
+    pub amount: Amount,
+    /// Fees paid by the sender
+    pub fee: Amount,
+    /// Amount returned to the wallet as change
+    pub change: Amount,
+}
+
+impl SendResponse {
+    /// Confirm a prepared send and summarize it
+    pub async fn confirm(prepared: PreparedSend, memo: Option<SendMemo>) -> Result<Self, Error> {
+        let amount = prepared.amount();
+        let fee = prepared.fee();
+        let change = prepared
+            .proofs()
+            .total_amount()?
+            .checked_sub(amount + fee)
+            .unwrap_or(Amount::ZERO);
+
+        let token = prepared.confirm(memo).await?;
+
+        Ok(Self {
+            token: token.to_string(),
+            amount,
+            fee,
+            change,
+        })
+    }
+}
+
+/// Result of a melt
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MeltResponse {
+    /// Melt quote id
+    pub quote_id: String,
+    /// State of the melt quote
+    pub state: MeltQuoteState,
+    /// Amount paid out
+    pub amount: Amount,
+    /// Fee paid
+    pub fee_paid: Amount,
+    /// Amount plus fee paid
+    pub total: Amount,
+    /// Payment preimage
+    pub preimage: Option<String>,
+    /// Amount returned as change
+    pub change: Amount,
+    /// Number of change proofs
+    pub change_proof_count: usize,
+}
+
+impl MeltResponse {
+    /// Create new [`MeltResponse`] from the result of melting a quote
+    pub fn new(quote_id: &str, melted: &Melted) -> Result<Self, Error> {
+        let change = melted.change.clone().unwrap_or_default();
+
+        Ok(Self {
+            quote_id: quote_id.to_string(),
+            state: melted.state,
+            amount: melted.amount,
+            fee_paid: melted.fee_paid,
+            total: melted.total_amount(),
+            preimage: melted.preimage.clone(),
+            change: change.total_amount()?,
+            change_proof_count: change.len(),
+        })
+    }
+}
+
+/// Result of receiving a token
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReceiveResponse {
+    /// Mint the token was received from
+    pub mint_url: MintUrl,
+    /// Unit of the received proofs
+    pub unit: CurrencyUnit,
+    /// Amount received after fees
+    pub amount: Amount,
+}
+
+impl ReceiveResponse {
+    /// Create new [`ReceiveResponse`]
+    pub fn new(mint_url: MintUrl, unit: CurrencyUnit, amount: Amount) -> Self {
+        Self {
+            mint_url,
+            unit,
+            amount,
+        }
+    }
+}
+
+/// Result of syncing the wallet with its mint
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyncResponse {
+    /// Mint synced with
+    pub mint_url: MintUrl,
+    /// Unit synced
+    pub unit: CurrencyUnit,
+    /// Amount minted from paid quotes
+    pub minted: Amount,
+    /// Amount still pending after checking pending proofs
+    pub pending: Amount,
+}
+
+impl SyncResponse {
+    /// Create new [`SyncResponse`] from the results of
+    /// [`Wallet::check_all_mint_quotes`](super::Wallet::check_all_mint_quotes) and
+    /// [`Wallet::check_all_pending_proofs`](super::Wallet::check_all_pending_proofs)
+    pub fn new(mint_url: MintUrl, unit: CurrencyUnit, minted: Amount, pending: Amount) -> Self {
+        Self {
+            mint_url,
+            unit,
+            minted,
+            pending,
+        }
+    }
+}
+
+/// Wallet balance by proof state
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BalanceResponse {
+    /// Mint of the balance
+    pub mint_url: MintUrl,
+    /// Unit of the balance
+    pub unit: CurrencyUnit,
+    /// Unspent balance
+    pub spendable: Amount,
+    /// Balance of pending proofs
+    pub pending: Amount,
+    /// Balance of reserved proofs
+    pub reserved: Amount,
+    /// Sum of all of the above
+    pub total: Amount,
+}
+
+impl BalanceResponse {
+    /// Create new [`BalanceResponse`]
+    pub fn new(
+        mint_url: MintUrl,
+        unit: CurrencyUnit,
+        spendable: Amount,
+        pending: Amount,
+        reserved: Amount,
+    ) -> Result<Self, Error> {
+        let total = spendable
+            .checked_add(pending)
+            .and_then(|total| total.checked_add(reserved))
+            .ok_or(Error::AmountOverflow)?;
+
+        Ok(Self {
+            mint_url,
+            unit,
+            spendable,
+            pending,
+            reserved,
+            total,
+        })
+    }
+}
+
+/// Result of consolidating the wallet by refreshing its proofs
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RefreshResponse {
+    /// Number of swaps completed
+    pub batches: usize,
+    /// Number of proofs swapped
+    pub proofs_refreshed: usize,
+    /// Value of the proofs swapped
+    pub amount_refreshed: Amount,
+    /// Fees paid
+    pub fees_paid: Amount,
+    /// Number of locked proofs skipped
+    pub skipped: usize,
+    /// Number of proofs left untouched
+    pub remaining: usize,
+    /// Whether the fee cap stopped the refresh
+    pub fee_cap_reached: bool,
+}
+
+impl From<&RefreshSummary> for RefreshResponse {
+    fn from(summary: &RefreshSummary) -> Self {
+        Self {
+            batches: summary.batches_completed,
+            proofs_refreshed: summary.proofs_refreshed,
+            amount_refreshed: summary.amount_refreshed,
+            fees_paid: summary.fees_paid,
+            skipped: summary.skipped.len(),
+            remaining: summary.remaining.len(),
+            fee_cap_reached: summary.fee_cap_reached,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::nuts::{Id, Proof, PublicKey, SecretKey};
+    use crate::secret::Secret;
+
+    fn mint_url() -> MintUrl {
+        MintUrl::from_str("https://mint.example.com").unwrap()
+    }
+
+    fn proof(amount: u64) -> Proof {
+        Proof::new(
+            Amount::from(amount),
+            Id::from_str("009a1f293253e41e").unwrap(),
+            Secret::generate(),
+            SecretKey::generate().public_key(),
+        )
+    }
+
+    #[test]
+    fn test_mint_response_json() {
+        let response =
+            MintResponse::new("quote", CurrencyUnit::Sat, &vec![proof(2), proof(8)]).unwrap();
+
+        assert_eq!(
+            serde_json::to_value(&response).unwrap(),
+            json!({
+                "quote_id": "quote",
+                "unit": "sat",
+                "amount": 10,
+                "proof_count": 2
+            })
+        );
+    }
+
+    #[test]
+    fn test_send_response_json() {
+        let response = SendResponse {
+            token: "cashuB".to_string(),
+            amount: 10.into(),
+            fee: 1.into(),
+            change: 5.into(),
+        };
+
+        assert_eq!(
+            serde_json::to_value(&response).unwrap(),
+            json!({
+                "token": "cashuB",
+                "amount": 10,
+                "fee": 1,
+                "change": 5
+            })
+        );
+    }
+
+    #[test]
+    fn test_melt_response_json() {
+        let melted = Melted {
+            state: MeltQuoteState::Paid,
+            preimage: Some("preimage".to_string()),
+            change: Some(vec![proof(1), proof(2)]),
+            amount: 100.into(),
+            fee_paid: 2.into(),
+        };
+
+        let response = MeltResponse::new("quote", &melted).unwrap();
+
+        assert_eq!(
+            serde_json::to_value(&response).unwrap(),
+            json!({
+                "quote_id": "quote",
+                "state": "PAID",
+                "amount": 100,
+                "fee_paid": 2,
+                "total": 102,
+                "preimage": "preimage",
+                "change": 3,
+                "change_proof_count": 2
+            })
+        );
+
+        let unpaid = Melted {
+            state: MeltQuoteState::Unpaid,
+            preimage: None,
+            change: None,
+            amount: 100.into(),
+            fee_paid: 0.into(),
+        };
+
+        let response = MeltResponse::new("quote", &unpaid).unwrap();
+        let json = serde_json::to_value(&response).unwrap();
+        assert_eq!(json["preimage"], serde_json::Value::Null);
+        assert_eq!(json["change"], json!(0));
+        assert_eq!(json["change_proof_count"], json!(0));
+    }
+
+    #[test]
+    fn test_receive_response_json() {
+        let response = ReceiveResponse::new(mint_url(), CurrencyUnit::Sat, 21.into());
+
+        assert_eq!(
+            serde_json::to_value(&response).unwrap(),
+            json!({
+                "mint_url": "https://mint.example.com",
+                "unit": "sat",
+                "amount": 21
+            })
+        );
+    }
+
+    #[test]
+    fn test_sync_response_json() {
+        let response = SyncResponse::new(mint_url(), CurrencyUnit::Sat, 64.into(), 4.into());
+
+        assert_eq!(
+            serde_json::to_value(&response).unwrap(),
+            json!({
+                "mint_url": "https://mint.example.com",
+                "unit": "sat",
+                "minted": 64,
+                "pending": 4
+            })
+        );
+    }
+
+    #[test]
+    fn test_balance_response_json() {
+        let response = BalanceResponse::new(
+            mint_url(),
+            CurrencyUnit::Sat,
+            100.into(),
+            10.into(),
+            1.into(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            serde_json::to_value(&response).unwrap(),
+            json!({
+                "mint_url": "https://mint.example.com",
+                "unit": "sat",
+                "spendable": 100,
+                "pending": 10,
+                "reserved": 1,
+                "total": 111
+            })
+        );
+    }
+
+    #[test]
+    fn test_refresh_response_json() {
+        let y = || -> PublicKey { SecretKey::generate().public_key() };
+        let summary = RefreshSummary {
+            batches_completed: 3,
+            proofs_refreshed: 12,
+            amount_refreshed: 100.into(),
+            fees_paid: 3.into(),
+            skipped: vec![y()],
+            remaining: vec![y(), y()],
+            fee_cap_reached: true,
+        };
+
+        assert_eq!(
+            serde_json::to_value(RefreshResponse::from(&summary)).unwrap(),
+            json!({
+                "batches": 3,
+                "proofs_refreshed": 12,
+                "amount_refreshed": 100,
+                "fees_paid": 3,
+                "skipped": 1,
+                "remaining": 2,
+                "fee_cap_reached": true
+            })
+        );
+    }
+}
@@ -0,0 +1,277 @@
+//! Restore from seed
+//!
+//! Keysets are scanned in batches of counters until no signature was found
+//! for `gap_limit` counters. The progress of every keyset is stored after
+//! each batch, so an interrupted restore resumes where it stopped.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use cdk_common::wallet::RestoreProgress;
+use futures::{StreamExt, TryStreamExt};
+use tokio::sync::mpsc;
+use tracing::instrument;
+
+use crate::dhke::construct_proofs;
+use crate::nuts::nut00::ProofsMethods;
+use crate::nuts::{Id, KeySetInfo, PreMintSecrets, Proof, RestoreRequest, State};
+use crate::types::ProofInfo;
+use crate::{ensure_cdk, Amount, Error, Wallet};
+
+/// Options for [`Wallet::restore_with_options`]
+#[derive(Debug, Clone)]
+pub struct RestoreOptions {
+    /// Counters scanned past the last signature found before a keyset is done
+    pub gap_limit: u32,
+    /// Counters sent to the mint per restore request
+    pub batch_size: u32,
+    /// Keysets scanned concurrently
+    pub parallelism: usize,
+    /// Receiver of progress updates
+    pub progress: Option<mpsc::UnboundedSender<RestoreUpdate>>,
+}
+
+impl Default for RestoreOptions {
+    fn default() -> Self {
+        Self {
+            gap_limit: 300,
+            batch_size: 100,
+            parallelism: 4,
+            progress: None,
+        }
+    }
+}
+
+impl RestoreOptions {
+    /// Counters scanned past the last signature found before a keyset is done
+    pub fn gap_limit(mut self, gap_limit: u32) -> Self {
+        self.gap_limit = gap_limit;
+        self
+    }
+
+    /// Counters sent to the mint per restore request
+    pub fn batch_size(mut self, batch_size: u32) -> Self {
+        self.batch_size = batch_size;
+        self
+    }
+
+    /// Keysets scanned concurrently
+    pub fn parallelism(mut self, parallelism: usize) -> Self {
+        self.parallelism = parallelism;
+        self
+    }
+
+    /// Send progress updates to `progress`
+    pub fn progress(mut self, progress: mpsc::UnboundedSender<RestoreUpdate>) -> Self {
+        self.progress = Some(progress);
+        self
+    }
+}
+
+/// Progress of a restore, sent after every scanned batch
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RestoreUpdate {
+    /// Keyset scanned
+    pub keyset_id: Id,
+    /// Position of the keyset, starting at 1
+    pub keyset_index: usize,
+    /// Number of keysets restored
+    pub keyset_count: usize,
+    /// Counters of the keyset scanned so far
+    pub counters_scanned: u32,
+    /// Whether the scan of the keyset finished
+    pub keyset_complete: bool,
+    /// Unspent amount recovered so far over all keysets
+    pub amount_recovered: Amount,
+}
+
+impl Wallet {
+    /// Restore proofs from the wallet seed with default [`RestoreOptions`]
+    #[instrument(skip(self))]
+    pub async fn restore(&self) -> Result<Amount, Error> {
+        self.restore_with_options(RestoreOptions::default()).await
+    }
+
+    /// Restore proofs from the wallet seed
+    ///
+    /// Returns the unspent amount recovered. A restore interrupted by an
+    /// error resumes from the stored progress of each keyset when called
+    /// again, and the amount returned then includes what the interrupted
+    /// run recovered. The progress is cleared once every keyset is done.
+    #[instrument(skip(self, opts))]
+    pub async fn restore_with_options(&self, opts: RestoreOptions) -> Result<Amount, Error> {
+        ensure_cdk!(
+            opts.batch_size > 0,
+            Error::Custom("Restore batch size must be positive".to_string())
+        );
+
+        // Check that mint is in store of mints
+        if self
+            .localstore
+            .get_mint(self.mint_url.clone())
+            .await?
+            .is_none()
+        {
+            self.fetch_mint_info().await?;
+        }
+
+        let keysets = self.load_mint_keysets().await?;
+        let keyset_count = keysets.len();
+        let recovered = AtomicU64::new(0);
+
+        let amounts: Vec<Amount> = futures::stream::iter(keysets.iter().enumerate())
+            .map(|(index, keyset)| {
+                self.restore_keyset(keyset, index + 1, keyset_count, &opts, &recovered)
+            })
+            .buffer_unordered(opts.parallelism.max(1))
+            .try_collect()
+            .await?;
+
+        for keyset in &keysets {
+            self.localstore.remove_restore_progress(&keyset.id).await?;
+        }
+
+        Ok(Amount::try_sum(amounts)?)
+    }
+
+    /// Scan a keyset from its stored progress until the gap limit is reached
+    async fn restore_keyset(
+        &self,
+        keyset: &KeySetInfo,
+        keyset_index: usize,
+        keyset_count: usize,
+        opts: &RestoreOptions,
+        recovered: &AtomicU64,
+    ) -> Result<Amount, Error> {
+        let mut progress = self
+            .localstore
+            .get_restore_progress(&keyset.id)
+            .await?
+            .unwrap_or_else(|| RestoreProgress::new(keyset.id));
+
+        if progress.next_counter > 0 {
+            tracing::debug!(
+                "Resuming restore of keyset {} at counter {}",
+                keyset.id,
+                progress.next_counter
+            );
+        }
+        recovered.fetch_add(u64::from(progress.amount_recovered), Ordering::SeqCst);
+
+        let keys = self.load_keyset_keys(keyset.id).await?;
+
+        while !progress.complete {
+            let start_counter = progress.next_counter;
+            let end_counter = start_counter + opts.batch_size - 1;
+
+            let premint_secrets =
+                PreMintSecrets::restore_batch(keyset.id, &self.seed, start_counter, end_counter)?;
+
+            tracing::debug!(
+                "Attempting to restore counter {}-{} for mint {} keyset {}",
+                start_counter,
+                end_counter,
+                self.mint_url,
+                keyset.id
+            );
+
+            let restore_request = RestoreRequest {
+                outputs: premint_secrets.blinded_messages(),
+            };
+
+            let response = self.client.post_restore(restore_request).await?;
+
+            if response.signatures.is_empty() {
+                progress.gap += opts.batch_size;
+            } else {
+                let (counters, premint_secrets): (Vec<u32>, Vec<_>) = premint_secrets
+                    .secrets
+                    .iter()
+                    .zip(start_counter..)
+                    .filter(|(p, _)| response.outputs.contains(&p.blinded_message))
+                    .map(|(p, counter)| (counter, p))
+                    .unzip();
+
+                // the response outputs and premint secrets should be the same after filtering
+                // blinded messages the mint did not have signatures for
+                assert_eq!(response.outputs.len(), premint_secrets.len());
+
+                let proofs = construct_proofs(
+                    response.signatures,
+                    premint_secrets.iter().map(|p| p.r.clone()).collect(),
+                    premint_secrets.iter().map(|p| p.secret.clone()).collect(),
+                    &keys,
+                )?;
+
+                tracing::debug!("Restored {} proofs", proofs.len());
+
+                // Move the counter past the highest counter the mint signed
+                if let Some(highest) = counters.iter().max() {
+                    let counter = self
+                        .localstore
+                        .increment_keyset_counter(&keyset.id, 0)
+                        .await?;
+                    if highest + 1 > counter {
+                        self.localstore
+                            .increment_keyset_counter(&keyset.id, highest + 1 - counter)
+                            .await?;
+                    }
+                }
+
+                let states = self.check_proofs_spent(proofs.clone()).await?;
+
+                let unspent_proofs: Vec<Proof> = proofs
+                    .iter()
+                    .zip(states)
+                    .filter(|(_, state)| !state.state.eq(&State::Spent))
+                    .map(|(p, _)| p)
+                    .cloned()
+                    .collect();
+
+                let unspent_amount = unspent_proofs.total_amount()?;
+
+                let unspent_proofs = unspent_proofs
+                    .into_iter()
+                    .map(|proof| {
+                        ProofInfo::new(
+                            proof,
+                            self.mint_url.clone(),
+                            State::Unspent,
+                            keyset.unit.clone(),
+                        )
+                    })
+                    .collect::<Result<Vec<ProofInfo>, _>>()?;
+
+                self.localstore
+                    .update_proofs(unspent_proofs, vec![])
+                    .await?;
+
+                progress.signatures_found += proofs.len() as u32;
+                progress.amount_recovered = progress
+                    .amount_recovered
+                    .checked_add(unspent_amount)
+                    .ok_or(Error::AmountOverflow)?;
+                progress.gap = 0;
+                recovered.fetch_add(u64::from(unspent_amount), Ordering::SeqCst);
+            }
+
+            progress.next_counter = end_counter + 1;
+            progress.complete = progress.gap >= opts.gap_limit;
+            self.localstore
+                .add_restore_progress(progress.clone())
+                .await?;
+
+            if let Some(sender) = &opts.progress {
+                let _ = sender.send(RestoreUpdate {
+                    keyset_id: keyset.id,
+                    keyset_index,
+                    keyset_count,
+                    counters_scanned: progress.next_counter,
+                    keyset_complete: progress.complete,
+                    amount_recovered: Amount::from(recovered.load(Ordering::SeqCst)),
+                });
+            }
+        }
+
+        Ok(progress.amount_recovered)
+    }
+}
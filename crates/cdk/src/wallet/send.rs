@@ -7,7 +7,7 @@ use cdk_common::wallet::{Transaction, TransactionDirection};
 use tracing::instrument;
 
 use super::SendKind;
-use crate::amount::SplitTarget;
+use crate::amount::{SplitTarget, UnitAmount};
 use crate::nuts::nut00::ProofsMethods;
 use crate::nuts::{Proofs, SpendingConditions, State, Token};
 use crate::{Amount, Error, Wallet};
@@ -23,11 +23,36 @@ impl Wallet {
     /// assert!(send.fee() <= Amount::from(1));
     /// let token = send.confirm(None).await?;
     /// ```
+    ///
+    /// Refused with strict amounts, use [`Wallet::prepare_send_checked`] instead.
     #[instrument(skip(self), err)]
     pub async fn prepare_send(
         &self,
         amount: Amount,
         opts: SendOptions,
+    ) -> Result<PreparedSend, Error> {
+        self.ensure_not_strict("prepare_send_checked")?;
+        self.create_send(amount, opts).await
+    }
+
+    /// Prepare a send of an amount in the wallet unit
+    ///
+    /// Fails with [`Error::UnitMismatch`] if `amount` is in another unit.
+    #[instrument(skip(self), err)]
+    pub async fn prepare_send_checked(
+        &self,
+        amount: UnitAmount,
+        opts: SendOptions,
+    ) -> Result<PreparedSend, Error> {
+        self.check_amount_unit(&amount, &self.unit)?;
+        self.create_send(amount.amount, opts).await
+    }
+
+    /// Select the proofs to send and to swap for `amount`
+    pub(crate) async fn create_send(
+        &self,
+        amount: Amount,
+        opts: SendOptions,
     ) -> Result<PreparedSend, Error> {
         tracing::info!("Preparing send");
 
@@ -82,13 +107,22 @@ impl Wallet {
             .map(|k| k.id)
             .collect();
 
-        let selected_proofs = Wallet::select_proofs(
-            amount,
-            available_proofs,
-            &active_keyset_ids,
-            &keyset_fees,
-            opts.include_fee,
-        )?;
+        let selected_proofs = self
+            .select_within_max_inputs(
+                available_proofs,
+                |proofs, strategy| {
+                    Wallet::select_proofs_with_strategy(
+                        amount,
+                        proofs,
+                        &active_keyset_ids,
+                        &keyset_fees,
+                        opts.include_fee,
+                        strategy,
+                    )
+                },
+                Proofs::clone,
+            )
+            .await?;
         let selected_total = selected_proofs.total_amount()?;
 
         // Check if selected proofs are exact
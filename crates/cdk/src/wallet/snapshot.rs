@@ -0,0 +1,343 @@
+//! Wallet snapshots
+//!
+//! Snapshots of the wallet's proofs, quotes and transactions, taken before
+//! risky maintenance so the wallet can be rolled back if it goes wrong. They
+//! are kept in the wallet database, so they survive a restart of the wallet.
+//!
+//! Rolling back is refused once something left the wallet after the snapshot,
+//! as restoring the old state would resurrect proofs the mint or a recipient
+//! already knows about. Keyset counters are never rewound, even when forced, so
+//! blinded messages are never reused.
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use cdk_common::wallet::{WalletSnapshot, WalletSnapshotContent};
+use tracing::instrument;
+
+use crate::nuts::{Id, State};
+use crate::wallet::types::{MeltQuote, MintQuote, Transaction, TransactionDirection};
+use crate::{Error, Wallet};
+
+/// Wallet snapshot identifier
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SnapshotId(u64);
+
+impl std::fmt::Display for SnapshotId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Summary of a wallet snapshot
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnapshotInfo {
+    /// Snapshot id
+    pub id: SnapshotId,
+    /// Label given when the snapshot was created
+    pub label: String,
+    /// Unix time the snapshot was created
+    pub created_at: u64,
+    /// Number of proofs in the snapshot
+    pub proof_count: usize,
+    /// Approximate size of the snapshot in bytes
+    pub size: usize,
+}
+
+/// Which snapshots [`Wallet::prune_snapshots`] keeps
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SnapshotPolicy {
+    /// Keep at most this many of the most recent snapshots
+    pub keep_last: Option<usize>,
+    /// Remove snapshots older than this many seconds
+    pub max_age: Option<u64>,
+}
+
+impl SnapshotInfo {
+    fn new(snapshot: &WalletSnapshot) -> Result<Self, Error> {
+        Ok(Self {
+            id: SnapshotId(snapshot.id),
+            label: snapshot.label.clone(),
+            created_at: snapshot.created_at,
+            proof_count: snapshot.content.proofs.len(),
+            size: serde_json::to_vec(&snapshot.content)?.len(),
+        })
+    }
+}
+
+impl Wallet {
+    /// Current keyset counters of the wallet's keysets
+    async fn keyset_counters(&self) -> Result<HashMap<Id, u32>, Error> {
+        let mut counters = HashMap::new();
+        for keyset in self.get_mint_keysets().await? {
+            if keyset.unit != self.unit {
+                continue;
+            }
+
+            let counter = self
+                .localstore
+                .increment_keyset_counter(&keyset.id, 0)
+                .await?;
+            counters.insert(keyset.id, counter);
+        }
+
+        Ok(counters)
+    }
+
+    /// Wallet transactions
+    async fn wallet_transactions(&self) -> Result<Vec<Transaction>, Error> {
+        Ok(self
+            .localstore
+            .list_transactions(Some(self.mint_url.clone()), None, Some(self.unit.clone()))
+            .await?)
+    }
+
+    /// Mint quotes of the wallet's mint and unit
+    async fn wallet_mint_quotes(&self) -> Result<Vec<MintQuote>, Error> {
+        Ok(self
+            .localstore
+            .get_mint_quotes()
+            .await?
+            .into_iter()
+            .filter(|quote| quote.mint_url == self.mint_url && quote.unit == self.unit)
+            .collect())
+    }
+
+    /// Melt quotes of the wallet's unit
+    ///
+    /// Melt quotes do not record their mint, so quotes of other mints sharing the
+    /// database are included.
+    async fn wallet_melt_quotes(&self) -> Result<Vec<MeltQuote>, Error> {
+        Ok(self
+            .localstore
+            .get_melt_quotes()
+            .await?
+            .into_iter()
+            .filter(|quote| quote.unit == self.unit)
+            .collect())
+    }
+
+    /// Snapshot the wallet's proofs, quotes and transactions
+    #[instrument(skip(self))]
+    pub async fn create_snapshot(&self, label: &str) -> Result<SnapshotId, Error> {
+        let proofs = self
+            .localstore
+            .get_proofs(
+                Some(self.mint_url.clone()),
+                Some(self.unit.clone()),
+                None,
+                None,
+            )
+            .await?;
+        let mint_quotes = self.wallet_mint_quotes().await?;
+        let melt_quotes = self.wallet_melt_quotes().await?;
+        let transactions = self.wallet_transactions().await?;
+        let counters = self.keyset_counters().await?;
+        let proof_count = proofs.len();
+
+        let id = self
+            .localstore
+            .add_wallet_snapshot(WalletSnapshot {
+                id: 0,
+                mint_url: self.mint_url.clone(),
+                unit: self.unit.clone(),
+                label: label.to_string(),
+                created_at: self.clock.now_unix(),
+                content: WalletSnapshotContent {
+                    proofs,
+                    mint_quotes,
+                    melt_quotes,
+                    transactions,
+                    counters,
+                },
+            })
+            .await?;
+        let id = SnapshotId(id);
+
+        tracing::info!(
+            "Created wallet snapshot {} ({}) with {} proofs",
+            id,
+            label,
+            proof_count
+        );
+
+        Ok(id)
+    }
+
+    /// Snapshots of the wallet's mint and unit, oldest first
+    async fn wallet_snapshots(&self) -> Result<Vec<WalletSnapshot>, Error> {
+        Ok(self
+            .localstore
+            .get_wallet_snapshots(self.mint_url.clone(), self.unit.clone())
+            .await?)
+    }
+
+    /// Snapshot `id` of the wallet's mint and unit
+    async fn wallet_snapshot(&self, id: SnapshotId) -> Result<WalletSnapshot, Error> {
+        self.localstore
+            .get_wallet_snapshot(id.0)
+            .await?
+            .filter(|snapshot| snapshot.mint_url == self.mint_url && snapshot.unit == self.unit)
+            .ok_or(Error::UnknownSnapshot)
+    }
+
+    /// List snapshots, oldest first
+    pub async fn list_snapshots(&self) -> Result<Vec<SnapshotInfo>, Error> {
+        self.wallet_snapshots()
+            .await?
+            .iter()
+            .map(SnapshotInfo::new)
+            .collect()
+    }
+
+    /// Remove a snapshot
+    pub async fn remove_snapshot(&self, id: SnapshotId) -> Result<(), Error> {
+        self.wallet_snapshot(id).await?;
+
+        Ok(self.localstore.remove_wallet_snapshot(id.0).await?)
+    }
+
+    /// Remove the snapshots the policy does not keep
+    ///
+    /// Returns the number of snapshots removed.
+    pub async fn prune_snapshots(&self, policy: &SnapshotPolicy) -> Result<usize, Error> {
+        let now = self.clock.now_unix();
+        let snapshots = self.wallet_snapshots().await?;
+        let excess = policy
+            .keep_last
+            .map_or(0, |keep_last| snapshots.len().saturating_sub(keep_last));
+
+        let mut removed = 0;
+        for (position, snapshot) in snapshots.iter().enumerate() {
+            let expired = policy
+                .max_age
+                .is_some_and(|max_age| now.saturating_sub(snapshot.created_at) > max_age);
+
+            if position < excess || expired {
+                self.localstore.remove_wallet_snapshot(snapshot.id).await?;
+                removed += 1;
+            }
+        }
+
+        Ok(removed)
+    }
+
+    /// Roll the wallet back to a snapshot
+    ///
+    /// Proofs and transactions are restored to the snapshot, and quotes to
+    /// their state at the snapshot. Quotes created after the snapshot are kept,
+    /// as they may have been paid.
+    ///
+    /// Fails with [`Error::SnapshotRollbackRefused`] if anything became visible
+    /// outside the wallet since the snapshot: blinded messages sent to the mint,
+    /// quotes requested, proofs reserved for sending or exported, or outgoing
+    /// transactions. `force` rolls back anyway, which can bring back proofs that
+    /// are already spent.
+    #[instrument(skip(self))]
+    pub async fn rollback_to_snapshot(&self, id: SnapshotId, force: bool) -> Result<(), Error> {
+        let WalletSnapshotContent {
+            proofs: snapshot_proofs,
+            mint_quotes,
+            melt_quotes,
+            transactions,
+            counters,
+        } = self.wallet_snapshot(id).await?.content;
+
+        let current_proofs = self
+            .localstore
+            .get_proofs(
+                Some(self.mint_url.clone()),
+                Some(self.unit.clone()),
+                None,
+                None,
+            )
+            .await?;
+        let current_mint_quotes = self.wallet_mint_quotes().await?;
+        let current_melt_quotes = self.wallet_melt_quotes().await?;
+        let current_transactions = self.wallet_transactions().await?;
+
+        let mut effects = Vec::new();
+
+        let current_counters = self.keyset_counters().await?;
+        if current_counters
+            .iter()
+            .any(|(id, counter)| *counter > counters.get(id).copied().unwrap_or(0))
+        {
+            effects.push("blinded messages were sent to the mint");
+        }
+
+        let quote_ids: HashSet<&str> = mint_quotes
+            .iter()
+            .map(|quote| quote.id.as_str())
+            .chain(melt_quotes.iter().map(|quote| quote.id.as_str()))
+            .collect();
+        if current_mint_quotes
+            .iter()
+            .map(|quote| quote.id.as_str())
+            .chain(current_melt_quotes.iter().map(|quote| quote.id.as_str()))
+            .any(|id| !quote_ids.contains(id))
+        {
+            effects.push("quotes were requested from the mint");
+        }
+
+        let unspent: HashSet<_> = snapshot_proofs
+            .iter()
+            .filter(|info| info.state == State::Unspent)
+            .map(|info| info.y)
+            .collect();
+        if current_proofs.iter().any(|info| {
+            unspent.contains(&info.y)
+                && matches!(
+                    info.state,
+                    State::Reserved | State::Pending | State::PendingSpent
+                )
+        }) {
+            effects.push("proofs were reserved for sending or exported");
+        }
+
+        let transaction_ids: BTreeSet<_> = transactions.iter().map(|tx| tx.id()).collect();
+        if current_transactions.iter().any(|tx| {
+            tx.direction == TransactionDirection::Outgoing && !transaction_ids.contains(&tx.id())
+        }) {
+            effects.push("funds were sent");
+        }
+
+        if !effects.is_empty() {
+            let reason = effects.join(", ");
+            if !force {
+                return Err(Error::SnapshotRollbackRefused(reason));
+            }
+
+            tracing::warn!("Forcing rollback to snapshot {} although {}", id, reason);
+        }
+
+        let snapshot_ys: HashSet<_> = snapshot_proofs.iter().map(|info| info.y).collect();
+        let added_ys = current_proofs
+            .iter()
+            .filter(|info| !snapshot_ys.contains(&info.y))
+            .map(|info| info.y)
+            .collect();
+        self.localstore
+            .update_proofs(snapshot_proofs, added_ys)
+            .await?;
+
+        for quote in mint_quotes {
+            self.localstore.add_mint_quote(quote).await?;
+        }
+        for quote in melt_quotes {
+            self.localstore.add_melt_quote(quote).await?;
+        }
+
+        for transaction in current_transactions {
+            if !transaction_ids.contains(&transaction.id()) {
+                self.localstore.remove_transaction(transaction.id()).await?;
+            }
+        }
+        for transaction in transactions {
+            self.localstore.add_transaction(transaction).await?;
+        }
+
+        tracing::info!("Rolled back wallet to snapshot {}", id);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,48 @@
+//! Strict amounts
+//!
+//! An [`Amount`](crate::Amount) does not know its unit, so an integration
+//! passing msat where sat are expected pays a thousand times too much. The
+//! `_checked` variants of the monetary methods take a [`UnitAmount`] and refuse
+//! amounts in another unit than the operation's. With
+//! [`WalletBuilder::strict_amounts`](crate::wallet::WalletBuilder::strict_amounts)
+//! the methods taking amounts without unit are refused altogether.
+
+use crate::amount::UnitAmount;
+use crate::nuts::CurrencyUnit;
+use crate::{Error, Wallet};
+
+impl Wallet {
+    /// Whether methods taking amounts without unit are refused
+    pub fn strict_amounts(&self) -> bool {
+        self.strict_amounts
+    }
+
+    /// Refuse a method taking an amount without unit in strict mode
+    ///
+    /// `checked` names the method to use instead.
+    pub(crate) fn ensure_not_strict(&self, checked: &'static str) -> Result<(), Error> {
+        if self.strict_amounts {
+            return Err(Error::StrictAmounts(checked));
+        }
+
+        Ok(())
+    }
+
+    /// Check that `amount` is denominated in the `unit` of the operation
+    pub(crate) fn check_amount_unit(
+        &self,
+        amount: &UnitAmount,
+        unit: &CurrencyUnit,
+    ) -> Result<(), Error> {
+        if &amount.unit != unit {
+            tracing::warn!(
+                "Amount {} does not match unit {} of the operation",
+                amount,
+                unit
+            );
+            return Err(Error::UnitMismatch);
+        }
+
+        Ok(())
+    }
+}
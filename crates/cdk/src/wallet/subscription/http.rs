@@ -1,7 +1,7 @@
 use std::collections::HashMap;
 use std::sync::Arc;
 
-use cdk_common::MintQuoteBolt12Response;
+use cdk_common::{MintQuoteBolt12Response, MintQuoteMiningShareResponse};
 use tokio::sync::{mpsc, RwLock};
 use tokio::time;
 use web_time::Duration;
@@ -17,6 +17,7 @@ use crate::Wallet;
 enum UrlType {
     Mint(String),
     MintBolt12(String),
+    MintMiningShare(String),
     Melt(String),
     PublicKey(nut01::PublicKey),
 }
@@ -25,6 +26,7 @@ enum UrlType {
 enum AnyState {
     MintQuoteState(nut23::QuoteState),
     MintBolt12QuoteState(MintQuoteBolt12Response<String>),
+    MintMiningShareQuoteState(MintQuoteMiningShareResponse<String>),
     MeltQuoteState(nut05::QuoteState),
     PublicKey(nut07::State),
     Empty,
@@ -79,6 +81,16 @@ async fn convert_subscription(
                 subscribed_to.insert(id, (sub.0.clone(), sub.1.id.clone(), AnyState::Empty));
             }
         }
+        Kind::MiningShareMintQuote => {
+            for id in sub
+                .1
+                .filters
+                .iter()
+                .map(|id| UrlType::MintMiningShare(id.clone()))
+            {
+                subscribed_to.insert(id, (sub.0.clone(), sub.1.id.clone(), AnyState::Empty));
+            }
+        }
     }
 
     Some(())
@@ -118,6 +130,18 @@ pub async fn http_main<S: IntoIterator<Item = SubId>>(
                                 }
                             }
                         },
+                        UrlType::MintMiningShare(id) => {
+                            let response = http_client.get_mint_quote_status_mining_share(id).await;
+                            if let Ok(response) = response {
+                                if *last_state == AnyState::MintMiningShareQuoteState(response.clone()) {
+                                    continue;
+                                }
+                                *last_state = AnyState::MintMiningShareQuoteState(response.clone());
+                                if let Err(err) = sender.try_send(NotificationPayload::MintQuoteMiningShareResponse(response)) {
+                                    tracing::error!("Error sending mint quote response: {:?}", err);
+                                }
+                            }
+                        },
                         UrlType::Mint(id) => {
 
                             let response = http_client.get_mint_quote_status(id, crate::nuts::PaymentMethod::Bolt11).await;
@@ -10,6 +10,7 @@ use std::fmt::Debug;
 use std::sync::Arc;
 
 use cdk_common::subscription::Params;
+use cdk_common::MintQuoteMiningShareResponse;
 use tokio::sync::{mpsc, RwLock};
 use tokio::task::JoinHandle;
 use tracing::error;
@@ -180,6 +181,40 @@ impl Drop for ActiveSubscription {
     }
 }
 
+/// Subscription to mining share mint quote updates
+///
+/// Yields the [`MintQuoteMiningShareResponse`] notifications of the subscribed
+/// quotes, other payloads are skipped. Unsubscribes on drop.
+pub struct MiningShareQuoteSubscription(ActiveSubscription);
+
+impl MiningShareQuoteSubscription {
+    pub(crate) fn new(subscription: ActiveSubscription) -> Self {
+        Self(subscription)
+    }
+
+    /// Try to receive a quote update
+    pub fn try_recv(&mut self) -> Result<Option<MintQuoteMiningShareResponse<String>>, Error> {
+        while let Some(payload) = self.0.try_recv()? {
+            if let NotificationPayload::MintQuoteMiningShareResponse(response) = payload {
+                return Ok(Some(response));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Receive a quote update asynchronously
+    pub async fn recv(&mut self) -> Option<MintQuoteMiningShareResponse<String>> {
+        while let Some(payload) = self.0.recv().await {
+            if let NotificationPayload::MintQuoteMiningShareResponse(response) = payload {
+                return Some(response);
+            }
+        }
+
+        None
+    }
+}
+
 /// Subscription client error
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
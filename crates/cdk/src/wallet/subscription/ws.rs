@@ -1,6 +1,7 @@
 use std::collections::{HashMap, HashSet};
 use std::sync::atomic::AtomicUsize;
 use std::sync::Arc;
+use std::time::Duration;
 
 use cdk_common::subscription::Params;
 use cdk_common::ws::{WsMessageOrResponse, WsMethodRequest, WsRequest, WsUnsubscribeRequest};
@@ -41,8 +42,14 @@ pub async fn ws_main(
 
     let mut active_subscriptions = HashMap::<SubId, mpsc::Sender<_>>::new();
     let mut failure_count = 0;
+    let mut reconnect_after = None;
 
     loop {
+        if let Some(delay) = reconnect_after.take() {
+            tracing::debug!("Waiting {:?} before reconnecting to draining mint", delay);
+            tokio::time::sleep(delay).await;
+        }
+
         tracing::debug!("Connecting to {}", url);
         let ws_stream = match connect_async(&url).await {
             Ok((ws_stream, _)) => ws_stream,
@@ -121,27 +128,45 @@ pub async fn ws_main(
 
         loop {
             tokio::select! {
-                Some(msg) = read.next() => {
+                msg = read.next() => {
                     let msg = match msg {
-                        Ok(msg) => msg,
-                        Err(_) => break,
+                        Some(Ok(msg)) => msg,
+                        Some(Err(_)) | None => break,
                     };
                     let msg = match msg {
                         Message::Text(msg) => msg,
+                        Message::Close(_) => break,
                         _ => continue,
                     };
                     let msg = match serde_json::from_str::<WsMessageOrResponse>(&msg) {
                         Ok(msg) => msg,
-                        Err(_) => continue,
+                        Err(err) => {
+                            tracing::debug!("Could not parse message from server: {:?}", err);
+                            if let Err(err) = wallet.handle_invalid_message(&msg).await {
+                                tracing::error!("Could not store dead letter: {:?}", err);
+                            }
+                            continue;
+                        }
                     };
 
                     match msg {
                         WsMessageOrResponse::Notification(payload) => {
                             tracing::debug!("Received notification from server: {:?}", payload);
+                            let sub_id = Some(payload.params.sub_id.to_string());
+                            if let Err(err) =
+                                wallet.handle_notification(sub_id, &payload.params.payload).await
+                            {
+                                tracing::error!("Could not store notified quote state: {:?}", err);
+                            }
                             let _ = active_subscriptions.get(&payload.params.sub_id).map(|sender| {
                                 let _ = sender.try_send(payload.params.payload);
                             });
                         }
+                        WsMessageOrResponse::Draining(draining) => {
+                            tracing::debug!("Mint is draining connections: {:?}", draining.params);
+                            // Keep reading queued notifications until the server closes the connection
+                            reconnect_after = Some(Duration::from_millis(draining.params.reconnect_after_ms));
+                        }
                         WsMessageOrResponse::Response(response) => {
                             tracing::debug!("Received response from server: {:?}", response);
                             subscription_requests.remove(&response.id);
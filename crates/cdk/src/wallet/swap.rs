@@ -7,7 +7,7 @@ use crate::nuts::nut00::ProofsMethods;
 use crate::nuts::{
     nut10, PreMintSecrets, PreSwap, Proofs, PublicKey, SpendingConditions, State, SwapRequest,
 };
-use crate::types::ProofInfo;
+use crate::types::{ProofInfo, ProofSource};
 use crate::{ensure_cdk, Amount, Error, Wallet};
 
 impl Wallet {
@@ -105,6 +105,7 @@ impl Wallet {
                     .into_iter()
                     .map(|proof| {
                         ProofInfo::new(proof, mint_url.clone(), State::Reserved, unit.clone())
+                            .map(|info| info.with_source(ProofSource::Swap))
                     })
                     .collect::<Result<Vec<ProofInfo>, _>>()?;
                 added_proofs = send_proofs_info;
@@ -120,7 +121,10 @@ impl Wallet {
 
         let keep_proofs = change_proofs
             .into_iter()
-            .map(|proof| ProofInfo::new(proof, mint_url.clone(), State::Unspent, unit.clone()))
+            .map(|proof| {
+                ProofInfo::new(proof, mint_url.clone(), State::Unspent, unit.clone())
+                    .map(|info| info.with_source(ProofSource::Swap))
+            })
             .collect::<Result<Vec<ProofInfo>, _>>()?;
         added_proofs.extend(keep_proofs);
 
@@ -173,13 +177,22 @@ impl Wallet {
             .collect();
 
         let keyset_fees = self.get_keyset_fees().await?;
-        let proofs = Wallet::select_proofs(
-            amount,
-            available_proofs,
-            &active_keyset_ids,
-            &keyset_fees,
-            true,
-        )?;
+        let proofs = self
+            .select_within_max_inputs(
+                available_proofs,
+                |proofs, strategy| {
+                    Wallet::select_proofs_with_strategy(
+                        amount,
+                        proofs,
+                        &active_keyset_ids,
+                        &keyset_fees,
+                        true,
+                        strategy,
+                    )
+                },
+                Proofs::clone,
+            )
+            .await?;
 
         self.swap(
             Some(amount),